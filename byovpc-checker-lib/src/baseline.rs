@@ -0,0 +1,202 @@
+//! Suppresses known findings, so long-lived clusters with accepted deviations
+//! only report new issues.
+//!
+//! The first run with `--baseline <file>` records all findings in the file,
+//! later runs suppress the findings it contains. A finding is identified by
+//! its check ID, its code and its resource - if it has none, by the AWS
+//! resources named in its message, or else by the message itself.
+//!
+//! Baselines recorded before results carried their resource have no resource
+//! types; their entries are still matched by the resources in the message.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Severity, VerificationResult};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub check_id: String,
+    #[serde(default)]
+    pub code: Option<String>,
+    /// The type of the resource of the finding, e.g. `subnet`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    /// The AWS resources the finding is about, e.g. `subnet-0123456789abcdef0`.
+    #[serde(default)]
+    pub resource: Option<String>,
+    /// The message at the time the finding was recorded - only used to
+    /// identify findings that name no resource.
+    pub message: String,
+}
+
+impl BaselineEntry {
+    /// The entry identifying the result - `None` for results that are no
+    /// findings.
+    fn of(result: &VerificationResult) -> Option<BaselineEntry> {
+        if result.severity == Severity::Ok {
+            return None;
+        }
+        Some(BaselineEntry {
+            check_id: result.check_id?.to_string(),
+            code: result.code.map(|c| c.as_str().to_string()),
+            resource_type: result.resource_type.map(|t| t.as_str().to_string()),
+            resource: result
+                .resource_id
+                .clone()
+                .or_else(|| resources_in(&result.message)),
+            message: result.message.clone(),
+        })
+    }
+
+    /// The resources of the entry the way they were recorded without resource
+    /// types.
+    fn legacy_resource(&self) -> Option<String> {
+        match self.resource_type {
+            Some(_) => resources_in(&self.message),
+            None => self.resource.clone(),
+        }
+    }
+
+    fn matches(&self, other: &BaselineEntry) -> bool {
+        let (resource, other_resource) = match (&self.resource_type, &other.resource_type) {
+            (Some(a), Some(b)) if a != b => return false,
+            (Some(_), Some(_)) => (self.resource.clone(), other.resource.clone()),
+            _ => (self.legacy_resource(), other.legacy_resource()),
+        };
+        self.check_id == other.check_id
+            && self.code == other.code
+            && match (resource, other_resource) {
+                (Some(a), Some(b)) => a == b,
+                (None, None) => self.message == other.message,
+                _ => false,
+            }
+    }
+}
+
+/// Whether the word is the ID of an AWS resource, e.g. `subnet-0a1b2c3d` or
+/// `eipalloc-0123456789abcdef0`.
+fn is_resource_id(word: &str) -> bool {
+    let Some((prefix, id)) = word.rsplit_once('-') else {
+        return false;
+    };
+    !prefix.is_empty()
+        && prefix.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        && (id.len() == 8 || id.len() == 17)
+        && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The AWS resource IDs and ARNs named in a message, in order.
+pub(crate) fn resources_in(message: &str) -> Option<String> {
+    let resources = message
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '\'' | '"'))
+        .map(|w| w.trim_end_matches(['.', ':']))
+        .filter(|w| w.starts_with("arn:") || is_resource_id(w))
+        .unique()
+        .join(",");
+    (!resources.is_empty()).then_some(resources)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Baseline {
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// The baseline of all findings of the results.
+    pub fn record(results: &[VerificationResult]) -> Baseline {
+        Baseline {
+            entries: results.iter().filter_map(BaselineEntry::of).collect(),
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Baseline, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(Baseline {
+            entries: serde_json::from_reader(reader)?,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, &self.entries)?;
+        Ok(())
+    }
+
+    /// Whether the result is a known finding.
+    pub fn contains(&self, result: &VerificationResult) -> bool {
+        BaselineEntry::of(result)
+            .is_some_and(|entry| self.entries.iter().any(|e| e.matches(&entry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{FindingCode, ResourceType};
+
+    use super::*;
+
+    #[test]
+    fn test_baseline() {
+        let result = |message: &str, code| VerificationResult {
+            message: message.to_string(),
+            severity: Severity::Warning,
+            code,
+            check_id: Some("NET-002"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let baseline = Baseline::record(&[
+            result(
+                "Subnet subnet-0a1b2c3d is missing public ELB tag",
+                Some(FindingCode::MissingPublicElbTag),
+            ),
+            result("Some finding without a resource", None),
+        ]);
+        assert_eq!(
+            baseline.entries[0].resource,
+            Some("subnet-0a1b2c3d".to_string())
+        );
+        assert!(baseline.contains(&result(
+            "Subnet subnet-0a1b2c3d lacks the public ELB tag",
+            Some(FindingCode::MissingPublicElbTag)
+        )));
+        assert!(!baseline.contains(&result(
+            "Subnet subnet-0a1b2c3d is missing cluster tag",
+            Some(FindingCode::MissingClusterTag)
+        )));
+        assert!(!baseline.contains(&result(
+            "Subnet subnet-0a1b2c3e is missing public ELB tag",
+            Some(FindingCode::MissingPublicElbTag)
+        )));
+        assert!(baseline.contains(&result("Some finding without a resource", None)));
+
+        let subnet_result = |message: &str| VerificationResult {
+            resource_type: Some(ResourceType::Subnet),
+            resource_id: Some("subnet-0a1b2c3d".to_string()),
+            ..result(message, Some(FindingCode::MissingPublicElbTag))
+        };
+        // Baselines recorded without resource types match by the message.
+        assert!(baseline.contains(&subnet_result(
+            "Subnet subnet-0a1b2c3d is missing public ELB tag"
+        )));
+        let baseline = Baseline::record(&[subnet_result(
+            "Subnet subnet-0a1b2c3d is missing public ELB tag",
+        )]);
+        assert_eq!(
+            baseline.entries[0].resource_type,
+            Some("subnet".to_string())
+        );
+        assert!(baseline.contains(&subnet_result(
+            "Subnet <subnet-0a1b2c3d> needs the public ELB tag"
+        )));
+    }
+}
@@ -0,0 +1,989 @@
+//! Checks encapsulate a multitude of checks that can be performed on the cloud
+//! provider.
+//!
+//! Right now the following checks are implemented:
+//! - azure: can check the virtual network, network security groups, load
+//!   balancers and private DNS zones of clusters on Azure.
+//! - cidr: can check the subnets are part of the machine CIDR and the cluster
+//!   networks do not overlap the VPC.
+//! - network: can check basic subnet configuration (number of subnets, tags).
+//! - cost: flags networking patterns that cause avoidable costs.
+//! - custom: can run rules defined by the user in YAML files.
+//! - delegation: can report cluster subnets shared with other AWS services.
+//! - dhcp: can check the DHCP options of the VPCs do not break node name
+//!   resolution.
+//! - doctor: can check the local environment the tool runs in.
+//! - egress: can check the subnets can host the egress IPs of the cluster.
+//! - endpoints: can check the VPC endpoints private clusters need exist.
+//! - flowlogs: can report the flow logs of the cluster VPCs and subnets.
+//! - hypershift: can check the resources hosted control planes need in the
+//!   customer account.
+//! - health: can report open AWS Health events in the region of the cluster.
+//! - iam: can check the instance profiles allow the actions the nodes need.
+//! - kubernetes: can cross-check the Services and nodes of the cluster with
+//!   the load balancers and instances in AWS.
+//! - ipv6: can check dual-stack clusters have IPv6 CIDRs and IPv6 default
+//!   routes.
+//! - instances: can check the instances of the cluster and their security
+//!   groups.
+//! - leftovers: can report resources still tagged for a deleted cluster.
+//! - loadbalancer: can check the API load balancers were not modified and
+//!   have healthy targets on every control plane node.
+//! - machinepools: can check machine pools reference valid subnets.
+//! - nacl: can check the network ACLs of the subnets allow required traffic.
+//! - permissions: can report AWS API calls denied by a policy while gathering.
+//! - preflight: can check a VPC before a cluster is installed into it.
+//! - probes: can check the endpoints the cluster needs resolve and accept
+//!   connections.
+//! - proxy: can check the endpoints the cluster needs are reachable through
+//!   its proxy.
+//! - quotas: can check the service quotas installs need are not exhausted.
+//! - resolver: can check the Route53 Resolver query logs for failed lookups.
+//! - scaling: can check subnets and NAT gateways can carry the machine pools at
+//!   their maximum size.
+//! - targetgroups: can check the targets of the load balancers are healthy and
+//!   reachable by their health checks.
+//! - sharedvpc: can check the constraints of subnets shared from another
+//!   account through RAM.
+//! - ssm: can report whether nodes can reach SSM for break-glass access.
+//! - topology: can summarize every availability zone of the cluster and report
+//!   zones missing what the other zones have.
+//! - vpc: can check the cluster VPCs have DNS support and hostnames enabled.
+//! - zones: can check the availability zones are enabled and offer the
+//!   configured instance types.
+//!
+//! Every check has a stable ID (e.g. `NET-002`) that is reported with its
+//! results - messages may change, IDs never do. IDs are never reused after a
+//! check is removed.
+//!
+//! Planned checks:
+//! - Compare LB setup to configured subnets.
+
+pub mod azure;
+pub mod cidr;
+pub mod cost;
+pub mod custom;
+pub mod delegation;
+pub mod dhcp;
+pub mod dns;
+pub mod doctor;
+pub mod egress;
+pub mod endpoints;
+pub mod flowlogs;
+pub mod health;
+pub mod hypershift;
+pub mod iam;
+pub mod instances;
+pub mod ipv6;
+pub mod kubernetes;
+pub mod leftovers;
+pub mod loadbalancer;
+pub mod machinepools;
+pub mod nacl;
+pub mod network;
+pub mod permissions;
+pub mod preflight;
+pub mod probes;
+pub mod proxy;
+pub mod quotas;
+pub mod resolver;
+pub mod scaling;
+pub mod sharedvpc;
+pub mod ssm;
+pub mod targetgroups;
+pub mod topology;
+pub mod vpc;
+pub mod zones;
+
+use crate::types::{DataSet, GatherFailure, Severity, VerificationResult};
+
+/// Describes a single check of one of the checkers.
+#[derive(Debug)]
+pub struct CheckDescription {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// The data sets the check needs - it is skipped if one of them is
+    /// missing.
+    pub requires: &'static [DataSet],
+    /// Whether the check reports an empty data set itself, e.g. "Too few
+    /// hosted zones found" - it is only skipped if gathering the data set
+    /// failed then.
+    pub reports_empty: bool,
+}
+
+/// All checks, ordered by checker.
+pub const REGISTRY: &[CheckDescription] = &[
+    CheckDescription {
+        id: "NET-001",
+        description: "Every availability zone has a public and a private subnet",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-002",
+        description: "Subnets are tagged for the cluster and for load balancers",
+        requires: &[DataSet::Subnets, DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-003",
+        description: "Subnets use the route tables created by the installer",
+        requires: &[DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-004",
+        description: "Subnets are associated with exactly one route table",
+        requires: &[DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-006",
+        description: "Load balancers use the cluster subnets",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-007",
+        description: "Load balancer ENIs are in the cluster subnets",
+        requires: &[DataSet::LoadBalancers, DataSet::LoadBalancerEnis],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-008",
+        description: "Load balancer ENIs cover the availability zones of the cluster",
+        requires: &[DataSet::LoadBalancers, DataSet::LoadBalancerEnis],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-009",
+        description: "Private subnets route through an available NAT gateway",
+        requires: &[DataSet::RouteTables, DataSet::NatGateways],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-010",
+        description: "No subnet route is a blackhole and private subnets have a default route",
+        requires: &[DataSet::Subnets, DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NET-011",
+        description: "The internet gateways of the public subnets are attached to their VPC and available",
+        requires: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::InternetGateways,
+        ],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NACL-001",
+        description: "Network ACLs allow the traffic the cluster requires",
+        requires: &[DataSet::NetworkAcls],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "NACL-002",
+        description: "Network ACLs use the default allow-all rules",
+        requires: &[DataSet::NetworkAcls],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DLG-001",
+        description:
+            "No ENIs of other services (Transit Gateway, RDS, EKS, ...) are in the cluster subnets",
+        requires: &[DataSet::SubnetNetworkInterfaces],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DLG-002",
+        description: "No cluster subnet is tagged for an EKS cluster",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-001",
+        description: "The cluster has the expected number of hosted zones",
+        requires: &[DataSet::HostedZones],
+        reports_empty: true,
+    },
+    CheckDescription {
+        id: "DNS-002",
+        description: "Records point to existing load balancers",
+        requires: &[DataSet::HostedZones, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-006",
+        description: "The private hosted zone is associated with the cluster VPC",
+        requires: &[DataSet::HostedZones, DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-007",
+        description: "The public hosted zone is delegated from its parent domain",
+        requires: &[DataSet::HostedZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-003",
+        description: "Records only point to load balancers of the cluster",
+        requires: &[DataSet::HostedZones, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-004",
+        description: "No other hosted zone has the same name",
+        requires: &[DataSet::HostedZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-008",
+        description: "The API records point to the API load balancers and the apps wildcard to the default router",
+        requires: &[DataSet::HostedZones, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-009",
+        description: "The API and apps records are aliases of the load balancers and no CNAME is at a zone apex",
+        requires: &[DataSet::HostedZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-010",
+        description: "The TTLs of the records are in a sane range",
+        requires: &[DataSet::HostedZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-011",
+        description: "No name has conflicting records",
+        requires: &[DataSet::HostedZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DNS-005",
+        description: "Every ingress domain has a record pointing to a load balancer of the cluster",
+        requires: &[DataSet::HostedZones, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZ-001",
+        description: "The availability zones of the subnets are enabled",
+        requires: &[DataSet::AvailabilityZones],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZ-002",
+        description: "The instance types are offered in the availability zones",
+        requires: &[DataSet::AvailabilityZones, DataSet::InstanceTypeOfferings],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "MP-001",
+        description: "Machine pools reference existing subnets in their availability zones",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "RES-001",
+        description: "The resolver query logs show no failed lookups of cluster names",
+        requires: &[DataSet::ResolverQueryLogs, DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "HEALTH-001",
+        description: "AWS reports no open EC2, ELB or Route53 events for the cluster",
+        requires: &[DataSet::HealthEvents],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "IAM-001",
+        description: "Instances have an instance profile",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "IAM-002",
+        description: "Instance profile roles allow the actions the nodes need",
+        requires: &[DataSet::Instances, DataSet::InstanceProfiles],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "IPV6-001",
+        description: "Subnets of dual-stack clusters have an IPv6 CIDR block",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "IPV6-002",
+        description: "Subnets of dual-stack clusters have an IPv6 default route",
+        requires: &[DataSet::Subnets, DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "IPV6-003",
+        description: "IPv6 default routes use an egress-only internet gateway of the VPC",
+        requires: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::EgressOnlyInternetGateways,
+        ],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "CIDR-001",
+        description: "Cluster subnets are part of the machine CIDR",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "CIDR-002",
+        description: "Service and pod CIDRs do not overlap the machine CIDR, the VPC or each other",
+        requires: &[DataSet::Vpcs],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DHCP-001",
+        description: "DHCP options provide the AmazonProvidedDNS or custom DNS servers",
+        requires: &[DataSet::Vpcs, DataSet::DhcpOptions],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DHCP-002",
+        description: "The domain name of the DHCP options is the one of the region",
+        requires: &[DataSet::Vpcs, DataSet::DhcpOptions],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "VPC-001",
+        description: "Cluster VPCs have DNS support enabled",
+        requires: &[DataSet::VpcAttributes],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "VPC-002",
+        description: "Cluster VPCs have DNS hostnames enabled",
+        requires: &[DataSet::VpcAttributes],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "VPC-003",
+        description: "Cluster subnets are all in the same VPC",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "TOPO-001",
+        description: "Every availability zone of the cluster has the same subnets, NAT gateways, load balancers and nodes",
+        requires: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::NatGateways,
+            DataSet::LoadBalancers,
+            DataSet::Instances,
+        ],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "COST-001",
+        description: "Subnets use a NAT gateway in their own availability zone",
+        requires: &[DataSet::RouteTables, DataSet::NatGateways],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "COST-002",
+        description: "Availability zones have at most one NAT gateway",
+        requires: &[DataSet::NatGateways],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "COST-003",
+        description: "Elastic IPs are associated",
+        requires: &[DataSet::ElasticIps],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "COST-004",
+        description: "Load balancers have registered targets",
+        requires: &[DataSet::LoadBalancers, DataSet::TargetGroups],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SCALE-001",
+        description: "Subnets have enough free addresses for the machine pools at maximum size",
+        requires: &[DataSet::Subnets, DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SCALE-002",
+        description: "NAT gateways can carry the egress of the machine pools at maximum size",
+        requires: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::NatGateways,
+            DataSet::Instances,
+        ],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "TG-001",
+        description: "Load balancer targets are healthy",
+        requires: &[DataSet::TargetGroups],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "TG-002",
+        description: "Target groups have registered targets",
+        requires: &[DataSet::TargetGroups],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "TG-003",
+        description: "Security groups of the targets allow the health check port",
+        requires: &[DataSet::TargetGroups, DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SSM-001",
+        description: "Cluster subnets can reach SSM for break-glass node access",
+        requires: &[DataSet::Subnets, DataSet::RouteTables, DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-001",
+        description: "API load balancers have deletion protection enabled",
+        requires: &[DataSet::LoadBalancerAttributes],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-002",
+        description: "API load balancers have the tags of the installer",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-003",
+        description: "API and router load balancers write access logs to a valid bucket",
+        requires: &[DataSet::LoadBalancerAttributes, DataSet::AccessLogBuckets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-004",
+        description: "API target groups have a healthy target for every control plane node",
+        requires: &[DataSet::LoadBalancers, DataSet::TargetGroups, DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-005",
+        description: "API and router load balancer schemes match the privacy of the cluster",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-006",
+        description: "API and router load balancers have cross-zone load balancing enabled",
+        requires: &[DataSet::LoadBalancerAttributes],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-007",
+        description: "API and router load balancers have an idle timeout of at least 60 seconds",
+        requires: &[DataSet::LoadBalancerAttributes],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LB-008",
+        description: "A load balancer for the default router exists",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: true,
+    },
+    CheckDescription {
+        id: "INST-001",
+        description: "Instances of the cluster exist",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-002",
+        description: "Instances are running",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-003",
+        description: "Instance security groups allow the cluster traffic",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-004",
+        description: "Instances have private DNS names of the form the cluster expects",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-005",
+        description: "Control plane instances of multi-AZ clusters are spread across 3 availability zones",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-006",
+        description: "Instances run in the cluster VPC and in subnets of the cluster",
+        requires: &[DataSet::Instances, DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "INST-007",
+        description: "Security group rules for node traffic are scoped to the machine CIDR or the cluster security groups",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EP-001",
+        description: "The VPC endpoints private clusters need exist",
+        requires: &[DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EP-002",
+        description: "VPC endpoints are available",
+        requires: &[DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EP-003",
+        description:
+            "Gateway endpoints are associated with the route tables of the cluster subnets",
+        requires: &[DataSet::VpcEndpoints, DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EP-004",
+        description: "Interface endpoints have private DNS enabled",
+        requires: &[DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EP-005",
+        description: "Zero-egress clusters reach the release image mirror in ECR",
+        requires: &[DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PERM-001",
+        description: "No AWS API calls were denied by a policy",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EGR-001",
+        description: "The network type supports egress IPs",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EGR-002",
+        description: "Egress IPs are within the cluster subnets",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EGR-003",
+        description: "Subnets have free addresses for their egress IPs",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EGR-004",
+        description: "Network ACLs allow the traffic of the egress IPs",
+        requires: &[DataSet::NetworkAcls],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "EGR-005",
+        description: "Security groups of egress nodes allow all egress",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-001",
+        description: "The subnets exist and belong to the VPC",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-002",
+        description: "Subnets are tagged for load balancers",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-003",
+        description: "Subnets have enough free addresses",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-004",
+        description: "Subnets have egress to the internet",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-005",
+        description: "The VPC has an S3 gateway endpoint",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-006",
+        description: "Quotas allow the install",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PRE-007",
+        description: "A hosted zone exists for the base domain",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LEFT-001",
+        description: "No load balancers are left over",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LEFT-002",
+        description: "No ENIs are left over",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LEFT-003",
+        description: "No security groups are left over",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LEFT-004",
+        description: "No elastic IPs are left over",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "LEFT-005",
+        description: "No hosted zones or records are left over",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PROXY-001",
+        description: "OCM, the registries and the AWS APIs are reachable through the cluster proxy",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "PROBE-001",
+        description: "OCM, the registries and the AWS APIs resolve and accept connections",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "K8S-001",
+        description: "The load balancers of LoadBalancer Services exist in AWS",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "K8S-002",
+        description: "Every node has an instance and every running instance is a node",
+        requires: &[DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "K8S-003",
+        description: "The ingress domain record points to the default router load balancer",
+        requires: &[DataSet::HostedZones, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SHARED-001",
+        description: "Reports the cluster subnets shared from another account",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SHARED-002",
+        description: "Shared subnets have the cluster tag with value shared in the cluster account",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "SHARED-003",
+        description: "Shared VPCs of public clusters route to an internet gateway",
+        requires: &[DataSet::Subnets, DataSet::RouteTables],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "HCP-001",
+        description: "Every hosted service has an NLB tagged with its service name",
+        requires: &[DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "HCP-002",
+        description: "The private link to the hosted control plane is available",
+        requires: &[DataSet::Subnets, DataSet::VpcEndpoints],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "HCP-003",
+        description: "The worker subnets carry the hosted cluster tag",
+        requires: &[DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "QUOTA-001",
+        description: "The elastic IPs of the region are below their quota",
+        requires: &[DataSet::ServiceQuotas, DataSet::ElasticIps],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "QUOTA-002",
+        description: "The NAT gateways of every availability zone are below their quota",
+        requires: &[DataSet::ServiceQuotas, DataSet::NatGateways, DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "QUOTA-003",
+        description: "The network interfaces of the cluster subnets are below the region quota",
+        requires: &[DataSet::ServiceQuotas, DataSet::SubnetNetworkInterfaces],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "QUOTA-004",
+        description: "The security groups of the instances are below the rule quota",
+        requires: &[DataSet::ServiceQuotas, DataSet::Instances],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "QUOTA-005",
+        description: "The load balancers of the region are below their quotas",
+        requires: &[DataSet::ServiceQuotas, DataSet::LoadBalancers],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "FLOW-001",
+        description: "Flow logs capture the traffic of the cluster VPCs",
+        requires: &[DataSet::FlowLogs, DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "FLOW-002",
+        description: "The flow logs of the cluster deliver their logs",
+        requires: &[DataSet::FlowLogs, DataSet::Subnets],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DOC-001",
+        description: "AWS credentials can be loaded and do not expire during the run",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DOC-002",
+        description: "An AWS region is configured",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DOC-003",
+        description: "The OCM login works",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DOC-004",
+        description: "The configured proxy is reachable",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "DOC-005",
+        description: "The CLIs the tool relies on are installed",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZURE-001",
+        description: "The cluster subnets exist in their Azure virtual network",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZURE-002",
+        description: "The cluster subnets have the network security group of the cluster attached",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZURE-003",
+        description: "Network security groups do not deny the API and router ports",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZURE-004",
+        description: "The managed resource group has the load balancers of the cluster",
+        requires: &[],
+        reports_empty: false,
+    },
+    CheckDescription {
+        id: "AZURE-005",
+        description: "Private DNS zones for the base domain are linked to the virtual network",
+        requires: &[],
+        reports_empty: false,
+    },
+];
+
+/// Replaces the results of the checks with the given ID prefix whose
+/// prerequisites are missing by a note that they were skipped - their results
+/// would be misleading, e.g. "No ENIs found" if the ENIs could not be
+/// gathered. The summary of a checker is dropped as well if any of its checks
+/// were skipped, as it claims all of them passed.
+pub fn skip_missing_prerequisites(
+    prefixes: &[&str],
+    results: Vec<VerificationResult>,
+    missing: &[GatherFailure],
+) -> Vec<VerificationResult> {
+    let skipped: Vec<(&CheckDescription, Vec<&GatherFailure>)> = REGISTRY
+        .iter()
+        .filter(|c| prefixes.iter().any(|p| c.id.starts_with(p)))
+        .map(|c| {
+            let unmet = missing
+                .iter()
+                .filter(|m| c.requires.contains(&m.set) && !(c.reports_empty && m.empty))
+                .collect();
+            (c, unmet)
+        })
+        .filter(|(_, unmet): &(_, Vec<&GatherFailure>)| !unmet.is_empty())
+        .collect();
+    if skipped.is_empty() {
+        return results;
+    }
+    let mut kept: Vec<VerificationResult> = results
+        .into_iter()
+        .filter(|r| match r.check_id {
+            Some(id) => !skipped.iter().any(|(c, _)| c.id == id),
+            None => r.severity != Severity::Ok,
+        })
+        .collect();
+    kept.extend(skipped.iter().map(|(c, unmet)| VerificationResult {
+        message: format!(
+            "{}: skipped: could not gather {}",
+            c.description,
+            unmet.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+        severity: Severity::Info,
+        code: None,
+        check_id: Some(c.id),
+        resource_type: None,
+        resource_id: None,
+        evidence: None,
+    }));
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_registry_ids_are_unique() {
+        let ids: HashSet<&str> = REGISTRY.iter().map(|c| c.id).collect();
+        assert_eq!(ids.len(), REGISTRY.len());
+    }
+
+    #[test]
+    fn test_skip_missing_prerequisites() {
+        let results = || {
+            vec![
+                VerificationResult {
+                    message: "No ENIs found".to_string(),
+                    severity: Severity::Critical,
+                    code: None,
+                    check_id: Some("NET-007"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-1 is missing tag".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("NET-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ]
+        };
+        let missing = |set| GatherFailure {
+            set,
+            reason: "AccessDenied".to_string(),
+            empty: false,
+        };
+        let kept =
+            skip_missing_prerequisites(&["NET-"], results(), &[missing(DataSet::LoadBalancers)]);
+        assert_eq!(kept[0], results()[1]);
+        assert_eq!(
+            kept.iter()
+                .skip(1)
+                .map(|r| r.check_id.unwrap())
+                .collect::<Vec<_>>(),
+            vec!["NET-006", "NET-007", "NET-008"]
+        );
+        assert_eq!(
+            kept[1].message,
+            "Load balancers use the cluster subnets: skipped: could not gather load balancers (AccessDenied)"
+        );
+        assert_eq!(
+            skip_missing_prerequisites(&["NET-"], results(), &[missing(DataSet::HostedZones)]),
+            results()
+        );
+
+        let too_few_zones = vec![VerificationResult {
+            message: "Too few hosted zones found: 0".to_string(),
+            severity: Severity::Critical,
+            code: None,
+            check_id: Some("DNS-001"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }];
+        let none_found = GatherFailure {
+            set: DataSet::HostedZones,
+            reason: "none found".to_string(),
+            empty: true,
+        };
+        let kept = skip_missing_prerequisites(&["DNS-001"], too_few_zones.clone(), &[none_found]);
+        assert_eq!(kept, too_few_zones);
+        let kept = skip_missing_prerequisites(
+            &["DNS-001"],
+            too_few_zones,
+            &[missing(DataSet::HostedZones)],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].severity, Severity::Info);
+        assert_eq!(
+            kept[0].message,
+            "The cluster has the expected number of hosted zones: skipped: could not gather hosted zones (AccessDenied)"
+        );
+    }
+}
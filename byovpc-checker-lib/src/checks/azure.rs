@@ -0,0 +1,420 @@
+//! This checker verifies the network of clusters installed into an existing
+//! virtual network on Azure - the same misconfigurations as in existing AWS
+//! VPCs, with the resources of Azure.
+//! It can check the following conditions right now:
+//!
+//! - The cluster subnets exist in their virtual network.
+//! - The cluster subnets have the network security group of the cluster
+//!   attached.
+//! - The network security groups do not deny the API and router ports.
+//! - The managed resource group has the load balancers of the cluster.
+//! - Private DNS zones for the base domain are linked to the virtual network.
+
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::azure::{
+        resource_name, same_resource, virtual_network_of, AzureClusterData, AzureSubnet,
+    },
+    types::{
+        AzureClusterInfo, FindingCode, MinimalClusterInfo, ResourceType, Severity,
+        VerificationResult, Verifier,
+    },
+};
+
+/// The ports the API and the default router listen on.
+const REQUIRED_PORTS: [(u16, &str); 2] = [(6443, "API"), (443, "router")];
+
+/// Sources that include the traffic of the nodes and of the clients of the
+/// cluster.
+const BROAD_SOURCES: [&str; 4] = ["*", "any", "internet", "virtualnetwork"];
+
+#[derive(Debug, Builder)]
+pub struct AzureChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    azure_info: &'a AzureClusterInfo,
+    data: &'a AzureClusterData,
+}
+
+impl<'a> AzureChecks<'a> {
+    fn gathered(&self, what: &str) -> bool {
+        !self.data.failed.iter().any(|f| f == what)
+    }
+
+    /// The cluster subnets that were found in their virtual network.
+    fn cluster_subnets(&self) -> Vec<&AzureSubnet> {
+        self.data
+            .virtual_networks
+            .iter()
+            .flat_map(|n| n.subnets.iter())
+            .filter(|s| {
+                self.azure_info
+                    .subnet_ids
+                    .iter()
+                    .any(|id| same_resource(id, &s.id))
+            })
+            .collect()
+    }
+
+    pub fn verify_subnets_exist(&self) -> Vec<VerificationResult> {
+        info!("Checking the cluster subnets exist");
+        self.azure_info
+            .subnet_ids
+            .iter()
+            .map(|id| {
+                let found = self
+                    .data
+                    .virtual_networks
+                    .iter()
+                    .flat_map(|n| n.subnets.iter())
+                    .any(|s| same_resource(id, &s.id));
+                if found {
+                    VerificationResult {
+                        message: format!("Subnet {} exists", resource_name(id)),
+                        severity: Severity::Ok,
+                        code: None,
+                        check_id: Some("AZURE-001"),
+                        resource_type: Some(ResourceType::AzureSubnet),
+                        resource_id: Some(id.to_string()),
+                        evidence: None,
+                    }
+                } else {
+                    VerificationResult {
+                        message: format!(
+                            "Subnet {} does not exist in virtual network {}",
+                            resource_name(id),
+                            virtual_network_of(id).map_or("<unknown>", resource_name)
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::AzureSubnetNotFound),
+                        check_id: Some("AZURE-001"),
+                        resource_type: Some(ResourceType::AzureSubnet),
+                        resource_id: Some(id.to_string()),
+                        evidence: Some(json!({"subnet_id": id})),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    pub fn verify_subnet_security_groups(&self) -> Vec<VerificationResult> {
+        info!("Checking the network security groups of the cluster subnets");
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            match (
+                &subnet.network_security_group,
+                &self.azure_info.network_security_group_id,
+            ) {
+                (None, _) => results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has no network security group attached - the cluster requires one on its subnets",
+                        subnet.name
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::AzureSubnetWithoutNsg),
+                    check_id: Some("AZURE-002"),
+                    resource_type: Some(ResourceType::AzureSubnet),
+                    resource_id: Some(subnet.id.clone()),
+                    evidence: Some(json!({"subnet_id": subnet.id})),
+                }),
+                (Some(attached), Some(expected)) if !same_resource(attached, expected) => {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Subnet {} has network security group {} attached, but the cluster was configured with {}",
+                            subnet.name,
+                            resource_name(attached),
+                            resource_name(expected)
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::AzureSubnetNsgMismatch),
+                        check_id: Some("AZURE-002"),
+                        resource_type: Some(ResourceType::AzureSubnet),
+                        resource_id: Some(subnet.id.clone()),
+                        evidence: Some(json!({"attached": attached, "expected": expected})),
+                    })
+                }
+                (Some(attached), _) => results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has network security group {} attached",
+                        subnet.name,
+                        resource_name(attached)
+                    ),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("AZURE-002"),
+                    resource_type: Some(ResourceType::AzureSubnet),
+                    resource_id: Some(subnet.id.clone()),
+                    evidence: None,
+                }),
+            }
+        }
+        results
+    }
+
+    pub fn verify_security_group_rules(&self) -> Vec<VerificationResult> {
+        info!("Checking the network security groups allow the API and router");
+        let mut results = vec![];
+        for group in self.data.network_security_groups.iter() {
+            let mut inbound: Vec<_> = group
+                .security_rules
+                .iter()
+                .filter(|r| r.direction.eq_ignore_ascii_case("inbound"))
+                .filter(|r| {
+                    r.source_address_prefixes
+                        .iter()
+                        .any(|s| BROAD_SOURCES.contains(&s.to_ascii_lowercase().as_str()))
+                })
+                .collect();
+            inbound.sort_by_key(|r| r.priority);
+            for (port, what) in REQUIRED_PORTS {
+                // Rules are evaluated by priority - the first matching rule
+                // wins.
+                let Some(rule) = inbound.iter().find(|r| r.matches_tcp_port(port)) else {
+                    continue;
+                };
+                if rule.access.eq_ignore_ascii_case("deny") {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Network security group {} denies the {} port {} with rule {} (priority {})",
+                            group.name, what, port, rule.name, rule.priority
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::AzureNsgDeniesRequiredPort),
+                        check_id: Some("AZURE-003"),
+                        resource_type: Some(ResourceType::AzureNetworkSecurityGroup),
+                        resource_id: Some(group.id.clone()),
+                        evidence: Some(json!({
+                            "network_security_group": group.id,
+                            "rule": rule.name,
+                            "port": port,
+                        })),
+                    });
+                }
+            }
+        }
+        if results.is_empty() && !self.data.network_security_groups.is_empty() {
+            results.push(VerificationResult {
+                message: "No network security group denies the API or router ports".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("AZURE-003"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+
+    pub fn verify_load_balancers(&self) -> Vec<VerificationResult> {
+        info!("Checking the load balancers of the cluster");
+        let Some(ref resource_group) = self.azure_info.managed_resource_group else {
+            return vec![];
+        };
+        if !self.data.load_balancers.is_empty() {
+            return vec![VerificationResult {
+                message: format!(
+                    "Resource group {} has {} load balancers",
+                    resource_group,
+                    self.data.load_balancers.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("AZURE-004"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        vec![VerificationResult {
+            message: format!(
+                "Resource group {} has no load balancers - the API and router of the cluster are not reachable",
+                resource_group
+            ),
+            severity: Severity::Warning,
+            code: Some(FindingCode::AzureLoadBalancersMissing),
+            check_id: Some("AZURE-004"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(json!({"resource_group": resource_group})),
+        }]
+    }
+
+    pub fn verify_private_dns_zones(&self) -> Vec<VerificationResult> {
+        info!("Checking the private DNS zones are linked to the virtual network");
+        let mut results = vec![];
+        for network in self.data.virtual_networks.iter() {
+            for zone in self.data.private_dns_zones.iter() {
+                if zone
+                    .virtual_networks
+                    .iter()
+                    .any(|id| same_resource(id, &network.id))
+                {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Private DNS zone {} is linked to virtual network {}",
+                            zone.name, network.name
+                        ),
+                        severity: Severity::Ok,
+                        code: None,
+                        check_id: Some("AZURE-005"),
+                        resource_type: Some(ResourceType::AzurePrivateDnsZone),
+                        resource_id: Some(zone.id.clone()),
+                        evidence: None,
+                    });
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "Private DNS zone {} for {} is not linked to virtual network {} - the nodes can not resolve its records",
+                        zone.name,
+                        self.cluster_info.base_domain.as_deref().unwrap_or_default(),
+                        network.name
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::AzurePrivateDnsZoneNotLinked),
+                    check_id: Some("AZURE-005"),
+                    resource_type: Some(ResourceType::AzurePrivateDnsZone),
+                    resource_id: Some(zone.id.clone()),
+                    evidence: Some(json!({
+                        "private_dns_zone": zone.id,
+                        "virtual_network": network.id,
+                    })),
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for AzureChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results: Vec<VerificationResult> = self
+            .data
+            .failed
+            .iter()
+            .map(|what| VerificationResult {
+                message: format!(
+                    "The {} could not be gathered - the checks depending on them are skipped",
+                    what
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            })
+            .collect();
+        if self.gathered("virtual networks") {
+            results.extend(self.verify_subnets_exist());
+            results.extend(self.verify_subnet_security_groups());
+        }
+        if self.gathered("network security groups") {
+            results.extend(self.verify_security_group_rules());
+        }
+        if self.gathered("load balancers") {
+            results.extend(self.verify_load_balancers());
+        }
+        if self.gathered("private DNS zones") {
+            results.extend(self.verify_private_dns_zones());
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        gatherer::azure::{
+            AzureNetworkSecurityGroup, AzurePrivateDnsZone, AzureSecurityRule, AzureVirtualNetwork,
+        },
+        types::MinimalClusterInfoBuilder,
+    };
+
+    use super::*;
+
+    const VNET: &str =
+        "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/virtualNetworks/vnet";
+    const NSG: &str =
+        "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/networkSecurityGroups/nsg";
+
+    #[test]
+    fn test_verify_azure() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cloud_provider("azure".to_string())
+            .base_domain(Some("example.com".to_string()))
+            .build()
+            .unwrap();
+        let azure_info = AzureClusterInfo {
+            subscription_id: "1".to_string(),
+            resource_group: "rg".to_string(),
+            managed_resource_group: Some("rg-managed".to_string()),
+            subnet_ids: vec![
+                format!("{}/subnets/nodes", VNET),
+                format!("{}/subnets/gone", VNET),
+            ],
+            network_security_group_id: Some(NSG.to_string()),
+        };
+        let data = AzureClusterData {
+            virtual_networks: vec![AzureVirtualNetwork {
+                id: VNET.to_uppercase(),
+                name: "vnet".to_string(),
+                address_prefixes: vec!["10.0.0.0/16".to_string()],
+                subnets: vec![AzureSubnet {
+                    id: format!("{}/subnets/nodes", VNET),
+                    name: "nodes".to_string(),
+                    address_prefixes: vec!["10.0.0.0/24".to_string()],
+                    network_security_group: Some(format!("{}-other", NSG)),
+                    route_table: None,
+                    nat_gateway: None,
+                }],
+            }],
+            network_security_groups: vec![AzureNetworkSecurityGroup {
+                id: NSG.to_string(),
+                name: "nsg".to_string(),
+                security_rules: vec![AzureSecurityRule {
+                    name: "deny-all".to_string(),
+                    priority: 100,
+                    direction: "Inbound".to_string(),
+                    access: "Deny".to_string(),
+                    protocol: "*".to_string(),
+                    source_address_prefixes: vec!["Internet".to_string()],
+                    destination_port_ranges: vec!["400-500".to_string()],
+                }],
+            }],
+            load_balancers: vec![],
+            private_dns_zones: vec![AzurePrivateDnsZone {
+                id: "zone".to_string(),
+                name: "example.com".to_string(),
+                virtual_networks: vec![],
+            }],
+            failed: vec![],
+        };
+        let checks = AzureChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .azure_info(&azure_info)
+            .data(&data)
+            .build()
+            .unwrap();
+        let codes: Vec<Option<FindingCode>> = checks
+            .verify()
+            .into_iter()
+            .filter(|r| r.severity > Severity::Ok)
+            .map(|r| r.code)
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                Some(FindingCode::AzureSubnetNotFound),
+                Some(FindingCode::AzureSubnetNsgMismatch),
+                Some(FindingCode::AzureNsgDeniesRequiredPort),
+                Some(FindingCode::AzureLoadBalancersMissing),
+                Some(FindingCode::AzurePrivateDnsZoneNotLinked),
+            ]
+        );
+    }
+}
@@ -0,0 +1,225 @@
+//! This checker compares the CIDR blocks of the VPC and the subnets with the
+//! machine, service and pod CIDRs of the cluster. Mismatches make the install
+//! fail in ways that are painful to diagnose.
+//! It can check the following conditions right now:
+//!
+//! - Every cluster subnet is part of the machine CIDR.
+//! - The service and pod CIDRs do not overlap the machine CIDR, the CIDR
+//!   blocks of the VPC or each other.
+
+use aws_sdk_ec2::types::{Subnet, Vpc};
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    checks::egress::{cidr_contains, parse_cidr},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// Whether the two CIDR blocks share any address.
+fn cidr_overlaps(a: (u32, u8), b: (u32, u8)) -> bool {
+    cidr_contains(a, b) || cidr_contains(b, a)
+}
+
+#[derive(Debug, Builder)]
+pub struct CidrChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    vpcs: Vec<Vpc>,
+}
+
+impl<'a> CidrChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    pub fn verify_subnets_in_machine_cidr(&self) -> Vec<VerificationResult> {
+        info!("Checking subnets are part of the machine CIDR");
+        let Some(machine_cidr) = self.cluster_info.machine_cidr.as_deref() else {
+            return vec![];
+        };
+        let Some(machine) = parse_cidr(machine_cidr) else {
+            return vec![];
+        };
+        self.cluster_subnets()
+            .into_iter()
+            .filter_map(|s| {
+                let cidr_block = s.cidr_block()?;
+                if cidr_contains(machine, parse_cidr(cidr_block)?) {
+                    return None;
+                }
+                Some(VerificationResult {
+                    message: format!(
+                        "Subnet {} ({}) is outside the machine CIDR {}",
+                        s.subnet_id().unwrap_or_default(),
+                        cidr_block,
+                        machine_cidr
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::SubnetOutsideMachineCidr),
+                    check_id: Some("CIDR-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(s.subnet_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn verify_no_overlapping_cidrs(&self) -> Vec<VerificationResult> {
+        info!("Checking service and pod CIDRs do not overlap other networks");
+        let mut networks: Vec<(String, &str)> = vec![];
+        if let Some(ref machine_cidr) = self.cluster_info.machine_cidr {
+            networks.push(("the machine CIDR".to_string(), machine_cidr));
+        }
+        for vpc in self.vpcs.iter() {
+            for association in vpc.cidr_block_association_set() {
+                if let Some(cidr_block) = association.cidr_block() {
+                    networks.push((
+                        format!("VPC {}", vpc.vpc_id().unwrap_or_default()),
+                        cidr_block,
+                    ));
+                }
+            }
+        }
+        let mut results = vec![];
+        let cluster_networks = [
+            ("Service", self.cluster_info.service_cidr.as_deref()),
+            ("Pod", self.cluster_info.pod_cidr.as_deref()),
+        ];
+        for (i, (name, cidr)) in cluster_networks.iter().enumerate() {
+            let Some((cidr, parsed)) = cidr.and_then(|c| Some((c, parse_cidr(c)?))) else {
+                continue;
+            };
+            let mut others = networks.clone();
+            // Every pair of the cluster networks is only reported once.
+            for (other_name, other_cidr) in cluster_networks.iter().skip(i + 1) {
+                if let Some(other_cidr) = other_cidr {
+                    others.push((
+                        format!("the {} CIDR", other_name.to_lowercase()),
+                        other_cidr,
+                    ));
+                }
+            }
+            for (other, other_cidr) in others {
+                if parse_cidr(other_cidr).is_some_and(|o| cidr_overlaps(parsed, o)) {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "{} CIDR {} overlaps {} ({})",
+                            name, cidr, other, other_cidr
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::CidrOverlap),
+                        check_id: Some("CIDR-002"),
+                        resource_type: None,
+                        resource_id: None,
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for CidrChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_subnets_in_machine_cidr());
+        results.extend(self.verify_no_overlapping_cidrs());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message:
+                    "Subnets are part of the machine CIDR and no cluster network overlaps the VPC"
+                        .to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::VpcCidrBlockAssociation;
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_cidrs() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_cidr(Some("10.0.0.0/16".to_string()))
+            .service_cidr(Some("172.30.0.0/16".to_string()))
+            .pod_cidr(Some("10.128.0.0/14".to_string()))
+            .build()
+            .unwrap();
+        let cc = CidrChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                Subnet::builder()
+                    .subnet_id("subnet-1")
+                    .cidr_block("10.0.1.0/24")
+                    .build(),
+                Subnet::builder()
+                    .subnet_id("subnet-2")
+                    .cidr_block("10.1.0.0/24")
+                    .build(),
+            ])
+            .vpcs(vec![Vpc::builder()
+                .vpc_id("vpc-1")
+                .cidr_block_association_set(
+                    VpcCidrBlockAssociation::builder()
+                        .cidr_block("10.0.0.0/16")
+                        .build(),
+                )
+                .cidr_block_association_set(
+                    VpcCidrBlockAssociation::builder()
+                        .cidr_block("172.30.0.0/24")
+                        .build(),
+                )
+                .build()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cc.verify(),
+            vec![
+                VerificationResult {
+                    message:
+                        "Subnet subnet-2 (10.1.0.0/24) is outside the machine CIDR 10.0.0.0/16"
+                            .to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::SubnetOutsideMachineCidr),
+                    check_id: Some("CIDR-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-2".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Service CIDR 172.30.0.0/16 overlaps VPC vpc-1 (172.30.0.0/24)"
+                        .to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::CidrOverlap),
+                    check_id: Some("CIDR-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
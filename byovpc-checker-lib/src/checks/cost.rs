@@ -0,0 +1,269 @@
+//! This checker flags networking patterns that are more expensive than they
+//! need to be. All findings are informational.
+//! It can check the following conditions right now:
+//!
+//! - Subnets routing through a NAT gateway in a different AZ.
+//! - More than one NAT gateway per AZ.
+//! - Elastic IPs that are not associated with anything.
+//! - Load balancers without any registered targets.
+
+use std::collections::{BTreeMap, HashMap};
+
+use aws_sdk_ec2::types::{Address, NatGateway, NatGatewayState, RouteTable, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    checks::network::subnet_routetable_mapping,
+    gatherer::aws::shared_types::{AWSLoadBalancer, AWSTargetGroup},
+    types::{ResourceType, Severity, VerificationResult, Verifier},
+};
+
+#[derive(Debug, Builder)]
+pub struct CostChecks {
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<NatGateway>,
+    #[builder(default = "vec![]")]
+    elastic_ips: Vec<Address>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    target_groups: Vec<AWSTargetGroup>,
+}
+
+impl CostChecks {
+    fn subnet_azs(&self) -> HashMap<&str, &str> {
+        self.subnets
+            .iter()
+            .filter_map(|s| Some((s.subnet_id()?, s.availability_zone()?)))
+            .collect()
+    }
+
+    fn available_nat_gateways(&self) -> impl Iterator<Item = &NatGateway> {
+        self.nat_gateways
+            .iter()
+            .filter(|n| n.state() == Some(&NatGatewayState::Available))
+    }
+
+    pub fn verify_cross_az_nat_usage(&self) -> Vec<VerificationResult> {
+        info!("Checking for cross-AZ NAT gateway usage");
+        let mut results = vec![];
+        let subnet_azs = self.subnet_azs();
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let mut subnet_ids: Vec<&String> = mapping.keys().collect();
+        subnet_ids.sort();
+        for subnet_id in subnet_ids {
+            let Some(subnet_az) = subnet_azs.get(subnet_id.as_str()) else {
+                continue;
+            };
+            for route in mapping[subnet_id].routes() {
+                let Some(nat_id) = route.nat_gateway_id() else {
+                    continue;
+                };
+                let nat_az = self
+                    .nat_gateways
+                    .iter()
+                    .find(|n| n.nat_gateway_id() == Some(nat_id))
+                    .and_then(|n| n.subnet_id())
+                    .and_then(|s| subnet_azs.get(s));
+                if let Some(nat_az) = nat_az {
+                    if nat_az != subnet_az {
+                        results.push(VerificationResult {
+                            message: format!(
+                                "Subnet {} in AZ {} routes through NAT gateway {} in AZ {} - cross-AZ traffic incurs data transfer charges",
+                                subnet_id, subnet_az, nat_id, nat_az
+                            ),
+                            severity: Severity::Info,
+                            code: None,
+                            check_id: Some("COST-001"),
+                            resource_type: Some(ResourceType::Subnet),
+                            resource_id: Some(subnet_id.to_string()),
+                            evidence: None,
+                        });
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_nat_gateways_per_az(&self) -> Vec<VerificationResult> {
+        info!("Checking number of NAT gateways per AZ");
+        let subnet_azs = self.subnet_azs();
+        let mut nats_per_az: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for nat in self.available_nat_gateways() {
+            let Some(az) = nat.subnet_id().and_then(|s| subnet_azs.get(s)) else {
+                continue;
+            };
+            nats_per_az
+                .entry(az)
+                .or_default()
+                .push(nat.nat_gateway_id().unwrap_or_default());
+        }
+        nats_per_az
+            .into_iter()
+            .filter(|(_, nats)| nats.len() > 1)
+            .map(|(az, nats)| VerificationResult {
+                message: format!(
+                    "AZ {} has {} NAT gateways ({}) - one NAT gateway per AZ is usually sufficient",
+                    az,
+                    nats.len(),
+                    nats.join(", ")
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("COST-002"),
+                resource_type: Some(ResourceType::AvailabilityZone),
+                resource_id: Some(az.to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_unused_elastic_ips(&self) -> Vec<VerificationResult> {
+        info!("Checking for unused elastic IPs");
+        self.elastic_ips
+            .iter()
+            .filter(|a| a.association_id().is_none() && a.network_interface_id().is_none())
+            .map(|a| VerificationResult {
+                message: format!(
+                    "Elastic IP {} ({}) in the region is not associated - unused elastic IPs are billed",
+                    a.public_ip().unwrap_or_default(),
+                    a.allocation_id().unwrap_or_default()
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("COST-003"),
+                resource_type: Some(ResourceType::ElasticIp),
+                resource_id: Some(a.allocation_id().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_idle_load_balancers(&self) -> Vec<VerificationResult> {
+        info!("Checking for load balancers without targets");
+        let mut results = vec![];
+        for lb in self.load_balancers.iter() {
+            let (name, idle) = match lb {
+                AWSLoadBalancer::ClassicLoadBalancer((c, _)) => (
+                    c.load_balancer_name().unwrap_or_default(),
+                    c.instances().is_empty(),
+                ),
+                AWSLoadBalancer::ModernLoadBalancer((m, _)) => {
+                    let arn = m.load_balancer_arn().unwrap_or_default();
+                    let targets = self
+                        .target_groups
+                        .iter()
+                        .filter(|tg| {
+                            tg.target_group
+                                .load_balancer_arns()
+                                .iter()
+                                .any(|a| a == arn)
+                        })
+                        .map(|tg| tg.targets.len())
+                        .sum::<usize>();
+                    (m.load_balancer_name().unwrap_or_default(), targets == 0)
+                }
+            };
+            if idle {
+                results.push(VerificationResult {
+                    message: format!(
+                        "LoadBalancer {} has no registered targets - idle load balancers are still billed",
+                        name
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("COST-004"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some(lb.identifier().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl Verifier for CostChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_cross_az_nat_usage());
+        results.extend(self.verify_nat_gateways_per_az());
+        results.extend(self.verify_unused_elastic_ips());
+        results.extend(self.verify_idle_load_balancers());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "No costly networking patterns found".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Route, RouteTableAssociation};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_cross_az_nat_usage() {
+        let subnets = vec![
+            Subnet::builder()
+                .subnet_id("private-a")
+                .availability_zone("us-east-1a")
+                .build(),
+            Subnet::builder()
+                .subnet_id("public-b")
+                .availability_zone("us-east-1b")
+                .build(),
+        ];
+        let rtb = RouteTable::builder()
+            .associations(
+                RouteTableAssociation::builder()
+                    .subnet_id("private-a")
+                    .build(),
+            )
+            .routes(
+                Route::builder()
+                    .destination_cidr_block("0.0.0.0/0")
+                    .nat_gateway_id("nat-1")
+                    .build(),
+            )
+            .build();
+        let nat = NatGateway::builder()
+            .nat_gateway_id("nat-1")
+            .subnet_id("public-b")
+            .state(NatGatewayState::Available)
+            .build();
+        let cc = CostChecksBuilder::default()
+            .subnets(subnets)
+            .routetables(vec![rtb])
+            .nat_gateways(vec![nat])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cc.verify_cross_az_nat_usage(),
+            vec![VerificationResult {
+                message: "Subnet private-a in AZ us-east-1a routes through NAT gateway nat-1 in AZ us-east-1b - cross-AZ traffic incurs data transfer charges".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("COST-001"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some("private-a".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+}
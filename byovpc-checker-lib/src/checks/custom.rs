@@ -0,0 +1,358 @@
+//! This checker runs rules defined by the user in YAML files, so teams can
+//! encode the VPC policies of their organization (e.g. required tags or
+//! minimum subnet sizes) without changing the tool.
+//!
+//! ```yaml
+//! rules:
+//!   - id: CUSTOM-TAG-001
+//!     description: Subnets have a cost center
+//!     resource: subnets
+//!     when: "!has(tags['kubernetes.io/role/elb'])"
+//!     expect: has(tags['cost-center'])
+//!     severity: warning
+//!     message: Subnet {subnet_id} has no cost-center tag
+//! ```
+//!
+//! A rule is evaluated for every resource of its data set, in the format of
+//! the data set in a snapshot - except tags, which are a map of key to value.
+//! Resources matching `when` (all if it is not set) that do not match `expect`
+//! are reported with the message, where `{...}` is replaced by the value of
+//! the expression in the braces. See [`expression`] for the syntax of the
+//! expressions.
+
+pub mod expression;
+
+use std::{collections::HashSet, path::Path};
+
+use derive_builder::Builder;
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::types::{
+    DataSet, GatherFailure, InvariantError, ResourceType, Severity, VerificationResult, Verifier,
+};
+use expression::Expression;
+
+/// The IDs of custom rules start with it, so they never clash with the IDs of
+/// the built-in checks.
+pub const CUSTOM_PREFIX: &str = "CUSTOM-";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    rules: Vec<RuleDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDefinition {
+    id: String,
+    description: String,
+    resource: DataSet,
+    when: Option<String>,
+    expect: String,
+    severity: Severity,
+    message: String,
+}
+
+/// A part of the message of a rule.
+#[derive(Clone, Debug, PartialEq)]
+enum MessagePart {
+    Text(String),
+    Value(Expression),
+}
+
+fn parse_message(message: &str) -> Result<Vec<MessagePart>, InvariantError> {
+    let mut parts = vec![];
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(InvariantError {
+                msg: format!("Unclosed '{{' in message '{}'", message),
+            });
+        };
+        parts.push(MessagePart::Text(rest[..start].to_string()));
+        parts.push(MessagePart::Value(Expression::parse(
+            &rest[start + 1..start + len],
+        )?));
+        rest = &rest[start + len + 1..];
+    }
+    parts.push(MessagePart::Text(rest.to_string()));
+    Ok(parts)
+}
+
+/// A rule loaded from a rule file.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: String,
+    pub resource: DataSet,
+    pub severity: Severity,
+    when: Option<Expression>,
+    expect: Expression,
+    message: Vec<MessagePart>,
+}
+
+impl Rule {
+    fn message(&self, resource: &Value) -> String {
+        self.message
+            .iter()
+            .map(|part| match part {
+                MessagePart::Text(text) => text.clone(),
+                MessagePart::Value(expression) => match expression.evaluate(resource) {
+                    Ok(Value::String(s)) => s,
+                    Ok(value) => value.to_string(),
+                    Err(_) => "<invalid>".to_string(),
+                },
+            })
+            .collect()
+    }
+}
+
+/// Reads the rules of a rule file and verifies their expressions.
+pub fn load_rules(path: &Path) -> Result<Vec<Rule>, InvariantError> {
+    let invalid = |msg: String| InvariantError {
+        msg: format!("Invalid rule file {}: {}", path.display(), msg),
+    };
+    let content = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let file: RuleFile = serde_yaml::from_str(&content).map_err(|e| invalid(e.to_string()))?;
+    let mut ids = HashSet::new();
+    let mut rules = vec![];
+    for definition in file.rules {
+        if !definition.id.starts_with(CUSTOM_PREFIX) {
+            return Err(invalid(format!(
+                "the ID of rule {} does not start with {}",
+                definition.id, CUSTOM_PREFIX
+            )));
+        }
+        if !ids.insert(definition.id.clone()) {
+            return Err(invalid(format!("rule {} is defined twice", definition.id)));
+        }
+        let in_rule = |e: InvariantError| invalid(format!("rule {}: {}", definition.id, e));
+        rules.push(Rule {
+            when: definition
+                .when
+                .as_deref()
+                .map(Expression::parse)
+                .transpose()
+                .map_err(in_rule)?,
+            expect: Expression::parse(&definition.expect).map_err(in_rule)?,
+            message: parse_message(&definition.message).map_err(in_rule)?,
+            // Results refer to their check by a static ID. Rules are loaded
+            // once per run, so their IDs live until the end of it anyway.
+            id: Box::leak(definition.id.into_boxed_str()),
+            description: definition.description,
+            resource: definition.resource,
+            severity: definition.severity,
+        });
+    }
+    Ok(rules)
+}
+
+/// The field of a snapshot that holds the resources of the data set.
+fn snapshot_field(set: DataSet) -> String {
+    match set {
+        DataSet::RouteTables => "routetables".to_string(),
+        set => serde_json::to_value(set)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    }
+}
+
+/// The type of the resources of a data set and the field of the snapshot
+/// holding their ID - the data sets of composite resources have none.
+fn resource_key(set: DataSet) -> Option<(ResourceType, &'static str)> {
+    match set {
+        DataSet::Subnets => Some((ResourceType::Subnet, "subnet_id")),
+        DataSet::Vpcs => Some((ResourceType::Vpc, "vpc_id")),
+        DataSet::RouteTables => Some((ResourceType::RouteTable, "route_table_id")),
+        DataSet::NetworkAcls => Some((ResourceType::NetworkAcl, "network_acl_id")),
+        DataSet::NatGateways => Some((ResourceType::NatGateway, "nat_gateway_id")),
+        DataSet::InternetGateways => Some((ResourceType::InternetGateway, "internet_gateway_id")),
+        DataSet::EgressOnlyInternetGateways => Some((
+            ResourceType::EgressOnlyInternetGateway,
+            "egress_only_internet_gateway_id",
+        )),
+        DataSet::ElasticIps => Some((ResourceType::ElasticIp, "allocation_id")),
+        DataSet::VpcEndpoints => Some((ResourceType::VpcEndpoint, "vpc_endpoint_id")),
+        DataSet::SubnetNetworkInterfaces => {
+            Some((ResourceType::NetworkInterface, "network_interface_id"))
+        }
+        DataSet::DhcpOptions => Some((ResourceType::DhcpOptions, "dhcp_options_id")),
+        DataSet::FlowLogs => Some((ResourceType::FlowLog, "flow_log_id")),
+        _ => None,
+    }
+}
+
+/// Turns the list of tags of a resource into a map of key to value, so rules
+/// can refer to a tag as `tags.Name`.
+fn with_tag_map(mut resource: Value) -> Value {
+    if let Some(Value::Array(tags)) = resource.get("tags") {
+        let map = tags
+            .iter()
+            .filter_map(|t| Some((t.get("key")?.as_str()?.to_string(), t["value"].clone())))
+            .collect();
+        resource["tags"] = Value::Object(map);
+    }
+    resource
+}
+
+#[derive(Debug, Builder)]
+pub struct CustomChecks {
+    #[builder(default = "vec![]")]
+    rules: Vec<Rule>,
+    /// The gathered data in the format of a snapshot.
+    #[builder(default = "Value::Null")]
+    data: Value,
+    #[builder(default = "vec![]")]
+    missing: Vec<GatherFailure>,
+}
+
+impl CustomChecks {
+    pub fn verify_rule(&self, rule: &Rule) -> Vec<VerificationResult> {
+        info!("Checking custom rule {}", rule.id);
+        if let Some(failure) = self.missing.iter().find(|m| m.set == rule.resource) {
+            return vec![VerificationResult {
+                message: format!(
+                    "{}: skipped: could not gather {}",
+                    rule.description, failure
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some(rule.id),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let resources = self.data[snapshot_field(rule.resource)]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let mut results = vec![];
+        let mut checked = 0;
+        for resource in resources.into_iter().map(with_tag_map) {
+            let applies = match rule.when {
+                Some(ref when) => when.holds(&resource),
+                None => Ok(true),
+            };
+            let matches = match applies {
+                Ok(false) => continue,
+                Ok(true) => rule.expect.holds(&resource),
+                Err(e) => Err(e),
+            };
+            checked += 1;
+            match matches {
+                Ok(true) => {}
+                Ok(false) => results.push(VerificationResult {
+                    message: rule.message(&resource),
+                    severity: rule.severity,
+                    code: None,
+                    check_id: Some(rule.id),
+                    resource_type: resource_key(rule.resource).map(|(t, _)| t),
+                    resource_id: resource_key(rule.resource)
+                        .and_then(|(_, field)| resource[field].as_str().map(str::to_string)),
+                    evidence: None,
+                }),
+                Err(e) => {
+                    results.push(VerificationResult {
+                        message: format!("Rule {} can not be evaluated: {}", rule.id, e),
+                        severity: Severity::Warning,
+                        code: None,
+                        check_id: Some(rule.id),
+                        resource_type: None,
+                        resource_id: None,
+                        evidence: None,
+                    });
+                    return results;
+                }
+            }
+        }
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "{}: all {} {} match",
+                    rule.description, checked, rule.resource
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some(rule.id),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl Verifier for CustomChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self.rules.is_empty() {
+            return vec![VerificationResult {
+                message: "No custom rules are loaded - pass them with --rules".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        self.rules
+            .iter()
+            .flat_map(|r| self.verify_rule(r))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_rule() {
+        let path = std::env::temp_dir().join("byovpc-checker-test-rules.yaml");
+        std::fs::write(
+            &path,
+            r#"
+rules:
+  - id: CUSTOM-TAG-001
+    description: Subnets have a cost center
+    resource: subnets
+    when: "available_ip_address_count > 0"
+    expect: has(tags['cost-center'])
+    severity: warning
+    message: Subnet {subnet_id} ({tags.Name}) has no cost-center tag
+"#,
+        )
+        .unwrap();
+        let rules = load_rules(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let subnet = |id: &str, tags: Value| json!({"subnet_id": id, "available_ip_address_count": 10, "tags": tags});
+        let cc = CustomChecksBuilder::default()
+            .rules(rules.clone())
+            .data(json!({"subnets": [
+                subnet("subnet-1", json!([{"key": "cost-center", "value": "42"}])),
+                subnet("subnet-2", json!([{"key": "Name", "value": "private"}])),
+            ]}))
+            .build()
+            .unwrap();
+        assert_eq!(
+            cc.verify_rule(&rules[0]),
+            vec![VerificationResult {
+                message: "Subnet subnet-2 (private) has no cost-center tag".to_string(),
+                severity: Severity::Warning,
+                code: None,
+                check_id: Some("CUSTOM-TAG-001"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some("subnet-2".to_string()),
+                evidence: None,
+            }]
+        );
+    }
+}
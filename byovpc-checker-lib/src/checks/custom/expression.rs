@@ -0,0 +1,472 @@
+//! A small expression language in the style of CEL to write the conditions of
+//! custom rules, evaluated against the JSON of a gathered resource.
+//!
+//! ```text
+//! expr    := and ('||' and)*
+//! and     := unary ('&&' unary)*
+//! unary   := '!' unary | compare
+//! compare := operand (('==' | '!=' | '<' | '<=' | '>' | '>=' | 'in') operand)?
+//! operand := literal | '[' (expr (',' expr)*)? ']' | call | path | '(' expr ')'
+//! call    := ('has' | 'size' | 'startsWith' | 'endsWith' | 'contains') '(' expr (',' expr)* ')'
+//! path    := ident ('.' ident | '[' (string | number) ']')*
+//! ```
+//!
+//! Fields that do not exist evaluate to `null`, so `has(tags.owner)` and
+//! `tags.owner == null` are the same.
+
+use std::fmt::Display;
+
+use serde_json::Value;
+
+use crate::types::InvariantError;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Punct(&'static str),
+}
+
+const PUNCTUATION: [&str; 16] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", "[", "]", ".", ",", "-",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|e| *e == c)
+                .ok_or_else(|| format!("unterminated string at {}", i))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_ascii_digit() {
+            let len = chars[i..]
+                .iter()
+                .take_while(|d| d.is_ascii_digit() || **d == '.')
+                .count();
+            let number: String = chars[i..i + len].iter().collect();
+            tokens.push(Token::Num(
+                number
+                    .parse()
+                    .map_err(|_| format!("invalid number {}", number))?,
+            ));
+            i += len;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = chars[i..]
+                .iter()
+                .take_while(|d| d.is_ascii_alphanumeric() || **d == '_')
+                .count();
+            tokens.push(Token::Ident(chars[i..i + len].iter().collect()));
+            i += len;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let punct = PUNCTUATION
+                .iter()
+                .find(|p| rest.starts_with(**p))
+                .ok_or_else(|| format!("unexpected character '{}' at {}", c, i))?;
+            tokens.push(Token::Punct(punct));
+            i += punct.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Has,
+    Size,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Function> {
+        match name {
+            "has" => Some(Function::Has),
+            "size" => Some(Function::Size),
+            "startsWith" => Some(Function::StartsWith),
+            "endsWith" => Some(Function::EndsWith),
+            "contains" => Some(Function::Contains),
+            _ => None,
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Has | Function::Size => 1,
+            Function::StartsWith | Function::EndsWith | Function::Contains => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Literal(Value),
+    List(Vec<Node>),
+    Path(Vec<Segment>),
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Compare(Op, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Punct(p)) if *p == punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), String> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}'", punct))
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.eat("||") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while self.eat("&&") {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<Node, String> {
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Punct("==")) => Op::Eq,
+            Some(Token::Punct("!=")) => Op::Ne,
+            Some(Token::Punct("<")) => Op::Lt,
+            Some(Token::Punct("<=")) => Op::Le,
+            Some(Token::Punct(">")) => Op::Gt,
+            Some(Token::Punct(">=")) => Op::Ge,
+            Some(Token::Ident(i)) if i == "in" => Op::In,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Node::Compare(op, Box::new(left), Box::new(self.operand()?)))
+    }
+
+    fn operand(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(Node::Literal(Value::String(s))),
+            Some(Token::Num(n)) => Ok(Node::Literal(number(n))),
+            Some(Token::Punct("-")) => match self.next() {
+                Some(Token::Num(n)) => Ok(Node::Literal(number(-n))),
+                _ => Err("expected a number after '-'".to_string()),
+            },
+            Some(Token::Punct("(")) => {
+                let node = self.or()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Some(Token::Punct("[")) => {
+                let mut items = vec![];
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.or()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Node::List(items))
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "true" => Ok(Node::Literal(Value::Bool(true))),
+                "false" => Ok(Node::Literal(Value::Bool(false))),
+                "null" => Ok(Node::Literal(Value::Null)),
+                _ if self.peek() == Some(&Token::Punct("(")) => {
+                    let function = Function::parse(&ident)
+                        .ok_or_else(|| format!("unknown function {}", ident))?;
+                    self.pos += 1;
+                    let mut args = vec![self.or()?];
+                    while self.eat(",") {
+                        args.push(self.or()?);
+                    }
+                    self.expect(")")?;
+                    if args.len() != function.arity() {
+                        return Err(format!("{} takes {} arguments", ident, function.arity()));
+                    }
+                    Ok(Node::Call(function, args))
+                }
+                _ => self.path(ident),
+            },
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+
+    fn path(&mut self, first: String) -> Result<Node, String> {
+        let mut segments = vec![Segment::Field(first)];
+        loop {
+            if self.eat(".") {
+                match self.next() {
+                    Some(Token::Ident(field)) => segments.push(Segment::Field(field)),
+                    _ => return Err("expected a field name after '.'".to_string()),
+                }
+            } else if self.eat("[") {
+                match self.next() {
+                    Some(Token::Str(field)) => segments.push(Segment::Field(field)),
+                    Some(Token::Num(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                        segments.push(Segment::Index(n as usize))
+                    }
+                    _ => return Err("expected a field name or index in '[]'".to_string()),
+                }
+                self.expect("]")?;
+            } else {
+                return Ok(Node::Path(segments));
+            }
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+/// Values are equal if they are the same JSON, except numbers which are
+/// compared by value, so `1 == 1.0`.
+fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64() == r.as_f64(),
+        _ => left == right,
+    }
+}
+
+fn as_bool(value: Value) -> Result<bool, String> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(format!("expected a boolean, got {}", other)),
+    }
+}
+
+fn evaluate(node: &Node, resource: &Value) -> Result<Value, String> {
+    Ok(match node {
+        Node::Literal(value) => value.clone(),
+        Node::List(items) => Value::Array(
+            items
+                .iter()
+                .map(|i| evaluate(i, resource))
+                .collect::<Result<_, _>>()?,
+        ),
+        Node::Path(segments) => segments
+            .iter()
+            .try_fold(resource, |value, segment| match segment {
+                Segment::Field(field) => value.get(field),
+                Segment::Index(index) => value.get(index),
+            })
+            .cloned()
+            .unwrap_or(Value::Null),
+        Node::Not(inner) => Value::Bool(!as_bool(evaluate(inner, resource)?)?),
+        Node::And(left, right) => {
+            Value::Bool(as_bool(evaluate(left, resource)?)? && as_bool(evaluate(right, resource)?)?)
+        }
+        Node::Or(left, right) => {
+            Value::Bool(as_bool(evaluate(left, resource)?)? || as_bool(evaluate(right, resource)?)?)
+        }
+        Node::Compare(op, left, right) => {
+            let left = evaluate(left, resource)?;
+            let right = evaluate(right, resource)?;
+            let ordering = match (&left, &right) {
+                (Value::Number(l), Value::Number(r)) => l.as_f64().partial_cmp(&r.as_f64()),
+                (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+                _ => None,
+            };
+            Value::Bool(match op {
+                Op::Eq => equals(&left, &right),
+                Op::Ne => !equals(&left, &right),
+                Op::Lt => ordering.is_some_and(|o| o.is_lt()),
+                Op::Le => ordering.is_some_and(|o| o.is_le()),
+                Op::Gt => ordering.is_some_and(|o| o.is_gt()),
+                Op::Ge => ordering.is_some_and(|o| o.is_ge()),
+                Op::In => match right {
+                    Value::Array(items) => items.iter().any(|i| equals(&left, i)),
+                    Value::Object(map) => left.as_str().is_some_and(|k| map.contains_key(k)),
+                    other => return Err(format!("'in' expects a list or map, got {}", other)),
+                },
+            })
+        }
+        Node::Call(function, args) => {
+            let args: Vec<Value> = args
+                .iter()
+                .map(|a| evaluate(a, resource))
+                .collect::<Result<_, _>>()?;
+            match (function, args.as_slice()) {
+                (Function::Has, [value]) => Value::Bool(!value.is_null()),
+                (Function::Size, [Value::String(s)]) => Value::from(s.chars().count()),
+                (Function::Size, [Value::Array(a)]) => Value::from(a.len()),
+                (Function::Size, [Value::Object(o)]) => Value::from(o.len()),
+                (Function::Size, [Value::Null]) => Value::from(0),
+                (Function::StartsWith, [Value::String(s), Value::String(p)]) => {
+                    Value::Bool(s.starts_with(p.as_str()))
+                }
+                (Function::EndsWith, [Value::String(s), Value::String(p)]) => {
+                    Value::Bool(s.ends_with(p.as_str()))
+                }
+                (Function::Contains, [Value::String(s), Value::String(p)]) => {
+                    Value::Bool(s.contains(p.as_str()))
+                }
+                (Function::Contains, [Value::Array(items), item]) => {
+                    Value::Bool(items.iter().any(|i| equals(i, item)))
+                }
+                (
+                    Function::StartsWith | Function::EndsWith | Function::Contains,
+                    [Value::Null, _],
+                ) => Value::Bool(false),
+                (function, args) => {
+                    return Err(format!(
+                        "{:?} can not be applied to {}",
+                        function,
+                        args.iter()
+                            .map(|a| a.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                }
+            }
+        }
+    })
+}
+
+/// A parsed expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Expression, InvariantError> {
+        let invalid = |e: String| InvariantError {
+            msg: format!("Invalid expression '{}': {}", source, e),
+        };
+        let mut parser = Parser {
+            tokens: tokenize(source).map_err(invalid)?,
+            pos: 0,
+        };
+        let root = parser.or().map_err(invalid)?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Expression {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    pub fn evaluate(&self, resource: &Value) -> Result<Value, String> {
+        evaluate(&self.root, resource)
+    }
+
+    /// Evaluates a condition - it is an error if it is not a boolean.
+    pub fn holds(&self, resource: &Value) -> Result<bool, String> {
+        as_bool(self.evaluate(resource)?)
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate_expression() {
+        let subnet = json!({
+            "subnet_id": "subnet-1",
+            "available_ip_address_count": 12,
+            "map_public_ip_on_launch": false,
+            "tags": {"Name": "private-a", "kubernetes.io/role/internal-elb": "1"},
+            "ipv6_cidr_blocks": [],
+        });
+        let holds = |source: &str| Expression::parse(source).unwrap().holds(&subnet);
+        assert_eq!(
+            holds("has(tags['kubernetes.io/role/internal-elb'])"),
+            Ok(true)
+        );
+        assert_eq!(
+            holds("has(tags.owner) || startsWith(tags.Name, 'private')"),
+            Ok(true)
+        );
+        assert_eq!(holds("available_ip_address_count >= 16"), Ok(false));
+        assert_eq!(
+            holds("!map_public_ip_on_launch && size(ipv6_cidr_blocks) == 0"),
+            Ok(true)
+        );
+        assert_eq!(holds("subnet_id in ['subnet-2', 'subnet-3']"), Ok(false));
+        assert_eq!(holds("'Name' in tags && tags.owner == null"), Ok(true));
+        assert!(holds("subnet_id").is_err());
+        assert!(Expression::parse("tags.Name ==").is_err());
+        assert!(Expression::parse("lower(subnet_id)").is_err());
+    }
+}
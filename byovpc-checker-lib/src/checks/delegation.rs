@@ -0,0 +1,241 @@
+//! This checker reports cluster subnets that are shared with other AWS
+//! services, e.g. Transit Gateway attachments, RDS databases or EKS clusters.
+//! These services allocate addresses from the same subnets as the cluster, so
+//! scaling either of them can exhaust the subnet.
+//! It can check the following conditions right now:
+//!
+//! - No ENIs of other services are in the cluster subnets. The services are
+//!   identified by the type, requester and description of their ENIs.
+//! - No cluster subnet is tagged for an EKS cluster.
+
+use std::collections::BTreeMap;
+
+use aws_sdk_ec2::types::{NetworkInterface, NetworkInterfaceType, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::types::{
+    FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+};
+
+/// Tags EKS and eksctl put on the subnets of a cluster.
+const EKS_SUBNET_TAGS: [&str; 3] = [
+    "eks:cluster-name",
+    "aws:eks:cluster-name",
+    "alpha.eksctl.io/cluster-name",
+];
+
+/// Returns the service that created the ENI, if it is a service that claims
+/// addresses of the subnet for itself.
+fn delegated_service(eni: &NetworkInterface) -> Option<&'static str> {
+    let description = eni.description().unwrap_or_default();
+    match eni.interface_type() {
+        Some(NetworkInterfaceType::TransitGateway) => Some("Transit Gateway"),
+        Some(NetworkInterfaceType::Lambda) => Some("Lambda"),
+        _ if eni.requester_id() == Some("amazon-rds")
+            || description.starts_with("RDSNetworkInterface") =>
+        {
+            Some("RDS")
+        }
+        _ if description.starts_with("Amazon EKS") => Some("EKS"),
+        _ if description.starts_with("ElastiCache") => Some("ElastiCache"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Builder)]
+pub struct DelegatedSubnetChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    network_interfaces: Vec<NetworkInterface>,
+}
+
+impl<'a> DelegatedSubnetChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    pub fn verify_no_service_enis(&self) -> Vec<VerificationResult> {
+        info!("Checking subnets are not shared with other services");
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let Some(subnet_id) = subnet.subnet_id() else {
+                continue;
+            };
+            let mut enis_per_service: BTreeMap<&str, usize> = BTreeMap::new();
+            for eni in self
+                .network_interfaces
+                .iter()
+                .filter(|eni| eni.subnet_id() == Some(subnet_id))
+            {
+                if let Some(service) = delegated_service(eni) {
+                    *enis_per_service.entry(service).or_default() += 1;
+                }
+            }
+            for (service, count) in enis_per_service {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is shared with {} ({} ENIs) - both compete for its {} free addresses",
+                        subnet_id,
+                        service,
+                        count,
+                        subnet.available_ip_address_count().unwrap_or_default()
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetSharedWithService),
+                    check_id: Some("DLG-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_no_eks_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking subnets are not tagged for EKS");
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let Some(tag) = subnet
+                .tags()
+                .iter()
+                .find(|t| t.key().is_some_and(|k| EKS_SUBNET_TAGS.contains(&k)))
+            else {
+                continue;
+            };
+            results.push(VerificationResult {
+                message: format!(
+                    "Subnet {} is tagged for EKS cluster {} - EKS (custom networking) allocates pod addresses from it",
+                    subnet.subnet_id().unwrap_or_default(),
+                    tag.value().unwrap_or_default()
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::SubnetSharedWithService),
+                check_id: Some("DLG-002"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some(subnet.subnet_id().unwrap_or_default().to_string()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for DelegatedSubnetChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_no_service_enis());
+        results.extend(self.verify_no_eks_tags());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "Cluster subnets are not shared with other services".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::Tag;
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_delegated_subnets() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .subnets(vec!["subnet-1".to_string(), "subnet-2".to_string()])
+            .build()
+            .unwrap();
+        let eni = |id: &str, subnet_id: &str| {
+            NetworkInterface::builder()
+                .network_interface_id(id)
+                .subnet_id(subnet_id)
+        };
+        let dsc = DelegatedSubnetChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                Subnet::builder()
+                    .subnet_id("subnet-1")
+                    .available_ip_address_count(10)
+                    .build(),
+                Subnet::builder()
+                    .subnet_id("subnet-2")
+                    .tags(
+                        Tag::builder()
+                            .key("eks:cluster-name")
+                            .value("eks-1")
+                            .build(),
+                    )
+                    .build(),
+                Subnet::builder().subnet_id("subnet-3").build(),
+            ])
+            .network_interfaces(vec![
+                eni("eni-1", "subnet-1")
+                    .interface_type(NetworkInterfaceType::TransitGateway)
+                    .build(),
+                eni("eni-2", "subnet-1")
+                    .requester_id("amazon-rds")
+                    .description("RDSNetworkInterface")
+                    .build(),
+                eni("eni-3", "subnet-1")
+                    .description("RDSNetworkInterface")
+                    .build(),
+                eni("eni-4", "subnet-1")
+                    .interface_type(NetworkInterfaceType::NetworkLoadBalancer)
+                    .build(),
+                eni("eni-5", "subnet-3")
+                    .interface_type(NetworkInterfaceType::TransitGateway)
+                    .build(),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            dsc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Subnet subnet-1 is shared with RDS (2 ENIs) - both compete for its 10 free addresses".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetSharedWithService),
+                    check_id: Some("DLG-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-1 is shared with Transit Gateway (1 ENIs) - both compete for its 10 free addresses".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetSharedWithService),
+                    check_id: Some("DLG-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-2 is tagged for EKS cluster eks-1 - EKS (custom networking) allocates pod addresses from it".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetSharedWithService),
+                    check_id: Some("DLG-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-2".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
@@ -0,0 +1,346 @@
+//! This checker verifies the DHCP options sets of the cluster VPCs. Custom
+//! option sets routinely break node registration, as nodes resolve each other
+//! and the API through them.
+//! It can check the following conditions right now:
+//!
+//! - The DHCP options provide the AmazonProvidedDNS or custom DNS servers.
+//! - The domain name of the DHCP options is the one AWS assigns in the region,
+//!   so the hostnames of the nodes match their private DNS names.
+
+use std::net::Ipv4Addr;
+
+use aws_sdk_ec2::types::{DhcpOptions, Subnet, Vpc};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    checks::{egress::parse_cidr, instances::expected_dns_domain},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+const AMAZON_PROVIDED_DNS: &str = "AmazonProvidedDNS";
+
+#[derive(Debug, Builder)]
+pub struct DhcpOptionsChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    vpcs: Vec<Vpc>,
+    #[builder(default = "vec![]")]
+    dhcp_options: Vec<DhcpOptions>,
+}
+
+/// The values of a DHCP option, e.g. the servers of `domain-name-servers` -
+/// `None` if the option is not set.
+fn option_values<'o>(options: &'o DhcpOptions, key: &str) -> Option<Vec<&'o str>> {
+    options
+        .dhcp_configurations()
+        .iter()
+        .find(|c| c.key() == Some(key))
+        .map(|c| c.values().iter().filter_map(|v| v.value()).collect())
+}
+
+/// The options of the set as evidence of a finding.
+fn options_evidence(options: &DhcpOptions) -> serde_json::Value {
+    options
+        .dhcp_configurations()
+        .iter()
+        .filter_map(|c| {
+            let values: Vec<&str> = c.values().iter().filter_map(|v| v.value()).collect();
+            Some((c.key()?.to_string(), json!(values)))
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// The address of the AmazonProvidedDNS in the VPC - the base of its primary
+/// CIDR block plus two.
+fn amazon_provided_dns_address(vpc: &Vpc) -> Option<Ipv4Addr> {
+    let (ip, prefix) = parse_cidr(vpc.cidr_block()?)?;
+    let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    Some(Ipv4Addr::from((ip & mask) + 2))
+}
+
+impl<'a> DhcpOptionsChecks<'a> {
+    /// The VPCs of the cluster subnets with their DHCP options - VPCs without
+    /// an options set use the AmazonProvidedDNS and are left out.
+    fn cluster_dhcp_options(&self) -> Vec<(&Vpc, &DhcpOptions)> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .filter_map(|s| s.vpc_id())
+            .unique()
+            .filter_map(|vpc_id| self.vpcs.iter().find(|v| v.vpc_id() == Some(vpc_id)))
+            .filter_map(|vpc| {
+                let options = self.dhcp_options.iter().find(|d| {
+                    d.dhcp_options_id().is_some() && d.dhcp_options_id() == vpc.dhcp_options_id()
+                })?;
+                Some((vpc, options))
+            })
+            .collect()
+    }
+
+    /// The region of the cluster, e.g. us-east-1 for subnets in us-east-1a.
+    fn region(&self) -> Option<String> {
+        self.cluster_info.region.clone().or_else(|| {
+            self.subnets
+                .iter()
+                .find_map(|s| s.availability_zone())
+                .map(|az| {
+                    az.trim_end_matches(|c: char| c.is_ascii_lowercase())
+                        .to_string()
+                })
+        })
+    }
+
+    pub fn verify_dns_servers(&self) -> Vec<VerificationResult> {
+        info!("Checking DNS servers of the DHCP options");
+        let mut results = vec![];
+        for (vpc, options) in self.cluster_dhcp_options() {
+            let vpc_id = vpc.vpc_id().unwrap_or_default();
+            let options_id = options.dhcp_options_id().unwrap_or_default();
+            let servers = option_values(options, "domain-name-servers").unwrap_or_default();
+            if servers.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "DHCP options {} of VPC {} set no domain-name-servers - nodes can not resolve any name",
+                        options_id, vpc_id
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::DhcpNoDnsServers),
+                    check_id: Some("DHCP-001"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some(options_id.to_string()),
+                    evidence: Some(options_evidence(options)),
+                });
+                continue;
+            }
+            if servers.contains(&AMAZON_PROVIDED_DNS) {
+                continue;
+            }
+            let invalid: Vec<&str> = servers
+                .iter()
+                .filter(|s| s.parse::<Ipv4Addr>().is_err())
+                .copied()
+                .collect();
+            if !invalid.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "DHCP options {} of VPC {} use domain-name-servers that are not IP addresses: {}",
+                        options_id,
+                        vpc_id,
+                        invalid.join(", ")
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::DhcpNoDnsServers),
+                    check_id: Some("DHCP-001"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some(options_id.to_string()),
+                    evidence: Some(options_evidence(options)),
+                });
+                continue;
+            }
+            let forward_to = amazon_provided_dns_address(vpc)
+                .map(|a| format!(" ({})", a))
+                .unwrap_or_default();
+            results.push(VerificationResult {
+                message: format!(
+                    "DHCP options {} of VPC {} use the custom DNS servers {} - they must forward the cluster domains to the AmazonProvidedDNS{} for nodes to resolve the private hosted zones",
+                    options_id,
+                    vpc_id,
+                    servers.join(", "),
+                    forward_to
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::CustomDhcpDnsServers),
+                check_id: Some("DHCP-001"),
+                resource_type: Some(ResourceType::DhcpOptions),
+                resource_id: Some(options_id.to_string()),
+                evidence: Some(options_evidence(options)),
+            });
+        }
+        results
+    }
+
+    pub fn verify_domain_name(&self) -> Vec<VerificationResult> {
+        info!("Checking domain name of the DHCP options");
+        let expected = self.region().as_deref().map(expected_dns_domain);
+        let mut results = vec![];
+        for (vpc, options) in self.cluster_dhcp_options() {
+            let vpc_id = vpc.vpc_id().unwrap_or_default();
+            let options_id = options.dhcp_options_id().unwrap_or_default();
+            let Some(domains) = option_values(options, "domain-name") else {
+                continue;
+            };
+            let domains: Vec<&str> = domains.iter().flat_map(|d| d.split_whitespace()).collect();
+            if domains.len() > 1 || domains.iter().any(|d| d.chars().any(char::is_uppercase)) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "DHCP options {} of VPC {} use the domain name '{}' - multiple domains or uppercase letters break node hostname resolution",
+                        options_id,
+                        vpc_id,
+                        domains.join(" ")
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InvalidDhcpDomainName),
+                    check_id: Some("DHCP-002"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some(options_id.to_string()),
+                    evidence: Some(options_evidence(options)),
+                });
+                continue;
+            }
+            let (Some(domain), Some(expected)) = (domains.first(), expected.as_deref()) else {
+                continue;
+            };
+            if *domain != expected {
+                results.push(VerificationResult {
+                    message: format!(
+                        "DHCP options {} of VPC {} use the custom domain name {} instead of {} - node hostnames no longer match their private DNS names",
+                        options_id, vpc_id, domain, expected
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::CustomDhcpDomainName),
+                    check_id: Some("DHCP-002"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some(options_id.to_string()),
+                    evidence: Some(options_evidence(options)),
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for DhcpOptionsChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_dns_servers());
+        results.extend(self.verify_domain_name());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "DHCP options of the cluster VPCs use the AmazonProvidedDNS or custom DNS servers and the domain name of the region".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{AttributeValue, DhcpConfiguration};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    fn dhcp_options(id: &str, configurations: &[(&str, &[&str])]) -> DhcpOptions {
+        DhcpOptions::builder()
+            .dhcp_options_id(id)
+            .set_dhcp_configurations(Some(
+                configurations
+                    .iter()
+                    .map(|(key, values)| {
+                        DhcpConfiguration::builder()
+                            .key(*key)
+                            .set_values(Some(
+                                values
+                                    .iter()
+                                    .map(|v| AttributeValue::builder().value(*v).build())
+                                    .collect(),
+                            ))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .build()
+    }
+
+    #[test]
+    fn test_verify_dhcp_options() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .region(Some("eu-west-1".to_string()))
+            .build()
+            .unwrap();
+        let subnet =
+            |id: &str, vpc_id: &str| Subnet::builder().subnet_id(id).vpc_id(vpc_id).build();
+        let vpc = |id: &str, dhcp_options_id: &str| {
+            Vpc::builder()
+                .vpc_id(id)
+                .cidr_block("10.0.0.0/16")
+                .dhcp_options_id(dhcp_options_id)
+                .build()
+        };
+        let dc = DhcpOptionsChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-1", "vpc-1"),
+                subnet("subnet-2", "vpc-2"),
+                subnet("subnet-3", "vpc-3"),
+            ])
+            .vpcs(vec![
+                vpc("vpc-1", "dopt-1"),
+                vpc("vpc-2", "dopt-2"),
+                vpc("vpc-3", "default"),
+            ])
+            .dhcp_options(vec![
+                dhcp_options(
+                    "dopt-1",
+                    &[
+                        ("domain-name-servers", &["AmazonProvidedDNS"]),
+                        ("domain-name", &["eu-west-1.compute.internal"]),
+                    ],
+                ),
+                dhcp_options(
+                    "dopt-2",
+                    &[
+                        ("domain-name-servers", &["10.0.0.10"]),
+                        ("domain-name", &["corp.example.com"]),
+                    ],
+                ),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            dc.verify(),
+            vec![
+                VerificationResult {
+                    message: "DHCP options dopt-2 of VPC vpc-2 use the custom DNS servers 10.0.0.10 - they must forward the cluster domains to the AmazonProvidedDNS (10.0.0.2) for nodes to resolve the private hosted zones".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::CustomDhcpDnsServers),
+                    check_id: Some("DHCP-001"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some("dopt-2".to_string()),
+                    evidence: Some(json!({
+                        "domain-name-servers": ["10.0.0.10"],
+                        "domain-name": ["corp.example.com"],
+                    })),
+                },
+                VerificationResult {
+                    message: "DHCP options dopt-2 of VPC vpc-2 use the custom domain name corp.example.com instead of eu-west-1.compute.internal - node hostnames no longer match their private DNS names".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::CustomDhcpDomainName),
+                    check_id: Some("DHCP-002"),
+                    resource_type: Some(ResourceType::DhcpOptions),
+                    resource_id: Some("dopt-2".to_string()),
+                    evidence: Some(json!({
+                        "domain-name-servers": ["10.0.0.10"],
+                        "domain-name": ["corp.example.com"],
+                    })),
+                },
+            ]
+        );
+    }
+}
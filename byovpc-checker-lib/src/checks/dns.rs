@@ -0,0 +1,1278 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use aws_sdk_route53::types::{ResourceRecordSet, RrType};
+use derive_builder::Builder;
+use itertools::Itertools;
+use serde_json::json;
+
+use crate::{
+    gatherer::aws::shared_types::{
+        AWSLoadBalancer, HostedZoneWithRecords, DEFAULT_ROUTER_TAG_HYPERSHIFT,
+        DEFAULT_ROUTER_VALUE_HYPERSHIFT,
+    },
+    types::{VerificationResult, Verifier},
+};
+
+#[derive(Builder)]
+pub struct HostedZoneChecks {
+    pub hosted_zones: Vec<HostedZoneWithRecords>,
+    pub load_balancers: Vec<AWSLoadBalancer>,
+    /// The region of the cluster - records pointing to load balancers in
+    /// other regions are reported separately.
+    #[builder(default = "None")]
+    pub region: Option<String>,
+    /// The domains of the ingresses configured in OCM, including custom
+    /// domains.
+    #[builder(default = "vec![]")]
+    pub ingress_domains: Vec<String>,
+    /// The VPCs of the cluster subnets - the private zone must be associated
+    /// with them.
+    #[builder(default = "vec![]")]
+    pub vpc_ids: Vec<String>,
+    /// The infra name of the cluster - the API load balancers are named after
+    /// it.
+    #[builder(default = "\"\".to_string()")]
+    pub infra_name: String,
+}
+
+/// What a cluster load balancer serves - records of the cluster domain must
+/// point to the load balancer serving them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LoadBalancerRole {
+    InternalApi,
+    ExternalApi,
+    Router,
+}
+
+impl LoadBalancerRole {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LoadBalancerRole::InternalApi => "internal API",
+            LoadBalancerRole::ExternalApi => "external API",
+            LoadBalancerRole::Router => "default router",
+        }
+    }
+
+    /// The role of the load balancer the record has to point to: `api-int`
+    /// and `api` of the private zone to the internal API load balancer, `api`
+    /// of the public zone to the external one and the wildcard of `apps` to
+    /// the default router.
+    fn expected_for(record_name: &str, private_zone: bool) -> Option<LoadBalancerRole> {
+        let name = record_name.replace("\\052", "*");
+        if name.starts_with("api-int.") || (name.starts_with("api.") && private_zone) {
+            Some(LoadBalancerRole::InternalApi)
+        } else if name.starts_with("api.") {
+            Some(LoadBalancerRole::ExternalApi)
+        } else if name.starts_with("*.apps.") {
+            Some(LoadBalancerRole::Router)
+        } else {
+            None
+        }
+    }
+}
+
+/// The range of TTLs (in seconds) records of the cluster zones should have -
+/// shorter TTLs flood the resolvers, longer ones delay every DNS change by
+/// hours.
+const MIN_TTL: i64 = 30;
+const MAX_TTL: i64 = 3600;
+
+/// Normalizes name servers for comparison: lowercase and without the trailing
+/// dot.
+fn normalized_name_servers(name_servers: &[String]) -> BTreeSet<String> {
+    name_servers
+        .iter()
+        .map(|ns| ns.trim_end_matches('.').to_lowercase())
+        .collect()
+}
+
+/// Extracts the region from the DNS name of a load balancer:
+/// `<name>-<id>.<region>.elb.amazonaws.com` for classic and
+/// `<name>-<id>.elb.<region>.amazonaws.com` for network load balancers. Alias
+/// targets may be prefixed with `dualstack.`.
+pub fn load_balancer_region(dns_name: &str) -> Option<&str> {
+    let labels: Vec<&str> = dns_name.trim_end_matches('.').split('.').collect();
+    let elb = labels.iter().position(|l| *l == "elb")?;
+    match labels.get(elb + 1) {
+        Some(&"amazonaws") => labels.get(elb.checked_sub(1)?).copied(),
+        next => next.copied(),
+    }
+}
+
+/// The target of a record: the alias target or the sorted values.
+fn record_target(record: &ResourceRecordSet) -> String {
+    match record.alias_target {
+        Some(ref at) => at.dns_name.trim_end_matches('.').to_lowercase(),
+        None => record
+            .resource_records()
+            .iter()
+            .map(|rr| rr.value.trim_end_matches('.').to_lowercase())
+            .sorted()
+            .join(", "),
+    }
+}
+
+/// NS and SOA records are managed by Route53 and the parent zone.
+fn is_zone_record(record: &ResourceRecordSet) -> bool {
+    matches!(record.r#type, RrType::Ns | RrType::Soa)
+}
+
+impl HostedZoneChecks {
+    fn get_resource_record_targets(&self) -> Vec<(String, String)> {
+        let resource_record_sets: Vec<ResourceRecordSet> = self
+            .hosted_zones
+            .iter()
+            .map(|h| h.resource_records.clone())
+            .flatten()
+            .collect();
+        let resource_values: Vec<(String, String)> = resource_record_sets
+            .iter()
+            .map(|r| {
+                r.alias_target
+                    .clone()
+                    .map(|at| (r.name.clone(), at.dns_name.clone()))
+            })
+            .flatten()
+            .collect();
+        resource_values
+    }
+
+    fn get_load_balancer_names(&self) -> Vec<String> {
+        self.load_balancers
+            .iter()
+            .map(|l| match l {
+                AWSLoadBalancer::ClassicLoadBalancer((c, _)) => {
+                    c.dns_name.clone().unwrap_or("".to_string())
+                }
+                AWSLoadBalancer::ModernLoadBalancer((m, _)) => {
+                    m.dns_name.clone().unwrap_or("".to_string())
+                }
+            })
+            .collect()
+    }
+
+    /// The role of the load balancer - `None` for load balancers that serve
+    /// neither the API nor the default router.
+    fn load_balancer_role(&self, lb: &AWSLoadBalancer) -> Option<LoadBalancerRole> {
+        if lb.name() == format!("{}-int", self.infra_name) {
+            Some(LoadBalancerRole::InternalApi)
+        } else if lb.name() == format!("{}-ext", self.infra_name) {
+            Some(LoadBalancerRole::ExternalApi)
+        } else if lb.tags().iter().any(|t| {
+            t.key.as_deref() == Some(DEFAULT_ROUTER_TAG_HYPERSHIFT)
+                && t.value.as_deref() == Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT)
+        }) {
+            Some(LoadBalancerRole::Router)
+        } else {
+            None
+        }
+    }
+
+    /// Verifies the API and apps records point to the load balancer serving
+    /// them, e.g. that `api` does not point to the router. Records pointing to
+    /// load balancers outside the cluster are reported by DNS-003.
+    pub fn verify_record_load_balancer_roles(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        for hz in self.hosted_zones.iter() {
+            let private = hz
+                .hosted_zone
+                .config
+                .as_ref()
+                .is_some_and(|c| c.private_zone);
+            for record in hz.resource_records.iter() {
+                let Some(expected) = LoadBalancerRole::expected_for(&record.name, private) else {
+                    continue;
+                };
+                let Some(ref alias_target) = record.alias_target else {
+                    continue;
+                };
+                let target = alias_target.dns_name.to_lowercase();
+                let Some((lb, role)) = self.load_balancers.iter().find_map(|lb| {
+                    let dns_name = match lb {
+                        AWSLoadBalancer::ClassicLoadBalancer((c, _)) => c.dns_name(),
+                        AWSLoadBalancer::ModernLoadBalancer((m, _)) => m.dns_name(),
+                    }?;
+                    if dns_name.is_empty() || !target.contains(&dns_name.to_lowercase()) {
+                        return None;
+                    }
+                    Some((lb, self.load_balancer_role(lb)?))
+                }) else {
+                    continue;
+                };
+                if role == expected {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Record {} points to the {} LoadBalancer {}",
+                            record.name,
+                            role.as_str(),
+                            lb.name()
+                        ),
+                        severity: crate::types::Severity::Ok,
+                        code: None,
+                        check_id: Some("DNS-008"),
+                        resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                        resource_id: Some(record.name.to_string()),
+                        evidence: None,
+                    });
+                } else {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Record {} points to the {} LoadBalancer {} instead of the {} LoadBalancer",
+                            record.name,
+                            role.as_str(),
+                            lb.name(),
+                            expected.as_str()
+                        ),
+                        severity: crate::types::Severity::Critical,
+                        code: Some(crate::types::FindingCode::RecordWithWrongLb),
+                        check_id: Some("DNS-008"),
+                        resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                        resource_id: Some(record.name.to_string()),
+                        evidence: Some(json!({
+                            "record": record.name,
+                            "target": alias_target.dns_name,
+                        })),
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Verifies the API and apps records are aliases of the load balancers:
+    /// a CNAME is not allowed at the zone apex, and fixed addresses break once
+    /// the addresses of the load balancer change.
+    pub fn verify_record_types(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        for hz in self.hosted_zones.iter() {
+            let private = hz
+                .hosted_zone
+                .config
+                .as_ref()
+                .is_some_and(|c| c.private_zone);
+            for record in hz.resource_records.iter() {
+                let apex = record.name == hz.hosted_zone.name;
+                if is_zone_record(record)
+                    || record.alias_target.is_some()
+                    || (!apex && LoadBalancerRole::expected_for(&record.name, private).is_none())
+                {
+                    continue;
+                }
+                let result = match record.r#type {
+                    RrType::Cname if apex => VerificationResult {
+                        message: format!(
+                            "Record {} is a CNAME at the apex of zone {} - use an ALIAS record",
+                            record.name, hz.hosted_zone.id
+                        ),
+                        severity: crate::types::Severity::Critical,
+                        code: Some(crate::types::FindingCode::CnameAtZoneApex),
+                        check_id: Some("DNS-009"),
+                        resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                        resource_id: Some(record.name.to_string()),
+                        evidence: Some(json!({
+                            "record": record.name,
+                            "target": record_target(record),
+                        })),
+                    },
+                    RrType::Cname | RrType::A | RrType::Aaaa if !apex => VerificationResult {
+                        message: format!(
+                            "Record {} has type {} instead of an ALIAS of the LoadBalancer: {}",
+                            record.name,
+                            record.r#type.as_str(),
+                            record_target(record)
+                        ),
+                        severity: crate::types::Severity::Warning,
+                        code: Some(crate::types::FindingCode::RecordNotAlias),
+                        check_id: Some("DNS-009"),
+                        resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                        resource_id: Some(record.name.to_string()),
+                        evidence: Some(json!({
+                            "record": record.name,
+                            "target": record_target(record),
+                        })),
+                    },
+                    _ => continue,
+                };
+                results.push(result);
+            }
+        }
+        results
+    }
+
+    /// Verifies the TTLs of the records are in a sane range - alias records
+    /// use the TTL of their target.
+    pub fn verify_record_ttls(&self) -> Vec<VerificationResult> {
+        self.hosted_zones
+            .iter()
+            .flat_map(|hz| hz.resource_records.iter())
+            .filter(|r| !is_zone_record(r) && r.alias_target.is_none())
+            .filter_map(|r| Some((r, r.ttl?)))
+            .filter(|(_, ttl)| !(MIN_TTL..=MAX_TTL).contains(ttl))
+            .map(|(r, ttl)| VerificationResult {
+                message: format!(
+                    "Record {} ({}) has a TTL of {}s - expected between {}s and {}s",
+                    r.name,
+                    r.r#type.as_str(),
+                    ttl,
+                    MIN_TTL,
+                    MAX_TTL
+                ),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::RecordTtlOutOfRange),
+                check_id: Some("DNS-010"),
+                resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                resource_id: Some(r.name.to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    /// Verifies no name has records with different targets in zones of the
+    /// same visibility, e.g. a CNAME in the parent zone shadowing the alias of
+    /// the cluster zone, or weighted records pointing to an old load balancer.
+    pub fn verify_conflicting_records(&self) -> Vec<VerificationResult> {
+        let mut targets_by_name: BTreeMap<(String, bool), BTreeSet<String>> = BTreeMap::new();
+        for hz in self.hosted_zones.iter() {
+            let private = hz
+                .hosted_zone
+                .config
+                .as_ref()
+                .is_some_and(|c| c.private_zone);
+            for record in hz.resource_records.iter() {
+                if is_zone_record(record) || record.r#type == RrType::Txt {
+                    continue;
+                }
+                targets_by_name
+                    .entry((record.name.to_lowercase(), private))
+                    .or_default()
+                    .insert(record_target(record));
+            }
+        }
+        targets_by_name
+            .into_iter()
+            .filter(|(_, targets)| targets.len() > 1)
+            .map(|((name, private), targets)| VerificationResult {
+                message: format!(
+                    "Found conflicting {} records for {}: {}",
+                    if private { "private" } else { "public" },
+                    name,
+                    targets.iter().join(" / ")
+                ),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::ConflictingRecords),
+                check_id: Some("DNS-011"),
+                resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                resource_id: Some(name.to_string()),
+                evidence: Some(json!({ "record": name, "targets": targets })),
+            })
+            .collect()
+    }
+
+    pub fn verify_number_of_hosted_zones(&self) -> VerificationResult {
+        match self.hosted_zones.len() {
+            0 | 1 => VerificationResult {
+                message: format!("Too few hosted zones found: {}", self.hosted_zones.len()),
+                severity: crate::types::Severity::Critical,
+                code: Some(crate::types::FindingCode::HostedZoneCount),
+                check_id: Some("DNS-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            2 => VerificationResult {
+                message: "Expected number of hosted zones found: 2".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("DNS-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            _ => VerificationResult {
+                message: format!("Too many hosted zones found: {}", self.hosted_zones.len()),
+                severity: crate::types::Severity::Critical,
+                code: Some(crate::types::FindingCode::HostedZoneCount),
+                check_id: Some("DNS-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        }
+    }
+
+    pub fn verify_load_balancers_are_used(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        let resource_targets = self.get_resource_record_targets();
+        let load_balancer_names: Vec<String> = self.get_load_balancer_names();
+        for lb in load_balancer_names {
+            if !resource_targets
+                .iter()
+                .any(|(_, target)| target.contains(&lb))
+            {
+                results.push(VerificationResult {
+                    message: format!("LoadBalancer '{}' is not being used in any hosted zone", lb),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::UnusedLoadBalancer),
+                    check_id: Some("DNS-002"),
+                    resource_type: Some(crate::types::ResourceType::LoadBalancer),
+                    resource_id: Some(lb.clone()),
+                    evidence: None,
+                })
+            } else {
+                if let Some((name, _)) = resource_targets
+                    .iter()
+                    .find(|(_, target)| target.contains(&lb))
+                {
+                    results.push(VerificationResult {
+                        message: format!("LoadBalancer {} is used in record {}", lb, name),
+                        severity: crate::types::Severity::Ok,
+                        code: None,
+                        check_id: Some("DNS-002"),
+                        resource_type: Some(crate::types::ResourceType::LoadBalancer),
+                        resource_id: Some(lb.clone()),
+                        evidence: None,
+                    })
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_only_known_load_balancers_are_used(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        let resource_targets = self.get_resource_record_targets();
+        let load_balancer_names: Vec<String> = self.get_load_balancer_names();
+        for (name, target) in resource_targets {
+            if load_balancer_names.iter().any(|lb| target.contains(lb)) {
+                continue;
+            }
+            let target_region = load_balancer_region(&target);
+            match (self.region.as_deref(), target_region) {
+                (Some(region), Some(target_region)) if region != target_region => {
+                    results.push(VerificationResult {
+                        message: format!("ResourceRecord '{}' is using a LoadBalancer in region {} while the cluster is in {}: {}", name, target_region, region, target),
+                        severity: crate::types::Severity::Info,
+                        code: Some(crate::types::FindingCode::RecordWithCrossRegionLb),
+                        check_id: Some("DNS-003"),
+                        resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                        resource_id: Some(name.to_string()),
+                        evidence: Some(json!({ "record": name, "target": target })),
+                    })
+                }
+                _ => results.push(VerificationResult {
+                    message: format!("ResourceRecord '{}' is using a LoadBalancer not associated with the cluster: {}", name, target),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::RecordWithForeignLb),
+                    check_id: Some("DNS-003"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some(name.to_string()),
+                    evidence: Some(json!({ "record": name, "target": target })),
+                }),
+            }
+        }
+        results
+    }
+
+    /// Verifies every ingress domain has a wildcard record that points to a
+    /// load balancer of the cluster. Domains outside of the gathered hosted
+    /// zones are managed by the customer and can not be verified.
+    pub fn verify_ingress_domain_records(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        let load_balancer_names = self.get_load_balancer_names();
+        for domain in self.ingress_domains.iter() {
+            let domain = domain.trim_end_matches('.');
+            let zones: Vec<&HostedZoneWithRecords> = self
+                .hosted_zones
+                .iter()
+                .filter(|hz| {
+                    let zone = hz.hosted_zone.name.trim_end_matches('.');
+                    domain == zone || domain.ends_with(&format!(".{}", zone))
+                })
+                .collect();
+            if zones.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Ingress domain {} is not in a hosted zone of the cluster - its records can not be verified",
+                        domain
+                    ),
+                    severity: crate::types::Severity::Info,
+                    code: None,
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some(format!("*.{}", domain)),
+                    evidence: None,
+                });
+                continue;
+            }
+            // Route53 returns the wildcard escaped as `\052`.
+            let record = zones
+                .iter()
+                .flat_map(|hz| hz.resource_records.iter())
+                .find(|r| {
+                    let name = r.name.trim_end_matches('.').replace("\\052", "*");
+                    name == format!("*.{}", domain)
+                });
+            let Some(record) = record else {
+                results.push(VerificationResult {
+                    message: format!("No record found for ingress domain *.{}", domain),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::IngressDomainRecordMissing),
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some(format!("*.{}", domain)),
+                    evidence: None,
+                });
+                continue;
+            };
+            let target = match record.alias_target {
+                Some(ref at) => at.dns_name.clone(),
+                None => record
+                    .resource_records()
+                    .first()
+                    .map(|rr| rr.value.clone())
+                    .unwrap_or_default(),
+            };
+            if load_balancer_names
+                .iter()
+                .any(|lb| !lb.is_empty() && target.contains(lb))
+            {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Ingress domain *.{} points to LoadBalancer {}",
+                        domain, target
+                    ),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some(format!("*.{}", domain)),
+                    evidence: None,
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Ingress domain *.{} points to {} which is not a LoadBalancer of the cluster",
+                        domain, target
+                    ),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::IngressDomainWithForeignLb),
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some(format!("*.{}", domain)),
+                    evidence: Some(json!({ "record": record.name, "target": target })),
+                });
+            }
+        }
+        results
+    }
+
+    /// Verifies no two zones of the same visibility share a name - only one of
+    /// them is used for resolution, records in the other are ignored.
+    pub fn verify_duplicate_hosted_zones(&self) -> Vec<VerificationResult> {
+        let mut zones_by_name: BTreeMap<(&str, bool), Vec<&str>> = BTreeMap::new();
+        for hz in self.hosted_zones.iter() {
+            let private = hz
+                .hosted_zone
+                .config
+                .as_ref()
+                .is_some_and(|c| c.private_zone);
+            zones_by_name
+                .entry((hz.hosted_zone.name.as_str(), private))
+                .or_default()
+                .push(hz.hosted_zone.id.as_str());
+        }
+        zones_by_name
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|((name, private), ids)| VerificationResult {
+                message: format!(
+                    "Found {} {} hosted zones named {}: {}",
+                    ids.len(),
+                    if private { "private" } else { "public" },
+                    name,
+                    ids.join(", ")
+                ),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::DuplicateHostedZone),
+                check_id: Some("DNS-004"),
+                resource_type: Some(crate::types::ResourceType::HostedZone),
+                resource_id: Some(name.to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    /// Verifies the private zones are associated with the VPC of the cluster -
+    /// otherwise the nodes can not resolve the API.
+    pub fn verify_private_zone_vpc_association(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        if self.vpc_ids.is_empty() {
+            return results;
+        }
+        for hz in self.hosted_zones.iter() {
+            let private = hz
+                .hosted_zone
+                .config
+                .as_ref()
+                .is_some_and(|c| c.private_zone);
+            // Older snapshots do not contain the associated VPCs.
+            if !private || hz.vpcs.is_empty() {
+                continue;
+            }
+            if self.vpc_ids.iter().any(|v| hz.vpcs.contains(v)) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Private hosted zone {} is associated with the cluster VPC",
+                        hz.hosted_zone.name
+                    ),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-006"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some(hz.hosted_zone.id.clone()),
+                    evidence: None,
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Private hosted zone {} ({}) is not associated with the cluster VPC {} but with: {}",
+                        hz.hosted_zone.name,
+                        hz.hosted_zone.id,
+                        self.vpc_ids.join(", "),
+                        hz.vpcs.join(", ")
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::PrivateZoneNotAssociated),
+                    check_id: Some("DNS-006"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some(hz.hosted_zone.id.clone()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    /// Verifies the public zones are delegated to their name servers by the
+    /// parent domain - otherwise the cluster is unreachable from the internet.
+    pub fn verify_public_zone_delegation(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        for hz in self.hosted_zones.iter() {
+            let Some(ref delegated) = hz.delegated_name_servers else {
+                continue;
+            };
+            let name = &hz.hosted_zone.name;
+            if delegated.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Public hosted zone {} is not delegated from its parent domain - add its name servers as NS record: {}",
+                        name,
+                        hz.name_servers.join(", ")
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::ZoneNotDelegated),
+                    check_id: Some("DNS-007"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some(hz.hosted_zone.id.clone()),
+                    evidence: None,
+                });
+            } else if !hz.name_servers.is_empty()
+                && normalized_name_servers(delegated) != normalized_name_servers(&hz.name_servers)
+            {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Public hosted zone {} is delegated to {} instead of its name servers {}",
+                        name,
+                        delegated.join(", "),
+                        hz.name_servers.join(", ")
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::ZoneDelegatedElsewhere),
+                    check_id: Some("DNS-007"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some(hz.hosted_zone.id.clone()),
+                    evidence: None,
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!("Public hosted zone {} is delegated correctly", name),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-007"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some(hz.hosted_zone.id.clone()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl Verifier for HostedZoneChecks {
+    fn verify(&self) -> Vec<crate::types::VerificationResult> {
+        let mut results = vec![];
+        results.push(self.verify_number_of_hosted_zones());
+        results.extend(self.verify_duplicate_hosted_zones());
+        results.extend(self.verify_load_balancers_are_used());
+        results.extend(self.verify_only_known_load_balancers_are_used());
+        results.extend(self.verify_record_load_balancer_roles());
+        results.extend(self.verify_record_types());
+        results.extend(self.verify_record_ttls());
+        results.extend(self.verify_conflicting_records());
+        results.extend(self.verify_ingress_domain_records());
+        results.extend(self.verify_private_zone_vpc_association());
+        results.extend(self.verify_public_zone_delegation());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_route53::types::{AliasTarget, HostedZone, HostedZoneConfig, RrType};
+
+    use super::*;
+    use crate::gatherer::aws::shared_types::Tag;
+
+    #[test]
+    fn test_verify_cross_region_load_balancers() {
+        let record = |name: &str, target: &str| {
+            ResourceRecordSet::builder()
+                .name(name)
+                .r#type(RrType::A)
+                .alias_target(
+                    AliasTarget::builder()
+                        .hosted_zone_id("Z1")
+                        .dns_name(target)
+                        .evaluate_target_health(false)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("Z1")
+                    .name("mycluster.example.com.")
+                    .caller_reference("Z1")
+                    .build()
+                    .unwrap(),
+                resource_records: vec![
+                    record(
+                        "api.mycluster.example.com.",
+                        "dualstack.a-1.eu-west-1.elb.amazonaws.com.",
+                    ),
+                    record(
+                        "*.apps.mycluster.example.com.",
+                        "b-2.elb.us-east-1.amazonaws.com.",
+                    ),
+                ],
+                vpcs: vec![],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }])
+            .load_balancers(vec![])
+            .region(Some("us-east-1".to_string()))
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_only_known_load_balancers_are_used(),
+            vec![
+                VerificationResult {
+                    message: "ResourceRecord 'api.mycluster.example.com.' is using a LoadBalancer in region eu-west-1 while the cluster is in us-east-1: dualstack.a-1.eu-west-1.elb.amazonaws.com.".to_string(),
+                    severity: crate::types::Severity::Info,
+                    code: Some(crate::types::FindingCode::RecordWithCrossRegionLb),
+                    check_id: Some("DNS-003"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("api.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "api.mycluster.example.com.",
+                        "target": "dualstack.a-1.eu-west-1.elb.amazonaws.com.",
+                    })),
+                },
+                VerificationResult {
+                    message: "ResourceRecord '*.apps.mycluster.example.com.' is using a LoadBalancer not associated with the cluster: b-2.elb.us-east-1.amazonaws.com.".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::RecordWithForeignLb),
+                    check_id: Some("DNS-003"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("*.apps.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "*.apps.mycluster.example.com.",
+                        "target": "b-2.elb.us-east-1.amazonaws.com.",
+                    })),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_verify_record_types_and_ttls() {
+        let record = |name: &str, r#type: RrType, ttl: i64, value: &str| {
+            ResourceRecordSet::builder()
+                .name(name)
+                .r#type(r#type)
+                .ttl(ttl)
+                .resource_records(
+                    aws_sdk_route53::types::ResourceRecord::builder()
+                        .value(value)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let zone = |id: &str, name: &str, resource_records| HostedZoneWithRecords {
+            hosted_zone: HostedZone::builder()
+                .id(id)
+                .name(name)
+                .caller_reference(id)
+                .build()
+                .unwrap(),
+            resource_records,
+            vpcs: vec![],
+            name_servers: vec![],
+            delegated_name_servers: None,
+        };
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![
+                zone(
+                    "Z1",
+                    "mycluster.example.com.",
+                    vec![
+                        record(
+                            "mycluster.example.com.",
+                            RrType::Cname,
+                            300,
+                            "a-1.elb.us-east-1.amazonaws.com.",
+                        ),
+                        record(
+                            "api.mycluster.example.com.",
+                            RrType::Cname,
+                            86400,
+                            "a-1.elb.us-east-1.amazonaws.com.",
+                        ),
+                        record(
+                            "mycluster.example.com.",
+                            RrType::Ns,
+                            172800,
+                            "ns-1.awsdns-1.com.",
+                        ),
+                    ],
+                ),
+                zone(
+                    "Z2",
+                    "example.com.",
+                    vec![record(
+                        "api.mycluster.example.com.",
+                        RrType::A,
+                        60,
+                        "10.0.0.1",
+                    )],
+                ),
+            ])
+            .load_balancers(vec![])
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_record_types(),
+            vec![
+                VerificationResult {
+                    message: "Record mycluster.example.com. is a CNAME at the apex of zone Z1 - use an ALIAS record".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::CnameAtZoneApex),
+                    check_id: Some("DNS-009"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "mycluster.example.com.",
+                        "target": "a-1.elb.us-east-1.amazonaws.com",
+                    })),
+                },
+                VerificationResult {
+                    message: "Record api.mycluster.example.com. has type CNAME instead of an ALIAS of the LoadBalancer: a-1.elb.us-east-1.amazonaws.com".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::RecordNotAlias),
+                    check_id: Some("DNS-009"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("api.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "api.mycluster.example.com.",
+                        "target": "a-1.elb.us-east-1.amazonaws.com",
+                    })),
+                },
+                VerificationResult {
+                    message: "Record api.mycluster.example.com. has type A instead of an ALIAS of the LoadBalancer: 10.0.0.1".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::RecordNotAlias),
+                    check_id: Some("DNS-009"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("api.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "api.mycluster.example.com.",
+                        "target": "10.0.0.1",
+                    })),
+                },
+            ]
+        );
+        assert_eq!(
+            hzc.verify_record_ttls(),
+            vec![VerificationResult {
+                message: "Record api.mycluster.example.com. (CNAME) has a TTL of 86400s - expected between 30s and 3600s".to_string(),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::RecordTtlOutOfRange),
+                check_id: Some("DNS-010"),
+                resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                resource_id: Some("api.mycluster.example.com.".to_string()),
+                evidence: None,
+            }]
+        );
+        assert_eq!(
+            hzc.verify_conflicting_records(),
+            vec![VerificationResult {
+                message: "Found conflicting public records for api.mycluster.example.com.: 10.0.0.1 / a-1.elb.us-east-1.amazonaws.com".to_string(),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::ConflictingRecords),
+                check_id: Some("DNS-011"),
+                resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                resource_id: Some("api.mycluster.example.com.".to_string()),
+                evidence: Some(json!({
+                    "record": "api.mycluster.example.com.",
+                    "targets": ["10.0.0.1", "a-1.elb.us-east-1.amazonaws.com"],
+                })),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_verify_ingress_domain_records() {
+        let record = |name: &str, target: &str| {
+            ResourceRecordSet::builder()
+                .name(name)
+                .r#type(RrType::A)
+                .alias_target(
+                    AliasTarget::builder()
+                        .hosted_zone_id("Z1")
+                        .dns_name(target)
+                        .evaluate_target_health(false)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("Z1")
+                    .name("mycluster.example.com.")
+                    .caller_reference("Z1")
+                    .build()
+                    .unwrap(),
+                resource_records: vec![
+                    record(
+                        "\\052.apps.mycluster.example.com.",
+                        "a-1.elb.us-east-1.amazonaws.com.",
+                    ),
+                    record(
+                        "\\052.apps2.mycluster.example.com.",
+                        "b-2.elb.us-east-1.amazonaws.com.",
+                    ),
+                ],
+                vpcs: vec![],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }])
+            .load_balancers(vec![AWSLoadBalancer::ModernLoadBalancer((
+                aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                    .dns_name("a-1.elb.us-east-1.amazonaws.com")
+                    .build(),
+                vec![],
+            ))])
+            .ingress_domains(vec![
+                "apps.mycluster.example.com".to_string(),
+                "apps2.mycluster.example.com".to_string(),
+                "apps3.mycluster.example.com".to_string(),
+                "apps.custom.io".to_string(),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_ingress_domain_records(),
+            vec![
+                VerificationResult {
+                    message: "Ingress domain *.apps.mycluster.example.com points to LoadBalancer a-1.elb.us-east-1.amazonaws.com.".to_string(),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("*.apps.mycluster.example.com".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Ingress domain *.apps2.mycluster.example.com points to b-2.elb.us-east-1.amazonaws.com. which is not a LoadBalancer of the cluster".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::IngressDomainWithForeignLb),
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("*.apps2.mycluster.example.com".to_string()),
+                    evidence: Some(json!({
+                        "record": "\\052.apps2.mycluster.example.com.",
+                        "target": "b-2.elb.us-east-1.amazonaws.com.",
+                    })),
+                },
+                VerificationResult {
+                    message: "No record found for ingress domain *.apps3.mycluster.example.com".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::IngressDomainRecordMissing),
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("*.apps3.mycluster.example.com".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Ingress domain apps.custom.io is not in a hosted zone of the cluster - its records can not be verified".to_string(),
+                    severity: crate::types::Severity::Info,
+                    code: None,
+                    check_id: Some("DNS-005"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("*.apps.custom.io".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_verify_duplicate_hosted_zones() {
+        let zone = |id: &str, private: bool| HostedZoneWithRecords {
+            hosted_zone: HostedZone::builder()
+                .id(id)
+                .name("mycluster.example.com.")
+                .caller_reference(id)
+                .config(HostedZoneConfig::builder().private_zone(private).build())
+                .build()
+                .unwrap(),
+            resource_records: vec![],
+            vpcs: vec![],
+            name_servers: vec![],
+            delegated_name_servers: None,
+        };
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![zone("Z1", true), zone("Z2", false), zone("Z3", true)])
+            .load_balancers(vec![])
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_duplicate_hosted_zones(),
+            vec![VerificationResult {
+                message: "Found 2 private hosted zones named mycluster.example.com.: Z1, Z3"
+                    .to_string(),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::DuplicateHostedZone),
+                check_id: Some("DNS-004"),
+                resource_type: Some(crate::types::ResourceType::HostedZone),
+                resource_id: Some("mycluster.example.com.".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+    #[test]
+    fn test_verify_zone_association_and_delegation() {
+        let zone = |id: &str, private: bool| HostedZoneWithRecords {
+            hosted_zone: HostedZone::builder()
+                .id(id)
+                .name(format!("{}.mycluster.example.com.", id))
+                .caller_reference(id)
+                .config(HostedZoneConfig::builder().private_zone(private).build())
+                .build()
+                .unwrap(),
+            resource_records: vec![],
+            vpcs: vec![],
+            name_servers: vec![],
+            delegated_name_servers: None,
+        };
+        let name_servers = vec![
+            "ns-1.awsdns-01.org".to_string(),
+            "ns-2.awsdns-02.com".to_string(),
+        ];
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![
+                HostedZoneWithRecords {
+                    vpcs: vec!["vpc-2".to_string()],
+                    ..zone("private", true)
+                },
+                HostedZoneWithRecords {
+                    name_servers: name_servers.clone(),
+                    delegated_name_servers: Some(vec![
+                        "NS-2.awsdns-02.com.".to_string(),
+                        "ns-1.awsdns-01.org.".to_string(),
+                    ]),
+                    ..zone("public", false)
+                },
+                HostedZoneWithRecords {
+                    name_servers: name_servers.clone(),
+                    delegated_name_servers: Some(vec![]),
+                    ..zone("undelegated", false)
+                },
+                zone("unresolved", false),
+            ])
+            .load_balancers(vec![])
+            .vpc_ids(vec!["vpc-1".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_private_zone_vpc_association(),
+            vec![VerificationResult {
+                message: "Private hosted zone private.mycluster.example.com. (private) is not associated with the cluster VPC vpc-1 but with: vpc-2".to_string(),
+                severity: crate::types::Severity::Critical,
+                code: Some(crate::types::FindingCode::PrivateZoneNotAssociated),
+                check_id: Some("DNS-006"),
+                resource_type: Some(crate::types::ResourceType::HostedZone),
+                resource_id: Some("private".to_string()),
+                evidence: None,
+            }]
+        );
+        assert_eq!(
+            hzc.verify_public_zone_delegation(),
+            vec![
+                VerificationResult {
+                    message: "Public hosted zone public.mycluster.example.com. is delegated correctly".to_string(),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-007"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some("public".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Public hosted zone undelegated.mycluster.example.com. is not delegated from its parent domain - add its name servers as NS record: ns-1.awsdns-01.org, ns-2.awsdns-02.com".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::ZoneNotDelegated),
+                    check_id: Some("DNS-007"),
+                    resource_type: Some(crate::types::ResourceType::HostedZone),
+                    resource_id: Some("undelegated".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_record_load_balancer_roles() {
+        let record = |name: &str, target: &str| {
+            ResourceRecordSet::builder()
+                .name(name)
+                .r#type(RrType::A)
+                .alias_target(
+                    AliasTarget::builder()
+                        .hosted_zone_id("Z1")
+                        .dns_name(target)
+                        .evaluate_target_health(false)
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+                .unwrap()
+        };
+        let load_balancer = |name: &str, dns_name: &str, tags: Vec<Tag>| {
+            AWSLoadBalancer::ModernLoadBalancer((
+                aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                    .load_balancer_name(name)
+                    .dns_name(dns_name)
+                    .build(),
+                tags,
+            ))
+        };
+        let hzc = HostedZoneChecksBuilder::default()
+            .hosted_zones(vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("Z1")
+                    .name("mycluster.example.com.")
+                    .caller_reference("Z1")
+                    .config(HostedZoneConfig::builder().private_zone(false).build())
+                    .build()
+                    .unwrap(),
+                resource_records: vec![
+                    record(
+                        "api.mycluster.example.com.",
+                        "router-1.elb.us-east-1.amazonaws.com.",
+                    ),
+                    record(
+                        "\\052.apps.mycluster.example.com.",
+                        "dualstack.mycluster-x1-ext-1.elb.us-east-1.amazonaws.com.",
+                    ),
+                    record(
+                        "api-int.mycluster.example.com.",
+                        "mycluster-x1-int-1.elb.us-east-1.amazonaws.com.",
+                    ),
+                ],
+                vpcs: vec![],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }])
+            .load_balancers(vec![
+                load_balancer(
+                    "mycluster-x1-int",
+                    "mycluster-x1-int-1.elb.us-east-1.amazonaws.com",
+                    vec![],
+                ),
+                load_balancer(
+                    "mycluster-x1-ext",
+                    "mycluster-x1-ext-1.elb.us-east-1.amazonaws.com",
+                    vec![],
+                ),
+                load_balancer(
+                    "router",
+                    "router-1.elb.us-east-1.amazonaws.com",
+                    vec![Tag {
+                        key: Some(DEFAULT_ROUTER_TAG_HYPERSHIFT.to_string()),
+                        value: Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT.to_string()),
+                    }],
+                ),
+            ])
+            .infra_name("mycluster-x1".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(
+            hzc.verify_record_load_balancer_roles(),
+            vec![
+                VerificationResult {
+                    message: "Record api.mycluster.example.com. points to the default router LoadBalancer router instead of the external API LoadBalancer".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::RecordWithWrongLb),
+                    check_id: Some("DNS-008"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("api.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "api.mycluster.example.com.",
+                        "target": "router-1.elb.us-east-1.amazonaws.com.",
+                    })),
+                },
+                VerificationResult {
+                    message: "Record \\052.apps.mycluster.example.com. points to the external API LoadBalancer mycluster-x1-ext instead of the default router LoadBalancer".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::RecordWithWrongLb),
+                    check_id: Some("DNS-008"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("\\052.apps.mycluster.example.com.".to_string()),
+                    evidence: Some(json!({
+                        "record": "\\052.apps.mycluster.example.com.",
+                        "target": "dualstack.mycluster-x1-ext-1.elb.us-east-1.amazonaws.com.",
+                    })),
+                },
+                VerificationResult {
+                    message: "Record api-int.mycluster.example.com. points to the internal API LoadBalancer mycluster-x1-int".to_string(),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("DNS-008"),
+                    resource_type: Some(crate::types::ResourceType::ResourceRecord),
+                    resource_id: Some("api-int.mycluster.example.com.".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+    }
+}
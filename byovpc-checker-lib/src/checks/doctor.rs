@@ -0,0 +1,251 @@
+//! This checker verifies the local environment the tool runs in - most runs
+//! that fail do so because of the environment, not the cluster.
+//! It can check the following conditions right now:
+//!
+//! - AWS credentials can be loaded and do not expire during the run.
+//! - A region is configured.
+//! - The OCM login works.
+//! - The configured proxy is reachable.
+//! - The CLIs the tool relies on are installed.
+
+use derive_builder::Builder;
+use log::info;
+
+use crate::types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier};
+
+/// A CLI the tool relies on, with the version that was found.
+#[derive(Clone, Debug)]
+pub struct Binary {
+    pub name: &'static str,
+    /// What the CLI is used for.
+    pub purpose: &'static str,
+    /// The first line of the version output - `None` if the CLI was not found.
+    pub version: Option<String>,
+    /// Whether the tool can not work without the CLI.
+    pub required: bool,
+}
+
+#[derive(Debug, Builder)]
+pub struct DoctorChecks {
+    profile: String,
+    /// Why the credentials can not be used - `None` if they are valid.
+    #[builder(default)]
+    credentials_error: Option<String>,
+    #[builder(default)]
+    region: Option<String>,
+    #[builder(default)]
+    ocm_url: Option<String>,
+    /// Why the OCM API can not be used - `None` if the login works.
+    #[builder(default)]
+    ocm_error: Option<String>,
+    /// The proxy without its credentials.
+    #[builder(default)]
+    proxy: Option<String>,
+    #[builder(default)]
+    proxy_error: Option<String>,
+    #[builder(default = "vec![]")]
+    binaries: Vec<Binary>,
+}
+
+impl DoctorChecks {
+    pub fn verify_credentials(&self) -> VerificationResult {
+        info!("Checking AWS credentials");
+        match self.credentials_error {
+            Some(ref e) => VerificationResult {
+                message: e.clone(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::CredentialsUnusable),
+                check_id: Some("DOC-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            None => VerificationResult {
+                message: format!("AWS credentials of profile {} are valid", self.profile),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("DOC-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        }
+    }
+
+    pub fn verify_region(&self) -> VerificationResult {
+        info!("Checking AWS region");
+        match self.region {
+            Some(ref region) => VerificationResult {
+                message: format!("AWS region {} is configured", region),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("DOC-002"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            None => VerificationResult {
+                message: "No AWS region is configured - the region of the cluster in OCM is used, or pass --region".to_string(),
+                severity: Severity::Info,
+                code: Some(FindingCode::RegionNotConfigured),
+                check_id: Some("DOC-002"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        }
+    }
+
+    pub fn verify_ocm_login(&self) -> VerificationResult {
+        info!("Checking OCM login");
+        match (&self.ocm_url, &self.ocm_error) {
+            (_, Some(e)) => VerificationResult {
+                message: format!("Could not use the OCM API - run `ocm login`: {}", e),
+                severity: Severity::Critical,
+                code: Some(FindingCode::OcmNotLoggedIn),
+                check_id: Some("DOC-003"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            (Some(url), None) => VerificationResult {
+                message: format!("Logged in to OCM at {}", url),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("DOC-003"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+            (None, None) => VerificationResult {
+                message: "Not logged in to OCM - run `ocm login`".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::OcmNotLoggedIn),
+                check_id: Some("DOC-003"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        }
+    }
+
+    pub fn verify_proxy(&self) -> Vec<VerificationResult> {
+        info!("Checking proxy");
+        let Some(ref proxy) = self.proxy else {
+            return vec![];
+        };
+        let result = match self.proxy_error {
+            Some(ref e) => VerificationResult {
+                message: format!("Proxy {} is not reachable: {}", proxy, e),
+                severity: Severity::Critical,
+                code: Some(FindingCode::ProxyUnreachable),
+                check_id: Some("DOC-004"),
+                resource_type: Some(ResourceType::Proxy),
+                resource_id: Some(proxy.to_string()),
+                evidence: None,
+            },
+            None => VerificationResult {
+                message: format!("Proxy {} is reachable", proxy),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("DOC-004"),
+                resource_type: Some(ResourceType::Proxy),
+                resource_id: Some(proxy.to_string()),
+                evidence: None,
+            },
+        };
+        vec![result]
+    }
+
+    pub fn verify_binaries(&self) -> Vec<VerificationResult> {
+        info!("Checking installed CLIs");
+        self.binaries
+            .iter()
+            .map(|b| match b.version {
+                Some(ref version) => VerificationResult {
+                    message: format!("{} is installed: {}", b.name, version),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("DOC-005"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                None => VerificationResult {
+                    message: format!("{} is not installed - it is needed {}", b.name, b.purpose),
+                    severity: if b.required {
+                        Severity::Warning
+                    } else {
+                        Severity::Info
+                    },
+                    code: Some(FindingCode::BinaryMissing),
+                    check_id: Some("DOC-005"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            })
+            .collect()
+    }
+}
+
+impl Verifier for DoctorChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.push(self.verify_credentials());
+        results.push(self.verify_region());
+        results.push(self.verify_ocm_login());
+        results.extend(self.verify_proxy());
+        results.extend(self.verify_binaries());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_environment() {
+        let dc = DoctorChecksBuilder::default()
+            .profile("dev".to_string())
+            .region(Some("us-east-1".to_string()))
+            .proxy(Some("http://proxy:3128/".to_string()))
+            .proxy_error(Some("connection refused".to_string()))
+            .binaries(vec![
+                Binary {
+                    name: "ocm",
+                    purpose: "if the OCM API can not be reached",
+                    version: Some("0.1.72".to_string()),
+                    required: false,
+                },
+                Binary {
+                    name: "aws",
+                    purpose: "to log in to SSO sessions",
+                    version: None,
+                    required: true,
+                },
+            ])
+            .build()
+            .unwrap();
+        let results = dc.verify();
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.check_id.unwrap(), r.severity))
+                .collect::<Vec<_>>(),
+            vec![
+                ("DOC-001", Severity::Ok),
+                ("DOC-002", Severity::Ok),
+                ("DOC-003", Severity::Critical),
+                ("DOC-004", Severity::Critical),
+                ("DOC-005", Severity::Ok),
+                ("DOC-005", Severity::Warning),
+            ]
+        );
+        assert_eq!(
+            results[5].message,
+            "aws is not installed - it is needed to log in to SSO sessions"
+        );
+    }
+}
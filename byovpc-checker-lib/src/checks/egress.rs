@@ -0,0 +1,385 @@
+//! This checker verifies the subnets can host the OVN-Kubernetes egress IPs of
+//! the cluster. Egress IPs are assigned as secondary IPs to the ENIs of the
+//! egress nodes, so they have to be free addresses of the node subnets.
+//! It can check the following conditions right now:
+//!
+//! - The cluster uses OVN-Kubernetes.
+//! - Every egress IP is within a cluster subnet.
+//! - The subnets have enough free addresses for their egress IPs.
+//! - The network ACLs of the subnets do not deny traffic of the egress IPs.
+//!   Only the first matching rule is considered, protocols and ports are
+//!   ignored.
+//! - The security groups of the nodes in the subnets allow all egress.
+
+use std::{collections::BTreeMap, net::Ipv4Addr};
+
+use aws_sdk_ec2::types::{NetworkAcl, RuleAction, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    gatherer::aws::shared_types::AWSInstance,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+const OVN_KUBERNETES: &str = "OVNKubernetes";
+
+/// Parses an IPv4 CIDR block into its network address and prefix length. A
+/// single address is treated as a `/32`.
+pub fn parse_cidr(cidr: &str) -> Option<(u32, u8)> {
+    let (ip, prefix) = match cidr.split_once('/') {
+        Some((ip, prefix)) => (ip, prefix.parse().ok()?),
+        None => (cidr, 32),
+    };
+    if prefix > 32 {
+        return None;
+    }
+    let ip: Ipv4Addr = ip.parse().ok()?;
+    Some((u32::from(ip), prefix))
+}
+
+/// Whether the CIDR block `outer` contains all addresses of `inner`.
+pub fn cidr_contains(outer: (u32, u8), inner: (u32, u8)) -> bool {
+    let mask = |prefix: u8| u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+    outer.1 <= inner.1 && outer.0 & mask(outer.1) == inner.0 & mask(outer.1)
+}
+
+#[derive(Debug, Builder)]
+pub struct EgressIpChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    /// The egress IPs (or CIDR blocks) configured for the cluster.
+    #[builder(default = "vec![]")]
+    egress_ips: Vec<String>,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    network_acls: Vec<NetworkAcl>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+}
+
+impl<'a> EgressIpChecks<'a> {
+    /// Maps every egress IP to the subnet containing it.
+    fn egress_ip_subnets(&self) -> Vec<(&String, Option<&Subnet>)> {
+        self.egress_ips
+            .iter()
+            .map(|ip| {
+                let subnet = parse_cidr(ip).and_then(|egress| {
+                    self.subnets.iter().find(|s| {
+                        s.cidr_block()
+                            .and_then(parse_cidr)
+                            .is_some_and(|c| cidr_contains(c, egress))
+                    })
+                });
+                (ip, subnet)
+            })
+            .collect()
+    }
+
+    pub fn verify_network_type(&self) -> Vec<VerificationResult> {
+        info!("Checking network type supports egress IPs");
+        match self.cluster_info.network_type {
+            Some(ref network_type) if network_type != OVN_KUBERNETES => {
+                vec![VerificationResult {
+                    message: format!(
+                        "Egress IPs require {} but the cluster uses {}",
+                        OVN_KUBERNETES, network_type
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::EgressIpUnsupportedNetworkType),
+                    check_id: Some("EGR-001"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                }]
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn verify_egress_ips_in_subnets(&self) -> Vec<VerificationResult> {
+        info!("Checking egress IPs are within the cluster subnets");
+        self.egress_ip_subnets()
+            .into_iter()
+            .filter(|(_, subnet)| subnet.is_none())
+            .map(|(ip, _)| VerificationResult {
+                message: format!(
+                    "Egress IP {} is not within the CIDR block of any cluster subnet",
+                    ip
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::EgressIpOutsideSubnets),
+                check_id: Some("EGR-002"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_subnet_capacity(&self) -> Vec<VerificationResult> {
+        info!("Checking subnets have capacity for egress IPs");
+        let mut egress_ips_per_subnet: BTreeMap<&str, (&Subnet, i32)> = BTreeMap::new();
+        for (_, subnet) in self.egress_ip_subnets() {
+            let Some(subnet) = subnet else {
+                continue;
+            };
+            egress_ips_per_subnet
+                .entry(subnet.subnet_id().unwrap_or_default())
+                .or_insert((subnet, 0))
+                .1 += 1;
+        }
+        egress_ips_per_subnet
+            .into_iter()
+            .filter_map(|(subnet_id, (subnet, count))| {
+                let available = subnet.available_ip_address_count()?;
+                if available >= count {
+                    return None;
+                }
+                Some(VerificationResult {
+                    message: format!(
+                        "Subnet {} has {} free addresses but {} egress IPs",
+                        subnet_id, available, count
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::EgressIpSubnetExhausted),
+                    check_id: Some("EGR-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn verify_network_acls(&self) -> Vec<VerificationResult> {
+        info!("Checking network ACLs allow egress IP traffic");
+        let mut results = vec![];
+        for (ip, subnet) in self.egress_ip_subnets() {
+            let (Some(egress), Some(subnet_id)) =
+                (parse_cidr(ip), subnet.and_then(|s| s.subnet_id()))
+            else {
+                continue;
+            };
+            let Some(acl) = self.network_acls.iter().find(|acl| {
+                acl.associations()
+                    .iter()
+                    .any(|a| a.subnet_id() == Some(subnet_id))
+            }) else {
+                continue;
+            };
+            for egress_rule in [false, true] {
+                let mut entries: Vec<_> = acl
+                    .entries()
+                    .iter()
+                    .filter(|e| e.egress() == Some(egress_rule))
+                    .collect();
+                entries.sort_by_key(|e| e.rule_number());
+                let first_match = entries.into_iter().find(|e| {
+                    e.cidr_block()
+                        .and_then(parse_cidr)
+                        .is_some_and(|c| cidr_contains(c, egress))
+                });
+                if let Some(entry) = first_match {
+                    if entry.rule_action() == Some(&RuleAction::Deny) {
+                        results.push(VerificationResult {
+                            message: format!(
+                                "Network ACL {} denies {} traffic for egress IP {} (rule {})",
+                                acl.network_acl_id().unwrap_or_default(),
+                                if egress_rule { "outbound" } else { "inbound" },
+                                ip,
+                                entry.rule_number().unwrap_or_default()
+                            ),
+                            severity: Severity::Warning,
+                            code: Some(FindingCode::EgressIpBlockedByNacl),
+                            check_id: Some("EGR-004"),
+                            resource_type: Some(ResourceType::NetworkAcl),
+                            resource_id: Some(acl.network_acl_id().unwrap_or_default().to_string()),
+                            evidence: None,
+                        });
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_security_groups(&self) -> Vec<VerificationResult> {
+        info!("Checking security groups of egress nodes allow all egress");
+        let egress_subnets: Vec<&str> = self
+            .egress_ip_subnets()
+            .into_iter()
+            .filter_map(|(_, s)| s.and_then(|s| s.subnet_id()))
+            .collect();
+        let mut results = vec![];
+        for i in self.instances.iter() {
+            if !i
+                .instance
+                .subnet_id()
+                .is_some_and(|id| egress_subnets.contains(&id))
+            {
+                continue;
+            }
+            let allows_all_egress = i.security_groups.iter().any(|sg| {
+                sg.ip_permissions_egress().iter().any(|p| {
+                    p.ip_protocol() == Some("-1")
+                        && p.ip_ranges()
+                            .iter()
+                            .any(|r| r.cidr_ip() == Some("0.0.0.0/0"))
+                })
+            });
+            if !allows_all_egress {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Security groups of instance {} do not allow all egress - traffic of egress IPs assigned to it might be blocked",
+                        i.instance.instance_id().unwrap_or_default()
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::EgressIpBlockedBySecurityGroup),
+                    check_id: Some("EGR-005"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(i.instance.instance_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for EgressIpChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self.egress_ips.is_empty() {
+            return vec![VerificationResult {
+                message: "No egress IPs configured - skipping egress IP checks".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        results.extend(self.verify_network_type());
+        results.extend(self.verify_egress_ips_in_subnets());
+        results.extend(self.verify_subnet_capacity());
+        results.extend(self.verify_network_acls());
+        results.extend(self.verify_security_groups());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "{} egress IPs can be hosted by the cluster subnets",
+                    self.egress_ips.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{NetworkAclAssociation, NetworkAclEntry};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_cidr_contains() {
+        let subnet = parse_cidr("10.0.16.0/20").unwrap();
+        assert!(cidr_contains(subnet, parse_cidr("10.0.31.255").unwrap()));
+        assert!(!cidr_contains(subnet, parse_cidr("10.0.32.1").unwrap()));
+        assert!(cidr_contains(parse_cidr("0.0.0.0/0").unwrap(), subnet));
+        assert!(!cidr_contains(subnet, parse_cidr("10.0.0.0/16").unwrap()));
+    }
+
+    #[test]
+    fn test_verify_egress_ips() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .network_type(Some(OVN_KUBERNETES.to_string()))
+            .build()
+            .unwrap();
+        let subnet = Subnet::builder()
+            .subnet_id("subnet-1")
+            .cidr_block("10.0.0.0/24")
+            .available_ip_address_count(1)
+            .build();
+        let entry = |rule: i32, cidr: &str, action: RuleAction| {
+            NetworkAclEntry::builder()
+                .rule_number(rule)
+                .egress(false)
+                .cidr_block(cidr)
+                .rule_action(action)
+                .build()
+        };
+        let acl = NetworkAcl::builder()
+            .network_acl_id("acl-1")
+            .associations(
+                NetworkAclAssociation::builder()
+                    .subnet_id("subnet-1")
+                    .build(),
+            )
+            .entries(entry(32767, "0.0.0.0/0", RuleAction::Deny))
+            .entries(entry(100, "0.0.0.0/0", RuleAction::Allow))
+            .entries(entry(50, "10.0.0.11/32", RuleAction::Deny))
+            .build();
+        let eic = EgressIpChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .egress_ips(vec![
+                "10.0.0.10".to_string(),
+                "10.0.0.11".to_string(),
+                "10.0.1.10".to_string(),
+            ])
+            .subnets(vec![subnet])
+            .network_acls(vec![acl])
+            .build()
+            .unwrap();
+        assert_eq!(
+            eic.verify(),
+            vec![
+                VerificationResult {
+                    message:
+                        "Egress IP 10.0.1.10 is not within the CIDR block of any cluster subnet"
+                            .to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::EgressIpOutsideSubnets),
+                    check_id: Some("EGR-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-1 has 1 free addresses but 2 egress IPs".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::EgressIpSubnetExhausted),
+                    check_id: Some("EGR-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message:
+                        "Network ACL acl-1 denies inbound traffic for egress IP 10.0.0.11 (rule 50)"
+                            .to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::EgressIpBlockedByNacl),
+                    check_id: Some("EGR-004"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some("acl-1".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
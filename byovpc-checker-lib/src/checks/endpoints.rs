@@ -0,0 +1,441 @@
+//! This checker verifies the VPC endpoints private and PrivateLink clusters
+//! need to bootstrap nodes without egress to the internet.
+//! It can check the following conditions right now:
+//!
+//! - Endpoints for S3, EC2, ELB and STS exist in the cluster VPC.
+//! - The endpoints are available.
+//! - The S3 gateway endpoint is associated with the route tables of the
+//!   cluster subnets.
+//! - Interface endpoints have private DNS enabled - otherwise the AWS APIs
+//!   resolve to their public addresses.
+//!
+//! Zero-egress clusters have no other way to reach AWS, so they also need the
+//! ECR endpoints the mirrored release images are pulled through, and a
+//! missing endpoint is critical.
+
+use aws_sdk_ec2::types::{RouteTable, State, Subnet, VpcEndpoint, VpcEndpointType};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    checks::network::subnet_routetable_mapping,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// The services nodes need to reach during bootstrapping, identified by the
+/// suffix of the endpoint service name, e.g. `com.amazonaws.us-east-1.s3`.
+const REQUIRED_SERVICES: [&str; 4] = ["s3", "ec2", "elasticloadbalancing", "sts"];
+
+/// The services zero-egress clusters additionally need to pull the release
+/// images mirrored to ECR.
+const ZERO_EGRESS_SERVICES: [&str; 2] = ["ecr.api", "ecr.dkr"];
+
+#[derive(Debug, Builder)]
+pub struct EndpointChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    vpc_endpoints: Vec<VpcEndpoint>,
+}
+
+impl<'a> EndpointChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    fn cluster_vpcs(&self) -> Vec<&str> {
+        self.cluster_subnets()
+            .iter()
+            .filter_map(|s| s.vpc_id())
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    fn required_services(&self) -> Vec<&'static str> {
+        let mut services = REQUIRED_SERVICES.to_vec();
+        if self.cluster_info.zero_egress {
+            services.extend(ZERO_EGRESS_SERVICES);
+        }
+        services
+    }
+
+    fn endpoints_for(&self, vpc_id: &str, service: &str) -> Vec<&VpcEndpoint> {
+        let suffix = format!(".{}", service);
+        self.vpc_endpoints
+            .iter()
+            .filter(|e| e.vpc_id() == Some(vpc_id))
+            .filter(|e| e.service_name().is_some_and(|n| n.ends_with(&suffix)))
+            .collect()
+    }
+
+    pub fn verify_endpoints_exist(&self) -> Vec<VerificationResult> {
+        info!("Checking required VPC endpoints exist");
+        let mut results = vec![];
+        for vpc_id in self.cluster_vpcs() {
+            for service in self.required_services() {
+                if self.endpoints_for(vpc_id, service).is_empty() {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "VPC {} has no endpoint for {} - nodes can not reach it without egress to the internet",
+                            vpc_id, service
+                        ),
+                        severity: if self.cluster_info.zero_egress {
+                            Severity::Critical
+                        } else {
+                            Severity::Warning
+                        },
+                        code: Some(FindingCode::MissingVpcEndpoint),
+                        check_id: Some("EP-001"),
+                        resource_type: Some(ResourceType::Vpc),
+                        resource_id: Some(vpc_id.to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_endpoints_available(&self) -> Vec<VerificationResult> {
+        info!("Checking VPC endpoints are available");
+        let mut results = vec![];
+        for vpc_id in self.cluster_vpcs() {
+            for service in self.required_services() {
+                for e in self.endpoints_for(vpc_id, service) {
+                    if e.state() == Some(&State::Available) {
+                        continue;
+                    }
+                    results.push(VerificationResult {
+                        message: format!(
+                            "VPC endpoint {} for {} is in state: {}",
+                            e.vpc_endpoint_id().unwrap_or_default(),
+                            service,
+                            e.state().map_or("unknown", |s| s.as_str())
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::VpcEndpointUnavailable),
+                        check_id: Some("EP-002"),
+                        resource_type: Some(ResourceType::VpcEndpoint),
+                        resource_id: Some(e.vpc_endpoint_id().unwrap_or_default().to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Gateway endpoints only work for subnets whose route table has a route
+    /// to the endpoint.
+    pub fn verify_gateway_endpoint_associations(&self) -> Vec<VerificationResult> {
+        info!("Checking gateway endpoints are associated with the cluster subnets");
+        let mut results = vec![];
+        let subnets: Vec<Subnet> = self.cluster_subnets().into_iter().cloned().collect();
+        let mapping = subnet_routetable_mapping(&subnets, &self.routetables);
+        for subnet in subnets.iter() {
+            let (Some(subnet_id), Some(vpc_id)) = (subnet.subnet_id(), subnet.vpc_id()) else {
+                continue;
+            };
+            let Some(rtb_id) = mapping.get(subnet_id).and_then(|r| r.route_table_id()) else {
+                continue;
+            };
+            let gateways: Vec<&VpcEndpoint> = self
+                .endpoints_for(vpc_id, "s3")
+                .into_iter()
+                .filter(|e| e.vpc_endpoint_type() == Some(&VpcEndpointType::Gateway))
+                .collect();
+            if gateways.is_empty() {
+                continue;
+            }
+            if !gateways
+                .iter()
+                .any(|e| e.route_table_ids().iter().any(|id| id == rtb_id))
+            {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Route table {} of subnet {} is not associated with the S3 gateway endpoint {}",
+                        rtb_id,
+                        subnet_id,
+                        gateways
+                            .iter()
+                            .filter_map(|e| e.vpc_endpoint_id())
+                            .join(", ")
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::VpcEndpointNotAssociated),
+                    check_id: Some("EP-003"),
+                    resource_type: Some(ResourceType::RouteTable),
+                    resource_id: Some(rtb_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_interface_endpoint_private_dns(&self) -> Vec<VerificationResult> {
+        info!("Checking interface endpoints have private DNS enabled");
+        let mut results = vec![];
+        for vpc_id in self.cluster_vpcs() {
+            for service in self.required_services() {
+                for e in self.endpoints_for(vpc_id, service) {
+                    if e.vpc_endpoint_type() != Some(&VpcEndpointType::Interface)
+                        || e.private_dns_enabled() == Some(true)
+                    {
+                        continue;
+                    }
+                    results.push(VerificationResult {
+                        message: format!(
+                            "VPC endpoint {} for {} does not have private DNS enabled - {} resolves to its public address",
+                            e.vpc_endpoint_id().unwrap_or_default(),
+                            service,
+                            service
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::VpcEndpointPrivateDnsDisabled),
+                        check_id: Some("EP-004"),
+                        resource_type: Some(ResourceType::VpcEndpoint),
+                        resource_id: Some(e.vpc_endpoint_id().unwrap_or_default().to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Zero-egress clusters pull the release images from a mirror in ECR -
+    /// notes the endpoints the images are pulled through.
+    pub fn verify_registry_mirror(&self) -> Vec<VerificationResult> {
+        info!("Checking the release image mirror is reachable");
+        if !self.cluster_info.zero_egress {
+            return vec![];
+        }
+        let mut results = vec![];
+        for vpc_id in self.cluster_vpcs() {
+            let endpoints: Vec<&VpcEndpoint> = ZERO_EGRESS_SERVICES
+                .iter()
+                .flat_map(|service| self.endpoints_for(vpc_id, service))
+                .collect();
+            if endpoints.len() < ZERO_EGRESS_SERVICES.len() {
+                continue;
+            }
+            results.push(VerificationResult {
+                message: format!(
+                    "VPC {} pulls the release images mirrored to ECR through the endpoints {}",
+                    vpc_id,
+                    endpoints
+                        .iter()
+                        .filter_map(|e| e.vpc_endpoint_id())
+                        .join(", ")
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("EP-005"),
+                resource_type: Some(ResourceType::Vpc),
+                resource_id: Some(vpc_id.to_string()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for EndpointChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if !self.cluster_info.private && !self.cluster_info.zero_egress {
+            return vec![VerificationResult {
+                message: "The cluster is not private - skipping VPC endpoint checks".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        results.extend(self.verify_endpoints_exist());
+        results.extend(self.verify_endpoints_available());
+        results.extend(self.verify_gateway_endpoint_associations());
+        results.extend(self.verify_interface_endpoint_private_dns());
+        results.extend(self.verify_registry_mirror());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "Required VPC endpoints exist and are available".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::RouteTableAssociation;
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    fn endpoint(id: &str, service: &str, t: VpcEndpointType) -> VpcEndpoint {
+        VpcEndpoint::builder()
+            .vpc_endpoint_id(id)
+            .vpc_id("vpc-1")
+            .service_name(format!("com.amazonaws.us-east-1.{}", service))
+            .vpc_endpoint_type(t)
+            .state(State::Available)
+            .private_dns_enabled(true)
+            .build()
+    }
+
+    #[test]
+    fn test_verify_endpoints() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .subnets(vec!["subnet-1".to_string()])
+            .private(true)
+            .build()
+            .unwrap();
+        let subnet = Subnet::builder()
+            .subnet_id("subnet-1")
+            .vpc_id("vpc-1")
+            .build();
+        let rtb = RouteTable::builder()
+            .route_table_id("rtb-1")
+            .associations(
+                RouteTableAssociation::builder()
+                    .subnet_id("subnet-1")
+                    .build(),
+            )
+            .build();
+        let mut s3 = endpoint("vpce-s3", "s3", VpcEndpointType::Gateway);
+        s3.route_table_ids = Some(vec!["rtb-2".to_string()]);
+        let mut sts = endpoint("vpce-sts", "sts", VpcEndpointType::Interface);
+        sts.private_dns_enabled = Some(false);
+        let ec = EndpointChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![subnet])
+            .routetables(vec![rtb])
+            .vpc_endpoints(vec![
+                s3,
+                sts,
+                endpoint("vpce-ec2", "ec2", VpcEndpointType::Interface),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            ec.verify(),
+            vec![
+                VerificationResult {
+                    message: "VPC vpc-1 has no endpoint for elasticloadbalancing - nodes can not reach it without egress to the internet".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::MissingVpcEndpoint),
+                    check_id: Some("EP-001"),
+                    resource_type: Some(ResourceType::Vpc),
+                    resource_id: Some("vpc-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Route table rtb-1 of subnet subnet-1 is not associated with the S3 gateway endpoint vpce-s3".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::VpcEndpointNotAssociated),
+                    check_id: Some("EP-003"),
+                    resource_type: Some(ResourceType::RouteTable),
+                    resource_id: Some("rtb-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "VPC endpoint vpce-sts for sts does not have private DNS enabled - sts resolves to its public address".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::VpcEndpointPrivateDnsDisabled),
+                    check_id: Some("EP-004"),
+                    resource_type: Some(ResourceType::VpcEndpoint),
+                    resource_id: Some("vpce-sts".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_verify_endpoints_zero_egress() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .subnets(vec!["subnet-1".to_string()])
+            .zero_egress(true)
+            .build()
+            .unwrap();
+        let subnet = Subnet::builder()
+            .subnet_id("subnet-1")
+            .vpc_id("vpc-1")
+            .build();
+        let mut s3 = endpoint("vpce-s3", "s3", VpcEndpointType::Gateway);
+        s3.route_table_ids = Some(vec!["rtb-1".to_string()]);
+        let endpoints = vec![
+            s3,
+            endpoint("vpce-ec2", "ec2", VpcEndpointType::Interface),
+            endpoint(
+                "vpce-elb",
+                "elasticloadbalancing",
+                VpcEndpointType::Interface,
+            ),
+            endpoint("vpce-sts", "sts", VpcEndpointType::Interface),
+            endpoint("vpce-ecr-api", "ecr.api", VpcEndpointType::Interface),
+        ];
+        let checks = |endpoints: Vec<VpcEndpoint>| {
+            EndpointChecksBuilder::default()
+                .cluster_info(&cluster_info)
+                .subnets(vec![subnet.clone()])
+                .vpc_endpoints(endpoints)
+                .build()
+                .unwrap()
+                .verify()
+        };
+        assert_eq!(
+            checks(endpoints.clone()),
+            vec![VerificationResult {
+                message: "VPC vpc-1 has no endpoint for ecr.dkr - nodes can not reach it without egress to the internet".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::MissingVpcEndpoint),
+                check_id: Some("EP-001"),
+                resource_type: Some(ResourceType::Vpc),
+                resource_id: Some("vpc-1".to_string()),
+                evidence: None,
+            }]
+        );
+        let mut endpoints = endpoints;
+        endpoints.push(endpoint(
+            "vpce-ecr-dkr",
+            "ecr.dkr",
+            VpcEndpointType::Interface,
+        ));
+        assert_eq!(
+            checks(endpoints),
+            vec![VerificationResult {
+                message: "VPC vpc-1 pulls the release images mirrored to ECR through the endpoints vpce-ecr-api, vpce-ecr-dkr".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("EP-005"),
+                resource_type: Some(ResourceType::Vpc),
+                resource_id: Some("vpc-1".to_string()),
+                evidence: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! This checker reports the VPC flow logs of the cluster - they are the first
+//! thing to look at when traffic is dropped, so knowing whether they exist and
+//! where they are delivered saves a round trip to the customer.
+//! It can check the following conditions right now:
+//!
+//! - Flow logs capture the traffic of the cluster VPCs or subnets (and where
+//!   they deliver to).
+//! - The flow logs deliver their logs successfully.
+
+use aws_sdk_ec2::types::{FlowLog, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier};
+
+#[derive(Debug, Builder)]
+pub struct FlowLogChecks {
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    flow_logs: Vec<FlowLog>,
+}
+
+impl FlowLogChecks {
+    fn vpc_ids(&self) -> Vec<&str> {
+        let mut vpc_ids: Vec<&str> = self.subnets.iter().filter_map(|s| s.vpc_id()).collect();
+        vpc_ids.sort();
+        vpc_ids.dedup();
+        vpc_ids
+    }
+
+    /// The flow logs of the VPC or one of the cluster subnets in it.
+    fn flow_logs_of(&self, vpc_id: &str) -> Vec<&FlowLog> {
+        let subnet_ids: Vec<&str> = self
+            .subnets
+            .iter()
+            .filter(|s| s.vpc_id() == Some(vpc_id))
+            .filter_map(|s| s.subnet_id())
+            .collect();
+        self.flow_logs
+            .iter()
+            .filter(|f| {
+                f.resource_id()
+                    .is_some_and(|r| r == vpc_id || subnet_ids.contains(&r))
+            })
+            .collect()
+    }
+
+    fn destination(flow_log: &FlowLog) -> String {
+        let destination_type = flow_log
+            .log_destination_type()
+            .map_or("cloud-watch-logs", |t| t.as_str());
+        let destination = flow_log
+            .log_destination()
+            .or(flow_log.log_group_name())
+            .unwrap_or("an unknown destination");
+        format!("{} ({})", destination, destination_type)
+    }
+
+    pub fn verify_flow_logs_enabled(&self) -> Vec<VerificationResult> {
+        info!("Checking flow logs of the cluster VPCs");
+        let mut results = vec![];
+        for vpc_id in self.vpc_ids() {
+            let flow_logs = self.flow_logs_of(vpc_id);
+            if flow_logs.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "No flow logs capture the traffic of VPC {} or its cluster subnets",
+                        vpc_id
+                    ),
+                    severity: Severity::Info,
+                    code: Some(FindingCode::FlowLogsDisabled),
+                    check_id: Some("FLOW-001"),
+                    resource_type: Some(ResourceType::Vpc),
+                    resource_id: Some(vpc_id.to_string()),
+                    evidence: None,
+                });
+            }
+            for flow_log in flow_logs {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Flow log {} captures {} traffic of {} to {}",
+                        flow_log.flow_log_id().unwrap_or_default(),
+                        flow_log.traffic_type().map_or("ALL", |t| t.as_str()),
+                        flow_log.resource_id().unwrap_or_default(),
+                        Self::destination(flow_log)
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("FLOW-001"),
+                    resource_type: Some(ResourceType::FlowLog),
+                    resource_id: Some(flow_log.flow_log_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_flow_logs_delivered(&self) -> Vec<VerificationResult> {
+        info!("Checking flow logs deliver their logs");
+        self.vpc_ids()
+            .into_iter()
+            .flat_map(|vpc_id| self.flow_logs_of(vpc_id))
+            .filter(|f| f.deliver_logs_status() == Some("FAILED"))
+            .map(|f| VerificationResult {
+                message: format!(
+                    "Flow log {} of {} fails to deliver its logs to {}: {}",
+                    f.flow_log_id().unwrap_or_default(),
+                    f.resource_id().unwrap_or_default(),
+                    Self::destination(f),
+                    f.deliver_logs_error_message()
+                        .unwrap_or("no error reported")
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::FlowLogDeliveryFailed),
+                check_id: Some("FLOW-002"),
+                resource_type: Some(ResourceType::FlowLog),
+                resource_id: Some(f.flow_log_id().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+}
+
+impl Verifier for FlowLogChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = self.verify_flow_logs_enabled();
+        results.extend(self.verify_flow_logs_delivered());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::LogDestinationType;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_flow_logs() {
+        let subnet =
+            |id: &str, vpc_id: &str| Subnet::builder().subnet_id(id).vpc_id(vpc_id).build();
+        let flc = FlowLogChecksBuilder::default()
+            .subnets(vec![
+                subnet("subnet-1", "vpc-1"),
+                subnet("subnet-2", "vpc-2"),
+            ])
+            .flow_logs(vec![
+                FlowLog::builder()
+                    .flow_log_id("fl-1")
+                    .resource_id("subnet-1")
+                    .log_destination_type(LogDestinationType::S3)
+                    .log_destination("arn:aws:s3:::flow-logs")
+                    .deliver_logs_status("FAILED")
+                    .deliver_logs_error_message("Access error")
+                    .build(),
+                FlowLog::builder()
+                    .flow_log_id("fl-2")
+                    .resource_id("vpc-3")
+                    .log_group_name("other")
+                    .build(),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            flc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Flow log fl-1 captures ALL traffic of subnet-1 to arn:aws:s3:::flow-logs (s3)".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("FLOW-001"),
+                    resource_type: Some(ResourceType::FlowLog),
+                    resource_id: Some("fl-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "No flow logs capture the traffic of VPC vpc-2 or its cluster subnets".to_string(),
+                    severity: Severity::Info,
+                    code: Some(FindingCode::FlowLogsDisabled),
+                    check_id: Some("FLOW-001"),
+                    resource_type: Some(ResourceType::Vpc),
+                    resource_id: Some("vpc-2".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Flow log fl-1 of subnet-1 fails to deliver its logs to arn:aws:s3:::flow-logs (s3): Access error".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::FlowLogDeliveryFailed),
+                    check_id: Some("FLOW-002"),
+                    resource_type: Some(ResourceType::FlowLog),
+                    resource_id: Some("fl-1".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
@@ -0,0 +1,153 @@
+//! This checker reports open events of the AWS Health API that can affect the
+//! cluster network - "is AWS itself degraded?" is the first question when
+//! triaging.
+//! It can check the following conditions right now:
+//!
+//! - No EC2, ELB or Route53 event is open in the region of the cluster or in
+//!   the availability zones of its subnets.
+
+use aws_sdk_ec2::types::Subnet;
+use derive_builder::Builder;
+use log::{debug, info};
+
+use crate::{
+    gatherer::aws::{format_age, health::HealthEvent, now},
+    types::{MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+#[derive(Debug, Builder)]
+pub struct HealthEventChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    events: Vec<HealthEvent>,
+    /// Seconds since the epoch - the age of the events is reported relative to
+    /// it.
+    #[builder(default = "now()")]
+    now: u64,
+}
+
+impl<'a> HealthEventChecks<'a> {
+    fn cluster_availability_zones(&self) -> Vec<&str> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .filter_map(|s| s.availability_zone())
+            .collect()
+    }
+
+    pub fn verify_no_open_events(&self) -> Vec<VerificationResult> {
+        info!("Checking AWS Health events");
+        let azs = self.cluster_availability_zones();
+        let mut results = vec![];
+        for event in self.events.iter() {
+            let location = match event.availability_zone {
+                Some(ref az) if !azs.contains(&az.as_str()) => {
+                    debug!("Ignoring event {} in availability zone {}", event.arn, az);
+                    continue;
+                }
+                Some(ref az) => az.clone(),
+                None => event.region.clone().unwrap_or("all regions".to_string()),
+            };
+            let since = match event.start_time {
+                Some(start) => {
+                    format!(" for {}", format_age(self.now.saturating_sub(start as u64)))
+                }
+                None => String::new(),
+            };
+            results.push(VerificationResult {
+                message: format!(
+                    "AWS reports an open {} event in {}{}: {}",
+                    event.service, location, since, event.event_type_code
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("HEALTH-001"),
+                resource_type: Some(ResourceType::HealthEvent),
+                resource_id: Some(event.arn.clone()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for HealthEventChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = self.verify_no_open_events();
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "AWS reports no open EC2, ELB or Route53 events for the cluster"
+                    .to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_health_events() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let event = |arn: &str, az: Option<&str>| HealthEvent {
+            arn: arn.to_string(),
+            service: "EC2".to_string(),
+            event_type_code: "AWS_EC2_OPERATIONAL_ISSUE".to_string(),
+            region: Some("us-east-1".to_string()),
+            availability_zone: az.map(|az| az.to_string()),
+            start_time: Some(1000.0),
+        };
+        let hec = HealthEventChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .availability_zone("us-east-1a")
+                .build()])
+            .events(vec![
+                event("event-1", Some("us-east-1a")),
+                event("event-2", Some("us-east-1b")),
+                event("event-3", None),
+            ])
+            .now(1000 + 2 * 3600 + 5 * 60)
+            .build()
+            .unwrap();
+        assert_eq!(
+            hec.verify(),
+            vec![
+                VerificationResult {
+                    message: "AWS reports an open EC2 event in us-east-1a for 2h 5m: AWS_EC2_OPERATIONAL_ISSUE".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("HEALTH-001"),
+                    resource_type: Some(ResourceType::HealthEvent),
+                    resource_id: Some("event-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "AWS reports an open EC2 event in us-east-1 for 2h 5m: AWS_EC2_OPERATIONAL_ISSUE".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("HEALTH-001"),
+                    resource_type: Some(ResourceType::HealthEvent),
+                    resource_id: Some("event-3".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
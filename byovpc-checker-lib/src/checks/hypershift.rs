@@ -0,0 +1,318 @@
+//! This checker verifies the resources hosted control planes (Hypershift)
+//! need in the customer account - the control plane itself runs in the
+//! management account, so the generic checks can not see it.
+//! It can check the following conditions right now:
+//!
+//! - Every hosted service has an NLB tagged with its
+//!   `kubernetes.io/service-name`.
+//! - The VPC endpoint of the private link to the control plane is available,
+//!   i.e. its connection was accepted by the endpoint service.
+//! - The worker subnets carry the cluster tag of the hosted cluster.
+
+use aws_sdk_ec2::types::{State, Subnet, VpcEndpoint};
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancerTypeEnum;
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    gatherer::aws::shared_types::{
+        AWSLoadBalancer, CLUSTER_TAG_PREFIX, DEFAULT_ROUTER_TAG_HYPERSHIFT,
+        DEFAULT_ROUTER_VALUE_HYPERSHIFT,
+    },
+    types::{
+        ClusterType, FindingCode, MinimalClusterInfo, ResourceType, Severity, TagDiff,
+        VerificationResult, Verifier,
+    },
+};
+
+/// The services of the hosted cluster that are exposed through an NLB in the
+/// customer account, as their `kubernetes.io/service-name`.
+const HOSTED_SERVICES: [&str; 1] = [DEFAULT_ROUTER_VALUE_HYPERSHIFT];
+
+/// The prefix of the names of endpoint services offered by accounts (instead
+/// of AWS services) - the private link to the control plane is one of them.
+const ENDPOINT_SERVICE_PREFIX: &str = "com.amazonaws.vpce.";
+
+#[derive(Debug, Builder)]
+pub struct HypershiftChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    vpc_endpoints: Vec<VpcEndpoint>,
+}
+
+impl<'a> HypershiftChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    fn cluster_vpcs(&self) -> Vec<&str> {
+        self.cluster_subnets()
+            .iter()
+            .filter_map(|s| s.vpc_id())
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    /// The NLBs tagged with the service name.
+    fn service_load_balancers(&self, service: &str) -> Vec<&AWSLoadBalancer> {
+        self.load_balancers
+            .iter()
+            .filter(|lb| match lb {
+                AWSLoadBalancer::ModernLoadBalancer((m, _)) => {
+                    m.r#type() == Some(&LoadBalancerTypeEnum::Network)
+                }
+                AWSLoadBalancer::ClassicLoadBalancer(_) => false,
+            })
+            .filter(|lb| {
+                lb.tags().iter().any(|t| {
+                    t.key.as_deref() == Some(DEFAULT_ROUTER_TAG_HYPERSHIFT)
+                        && t.value.as_deref() == Some(service)
+                })
+            })
+            .collect()
+    }
+
+    /// The interface endpoints of the cluster VPCs connecting to an endpoint
+    /// service of another account.
+    fn private_link_endpoints(&self, vpc_id: &str) -> Vec<&VpcEndpoint> {
+        self.vpc_endpoints
+            .iter()
+            .filter(|e| e.vpc_id() == Some(vpc_id))
+            .filter(|e| {
+                e.service_name()
+                    .is_some_and(|n| n.starts_with(ENDPOINT_SERVICE_PREFIX))
+            })
+            .collect()
+    }
+
+    pub fn verify_service_load_balancers(&self) -> Vec<VerificationResult> {
+        info!("Checking the NLBs of the hosted services exist");
+        HOSTED_SERVICES
+            .iter()
+            .filter(|service| self.service_load_balancers(service).is_empty())
+            .map(|service| VerificationResult {
+                message: format!(
+                    "No NLB is tagged with {}={} - the hosted service is not reachable",
+                    DEFAULT_ROUTER_TAG_HYPERSHIFT, service
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::HostedServiceLbMissing),
+                check_id: Some("HCP-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            })
+            .collect()
+    }
+
+    /// Only private clusters reach their control plane through a private
+    /// link.
+    pub fn verify_private_link(&self) -> Vec<VerificationResult> {
+        if !self.cluster_info.private {
+            return vec![];
+        }
+        info!("Checking the private link to the control plane");
+        let mut results = vec![];
+        for vpc_id in self.cluster_vpcs() {
+            let endpoints = self.private_link_endpoints(vpc_id);
+            if endpoints.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "VPC {} has no endpoint for the private link to the control plane",
+                        vpc_id
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::PrivateLinkEndpointMissing),
+                    check_id: Some("HCP-002"),
+                    resource_type: Some(ResourceType::Vpc),
+                    resource_id: Some(vpc_id.to_string()),
+                    evidence: None,
+                });
+            }
+            for e in endpoints {
+                if e.state() == Some(&State::Available) {
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "VPC endpoint {} for {} is in state {} - its connection was not accepted",
+                        e.vpc_endpoint_id().unwrap_or_default(),
+                        e.service_name().unwrap_or_default(),
+                        e.state().map_or("unknown", |s| s.as_str())
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::PrivateLinkNotAccepted),
+                    check_id: Some("HCP-002"),
+                    resource_type: Some(ResourceType::VpcEndpoint),
+                    resource_id: Some(e.vpc_endpoint_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_worker_subnet_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking the worker subnets carry the hosted cluster tag");
+        let cluster_tag = format!(
+            "{}{}",
+            CLUSTER_TAG_PREFIX, self.cluster_info.cluster_infra_name
+        );
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let tagged = subnet.tags().iter().any(|t| {
+                t.key() == Some(cluster_tag.as_str())
+                    && matches!(t.value(), Some("owned") | Some("shared"))
+            });
+            if tagged {
+                continue;
+            }
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let diff = TagDiff::new(
+                subnet_id,
+                subnet
+                    .tags()
+                    .iter()
+                    .filter_map(|t| Some((t.key()?, t.value().unwrap_or_default()))),
+            );
+            results.push(VerificationResult {
+                message: format!(
+                    "Worker subnet {} is missing the hosted cluster tag: {}",
+                    subnet_id, cluster_tag
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::MissingClusterTag),
+                check_id: Some("HCP-003"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some(subnet_id.to_string()),
+                evidence: Some(diff.evidence(&cluster_tag, "shared")),
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for HypershiftChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self.cluster_info.cluster_type != ClusterType::Hypershift {
+            return vec![VerificationResult {
+                message: "Not a Hypershift cluster - skipping hosted control plane checks"
+                    .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = self.verify_service_load_balancers();
+        results.extend(self.verify_private_link());
+        results.extend(self.verify_worker_subnet_tags());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "The hosted control plane resources in the customer account are set up"
+                    .to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::Tag;
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_hypershift() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .cluster_type(ClusterType::Hypershift)
+            .subnets(vec!["subnet-1".to_string(), "subnet-2".to_string()])
+            .private(true)
+            .build()
+            .unwrap();
+        let hc = HypershiftChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                Subnet::builder()
+                    .subnet_id("subnet-1")
+                    .vpc_id("vpc-1")
+                    .tags(
+                        Tag::builder()
+                            .key("kubernetes.io/cluster/mycluster-abcde")
+                            .value("shared")
+                            .build(),
+                    )
+                    .build(),
+                Subnet::builder()
+                    .subnet_id("subnet-2")
+                    .vpc_id("vpc-1")
+                    .build(),
+            ])
+            .vpc_endpoints(vec![VpcEndpoint::builder()
+                .vpc_endpoint_id("vpce-1")
+                .vpc_id("vpc-1")
+                .service_name("com.amazonaws.vpce.us-east-1.vpce-svc-1")
+                .state(State::PendingAcceptance)
+                .build()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            hc.verify(),
+            vec![
+                VerificationResult {
+                    message: "No NLB is tagged with kubernetes.io/service-name=openshift-ingress/router-default - the hosted service is not reachable".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::HostedServiceLbMissing),
+                    check_id: Some("HCP-001"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "VPC endpoint vpce-1 for com.amazonaws.vpce.us-east-1.vpce-svc-1 is in state PendingAcceptance - its connection was not accepted".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::PrivateLinkNotAccepted),
+                    check_id: Some("HCP-002"),
+                    resource_type: Some(ResourceType::VpcEndpoint),
+                    resource_id: Some("vpce-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Worker subnet subnet-2 is missing the hosted cluster tag: kubernetes.io/cluster/mycluster-abcde".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::MissingClusterTag),
+                    check_id: Some("HCP-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-2".to_string()),
+                    evidence: Some(serde_json::json!({
+                        "resource": "subnet-2",
+                        "expected": {"kubernetes.io/cluster/mycluster-abcde": "shared"},
+                        "actual": {},
+                    })),
+                },
+            ]
+        )
+    }
+}
@@ -0,0 +1,308 @@
+//! This checker verifies the instance profiles of the cluster instances. The
+//! cloud provider and the CSI drivers on the nodes call the AWS APIs with the
+//! role of the instance profile - without the permissions load balancers and
+//! volumes can not be created.
+//! It can check the following conditions right now:
+//!
+//! - Every cluster instance has an instance profile.
+//! - The roles of the instance profiles allow the actions OpenShift documents
+//!   for control plane and worker nodes.
+
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::{debug, info};
+use serde_json::{json, Value};
+
+use crate::{
+    gatherer::aws::{
+        iam::{IamRole, InstanceProfile},
+        shared_types::AWSInstance,
+    },
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// The actions the control plane nodes need, from the instance profile policy
+/// of the OpenShift installer.
+pub const CONTROL_PLANE_ACTIONS: [&str; 16] = [
+    "ec2:DescribeInstances",
+    "ec2:DescribeRegions",
+    "ec2:AttachVolume",
+    "ec2:DetachVolume",
+    "ec2:CreateVolume",
+    "ec2:DeleteVolume",
+    "ec2:CreateTags",
+    "ec2:CreateSecurityGroup",
+    "ec2:AuthorizeSecurityGroupIngress",
+    "ec2:ModifyInstanceAttribute",
+    "elasticloadbalancing:CreateLoadBalancer",
+    "elasticloadbalancing:DescribeLoadBalancers",
+    "elasticloadbalancing:RegisterInstancesWithLoadBalancer",
+    "elasticloadbalancing:CreateTargetGroup",
+    "elasticloadbalancing:RegisterTargets",
+    "s3:GetObject",
+];
+
+/// The actions the worker nodes need.
+pub const WORKER_ACTIONS: [&str; 2] = ["ec2:DescribeInstances", "ec2:DescribeRegions"];
+
+#[derive(Debug, Builder)]
+pub struct IamChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+    #[builder(default = "vec![]")]
+    instance_profiles: Vec<InstanceProfile>,
+}
+
+/// Whether the pattern of a policy matches the action - patterns may contain
+/// `*` and `?` and are case insensitive.
+fn action_matches(pattern: &str, action: &str) -> bool {
+    fn matches(pattern: &[u8], action: &[u8]) -> bool {
+        match (pattern.first(), action.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                matches(&pattern[1..], action)
+                    || (!action.is_empty() && matches(pattern, &action[1..]))
+            }
+            (Some(b'?'), Some(_)) => matches(&pattern[1..], &action[1..]),
+            (Some(p), Some(a)) if p.eq_ignore_ascii_case(a) => matches(&pattern[1..], &action[1..]),
+            _ => false,
+        }
+    }
+    matches(pattern.as_bytes(), action.as_bytes())
+}
+
+/// The actions of the statements with the effect in the policy document.
+/// Statements using `NotAction` are ignored.
+fn statement_actions(document: &Value, effect: &str) -> Vec<String> {
+    let statements = match &document["Statement"] {
+        Value::Array(statements) => statements.iter().collect(),
+        statement => vec![statement],
+    };
+    statements
+        .into_iter()
+        .filter(|s| s["Effect"] == effect)
+        .flat_map(|s| match &s["Action"] {
+            Value::String(action) => vec![action.clone()],
+            Value::Array(actions) => actions
+                .iter()
+                .filter_map(|a| a.as_str().map(|a| a.to_string()))
+                .collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// The required actions the policies of the role do not allow, or deny.
+pub fn missing_actions<'r>(role: &IamRole, required: &[&'r str]) -> Vec<&'r str> {
+    let documents: Vec<Value> = role
+        .policies
+        .iter()
+        .filter_map(|p| p.document.as_ref())
+        .filter_map(|d| serde_json::from_str(d).ok())
+        .collect();
+    let allowed: Vec<String> = documents
+        .iter()
+        .flat_map(|d| statement_actions(d, "Allow"))
+        .collect();
+    let denied: Vec<String> = documents
+        .iter()
+        .flat_map(|d| statement_actions(d, "Deny"))
+        .collect();
+    required
+        .iter()
+        .filter(|action| {
+            !allowed.iter().any(|p| action_matches(p, action))
+                || denied.iter().any(|p| action_matches(p, action))
+        })
+        .copied()
+        .collect()
+}
+
+impl<'a> IamChecks<'a> {
+    pub fn verify_instance_profiles_assigned(&self) -> Vec<VerificationResult> {
+        info!("Checking instances have an instance profile");
+        self.instances
+            .iter()
+            .filter(|i| i.instance.iam_instance_profile().is_none())
+            .map(|i| VerificationResult {
+                message: format!(
+                    "Instance {} has no instance profile - the node can not call the AWS APIs to attach volumes or register with load balancers",
+                    i.instance.instance_id().unwrap_or_default()
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::InstanceProfileMissing),
+                check_id: Some("IAM-001"),
+                resource_type: Some(ResourceType::Instance),
+                resource_id: Some(i.instance.instance_id().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_role_policies(&self) -> Vec<VerificationResult> {
+        info!("Checking the policies of the instance profile roles");
+        let infra_name = &self.cluster_info.cluster_infra_name;
+        let mut results = vec![];
+        for (arn, instances) in self
+            .instances
+            .iter()
+            .filter_map(|i| {
+                let arn = i.instance.iam_instance_profile()?.arn()?;
+                Some((arn, i))
+            })
+            .into_group_map()
+            .into_iter()
+            .sorted_by_key(|(arn, _)| *arn)
+        {
+            let Some(profile) = self.instance_profiles.iter().find(|p| p.arn == arn) else {
+                debug!("Instance profile {} was not gathered", arn);
+                continue;
+            };
+            let (kind, required): (&str, &[&str]) =
+                if instances.iter().any(|i| i.is_control_plane(infra_name)) {
+                    ("control plane", &CONTROL_PLANE_ACTIONS)
+                } else {
+                    ("worker", &WORKER_ACTIONS)
+                };
+            for role in profile.roles.iter() {
+                let missing = missing_actions(role, required);
+                if missing.is_empty() {
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "Role {} of the {} instance profile {} does not allow {}",
+                        role.name,
+                        kind,
+                        arn,
+                        missing.join(", ")
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InstanceProfileMissingPermissions),
+                    check_id: Some("IAM-002"),
+                    resource_type: Some(ResourceType::InstanceProfile),
+                    resource_id: Some(arn.to_string()),
+                    evidence: Some(json!({
+                        "missingActions": missing,
+                        "policies": role.policies.iter().map(|p| p.arn.as_ref().unwrap_or(&p.name)).collect::<Vec<_>>(),
+                    })),
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for IamChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_instance_profiles_assigned());
+        results.extend(self.verify_role_policies());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "{} instance profiles allow the actions the nodes need",
+                    self.instance_profiles.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{IamInstanceProfile, Instance, Tag};
+
+    use crate::{gatherer::aws::iam::IamPolicy, types::MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_instance_profiles() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let instance = |id: &str, name: &str, profile: Option<&str>| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .tags(Tag::builder().key("Name").value(name).build())
+                .set_iam_instance_profile(
+                    profile.map(|arn| IamInstanceProfile::builder().arn(arn).build()),
+                )
+                .build(),
+            security_groups: vec![],
+        };
+        let profile = |arn: &str, document: Value| InstanceProfile {
+            arn: arn.to_string(),
+            roles: vec![IamRole {
+                name: format!("{}-role", arn),
+                policies: vec![IamPolicy {
+                    name: "policy".to_string(),
+                    arn: None,
+                    document: Some(document.to_string()),
+                }],
+            }],
+        };
+        let ic = IamChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .instances(vec![
+                instance("i-1", "mycluster-abcde-master-0", Some("arn:master")),
+                instance("i-2", "mycluster-abcde-worker-a", Some("arn:worker")),
+                instance("i-3", "mycluster-abcde-worker-b", None),
+            ])
+            .instance_profiles(vec![
+                profile(
+                    "arn:master",
+                    json!({"Statement": [
+                        {"Effect": "Allow", "Action": ["ec2:*", "elasticloadbalancing:*"], "Resource": "*"},
+                        {"Effect": "Allow", "Action": "S3:Get*", "Resource": "*"},
+                        {"Effect": "Deny", "Action": "ec2:DeleteVolume", "Resource": "*"},
+                    ]}),
+                ),
+                profile(
+                    "arn:worker",
+                    json!({"Statement": {"Effect": "Allow", "Action": "ec2:Describe*", "Resource": "*"}}),
+                ),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            ic.verify(),
+            vec![
+                VerificationResult {
+                    message: "Instance i-3 has no instance profile - the node can not call the AWS APIs to attach volumes or register with load balancers".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InstanceProfileMissing),
+                    check_id: Some("IAM-001"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some("i-3".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Role arn:master-role of the control plane instance profile arn:master does not allow ec2:DeleteVolume".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InstanceProfileMissingPermissions),
+                    check_id: Some("IAM-002"),
+                    resource_type: Some(ResourceType::InstanceProfile),
+                    resource_id: Some("arn:master".to_string()),
+                    evidence: Some(json!({
+                        "missingActions": ["ec2:DeleteVolume"],
+                        "policies": ["policy"],
+                    })),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,770 @@
+//! This checker verifies the instances of the cluster and their security
+//! groups.
+//! It can check the following conditions right now:
+//!
+//! - Instances tagged for the cluster exist.
+//! - Instances are running.
+//! - All security groups attached to an instance exist.
+//! - Running instances have a private DNS name of the form
+//!   `ip-a-b-c-d.<region>.compute.internal` (`ip-a-b-c-d.ec2.internal` in
+//!   us-east-1). Node names are derived from it, a custom domain name of the
+//!   DHCP options or resource based hostnames break kubelet node
+//!   registration.
+//! - The control plane instances of multi-AZ clusters are spread across 3
+//!   availability zones.
+//! - Instances run in the cluster VPC and in subnets of the cluster.
+//! - Security group rules for the traffic between nodes are scoped to the
+//!   machine CIDR or to the cluster security groups, and only the API and
+//!   ingress ports are open to 0.0.0.0/0.
+
+use aws_sdk_ec2::types::{InstanceStateName, IpPermission, SecurityGroup, Subnet};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    checks::egress::{cidr_contains, parse_cidr},
+    gatherer::aws::shared_types::AWSInstance,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+#[derive(Debug, Builder)]
+pub struct InstanceChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+}
+
+/// The number of availability zones the control plane of a multi-AZ cluster
+/// is spread across.
+const CONTROL_PLANE_ZONES: usize = 3;
+
+/// The TCP ports clients outside the VPC may reach: ingress, the API and
+/// node ports of load balancer services.
+const PUBLIC_PORTS: [(i32, i32); 4] = [(80, 80), (443, 443), (6443, 6443), (30000, 32767)];
+
+/// The ports only the nodes of the cluster talk to each other on: etcd, the
+/// host level services, kubelet and the control plane, the machine config
+/// server and the overlay networks (VXLAN, Geneve).
+const INTERNAL_PORTS: [(&str, i32, i32); 6] = [
+    ("tcp", 2379, 2380),
+    ("tcp", 9000, 9999),
+    ("tcp", 10250, 10259),
+    ("tcp", 22623, 22623),
+    ("udp", 4789, 4789),
+    ("udp", 6081, 6081),
+];
+
+/// The protocol of a rule without its number aliases, `-1` being all.
+fn rule_protocol(rule: &IpPermission) -> &str {
+    match rule.ip_protocol() {
+        Some("6") => "tcp",
+        Some("17") => "udp",
+        Some(protocol) => protocol,
+        None => "-1",
+    }
+}
+
+/// The port range of a rule - rules for all protocols have none.
+fn rule_ports(rule: &IpPermission) -> (i32, i32) {
+    match rule_protocol(rule) {
+        "-1" => (0, 65535),
+        _ => (
+            rule.from_port().unwrap_or(0),
+            rule.to_port().unwrap_or(65535),
+        ),
+    }
+}
+
+/// Whether the rule only opens one of the ports clients may reach.
+fn is_public_rule(rule: &IpPermission) -> bool {
+    let (from, to) = rule_ports(rule);
+    rule_protocol(rule) == "tcp"
+        && PUBLIC_PORTS
+            .iter()
+            .any(|(start, end)| *start <= from && to <= *end)
+}
+
+/// Whether the rule opens any of the ports only nodes talk to each other on.
+fn is_internal_rule(rule: &IpPermission) -> bool {
+    let protocol = rule_protocol(rule);
+    let (from, to) = rule_ports(rule);
+    INTERNAL_PORTS
+        .iter()
+        .any(|(p, start, end)| (protocol == "-1" || protocol == *p) && from <= *end && *start <= to)
+}
+
+fn rule_evidence(sg: &SecurityGroup, rule: &IpPermission, source: &str) -> serde_json::Value {
+    let (from, to) = rule_ports(rule);
+    json!({
+        "security_group": sg.group_id().unwrap_or_default(),
+        "protocol": rule_protocol(rule),
+        "from_port": from,
+        "to_port": to,
+        "source": source,
+    })
+}
+
+/// The domain of the private DNS names AWS assigns in the region.
+pub fn expected_dns_domain(region: &str) -> String {
+    match region {
+        "us-east-1" => "ec2.internal".to_string(),
+        region => format!("{}.compute.internal", region),
+    }
+}
+
+impl<'a> InstanceChecks<'a> {
+    pub fn verify_instances_found(&self) -> Vec<VerificationResult> {
+        info!("Checking instances exist");
+        if !self.instances.is_empty() {
+            return vec![];
+        }
+        vec![VerificationResult {
+            message: format!(
+                "No instances tagged for {} found",
+                self.cluster_info.cluster_infra_name
+            ),
+            severity: Severity::Warning,
+            code: None,
+            check_id: Some("INST-001"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }]
+    }
+
+    pub fn verify_instance_state(&self) -> Vec<VerificationResult> {
+        info!("Checking instance state");
+        self.instances
+            .iter()
+            .filter_map(|i| {
+                let state = i.instance.state().and_then(|s| s.name())?;
+                if *state == InstanceStateName::Running {
+                    return None;
+                }
+                Some(VerificationResult {
+                    message: format!(
+                        "Instance {} is in state: {}",
+                        i.instance.instance_id().unwrap_or_default(),
+                        state.as_str()
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("INST-002"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(i.instance.instance_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                })
+            })
+            .collect()
+    }
+
+    pub fn verify_instance_security_groups(&self) -> Vec<VerificationResult> {
+        info!("Checking instance security groups");
+        let mut results = vec![];
+        for i in self.instances.iter() {
+            let instance_id = i.instance.instance_id().unwrap_or_default();
+            if i.instance.security_groups().is_empty() {
+                results.push(VerificationResult {
+                    message: format!("Instance {} has no security groups attached", instance_id),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("INST-003"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(instance_id.to_string()),
+                    evidence: None,
+                });
+            }
+            for gi in i.instance.security_groups() {
+                let group_id = gi.group_id().unwrap_or_default();
+                if !i
+                    .security_groups
+                    .iter()
+                    .any(|sg| sg.group_id() == Some(group_id))
+                {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Instance {} references security group {} that could not be found",
+                            instance_id, group_id
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::InstanceSecurityGroupNotFound),
+                        check_id: Some("INST-003"),
+                        resource_type: Some(ResourceType::Instance),
+                        resource_id: Some(instance_id.to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_instance_hostnames(&self) -> Vec<VerificationResult> {
+        info!("Checking instance hostnames");
+        let mut results = vec![];
+        for i in self.instances.iter() {
+            if i.instance.state().and_then(|s| s.name()) != Some(&InstanceStateName::Running) {
+                continue;
+            }
+            let instance_id = i.instance.instance_id().unwrap_or_default();
+            let Some(dns_name) = i.instance.private_dns_name().filter(|n| !n.is_empty()) else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance {} has no private DNS name - enable DNS hostnames and DNS support on the VPC",
+                        instance_id
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::MissingPrivateDnsName),
+                    check_id: Some("INST-004"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(instance_id.to_string()),
+                    evidence: None,
+                });
+                continue;
+            };
+            // The region of the instance, e.g. us-east-1 for us-east-1a.
+            let region = self.cluster_info.region.clone().or_else(|| {
+                i.instance
+                    .placement()
+                    .and_then(|p| p.availability_zone())
+                    .map(|az| {
+                        az.trim_end_matches(|c: char| c.is_ascii_lowercase())
+                            .to_string()
+                    })
+            });
+            let (host, domain) = dns_name.split_once('.').unwrap_or((dns_name, ""));
+            if let Some(expected) = region.as_deref().map(expected_dns_domain) {
+                if domain != expected {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Instance {} has private DNS name {} instead of a name in {} - a custom domain name of the DHCP options breaks node registration",
+                            instance_id, dns_name, expected
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::CustomDhcpDomainName),
+                        check_id: Some("INST-004"),
+                        resource_type: Some(ResourceType::Instance),
+                        resource_id: Some(instance_id.to_string()),
+                        evidence: None,
+                    });
+                    continue;
+                }
+            }
+            if !host.starts_with("ip-") {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance {} uses the resource based hostname {} - the cluster expects IP based names (ip-a-b-c-d)",
+                        instance_id, dns_name
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("INST-004"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(instance_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    /// Verifies the control plane of a multi-AZ cluster survives the outage
+    /// of one availability zone.
+    pub fn verify_control_plane_zones(&self) -> Vec<VerificationResult> {
+        info!("Checking control plane availability zones");
+        if !self.cluster_info.multi_az {
+            return vec![];
+        }
+        let control_plane: Vec<&AWSInstance> = self
+            .instances
+            .iter()
+            .filter(|i| i.is_control_plane(&self.cluster_info.cluster_infra_name))
+            .filter(|i| {
+                i.instance.state().and_then(|s| s.name()) != Some(&InstanceStateName::Terminated)
+            })
+            .collect();
+        // Hosted control planes do not run on instances of the cluster.
+        if control_plane.is_empty() {
+            return vec![];
+        }
+        let zones: Vec<&str> = control_plane
+            .iter()
+            .filter_map(|i| i.instance.placement().and_then(|p| p.availability_zone()))
+            .unique()
+            .sorted()
+            .collect();
+        if zones.len() >= CONTROL_PLANE_ZONES {
+            return vec![];
+        }
+        vec![VerificationResult {
+            message: format!(
+                "The {} control plane instances of the multi-AZ cluster run in {} availability zones ({}) instead of {} - an outage of one zone can cost the etcd quorum",
+                control_plane.len(),
+                zones.len(),
+                zones.join(", "),
+                CONTROL_PLANE_ZONES
+            ),
+            severity: Severity::Warning,
+            code: Some(FindingCode::ControlPlaneNotSpread),
+            check_id: Some("INST-005"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(json!({"zones": zones})),
+        }]
+    }
+
+    /// Verifies the instances run in the VPC and the subnets of the cluster.
+    pub fn verify_instance_subnets(&self) -> Vec<VerificationResult> {
+        info!("Checking instance subnets");
+        if self.subnets.is_empty() {
+            return vec![];
+        }
+        let vpcs: Vec<&str> = self.subnets.iter().filter_map(|s| s.vpc_id()).collect();
+        let mut results = vec![];
+        for i in self.instances.iter() {
+            if i.instance.state().and_then(|s| s.name()) == Some(&InstanceStateName::Terminated) {
+                continue;
+            }
+            let instance_id = i.instance.instance_id().unwrap_or_default();
+            let (Some(vpc_id), Some(subnet_id)) = (i.instance.vpc_id(), i.instance.subnet_id())
+            else {
+                continue;
+            };
+            if !vpcs.contains(&vpc_id) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance {} runs in VPC {}, not in the VPC of the cluster subnets",
+                        instance_id, vpc_id
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InstanceOutsideClusterVpc),
+                    check_id: Some("INST-006"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(instance_id.to_string()),
+                    evidence: Some(json!({"instance": instance_id, "vpc": vpc_id})),
+                });
+            } else if !self
+                .subnets
+                .iter()
+                .any(|s| s.subnet_id() == Some(subnet_id))
+            {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance {} runs in subnet {}, which is not a subnet of the cluster",
+                        instance_id, subnet_id
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::InstanceOutsideClusterSubnets),
+                    check_id: Some("INST-006"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some(instance_id.to_string()),
+                    evidence: Some(json!({"instance": instance_id, "subnet": subnet_id})),
+                });
+            }
+        }
+        results
+    }
+
+    /// The security groups attached to the instances of the cluster.
+    fn cluster_security_groups(&self) -> Vec<&SecurityGroup> {
+        self.instances
+            .iter()
+            .flat_map(|i| i.security_groups.iter())
+            .unique_by(|sg| sg.group_id())
+            .sorted_by_key(|sg| sg.group_id())
+            .collect()
+    }
+
+    /// Verifies the ingress rules of the cluster security groups do not open
+    /// the node ports to more (or fewer) sources than the nodes.
+    pub fn verify_security_group_rule_sources(&self) -> Vec<VerificationResult> {
+        info!("Checking the sources of security group rules");
+        let machine_cidr = self
+            .cluster_info
+            .machine_cidr
+            .as_deref()
+            .and_then(|cidr| parse_cidr(cidr).map(|parsed| (cidr, parsed)));
+        let security_groups = self.cluster_security_groups();
+        let mut results = vec![];
+        for sg in security_groups.iter() {
+            let group_id = sg.group_id().unwrap_or_default();
+            for rule in sg.ip_permissions() {
+                let (from, to) = rule_ports(rule);
+                let ports = format!("{} {}-{}", rule_protocol(rule), from, to);
+                for range in rule.ip_ranges() {
+                    let cidr = range.cidr_ip().unwrap_or_default();
+                    if cidr == "0.0.0.0/0" {
+                        if !is_public_rule(rule) {
+                            results.push(VerificationResult {
+                                message: format!(
+                                    "Security group {} allows {} from 0.0.0.0/0 - only the API and ingress ports should be open to the internet",
+                                    group_id, ports
+                                ),
+                                severity: Severity::Warning,
+                                code: Some(FindingCode::SecurityGroupOpenToInternet),
+                                check_id: Some("INST-007"),
+                                resource_type: Some(ResourceType::SecurityGroup),
+                                resource_id: Some(group_id.to_string()),
+                                evidence: Some(rule_evidence(sg, rule, cidr)),
+                            });
+                        }
+                        continue;
+                    }
+                    let (Some((machine_cidr, machine)), Some(source)) =
+                        (machine_cidr, parse_cidr(cidr))
+                    else {
+                        continue;
+                    };
+                    if !is_internal_rule(rule) || source == machine {
+                        continue;
+                    }
+                    if cidr_contains(machine, source) {
+                        results.push(VerificationResult {
+                            message: format!(
+                                "Security group {} allows {} only from {} instead of the machine CIDR {} - nodes outside of it can not reach each other",
+                                group_id, ports, cidr, machine_cidr
+                            ),
+                            severity: Severity::Warning,
+                            code: Some(FindingCode::SecurityGroupRuleNarrowerThanMachineCidr),
+                            check_id: Some("INST-007"),
+                            resource_type: Some(ResourceType::SecurityGroup),
+                            resource_id: Some(group_id.to_string()),
+                            evidence: Some(rule_evidence(sg, rule, cidr)),
+                        });
+                    } else {
+                        results.push(VerificationResult {
+                            message: format!(
+                                "Security group {} allows {} from {}, which is not within the machine CIDR {}",
+                                group_id, ports, cidr, machine_cidr
+                            ),
+                            severity: Severity::Info,
+                            code: Some(FindingCode::SecurityGroupRuleOutsideMachineCidr),
+                            check_id: Some("INST-007"),
+                            resource_type: Some(ResourceType::SecurityGroup),
+                            resource_id: Some(group_id.to_string()),
+                            evidence: Some(rule_evidence(sg, rule, cidr)),
+                        });
+                    }
+                }
+                if !is_internal_rule(rule) {
+                    continue;
+                }
+                for pair in rule.user_id_group_pairs() {
+                    let source = pair.group_id().unwrap_or_default();
+                    if security_groups
+                        .iter()
+                        .any(|sg| sg.group_id() == Some(source))
+                    {
+                        continue;
+                    }
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Security group {} allows {} from security group {}, which is not attached to the cluster instances",
+                            group_id, ports, source
+                        ),
+                        severity: Severity::Info,
+                        code: Some(FindingCode::SecurityGroupRuleOutsideMachineCidr),
+                        check_id: Some("INST-007"),
+                        resource_type: Some(ResourceType::SecurityGroup),
+                        resource_id: Some(group_id.to_string()),
+                        evidence: Some(rule_evidence(sg, rule, source)),
+                    });
+                }
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for InstanceChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_instances_found());
+        results.extend(self.verify_instance_state());
+        results.extend(self.verify_instance_security_groups());
+        results.extend(self.verify_instance_hostnames());
+        results.extend(self.verify_control_plane_zones());
+        results.extend(self.verify_instance_subnets());
+        results.extend(self.verify_security_group_rule_sources());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "{} instances, their placement and their security groups are valid",
+                    self.instances.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+/// Runs only the security group checks of the instance checks.
+pub struct SecurityGroupChecks<'a>(pub InstanceChecks<'a>);
+
+impl<'a> Verifier for SecurityGroupChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = self.0.verify_instance_security_groups();
+        results.extend(self.0.verify_security_group_rule_sources());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "Security groups of {} instances exist and allow the cluster traffic",
+                    self.0.instances.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{
+        GroupIdentifier, Instance, InstanceState, IpRange, Placement, Tag, UserIdGroupPair,
+    };
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_instance_security_groups() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let instance = AWSInstance {
+            instance: Instance::builder()
+                .instance_id("i-1")
+                .state(
+                    InstanceState::builder()
+                        .name(InstanceStateName::Running)
+                        .build(),
+                )
+                .security_groups(GroupIdentifier::builder().group_id("sg-1").build())
+                .security_groups(GroupIdentifier::builder().group_id("sg-2").build())
+                .private_dns_name("ip-10-0-0-1.ec2.internal")
+                .build(),
+            security_groups: vec![SecurityGroup::builder().group_id("sg-1").build()],
+        };
+        let ic = InstanceChecksBuilder::default()
+            .cluster_info(&mci)
+            .instances(vec![instance])
+            .build()
+            .unwrap();
+        assert_eq!(
+            ic.verify(),
+            vec![VerificationResult {
+                message: "Instance i-1 references security group sg-2 that could not be found"
+                    .to_string(),
+                severity: Severity::Warning,
+                code: Some(FindingCode::InstanceSecurityGroupNotFound),
+                check_id: Some("INST-003"),
+                resource_type: Some(ResourceType::Instance),
+                resource_id: Some("i-1".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+
+    #[test]
+    fn test_verify_instance_hostnames() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .region(Some("eu-west-1".to_string()))
+            .build()
+            .unwrap();
+        let instance = |id: &str, dns_name: &str| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .state(
+                    InstanceState::builder()
+                        .name(InstanceStateName::Running)
+                        .build(),
+                )
+                .private_dns_name(dns_name)
+                .build(),
+            security_groups: vec![],
+        };
+        let ic = InstanceChecksBuilder::default()
+            .cluster_info(&mci)
+            .instances(vec![
+                instance("i-1", "ip-10-0-0-1.eu-west-1.compute.internal"),
+                instance("i-2", ""),
+                instance("i-3", "ip-10-0-0-3.corp.example.com"),
+                instance("i-4", "i-4.eu-west-1.compute.internal"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            ic.verify_instance_hostnames(),
+            vec![
+                VerificationResult {
+                    message: "Instance i-2 has no private DNS name - enable DNS hostnames and DNS support on the VPC".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::MissingPrivateDnsName),
+                    check_id: Some("INST-004"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some("i-2".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Instance i-3 has private DNS name ip-10-0-0-3.corp.example.com instead of a name in eu-west-1.compute.internal - a custom domain name of the DHCP options breaks node registration".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::CustomDhcpDomainName),
+                    check_id: Some("INST-004"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some("i-3".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Instance i-4 uses the resource based hostname i-4.eu-west-1.compute.internal - the cluster expects IP based names (ip-a-b-c-d)".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("INST-004"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some("i-4".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_verify_instance_placement() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("infra-1".to_string())
+            .multi_az(true)
+            .build()
+            .unwrap();
+        let instance = |id: &str, name: &str, zone: &str, subnet: &str, vpc: &str| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .tags(Tag::builder().key("Name").value(name).build())
+                .placement(Placement::builder().availability_zone(zone).build())
+                .subnet_id(subnet)
+                .vpc_id(vpc)
+                .build(),
+            security_groups: vec![],
+        };
+        let ic = InstanceChecksBuilder::default()
+            .cluster_info(&mci)
+            .instances(vec![
+                instance("i-1", "infra-1-master-0", "eu-west-1a", "subnet-1", "vpc-1"),
+                instance("i-2", "infra-1-master-1", "eu-west-1b", "subnet-1", "vpc-1"),
+                instance("i-3", "infra-1-master-2", "eu-west-1b", "subnet-2", "vpc-1"),
+                instance("i-4", "infra-1-worker-0", "eu-west-1a", "subnet-3", "vpc-2"),
+            ])
+            .subnets(vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .vpc_id("vpc-1")
+                .build()])
+            .build()
+            .unwrap();
+        let results = ic.verify_control_plane_zones();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].code, Some(FindingCode::ControlPlaneNotSpread));
+        assert_eq!(
+            ic.verify_instance_subnets()
+                .iter()
+                .map(|r| r.code)
+                .collect::<Vec<_>>(),
+            vec![
+                Some(FindingCode::InstanceOutsideClusterSubnets),
+                Some(FindingCode::InstanceOutsideClusterVpc)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_security_group_rule_sources() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_cidr(Some("10.0.0.0/16".to_string()))
+            .build()
+            .unwrap();
+        let rule = |protocol: &str, from: i32, to: i32| {
+            IpPermission::builder()
+                .ip_protocol(protocol)
+                .from_port(from)
+                .to_port(to)
+        };
+        let sg = SecurityGroup::builder()
+            .group_id("sg-1")
+            .ip_permissions(
+                rule("tcp", 6443, 6443)
+                    .ip_ranges(IpRange::builder().cidr_ip("0.0.0.0/0").build())
+                    .build(),
+            )
+            .ip_permissions(
+                rule("tcp", 22, 22)
+                    .ip_ranges(IpRange::builder().cidr_ip("0.0.0.0/0").build())
+                    .build(),
+            )
+            .ip_permissions(
+                rule("tcp", 10250, 10259)
+                    .ip_ranges(IpRange::builder().cidr_ip("10.0.0.0/16").build())
+                    .ip_ranges(IpRange::builder().cidr_ip("10.0.1.0/24").build())
+                    .ip_ranges(IpRange::builder().cidr_ip("192.168.0.0/16").build())
+                    .build(),
+            )
+            .ip_permissions(
+                rule("udp", 4789, 4789)
+                    .user_id_group_pairs(UserIdGroupPair::builder().group_id("sg-1").build())
+                    .user_id_group_pairs(UserIdGroupPair::builder().group_id("sg-9").build())
+                    .build(),
+            )
+            .build();
+        let ic = InstanceChecksBuilder::default()
+            .cluster_info(&mci)
+            .instances(vec![AWSInstance {
+                instance: Instance::builder().instance_id("i-1").build(),
+                security_groups: vec![sg],
+            }])
+            .build()
+            .unwrap();
+        let results = ic.verify_security_group_rule_sources();
+        assert_eq!(
+            results.iter().map(|r| r.code).collect::<Vec<_>>(),
+            vec![
+                Some(FindingCode::SecurityGroupOpenToInternet),
+                Some(FindingCode::SecurityGroupRuleNarrowerThanMachineCidr),
+                Some(FindingCode::SecurityGroupRuleOutsideMachineCidr),
+                Some(FindingCode::SecurityGroupRuleOutsideMachineCidr),
+            ]
+        );
+        assert_eq!(
+            results[1].message,
+            "Security group sg-1 allows tcp 10250-10259 only from 10.0.1.0/24 instead of the machine CIDR 10.0.0.0/16 - nodes outside of it can not reach each other"
+        );
+        assert_eq!(
+            results[3].evidence,
+            Some(json!({
+                "security_group": "sg-1",
+                "protocol": "udp",
+                "from_port": 4789,
+                "to_port": 4789,
+                "source": "sg-9",
+            }))
+        );
+    }
+}
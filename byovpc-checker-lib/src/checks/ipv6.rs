@@ -0,0 +1,311 @@
+//! This checker verifies the subnets of dual-stack clusters are ready for
+//! IPv6. A cluster is dual-stack if any of its subnets has an IPv6 CIDR block
+//! associated - IPv4-only clusters are not checked.
+//! It can check the following conditions right now:
+//!
+//! - Every cluster subnet has an IPv6 CIDR block.
+//! - Every cluster subnet has a `::/0` route that is not a blackhole.
+//! - `::/0` routes through egress-only internet gateways point to gateways
+//!   attached to the VPC of the subnet.
+
+use aws_sdk_ec2::types::{
+    AttachmentStatus, EgressOnlyInternetGateway, RouteState, RouteTable, Subnet,
+    SubnetCidrBlockStateCode,
+};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    checks::network::{route_target, subnet_routetable_mapping, DEFAULT_IPV6_ROUTE},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+#[derive(Debug, Builder)]
+pub struct Ipv6Checks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    egress_only_internet_gateways: Vec<EgressOnlyInternetGateway>,
+}
+
+/// The IPv6 CIDR blocks associated with the subnet.
+fn ipv6_cidr_blocks(subnet: &Subnet) -> Vec<&str> {
+    subnet
+        .ipv6_cidr_block_association_set()
+        .iter()
+        .filter(|a| {
+            a.ipv6_cidr_block_state().and_then(|s| s.state())
+                == Some(&SubnetCidrBlockStateCode::Associated)
+        })
+        .filter_map(|a| a.ipv6_cidr_block())
+        .collect()
+}
+
+impl<'a> Ipv6Checks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .sorted_by_key(|s| s.subnet_id())
+            .collect()
+    }
+
+    /// Whether any cluster subnet has an IPv6 CIDR block.
+    pub fn is_dual_stack(&self) -> bool {
+        self.cluster_subnets()
+            .iter()
+            .any(|s| !ipv6_cidr_blocks(s).is_empty())
+    }
+
+    /// Whether the egress-only internet gateway is attached to the VPC.
+    fn egress_only_gateway_attached(&self, id: &str, vpc_id: Option<&str>) -> bool {
+        self.egress_only_internet_gateways.iter().any(|g| {
+            g.egress_only_internet_gateway_id() == Some(id)
+                && g.attachments()
+                    .iter()
+                    .any(|a| a.vpc_id() == vpc_id && a.state() == Some(&AttachmentStatus::Attached))
+        })
+    }
+
+    pub fn verify_ipv6_cidr_blocks(&self) -> Vec<VerificationResult> {
+        info!("Checking IPv6 CIDR blocks of the cluster subnets");
+        self.cluster_subnets()
+            .into_iter()
+            .filter(|s| ipv6_cidr_blocks(s).is_empty())
+            .map(|s| VerificationResult {
+                message: format!(
+                    "Subnet {} has no IPv6 CIDR block - nodes in it get no IPv6 address in the dual-stack cluster",
+                    s.subnet_id().unwrap_or_default()
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::SubnetMissingIpv6Cidr),
+                check_id: Some("IPV6-001"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some(s.subnet_id().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_ipv6_default_routes(&self) -> Vec<VerificationResult> {
+        info!("Checking IPv6 default routes of the cluster subnets");
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let Some(rtb) = mapping.get(subnet_id) else {
+                continue;
+            };
+            let default_route = rtb.routes().iter().find(|r| {
+                r.destination_ipv6_cidr_block() == Some(DEFAULT_IPV6_ROUTE)
+                    && r.state() != Some(&RouteState::Blackhole)
+            });
+            let Some(route) = default_route else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has no {} route - nodes can not reach IPv6 destinations outside the VPC",
+                        subnet_id, DEFAULT_IPV6_ROUTE
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::MissingIpv6DefaultRoute),
+                    check_id: Some("IPV6-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": rtb.route_table_id(),
+                    })),
+                });
+                continue;
+            };
+            let Some(gateway) = route.egress_only_internet_gateway_id() else {
+                continue;
+            };
+            if !self.egress_only_gateway_attached(gateway, subnet.vpc_id()) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} routes {} through egress-only internet gateway {} that is not attached to VPC {}",
+                        subnet_id,
+                        DEFAULT_IPV6_ROUTE,
+                        gateway,
+                        subnet.vpc_id().unwrap_or_default()
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::EgressOnlyIgwMissing),
+                    check_id: Some("IPV6-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": rtb.route_table_id(),
+                        "target": route_target(route),
+                    })),
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for Ipv6Checks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if !self.is_dual_stack() {
+            return vec![VerificationResult {
+                message: "Cluster subnets have no IPv6 CIDR blocks - the cluster is IPv4 only"
+                    .to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        results.extend(self.verify_ipv6_cidr_blocks());
+        results.extend(self.verify_ipv6_default_routes());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "Cluster subnets have IPv6 CIDR blocks and IPv6 default routes"
+                    .to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{
+        InternetGatewayAttachment, Route, RouteTableAssociation, SubnetCidrBlockState,
+        SubnetIpv6CidrBlockAssociation,
+    };
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_dual_stack_subnets() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let subnet = |id: &str, ipv6: Option<&str>| {
+            Subnet::builder()
+                .subnet_id(id)
+                .vpc_id("vpc-1")
+                .set_ipv6_cidr_block_association_set(ipv6.map(|cidr| {
+                    vec![SubnetIpv6CidrBlockAssociation::builder()
+                        .ipv6_cidr_block(cidr)
+                        .ipv6_cidr_block_state(
+                            SubnetCidrBlockState::builder()
+                                .state(SubnetCidrBlockStateCode::Associated)
+                                .build(),
+                        )
+                        .build()]
+                }))
+                .build()
+        };
+        let routetable = |subnet_id: &str, route: Route| {
+            RouteTable::builder()
+                .route_table_id(format!("rtb-{}", subnet_id))
+                .associations(
+                    RouteTableAssociation::builder()
+                        .subnet_id(subnet_id)
+                        .build(),
+                )
+                .routes(route)
+                .build()
+        };
+        let ic = Ipv6ChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-1", Some("2600:1f18::/64")),
+                subnet("subnet-2", Some("2600:1f18:0:1::/64")),
+                subnet("subnet-3", None),
+            ])
+            .routetables(vec![
+                routetable(
+                    "subnet-1",
+                    Route::builder()
+                        .destination_ipv6_cidr_block("::/0")
+                        .egress_only_internet_gateway_id("eigw-1")
+                        .build(),
+                ),
+                routetable(
+                    "subnet-2",
+                    Route::builder()
+                        .destination_ipv6_cidr_block("::/0")
+                        .egress_only_internet_gateway_id("eigw-2")
+                        .build(),
+                ),
+                routetable(
+                    "subnet-3",
+                    Route::builder()
+                        .destination_cidr_block("0.0.0.0/0")
+                        .nat_gateway_id("nat-1")
+                        .build(),
+                ),
+            ])
+            .egress_only_internet_gateways(vec![EgressOnlyInternetGateway::builder()
+                .egress_only_internet_gateway_id("eigw-1")
+                .attachments(
+                    InternetGatewayAttachment::builder()
+                        .vpc_id("vpc-1")
+                        .state(AttachmentStatus::Attached)
+                        .build(),
+                )
+                .build()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            ic.verify(),
+            vec![
+                VerificationResult {
+                    message: "Subnet subnet-3 has no IPv6 CIDR block - nodes in it get no IPv6 address in the dual-stack cluster".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::SubnetMissingIpv6Cidr),
+                    check_id: Some("IPV6-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-3".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-2 routes ::/0 through egress-only internet gateway eigw-2 that is not attached to VPC vpc-1".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::EgressOnlyIgwMissing),
+                    check_id: Some("IPV6-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-2".to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": "rtb-subnet-2",
+                        "target": "eigw-2",
+                    })),
+                },
+                VerificationResult {
+                    message: "Subnet subnet-3 has no ::/0 route - nodes can not reach IPv6 destinations outside the VPC".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::MissingIpv6DefaultRoute),
+                    check_id: Some("IPV6-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-3".to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": "rtb-subnet-3",
+                    })),
+                },
+            ]
+        );
+    }
+}
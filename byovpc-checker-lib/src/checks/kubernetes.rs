@@ -0,0 +1,425 @@
+//! This checker cross-checks the objects of the cluster read from its
+//! Kubernetes API with the resources found in AWS, to catch drift neither side
+//! shows on its own.
+//! It can check the following conditions right now:
+//!
+//! - Every Service of type `LoadBalancer` has a load balancer that exists in
+//!   AWS.
+//! - Every node has an instance in AWS and every running cluster instance is
+//!   a node.
+//! - The wildcard record of the ingress domain points to the load balancer of
+//!   the default router Service.
+
+use aws_sdk_ec2::types::InstanceStateName;
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::{
+        aws::shared_types::{AWSInstance, AWSLoadBalancer, HostedZoneWithRecords},
+        k8s::{KubernetesData, LoadBalancerService},
+    },
+    types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// The namespace and name of the Service of the default router.
+const ROUTER_SERVICE: (&str, &str) = ("openshift-ingress", "router-default");
+
+/// Whether the DNS name refers to the load balancer - records may use the
+/// `dualstack.` name and a trailing dot.
+fn refers_to(dns_name: &str, load_balancer_dns_name: &str) -> bool {
+    let normalize = |n: &str| {
+        n.trim_end_matches('.')
+            .trim_start_matches("dualstack.")
+            .to_lowercase()
+    };
+    !load_balancer_dns_name.is_empty() && normalize(dns_name) == normalize(load_balancer_dns_name)
+}
+
+#[derive(Debug, Builder)]
+pub struct KubernetesChecks<'a> {
+    /// The objects read from the Kubernetes API - `None` without a
+    /// kubeconfig.
+    #[builder(default = "None")]
+    kubernetes: Option<&'a KubernetesData>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+    #[builder(default = "vec![]")]
+    hosted_zones: Vec<HostedZoneWithRecords>,
+}
+
+impl<'a> KubernetesChecks<'a> {
+    fn service_name(service: &LoadBalancerService) -> String {
+        format!("{}/{}", service.namespace, service.name)
+    }
+
+    /// Verifies the load balancers of the Services exist in AWS - a Service
+    /// whose load balancer was deleted in AWS is not recreated.
+    pub fn verify_service_load_balancers(
+        &self,
+        kubernetes: &KubernetesData,
+    ) -> Vec<VerificationResult> {
+        info!("Checking load balancers of LoadBalancer Services exist");
+        let mut results = vec![];
+        for service in kubernetes.services.iter() {
+            if service.hostnames.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Service {} has no load balancer in its status",
+                        Self::service_name(service)
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("K8S-001"),
+                    resource_type: Some(ResourceType::KubernetesService),
+                    resource_id: Some(Self::service_name(service)),
+                    evidence: None,
+                });
+            }
+            for hostname in service.hostnames.iter() {
+                if self
+                    .load_balancers
+                    .iter()
+                    .any(|lb| refers_to(hostname, lb.dns_name().unwrap_or_default()))
+                {
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "Service {} uses load balancer {} which does not exist in AWS",
+                        Self::service_name(service),
+                        hostname
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::ServiceLbMissing),
+                    check_id: Some("K8S-001"),
+                    resource_type: Some(ResourceType::KubernetesService),
+                    resource_id: Some(Self::service_name(service)),
+                    evidence: Some(json!({
+                        "service": Self::service_name(service),
+                        "hostname": hostname,
+                    })),
+                });
+            }
+        }
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "The load balancers of all {} LoadBalancer Services exist",
+                    kubernetes.services.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("K8S-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+
+    /// Verifies the nodes and the instances of the cluster match: every node
+    /// has an instance and every running instance joined the cluster.
+    pub fn verify_node_instances(&self, kubernetes: &KubernetesData) -> Vec<VerificationResult> {
+        info!("Checking nodes match the instances of the cluster");
+        let mut results = vec![];
+        for node in kubernetes.nodes.iter() {
+            let Some(ref instance_id) = node.instance_id else {
+                results.push(VerificationResult {
+                    message: format!("Node {} has no AWS provider ID", node.name),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("K8S-002"),
+                    resource_type: Some(ResourceType::KubernetesNode),
+                    resource_id: Some(node.name.clone()),
+                    evidence: None,
+                });
+                continue;
+            };
+            if !self
+                .instances
+                .iter()
+                .any(|i| i.instance.instance_id() == Some(instance_id))
+            {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Node {} runs on instance {} which does not exist in AWS",
+                        node.name, instance_id
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::NodeInstanceMissing),
+                    check_id: Some("K8S-002"),
+                    resource_type: Some(ResourceType::KubernetesNode),
+                    resource_id: Some(node.name.clone()),
+                    evidence: Some(json!({"node": node.name, "instance": instance_id})),
+                });
+            }
+        }
+        for instance in self.instances.iter().filter(|i| {
+            i.instance.state().and_then(|s| s.name()) == Some(&InstanceStateName::Running)
+        }) {
+            let instance_id = instance.instance.instance_id().unwrap_or_default();
+            if kubernetes
+                .nodes
+                .iter()
+                .any(|n| n.instance_id.as_deref() == Some(instance_id))
+            {
+                continue;
+            }
+            results.push(VerificationResult {
+                message: format!(
+                    "Instance {} is running but is not a node of the cluster",
+                    instance_id
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::InstanceWithoutNode),
+                check_id: Some("K8S-002"),
+                resource_type: Some(ResourceType::Instance),
+                resource_id: Some(instance_id.to_string()),
+                evidence: Some(json!({"instance": instance_id})),
+            });
+        }
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "All {} nodes run on instances of the cluster",
+                    kubernetes.nodes.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("K8S-002"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+
+    /// Verifies the wildcard record of the ingress domain points to the load
+    /// balancer of the default router.
+    pub fn verify_ingress_domain_record(&self, kubernetes: &KubernetesData) -> VerificationResult {
+        info!("Checking the ingress domain points to the default router");
+        let result = |message: String, severity, code| VerificationResult {
+            message,
+            severity,
+            code,
+            check_id: Some("K8S-003"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let Some(ref domain) = kubernetes.ingress_domain else {
+            return result(
+                "The cluster ingress config has no domain".to_string(),
+                Severity::Info,
+                None,
+            );
+        };
+        let Some(router) = kubernetes
+            .services
+            .iter()
+            .find(|s| (s.namespace.as_str(), s.name.as_str()) == ROUTER_SERVICE)
+        else {
+            return result(
+                format!(
+                    "No LoadBalancer Service {}/{} found for ingress domain {}",
+                    ROUTER_SERVICE.0, ROUTER_SERVICE.1, domain
+                ),
+                Severity::Warning,
+                None,
+            );
+        };
+        let wildcard = format!("*.{}", domain.trim_end_matches('.'));
+        let Some(record) = self
+            .hosted_zones
+            .iter()
+            .flat_map(|hz| hz.resource_records.iter())
+            .find(|r| r.name.trim_end_matches('.').replace("\\052", "*") == wildcard)
+        else {
+            return result(
+                format!("No record found for ingress domain {}", wildcard),
+                Severity::Warning,
+                None,
+            );
+        };
+        let targets: Vec<&str> = match record.alias_target {
+            Some(ref alias_target) => vec![alias_target.dns_name.as_str()],
+            None => record
+                .resource_records()
+                .iter()
+                .map(|r| r.value.as_str())
+                .collect(),
+        };
+        if targets
+            .iter()
+            .any(|t| router.hostnames.iter().any(|h| refers_to(t, h)))
+        {
+            return result(
+                format!(
+                    "Ingress domain {} points to the load balancer of the default router",
+                    wildcard
+                ),
+                Severity::Ok,
+                None,
+            );
+        }
+        VerificationResult {
+            message: format!(
+                "Ingress domain {} points to {} instead of the load balancer of the default router {}",
+                wildcard,
+                targets.join(", "),
+                router.hostnames.join(", ")
+            ),
+            severity: Severity::Critical,
+            code: Some(FindingCode::IngressRecordNotRouter),
+            check_id: Some("K8S-003"),
+            resource_type: Some(ResourceType::ResourceRecord),
+            resource_id: Some(record.name.clone()),
+            evidence: Some(json!({
+                "record": record.name,
+                "targets": targets,
+                "router": router.hostnames,
+            })),
+        }
+    }
+}
+
+impl<'a> Verifier for KubernetesChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let Some(kubernetes) = self.kubernetes else {
+            return vec![VerificationResult {
+                message: "The Kubernetes API was not read - pass a kubeconfig with --kubeconfig"
+                    .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        };
+        let mut results = vec![];
+        results.extend(self.verify_service_load_balancers(kubernetes));
+        results.extend(self.verify_node_instances(kubernetes));
+        results.push(self.verify_ingress_domain_record(kubernetes));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Instance, InstanceState};
+    use aws_sdk_route53::types::{AliasTarget, HostedZone, ResourceRecordSet, RrType};
+
+    use super::*;
+    use crate::gatherer::k8s::Node;
+
+    #[test]
+    fn test_verify_kubernetes_drift() {
+        let kubernetes = KubernetesData {
+            services: vec![
+                LoadBalancerService {
+                    namespace: "openshift-ingress".to_string(),
+                    name: "router-default".to_string(),
+                    hostnames: vec!["router-1.elb.us-east-1.amazonaws.com".to_string()],
+                },
+                LoadBalancerService {
+                    namespace: "app".to_string(),
+                    name: "gone".to_string(),
+                    hostnames: vec!["gone-1.elb.us-east-1.amazonaws.com".to_string()],
+                },
+            ],
+            nodes: vec![Node {
+                name: "ip-10-0-1-2.ec2.internal".to_string(),
+                instance_id: Some("i-1".to_string()),
+            }],
+            ingress_domain: Some("apps.mycluster.example.com".to_string()),
+        };
+        let instance = |id: &str| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .state(
+                    InstanceState::builder()
+                        .name(InstanceStateName::Running)
+                        .build(),
+                )
+                .build(),
+            security_groups: vec![],
+        };
+        let kc = KubernetesChecksBuilder::default()
+            .kubernetes(Some(&kubernetes))
+            .load_balancers(vec![AWSLoadBalancer::ModernLoadBalancer((
+                aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                    .dns_name("router-1.elb.us-east-1.amazonaws.com")
+                    .build(),
+                vec![],
+            ))])
+            .instances(vec![instance("i-1"), instance("i-2")])
+            .hosted_zones(vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("Z1")
+                    .name("mycluster.example.com.")
+                    .caller_reference("Z1")
+                    .build()
+                    .unwrap(),
+                resource_records: vec![ResourceRecordSet::builder()
+                    .name("\\052.apps.mycluster.example.com.")
+                    .r#type(RrType::A)
+                    .alias_target(
+                        AliasTarget::builder()
+                            .hosted_zone_id("Z2")
+                            .dns_name("dualstack.router-1.elb.us-east-1.amazonaws.com.")
+                            .evaluate_target_health(false)
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap()],
+                vpcs: vec![],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }])
+            .build()
+            .unwrap();
+        assert_eq!(
+            kc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Service app/gone uses load balancer gone-1.elb.us-east-1.amazonaws.com which does not exist in AWS".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::ServiceLbMissing),
+                    check_id: Some("K8S-001"),
+                    resource_type: Some(ResourceType::KubernetesService),
+                    resource_id: Some("app/gone".to_string()),
+                    evidence: Some(json!({
+                        "service": "app/gone",
+                        "hostname": "gone-1.elb.us-east-1.amazonaws.com",
+                    })),
+                },
+                VerificationResult {
+                    message: "Instance i-2 is running but is not a node of the cluster".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::InstanceWithoutNode),
+                    check_id: Some("K8S-002"),
+                    resource_type: Some(ResourceType::Instance),
+                    resource_id: Some("i-2".to_string()),
+                    evidence: Some(json!({"instance": "i-2"})),
+                },
+                VerificationResult {
+                    message: "Ingress domain *.apps.mycluster.example.com points to the load balancer of the default router".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("K8S-003"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ]
+        );
+    }
+}
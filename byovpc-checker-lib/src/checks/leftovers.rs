@@ -0,0 +1,267 @@
+//! This checker reports resources that are still tagged for a deleted cluster.
+//! Leftovers block deleting the VPC and reusing its subnets for the next
+//! install. Every result contains the command to delete the resource, in the
+//! order the resources have to be deleted in.
+//! It can report the following resources right now:
+//!
+//! - LoadBalancers (modern and classic).
+//! - ENIs.
+//! - Security groups.
+//! - Elastic IPs.
+//! - Hosted zones and the records that have to be deleted before the zone.
+
+use aws_sdk_ec2::types::{Address, NetworkInterface, NetworkInterfaceStatus, SecurityGroup};
+use aws_sdk_route53::types::RrType;
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    gatherer::aws::shared_types::{AWSLoadBalancer, HostedZoneWithRecords},
+    types::{ResourceType, Severity, VerificationResult, Verifier},
+};
+
+#[derive(Debug, Builder)]
+pub struct LeftoverResourceChecks {
+    infra_id: String,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    network_interfaces: Vec<NetworkInterface>,
+    #[builder(default = "vec![]")]
+    security_groups: Vec<SecurityGroup>,
+    #[builder(default = "vec![]")]
+    elastic_ips: Vec<Address>,
+    #[builder(default = "vec![]")]
+    hosted_zones: Vec<HostedZoneWithRecords>,
+}
+
+impl LeftoverResourceChecks {
+    pub fn verify_load_balancers(&self) -> Vec<VerificationResult> {
+        info!("Checking for leftover load balancers");
+        self.load_balancers
+            .iter()
+            .map(|lb| {
+                let command = match lb {
+                    AWSLoadBalancer::ClassicLoadBalancer(_) => format!(
+                        "aws elb delete-load-balancer --load-balancer-name {}",
+                        lb.identifier()
+                    ),
+                    AWSLoadBalancer::ModernLoadBalancer(_) => format!(
+                        "aws elbv2 delete-load-balancer --load-balancer-arn {}",
+                        lb.identifier()
+                    ),
+                };
+                VerificationResult {
+                    message: format!("LoadBalancer {} is left over: `{}`", lb.name(), command),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-001"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some(lb.identifier().to_string()),
+                    evidence: None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn verify_network_interfaces(&self) -> Vec<VerificationResult> {
+        info!("Checking for leftover ENIs");
+        self.network_interfaces
+            .iter()
+            .map(|eni| {
+                let id = eni.network_interface_id().unwrap_or_default();
+                let attached = if eni.status() == Some(&NetworkInterfaceStatus::InUse) {
+                    " (still attached - delete the resource it is attached to first)"
+                } else {
+                    ""
+                };
+                VerificationResult {
+                    message: format!(
+                        "ENI {} is left over{}: `aws ec2 delete-network-interface --network-interface-id {}`",
+                        id, attached, id
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-002"),
+                    resource_type: Some(ResourceType::NetworkInterface),
+                    resource_id: Some(id.to_string()),
+                    evidence: None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn verify_security_groups(&self) -> Vec<VerificationResult> {
+        info!("Checking for leftover security groups");
+        self.security_groups
+            .iter()
+            .map(|sg| {
+                let id = sg.group_id().unwrap_or_default();
+                VerificationResult {
+                    message: format!(
+                        "Security group {} ({}) is left over: `aws ec2 delete-security-group --group-id {}`",
+                        id,
+                        sg.group_name().unwrap_or_default(),
+                        id
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-003"),
+                    resource_type: Some(ResourceType::SecurityGroup),
+                    resource_id: Some(id.to_string()),
+                    evidence: None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn verify_elastic_ips(&self) -> Vec<VerificationResult> {
+        info!("Checking for leftover elastic IPs");
+        self.elastic_ips
+            .iter()
+            .map(|a| VerificationResult {
+                message: format!(
+                    "Elastic IP {} is left over: `aws ec2 release-address --allocation-id {}`",
+                    a.public_ip().unwrap_or_default(),
+                    a.allocation_id().unwrap_or_default()
+                ),
+                severity: Severity::Warning,
+                code: None,
+                check_id: Some("LEFT-004"),
+                resource_type: Some(ResourceType::ElasticIp),
+                resource_id: Some(a.allocation_id().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_hosted_zones(&self) -> Vec<VerificationResult> {
+        info!("Checking for leftover hosted zones");
+        let mut results = vec![];
+        for hz in self.hosted_zones.iter() {
+            let zone_id = hz.hosted_zone.id.trim_start_matches("/hostedzone/");
+            // SOA and NS records of the zone apex are deleted with the zone.
+            let records: Vec<_> = hz
+                .resource_records
+                .iter()
+                .filter(|r| {
+                    !(r.name == hz.hosted_zone.name && matches!(r.r#type, RrType::Soa | RrType::Ns))
+                })
+                .collect();
+            for record in records.iter() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Record {} ({}) in hosted zone {} is left over and must be deleted before the zone: `aws route53 change-resource-record-sets --hosted-zone-id {} --change-batch <DELETE {} {}>`",
+                        record.name, record.r#type, hz.hosted_zone.name, zone_id, record.name, record.r#type
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-005"),
+                    resource_type: Some(ResourceType::ResourceRecord),
+                    resource_id: Some(record.name.clone()),
+                    evidence: None,
+                });
+            }
+            results.push(VerificationResult {
+                message: format!(
+                    "Hosted zone {} ({}) is left over: `aws route53 delete-hosted-zone --id {}`",
+                    hz.hosted_zone.name, zone_id, zone_id
+                ),
+                severity: Severity::Warning,
+                code: None,
+                check_id: Some("LEFT-005"),
+                resource_type: Some(ResourceType::HostedZone),
+                resource_id: Some(zone_id.to_string()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl Verifier for LeftoverResourceChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        // LoadBalancers own ENIs and reference security groups, so they have
+        // to go first.
+        results.extend(self.verify_load_balancers());
+        results.extend(self.verify_network_interfaces());
+        results.extend(self.verify_security_groups());
+        results.extend(self.verify_elastic_ips());
+        results.extend(self.verify_hosted_zones());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!("No leftover resources found for {}", self.infra_id),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_route53::types::{HostedZone, ResourceRecordSet};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_leftover_hosted_zone_records() {
+        let record = |name: &str, t: RrType| {
+            ResourceRecordSet::builder()
+                .name(name)
+                .r#type(t)
+                .build()
+                .unwrap()
+        };
+        let hz = HostedZoneWithRecords {
+            hosted_zone: HostedZone::builder()
+                .id("/hostedzone/Z123")
+                .name("mycluster.example.com.")
+                .caller_reference("ref")
+                .build()
+                .unwrap(),
+            resource_records: vec![
+                record("mycluster.example.com.", RrType::Soa),
+                record("mycluster.example.com.", RrType::Ns),
+                record("api.mycluster.example.com.", RrType::A),
+            ],
+            vpcs: vec![],
+            name_servers: vec![],
+            delegated_name_servers: None,
+        };
+        let lrc = LeftoverResourceChecksBuilder::default()
+            .infra_id("mycluster-abcde".to_string())
+            .hosted_zones(vec![hz])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lrc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Record api.mycluster.example.com. (A) in hosted zone mycluster.example.com. is left over and must be deleted before the zone: `aws route53 change-resource-record-sets --hosted-zone-id Z123 --change-batch <DELETE api.mycluster.example.com. A>`".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-005"),
+                    resource_type: Some(ResourceType::ResourceRecord),
+                    resource_id: Some("api.mycluster.example.com.".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Hosted zone mycluster.example.com. (Z123) is left over: `aws route53 delete-hosted-zone --id Z123`".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("LEFT-005"),
+                    resource_type: Some(ResourceType::HostedZone),
+                    resource_id: Some("Z123".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
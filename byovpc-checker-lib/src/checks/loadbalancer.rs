@@ -0,0 +1,1166 @@
+//! This checker verifies the API load balancers created by the installer have
+//! not been modified. Changes to managed load balancers are either reverted or
+//! cause reconcile loops.
+//! It can check the following conditions right now:
+//!
+//! - Deletion protection is disabled on the API load balancers.
+//! - The API load balancers carry the tags managed clusters expect. The value
+//!   of the cluster tag can be configured (owned, shared or either).
+//! - The API target groups have a healthy target for every control plane node.
+//!   With fewer the API is degraded while a node is replaced during upgrades.
+//! - The schemes of the API and default router load balancers match the
+//!   privacy of the API and the default ingress configured in OCM.
+//! - Whether the API and default router load balancers write access logs to
+//!   an existing bucket that allows the log delivery. This is informational:
+//!   enabling access logs is the usual next step to troubleshoot them.
+//! - The API and default router load balancers have cross-zone load balancing
+//!   enabled and an idle timeout of at least the 60 seconds OpenShift expects.
+//! - A load balancer for the `openshift-ingress/router-default` service
+//!   exists. Without it the checks of the router load balancers have nothing
+//!   to report on, while no application route is reachable.
+
+use aws_sdk_ec2::types::InstanceStateName;
+use aws_sdk_elasticloadbalancingv2::types::TargetHealthStateEnum;
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::aws::{
+        s3::{ACCESS_LOGS_BUCKET_ATTRIBUTE, ACCESS_LOGS_ENABLED_ATTRIBUTE},
+        shared_types::{
+            AWSInstance, AWSLoadBalancer, AWSLoadBalancerAttributes, AWSTargetGroup,
+            AccessLogBucket, DEFAULT_ROUTER_TAG_HYPERSHIFT, DEFAULT_ROUTER_VALUE_HYPERSHIFT,
+        },
+    },
+    types::{
+        ClusterType, FindingCode, MinimalClusterInfo, ResourceType, Severity, TagDiff,
+        TagValuePolicy, VerificationResult, Verifier,
+    },
+};
+
+const DELETION_PROTECTION_ATTRIBUTE: &str = "deletion_protection.enabled";
+const CROSS_ZONE_ATTRIBUTE: &str = "load_balancing.cross_zone.enabled";
+const IDLE_TIMEOUT_ATTRIBUTE: &str = "idle_timeout.timeout_seconds";
+
+/// The idle timeout of the load balancers created for OpenShift. Shorter
+/// timeouts close idle watches, `oc logs -f` and websocket connections.
+const MIN_IDLE_TIMEOUT_SECONDS: u64 = 60;
+
+const SCHEME_INTERNAL: &str = "internal";
+const SCHEME_INTERNET_FACING: &str = "internet-facing";
+
+/// The number of control plane nodes of a cluster.
+const CONTROL_PLANE_NODES: usize = 3;
+
+/// The service principals delivering the access logs of load balancers.
+const LOG_DELIVERY_PRINCIPALS: [&str; 2] = [
+    "logdelivery.elasticloadbalancing.amazonaws.com",
+    "delivery.logs.amazonaws.com",
+];
+
+#[derive(Debug, Builder)]
+pub struct LoadBalancerChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    load_balancer_attributes: Vec<AWSLoadBalancerAttributes>,
+    #[builder(default = "vec![]")]
+    access_log_buckets: Vec<AccessLogBucket>,
+    #[builder(default = "vec![]")]
+    target_groups: Vec<AWSTargetGroup>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+    /// The values the cluster tag of the API load balancers may have.
+    #[builder(default = "TagValuePolicy::Owned")]
+    cluster_tag_value: TagValuePolicy,
+}
+
+impl<'a> LoadBalancerChecks<'a> {
+    /// The API load balancers are the NLBs named after the infra name with an
+    /// `-int` or `-ext` suffix.
+    fn api_load_balancers(&self) -> Vec<&AWSLoadBalancer> {
+        let infra_name = &self.cluster_info.cluster_infra_name;
+        self.load_balancers
+            .iter()
+            .filter(|lb| matches!(lb, AWSLoadBalancer::ModernLoadBalancer(_)))
+            .filter(|lb| {
+                lb.name() == format!("{}-int", infra_name)
+                    || lb.name() == format!("{}-ext", infra_name)
+            })
+            .collect()
+    }
+
+    /// The load balancers of the default router, tagged with the service of
+    /// the default ingress controller.
+    fn router_load_balancers(&self) -> Vec<&AWSLoadBalancer> {
+        self.load_balancers
+            .iter()
+            .filter(|lb| {
+                lb.tags().iter().any(|t| {
+                    t.key.as_deref() == Some(DEFAULT_ROUTER_TAG_HYPERSHIFT)
+                        && t.value.as_deref() == Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT)
+                })
+            })
+            .collect()
+    }
+
+    /// The API and default router load balancers.
+    fn cluster_load_balancers(&self) -> Vec<&AWSLoadBalancer> {
+        self.api_load_balancers()
+            .into_iter()
+            .chain(self.router_load_balancers())
+            .unique_by(|lb| lb.identifier())
+            .collect()
+    }
+
+    /// The value of an attribute of the load balancer - `None` if it was not
+    /// gathered or the load balancer type has no such attribute.
+    fn attribute(&self, lb: &AWSLoadBalancer, key: &str) -> Option<&str> {
+        self.load_balancer_attributes
+            .iter()
+            .find(|a| a.load_balancer == lb.identifier())
+            .and_then(|a| a.attributes.get(key))
+            .map(|v| v.as_str())
+    }
+
+    fn cluster_tag(&self) -> String {
+        format!(
+            "kubernetes.io/cluster/{}",
+            self.cluster_info.cluster_infra_name
+        )
+    }
+
+    /// The tags and values the installer sets on the API load balancers.
+    fn expected_tags(&self) -> Vec<(String, String)> {
+        let mut tags = vec![(
+            self.cluster_tag(),
+            self.cluster_tag_value.as_str().to_string(),
+        )];
+        if self.cluster_info.cluster_type == ClusterType::Rosa {
+            tags.push(("red-hat-managed".to_string(), "true".to_string()));
+        }
+        tags
+    }
+
+    /// Whether the tag has the expected value - the cluster tag may have any
+    /// value of the configured policy.
+    fn has_expected_value(&self, key: &str, actual: &str, expected: &str) -> bool {
+        if key == self.cluster_tag() {
+            self.cluster_tag_value.allows(actual)
+        } else {
+            actual == expected
+        }
+    }
+
+    pub fn verify_deletion_protection(&self) -> Vec<VerificationResult> {
+        info!("Checking deletion protection of API load balancers");
+        let mut results = vec![];
+        for lb in self.api_load_balancers() {
+            let enabled = self
+                .load_balancer_attributes
+                .iter()
+                .find(|a| a.load_balancer == lb.identifier())
+                .and_then(|a| a.attributes.get(DELETION_PROTECTION_ATTRIBUTE))
+                .is_some_and(|v| v == "true");
+            if enabled {
+                results.push(VerificationResult {
+                    message: format!(
+                        "LoadBalancer {} has deletion protection enabled - the cluster can not be uninstalled cleanly",
+                        lb.name()
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::LbDeletionProtection),
+                    check_id: Some("LB-001"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some(lb.identifier().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking tags of API load balancers");
+        let mut results = vec![];
+        let expected_tags = self.expected_tags();
+        for lb in self.api_load_balancers() {
+            let diff = TagDiff::new(
+                lb.name(),
+                lb.tags().iter().filter_map(|t| {
+                    Some((t.key.as_deref()?, t.value.as_deref().unwrap_or_default()))
+                }),
+            );
+            for (key, value) in expected_tags.iter() {
+                let actual = lb
+                    .tags()
+                    .iter()
+                    .find(|t| t.key.as_ref() == Some(key))
+                    .and_then(|t| t.value.as_deref());
+                match actual {
+                    Some(actual) if self.has_expected_value(key, actual, value) => {}
+                    Some(actual) => results.push(VerificationResult {
+                        message: format!(
+                            "LoadBalancer {} has tag {}={} but {} is expected - the change will be reverted or cause reconcile loops",
+                            lb.name(),
+                            key,
+                            actual,
+                            value
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::LbTagsModified),
+                        check_id: Some("LB-002"),
+                        resource_type: Some(ResourceType::LoadBalancer),
+                        resource_id: Some(lb.identifier().to_string()),
+                        evidence: Some(diff.evidence(key, value)),
+                    }),
+                    None => results.push(VerificationResult {
+                        message: format!(
+                            "LoadBalancer {} is missing tag {}={} - the change will be reverted or cause reconcile loops",
+                            lb.name(),
+                            key,
+                            value
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::LbTagsModified),
+                        check_id: Some("LB-002"),
+                        resource_type: Some(ResourceType::LoadBalancer),
+                        resource_id: Some(lb.identifier().to_string()),
+                        evidence: Some(diff.evidence(key, value)),
+                    }),
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_api_targets(&self) -> Vec<VerificationResult> {
+        info!("Checking healthy targets of the API load balancers");
+        let mut results = vec![];
+        let control_plane: Vec<&AWSInstance> = self
+            .instances
+            .iter()
+            .filter(|i| i.is_control_plane(&self.cluster_info.cluster_infra_name))
+            .collect();
+        for lb in self.api_load_balancers() {
+            let target_groups = self.target_groups.iter().filter(|tg| {
+                tg.target_group
+                    .load_balancer_arns()
+                    .iter()
+                    .any(|arn| arn == lb.identifier())
+            });
+            for tg in target_groups {
+                let healthy = tg
+                    .targets
+                    .iter()
+                    .filter(|t| {
+                        t.target_health().and_then(|h| h.state())
+                            == Some(&TargetHealthStateEnum::Healthy)
+                    })
+                    .count();
+                if healthy >= CONTROL_PLANE_NODES {
+                    continue;
+                }
+                let targets: Vec<serde_json::Value> = tg
+                    .targets
+                    .iter()
+                    .map(|t| {
+                        let id = t.target().and_then(|t| t.id()).unwrap_or_default();
+                        let instance = self.instances.iter().find(|i| {
+                            i.instance.instance_id() == Some(id)
+                                || i.instance.private_ip_address() == Some(id)
+                        });
+                        json!({
+                            "id": id,
+                            "health": t.target_health().and_then(|h| h.state()).map(|s| s.as_str()),
+                            "instanceState": instance
+                                .and_then(|i| i.instance.state())
+                                .and_then(|s| s.name())
+                                .map(|s| s.as_str()),
+                        })
+                    })
+                    .collect();
+                let unregistered: Vec<&str> = control_plane
+                    .iter()
+                    .filter(|i| {
+                        i.instance.state().and_then(|s| s.name())
+                            == Some(&InstanceStateName::Running)
+                    })
+                    .filter_map(|i| i.instance.instance_id())
+                    .filter(|id| {
+                        !tg.targets
+                            .iter()
+                            .any(|t| t.target().and_then(|t| t.id()) == Some(id))
+                    })
+                    .collect();
+                results.push(VerificationResult {
+                    message: format!(
+                        "Target group {} of API LoadBalancer {} has {} of {} healthy control plane targets - the API is degraded while a control plane node is replaced during upgrades",
+                        tg.target_group.target_group_name().unwrap_or_default(),
+                        lb.name(),
+                        healthy,
+                        CONTROL_PLANE_NODES
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::ApiTargetsDegraded),
+                    check_id: Some("LB-004"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some(tg.target_group.target_group_arn().unwrap_or_default().to_string()),
+                    evidence: Some(json!({
+                        "targets": targets,
+                        "unregisteredControlPlaneInstances": unregistered,
+                    })),
+                });
+            }
+        }
+        results
+    }
+
+    /// Compares the scheme of the load balancer to the one the endpoint it
+    /// serves needs. Internal load balancers of public endpoints make them
+    /// unreachable, internet-facing ones of private endpoints expose them.
+    fn verify_scheme(
+        &self,
+        lb: &AWSLoadBalancer,
+        endpoint: &str,
+        private: bool,
+    ) -> Option<VerificationResult> {
+        let expected = if private {
+            SCHEME_INTERNAL
+        } else {
+            SCHEME_INTERNET_FACING
+        };
+        let actual = lb.scheme()?;
+        if actual == expected {
+            return None;
+        }
+        let (severity, impact) = if private {
+            (Severity::Warning, "exposes it to the internet")
+        } else {
+            (Severity::Critical, "makes it unreachable from the internet")
+        };
+        Some(VerificationResult {
+            message: format!(
+                "LoadBalancer {} of the {} is {} - this {}",
+                lb.name(),
+                endpoint,
+                actual,
+                impact
+            ),
+            severity,
+            code: Some(FindingCode::LbSchemeMismatch),
+            check_id: Some("LB-005"),
+            resource_type: Some(ResourceType::LoadBalancer),
+            resource_id: Some(lb.identifier().to_string()),
+            evidence: Some(json!({
+                "resource": lb.name(),
+                "scheme": actual,
+                "expected": expected,
+            })),
+        })
+    }
+
+    pub fn verify_api_schemes(&self) -> Vec<VerificationResult> {
+        info!("Checking schemes of API load balancers");
+        let internal_name = format!("{}-int", self.cluster_info.cluster_infra_name);
+        self.api_load_balancers()
+            .into_iter()
+            .filter_map(|lb| {
+                // The internal API load balancer is internal on every cluster.
+                let endpoint = if lb.name() == internal_name {
+                    "internal API"
+                } else if self.cluster_info.private {
+                    "private API"
+                } else {
+                    "public API"
+                };
+                let private = lb.name() == internal_name || self.cluster_info.private;
+                self.verify_scheme(lb, endpoint, private)
+            })
+            .collect()
+    }
+
+    pub fn verify_router_schemes(&self) -> Vec<VerificationResult> {
+        info!("Checking schemes of default router load balancers");
+        let private = self.cluster_info.ingress_private;
+        let endpoint = if private {
+            "private default ingress"
+        } else {
+            "public default ingress"
+        };
+        self.router_load_balancers()
+            .into_iter()
+            .filter_map(|lb| self.verify_scheme(lb, endpoint, private))
+            .collect()
+    }
+
+    pub fn verify_access_logs(&self) -> Vec<VerificationResult> {
+        info!("Checking access logs of API and router load balancers");
+        let mut results = vec![];
+        for lb in self.cluster_load_balancers() {
+            let Some(attributes) = self
+                .load_balancer_attributes
+                .iter()
+                .find(|a| a.load_balancer == lb.identifier())
+            else {
+                continue;
+            };
+            let Some(enabled) = attributes.attributes.get(ACCESS_LOGS_ENABLED_ATTRIBUTE) else {
+                continue;
+            };
+            let enabled = enabled == "true";
+            let bucket = attributes
+                .attributes
+                .get(ACCESS_LOGS_BUCKET_ATTRIBUTE)
+                .map_or("", |b| b.as_str());
+            let access_log_bucket = self.access_log_buckets.iter().find(|b| b.name == bucket);
+            let (message, code, evidence) = match access_log_bucket {
+                _ if !enabled => (
+                    format!(
+                        "LoadBalancer {} has access logs disabled - enable them to troubleshoot connections to it",
+                        lb.name()
+                    ),
+                    None,
+                    None,
+                ),
+                Some(b) if b.exists == Some(false) => (
+                    format!(
+                        "LoadBalancer {} writes access logs to bucket {} that does not exist - no access logs are written",
+                        lb.name(),
+                        bucket
+                    ),
+                    Some(FindingCode::AccessLogBucketMissing),
+                    None,
+                ),
+                Some(b) if b.exists == Some(true) && !allows_log_delivery(b.policy.as_deref()) => (
+                    format!(
+                        "LoadBalancer {} writes access logs to bucket {} whose policy does not allow the log delivery to write to it",
+                        lb.name(),
+                        bucket
+                    ),
+                    Some(FindingCode::AccessLogBucketPolicyInvalid),
+                    Some(json!({
+                        "bucket": bucket,
+                        "policy": b.policy.as_deref().and_then(|p| serde_json::from_str::<serde_json::Value>(p).ok()),
+                    })),
+                ),
+                _ => (
+                    format!(
+                        "LoadBalancer {} writes access logs to bucket {}",
+                        lb.name(),
+                        bucket
+                    ),
+                    None,
+                    None,
+                ),
+            };
+            results.push(VerificationResult {
+                message,
+                severity: Severity::Info,
+                code,
+                check_id: Some("LB-003"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some(lb.identifier().to_string()),
+                evidence,
+            });
+        }
+        results
+    }
+
+    pub fn verify_router_load_balancer(&self) -> Vec<VerificationResult> {
+        info!("Checking the default router load balancer exists");
+        let router_lbs = self.router_load_balancers();
+        if router_lbs.is_empty() {
+            return vec![VerificationResult {
+                message: format!(
+                    "No LoadBalancer is tagged with {}={} - the default ingress is not reachable",
+                    DEFAULT_ROUTER_TAG_HYPERSHIFT, DEFAULT_ROUTER_VALUE_HYPERSHIFT
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::RouterLbMissing),
+                check_id: Some("LB-008"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        router_lbs
+            .into_iter()
+            .map(|lb| VerificationResult {
+                message: format!("LoadBalancer {} serves the default router", lb.name()),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("LB-008"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some(lb.identifier().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_cross_zone(&self) -> Vec<VerificationResult> {
+        info!("Checking cross-zone load balancing of API and router load balancers");
+        self.cluster_load_balancers()
+            .into_iter()
+            .filter(|lb| self.attribute(lb, CROSS_ZONE_ATTRIBUTE) == Some("false"))
+            .map(|lb| VerificationResult {
+                message: format!(
+                    "LoadBalancer {} has cross-zone load balancing disabled - clients resolving the address of a zone without healthy targets fail. Enable it with: {}",
+                    lb.name(),
+                    modify_attribute_command(lb, CROSS_ZONE_ATTRIBUTE, "true")
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::LbCrossZoneDisabled),
+                check_id: Some("LB-006"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some(lb.identifier().to_string()),
+                evidence: Some(json!({CROSS_ZONE_ATTRIBUTE: "false"})),
+            })
+            .collect()
+    }
+
+    pub fn verify_idle_timeout(&self) -> Vec<VerificationResult> {
+        info!("Checking idle timeouts of API and router load balancers");
+        self.cluster_load_balancers()
+            .into_iter()
+            .filter_map(|lb| {
+                let timeout: u64 = self.attribute(lb, IDLE_TIMEOUT_ATTRIBUTE)?.parse().ok()?;
+                (timeout < MIN_IDLE_TIMEOUT_SECONDS).then(|| VerificationResult {
+                    message: format!(
+                        "LoadBalancer {} has an idle timeout of {}s, less than the {}s OpenShift expects - idle watches and streaming connections are closed early. Raise it with: {}",
+                        lb.name(),
+                        timeout,
+                        MIN_IDLE_TIMEOUT_SECONDS,
+                        modify_attribute_command(
+                            lb,
+                            IDLE_TIMEOUT_ATTRIBUTE,
+                            &MIN_IDLE_TIMEOUT_SECONDS.to_string()
+                        )
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::LbIdleTimeoutTooShort),
+                    check_id: Some("LB-007"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some(lb.identifier().to_string()),
+                    evidence: Some(json!({IDLE_TIMEOUT_ATTRIBUTE: timeout})),
+                })
+            })
+            .collect()
+    }
+}
+
+/// The AWS CLI command setting an attribute of the load balancer. Classic load
+/// balancers name their attributes differently.
+fn modify_attribute_command(lb: &AWSLoadBalancer, key: &str, value: &str) -> String {
+    match lb {
+        AWSLoadBalancer::ClassicLoadBalancer(_) => {
+            let attributes = match key {
+                CROSS_ZONE_ATTRIBUTE => format!("CrossZoneLoadBalancing={{Enabled={}}}", value),
+                _ => format!("ConnectionSettings={{IdleTimeout={}}}", value),
+            };
+            format!(
+                "aws elb modify-load-balancer-attributes --load-balancer-name {} --load-balancer-attributes '{}'",
+                lb.name(),
+                attributes
+            )
+        }
+        AWSLoadBalancer::ModernLoadBalancer(_) => format!(
+            "aws elbv2 modify-load-balancer-attributes --load-balancer-arn {} --attributes Key={},Value={}",
+            lb.identifier(),
+            key,
+            value
+        ),
+    }
+}
+
+/// Whether a statement of the bucket policy allows the log delivery to put
+/// objects into the bucket. Load balancers in older regions deliver logs as
+/// the ELB account of the region, so any AWS principal is accepted.
+fn allows_log_delivery(policy: Option<&str>) -> bool {
+    let Some(policy) = policy.and_then(|p| serde_json::from_str::<serde_json::Value>(p).ok())
+    else {
+        return false;
+    };
+    let as_list = |v: Option<&serde_json::Value>| -> Vec<String> {
+        match v {
+            Some(serde_json::Value::String(s)) => vec![s.clone()],
+            Some(serde_json::Value::Array(a)) => a
+                .iter()
+                .filter_map(|s| s.as_str().map(String::from))
+                .collect(),
+            _ => vec![],
+        }
+    };
+    let statements = match policy.get("Statement") {
+        Some(serde_json::Value::Array(a)) => a.clone(),
+        Some(s) => vec![s.clone()],
+        None => vec![],
+    };
+    statements.iter().any(|s| {
+        let allows = s.get("Effect").and_then(|e| e.as_str()) == Some("Allow");
+        let puts = as_list(s.get("Action"))
+            .iter()
+            .any(|a| a == "s3:PutObject" || a == "s3:*" || a == "*");
+        let principal = s.get("Principal");
+        let delivers = principal.and_then(|p| p.as_str()) == Some("*")
+            || principal.is_some_and(|p| p.get("AWS").is_some())
+            || as_list(principal.and_then(|p| p.get("Service")))
+                .iter()
+                .any(|p| LOG_DELIVERY_PRINCIPALS.contains(&p.as_str()));
+        allows && puts && delivers
+    })
+}
+
+impl<'a> Verifier for LoadBalancerChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        if self.cluster_info.cluster_type == ClusterType::Hypershift {
+            results.push(VerificationResult {
+                message: "Hypershift API load balancers are not in the customer account - skipping API load balancer checks".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        } else if self.api_load_balancers().is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "No API load balancers found for infra name {}",
+                    self.cluster_info.cluster_infra_name
+                ),
+                severity: Severity::Warning,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        } else {
+            results.extend(self.verify_deletion_protection());
+            results.extend(self.verify_tags());
+            results.extend(self.verify_api_targets());
+            results.extend(self.verify_api_schemes());
+            if results.is_empty() {
+                results.push(VerificationResult {
+                    message: "API load balancers are not modified".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: None,
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                });
+            }
+        }
+        results.extend(self.verify_router_load_balancer());
+        results.extend(self.verify_router_schemes());
+        results.extend(self.verify_access_logs());
+        results.extend(self.verify_cross_zone());
+        results.extend(self.verify_idle_timeout());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use aws_sdk_ec2::types::{Instance, InstanceState};
+    use aws_sdk_elasticloadbalancingv2::types::{
+        LoadBalancer, LoadBalancerSchemeEnum, TargetDescription, TargetGroup, TargetHealth,
+        TargetHealthDescription,
+    };
+    use serde_json::json;
+
+    use crate::{
+        checks::skip_missing_prerequisites,
+        gatherer::aws::shared_types::Tag,
+        types::{DataSet, GatherFailure, MinimalClusterInfoBuilder},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_verify_modified_api_load_balancer() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .cluster_type(ClusterType::Rosa)
+            .build()
+            .unwrap();
+        let lb = AWSLoadBalancer::ModernLoadBalancer((
+            LoadBalancer::builder()
+                .load_balancer_name("mycluster-abcde-int")
+                .load_balancer_arn("arn:int")
+                .build(),
+            vec![Tag {
+                key: Some("kubernetes.io/cluster/mycluster-abcde".to_string()),
+                value: Some("shared".to_string()),
+            }],
+        ));
+        let attributes = AWSLoadBalancerAttributes {
+            load_balancer: "arn:int".to_string(),
+            attributes: BTreeMap::from([(
+                DELETION_PROTECTION_ATTRIBUTE.to_string(),
+                "true".to_string(),
+            )]),
+        };
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![lb])
+            .load_balancer_attributes(vec![attributes])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify(),
+            vec![
+                VerificationResult {
+                    message: "LoadBalancer mycluster-abcde-int has deletion protection enabled - the cluster can not be uninstalled cleanly".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::LbDeletionProtection),
+                    check_id: Some("LB-001"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:int".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "LoadBalancer mycluster-abcde-int has tag kubernetes.io/cluster/mycluster-abcde=shared but owned is expected - the change will be reverted or cause reconcile loops".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::LbTagsModified),
+                    check_id: Some("LB-002"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:int".to_string()),
+                    evidence: Some(json!({
+                        "resource": "mycluster-abcde-int",
+                        "expected": {"kubernetes.io/cluster/mycluster-abcde": "owned"},
+                        "actual": {"kubernetes.io/cluster/mycluster-abcde": "shared"},
+                    })),
+                },
+                VerificationResult {
+                    message: "LoadBalancer mycluster-abcde-int is missing tag red-hat-managed=true - the change will be reverted or cause reconcile loops".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::LbTagsModified),
+                    check_id: Some("LB-002"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:int".to_string()),
+                    evidence: Some(json!({
+                        "resource": "mycluster-abcde-int",
+                        "expected": {"red-hat-managed": "true"},
+                        "actual": {"kubernetes.io/cluster/mycluster-abcde": "shared"},
+                    })),
+                },
+                VerificationResult {
+                    message: "No LoadBalancer is tagged with kubernetes.io/service-name=openshift-ingress/router-default - the default ingress is not reachable".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::RouterLbMissing),
+                    check_id: Some("LB-008"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn test_verify_router_load_balancer() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let api_lb = AWSLoadBalancer::ModernLoadBalancer((
+            LoadBalancer::builder()
+                .load_balancer_name("mycluster-abcde-int")
+                .load_balancer_arn("arn:int")
+                .build(),
+            vec![],
+        ));
+        let router_lb = AWSLoadBalancer::ClassicLoadBalancer((
+            aws_sdk_elasticloadbalancing::types::LoadBalancerDescription::builder()
+                .load_balancer_name("a1b2c3")
+                .build(),
+            vec![Tag {
+                key: Some(DEFAULT_ROUTER_TAG_HYPERSHIFT.to_string()),
+                value: Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT.to_string()),
+            }],
+        ));
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![api_lb.clone()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_router_load_balancer(),
+            vec![VerificationResult {
+                message: "No LoadBalancer is tagged with kubernetes.io/service-name=openshift-ingress/router-default - the default ingress is not reachable".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::RouterLbMissing),
+                check_id: Some("LB-008"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }]
+        );
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![api_lb, router_lb])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_router_load_balancer(),
+            vec![VerificationResult {
+                message: "LoadBalancer a1b2c3 serves the default router".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("LB-008"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some("a1b2c3".to_string()),
+                evidence: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_verify_router_load_balancer_without_load_balancers() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .cluster_type(ClusterType::Hypershift)
+            .build()
+            .unwrap();
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .build()
+            .unwrap();
+        let router_results = |missing: GatherFailure| {
+            skip_missing_prerequisites(&["LB-"], lbc.verify(), &[missing])
+                .into_iter()
+                .filter(|r| r.check_id == Some("LB-008"))
+                .collect::<Vec<_>>()
+        };
+        let none_found = router_results(GatherFailure {
+            set: DataSet::LoadBalancers,
+            reason: "none found".to_string(),
+            empty: true,
+        });
+        assert_eq!(none_found.len(), 1);
+        assert_eq!(none_found[0].severity, Severity::Critical);
+        assert_eq!(none_found[0].code, Some(FindingCode::RouterLbMissing));
+        let failed = router_results(GatherFailure {
+            set: DataSet::LoadBalancers,
+            reason: "AccessDenied".to_string(),
+            empty: false,
+        });
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_verify_access_logs() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let lb = |name: &str, tags: Vec<Tag>| {
+            AWSLoadBalancer::ModernLoadBalancer((
+                LoadBalancer::builder()
+                    .load_balancer_name(name)
+                    .load_balancer_arn(format!("arn:{}", name))
+                    .build(),
+                tags,
+            ))
+        };
+        let attributes = |name: &str, enabled: bool, bucket: &str| AWSLoadBalancerAttributes {
+            load_balancer: format!("arn:{}", name),
+            attributes: BTreeMap::from([
+                (
+                    ACCESS_LOGS_ENABLED_ATTRIBUTE.to_string(),
+                    enabled.to_string(),
+                ),
+                (ACCESS_LOGS_BUCKET_ATTRIBUTE.to_string(), bucket.to_string()),
+            ]),
+        };
+        let router_tag = vec![Tag {
+            key: Some(DEFAULT_ROUTER_TAG_HYPERSHIFT.to_string()),
+            value: Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT.to_string()),
+        }];
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![
+                lb("mycluster-abcde-int", vec![]),
+                lb("mycluster-abcde-ext", vec![]),
+                lb("router", router_tag),
+                lb("other", vec![]),
+            ])
+            .load_balancer_attributes(vec![
+                attributes("mycluster-abcde-int", false, ""),
+                attributes("mycluster-abcde-ext", true, "logs"),
+                attributes("router", true, "missing"),
+                attributes("other", false, ""),
+            ])
+            .access_log_buckets(vec![
+                AccessLogBucket {
+                    name: "logs".to_string(),
+                    exists: Some(true),
+                    policy: Some(
+                        json!({"Statement": [{
+                            "Effect": "Allow",
+                            "Principal": {"Service": "logdelivery.elasticloadbalancing.amazonaws.com"},
+                            "Action": "s3:PutObject",
+                            "Resource": "arn:aws:s3:::logs/*",
+                        }]})
+                        .to_string(),
+                    ),
+                },
+                AccessLogBucket {
+                    name: "missing".to_string(),
+                    exists: Some(false),
+                    policy: None,
+                },
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_access_logs(),
+            vec![
+                VerificationResult {
+                    message: "LoadBalancer mycluster-abcde-int has access logs disabled - enable them to troubleshoot connections to it".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("LB-003"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:mycluster-abcde-int".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "LoadBalancer mycluster-abcde-ext writes access logs to bucket logs".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("LB-003"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:mycluster-abcde-ext".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "LoadBalancer router writes access logs to bucket missing that does not exist - no access logs are written".to_string(),
+                    severity: Severity::Info,
+                    code: Some(FindingCode::AccessLogBucketMissing),
+                    check_id: Some("LB-003"),
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    resource_id: Some("arn:router".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+        assert!(!allows_log_delivery(Some(
+            &json!({"Statement": {"Effect": "Allow", "Principal": {"Service": "s3.amazonaws.com"}, "Action": "s3:PutObject"}}).to_string()
+        )));
+    }
+
+    #[test]
+    fn test_verify_cross_zone_and_idle_timeout() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let api = AWSLoadBalancer::ModernLoadBalancer((
+            LoadBalancer::builder()
+                .load_balancer_name("mycluster-abcde-int")
+                .load_balancer_arn("arn:int")
+                .build(),
+            vec![],
+        ));
+        let router = AWSLoadBalancer::ClassicLoadBalancer((
+            aws_sdk_elasticloadbalancing::types::LoadBalancerDescription::builder()
+                .load_balancer_name("router")
+                .build(),
+            vec![Tag {
+                key: Some(DEFAULT_ROUTER_TAG_HYPERSHIFT.to_string()),
+                value: Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT.to_string()),
+            }],
+        ));
+        let attributes =
+            |lb: &str, cross_zone: &str, idle_timeout: &str| AWSLoadBalancerAttributes {
+                load_balancer: lb.to_string(),
+                attributes: BTreeMap::from([
+                    (CROSS_ZONE_ATTRIBUTE.to_string(), cross_zone.to_string()),
+                    (IDLE_TIMEOUT_ATTRIBUTE.to_string(), idle_timeout.to_string()),
+                ]),
+            };
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![api, router])
+            .load_balancer_attributes(vec![
+                attributes("arn:int", "false", "350"),
+                attributes("router", "true", "30"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_cross_zone()
+                .iter()
+                .map(|r| r.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["LoadBalancer mycluster-abcde-int has cross-zone load balancing disabled - clients resolving the address of a zone without healthy targets fail. Enable it with: aws elbv2 modify-load-balancer-attributes --load-balancer-arn arn:int --attributes Key=load_balancing.cross_zone.enabled,Value=true"]
+        );
+        assert_eq!(
+            lbc.verify_idle_timeout()
+                .iter()
+                .map(|r| r.message.as_str())
+                .collect::<Vec<_>>(),
+            vec!["LoadBalancer router has an idle timeout of 30s, less than the 60s OpenShift expects - idle watches and streaming connections are closed early. Raise it with: aws elb modify-load-balancer-attributes --load-balancer-name router --load-balancer-attributes 'ConnectionSettings={IdleTimeout=60}'"]
+        );
+    }
+
+    #[test]
+    fn test_verify_api_targets() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let target = |id: &str, state: TargetHealthStateEnum| {
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id(id).port(6443).build())
+                .target_health(TargetHealth::builder().state(state).build())
+                .build()
+        };
+        let instance = |id: &str, n: usize, state: InstanceStateName| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .tags(
+                    aws_sdk_ec2::types::Tag::builder()
+                        .key("Name")
+                        .value(format!("mycluster-abcde-master-{}", n))
+                        .build(),
+                )
+                .state(InstanceState::builder().name(state).build())
+                .build(),
+            security_groups: vec![],
+        };
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![AWSLoadBalancer::ModernLoadBalancer((
+                LoadBalancer::builder()
+                    .load_balancer_name("mycluster-abcde-int")
+                    .load_balancer_arn("arn:int")
+                    .build(),
+                vec![],
+            ))])
+            .target_groups(vec![AWSTargetGroup {
+                target_group: TargetGroup::builder()
+                    .target_group_name("mycluster-abcde-aint")
+                    .load_balancer_arns("arn:int")
+                    .build(),
+                targets: vec![
+                    target("i-1", TargetHealthStateEnum::Healthy),
+                    target("i-2", TargetHealthStateEnum::Draining),
+                ],
+            }])
+            .instances(vec![
+                instance("i-1", 0, InstanceStateName::Running),
+                instance("i-2", 1, InstanceStateName::ShuttingDown),
+                instance("i-3", 2, InstanceStateName::Running),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_api_targets(),
+            vec![VerificationResult {
+                message: "Target group mycluster-abcde-aint of API LoadBalancer mycluster-abcde-int has 1 of 3 healthy control plane targets - the API is degraded while a control plane node is replaced during upgrades".to_string(),
+                severity: Severity::Warning,
+                code: Some(FindingCode::ApiTargetsDegraded),
+                check_id: Some("LB-004"),
+                resource_type: Some(ResourceType::TargetGroup),
+                resource_id: Some("".to_string()),
+                evidence: Some(json!({
+                    "targets": [
+                        {"id": "i-1", "health": "healthy", "instanceState": "running"},
+                        {"id": "i-2", "health": "draining", "instanceState": "shutting-down"},
+                    ],
+                    "unregisteredControlPlaneInstances": ["i-3"],
+                })),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_verify_schemes() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .private(true)
+            .build()
+            .unwrap();
+        let lb = |name: &str, scheme: LoadBalancerSchemeEnum, tags: Vec<Tag>| {
+            AWSLoadBalancer::ModernLoadBalancer((
+                LoadBalancer::builder()
+                    .load_balancer_name(name)
+                    .scheme(scheme)
+                    .build(),
+                tags,
+            ))
+        };
+        let router_tag = vec![Tag {
+            key: Some(DEFAULT_ROUTER_TAG_HYPERSHIFT.to_string()),
+            value: Some(DEFAULT_ROUTER_VALUE_HYPERSHIFT.to_string()),
+        }];
+        let lbc = LoadBalancerChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .load_balancers(vec![
+                lb(
+                    "mycluster-abcde-int",
+                    LoadBalancerSchemeEnum::Internal,
+                    vec![],
+                ),
+                lb(
+                    "mycluster-abcde-ext",
+                    LoadBalancerSchemeEnum::InternetFacing,
+                    vec![],
+                ),
+                lb("router", LoadBalancerSchemeEnum::Internal, router_tag),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            lbc.verify_api_schemes(),
+            vec![VerificationResult {
+                message: "LoadBalancer mycluster-abcde-ext of the private API is internet-facing - this exposes it to the internet".to_string(),
+                severity: Severity::Warning,
+                code: Some(FindingCode::LbSchemeMismatch),
+                check_id: Some("LB-005"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some("".to_string()),
+                evidence: Some(json!({
+                    "resource": "mycluster-abcde-ext",
+                    "scheme": "internet-facing",
+                    "expected": "internal",
+                })),
+            }]
+        );
+        assert_eq!(
+            lbc.verify_router_schemes(),
+            vec![VerificationResult {
+                message: "LoadBalancer router of the public default ingress is internal - this makes it unreachable from the internet".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::LbSchemeMismatch),
+                check_id: Some("LB-005"),
+                resource_type: Some(ResourceType::LoadBalancer),
+                resource_id: Some("".to_string()),
+                evidence: Some(json!({
+                    "resource": "router",
+                    "scheme": "internal",
+                    "expected": "internet-facing",
+                })),
+            }]
+        );
+    }
+}
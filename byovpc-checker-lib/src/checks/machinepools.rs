@@ -0,0 +1,189 @@
+//! This checker verifies the machine pools configured in OCM against the VPC.
+//! It can check the following conditions right now:
+//!
+//! - Every subnet referenced by a machine pool exists in the cluster VPC.
+//! - Every subnet referenced by a machine pool is configured for the cluster.
+//! - Every subnet referenced by a machine pool is in one of the pool's zones.
+
+use aws_sdk_ec2::types::Subnet;
+use derive_builder::Builder;
+use log::info;
+
+use crate::types::{
+    FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+};
+
+#[derive(Debug, Builder)]
+pub struct MachinePoolChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+}
+
+impl<'a> MachinePoolChecks<'a> {
+    pub fn verify_machine_pool_subnets(&self) -> Vec<VerificationResult> {
+        info!("Checking machine pool subnets");
+        let mut results = vec![];
+        for mp in self.cluster_info.machine_pools.iter() {
+            let mut problems = 0;
+            for subnet_id in mp.subnets.iter() {
+                let Some(subnet) = self
+                    .subnets
+                    .iter()
+                    .find(|s| s.subnet_id() == Some(subnet_id.as_str()))
+                else {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Machine pool {} references subnet {} that does not exist in the cluster VPC",
+                            mp.id, subnet_id
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::MachinePoolSubnetNotFound),
+                        check_id: Some("MP-001"),
+                        resource_type: Some(ResourceType::MachinePool),
+                        resource_id: Some(mp.id.clone()),
+                        evidence: None,
+                    });
+                    problems += 1;
+                    continue;
+                };
+                if !self.cluster_info.subnets.is_empty()
+                    && !self.cluster_info.subnets.contains(subnet_id)
+                {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Machine pool {} references subnet {} that is not configured for the cluster",
+                            mp.id, subnet_id
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::MachinePoolSubnetNotConfigured),
+                        check_id: Some("MP-001"),
+                        resource_type: Some(ResourceType::MachinePool),
+                        resource_id: Some(mp.id.clone()),
+                        evidence: None,
+                    });
+                    problems += 1;
+                }
+                let az = subnet.availability_zone().unwrap_or_default();
+                if !mp.availability_zones.is_empty()
+                    && !mp.availability_zones.iter().any(|z| z == az)
+                {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Machine pool {} references subnet {} in AZ {} which is not one of the pool's AZs: {}",
+                            mp.id,
+                            subnet_id,
+                            az,
+                            mp.availability_zones.join(", ")
+                        ),
+                        severity: Severity::Critical,
+                        code: Some(FindingCode::MachinePoolSubnetAzMismatch),
+                        check_id: Some("MP-001"),
+                        resource_type: Some(ResourceType::MachinePool),
+                        resource_id: Some(mp.id.clone()),
+                        evidence: None,
+                    });
+                    problems += 1;
+                }
+            }
+            if problems == 0 && !mp.subnets.is_empty() {
+                results.push(VerificationResult {
+                    message: format!("Machine pool {} subnets are correctly setup", mp.id),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("MP-001"),
+                    resource_type: Some(ResourceType::MachinePool),
+                    resource_id: Some(mp.id.clone()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for MachinePoolChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        self.verify_machine_pool_subnets()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::{MachinePool, MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    fn make_subnet(subnet_id: &str, az: &str) -> Subnet {
+        Subnet::builder()
+            .subnet_id(subnet_id)
+            .availability_zone(az)
+            .vpc_id("vpc-1")
+            .build()
+    }
+
+    #[test]
+    fn test_verify_machine_pool_subnets_wrong_az() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .subnets(vec!["subnet-1".to_string()])
+            .machine_pools(vec![MachinePool {
+                id: "worker".to_string(),
+                instance_type: None,
+                availability_zones: vec!["us-east-1b".to_string()],
+                subnets: vec!["subnet-1".to_string()],
+                max_replicas: None,
+            }])
+            .build()
+            .unwrap();
+        let mpc = MachinePoolChecksBuilder::default()
+            .cluster_info(&mci)
+            .subnets(vec![make_subnet("subnet-1", "us-east-1a")])
+            .build()
+            .unwrap();
+        assert_eq!(
+            mpc.verify_machine_pool_subnets(),
+            vec![VerificationResult {
+                message: "Machine pool worker references subnet subnet-1 in AZ us-east-1a which is not one of the pool's AZs: us-east-1b".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::MachinePoolSubnetAzMismatch),
+                check_id: Some("MP-001"),
+                resource_type: Some(ResourceType::MachinePool),
+                resource_id: Some("worker".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+
+    #[test]
+    fn test_verify_machine_pool_subnets_missing_subnet() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_pools(vec![MachinePool {
+                id: "worker".to_string(),
+                instance_type: None,
+                availability_zones: vec![],
+                subnets: vec!["subnet-2".to_string()],
+                max_replicas: None,
+            }])
+            .build()
+            .unwrap();
+        let mpc = MachinePoolChecksBuilder::default()
+            .cluster_info(&mci)
+            .subnets(vec![make_subnet("subnet-1", "us-east-1a")])
+            .build()
+            .unwrap();
+        assert_eq!(
+            mpc.verify_machine_pool_subnets(),
+            vec![VerificationResult {
+                message: "Machine pool worker references subnet subnet-2 that does not exist in the cluster VPC".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::MachinePoolSubnetNotFound),
+                check_id: Some("MP-001"),
+                resource_type: Some(ResourceType::MachinePool),
+                resource_id: Some("worker".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+}
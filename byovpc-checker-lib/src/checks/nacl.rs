@@ -0,0 +1,370 @@
+//! This checker verifies the network ACLs associated with the cluster subnets.
+//! Network ACLs are stateless, so return traffic on the ephemeral ports has
+//! to be allowed explicitly - blocked traffic looks just like a security group
+//! problem otherwise.
+//! It can check the following conditions right now:
+//!
+//! - Inbound traffic to 443 and 6443 and return traffic on the ephemeral ports
+//!   is not denied.
+//! - Outbound traffic to 443 and return traffic on the ephemeral ports is not
+//!   denied.
+//! - The network ACLs differ from the default allow-all rules.
+//!
+//! Only rules for `0.0.0.0/0` or rules that contain the CIDR block of the
+//! subnet are considered.
+
+use aws_sdk_ec2::types::{NetworkAcl, NetworkAclEntry, RuleAction, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    checks::egress::{cidr_contains, parse_cidr},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+const ALL_PROTOCOLS: &str = "-1";
+const TCP: &str = "6";
+/// The rule number of the rule every network ACL ends with.
+const DEFAULT_DENY_RULE: i32 = 32767;
+
+/// Traffic the cluster needs, as a range of TCP ports.
+struct RequiredTraffic {
+    egress: bool,
+    from: i32,
+    to: i32,
+    description: &'static str,
+}
+
+const REQUIRED_TRAFFIC: [RequiredTraffic; 5] = [
+    RequiredTraffic {
+        egress: false,
+        from: 443,
+        to: 443,
+        description: "traffic to port 443",
+    },
+    RequiredTraffic {
+        egress: false,
+        from: 6443,
+        to: 6443,
+        description: "traffic to port 6443",
+    },
+    RequiredTraffic {
+        egress: false,
+        from: 1024,
+        to: 65535,
+        description: "return traffic on the ephemeral ports (1024-65535)",
+    },
+    RequiredTraffic {
+        egress: true,
+        from: 443,
+        to: 443,
+        description: "traffic to port 443",
+    },
+    RequiredTraffic {
+        egress: true,
+        from: 1024,
+        to: 65535,
+        description: "return traffic on the ephemeral ports (1024-65535)",
+    },
+];
+
+#[derive(Debug, Builder)]
+pub struct NetworkAclChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    network_acls: Vec<NetworkAcl>,
+}
+
+/// Whether the entry applies to TCP traffic on any of the ports between `from`
+/// and `to`.
+fn matches_ports(entry: &NetworkAclEntry, from: i32, to: i32) -> bool {
+    match entry.protocol() {
+        Some(ALL_PROTOCOLS) => true,
+        Some(TCP) => entry
+            .port_range()
+            .is_none_or(|r| r.from().unwrap_or(0) <= to && r.to().unwrap_or(65535) >= from),
+        _ => false,
+    }
+}
+
+/// Whether the entry covers all ports between `from` and `to`.
+fn covers_ports(entry: &NetworkAclEntry, from: i32, to: i32) -> bool {
+    match entry.protocol() {
+        Some(ALL_PROTOCOLS) => true,
+        Some(TCP) => entry
+            .port_range()
+            .is_none_or(|r| r.from().unwrap_or(0) <= from && r.to().unwrap_or(65535) >= to),
+        _ => false,
+    }
+}
+
+/// Whether the entry is one of the rules of the default network ACL: allow
+/// all traffic (rule 100) and deny everything else.
+fn is_default_entry(entry: &NetworkAclEntry) -> bool {
+    entry.cidr_block() == Some("0.0.0.0/0")
+        && entry.protocol() == Some(ALL_PROTOCOLS)
+        && match entry.rule_number() {
+            Some(100) => entry.rule_action() == Some(&RuleAction::Allow),
+            Some(DEFAULT_DENY_RULE) => entry.rule_action() == Some(&RuleAction::Deny),
+            _ => false,
+        }
+}
+
+impl<'a> NetworkAclChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    fn network_acl_for(&self, subnet_id: &str) -> Option<&NetworkAcl> {
+        self.network_acls.iter().find(|acl| {
+            acl.associations()
+                .iter()
+                .any(|a| a.subnet_id() == Some(subnet_id))
+        })
+    }
+
+    /// The entry that blocks (some of) the traffic: the first deny rule
+    /// matching the ports before a rule allows all of them. Rules are
+    /// evaluated in the order of their rule numbers.
+    fn blocking_entry<'b>(
+        acl: &'b NetworkAcl,
+        subnet_cidr: (u32, u8),
+        traffic: &RequiredTraffic,
+    ) -> Option<&'b NetworkAclEntry> {
+        let mut entries: Vec<&NetworkAclEntry> = acl
+            .entries()
+            .iter()
+            .filter(|e| e.egress() == Some(traffic.egress))
+            .filter(|e| {
+                e.cidr_block()
+                    .and_then(parse_cidr)
+                    .is_some_and(|c| cidr_contains(c, subnet_cidr))
+            })
+            .collect();
+        entries.sort_by_key(|e| e.rule_number());
+        for entry in entries {
+            if !matches_ports(entry, traffic.from, traffic.to) {
+                continue;
+            }
+            if entry.rule_action() == Some(&RuleAction::Deny) {
+                return Some(entry);
+            }
+            if covers_ports(entry, traffic.from, traffic.to) {
+                return None;
+            }
+        }
+        None
+    }
+
+    pub fn verify_required_traffic(&self) -> Vec<VerificationResult> {
+        info!("Checking network ACLs allow required traffic");
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let (Some(subnet_id), Some(subnet_cidr)) =
+                (subnet.subnet_id(), subnet.cidr_block().and_then(parse_cidr))
+            else {
+                continue;
+            };
+            let Some(acl) = self.network_acl_for(subnet_id) else {
+                continue;
+            };
+            for traffic in REQUIRED_TRAFFIC.iter() {
+                if let Some(entry) = Self::blocking_entry(acl, subnet_cidr, traffic) {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Network ACL {} of subnet {} denies {} {} (rule {})",
+                            acl.network_acl_id().unwrap_or_default(),
+                            subnet_id,
+                            if traffic.egress {
+                                "outbound"
+                            } else {
+                                "inbound"
+                            },
+                            traffic.description,
+                            entry.rule_number().unwrap_or_default()
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::NaclBlocksRequiredTraffic),
+                        check_id: Some("NACL-001"),
+                        resource_type: Some(ResourceType::NetworkAcl),
+                        resource_id: Some(acl.network_acl_id().unwrap_or_default().to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    pub fn verify_default_rules(&self) -> Vec<VerificationResult> {
+        info!("Checking network ACLs use the default rules");
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let Some(subnet_id) = subnet.subnet_id() else {
+                continue;
+            };
+            let Some(acl) = self.network_acl_for(subnet_id) else {
+                continue;
+            };
+            let custom_rules = acl
+                .entries()
+                .iter()
+                .filter(|e| !is_default_entry(e))
+                .count();
+            if custom_rules > 0 {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Network ACL {} of subnet {} has {} rules that differ from the default allow-all rules",
+                        acl.network_acl_id().unwrap_or_default(),
+                        subnet_id,
+                        custom_rules
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("NACL-002"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some(acl.network_acl_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for NetworkAclChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_required_traffic());
+        results.extend(self.verify_default_rules());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "Network ACLs of the cluster subnets allow all traffic".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{NetworkAclAssociation, PortRange};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    fn entry(
+        rule: i32,
+        egress: bool,
+        protocol: &str,
+        ports: Option<(i32, i32)>,
+        action: RuleAction,
+    ) -> NetworkAclEntry {
+        NetworkAclEntry::builder()
+            .rule_number(rule)
+            .egress(egress)
+            .protocol(protocol)
+            .set_port_range(ports.map(|(from, to)| PortRange::builder().from(from).to(to).build()))
+            .cidr_block("0.0.0.0/0")
+            .rule_action(action)
+            .build()
+    }
+
+    #[test]
+    fn test_verify_network_acls() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let subnet = Subnet::builder()
+            .subnet_id("subnet-1")
+            .cidr_block("10.0.0.0/24")
+            .build();
+        let acl = NetworkAcl::builder()
+            .network_acl_id("acl-1")
+            .associations(
+                NetworkAclAssociation::builder()
+                    .subnet_id("subnet-1")
+                    .build(),
+            )
+            .entries(entry(90, false, TCP, Some((6443, 6443)), RuleAction::Deny))
+            .entries(entry(100, false, ALL_PROTOCOLS, None, RuleAction::Allow))
+            .entries(entry(
+                DEFAULT_DENY_RULE,
+                false,
+                ALL_PROTOCOLS,
+                None,
+                RuleAction::Deny,
+            ))
+            .entries(entry(100, true, TCP, Some((443, 443)), RuleAction::Allow))
+            .entries(entry(
+                DEFAULT_DENY_RULE,
+                true,
+                ALL_PROTOCOLS,
+                None,
+                RuleAction::Deny,
+            ))
+            .build();
+        let nac = NetworkAclChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![subnet])
+            .network_acls(vec![acl])
+            .build()
+            .unwrap();
+        assert_eq!(
+            nac.verify(),
+            vec![
+                VerificationResult {
+                    message: "Network ACL acl-1 of subnet subnet-1 denies inbound traffic to port 6443 (rule 90)".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::NaclBlocksRequiredTraffic),
+                    check_id: Some("NACL-001"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some("acl-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Network ACL acl-1 of subnet subnet-1 denies inbound return traffic on the ephemeral ports (1024-65535) (rule 90)".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::NaclBlocksRequiredTraffic),
+                    check_id: Some("NACL-001"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some("acl-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Network ACL acl-1 of subnet subnet-1 denies outbound return traffic on the ephemeral ports (1024-65535) (rule 32767)".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::NaclBlocksRequiredTraffic),
+                    check_id: Some("NACL-001"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some("acl-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Network ACL acl-1 of subnet subnet-1 has 2 rules that differ from the default allow-all rules".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("NACL-002"),
+                    resource_type: Some(ResourceType::NetworkAcl),
+                    resource_id: Some("acl-1".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
@@ -0,0 +1,1790 @@
+//! This checker provides networking setup checks.
+//! It can check the following conditions right now:
+//!
+//! - Number of subnets in the VPC matches expectation (2 subnets per AZ)
+//! - The subnets in the VPC have the expected tags. The value of the cluster
+//!   tag of the cluster subnets can be configured (owned, shared or either).
+//! - Private subnets route through an available NAT gateway in a public subnet.
+//! - No route of the subnets is a blackhole (e.g. pointing to a detached
+//!   internet gateway) and every private subnet has a default route.
+//! - The internet gateways of the public subnets are attached to their VPC
+//!   and available.
+//! - The ENIs of every LoadBalancer match the AZs the LoadBalancer is enabled in.
+
+use crate::{
+    checks::sharedvpc::shared_owner,
+    gatherer::aws::shared_types::{AWSLoadBalancer, AWSLoadBalancerEni, HostedZoneWithRecords},
+    types::{MinimalClusterInfo, TagDiff, TagValuePolicy, VerificationResult, Verifier},
+};
+use aws_sdk_ec2::types::{RouteTableAssociationStateCode, Subnet};
+use derive_builder::Builder;
+use log::{debug, info};
+use serde_json::json;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+pub const PRIVATE_ELB_TAG: &str = "kubernetes.io/role/internal-elb";
+pub const PUBLIC_ELB_TAG: &str = "kubernetes.io/role/elb";
+pub const CLUSTER_TAG: &str = "kubernetes.io/cluster/";
+pub const DEFAULT_ROUTE: &str = "0.0.0.0/0";
+pub const DEFAULT_IPV6_ROUTE: &str = "::/0";
+
+#[derive(Debug, Builder)]
+pub struct ClusterNetwork<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    all_subnets: Vec<aws_sdk_ec2::types::Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<aws_sdk_ec2::types::RouteTable>,
+    #[builder(default = "self.derive_subnet_routetable_mapping()")]
+    subnet_routetable_mapping: HashMap<String, aws_sdk_ec2::types::RouteTable>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    load_balancer_enis: Vec<AWSLoadBalancerEni>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<aws_sdk_ec2::types::NatGateway>,
+    /// The internet gateways attached to the cluster VPCs.
+    #[builder(default = "vec![]")]
+    internet_gateways: Vec<aws_sdk_ec2::types::InternetGateway>,
+    /// The values the cluster tag of the cluster subnets may have.
+    #[builder(default = "TagValuePolicy::Either")]
+    subnet_tag_value: TagValuePolicy,
+    /// The account the data was gathered with, to detect shared subnets.
+    #[builder(default)]
+    account_id: Option<String>,
+}
+
+impl<'a> ClusterNetworkBuilder<'a> {
+    fn derive_subnet_routetable_mapping(&self) -> HashMap<String, aws_sdk_ec2::types::RouteTable> {
+        if self.all_subnets.is_none() || self.routetables.is_none() {
+            return HashMap::new();
+        }
+        subnet_routetable_mapping(
+            self.all_subnets.as_ref().unwrap(),
+            self.routetables.as_ref().unwrap(),
+        )
+    }
+}
+
+/// Maps every subnet to the route table explicitly associated with it.
+pub fn subnet_routetable_mapping(
+    subnets: &[aws_sdk_ec2::types::Subnet],
+    routetables: &[aws_sdk_ec2::types::RouteTable],
+) -> HashMap<String, aws_sdk_ec2::types::RouteTable> {
+    let mut subnet_to_routetables: HashMap<String, aws_sdk_ec2::types::RouteTable> = HashMap::new();
+    for subnet in subnets.iter() {
+        let mut rtb: Vec<&aws_sdk_ec2::types::RouteTable> = routetables
+            .iter()
+            .filter(|rtb| {
+                rtb.associations
+                    .iter()
+                    .any(|a| a.iter().any(|b| b.subnet_id() == subnet.subnet_id()))
+            })
+            .collect();
+        // Prefer the route table whose association is actually active.
+        rtb.sort_by_key(|rt| {
+            !rt.associations()
+                .iter()
+                .any(|a| a.subnet_id() == subnet.subnet_id() && association_is_active(a))
+        });
+        if let Some(rt) = rtb.first() {
+            let drt = (**rt).clone();
+            subnet_to_routetables.insert(subnet.subnet_id.clone().unwrap(), drt);
+        }
+    }
+    subnet_to_routetables
+}
+
+/// The target of the route, e.g. `igw-1` or `nat-1`.
+pub fn route_target(route: &aws_sdk_ec2::types::Route) -> &str {
+    route
+        .gateway_id()
+        .or(route.nat_gateway_id())
+        .or(route.egress_only_internet_gateway_id())
+        .or(route.transit_gateway_id())
+        .or(route.network_interface_id())
+        .or(route.vpc_peering_connection_id())
+        .or(route.instance_id())
+        .unwrap_or("unknown")
+}
+
+/// The route as evidence of a finding.
+fn route_evidence(
+    rtb: &aws_sdk_ec2::types::RouteTable,
+    route: &aws_sdk_ec2::types::Route,
+) -> serde_json::Value {
+    json!({
+        "route_table_id": rtb.route_table_id(),
+        "destination": route
+            .destination_cidr_block()
+            .or(route.destination_ipv6_cidr_block())
+            .or(route.destination_prefix_list_id()),
+        "target": route_target(route),
+        "state": route.state().map(|s| s.as_str()),
+    })
+}
+
+/// The tags of the resource as evidence of a finding.
+fn association_is_active(association: &aws_sdk_ec2::types::RouteTableAssociation) -> bool {
+    association
+        .association_state()
+        .and_then(|s| s.state())
+        .is_none_or(|s| *s == RouteTableAssociationStateCode::Associated)
+}
+
+impl<'a> ClusterNetwork<'a> {
+    fn configured_subnets(&self) -> Vec<Subnet> {
+        self.all_subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .cloned()
+            .collect()
+    }
+
+    /// Subnets with a default route through an internet gateway - a
+    /// blackhole route to a detached gateway does not make a subnet public.
+    fn get_public_subnets(&self) -> Vec<String> {
+        let mut public_subnets = Vec::new();
+        for (subnet, rtb) in self.subnet_routetable_mapping.iter() {
+            let routes = rtb.routes.as_ref().map(|r| r);
+            if let Some(rs) = routes {
+                for r in rs {
+                    let is_0_cidr = r
+                        .destination_cidr_block
+                        .clone()
+                        .is_some_and(|f| f == DEFAULT_ROUTE);
+                    if is_0_cidr
+                        && r.gateway_id.as_ref().is_some_and(|g| g.starts_with("igw-"))
+                        && r.state() != Some(&aws_sdk_ec2::types::RouteState::Blackhole)
+                    {
+                        public_subnets.push(subnet.clone())
+                    }
+                }
+            }
+        }
+        return public_subnets;
+    }
+
+    fn get_private_subnets(&self) -> Vec<String> {
+        let mut private_subnets = Vec::new();
+        for (subnet, rtb) in self.subnet_routetable_mapping.iter() {
+            let routes = rtb.routes.as_ref().map(|r| r);
+            if let Some(rs) = routes {
+                let has_0_cidr = rs.iter().any(|r| {
+                    r.destination_cidr_block
+                        .clone()
+                        .is_some_and(|f| f == DEFAULT_ROUTE)
+                });
+                if !has_0_cidr {
+                    private_subnets.push(subnet.clone());
+                    break;
+                }
+                for r in rs {
+                    let is_0_cidr = r
+                        .destination_cidr_block
+                        .clone()
+                        .is_some_and(|f| f == DEFAULT_ROUTE);
+                    if is_0_cidr && (r.nat_gateway_id.is_some()) {
+                        private_subnets.push(subnet.clone());
+                    }
+                }
+            }
+        }
+        return private_subnets;
+    }
+
+    pub fn verify_number_of_subnets(&self) -> Vec<VerificationResult> {
+        info!("Checking number of subnets per AZ");
+        let mut subnets_per_az: HashMap<(String, String), u8> = HashMap::new();
+        for subnet in self.all_subnets.iter() {
+            let az = subnet.availability_zone.clone().unwrap();
+            info!("Checking {} in {}", subnet.subnet_id.as_ref().unwrap(), az);
+            *subnets_per_az
+                .entry((subnet.vpc_id.clone().unwrap(), az))
+                .or_insert(0) += 1;
+        }
+        let mut problematic_azs: Vec<(String, String)> = subnets_per_az
+            .into_iter()
+            .filter(|(_, number)| *number > 2)
+            .map(|(az, _)| az)
+            .collect();
+        problematic_azs.sort();
+        if problematic_azs.is_empty() {
+            return vec![VerificationResult {
+                message: "AZs have the expected number of subnets".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        problematic_azs
+            .into_iter()
+            .map(|(vpc_id, az)| VerificationResult {
+                message: format!("There are too many subnets in VPC {} (AZ: {})", vpc_id, az),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::TooManySubnetsPerAz),
+                check_id: Some("NET-001"),
+                resource_type: Some(crate::types::ResourceType::Vpc),
+                resource_id: Some(vpc_id),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    /// Checks that the subnets are tagged correctly for:
+    /// - The cluster
+    /// - Public/Private subnet tags
+    pub fn verify_subnet_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking tags per subnet");
+        let mut verification_results = Vec::new();
+        for subnet in self.all_subnets.iter() {
+            let mut missing_cluster_tag = true;
+            let mut incorrect_cluster_tag = String::new();
+            let mut incorrect_cluster_tag_expected = (String::new(), String::new());
+            let mut missing_private_elb_tag = true;
+            let mut missing_public_elb_tag = true;
+            let subnet_id = subnet.subnet_id().unwrap().to_string();
+            let tags = subnet.tags();
+            debug!("Checking subnet: {}", subnet_id);
+            for tag in tags {
+                if let (Some(key), Some(value)) = (&tag.key, &tag.value) {
+                    if key.contains(&CLUSTER_TAG) {
+                        missing_cluster_tag = false;
+                        let own_tag = key.strip_prefix(CLUSTER_TAG).is_some_and(|c| {
+                            !c.is_empty()
+                                && (c == self.cluster_info.cluster_id
+                                    || c == self.cluster_info.cluster_infra_name)
+                        });
+                        if own_tag && !self.subnet_tag_value.allows(value) {
+                            incorrect_cluster_tag = format!(
+                                "{}={} (expected {})",
+                                key,
+                                value,
+                                self.subnet_tag_value.as_str()
+                            );
+                            incorrect_cluster_tag_expected = (
+                                key.clone(),
+                                self.subnet_tag_value.suggested_value().to_string(),
+                            );
+                        } else if !own_tag && value == "owned" {
+                            incorrect_cluster_tag = key.clone();
+                            incorrect_cluster_tag_expected = (key.clone(), "shared".to_string());
+                        }
+                    }
+                    if !self.get_private_subnets().contains(&subnet_id) {
+                        missing_private_elb_tag = false;
+                    }
+                    if !self.get_public_subnets().contains(&subnet_id) {
+                        missing_public_elb_tag = false;
+                    }
+                    if self.get_private_subnets().contains(&subnet_id)
+                        && key.contains(&PRIVATE_ELB_TAG)
+                    {
+                        missing_private_elb_tag = false;
+                    }
+                    if self.get_public_subnets().contains(&subnet_id)
+                        && key.contains(&PUBLIC_ELB_TAG)
+                    {
+                        missing_public_elb_tag = false;
+                    }
+                }
+            }
+            let has_incorrect_cluster_tag = incorrect_cluster_tag.len() > 0;
+            let diff = TagDiff::new(
+                &subnet_id,
+                tags.iter()
+                    .filter_map(|t| Some((t.key()?, t.value().unwrap_or_default()))),
+            );
+            let cluster_tag = format!("{}{}", CLUSTER_TAG, self.cluster_info.cluster_infra_name);
+            // Tags of the owner of a shared subnet are not visible to the
+            // account of the cluster.
+            let shared_note = shared_owner(subnet, self.account_id.as_deref())
+                .map(|owner| {
+                    format!(
+                        " (shared from account {} - add the tag from the account of the cluster)",
+                        owner
+                    )
+                })
+                .unwrap_or_default();
+            if missing_cluster_tag {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is missing cluster tag: {}{}",
+                        subnet_id.clone(),
+                        cluster_tag,
+                        shared_note
+                    ),
+                    severity: crate::types::Severity::Info,
+                    code: Some(crate::types::FindingCode::MissingClusterTag),
+                    check_id: Some("NET-002"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: Some(
+                        diff.evidence(&cluster_tag, self.subnet_tag_value.suggested_value()),
+                    ),
+                });
+            }
+            if has_incorrect_cluster_tag {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is using incorrect cluster tag: {}",
+                        subnet_id.clone(),
+                        incorrect_cluster_tag
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::IncorrectClusterTag),
+                    check_id: Some("NET-002"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: Some(diff.evidence(
+                        &incorrect_cluster_tag_expected.0,
+                        &incorrect_cluster_tag_expected.1,
+                    )),
+                });
+            }
+            if missing_private_elb_tag {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is missing private ELB tag{}",
+                        subnet_id.clone(),
+                        shared_note
+                    ),
+                    severity: crate::types::Severity::Info,
+                    code: Some(crate::types::FindingCode::MissingInternalElbTag),
+                    check_id: Some("NET-002"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: Some(diff.evidence(PRIVATE_ELB_TAG, "1")),
+                });
+            }
+            if missing_public_elb_tag {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is missing public ELB tag{}",
+                        subnet_id.clone(),
+                        shared_note
+                    ),
+                    severity: crate::types::Severity::Info,
+                    code: Some(crate::types::FindingCode::MissingPublicElbTag),
+                    check_id: Some("NET-002"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: Some(diff.evidence(PUBLIC_ELB_TAG, "1")),
+                });
+            }
+            if !missing_cluster_tag
+                && !has_incorrect_cluster_tag
+                && !missing_public_elb_tag
+                && !missing_private_elb_tag
+            {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is correctly setup: expected tags are present.",
+                        subnet_id
+                    ),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("NET-002"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: None,
+                })
+            }
+        }
+        verification_results
+    }
+
+    /// Checks that the subnets are using the routetables created by the installer
+    /// Only applicable for non-BYOVPC clusters
+    pub fn verify_subnet_routetables(&self) -> Vec<VerificationResult> {
+        if !self.cluster_info.subnets.is_empty() {
+            return vec![VerificationResult {
+                message: "The cluster is BYOVPC - will not check routetables for subnets"
+                    .to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-003"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        vec![]
+    }
+
+    /// Checks that every subnet has at most one explicit route table
+    /// association and that the association is in the `associated` state.
+    /// Anything else indicates an in-progress or broken manual change.
+    pub fn verify_subnet_routetable_associations(&self) -> Vec<VerificationResult> {
+        info!("Checking route table associations per subnet");
+        let mut verification_results = vec![];
+        for subnet in self.all_subnets.iter() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let associations: Vec<(&str, &aws_sdk_ec2::types::RouteTableAssociation)> = self
+                .routetables
+                .iter()
+                .flat_map(|rtb| {
+                    rtb.associations()
+                        .iter()
+                        .filter(|a| a.subnet_id() == Some(subnet_id))
+                        .map(|a| (rtb.route_table_id().unwrap_or_default(), a))
+                })
+                .collect();
+            if associations.is_empty() {
+                continue;
+            }
+            let mut problem = false;
+            if associations.len() > 1 {
+                let rtbs: Vec<String> = associations
+                    .iter()
+                    .map(|(rtb, a)| {
+                        format!(
+                            "{} ({})",
+                            rtb,
+                            a.association_state()
+                                .and_then(|s| s.state())
+                                .map_or("unknown", |s| s.as_str())
+                        )
+                    })
+                    .collect();
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has multiple route table associations: {}",
+                        subnet_id,
+                        rtbs.join(", ")
+                    ),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::MultipleRouteTableAssociations),
+                    check_id: Some("NET-004"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+                problem = true;
+            }
+            for (rtb, association) in associations.iter() {
+                if !association_is_active(association) {
+                    verification_results.push(VerificationResult {
+                        message: format!(
+                            "Subnet {} association with route table {} is in state: {}",
+                            subnet_id,
+                            rtb,
+                            association
+                                .association_state()
+                                .and_then(|s| s.state())
+                                .map_or("unknown", |s| s.as_str())
+                        ),
+                        severity: crate::types::Severity::Warning,
+                        code: Some(crate::types::FindingCode::InactiveRouteTableAssociation),
+                        check_id: Some("NET-004"),
+                        resource_type: Some(crate::types::ResourceType::Subnet),
+                        resource_id: Some(subnet_id.to_string()),
+                        evidence: None,
+                    });
+                    problem = true;
+                }
+            }
+            if !problem {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has a single active route table association",
+                        subnet_id
+                    ),
+                    severity: crate::types::Severity::Ok,
+                    code: None,
+                    check_id: Some("NET-004"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        verification_results
+    }
+
+    /// Verifies that a LB is using the subnets that are actually configured for the cluster.
+    /// This can be incorrect, if subnet tagging was done incorrectly:
+    /// See https://access.redhat.com/documentation/en-us/red_hat_openshift_service_on_aws/4/html-single/networking/index#aws-installing-an-aws-load-balancer-operator_aws-load-balancer-operator
+    pub fn verify_loadbalancer_subnets(&self) -> Vec<VerificationResult> {
+        let mut verification_results = vec![];
+        let configured_subnets = self.configured_subnets();
+        let configured_subnet_ids: HashSet<&str> = configured_subnets
+            .iter()
+            .map(|s| s.subnet_id().unwrap())
+            .collect();
+        debug!("Configured subnets {:?}", configured_subnet_ids);
+        for alb in self.load_balancers.iter() {
+            // FIXME: This check should (partially) work for CLBs as well
+            let AWSLoadBalancer::ModernLoadBalancer((lb, _)) = alb else {
+                continue;
+            };
+            for az in lb.availability_zones() {
+                let sid = az.subnet_id().unwrap();
+                if !configured_subnet_ids.contains(sid) {
+                    verification_results.push(VerificationResult {
+                        message: format!("LoadBalancer {} is using subnet {} (AZ: {}) that is not configured for this cluster.",
+                        lb.load_balancer_arn.as_ref().unwrap().clone(),
+                        az.zone_name.as_ref().unwrap().to_string(),
+                        sid.to_string()),
+                        severity: crate::types::Severity::Warning,
+                        code: Some(crate::types::FindingCode::LbInForeignSubnet),
+                        check_id: Some("NET-006"),
+                        resource_type: Some(crate::types::ResourceType::LoadBalancer),
+                        resource_id: Some(lb.load_balancer_arn().unwrap_or_default().to_string()),
+                        evidence: None,
+                    })
+                }
+            }
+        }
+        if verification_results.len() == 0 {
+            verification_results.push(VerificationResult {
+                message: "LoadBalancer subnet associations are correct".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-006"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        verification_results
+    }
+
+    pub fn verify_loadbalancer_eni_subnets(&self) -> Vec<VerificationResult> {
+        if self.load_balancer_enis.is_empty() {
+            return vec![VerificationResult {
+                message: "No ENIs found".to_string(),
+                severity: crate::types::Severity::Critical,
+                code: None,
+                check_id: Some("NET-007"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut verification_results = vec![];
+        let configured_subnets = self.configured_subnets();
+        let configured_subnet_ids: HashSet<&str> = configured_subnets
+            .iter()
+            .map(|s| s.subnet_id().unwrap())
+            .collect();
+        for eni in self.load_balancer_enis.iter().map(|lb_eni| &lb_eni.eni) {
+            if let Some(sid) = &eni.subnet_id {
+                if !configured_subnet_ids.iter().any(|csid| csid == sid) {
+                    verification_results.push(VerificationResult {
+                        message: format!(
+                            "LoadBalancer ENI {} is using a non-cluster subnet: {}",
+                            eni.network_interface_id.as_ref().unwrap(),
+                            sid
+                        ),
+                        severity: crate::types::Severity::Warning,
+                        code: Some(crate::types::FindingCode::LbInForeignSubnet),
+                        check_id: Some("NET-007"),
+                        resource_type: Some(crate::types::ResourceType::NetworkInterface),
+                        resource_id: Some(
+                            eni.network_interface_id().unwrap_or_default().to_string(),
+                        ),
+                        evidence: None,
+                    });
+                } else {
+                    verification_results.push(VerificationResult {
+                        message: format!(
+                            "LoadBalancer ENI {} is using cluster subnet: {}",
+                            eni.network_interface_id.as_ref().unwrap(),
+                            sid
+                        ),
+                        severity: crate::types::Severity::Ok,
+                        code: None,
+                        check_id: Some("NET-007"),
+                        resource_type: Some(crate::types::ResourceType::NetworkInterface),
+                        resource_id: Some(
+                            eni.network_interface_id().unwrap_or_default().to_string(),
+                        ),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        verification_results
+    }
+
+    /// Verifies the ENIs of every LB match the AZs the LB is enabled in. An AZ
+    /// without ENI indicates provisioning is stuck, an ENI in an AZ the LB is
+    /// no longer enabled in is leaked.
+    pub fn verify_loadbalancer_eni_azs(&self) -> Vec<VerificationResult> {
+        let mut verification_results = vec![];
+        for lb in self.load_balancers.iter() {
+            let lb_azs: BTreeSet<String> = lb.availability_zones().into_iter().collect();
+            let enis: Vec<&aws_sdk_ec2::types::NetworkInterface> = self
+                .load_balancer_enis
+                .iter()
+                .filter(|lb_eni| lb_eni.load_balancer == lb.identifier())
+                .map(|lb_eni| &lb_eni.eni)
+                .collect();
+            let eni_azs: BTreeSet<String> = enis
+                .iter()
+                .filter_map(|eni| eni.availability_zone.clone())
+                .collect();
+            for az in lb_azs.difference(&eni_azs) {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "LoadBalancer {} is enabled in AZ {} but has no ENI there - provisioning might be stuck",
+                        lb.name(),
+                        az
+                    ),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::LbProvisioningStuck),
+                    check_id: Some("NET-008"),
+                    resource_type: Some(crate::types::ResourceType::LoadBalancer),
+                    resource_id: Some(lb.identifier().to_string()),
+                    evidence: None,
+                });
+            }
+            for eni in enis.iter() {
+                let Some(az) = eni.availability_zone() else {
+                    continue;
+                };
+                if !lb_azs.contains(az) {
+                    verification_results.push(VerificationResult {
+                        message: format!(
+                            "LoadBalancer {} has ENI {} in AZ {} that the LoadBalancer is not enabled in - the ENI is leaked",
+                            lb.name(),
+                            eni.network_interface_id().unwrap_or_default(),
+                            az
+                        ),
+                        severity: crate::types::Severity::Warning,
+                        code: Some(crate::types::FindingCode::LbEniLeaked),
+                        check_id: Some("NET-008"),
+                        resource_type: Some(crate::types::ResourceType::NetworkInterface),
+                        resource_id: Some(eni.network_interface_id().unwrap_or_default().to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        if verification_results.is_empty() {
+            verification_results.push(VerificationResult {
+                message: "LoadBalancer ENIs match the LoadBalancer AZs".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-008"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        verification_results
+    }
+
+    /// Verifies the default route of every private cluster subnet points at a
+    /// NAT gateway that is available and located in a public subnet of the
+    /// same VPC. Subnets without a default route or egressing through other
+    /// targets (e.g. transit gateways) are skipped.
+    pub fn verify_nat_gateways(&self) -> Vec<VerificationResult> {
+        info!("Checking NAT gateways of private subnets");
+        let mut verification_results = vec![];
+        let public_subnets = self.get_public_subnets();
+        let configured_subnets = self.configured_subnets();
+        let mut private_subnets: Vec<&Subnet> = configured_subnets
+            .iter()
+            .filter(|s| {
+                s.subnet_id()
+                    .is_some_and(|id| !public_subnets.iter().any(|p| p == id))
+            })
+            .collect();
+        private_subnets.sort_by_key(|s| s.subnet_id());
+        for subnet in private_subnets {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let Some((rtb, route)) =
+                self.subnet_routetable_mapping
+                    .get(subnet_id)
+                    .and_then(|rtb| {
+                        rtb.routes()
+                            .iter()
+                            .find(|r| r.destination_cidr_block() == Some(DEFAULT_ROUTE))
+                            .map(|r| (rtb, r))
+                    })
+            else {
+                continue;
+            };
+            let Some(nat_id) = route.nat_gateway_id() else {
+                continue;
+            };
+            let Some(nat) = self
+                .nat_gateways
+                .iter()
+                .find(|n| n.nat_gateway_id() == Some(nat_id))
+            else {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} routes through NAT gateway {} that does not exist",
+                        subnet_id, nat_id
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwDeleted),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({ "route": route_evidence(rtb, route) })),
+                });
+                continue;
+            };
+            let evidence = json!({
+                "route": route_evidence(rtb, route),
+                "nat_gateway": {
+                    "nat_gateway_id": nat_id,
+                    "state": nat.state().map(|s| s.as_str()),
+                    "vpc_id": nat.vpc_id(),
+                    "subnet_id": nat.subnet_id(),
+                },
+            });
+            if nat.state() != Some(&aws_sdk_ec2::types::NatGatewayState::Available) {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} routes through NAT gateway {} in state: {}",
+                        subnet_id,
+                        nat_id,
+                        nat.state().map_or("unknown", |s| s.as_str())
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwUnavailable),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(evidence.clone()),
+                });
+            }
+            if nat.vpc_id() != subnet.vpc_id() {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} routes through NAT gateway {} in a different VPC: {}",
+                        subnet_id,
+                        nat_id,
+                        nat.vpc_id().unwrap_or_default()
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwInForeignVpc),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(evidence.clone()),
+                });
+            } else if !nat
+                .subnet_id()
+                .is_some_and(|id| public_subnets.iter().any(|p| p == id))
+            {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} routes through NAT gateway {} that is not in a public subnet: {}",
+                        subnet_id,
+                        nat_id,
+                        nat.subnet_id().unwrap_or_default()
+                    ),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwInPrivateSubnet),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(evidence.clone()),
+                });
+            }
+        }
+        if verification_results.is_empty() {
+            verification_results.push(VerificationResult {
+                message: "NAT gateways of private subnets are available".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-009"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        verification_results
+    }
+
+    /// Verifies no route of a cluster subnet is a blackhole - the target of
+    /// the route was deleted or, for internet gateways, detached from the VPC -
+    /// and every private subnet has a default route, unless the cluster is
+    /// zero-egress.
+    pub fn verify_routes(&self) -> Vec<VerificationResult> {
+        info!("Checking routes of the subnets");
+        let mut verification_results = vec![];
+        let public_subnets = self.get_public_subnets();
+        let mut configured_subnets = self.configured_subnets();
+        configured_subnets.sort_by(|a, b| a.subnet_id().cmp(&b.subnet_id()));
+        for subnet in configured_subnets.iter() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let Some(rtb) = self.subnet_routetable_mapping.get(subnet_id) else {
+                continue;
+            };
+            for route in rtb.routes() {
+                if route.state() != Some(&aws_sdk_ec2::types::RouteState::Blackhole) {
+                    continue;
+                }
+                let destination = route
+                    .destination_cidr_block()
+                    .or(route.destination_ipv6_cidr_block())
+                    .or(route.destination_prefix_list_id())
+                    .unwrap_or("unknown");
+                let target = route_target(route);
+                verification_results.push(if target.starts_with("igw-") {
+                    VerificationResult {
+                        message: format!(
+                            "Subnet {} routes {} through internet gateway {} that is detached from the VPC",
+                            subnet_id, destination, target
+                        ),
+                        severity: crate::types::Severity::Critical,
+                        code: Some(crate::types::FindingCode::IgwDetached),
+                        check_id: Some("NET-010"),
+                        resource_type: Some(crate::types::ResourceType::Subnet),
+                        resource_id: Some(subnet_id.to_string()),
+                        evidence: Some(route_evidence(rtb, route)),
+                    }
+                } else {
+                    VerificationResult {
+                        message: format!(
+                            "Subnet {} has a blackhole route to {} through {} - the target no longer exists",
+                            subnet_id, destination, target
+                        ),
+                        severity: if destination == DEFAULT_ROUTE || destination == DEFAULT_IPV6_ROUTE {
+                            crate::types::Severity::Critical
+                        } else {
+                            crate::types::Severity::Warning
+                        },
+                        code: Some(crate::types::FindingCode::RouteBlackhole),
+                        check_id: Some("NET-010"),
+                        resource_type: Some(crate::types::ResourceType::Subnet),
+                        resource_id: Some(subnet_id.to_string()),
+                        evidence: Some(route_evidence(rtb, route)),
+                    }
+                });
+            }
+            let is_public = public_subnets.iter().any(|p| p == subnet_id);
+            if !is_public
+                && !self.cluster_info.zero_egress
+                && !rtb
+                    .routes()
+                    .iter()
+                    .any(|r| r.destination_cidr_block() == Some(DEFAULT_ROUTE))
+            {
+                verification_results.push(VerificationResult {
+                    message: format!(
+                        "Private subnet {} has no default route - it can only reach the internet through a proxy",
+                        subnet_id
+                    ),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::NoDefaultRoute),
+                    check_id: Some("NET-010"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({
+                        "routes": rtb
+                            .routes()
+                            .iter()
+                            .map(|r| route_evidence(rtb, r))
+                            .collect::<Vec<_>>(),
+                    })),
+                });
+            }
+        }
+        verification_results
+    }
+
+    /// Verifies the internet gateway the default route of every public subnet
+    /// points to is attached to the VPC of the subnet and available -
+    /// otherwise the subnet has no internet access although it looks public.
+    pub fn verify_internet_gateways(&self) -> Vec<VerificationResult> {
+        info!("Checking internet gateways of the public subnets");
+        let mut verification_results = vec![];
+        let public_subnets = self.get_public_subnets();
+        let mut configured_subnets = self.configured_subnets();
+        configured_subnets.sort_by(|a, b| a.subnet_id().cmp(&b.subnet_id()));
+        for subnet in configured_subnets.iter() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            if !public_subnets.iter().any(|p| p == subnet_id) {
+                continue;
+            }
+            let Some(rtb) = self.subnet_routetable_mapping.get(subnet_id) else {
+                continue;
+            };
+            let vpc_id = subnet.vpc_id().unwrap_or_default();
+            for route in rtb.routes() {
+                let Some(igw_id) = route.gateway_id().filter(|g| g.starts_with("igw-")) else {
+                    continue;
+                };
+                if route.destination_cidr_block() != Some(DEFAULT_ROUTE) {
+                    continue;
+                }
+                let attachment = self
+                    .internet_gateways
+                    .iter()
+                    .filter(|g| g.internet_gateway_id() == Some(igw_id))
+                    .flat_map(|g| g.attachments())
+                    .find(|a| a.vpc_id() == Some(vpc_id));
+                // Attached internet gateways report the state `available`.
+                let state = attachment.and_then(|a| a.state()).map(|s| s.as_str());
+                let message = match state {
+                    None if attachment.is_none() => format!(
+                        "Public subnet {} routes 0.0.0.0/0 through internet gateway {} that is not attached to VPC {}",
+                        subnet_id, igw_id, vpc_id
+                    ),
+                    Some("available") | Some("attached") => continue,
+                    state => format!(
+                        "Public subnet {} routes 0.0.0.0/0 through internet gateway {} that is {} instead of available",
+                        subnet_id,
+                        igw_id,
+                        state.unwrap_or("in an unknown state")
+                    ),
+                };
+                verification_results.push(VerificationResult {
+                    message,
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::IgwDetached),
+                    check_id: Some("NET-011"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(route_evidence(rtb, route)),
+                });
+            }
+        }
+        verification_results
+    }
+}
+
+impl<'a> Verifier for ClusterNetwork<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_number_of_subnets());
+        results.extend(self.verify_loadbalancer_subnets());
+        results.extend(self.verify_subnet_tags());
+        results.extend(self.verify_subnet_routetable_associations());
+        results.extend(self.verify_loadbalancer_eni_subnets());
+        results.extend(self.verify_loadbalancer_eni_azs());
+        results.extend(self.verify_nat_gateways());
+        results.extend(self.verify_routes());
+        results.extend(self.verify_internet_gateways());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        gatherer::aws::shared_types::CLUSTER_TAG_PREFIX, types::MinimalClusterInfoBuilder,
+    };
+
+    use super::*;
+
+    fn make_subnet(
+        subnet_id: &str,
+        az: &str,
+        tags: &HashMap<&str, &str>,
+    ) -> aws_sdk_ec2::types::Subnet {
+        let tags = tags
+            .iter()
+            .map(|(k, v)| {
+                aws_sdk_ec2::types::Tag::builder()
+                    .key(k.to_string())
+                    .value(v.to_string())
+                    .build()
+            })
+            .collect();
+        aws_sdk_ec2::types::Subnet::builder()
+            .subnet_id(subnet_id)
+            .availability_zone(az)
+            .set_tags(Some(tags))
+            .vpc_id("vpc-1")
+            .build()
+    }
+
+    fn make_private_subnet(
+        subnet_id: &str,
+        az: &str,
+        tags: &HashMap<&str, &str>,
+    ) -> (aws_sdk_ec2::types::Subnet, aws_sdk_ec2::types::RouteTable) {
+        let private_subnet = make_subnet(subnet_id, az, tags);
+        let private_rtb = aws_sdk_ec2::types::RouteTable::builder()
+            .associations(
+                aws_sdk_ec2::types::RouteTableAssociation::builder()
+                    .subnet_id(subnet_id)
+                    .build(),
+            )
+            .build();
+        (private_subnet, private_rtb)
+    }
+
+    fn make_public_subnet(
+        subnet_id: &str,
+        az: &str,
+        tags: &HashMap<&str, &str>,
+    ) -> (aws_sdk_ec2::types::Subnet, aws_sdk_ec2::types::RouteTable) {
+        let public_subnet = make_subnet(subnet_id, az, tags);
+        let public_rtb = aws_sdk_ec2::types::RouteTable::builder()
+            .associations(
+                aws_sdk_ec2::types::RouteTableAssociation::builder()
+                    .subnet_id(subnet_id)
+                    .build(),
+            )
+            .routes(
+                aws_sdk_ec2::types::Route::builder()
+                    .destination_cidr_block("0.0.0.0/0")
+                    .set_gateway_id(Some("1".to_string()))
+                    .build(),
+            )
+            .build();
+        (public_subnet, public_rtb)
+    }
+
+    #[test]
+    fn test_verify_number_of_subnets_success() {
+        let subnet = aws_sdk_ec2::types::Subnet::builder()
+            .subnet_id("1")
+            .vpc_id("vpc-1")
+            .availability_zone("us-east-1a")
+            .build();
+        let mut mcb = MinimalClusterInfoBuilder::default();
+        let mci = mcb
+            .cluster_id(String::from("1"))
+            .subnets(vec![String::from(subnet.subnet_id.clone().unwrap())])
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![subnet.clone()])
+            .build()
+            .unwrap();
+        let result = cn.verify_number_of_subnets();
+        assert_eq!(
+            result,
+            vec![VerificationResult {
+                message: "AZs have the expected number of subnets".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }]
+        )
+    }
+
+    #[test]
+    fn test_verify_number_of_subnets_fail() {
+        let mut subnets = vec![];
+        for i in 1..=3 {
+            subnets.push(
+                aws_sdk_ec2::types::Subnet::builder()
+                    .vpc_id("vpc-1")
+                    .subnet_id(i.to_string())
+                    .availability_zone("us-east-1a")
+                    .build(),
+            );
+        }
+        let subnet_ids = subnets.iter().map(|s| s.subnet_id.clone().unwrap());
+        let mut mcb = MinimalClusterInfoBuilder::default();
+        let mci = mcb
+            .cluster_id(String::from("1"))
+            .subnets(subnet_ids.collect())
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(subnets.clone())
+            .build()
+            .unwrap();
+        let result = cn.verify_number_of_subnets();
+        assert_eq!(
+            result,
+            vec![VerificationResult {
+                message: "There are too many subnets in VPC vpc-1 (AZ: us-east-1a)".to_string(),
+                severity: crate::types::Severity::Warning,
+                code: Some(crate::types::FindingCode::TooManySubnetsPerAz),
+                check_id: Some("NET-001"),
+                resource_type: Some(crate::types::ResourceType::Vpc),
+                resource_id: Some("vpc-1".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+
+    #[test]
+    fn test_verify_tags_missing_cluster_tag() {
+        let clusterid = "1";
+        let (public_subnet, public_rtb) =
+            make_public_subnet("1", "us-east-1a", &HashMap::from([(PUBLIC_ELB_TAG, "1")]));
+        let mut mcib = MinimalClusterInfoBuilder::default();
+        let mci = mcib
+            .cluster_id(clusterid.to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![public_subnet.clone()])
+            .routetables(vec![public_rtb.clone()])
+            .build()
+            .unwrap();
+        let results = cn.verify_subnet_tags();
+        assert_eq!(
+            results[0],
+            VerificationResult {
+                message: "Subnet 1 is missing cluster tag: kubernetes.io/cluster/".to_string(),
+                severity: crate::types::Severity::Info,
+                code: Some(crate::types::FindingCode::MissingClusterTag),
+                check_id: Some("NET-002"),
+                resource_type: Some(crate::types::ResourceType::Subnet),
+                resource_id: Some("1".to_string()),
+                evidence: Some(json!({
+                    "resource": "1",
+                    "expected": {"kubernetes.io/cluster/": "shared"},
+                    "actual": {"kubernetes.io/role/elb": "1"},
+                })),
+            }
+        )
+    }
+
+    #[test]
+    fn test_verify_tags_missing_cluster_tag_terraform() {
+        use crate::output::{terraform::TerraformRenderer, CheckRunReport, Renderer};
+
+        let (public_subnet, public_rtb) =
+            make_public_subnet("1", "us-east-1a", &HashMap::from([(PUBLIC_ELB_TAG, "1")]));
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("infra-1".to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        // The default policy allows either value, the fix sets a concrete one.
+        let cn = ClusterNetworkBuilder::default()
+            .cluster_info(&mci)
+            .all_subnets(vec![public_subnet.clone()])
+            .routetables(vec![public_rtb.clone()])
+            .build()
+            .unwrap();
+        let report = CheckRunReport {
+            environment: None,
+            results: cn.verify_subnet_tags(),
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        TerraformRenderer.render(&report, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(r#"key         = "kubernetes.io/cluster/infra-1""#));
+        assert!(out.contains(r#"value       = "shared""#));
+        assert!(!out.contains("owned or shared"));
+    }
+
+    #[test]
+    fn test_verify_tags_only_other_cluster_tags() {
+        let clusterid = "1";
+        let (public_subnet, public_rtb) = make_public_subnet(
+            "1",
+            "us-east-1a",
+            &HashMap::from([
+                (PUBLIC_ELB_TAG, "1"),
+                (&format!("{}{}", CLUSTER_TAG_PREFIX, "2"), "shared"),
+            ]),
+        );
+        let mut mcib = MinimalClusterInfoBuilder::default();
+        let mci = mcib
+            .cluster_id(clusterid.to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![public_subnet.clone()])
+            .routetables(vec![public_rtb.clone()])
+            .build()
+            .unwrap();
+        let results = cn.verify_subnet_tags();
+        assert_eq!(
+            results[0],
+            VerificationResult {
+                message: "Subnet 1 is correctly setup: expected tags are present.".to_string(),
+                severity: crate::types::Severity::Ok,
+                code: None,
+                check_id: Some("NET-002"),
+                resource_type: Some(crate::types::ResourceType::Subnet),
+                resource_id: Some("1".to_string()),
+                evidence: None,
+            }
+        )
+    }
+
+    #[test]
+    fn test_verify_tags_incorrect_cluster_tag() {
+        let clusterid = "1";
+        let (public_subnet, public_rtb) = make_public_subnet(
+            "1",
+            "us-east-1a",
+            &HashMap::from([
+                (PUBLIC_ELB_TAG, "1"),
+                (&format!("{}{}", CLUSTER_TAG_PREFIX, "2"), "owned"),
+            ]),
+        );
+        let mut mcib = MinimalClusterInfoBuilder::default();
+        let mci = mcib
+            .cluster_id(clusterid.to_string())
+            .cluster_infra_name("1".to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![public_subnet.clone()])
+            .routetables(vec![public_rtb.clone()])
+            .build()
+            .unwrap();
+        let results = cn.verify_subnet_tags();
+        assert_eq!(
+            results[0],
+            VerificationResult {
+                message: "Subnet 1 is using incorrect cluster tag: kubernetes.io/cluster/2"
+                    .to_string(),
+                severity: crate::types::Severity::Critical,
+                code: Some(crate::types::FindingCode::IncorrectClusterTag),
+                check_id: Some("NET-002"),
+                resource_type: Some(crate::types::ResourceType::Subnet),
+                resource_id: Some("1".to_string()),
+                evidence: Some(json!({
+                    "resource": "1",
+                    "expected": {"kubernetes.io/cluster/2": "shared"},
+                    "actual": {
+                        "kubernetes.io/cluster/2": "owned",
+                        "kubernetes.io/role/elb": "1",
+                    },
+                })),
+            }
+        )
+    }
+
+    #[test]
+    fn test_verify_tags_cluster_tag_value_policy() {
+        let (public_subnet, public_rtb) = make_public_subnet(
+            "1",
+            "us-east-1a",
+            &HashMap::from([
+                (PUBLIC_ELB_TAG, "1"),
+                (&format!("{}{}", CLUSTER_TAG_PREFIX, "infra-1"), "owned"),
+            ]),
+        );
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("infra-1".to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        let cn = |policy| {
+            ClusterNetworkBuilder::default()
+                .cluster_info(&mci)
+                .all_subnets(vec![public_subnet.clone()])
+                .routetables(vec![public_rtb.clone()])
+                .subnet_tag_value(policy)
+                .build()
+                .unwrap()
+        };
+        assert_eq!(
+            cn(TagValuePolicy::Either).verify_subnet_tags()[0].severity,
+            crate::types::Severity::Ok
+        );
+        let results = cn(TagValuePolicy::Shared).verify_subnet_tags();
+        assert_eq!(
+            results[0].message,
+            "Subnet 1 is using incorrect cluster tag: kubernetes.io/cluster/infra-1=owned (expected shared)"
+        );
+        assert_eq!(
+            results[0].code,
+            Some(crate::types::FindingCode::IncorrectClusterTag)
+        );
+    }
+
+    #[test]
+    fn test_verify_subnet_routetable_associations_conflict() {
+        let subnet = make_subnet("1", "us-east-1a", &HashMap::new());
+        let active_rtb = aws_sdk_ec2::types::RouteTable::builder()
+            .route_table_id("rtb-1")
+            .associations(
+                aws_sdk_ec2::types::RouteTableAssociation::builder()
+                    .subnet_id("1")
+                    .build(),
+            )
+            .build();
+        let stale_rtb = aws_sdk_ec2::types::RouteTable::builder()
+            .route_table_id("rtb-2")
+            .associations(
+                aws_sdk_ec2::types::RouteTableAssociation::builder()
+                    .subnet_id("1")
+                    .association_state(
+                        aws_sdk_ec2::types::RouteTableAssociationState::builder()
+                            .state(RouteTableAssociationStateCode::Disassociating)
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+        let mut mcib = MinimalClusterInfoBuilder::default();
+        let mci = mcib.cluster_id("1".to_string()).build().unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![subnet])
+            .routetables(vec![stale_rtb, active_rtb])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cn.subnet_routetable_mapping["1"].route_table_id(),
+            Some("rtb-1")
+        );
+        assert_eq!(
+            cn.verify_subnet_routetable_associations(),
+            vec![
+                VerificationResult {
+                    message: "Subnet 1 has multiple route table associations: rtb-2 (disassociating), rtb-1 (unknown)".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::MultipleRouteTableAssociations),
+                    check_id: Some("NET-004"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet 1 association with route table rtb-2 is in state: disassociating".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::InactiveRouteTableAssociation),
+                    check_id: Some("NET-004"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("1".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_builder_sets_subnet_rtb_mapping() {
+        let (public_subnet, public_rtb) = make_public_subnet(
+            "1",
+            "us-east-1a",
+            &HashMap::from([
+                (PUBLIC_ELB_TAG, "1"),
+                (&format!("{}{}", CLUSTER_TAG_PREFIX, "2"), "shared"),
+            ]),
+        );
+        let mut mcib = MinimalClusterInfoBuilder::default();
+        let mci = mcib
+            .cluster_id("1".to_string())
+            .subnets(vec![public_subnet.subnet_id.clone().unwrap()])
+            .build()
+            .unwrap();
+        let mut cnb = ClusterNetworkBuilder::default();
+        let cn = cnb
+            .cluster_info(&mci)
+            .all_subnets(vec![public_subnet.clone()])
+            .routetables(vec![public_rtb.clone()])
+            .build()
+            .unwrap();
+        assert_eq!(cn.subnet_routetable_mapping.len(), 1)
+    }
+
+    #[test]
+    fn test_verify_loadbalancer_eni_azs() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let lb = AWSLoadBalancer::ModernLoadBalancer((
+            aws_sdk_elasticloadbalancingv2::types::LoadBalancer::builder()
+                .load_balancer_name("lb")
+                .load_balancer_arn(
+                    "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/net/lb/abc",
+                )
+                .availability_zones(
+                    aws_sdk_elasticloadbalancingv2::types::AvailabilityZone::builder()
+                        .zone_name("us-east-1a")
+                        .build(),
+                )
+                .availability_zones(
+                    aws_sdk_elasticloadbalancingv2::types::AvailabilityZone::builder()
+                        .zone_name("us-east-1b")
+                        .build(),
+                )
+                .build(),
+            vec![],
+        ));
+        let make_eni = |id: &str, az: &str| AWSLoadBalancerEni {
+            load_balancer: lb.identifier().to_string(),
+            eni: aws_sdk_ec2::types::NetworkInterface::builder()
+                .network_interface_id(id)
+                .availability_zone(az)
+                .build(),
+        };
+        let cn = ClusterNetworkBuilder::default()
+            .cluster_info(&mci)
+            .load_balancers(vec![lb.clone()])
+            .load_balancer_enis(vec![
+                make_eni("eni-a", "us-east-1a"),
+                make_eni("eni-c", "us-east-1c"),
+                // An ENI of another LB in an AZ this LB is not enabled in
+                AWSLoadBalancerEni {
+                    load_balancer: "other".to_string(),
+                    eni: aws_sdk_ec2::types::NetworkInterface::builder()
+                        .network_interface_id("eni-d")
+                        .availability_zone("us-east-1d")
+                        .build(),
+                },
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cn.verify_loadbalancer_eni_azs(),
+            vec![
+                VerificationResult {
+                    message: "LoadBalancer lb is enabled in AZ us-east-1b but has no ENI there - provisioning might be stuck".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::LbProvisioningStuck),
+                    check_id: Some("NET-008"),
+                    resource_type: Some(crate::types::ResourceType::LoadBalancer),
+                    resource_id: Some("arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/net/lb/abc".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "LoadBalancer lb has ENI eni-c in AZ us-east-1c that the LoadBalancer is not enabled in - the ENI is leaked".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::LbEniLeaked),
+                    check_id: Some("NET-008"),
+                    resource_type: Some(crate::types::ResourceType::NetworkInterface),
+                    resource_id: Some("eni-c".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_nat_gateways() {
+        let subnet = |id: &str| {
+            aws_sdk_ec2::types::Subnet::builder()
+                .subnet_id(id)
+                .vpc_id("vpc-1")
+                .availability_zone("us-east-1a")
+                .build()
+        };
+        let rtb = |subnet_id: &str, route: aws_sdk_ec2::types::Route| {
+            aws_sdk_ec2::types::RouteTable::builder()
+                .associations(
+                    aws_sdk_ec2::types::RouteTableAssociation::builder()
+                        .subnet_id(subnet_id)
+                        .build(),
+                )
+                .routes(route)
+                .build()
+        };
+        let default_route =
+            aws_sdk_ec2::types::Route::builder().destination_cidr_block("0.0.0.0/0");
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let cn = ClusterNetworkBuilder::default()
+            .cluster_info(&mci)
+            .all_subnets(vec![
+                subnet("public"),
+                subnet("private-1"),
+                subnet("private-2"),
+            ])
+            .routetables(vec![
+                rtb("public", default_route.clone().gateway_id("igw-1").build()),
+                rtb(
+                    "private-1",
+                    default_route.clone().nat_gateway_id("nat-1").build(),
+                ),
+                rtb(
+                    "private-2",
+                    default_route.clone().nat_gateway_id("nat-2").build(),
+                ),
+            ])
+            .nat_gateways(vec![
+                aws_sdk_ec2::types::NatGateway::builder()
+                    .nat_gateway_id("nat-1")
+                    .vpc_id("vpc-1")
+                    .subnet_id("public")
+                    .state(aws_sdk_ec2::types::NatGatewayState::Available)
+                    .build(),
+                aws_sdk_ec2::types::NatGateway::builder()
+                    .nat_gateway_id("nat-2")
+                    .vpc_id("vpc-1")
+                    .subnet_id("private-1")
+                    .state(aws_sdk_ec2::types::NatGatewayState::Deleted)
+                    .build(),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cn.verify_nat_gateways(),
+            vec![
+                VerificationResult {
+                    message: "Subnet private-2 routes through NAT gateway nat-2 in state: deleted"
+                        .to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwUnavailable),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("private-2".to_string()),
+                    evidence: Some(json!({
+                        "route": {
+                            "route_table_id": null,
+                            "destination": "0.0.0.0/0",
+                            "target": "nat-2",
+                            "state": null,
+                        },
+                        "nat_gateway": {
+                            "nat_gateway_id": "nat-2",
+                            "state": "deleted",
+                            "vpc_id": "vpc-1",
+                            "subnet_id": "private-1",
+                        },
+                    })),
+                },
+                VerificationResult {
+                    message: "Subnet private-2 routes through NAT gateway nat-2 that is not in a public subnet: private-1".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::NatGwInPrivateSubnet),
+                    check_id: Some("NET-009"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("private-2".to_string()),
+                    evidence: Some(json!({
+                        "route": {
+                            "route_table_id": null,
+                            "destination": "0.0.0.0/0",
+                            "target": "nat-2",
+                            "state": null,
+                        },
+                        "nat_gateway": {
+                            "nat_gateway_id": "nat-2",
+                            "state": "deleted",
+                            "vpc_id": "vpc-1",
+                            "subnet_id": "private-1",
+                        },
+                    })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_routes() {
+        let subnet = |id: &str| {
+            aws_sdk_ec2::types::Subnet::builder()
+                .subnet_id(id)
+                .vpc_id("vpc-1")
+                .build()
+        };
+        let rtb = |subnet_id: &str, routes: Vec<aws_sdk_ec2::types::Route>| {
+            aws_sdk_ec2::types::RouteTable::builder()
+                .associations(
+                    aws_sdk_ec2::types::RouteTableAssociation::builder()
+                        .subnet_id(subnet_id)
+                        .build(),
+                )
+                .set_routes(Some(routes))
+                .build()
+        };
+        let route = |destination: &str| {
+            aws_sdk_ec2::types::Route::builder().destination_cidr_block(destination)
+        };
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let cn = ClusterNetworkBuilder::default()
+            .cluster_info(&mci)
+            .all_subnets(vec![
+                subnet("public"),
+                subnet("private-1"),
+                subnet("private-2"),
+            ])
+            .routetables(vec![
+                rtb(
+                    "public",
+                    vec![route("0.0.0.0/0")
+                        .gateway_id("igw-1")
+                        .state(aws_sdk_ec2::types::RouteState::Blackhole)
+                        .build()],
+                ),
+                rtb(
+                    "private-1",
+                    vec![
+                        route("0.0.0.0/0")
+                            .nat_gateway_id("nat-1")
+                            .state(aws_sdk_ec2::types::RouteState::Active)
+                            .build(),
+                        route("10.1.0.0/16")
+                            .vpc_peering_connection_id("pcx-1")
+                            .state(aws_sdk_ec2::types::RouteState::Blackhole)
+                            .build(),
+                    ],
+                ),
+                rtb(
+                    "private-2",
+                    vec![route("10.0.0.0/16")
+                        .gateway_id("local")
+                        .state(aws_sdk_ec2::types::RouteState::Active)
+                        .build()],
+                ),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            cn.verify_routes(),
+            vec![
+                VerificationResult {
+                    message: "Subnet private-1 has a blackhole route to 10.1.0.0/16 through pcx-1 - the target no longer exists".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::RouteBlackhole),
+                    check_id: Some("NET-010"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("private-1".to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": null,
+                        "destination": "10.1.0.0/16",
+                        "target": "pcx-1",
+                        "state": "blackhole",
+                    })),
+                },
+                VerificationResult {
+                    message: "Private subnet private-2 has no default route - it can only reach the internet through a proxy".to_string(),
+                    severity: crate::types::Severity::Warning,
+                    code: Some(crate::types::FindingCode::NoDefaultRoute),
+                    check_id: Some("NET-010"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("private-2".to_string()),
+                    evidence: Some(json!({
+                        "routes": [{
+                            "route_table_id": null,
+                            "destination": "10.0.0.0/16",
+                            "target": "local",
+                            "state": "active",
+                        }],
+                    })),
+                },
+                VerificationResult {
+                    message: "Subnet public routes 0.0.0.0/0 through internet gateway igw-1 that is detached from the VPC".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::IgwDetached),
+                    check_id: Some("NET-010"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("public".to_string()),
+                    evidence: Some(json!({
+                        "route_table_id": null,
+                        "destination": "0.0.0.0/0",
+                        "target": "igw-1",
+                        "state": "blackhole",
+                    })),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_internet_gateways() {
+        let subnet = |id: &str| {
+            aws_sdk_ec2::types::Subnet::builder()
+                .subnet_id(id)
+                .vpc_id("vpc-1")
+                .build()
+        };
+        let rtb = |subnet_id: &str, igw_id: &str| {
+            aws_sdk_ec2::types::RouteTable::builder()
+                .associations(
+                    aws_sdk_ec2::types::RouteTableAssociation::builder()
+                        .subnet_id(subnet_id)
+                        .build(),
+                )
+                .routes(
+                    aws_sdk_ec2::types::Route::builder()
+                        .destination_cidr_block(DEFAULT_ROUTE)
+                        .gateway_id(igw_id)
+                        .state(aws_sdk_ec2::types::RouteState::Active)
+                        .build(),
+                )
+                .build()
+        };
+        let igw = |igw_id: &str, vpc_id: &str, state: &str| {
+            aws_sdk_ec2::types::InternetGateway::builder()
+                .internet_gateway_id(igw_id)
+                .attachments(
+                    aws_sdk_ec2::types::InternetGatewayAttachment::builder()
+                        .vpc_id(vpc_id)
+                        .state(aws_sdk_ec2::types::AttachmentStatus::from(state))
+                        .build(),
+                )
+                .build()
+        };
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let cn = ClusterNetworkBuilder::default()
+            .cluster_info(&mci)
+            .all_subnets(vec![
+                subnet("public-1"),
+                subnet("public-2"),
+                subnet("public-3"),
+            ])
+            .routetables(vec![
+                rtb("public-1", "igw-1"),
+                rtb("public-2", "igw-2"),
+                rtb("public-3", "igw-3"),
+            ])
+            .internet_gateways(vec![
+                igw("igw-1", "vpc-1", "available"),
+                igw("igw-2", "vpc-2", "available"),
+                igw("igw-3", "vpc-1", "detaching"),
+            ])
+            .build()
+            .unwrap();
+        let evidence = |igw_id: &str| {
+            json!({
+                "route_table_id": null,
+                "destination": "0.0.0.0/0",
+                "target": igw_id,
+                "state": "active",
+            })
+        };
+        assert_eq!(
+            cn.verify_internet_gateways(),
+            vec![
+                VerificationResult {
+                    message: "Public subnet public-2 routes 0.0.0.0/0 through internet gateway igw-2 that is not attached to VPC vpc-1".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::IgwDetached),
+                    check_id: Some("NET-011"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("public-2".to_string()),
+                    evidence: Some(evidence("igw-2")),
+                },
+                VerificationResult {
+                    message: "Public subnet public-3 routes 0.0.0.0/0 through internet gateway igw-3 that is detaching instead of available".to_string(),
+                    severity: crate::types::Severity::Critical,
+                    code: Some(crate::types::FindingCode::IgwDetached),
+                    check_id: Some("NET-011"),
+                    resource_type: Some(crate::types::ResourceType::Subnet),
+                    resource_id: Some("public-3".to_string()),
+                    evidence: Some(evidence("igw-3")),
+                },
+            ]
+        );
+    }
+}
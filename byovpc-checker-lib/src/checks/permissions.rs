@@ -0,0 +1,57 @@
+//! This checker reports AWS API calls that were denied by a policy while
+//! gathering - e.g. a service control policy of the organization. The checks
+//! relying on the denied data can not see the resources, so their results
+//! have to be read with care.
+
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    gatherer::aws::credentials::PolicyDenial,
+    types::{FindingCode, Severity, VerificationResult, Verifier},
+};
+
+#[derive(Debug, Builder)]
+pub struct PermissionChecks {
+    #[builder(default = "vec![]")]
+    denials: Vec<PolicyDenial>,
+}
+
+impl PermissionChecks {
+    pub fn verify_no_denials(&self) -> Vec<VerificationResult> {
+        info!("Checking no AWS API calls were denied");
+        self.denials
+            .iter()
+            .map(|d| VerificationResult {
+                message: format!(
+                    "{} - checks relying on it are incomplete, request an exemption for the action",
+                    d
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::DeniedByPolicy),
+                check_id: Some("PERM-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            })
+            .collect()
+    }
+}
+
+impl Verifier for PermissionChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = self.verify_no_denials();
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "No AWS API calls were denied by a policy".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
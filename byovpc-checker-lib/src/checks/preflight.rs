@@ -0,0 +1,556 @@
+//! This checker verifies a VPC before a cluster is installed into it, so there
+//! is no cluster information from OCM available.
+//! It can check the following conditions right now:
+//!
+//! - The given subnets exist and belong to the same VPC.
+//! - The subnets are not owned by another cluster and carry the role tags.
+//! - The subnets have enough free IP addresses.
+//! - Private subnets egress via a NAT gateway, public subnets via an IGW.
+//! - An S3 gateway endpoint exists.
+//! - The elastic IP quota is not exhausted.
+//! - A hosted zone exists for the base domain.
+
+use aws_sdk_ec2::types::{
+    AccountAttribute, Address, NatGateway, NatGatewayState, Route, RouteState, RouteTable, Subnet,
+    VpcEndpoint, VpcEndpointType,
+};
+use aws_sdk_route53::types::HostedZone;
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    checks::network::{PRIVATE_ELB_TAG, PUBLIC_ELB_TAG},
+    gatherer::aws::shared_types::CLUSTER_TAG_PREFIX,
+    types::{ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// Subnets with less free IP addresses than this can not host a cluster that
+/// scales beyond a handful of nodes.
+pub const MIN_FREE_IPS_PER_SUBNET: i32 = 64;
+
+#[derive(Debug, Builder)]
+pub struct PreflightChecks {
+    #[builder(default = "None")]
+    vpc_id: Option<String>,
+    #[builder(default = "vec![]")]
+    subnet_ids: Vec<String>,
+    #[builder(default = "None")]
+    base_domain: Option<String>,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<NatGateway>,
+    #[builder(default = "vec![]")]
+    vpc_endpoints: Vec<VpcEndpoint>,
+    #[builder(default = "vec![]")]
+    elastic_ips: Vec<Address>,
+    #[builder(default = "vec![]")]
+    account_attributes: Vec<AccountAttribute>,
+    #[builder(default = "vec![]")]
+    hosted_zones: Vec<HostedZone>,
+}
+
+impl PreflightChecks {
+    /// The subnets the cluster will be installed into: the given subnets, or
+    /// every subnet of the VPC if none were given.
+    fn install_subnets(&self) -> Vec<&Subnet> {
+        if self.subnet_ids.is_empty() {
+            return self.subnets.iter().collect();
+        }
+        self.subnets
+            .iter()
+            .filter(|s| {
+                s.subnet_id()
+                    .is_some_and(|id| self.subnet_ids.iter().any(|sid| sid == id))
+            })
+            .collect()
+    }
+
+    /// Returns the route table in effect for a subnet: the explicitly
+    /// associated one or the main route table of the VPC.
+    fn routetable_for_subnet(&self, subnet: &Subnet) -> Option<&RouteTable> {
+        self.routetables
+            .iter()
+            .find(|rt| {
+                rt.associations()
+                    .iter()
+                    .any(|a| a.subnet_id() == subnet.subnet_id())
+            })
+            .or_else(|| {
+                self.routetables.iter().find(|rt| {
+                    rt.vpc_id() == subnet.vpc_id()
+                        && rt.associations().iter().any(|a| a.main() == Some(true))
+                })
+            })
+    }
+
+    fn default_route(routetable: &RouteTable) -> Option<&Route> {
+        routetable
+            .routes()
+            .iter()
+            .find(|r| r.destination_cidr_block() == Some("0.0.0.0/0"))
+    }
+
+    fn is_public(&self, subnet: &Subnet) -> bool {
+        self.routetable_for_subnet(subnet)
+            .and_then(Self::default_route)
+            .and_then(|r| r.gateway_id())
+            .is_some_and(|g| g.starts_with("igw-"))
+    }
+
+    fn has_tag(subnet: &Subnet, key: &str) -> bool {
+        subnet.tags().iter().any(|t| t.key() == Some(key))
+    }
+
+    pub fn verify_subnets_exist(&self) -> Vec<VerificationResult> {
+        info!("Checking subnets exist");
+        let mut results = vec![];
+        for subnet_id in self.subnet_ids.iter() {
+            if !self
+                .subnets
+                .iter()
+                .any(|s| s.subnet_id() == Some(subnet_id.as_str()))
+            {
+                results.push(VerificationResult {
+                    message: format!("Subnet {} does not exist", subnet_id),
+                    severity: Severity::Critical,
+                    code: None,
+                    check_id: Some("PRE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.clone()),
+                    evidence: None,
+                });
+            }
+        }
+        let vpc_ids: Vec<&str> = self
+            .install_subnets()
+            .iter()
+            .filter_map(|s| s.vpc_id())
+            .unique()
+            .collect();
+        if vpc_ids.len() > 1 {
+            results.push(VerificationResult {
+                message: format!("Subnets belong to multiple VPCs: {}", vpc_ids.join(", ")),
+                severity: Severity::Critical,
+                code: None,
+                check_id: Some("PRE-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        if let Some(ref vpc_id) = self.vpc_id {
+            for subnet in self.install_subnets() {
+                if subnet.vpc_id() != Some(vpc_id.as_str()) {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Subnet {} belongs to VPC {} and not to VPC {}",
+                            subnet.subnet_id().unwrap_or_default(),
+                            subnet.vpc_id().unwrap_or_default(),
+                            vpc_id
+                        ),
+                        severity: Severity::Critical,
+                        code: None,
+                        check_id: Some("PRE-001"),
+                        resource_type: Some(ResourceType::Subnet),
+                        resource_id: Some(subnet.subnet_id().unwrap_or_default().to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        if self.install_subnets().is_empty() {
+            results.push(VerificationResult {
+                message: "No subnets found to install the cluster into".to_string(),
+                severity: Severity::Critical,
+                code: None,
+                check_id: Some("PRE-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+
+    pub fn verify_subnet_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking subnet tags");
+        let mut results = vec![];
+        for subnet in self.install_subnets() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            for tag in subnet.tags() {
+                let Some(cluster) = tag.key().and_then(|k| k.strip_prefix(CLUSTER_TAG_PREFIX))
+                else {
+                    continue;
+                };
+                if tag.value() == Some("owned") {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Subnet {} is owned by cluster {} - it can not be used for another cluster",
+                            subnet_id, cluster
+                        ),
+                        severity: Severity::Critical,
+                        code: None,
+                        check_id: Some("PRE-002"),
+                        resource_type: Some(ResourceType::Subnet),
+                        resource_id: Some(subnet_id.to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+            let (role_tag, kind) = if self.is_public(subnet) {
+                (PUBLIC_ELB_TAG, "public")
+            } else {
+                (PRIVATE_ELB_TAG, "private")
+            };
+            if !Self::has_tag(subnet, role_tag) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} is {} but is missing the role tag {} - LoadBalancers will not be placed in it",
+                        subnet_id, kind, role_tag
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("PRE-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_cidr_capacity(&self) -> Vec<VerificationResult> {
+        info!("Checking free IP addresses of subnets");
+        let mut results = vec![];
+        for subnet in self.install_subnets() {
+            let free = subnet.available_ip_address_count().unwrap_or_default();
+            if free == 0 {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} ({}) has no free IP addresses",
+                        subnet.subnet_id().unwrap_or_default(),
+                        subnet.cidr_block().unwrap_or_default()
+                    ),
+                    severity: Severity::Critical,
+                    code: None,
+                    check_id: Some("PRE-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet.subnet_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            } else if free < MIN_FREE_IPS_PER_SUBNET {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} ({}) has only {} free IP addresses - at least {} are recommended",
+                        subnet.subnet_id().unwrap_or_default(),
+                        subnet.cidr_block().unwrap_or_default(),
+                        free,
+                        MIN_FREE_IPS_PER_SUBNET
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("PRE-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet.subnet_id().unwrap_or_default().to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_egress(&self) -> Vec<VerificationResult> {
+        info!("Checking egress of subnets");
+        let mut results = vec![];
+        for subnet in self.install_subnets() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let Some(route) = self
+                .routetable_for_subnet(subnet)
+                .and_then(Self::default_route)
+            else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has no default route - egress only works through a proxy or a transit gateway",
+                        subnet_id
+                    ),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("PRE-004"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+                continue;
+            };
+            if route.state() == Some(&RouteState::Blackhole) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "The default route of subnet {} is a blackhole - the target was deleted",
+                        subnet_id
+                    ),
+                    severity: Severity::Critical,
+                    code: None,
+                    check_id: Some("PRE-004"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+                continue;
+            }
+            if let Some(nat_id) = route.nat_gateway_id() {
+                let available = self.nat_gateways.iter().any(|n| {
+                    n.nat_gateway_id() == Some(nat_id)
+                        && n.state() == Some(&NatGatewayState::Available)
+                });
+                if !available {
+                    results.push(VerificationResult {
+                        message: format!(
+                            "Subnet {} routes through NAT gateway {} that is not available",
+                            subnet_id, nat_id
+                        ),
+                        severity: Severity::Critical,
+                        code: None,
+                        check_id: Some("PRE-004"),
+                        resource_type: Some(ResourceType::Subnet),
+                        resource_id: Some(subnet_id.to_string()),
+                        evidence: None,
+                    });
+                }
+            }
+        }
+        let install_subnets = self.install_subnets();
+        if !install_subnets.is_empty() && install_subnets.iter().all(|s| self.is_public(s)) {
+            results.push(VerificationResult {
+                message:
+                    "All subnets are public - a private subnet is required for the cluster nodes"
+                        .to_string(),
+                severity: Severity::Critical,
+                code: None,
+                check_id: Some("PRE-004"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+
+    pub fn verify_endpoints(&self) -> Vec<VerificationResult> {
+        info!("Checking VPC endpoints");
+        let has_s3_gateway = self.vpc_endpoints.iter().any(|e| {
+            e.vpc_endpoint_type() == Some(&VpcEndpointType::Gateway)
+                && e.service_name().is_some_and(|n| n.ends_with(".s3"))
+        });
+        if has_s3_gateway {
+            return vec![];
+        }
+        vec![VerificationResult {
+            message: "VPC has no S3 gateway endpoint - image pulls from S3 backed registries will traverse the NAT gateway".to_string(),
+            severity: Severity::Info,
+            code: None,
+            check_id: Some("PRE-005"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }]
+    }
+
+    pub fn verify_quotas(&self) -> Vec<VerificationResult> {
+        info!("Checking quotas");
+        let Some(max_eips) = self
+            .account_attributes
+            .iter()
+            .find(|a| a.attribute_name() == Some("vpc-max-elastic-ips"))
+            .and_then(|a| a.attribute_values().first())
+            .and_then(|v| v.attribute_value())
+            .and_then(|v| v.parse::<usize>().ok())
+        else {
+            return vec![];
+        };
+        if self.elastic_ips.len() < max_eips {
+            return vec![];
+        }
+        vec![VerificationResult {
+            message: format!(
+                "All {} elastic IPs of the quota are allocated - additional NAT gateways can not be created",
+                max_eips
+            ),
+            severity: Severity::Warning,
+            code: None,
+            check_id: Some("PRE-006"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }]
+    }
+
+    pub fn verify_base_domain(&self) -> Vec<VerificationResult> {
+        let Some(ref base_domain) = self.base_domain else {
+            return vec![];
+        };
+        info!("Checking hosted zone for base domain");
+        if self.hosted_zones.is_empty() {
+            return vec![VerificationResult {
+                message: format!(
+                    "No hosted zone exists for base domain {} - it will be created during install",
+                    base_domain
+                ),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("PRE-007"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        vec![]
+    }
+}
+
+impl Verifier for PreflightChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_subnets_exist());
+        results.extend(self.verify_subnet_tags());
+        results.extend(self.verify_cidr_capacity());
+        results.extend(self.verify_egress());
+        results.extend(self.verify_endpoints());
+        results.extend(self.verify_quotas());
+        results.extend(self.verify_base_domain());
+        results
+    }
+}
+
+/// Summarizes the results into a single go/no-go verdict: any critical
+/// result blocks the install.
+pub fn verdict(results: &[VerificationResult]) -> VerificationResult {
+    let blocking = results
+        .iter()
+        .filter(|r| r.severity == Severity::Critical)
+        .count();
+    let warnings = results
+        .iter()
+        .filter(|r| r.severity == Severity::Warning)
+        .count();
+    if blocking > 0 {
+        VerificationResult {
+            message: format!(
+                "NO-GO: {} problems block the install ({} warnings)",
+                blocking, warnings
+            ),
+            severity: Severity::Critical,
+            code: None,
+            check_id: None,
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }
+    } else {
+        VerificationResult {
+            message: format!(
+                "GO: the VPC can be used for the install ({} warnings)",
+                warnings
+            ),
+            severity: Severity::Ok,
+            code: None,
+            check_id: None,
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{RouteTableAssociation, Tag};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_private_subnet_without_nat() {
+        let subnet = Subnet::builder()
+            .subnet_id("private-a")
+            .vpc_id("vpc-1")
+            .cidr_block("10.0.0.0/27")
+            .available_ip_address_count(20)
+            .tags(Tag::builder().key(PRIVATE_ELB_TAG).value("1").build())
+            .tags(
+                Tag::builder()
+                    .key(format!("{}old-cluster", CLUSTER_TAG_PREFIX))
+                    .value("owned")
+                    .build(),
+            )
+            .build();
+        let main_rtb = RouteTable::builder()
+            .vpc_id("vpc-1")
+            .associations(RouteTableAssociation::builder().main(true).build())
+            .build();
+        let pc = PreflightChecksBuilder::default()
+            .vpc_id(Some("vpc-1".to_string()))
+            .subnet_ids(vec!["private-a".to_string()])
+            .subnets(vec![subnet])
+            .routetables(vec![main_rtb])
+            .build()
+            .unwrap();
+        let results = pc.verify();
+        assert_eq!(
+            results,
+            vec![
+                VerificationResult {
+                    message: "Subnet private-a is owned by cluster old-cluster - it can not be used for another cluster".to_string(),
+                    severity: Severity::Critical,
+                    code: None,
+                    check_id: Some("PRE-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("private-a".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet private-a (10.0.0.0/27) has only 20 free IP addresses - at least 64 are recommended".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("PRE-003"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("private-a".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet private-a has no default route - egress only works through a proxy or a transit gateway".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("PRE-004"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("private-a".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "VPC has no S3 gateway endpoint - image pulls from S3 backed registries will traverse the NAT gateway".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("PRE-005"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ]
+        );
+        assert_eq!(
+            verdict(&results),
+            VerificationResult {
+                message: "NO-GO: 1 problems block the install (2 warnings)".to_string(),
+                severity: Severity::Critical,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }
+        );
+    }
+}
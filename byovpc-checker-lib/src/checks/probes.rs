@@ -0,0 +1,142 @@
+//! This checker verifies the endpoints the cluster needs are reachable, by
+//! resolving and connecting to them with `--active-probes`. The route tables
+//! only show where traffic is sent, the probes show it arrives - but they run
+//! on the machine the tool runs on, so they only prove the egress of the
+//! cluster if it runs in the cluster VPC (or shares its egress path).
+//! It can check the following conditions right now:
+//!
+//! - OCM, the SSO, the registries and the AWS APIs of the region (including
+//!   S3) resolve and accept connections, through the proxy of the cluster if
+//!   it has one.
+
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::local::ActiveProbes,
+    types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+#[derive(Debug, Builder)]
+pub struct ActiveProbeChecks<'a> {
+    /// The results of the probes - `None` if they did not run, e.g. for
+    /// snapshots.
+    #[builder(default = "None")]
+    probes: Option<&'a ActiveProbes>,
+}
+
+impl<'a> ActiveProbeChecks<'a> {
+    pub fn verify_endpoints(&self, probes: &ActiveProbes) -> Vec<VerificationResult> {
+        info!("Checking the required endpoints are reachable");
+        let through = probes
+            .proxy
+            .as_ref()
+            .map_or(String::new(), |p| format!(" through proxy {}", p));
+        if let Some(ref e) = probes.error {
+            return vec![VerificationResult {
+                message: format!(
+                    "Proxy {} is not reachable from this machine, the endpoints can not be probed: {}",
+                    probes.proxy.as_deref().unwrap_or_default(),
+                    e
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::ProxyUnreachable),
+                check_id: Some("PROBE-001"),
+                resource_type: probes.proxy.as_ref().map(|_| ResourceType::Proxy),
+                resource_id: probes.proxy.clone(),
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        for probe in probes.probes.iter() {
+            let Some(ref e) = probe.error else {
+                continue;
+            };
+            results.push(VerificationResult {
+                message: format!("{} is not reachable{}: {}", probe.endpoint, through, e),
+                severity: Severity::Critical,
+                code: Some(FindingCode::EgressEndpointUnreachable),
+                check_id: Some("PROBE-001"),
+                resource_type: Some(ResourceType::Endpoint),
+                resource_id: Some(probe.endpoint.clone()),
+                evidence: Some(json!({"proxy": probes.proxy, "endpoint": probe.endpoint})),
+            });
+        }
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "All {} required endpoints are reachable{}",
+                    probes.probes.len(),
+                    through
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("PROBE-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for ActiveProbeChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let Some(probes) = self.probes else {
+            return vec![VerificationResult {
+                message: "The required endpoints were not probed - they are only probed against live clusters"
+                    .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        };
+        self.verify_endpoints(probes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gatherer::local::EgressProbe;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_endpoints() {
+        let probes = ActiveProbes {
+            proxy: None,
+            error: None,
+            probes: vec![
+                EgressProbe {
+                    endpoint: "quay.io:443".to_string(),
+                    error: None,
+                },
+                EgressProbe {
+                    endpoint: "s3.eu-west-1.amazonaws.com:443".to_string(),
+                    error: Some("no connection to 52.218.0.1 within 5s".to_string()),
+                },
+            ],
+        };
+        let apc = ActiveProbeChecksBuilder::default()
+            .probes(Some(&probes))
+            .build()
+            .unwrap();
+        assert_eq!(
+            apc.verify(),
+            vec![VerificationResult {
+                message: "s3.eu-west-1.amazonaws.com:443 is not reachable: no connection to 52.218.0.1 within 5s".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::EgressEndpointUnreachable),
+                check_id: Some("PROBE-001"),
+                resource_type: Some(ResourceType::Endpoint),
+                resource_id: Some("s3.eu-west-1.amazonaws.com:443".to_string()),
+                evidence: Some(json!({"proxy": null, "endpoint": "s3.eu-west-1.amazonaws.com:443"})),
+            }]
+        );
+    }
+}
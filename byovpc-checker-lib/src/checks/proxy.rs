@@ -0,0 +1,157 @@
+//! This checker verifies the cluster can reach the endpoints it needs through
+//! its HTTP(S) proxy. The connections are tunneled with `CONNECT` from the
+//! machine the tool runs on, so a proxy only reachable from within the VPC
+//! can not be verified.
+//! It can check the following conditions right now:
+//!
+//! - OCM, the SSO, the registries and the AWS APIs of the region are
+//!   reachable through the proxy.
+
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::local::ProxyEgress,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+#[derive(Debug, Builder)]
+pub struct ProxyChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    /// The probes of the proxy - `None` if they did not run, e.g. for
+    /// snapshots.
+    #[builder(default = "None")]
+    egress: Option<&'a ProxyEgress>,
+}
+
+impl<'a> ProxyChecks<'a> {
+    pub fn verify_endpoints(&self, egress: &ProxyEgress) -> Vec<VerificationResult> {
+        info!(
+            "Checking endpoints are reachable through proxy {}",
+            egress.proxy
+        );
+        if let Some(ref e) = egress.error {
+            return vec![VerificationResult {
+                message: format!(
+                    "Proxy {} is not reachable from this machine, the endpoints can not be verified: {}",
+                    egress.proxy, e
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::ProxyUnreachable),
+                check_id: Some("PROXY-001"),
+                resource_type: Some(ResourceType::Proxy),
+                resource_id: Some(egress.proxy.clone()),
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        for probe in egress.probes.iter() {
+            let Some(ref e) = probe.error else {
+                continue;
+            };
+            results.push(VerificationResult {
+                message: format!(
+                    "{} is not reachable through proxy {}: {}",
+                    probe.endpoint, egress.proxy, e
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::ProxyEndpointUnreachable),
+                check_id: Some("PROXY-001"),
+                resource_type: Some(ResourceType::Endpoint),
+                resource_id: Some(probe.endpoint.clone()),
+                evidence: Some(json!({"proxy": egress.proxy, "endpoint": probe.endpoint})),
+            });
+        }
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "All {} required endpoints are reachable through proxy {}",
+                    egress.probes.len(),
+                    egress.proxy
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("PROXY-001"),
+                resource_type: Some(ResourceType::Proxy),
+                resource_id: Some(egress.proxy.clone()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for ProxyChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self.cluster_info.proxy.is_none() {
+            return vec![VerificationResult {
+                message: "The cluster has no proxy configured".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let Some(egress) = self.egress else {
+            return vec![VerificationResult {
+                message: "The endpoints were not probed through the proxy of the cluster"
+                    .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        };
+        self.verify_endpoints(egress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{gatherer::local::EgressProbe, types::MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_endpoints() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .proxy(Some("http://proxy:3128".to_string()))
+            .build()
+            .unwrap();
+        let egress = ProxyEgress {
+            proxy: "http://proxy:3128/".to_string(),
+            error: None,
+            probes: vec![
+                EgressProbe {
+                    endpoint: "api.openshift.com:443".to_string(),
+                    error: None,
+                },
+                EgressProbe {
+                    endpoint: "quay.io:443".to_string(),
+                    error: Some("403 Forbidden".to_string()),
+                },
+            ],
+        };
+        let pc = ProxyChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .egress(Some(&egress))
+            .build()
+            .unwrap();
+        let results = pc.verify();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].severity, Severity::Critical);
+        assert_eq!(results[0].code, Some(FindingCode::ProxyEndpointUnreachable));
+        assert_eq!(
+            results[0].message,
+            "quay.io:443 is not reachable through proxy http://proxy:3128/: 403 Forbidden"
+        );
+    }
+}
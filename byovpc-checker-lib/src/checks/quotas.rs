@@ -0,0 +1,298 @@
+//! This checker compares the usage of the service quotas that commonly block
+//! installs into an existing VPC to their limits.
+//! It can check the following conditions right now:
+//!
+//! - Elastic IPs of the region.
+//! - NAT gateways of every availability zone.
+//! - Network interfaces of the region.
+//! - Rules of the security groups of the instances.
+//! - Application, network and classic load balancers of the cluster.
+//!
+//! The usage is counted from the gathered data: the NAT gateways and network
+//! interfaces only of the cluster VPC and subnets, so other VPCs of the
+//! account can use up more of the quota.
+
+use std::collections::BTreeMap;
+
+use aws_sdk_ec2::types::{IpPermission, NatGateway, NatGatewayState, NetworkInterface, Subnet};
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancerTypeEnum;
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::aws::{
+        quotas::{QuotaKind, ServiceQuota},
+        shared_types::{AWSInstance, AWSLoadBalancer},
+    },
+    types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// The share of a quota above which its usage is reported.
+const USAGE_WARNING_THRESHOLD: f64 = 0.8;
+
+/// The number of rules a permission counts as: one per source.
+fn rule_count(permissions: &[IpPermission]) -> usize {
+    permissions
+        .iter()
+        .map(|p| {
+            p.ip_ranges().len()
+                + p.ipv6_ranges().len()
+                + p.user_id_group_pairs().len()
+                + p.prefix_list_ids().len()
+        })
+        .sum()
+}
+
+#[derive(Debug, Builder)]
+pub struct QuotaChecks {
+    #[builder(default = "vec![]")]
+    service_quotas: Vec<ServiceQuota>,
+    #[builder(default = "vec![]")]
+    elastic_ips: Vec<aws_sdk_ec2::types::Address>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<NatGateway>,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    subnet_network_interfaces: Vec<NetworkInterface>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+}
+
+impl QuotaChecks {
+    /// Compares the usage of the quota to its limit - `None` if the quota was
+    /// not gathered.
+    fn verify_usage(
+        &self,
+        check_id: &'static str,
+        kind: QuotaKind,
+        what: &str,
+        usage: usize,
+    ) -> Option<VerificationResult> {
+        let limit = self.service_quotas.iter().find(|q| q.kind == kind)?.value;
+        let share = if limit > 0.0 {
+            usage as f64 / limit
+        } else {
+            1.0
+        };
+        let (severity, code) = if share >= 1.0 {
+            (Severity::Critical, Some(FindingCode::QuotaExhausted))
+        } else if share > USAGE_WARNING_THRESHOLD {
+            (Severity::Warning, Some(FindingCode::QuotaNearlyExhausted))
+        } else {
+            (Severity::Ok, None)
+        };
+        Some(VerificationResult {
+            message: format!(
+                "{} uses {} of {} {} ({:.0}%)",
+                what,
+                usage,
+                limit,
+                kind,
+                share * 100.0
+            ),
+            severity,
+            code,
+            check_id: Some(check_id),
+            resource_type: Some(ResourceType::ServiceQuota),
+            resource_id: Some(kind.to_string()),
+            evidence: code.map(|_| json!({"quota": kind, "usage": usage, "limit": limit})),
+        })
+    }
+
+    pub fn verify_elastic_ips(&self) -> Vec<VerificationResult> {
+        info!("Checking elastic IP quota");
+        self.verify_usage(
+            "QUOTA-001",
+            QuotaKind::ElasticIps,
+            "The region",
+            self.elastic_ips.len(),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    pub fn verify_nat_gateways(&self) -> Vec<VerificationResult> {
+        info!("Checking NAT gateway quota");
+        let mut per_zone: BTreeMap<&str, usize> = BTreeMap::new();
+        for nat in self.nat_gateways.iter().filter(|n| {
+            !matches!(
+                n.state(),
+                Some(NatGatewayState::Deleted) | Some(NatGatewayState::Failed)
+            )
+        }) {
+            let zone = self
+                .subnets
+                .iter()
+                .find(|s| s.subnet_id().is_some() && s.subnet_id() == nat.subnet_id())
+                .and_then(|s| s.availability_zone());
+            if let Some(zone) = zone {
+                *per_zone.entry(zone).or_default() += 1;
+            }
+        }
+        per_zone
+            .into_iter()
+            .filter_map(|(zone, usage)| {
+                self.verify_usage(
+                    "QUOTA-002",
+                    QuotaKind::NatGatewaysPerZone,
+                    &format!("Availability zone {}", zone),
+                    usage,
+                )
+            })
+            .collect()
+    }
+
+    pub fn verify_network_interfaces(&self) -> Vec<VerificationResult> {
+        info!("Checking network interface quota");
+        self.verify_usage(
+            "QUOTA-003",
+            QuotaKind::NetworkInterfaces,
+            "The cluster subnets",
+            self.subnet_network_interfaces.len(),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    pub fn verify_security_group_rules(&self) -> Vec<VerificationResult> {
+        info!("Checking security group rule quota");
+        let results: Vec<VerificationResult> = self
+            .instances
+            .iter()
+            .flat_map(|i| i.security_groups.iter())
+            .unique_by(|sg| sg.group_id())
+            .filter_map(|sg| {
+                let usage =
+                    rule_count(sg.ip_permissions()).max(rule_count(sg.ip_permissions_egress()));
+                self.verify_usage(
+                    "QUOTA-004",
+                    QuotaKind::RulesPerSecurityGroup,
+                    &format!("Security group {}", sg.group_id().unwrap_or_default()),
+                    usage,
+                )
+            })
+            .collect();
+        // A line per security group within its quota is noise.
+        let findings: Vec<VerificationResult> = results
+            .iter()
+            .filter(|r| r.severity != Severity::Ok)
+            .cloned()
+            .collect();
+        if !findings.is_empty() || results.is_empty() {
+            return findings;
+        }
+        vec![VerificationResult {
+            message: format!(
+                "All {} security groups of the instances are within the {} quota",
+                results.len(),
+                QuotaKind::RulesPerSecurityGroup
+            ),
+            severity: Severity::Ok,
+            code: None,
+            check_id: Some("QUOTA-004"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        }]
+    }
+
+    pub fn verify_load_balancers(&self) -> Vec<VerificationResult> {
+        info!("Checking load balancer quotas");
+        let count = |kind: QuotaKind| {
+            self.load_balancers
+                .iter()
+                .filter(|lb| match (lb, kind) {
+                    (AWSLoadBalancer::ClassicLoadBalancer(_), QuotaKind::ClassicLoadBalancers) => {
+                        true
+                    }
+                    (AWSLoadBalancer::ModernLoadBalancer((lb, _)), kind) => matches!(
+                        (lb.r#type(), kind),
+                        (
+                            Some(LoadBalancerTypeEnum::Application),
+                            QuotaKind::ApplicationLoadBalancers
+                        ) | (
+                            Some(LoadBalancerTypeEnum::Network),
+                            QuotaKind::NetworkLoadBalancers
+                        )
+                    ),
+                    _ => false,
+                })
+                .count()
+        };
+        [
+            QuotaKind::ApplicationLoadBalancers,
+            QuotaKind::NetworkLoadBalancers,
+            QuotaKind::ClassicLoadBalancers,
+        ]
+        .into_iter()
+        .filter_map(|kind| self.verify_usage("QUOTA-005", kind, "The cluster", count(kind)))
+        .collect()
+    }
+}
+
+impl Verifier for QuotaChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_elastic_ips());
+        results.extend(self.verify_nat_gateways());
+        results.extend(self.verify_network_interfaces());
+        results.extend(self.verify_security_group_rules());
+        results.extend(self.verify_load_balancers());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::Address;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_usage() {
+        let qc = QuotaChecksBuilder::default()
+            .service_quotas(vec![
+                ServiceQuota {
+                    kind: QuotaKind::ElasticIps,
+                    value: 5.0,
+                },
+                ServiceQuota {
+                    kind: QuotaKind::NatGatewaysPerZone,
+                    value: 5.0,
+                },
+            ])
+            .elastic_ips(vec![Address::builder().build(); 5])
+            .nat_gateways(vec![NatGateway::builder()
+                .subnet_id("subnet-1")
+                .state(NatGatewayState::Available)
+                .build()])
+            .subnets(vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .availability_zone("eu-west-1a")
+                .build()])
+            .build()
+            .unwrap();
+        assert_eq!(
+            qc.verify_elastic_ips(),
+            vec![VerificationResult {
+                message: "The region uses 5 of 5 elastic IPs per region (100%)".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::QuotaExhausted),
+                check_id: Some("QUOTA-001"),
+                resource_type: Some(ResourceType::ServiceQuota),
+                resource_id: Some("elastic IPs per region".to_string()),
+                evidence: Some(json!({"quota": "elastic_ips", "usage": 5, "limit": 5.0})),
+            }]
+        );
+        let nat = qc.verify_nat_gateways();
+        assert_eq!(nat.len(), 1);
+        assert_eq!(nat[0].severity, Severity::Ok);
+        // Quotas that were not gathered are not verified.
+        assert!(qc.verify_network_interfaces().is_empty());
+    }
+}
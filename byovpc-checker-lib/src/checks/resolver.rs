@@ -0,0 +1,193 @@
+//! This checker inspects the Route53 Resolver query logs of the cluster VPC.
+//! It can check the following conditions right now:
+//!
+//! - Queries for api-int of the cluster failed with NXDOMAIN or SERVFAIL.
+//! - Queries for the registries the cluster pulls images from failed.
+
+use std::collections::{BTreeMap, HashSet};
+
+use aws_sdk_route53resolver::types::ResolverQueryLogConfig;
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    gatherer::aws::{
+        resolver::{log_group_name, QUERY_LOG_WINDOW},
+        shared_types::{AWSInstance, ResolverQueryLogEntry},
+    },
+    types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// Domains the cluster needs to resolve to pull its images.
+pub const REGISTRY_DOMAINS: [&str; 4] = [
+    "quay.io.",
+    "registry.redhat.io.",
+    "registry.access.redhat.com.",
+    "api.openshift.com.",
+];
+
+#[derive(Debug, Builder)]
+pub struct ResolverQueryLogChecks {
+    #[builder(default = "vec![]")]
+    query_log_configs: Vec<ResolverQueryLogConfig>,
+    #[builder(default = "vec![]")]
+    query_logs: Vec<ResolverQueryLogEntry>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+}
+
+impl ResolverQueryLogChecks {
+    fn is_relevant_query(name: &str) -> bool {
+        name.starts_with("api-int.")
+            || REGISTRY_DOMAINS
+                .iter()
+                .any(|d| name == *d || name.ends_with(&format!(".{}", d)))
+    }
+
+    fn cluster_addresses(&self) -> HashSet<&str> {
+        self.instances
+            .iter()
+            .flat_map(|i| i.instance.network_interfaces())
+            .flat_map(|eni| eni.private_ip_addresses())
+            .filter_map(|ip| ip.private_ip_address())
+            .chain(
+                self.instances
+                    .iter()
+                    .filter_map(|i| i.instance.private_ip_address()),
+            )
+            .collect()
+    }
+
+    pub fn verify_failed_queries(&self) -> Vec<VerificationResult> {
+        info!("Checking resolver query logs for failed queries");
+        if self.query_log_configs.is_empty() {
+            return vec![VerificationResult {
+                message: "Route53 Resolver query logging is not configured for the cluster VPC - DNS resolution can not be verified".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("RES-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        if !self
+            .query_log_configs
+            .iter()
+            .any(|c| c.destination_arn().and_then(log_group_name).is_some())
+        {
+            return vec![VerificationResult {
+                message: "Route53 Resolver query logs are not delivered to CloudWatch Logs - DNS resolution can not be verified".to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("RES-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let cluster_addresses = self.cluster_addresses();
+        let mut failures: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for entry in self.query_logs.iter() {
+            if !Self::is_relevant_query(&entry.query_name) {
+                continue;
+            }
+            if !cluster_addresses.is_empty() && !cluster_addresses.contains(entry.srcaddr.as_str())
+            {
+                continue;
+            }
+            *failures
+                .entry((entry.query_name.as_str(), entry.rcode.as_str()))
+                .or_insert(0) += 1;
+        }
+        if failures.is_empty() {
+            return vec![VerificationResult {
+                message: format!(
+                    "No failed DNS queries for api-int or registry domains in the last {} minutes",
+                    QUERY_LOG_WINDOW.as_secs() / 60
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: Some("RES-001"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        failures
+            .into_iter()
+            .map(|((name, rcode), count)| VerificationResult {
+                message: format!(
+                    "{} DNS queries for {} from cluster nodes failed with {} in the last {} minutes",
+                    count,
+                    name,
+                    rcode,
+                    QUERY_LOG_WINDOW.as_secs() / 60
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::DnsQueryFailed),
+                check_id: Some("RES-001"),
+                resource_type: Some(ResourceType::ResourceRecord),
+                resource_id: Some(name.to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+}
+
+impl Verifier for ResolverQueryLogChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        self.verify_failed_queries()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::baseline::Baseline;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_failed_queries_reports_api_int() {
+        let config = ResolverQueryLogConfig::builder()
+            .id("rqlc-1")
+            .destination_arn("arn:aws:logs:us-east-1:123456789012:log-group:resolver-logs")
+            .build();
+        let failed = ResolverQueryLogEntry::from_log_message(
+            r#"{"query_name":"api-int.mycluster.example.com.","query_type":"A","rcode":"NXDOMAIN","srcaddr":"10.0.1.5"}"#,
+        )
+        .unwrap();
+        let unrelated = ResolverQueryLogEntry {
+            query_name: "example.org.".to_string(),
+            query_type: "A".to_string(),
+            rcode: "SERVFAIL".to_string(),
+            srcaddr: "10.0.1.5".to_string(),
+        };
+        let rqlc = ResolverQueryLogChecksBuilder::default()
+            .query_log_configs(vec![config.clone()])
+            .query_logs(vec![failed.clone(), failed.clone(), unrelated])
+            .build()
+            .unwrap();
+        assert_eq!(
+            rqlc.verify_failed_queries(),
+            vec![VerificationResult {
+                message: "2 DNS queries for api-int.mycluster.example.com. from cluster nodes failed with NXDOMAIN in the last 60 minutes".to_string(),
+                severity: Severity::Warning,
+                code: Some(FindingCode::DnsQueryFailed),
+                check_id: Some("RES-001"),
+                resource_type: Some(ResourceType::ResourceRecord),
+                resource_id: Some("api-int.mycluster.example.com.".to_string()),
+                evidence: None,
+            }]
+        );
+        // A baselined failure stays suppressed when the number of failed
+        // queries changes.
+        let baseline = Baseline::record(&rqlc.verify_failed_queries());
+        let rqlc = ResolverQueryLogChecksBuilder::default()
+            .query_log_configs(vec![config])
+            .query_logs(vec![failed])
+            .build()
+            .unwrap();
+        assert!(baseline.contains(&rqlc.verify_failed_queries()[0]));
+    }
+}
@@ -0,0 +1,432 @@
+//! This checker verifies the network of the cluster can carry it at the
+//! maximum size of its machine pools, so autoscaling does not stall on
+//! exhausted subnets or saturated NAT gateways.
+//! It can check the following conditions right now:
+//!
+//! - The subnets of every machine pool have enough free addresses for the
+//!   nodes the pool can scale up to.
+//! - The NAT gateways of every availability zone can carry the egress of the
+//!   nodes in the zone at maximum scale.
+//!
+//! Nodes are assumed to be spread evenly across the subnets of their pool.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use aws_sdk_ec2::types::{InstanceStateName, NatGateway, NatGatewayState, RouteTable, Subnet};
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    checks::network::subnet_routetable_mapping,
+    gatherer::aws::shared_types::AWSInstance,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// The bandwidth a NAT gateway scales up to.
+const NAT_GATEWAY_MBPS: u64 = 100_000;
+/// The peak egress bandwidth assumed for a single node.
+const NODE_EGRESS_MBPS: u64 = 500;
+/// Subnets with less free addresses left at maximum scale are reported.
+const LOW_HEADROOM: u64 = 16;
+
+#[derive(Debug, Builder)]
+pub struct ScalingChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<NatGateway>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+}
+
+impl<'a> ScalingChecks<'a> {
+    /// The private subnets of the cluster - subnets without a default route
+    /// to an internet gateway.
+    fn private_subnets(&self) -> Vec<&Subnet> {
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .filter(|s| {
+                !s.subnet_id()
+                    .and_then(|id| mapping.get(id))
+                    .is_some_and(|rtb| {
+                        rtb.routes().iter().any(|r| {
+                            r.destination_cidr_block() == Some("0.0.0.0/0")
+                                && r.gateway_id().is_some_and(|g| g.starts_with("igw-"))
+                        })
+                    })
+            })
+            .collect()
+    }
+
+    /// The number of nodes every subnet hosts when all machine pools run at
+    /// their maximum size. Pools without subnets of their own use the private
+    /// cluster subnets in their availability zones.
+    fn nodes_at_max_scale(&self) -> BTreeMap<&str, u64> {
+        let private_subnets = self.private_subnets();
+        let mut nodes: BTreeMap<&str, u64> = BTreeMap::new();
+        for mp in self.cluster_info.machine_pools.iter() {
+            let Some(max_replicas) = mp.max_replicas else {
+                continue;
+            };
+            let subnets: Vec<&Subnet> = if mp.subnets.is_empty() {
+                private_subnets
+                    .iter()
+                    .filter(|s| {
+                        mp.availability_zones.is_empty()
+                            || s.availability_zone()
+                                .is_some_and(|az| mp.availability_zones.iter().any(|a| a == az))
+                    })
+                    .copied()
+                    .collect()
+            } else {
+                self.subnets
+                    .iter()
+                    .filter(|s| {
+                        s.subnet_id()
+                            .is_some_and(|id| mp.subnets.iter().any(|m| m == id))
+                    })
+                    .collect()
+            };
+            if subnets.is_empty() {
+                continue;
+            }
+            let per_subnet = max_replicas.div_ceil(subnets.len() as u64);
+            for subnet_id in subnets.iter().filter_map(|s| s.subnet_id()) {
+                *nodes.entry(subnet_id).or_default() += per_subnet;
+            }
+        }
+        nodes
+    }
+
+    /// The number of running instances in the subnet - they already hold
+    /// their addresses.
+    fn running_nodes(&self, subnet_id: &str) -> u64 {
+        self.instances
+            .iter()
+            .filter(|i| i.instance.subnet_id() == Some(subnet_id))
+            .filter(|i| {
+                i.instance
+                    .state()
+                    .and_then(|s| s.name())
+                    .is_some_and(|n| *n == InstanceStateName::Running)
+            })
+            .count() as u64
+    }
+
+    pub fn verify_subnet_capacity(&self) -> Vec<VerificationResult> {
+        info!("Checking subnet capacity at maximum scale");
+        let mut results = vec![];
+        for (subnet_id, nodes) in self.nodes_at_max_scale() {
+            let Some(subnet) = self
+                .subnets
+                .iter()
+                .find(|s| s.subnet_id() == Some(subnet_id))
+            else {
+                continue;
+            };
+            let free = subnet
+                .available_ip_address_count()
+                .unwrap_or_default()
+                .max(0) as u64;
+            let running = self.running_nodes(subnet_id);
+            let additional = nodes.saturating_sub(running);
+            let evidence = json!({
+                "nodes_at_max_scale": nodes,
+                "running_nodes": running,
+                "available_ip_address_count": free,
+            });
+            if additional > free {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} needs {} more addresses for {} nodes at maximum scale, but only has {} free",
+                        subnet_id, additional, nodes, free
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetCapacityExceeded),
+                    check_id: Some("SCALE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(evidence),
+                });
+            } else if free - additional < LOW_HEADROOM {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} has only {} free addresses left with {} nodes at maximum scale - load balancers and other services need addresses too",
+                        subnet_id,
+                        free - additional,
+                        nodes
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SCALE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(evidence),
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} can host {} nodes at maximum scale with {} free addresses",
+                        subnet_id, nodes, free
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SCALE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_nat_capacity(&self) -> Vec<VerificationResult> {
+        info!("Checking NAT gateway capacity at maximum scale");
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let mut zones: BTreeMap<&str, (u64, BTreeSet<&str>)> = BTreeMap::new();
+        for (subnet_id, nodes) in self.nodes_at_max_scale() {
+            let Some(az) = self
+                .subnets
+                .iter()
+                .find(|s| s.subnet_id() == Some(subnet_id))
+                .and_then(|s| s.availability_zone())
+            else {
+                continue;
+            };
+            let zone = zones.entry(az).or_default();
+            zone.0 += nodes;
+            let nat_ids = mapping
+                .get(subnet_id)
+                .map(|rtb| rtb.routes())
+                .unwrap_or_default()
+                .iter()
+                .filter(|r| r.destination_cidr_block() == Some("0.0.0.0/0"))
+                .filter_map(|r| r.nat_gateway_id())
+                .filter(|id| {
+                    self.nat_gateways.iter().any(|n| {
+                        n.nat_gateway_id() == Some(*id)
+                            && n.state() == Some(&NatGatewayState::Available)
+                    })
+                });
+            zone.1.extend(nat_ids);
+        }
+        let mut results = vec![];
+        for (az, (nodes, nat_ids)) in zones {
+            if nat_ids.is_empty() {
+                continue;
+            }
+            let nat_ids: Vec<&str> = nat_ids.into_iter().collect();
+            let required = nodes * NODE_EGRESS_MBPS;
+            let capacity = nat_ids.len() as u64 * NAT_GATEWAY_MBPS;
+            if required > capacity {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Availability zone {} egresses through {} NAT gateway(s) ({}) with {} Gbps, but {} nodes at maximum scale may need up to {} Gbps ({} Mbps per node)",
+                        az,
+                        nat_ids.len(),
+                        nat_ids.join(", "),
+                        capacity / 1000,
+                        nodes,
+                        required / 1000,
+                        NODE_EGRESS_MBPS
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::NatCapacityExceeded),
+                    check_id: Some("SCALE-002"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(az.to_string()),
+                    evidence: Some(json!({
+                        "nodes_at_max_scale": nodes,
+                        "nat_gateways": nat_ids,
+                    })),
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Availability zone {} egresses through {} NAT gateway(s) ({}) with {} Gbps for {} nodes at maximum scale",
+                        az,
+                        nat_ids.len(),
+                        nat_ids.join(", "),
+                        capacity / 1000,
+                        nodes
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SCALE-002"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(az.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for ScalingChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self
+            .cluster_info
+            .machine_pools
+            .iter()
+            .all(|mp| mp.max_replicas.is_none())
+        {
+            return vec![VerificationResult {
+                message: "No machine pool has a known maximum size - skipping scaling checks"
+                    .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        results.extend(self.verify_subnet_capacity());
+        results.extend(self.verify_nat_capacity());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Instance, InstanceState, Route, RouteTableAssociation};
+
+    use crate::types::{MachinePool, MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_verify_scaling() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_pools(vec![
+                MachinePool {
+                    id: "worker".to_string(),
+                    instance_type: None,
+                    availability_zones: vec![],
+                    subnets: vec![],
+                    max_replicas: Some(400),
+                },
+                MachinePool {
+                    id: "infra".to_string(),
+                    instance_type: None,
+                    availability_zones: vec!["us-east-1b".to_string()],
+                    subnets: vec![],
+                    max_replicas: Some(3),
+                },
+            ])
+            .build()
+            .unwrap();
+        let subnet = |id: &str, az: &str, free: i32| {
+            Subnet::builder()
+                .subnet_id(id)
+                .availability_zone(az)
+                .available_ip_address_count(free)
+                .build()
+        };
+        let route_table = |subnet_id: &str, nat_id: &str| {
+            RouteTable::builder()
+                .associations(
+                    RouteTableAssociation::builder()
+                        .subnet_id(subnet_id)
+                        .build(),
+                )
+                .routes(
+                    Route::builder()
+                        .destination_cidr_block("0.0.0.0/0")
+                        .nat_gateway_id(nat_id)
+                        .build(),
+                )
+                .build()
+        };
+        let nat = |id: &str| {
+            NatGateway::builder()
+                .nat_gateway_id(id)
+                .state(NatGatewayState::Available)
+                .build()
+        };
+        let sc = ScalingChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-a", "us-east-1a", 190),
+                subnet("subnet-b", "us-east-1b", 250),
+            ])
+            .routetables(vec![
+                route_table("subnet-a", "nat-a"),
+                route_table("subnet-b", "nat-b"),
+            ])
+            .nat_gateways(vec![nat("nat-a"), nat("nat-b")])
+            .instances(vec![AWSInstance {
+                instance: Instance::builder()
+                    .subnet_id("subnet-a")
+                    .state(
+                        InstanceState::builder()
+                            .name(InstanceStateName::Running)
+                            .build(),
+                    )
+                    .build(),
+                security_groups: vec![],
+            }])
+            .build()
+            .unwrap();
+        assert_eq!(
+            sc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Subnet subnet-a needs 199 more addresses for 200 nodes at maximum scale, but only has 190 free".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SubnetCapacityExceeded),
+                    check_id: Some("SCALE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-a".to_string()),
+                    evidence: Some(json!({
+                        "nodes_at_max_scale": 200,
+                        "running_nodes": 1,
+                        "available_ip_address_count": 190,
+                    })),
+                },
+                VerificationResult {
+                    message: "Subnet subnet-b can host 203 nodes at maximum scale with 250 free addresses".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SCALE-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-b".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Availability zone us-east-1a egresses through 1 NAT gateway(s) (nat-a) with 100 Gbps for 200 nodes at maximum scale".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SCALE-002"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some("us-east-1a".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Availability zone us-east-1b egresses through 1 NAT gateway(s) (nat-b) with 100 Gbps, but 203 nodes at maximum scale may need up to 101 Gbps (500 Mbps per node)".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::NatCapacityExceeded),
+                    check_id: Some("SCALE-002"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some("us-east-1b".to_string()),
+                    evidence: Some(json!({
+                        "nodes_at_max_scale": 203,
+                        "nat_gateways": ["nat-b"],
+                    })),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! This checker verifies the constraints of cluster subnets that are shared
+//! with the account of the cluster through RAM from another account - the VPC
+//! owner. A subnet is shared if its owner is not the account the data was
+//! gathered with.
+//! It can check the following conditions right now:
+//!
+//! - Reports the cluster subnets that are shared and the accounts owning them.
+//! - The cluster tag of shared subnets has the value `shared` and is added from
+//!   the account of the cluster - tags of the owner account are not visible to
+//!   it.
+//! - Clusters with public endpoints have a cluster subnet routing to an
+//!   internet gateway, as only the owner account can attach one.
+
+use aws_sdk_ec2::types::{RouteTable, Subnet};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    checks::network::{subnet_routetable_mapping, CLUSTER_TAG, DEFAULT_ROUTE},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// The account owning the subnet if it is shared with the account of the
+/// cluster.
+pub fn shared_owner<'s>(subnet: &'s Subnet, account_id: Option<&str>) -> Option<&'s str> {
+    let owner = subnet.owner_id()?;
+    (account_id.is_some_and(|a| a != owner)).then_some(owner)
+}
+
+#[derive(Debug, Builder)]
+pub struct SharedVpcChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    /// The account the data was gathered with.
+    #[builder(default)]
+    account_id: Option<String>,
+}
+
+impl<'a> SharedVpcChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .sorted_by_key(|s| s.subnet_id())
+            .collect()
+    }
+
+    /// The cluster subnets that are shared, with the account owning them.
+    fn shared_subnets(&self) -> Vec<(&Subnet, &str)> {
+        self.cluster_subnets()
+            .into_iter()
+            .filter_map(|s| Some((s, shared_owner(s, self.account_id.as_deref())?)))
+            .collect()
+    }
+
+    pub fn verify_shared_subnets(&self) -> Vec<VerificationResult> {
+        info!("Checking for cluster subnets shared from another account");
+        self.shared_subnets()
+            .into_iter()
+            .map(|(s, owner)| VerificationResult {
+                message: format!(
+                    "Subnet {} is shared from account {} - tags, routes and internet gateways of its VPC are managed by the owner account",
+                    s.subnet_id().unwrap_or_default(),
+                    owner
+                ),
+                severity: Severity::Info,
+                code: Some(FindingCode::SharedSubnet),
+                check_id: Some("SHARED-001"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some(s.subnet_id().unwrap_or_default().to_string()),
+                evidence: Some(json!({
+                    "owner_id": owner,
+                    "account_id": self.account_id,
+                })),
+            })
+            .collect()
+    }
+
+    pub fn verify_shared_subnet_tags(&self) -> Vec<VerificationResult> {
+        info!("Checking the cluster tags of shared subnets");
+        let account = self.account_id.as_deref().unwrap_or_default();
+        let mut results = vec![];
+        for (subnet, owner) in self.shared_subnets() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            let cluster_tag = subnet.tags().iter().find(|t| {
+                t.key()
+                    .and_then(|k| k.strip_prefix(CLUSTER_TAG))
+                    .is_some_and(|c| {
+                        !c.is_empty()
+                            && (c == self.cluster_info.cluster_id
+                                || c == self.cluster_info.cluster_infra_name)
+                    })
+            });
+            match cluster_tag {
+                None => results.push(VerificationResult {
+                    message: format!(
+                        "Shared subnet {} has no cluster tag visible to account {} - tags added by the owner account {} are not visible to it, add {}{}=shared from account {}",
+                        subnet_id,
+                        account,
+                        owner,
+                        CLUSTER_TAG,
+                        self.cluster_info.cluster_infra_name,
+                        account
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::SharedSubnetTagMissing),
+                    check_id: Some("SHARED-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({"owner_id": owner, "account_id": account})),
+                }),
+                Some(tag) if tag.value() != Some("shared") => results.push(VerificationResult {
+                    message: format!(
+                        "Shared subnet {} has cluster tag {}={} - subnets of a shared VPC must be tagged shared",
+                        subnet_id,
+                        tag.key().unwrap_or_default(),
+                        tag.value().unwrap_or_default()
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::SharedSubnetTagNotShared),
+                    check_id: Some("SHARED-002"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: Some(json!({
+                        "owner_id": owner,
+                        "tag": {tag.key().unwrap_or_default(): tag.value()},
+                    })),
+                }),
+                Some(_) => {}
+            }
+        }
+        results
+    }
+
+    pub fn verify_internet_gateway(&self) -> Vec<VerificationResult> {
+        info!("Checking shared VPCs of public clusters route to an internet gateway");
+        let shared = self.shared_subnets();
+        let public = !self.cluster_info.private || !self.cluster_info.ingress_private;
+        if shared.is_empty() || !public || self.cluster_info.zero_egress {
+            return vec![];
+        }
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let routes_to_igw = self.cluster_subnets().iter().any(|s| {
+            s.subnet_id()
+                .and_then(|id| mapping.get(id))
+                .is_some_and(|rtb| {
+                    rtb.routes().iter().any(|r| {
+                        r.destination_cidr_block() == Some(DEFAULT_ROUTE)
+                            && r.gateway_id().is_some_and(|g| g.starts_with("igw-"))
+                    })
+                })
+        });
+        if routes_to_igw {
+            return vec![];
+        }
+        let owners: Vec<&str> = shared.iter().map(|(_, o)| *o).unique().collect();
+        vec![VerificationResult {
+            message: format!(
+                "No cluster subnet routes {} to an internet gateway, but the cluster has public endpoints - only the VPC owner account {} can attach an internet gateway to the shared VPC",
+                DEFAULT_ROUTE,
+                owners.join(", ")
+            ),
+            severity: Severity::Critical,
+            code: Some(FindingCode::SharedVpcWithoutInternetGateway),
+            check_id: Some("SHARED-003"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(json!({"owner_ids": owners})),
+        }]
+    }
+}
+
+impl<'a> Verifier for SharedVpcChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        if self.account_id.is_none() {
+            return vec![VerificationResult {
+                message:
+                    "The AWS account could not be determined - shared subnets are not detected"
+                        .to_string(),
+                severity: Severity::Info,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        if self.shared_subnets().is_empty() {
+            return vec![VerificationResult {
+                message: "Cluster subnets are owned by the account of the cluster".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }];
+        }
+        let mut results = vec![];
+        results.extend(self.verify_shared_subnets());
+        results.extend(self.verify_shared_subnet_tags());
+        results.extend(self.verify_internet_gateway());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Route, RouteTableAssociation, Tag};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_shared_subnets() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("infra-1".to_string())
+            .build()
+            .unwrap();
+        let subnet = |id: &str, owner: &str, tag: Option<&str>| {
+            Subnet::builder()
+                .subnet_id(id)
+                .owner_id(owner)
+                .set_tags(tag.map(|value| {
+                    vec![Tag::builder()
+                        .key("kubernetes.io/cluster/infra-1")
+                        .value(value)
+                        .build()]
+                }))
+                .build()
+        };
+        let svc = SharedVpcChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-1", "111111111111", Some("shared")),
+                subnet("subnet-2", "111111111111", Some("owned")),
+                subnet("subnet-3", "111111111111", None),
+                subnet("subnet-4", "222222222222", None),
+            ])
+            .routetables(vec![RouteTable::builder()
+                .route_table_id("rtb-1")
+                .associations(
+                    RouteTableAssociation::builder()
+                        .subnet_id("subnet-1")
+                        .build(),
+                )
+                .routes(
+                    Route::builder()
+                        .destination_cidr_block("0.0.0.0/0")
+                        .nat_gateway_id("nat-1")
+                        .build(),
+                )
+                .build()])
+            .account_id(Some("222222222222".to_string()))
+            .build()
+            .unwrap();
+        assert_eq!(svc.verify_shared_subnets().len(), 3);
+        let tags = svc.verify_shared_subnet_tags();
+        assert_eq!(
+            tags.iter().map(|r| r.code).collect::<Vec<_>>(),
+            vec![
+                Some(FindingCode::SharedSubnetTagNotShared),
+                Some(FindingCode::SharedSubnetTagMissing),
+            ]
+        );
+        assert_eq!(
+            svc.verify_internet_gateway()
+                .iter()
+                .map(|r| r.message.clone())
+                .collect::<Vec<_>>(),
+            vec!["No cluster subnet routes 0.0.0.0/0 to an internet gateway, but the cluster has public endpoints - only the VPC owner account 111111111111 can attach an internet gateway to the shared VPC"]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! This checker reports whether nodes can reach AWS Systems Manager (SSM).
+//! Break-glass access to nodes during network incidents goes through the SSM
+//! agent, so knowing up front whether it works saves checking it manually.
+//! All findings are informational.
+//! It can check the following conditions right now:
+//!
+//! - The cluster subnets reach SSM through VPC endpoints, through their
+//!   default route or not at all.
+
+use aws_sdk_ec2::types::{RouteState, RouteTable, State, Subnet, VpcEndpoint};
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+
+use crate::{
+    checks::network::{route_target, subnet_routetable_mapping},
+    types::{MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// The services the SSM agent connects to, identified by the suffix of the
+/// endpoint service name, e.g. `com.amazonaws.us-east-1.ssm`.
+const SSM_SERVICES: [&str; 3] = ["ssm", "ssmmessages", "ec2messages"];
+
+#[derive(Debug, Builder)]
+pub struct SsmChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    vpc_endpoints: Vec<VpcEndpoint>,
+}
+
+impl<'a> SsmChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .sorted_by_key(|s| s.subnet_id())
+            .collect()
+    }
+
+    /// The available SSM endpoints of the VPC - `None` unless there is one for
+    /// every service the agent needs.
+    fn ssm_endpoints(&self, vpc_id: &str) -> Option<Vec<&str>> {
+        let mut endpoints = vec![];
+        for service in SSM_SERVICES {
+            let suffix = format!(".{}", service);
+            let endpoint = self.vpc_endpoints.iter().find(|e| {
+                e.vpc_id() == Some(vpc_id)
+                    && e.state() == Some(&State::Available)
+                    && e.service_name().is_some_and(|n| n.ends_with(&suffix))
+            })?;
+            endpoints.push(endpoint.vpc_endpoint_id().unwrap_or_default());
+        }
+        Some(endpoints)
+    }
+
+    pub fn verify_ssm_reachability(&self) -> Vec<VerificationResult> {
+        info!("Checking SSM reachability of the cluster subnets");
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let mut results = vec![];
+        for subnet in self.cluster_subnets() {
+            let subnet_id = subnet.subnet_id().unwrap_or_default();
+            if let Some(endpoints) = subnet.vpc_id().and_then(|id| self.ssm_endpoints(id)) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Subnet {} reaches SSM through the VPC endpoints {}",
+                        subnet_id,
+                        endpoints.join(", ")
+                    ),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SSM-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some(subnet_id.to_string()),
+                    evidence: None,
+                });
+                continue;
+            }
+            let default_route = mapping.get(subnet_id).and_then(|rtb| {
+                rtb.routes().iter().find(|r| {
+                    r.destination_cidr_block() == Some("0.0.0.0/0")
+                        && r.state() != Some(&RouteState::Blackhole)
+                })
+            });
+            let message = match default_route {
+                Some(route) => format!(
+                    "Subnet {} reaches the public SSM endpoints through {} - break-glass access depends on that egress path",
+                    subnet_id,
+                    route_target(route)
+                ),
+                None => format!(
+                    "Subnet {} can not reach SSM - there are no SSM VPC endpoints and no default route, so nodes have no break-glass access via SSM",
+                    subnet_id
+                ),
+            };
+            results.push(VerificationResult {
+                message,
+                severity: Severity::Info,
+                code: None,
+                check_id: Some("SSM-001"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some(subnet_id.to_string()),
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for SsmChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        self.verify_ssm_reachability()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Route, RouteTableAssociation};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_ssm_reachability() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let subnet =
+            |id: &str, vpc_id: &str| Subnet::builder().subnet_id(id).vpc_id(vpc_id).build();
+        let endpoint = |id: &str, service: &str| {
+            VpcEndpoint::builder()
+                .vpc_endpoint_id(id)
+                .vpc_id("vpc-1")
+                .service_name(format!("com.amazonaws.us-east-1.{}", service))
+                .state(State::Available)
+                .build()
+        };
+        let sc = SsmChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-1", "vpc-1"),
+                subnet("subnet-2", "vpc-2"),
+                subnet("subnet-3", "vpc-2"),
+            ])
+            .routetables(vec![RouteTable::builder()
+                .associations(
+                    RouteTableAssociation::builder()
+                        .subnet_id("subnet-2")
+                        .build(),
+                )
+                .routes(
+                    Route::builder()
+                        .destination_cidr_block("0.0.0.0/0")
+                        .nat_gateway_id("nat-1")
+                        .build(),
+                )
+                .build()])
+            .vpc_endpoints(vec![
+                endpoint("vpce-1", "ssm"),
+                endpoint("vpce-2", "ssmmessages"),
+                endpoint("vpce-3", "ec2messages"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            sc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Subnet subnet-1 reaches SSM through the VPC endpoints vpce-1, vpce-2, vpce-3".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SSM-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-1".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-2 reaches the public SSM endpoints through nat-1 - break-glass access depends on that egress path".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SSM-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-2".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-3 can not reach SSM - there are no SSM VPC endpoints and no default route, so nodes have no break-glass access via SSM".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("SSM-001"),
+                    resource_type: Some(ResourceType::Subnet),
+                    resource_id: Some("subnet-3".to_string()),
+                    evidence: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! This checker verifies the target groups of the cluster load balancers and
+//! the health of their targets.
+//! It can check the following conditions right now:
+//!
+//! - Registered targets are healthy.
+//! - Target groups have registered targets.
+//! - The security groups of the target instances allow the health check port.
+
+use aws_sdk_ec2::types::SecurityGroup;
+use aws_sdk_elasticloadbalancingv2::types::{TargetHealthDescription, TargetHealthStateEnum};
+use derive_builder::Builder;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::aws::shared_types::{AWSInstance, AWSTargetGroup},
+    types::{FindingCode, ResourceType, Severity, VerificationResult, Verifier},
+};
+
+/// The health check port of a target group using the port of the targets.
+const TRAFFIC_PORT: &str = "traffic-port";
+
+#[derive(Debug, Builder)]
+pub struct TargetGroupChecks {
+    #[builder(default = "vec![]")]
+    target_groups: Vec<AWSTargetGroup>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+}
+
+fn target_group_name(tg: &AWSTargetGroup) -> &str {
+    tg.target_group.target_group_name().unwrap_or_default()
+}
+
+fn target_id(target: &TargetHealthDescription) -> &str {
+    target.target().and_then(|t| t.id()).unwrap_or_default()
+}
+
+/// Whether any ingress rule of the security groups allows TCP traffic to the
+/// port.
+fn allows_ingress(security_groups: &[SecurityGroup], port: i32) -> bool {
+    security_groups.iter().any(|sg| {
+        sg.ip_permissions().iter().any(|p| match p.ip_protocol() {
+            Some("-1") => true,
+            Some("tcp") | Some("6") => {
+                p.from_port().is_some_and(|from| from <= port)
+                    && p.to_port().is_some_and(|to| port <= to)
+            }
+            _ => false,
+        })
+    })
+}
+
+impl TargetGroupChecks {
+    /// The instance of a target - targets are registered by instance ID or by
+    /// private IP.
+    fn target_instance(&self, target: &TargetHealthDescription) -> Option<&AWSInstance> {
+        let id = target_id(target);
+        self.instances.iter().find(|i| {
+            i.instance.instance_id() == Some(id) || i.instance.private_ip_address() == Some(id)
+        })
+    }
+
+    pub fn verify_target_health(&self) -> Vec<VerificationResult> {
+        info!("Checking health of the load balancer targets");
+        let mut results = vec![];
+        for tg in self.target_groups.iter() {
+            for target in tg.targets.iter() {
+                let Some(health) = target.target_health() else {
+                    continue;
+                };
+                if !matches!(
+                    health.state(),
+                    Some(TargetHealthStateEnum::Unhealthy)
+                        | Some(TargetHealthStateEnum::Unavailable)
+                ) {
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "Target {} of target group {} is {}: {}",
+                        target_id(target),
+                        target_group_name(tg),
+                        health.state().map(|s| s.as_str()).unwrap_or_default(),
+                        health.description().unwrap_or("no reason given")
+                    ),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::TargetUnhealthy),
+                    check_id: Some("TG-001"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some(
+                        tg.target_group
+                            .target_group_arn()
+                            .unwrap_or_default()
+                            .to_string(),
+                    ),
+                    evidence: Some(json!({
+                        "target": target_id(target),
+                        "reason": health.reason().map(|r| r.as_str()),
+                    })),
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_targets_registered(&self) -> Vec<VerificationResult> {
+        info!("Checking target groups have registered targets");
+        self.target_groups
+            .iter()
+            .filter(|tg| tg.targets.is_empty())
+            .map(|tg| VerificationResult {
+                message: format!(
+                    "Target group {} has no registered targets - the load balancer can not forward any traffic",
+                    target_group_name(tg)
+                ),
+                severity: Severity::Warning,
+                code: Some(FindingCode::TargetGroupEmpty),
+                check_id: Some("TG-002"),
+                resource_type: Some(ResourceType::TargetGroup),
+                resource_id: Some(tg.target_group.target_group_arn().unwrap_or_default().to_string()),
+                evidence: None,
+            })
+            .collect()
+    }
+
+    pub fn verify_health_check_ports(&self) -> Vec<VerificationResult> {
+        info!("Checking security groups allow the health check ports");
+        let mut results = vec![];
+        for tg in self.target_groups.iter() {
+            let health_check_port = tg.target_group.health_check_port();
+            for target in tg.targets.iter() {
+                let port = match health_check_port {
+                    None | Some(TRAFFIC_PORT) => target
+                        .target()
+                        .and_then(|t| t.port())
+                        .or(tg.target_group.port()),
+                    Some(port) => port.parse().ok(),
+                };
+                let Some(port) = port else {
+                    continue;
+                };
+                let Some(instance) = self.target_instance(target) else {
+                    continue;
+                };
+                if instance.security_groups.is_empty()
+                    || allows_ingress(&instance.security_groups, port)
+                {
+                    continue;
+                }
+                results.push(VerificationResult {
+                    message: format!(
+                        "Security groups of target {} do not allow the health check port {} of target group {} - the target can never become healthy",
+                        target_id(target),
+                        port,
+                        target_group_name(tg)
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::HealthCheckPortBlocked),
+                    check_id: Some("TG-003"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some(tg.target_group.target_group_arn().unwrap_or_default().to_string()),
+                    evidence: Some(json!({
+                        "port": port,
+                        "securityGroups": instance
+                            .security_groups
+                            .iter()
+                            .filter_map(|sg| sg.group_id())
+                            .collect::<Vec<_>>(),
+                    })),
+                });
+            }
+        }
+        results
+    }
+}
+
+impl Verifier for TargetGroupChecks {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_target_health());
+        results.extend(self.verify_targets_registered());
+        results.extend(self.verify_health_check_ports());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: format!(
+                    "{} target groups have healthy targets reachable by their health checks",
+                    self.target_groups.len()
+                ),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Instance, IpPermission};
+    use aws_sdk_elasticloadbalancingv2::types::{
+        TargetDescription, TargetGroup, TargetHealth, TargetHealthReasonEnum,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_verify_target_groups() {
+        let target = |id: &str, state: TargetHealthStateEnum| {
+            TargetHealthDescription::builder()
+                .target(TargetDescription::builder().id(id).port(30080).build())
+                .target_health(
+                    TargetHealth::builder()
+                        .state(state)
+                        .reason(TargetHealthReasonEnum::FailedHealthChecks)
+                        .description("Health checks failed")
+                        .build(),
+                )
+                .build()
+        };
+        let instance = |id: &str, from_port: i32, to_port: i32| AWSInstance {
+            instance: Instance::builder().instance_id(id).build(),
+            security_groups: vec![SecurityGroup::builder()
+                .group_id(format!("sg-{}", id))
+                .ip_permissions(
+                    IpPermission::builder()
+                        .ip_protocol("tcp")
+                        .from_port(from_port)
+                        .to_port(to_port)
+                        .build(),
+                )
+                .build()],
+        };
+        let tgc = TargetGroupChecksBuilder::default()
+            .target_groups(vec![
+                AWSTargetGroup {
+                    target_group: TargetGroup::builder()
+                        .target_group_name("tg-ingress")
+                        .health_check_port(TRAFFIC_PORT)
+                        .build(),
+                    targets: vec![
+                        target("i-1", TargetHealthStateEnum::Healthy),
+                        target("i-2", TargetHealthStateEnum::Unhealthy),
+                    ],
+                },
+                AWSTargetGroup {
+                    target_group: TargetGroup::builder().target_group_name("tg-empty").build(),
+                    targets: vec![],
+                },
+            ])
+            .instances(vec![
+                instance("i-1", 30000, 32767),
+                instance("i-2", 443, 443),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            tgc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Target i-2 of target group tg-ingress is unhealthy: Health checks failed".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::TargetUnhealthy),
+                    check_id: Some("TG-001"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some("".to_string()),
+                    evidence: Some(json!({
+                        "target": "i-2",
+                        "reason": "Target.FailedHealthChecks",
+                    })),
+                },
+                VerificationResult {
+                    message: "Target group tg-empty has no registered targets - the load balancer can not forward any traffic".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::TargetGroupEmpty),
+                    check_id: Some("TG-002"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some("".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Security groups of target i-2 do not allow the health check port 30080 of target group tg-ingress - the target can never become healthy".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::HealthCheckPortBlocked),
+                    check_id: Some("TG-003"),
+                    resource_type: Some(ResourceType::TargetGroup),
+                    resource_id: Some("".to_string()),
+                    evidence: Some(json!({
+                        "port": 30080,
+                        "securityGroups": ["sg-i-2"],
+                    })),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! This checker summarizes the topology of every availability zone the cluster
+//! uses in a single result, so partial-AZ configurations are obvious at a
+//! glance instead of being scattered across many per-resource results.
+//! It can check the following conditions right now:
+//!
+//! - Every zone of the cluster has the public subnet, private subnet, NAT
+//!   gateway, load balancer and running nodes any other zone of the cluster
+//!   has.
+
+use std::collections::BTreeMap;
+
+use aws_sdk_ec2::types::{InstanceStateName, NatGateway, NatGatewayState, RouteTable, Subnet};
+use derive_builder::Builder;
+use log::info;
+
+use crate::{
+    checks::network::subnet_routetable_mapping,
+    gatherer::aws::shared_types::{AWSInstance, AWSLoadBalancer},
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+/// What the cluster has in a single availability zone.
+#[derive(Debug, Default)]
+struct ZoneTopology {
+    public_subnets: usize,
+    private_subnets: usize,
+    nat_gateways: usize,
+    load_balancers: usize,
+    running_nodes: usize,
+}
+
+impl ZoneTopology {
+    /// The components of the zone with how many of each the zone has.
+    fn components(&self) -> [(&'static str, usize); 5] {
+        [
+            ("public subnet", self.public_subnets),
+            ("private subnet", self.private_subnets),
+            ("NAT gateway", self.nat_gateways),
+            ("load balancer", self.load_balancers),
+            ("running nodes", self.running_nodes),
+        ]
+    }
+}
+
+#[derive(Debug, Builder)]
+pub struct TopologyChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    routetables: Vec<RouteTable>,
+    #[builder(default = "vec![]")]
+    nat_gateways: Vec<NatGateway>,
+    #[builder(default = "vec![]")]
+    load_balancers: Vec<AWSLoadBalancer>,
+    #[builder(default = "vec![]")]
+    instances: Vec<AWSInstance>,
+}
+
+impl<'a> TopologyChecks<'a> {
+    fn cluster_subnets(&self) -> Vec<&Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .collect()
+    }
+
+    /// Collects the topology of every zone of the cluster subnets.
+    fn zone_topologies(&self) -> BTreeMap<String, ZoneTopology> {
+        let mapping = subnet_routetable_mapping(&self.subnets, &self.routetables);
+        let mut zones: BTreeMap<String, ZoneTopology> = BTreeMap::new();
+        for subnet in self.cluster_subnets() {
+            let Some(az) = subnet.availability_zone() else {
+                continue;
+            };
+            let zone = zones.entry(az.to_string()).or_default();
+            let is_public = subnet
+                .subnet_id()
+                .and_then(|id| mapping.get(id))
+                .is_some_and(|rtb| {
+                    rtb.routes().iter().any(|r| {
+                        r.destination_cidr_block() == Some("0.0.0.0/0")
+                            && r.gateway_id().is_some_and(|g| g.starts_with("igw-"))
+                    })
+                });
+            if is_public {
+                zone.public_subnets += 1;
+            } else {
+                zone.private_subnets += 1;
+            }
+        }
+        for nat in self
+            .nat_gateways
+            .iter()
+            .filter(|n| n.state() == Some(&NatGatewayState::Available))
+        {
+            let az = self
+                .subnets
+                .iter()
+                .find(|s| s.subnet_id().is_some() && s.subnet_id() == nat.subnet_id())
+                .and_then(|s| s.availability_zone());
+            if let Some(zone) = az.and_then(|az| zones.get_mut(az)) {
+                zone.nat_gateways += 1;
+            }
+        }
+        for lb in self.load_balancers.iter() {
+            for az in lb.availability_zones() {
+                if let Some(zone) = zones.get_mut(&az) {
+                    zone.load_balancers += 1;
+                }
+            }
+        }
+        for i in self.instances.iter() {
+            let running = i
+                .instance
+                .state()
+                .and_then(|s| s.name())
+                .is_some_and(|n| *n == InstanceStateName::Running);
+            let az = i.instance.placement().and_then(|p| p.availability_zone());
+            if let Some(zone) = az.filter(|_| running).and_then(|az| zones.get_mut(az)) {
+                zone.running_nodes += 1;
+            }
+        }
+        zones
+    }
+
+    pub fn verify_zone_topology(&self) -> Vec<VerificationResult> {
+        info!("Checking the topology of every availability zone");
+        let zones = self.zone_topologies();
+        zones
+            .iter()
+            .map(|(az, zone)| {
+                let summary = zone
+                    .components()
+                    .iter()
+                    .map(|(name, count)| format!("{}: {}", name, count))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                // A zone is partial if it lacks something another zone has.
+                // Zero-egress clusters do not need NAT gateways at all.
+                let missing: Vec<&str> = zone
+                    .components()
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, (_, count))| {
+                        *count == 0 && zones.values().any(|z| z.components()[*i].1 > 0)
+                    })
+                    .filter(|(_, (name, _))| {
+                        !(self.cluster_info.zero_egress && *name == "NAT gateway")
+                    })
+                    .map(|(_, (name, _))| *name)
+                    .collect();
+                if missing.is_empty() {
+                    VerificationResult {
+                        message: format!("Availability zone {}: {}", az, summary),
+                        severity: Severity::Ok,
+                        code: None,
+                        check_id: Some("TOPO-001"),
+                        resource_type: Some(ResourceType::AvailabilityZone),
+                        resource_id: Some(az.to_string()),
+                        evidence: None,
+                    }
+                } else {
+                    VerificationResult {
+                        message: format!(
+                            "Availability zone {} has no {} unlike other zones of the cluster: {}",
+                            az,
+                            missing.join(", "),
+                            summary
+                        ),
+                        severity: Severity::Warning,
+                        code: Some(FindingCode::PartialAvailabilityZone),
+                        check_id: Some("TOPO-001"),
+                        resource_type: Some(ResourceType::AvailabilityZone),
+                        resource_id: Some(az.to_string()),
+                        evidence: None,
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a> Verifier for TopologyChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        self.verify_zone_topology()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{Instance, InstanceState, Placement, Route, RouteTableAssociation};
+
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_zone_topology() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let subnet = |id: &str, az: &str| {
+            Subnet::builder()
+                .subnet_id(id)
+                .availability_zone(az)
+                .build()
+        };
+        let instance = |id: &str, az: &str| AWSInstance {
+            instance: Instance::builder()
+                .instance_id(id)
+                .state(
+                    InstanceState::builder()
+                        .name(InstanceStateName::Running)
+                        .build(),
+                )
+                .placement(Placement::builder().availability_zone(az).build())
+                .build(),
+            security_groups: vec![],
+        };
+        let tc = TopologyChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-public-a", "us-east-1a"),
+                subnet("subnet-private-a", "us-east-1a"),
+                subnet("subnet-private-b", "us-east-1b"),
+            ])
+            .routetables(vec![
+                RouteTable::builder()
+                    .associations(
+                        RouteTableAssociation::builder()
+                            .subnet_id("subnet-public-a")
+                            .build(),
+                    )
+                    .routes(
+                        Route::builder()
+                            .destination_cidr_block("0.0.0.0/0")
+                            .gateway_id("igw-1")
+                            .build(),
+                    )
+                    .build(),
+                RouteTable::builder()
+                    .associations(
+                        RouteTableAssociation::builder()
+                            .subnet_id("subnet-private-a")
+                            .build(),
+                    )
+                    .associations(
+                        RouteTableAssociation::builder()
+                            .subnet_id("subnet-private-b")
+                            .build(),
+                    )
+                    .routes(
+                        Route::builder()
+                            .destination_cidr_block("0.0.0.0/0")
+                            .nat_gateway_id("nat-1")
+                            .build(),
+                    )
+                    .build(),
+            ])
+            .nat_gateways(vec![NatGateway::builder()
+                .nat_gateway_id("nat-1")
+                .subnet_id("subnet-public-a")
+                .state(NatGatewayState::Available)
+                .build()])
+            .instances(vec![
+                instance("i-1", "us-east-1a"),
+                instance("i-2", "us-east-1b"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            tc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Availability zone us-east-1a: public subnet: 1, private subnet: 1, NAT gateway: 1, load balancer: 0, running nodes: 1".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("TOPO-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some("us-east-1a".to_string()),
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Availability zone us-east-1b has no public subnet, NAT gateway unlike other zones of the cluster: public subnet: 0, private subnet: 1, NAT gateway: 0, load balancer: 0, running nodes: 1".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::PartialAvailabilityZone),
+                    check_id: Some("TOPO-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some("us-east-1b".to_string()),
+                    evidence: None,
+                },
+            ]
+        )
+    }
+}
@@ -0,0 +1,210 @@
+//! This checker verifies the attributes of the cluster VPCs. They are not part
+//! of the VPCs themselves and have to be read one by one.
+//! It can check the following conditions right now:
+//!
+//! - DNS support is enabled, so nodes can use the AmazonProvidedDNS.
+//! - DNS hostnames are enabled, so nodes get the private DNS names they
+//!   register with.
+//! - The cluster subnets are all in one VPC. A cluster can not span VPCs.
+
+use std::collections::BTreeMap;
+
+use aws_sdk_ec2::types::Subnet;
+use derive_builder::Builder;
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+
+use crate::{
+    gatherer::aws::shared_types::VpcAttributes,
+    types::{
+        FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+    },
+};
+
+#[derive(Debug, Builder)]
+pub struct VpcChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<Subnet>,
+    #[builder(default = "vec![]")]
+    vpc_attributes: Vec<VpcAttributes>,
+}
+
+fn attributes_evidence(attributes: &VpcAttributes) -> serde_json::Value {
+    json!({
+        "enableDnsSupport": attributes.enable_dns_support,
+        "enableDnsHostnames": attributes.enable_dns_hostnames,
+    })
+}
+
+impl<'a> VpcChecks<'a> {
+    /// The configured subnets - all gathered subnets if none are configured.
+    fn cluster_subnets(&self) -> impl Iterator<Item = &Subnet> {
+        self.subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+    }
+
+    /// The attributes of the VPCs of the cluster subnets.
+    fn cluster_vpc_attributes(&self) -> Vec<&VpcAttributes> {
+        self.cluster_subnets()
+            .filter_map(|s| s.vpc_id())
+            .unique()
+            .sorted()
+            .filter_map(|vpc_id| self.vpc_attributes.iter().find(|a| a.vpc_id == vpc_id))
+            .collect()
+    }
+
+    pub fn verify_single_vpc(&self) -> Vec<VerificationResult> {
+        info!("Checking the cluster subnets are in one VPC");
+        let subnets_per_vpc: BTreeMap<&str, Vec<&str>> = self
+            .cluster_subnets()
+            .filter_map(|s| Some((s.vpc_id()?, s.subnet_id()?)))
+            .into_group_map()
+            .into_iter()
+            .collect();
+        if subnets_per_vpc.len() < 2 {
+            return vec![];
+        }
+        vec![VerificationResult {
+            message: format!(
+                "Cluster resources span multiple VPCs: {} - all cluster subnets must be in the same VPC",
+                subnets_per_vpc.keys().join(", ")
+            ),
+            severity: Severity::Critical,
+            code: Some(FindingCode::MultipleVpcs),
+            check_id: Some("VPC-003"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(json!(subnets_per_vpc)),
+        }]
+    }
+
+    pub fn verify_dns_support(&self) -> Vec<VerificationResult> {
+        info!("Checking DNS support of the VPCs");
+        self.cluster_vpc_attributes()
+            .into_iter()
+            .filter(|a| a.enable_dns_support == Some(false))
+            .map(|a| VerificationResult {
+                message: format!(
+                    "VPC {} has DNS support disabled - nodes can not resolve any name through the AmazonProvidedDNS",
+                    a.vpc_id
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::VpcDnsSupportDisabled),
+                check_id: Some("VPC-001"),
+                resource_type: Some(ResourceType::Vpc),
+                resource_id: Some(a.vpc_id.clone()),
+                evidence: Some(attributes_evidence(a)),
+            })
+            .collect()
+    }
+
+    pub fn verify_dns_hostnames(&self) -> Vec<VerificationResult> {
+        info!("Checking DNS hostnames of the VPCs");
+        self.cluster_vpc_attributes()
+            .into_iter()
+            .filter(|a| a.enable_dns_hostnames == Some(false))
+            .map(|a| VerificationResult {
+                message: format!(
+                    "VPC {} has DNS hostnames disabled - instances get no private DNS names and nodes can not register",
+                    a.vpc_id
+                ),
+                severity: Severity::Critical,
+                code: Some(FindingCode::VpcDnsHostnamesDisabled),
+                check_id: Some("VPC-002"),
+                resource_type: Some(ResourceType::Vpc),
+                resource_id: Some(a.vpc_id.clone()),
+                evidence: Some(attributes_evidence(a)),
+            })
+            .collect()
+    }
+}
+
+impl<'a> Verifier for VpcChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_single_vpc());
+        results.extend(self.verify_dns_support());
+        results.extend(self.verify_dns_hostnames());
+        if results.is_empty() {
+            results.push(VerificationResult {
+                message: "Cluster VPCs have DNS support and DNS hostnames enabled".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            });
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_verify_vpc_attributes() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .subnets(vec!["subnet-1".to_string(), "subnet-2".to_string()])
+            .build()
+            .unwrap();
+        let subnet =
+            |id: &str, vpc_id: &str| Subnet::builder().subnet_id(id).vpc_id(vpc_id).build();
+        let attributes = |vpc_id: &str, dns_support: bool, dns_hostnames: bool| VpcAttributes {
+            vpc_id: vpc_id.to_string(),
+            enable_dns_support: Some(dns_support),
+            enable_dns_hostnames: Some(dns_hostnames),
+        };
+        let vc = VpcChecksBuilder::default()
+            .cluster_info(&cluster_info)
+            .subnets(vec![
+                subnet("subnet-1", "vpc-1"),
+                subnet("subnet-2", "vpc-2"),
+                subnet("subnet-3", "vpc-3"),
+            ])
+            .vpc_attributes(vec![
+                attributes("vpc-1", true, true),
+                attributes("vpc-2", true, false),
+                attributes("vpc-3", false, false),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(
+            vc.verify(),
+            vec![
+                VerificationResult {
+                    message: "Cluster resources span multiple VPCs: vpc-1, vpc-2 - all cluster subnets must be in the same VPC".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::MultipleVpcs),
+                    check_id: Some("VPC-003"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: Some(json!({
+                        "vpc-1": ["subnet-1"],
+                        "vpc-2": ["subnet-2"],
+                    })),
+                },
+                VerificationResult {
+                    message: "VPC vpc-2 has DNS hostnames disabled - instances get no private DNS names and nodes can not register".to_string(),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::VpcDnsHostnamesDisabled),
+                    check_id: Some("VPC-002"),
+                    resource_type: Some(ResourceType::Vpc),
+                    resource_id: Some("vpc-2".to_string()),
+                    evidence: Some(json!({
+                        "enableDnsSupport": true,
+                        "enableDnsHostnames": false,
+                    })),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! This checker verifies the availability zones used by the cluster.
+//! It can check the following conditions right now:
+//!
+//! - The availability zones of the cluster subnets are enabled for the account.
+//! - The instance types of the machine pools are offered in their zones.
+
+use std::collections::BTreeSet;
+
+use aws_sdk_ec2::types::{
+    AvailabilityZone, AvailabilityZoneOptInStatus, AvailabilityZoneState, InstanceTypeOffering,
+};
+use derive_builder::Builder;
+use log::info;
+
+use crate::types::{
+    FindingCode, MinimalClusterInfo, ResourceType, Severity, VerificationResult, Verifier,
+};
+
+#[derive(Debug, Builder)]
+pub struct AvailabilityZoneChecks<'a> {
+    cluster_info: &'a MinimalClusterInfo,
+    #[builder(default = "vec![]")]
+    subnets: Vec<aws_sdk_ec2::types::Subnet>,
+    #[builder(default = "vec![]")]
+    availability_zones: Vec<AvailabilityZone>,
+    #[builder(default = "vec![]")]
+    instance_type_offerings: Vec<InstanceTypeOffering>,
+}
+
+impl<'a> AvailabilityZoneChecks<'a> {
+    /// Returns the zones the cluster is using: the zones of the configured
+    /// subnets and the zones explicitly set on machine pools.
+    fn cluster_zones(&self) -> BTreeSet<String> {
+        let mut zones: BTreeSet<String> = self
+            .subnets
+            .iter()
+            .filter(|s| self.cluster_info.is_cluster_subnet(s))
+            .filter_map(|s| s.availability_zone.clone())
+            .collect();
+        for mp in self.cluster_info.machine_pools.iter() {
+            zones.extend(mp.availability_zones.iter().cloned());
+        }
+        zones
+    }
+
+    pub fn verify_zones_enabled(&self) -> Vec<VerificationResult> {
+        info!("Checking availability zones are enabled");
+        let mut results = vec![];
+        for zone in self.cluster_zones() {
+            let Some(az) = self
+                .availability_zones
+                .iter()
+                .find(|az| az.zone_name() == Some(zone.as_str()))
+            else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Availability zone {} used by the cluster was not found in the region",
+                        zone
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::AzNotFound),
+                    check_id: Some("AZ-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(zone.to_string()),
+                    evidence: None,
+                });
+                continue;
+            };
+            if az.opt_in_status() == Some(&AvailabilityZoneOptInStatus::NotOptedIn) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Availability zone {} used by the cluster is not enabled for this account",
+                        zone
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::AzNotOptedIn),
+                    check_id: Some("AZ-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(zone.to_string()),
+                    evidence: None,
+                });
+            } else if az.state() != Some(&AvailabilityZoneState::Available) {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Availability zone {} used by the cluster is not available: {}",
+                        zone,
+                        az.state().map_or("unknown", |s| s.as_str())
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::AzUnavailable),
+                    check_id: Some("AZ-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(zone.to_string()),
+                    evidence: None,
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!("Availability zone {} is enabled and available", zone),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("AZ-001"),
+                    resource_type: Some(ResourceType::AvailabilityZone),
+                    resource_id: Some(zone.to_string()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+
+    pub fn verify_instance_type_offerings(&self) -> Vec<VerificationResult> {
+        info!("Checking instance type offerings per availability zone");
+        let mut results = vec![];
+        let cluster_zones = self.cluster_zones();
+        for mp in self.cluster_info.machine_pools.iter() {
+            let Some(ref instance_type) = mp.instance_type else {
+                continue;
+            };
+            let zones: Vec<&String> = if mp.availability_zones.is_empty() {
+                cluster_zones.iter().collect()
+            } else {
+                mp.availability_zones.iter().collect()
+            };
+            let missing: Vec<&str> = zones
+                .iter()
+                .filter(|zone| {
+                    !self.instance_type_offerings.iter().any(|o| {
+                        o.location() == Some(zone.as_str())
+                            && o.instance_type().map(|t| t.as_str()) == Some(instance_type)
+                    })
+                })
+                .map(|z| z.as_str())
+                .collect();
+            if missing.is_empty() {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance type {} (machine pool {}) is offered in all its availability zones",
+                        instance_type, mp.id
+                    ),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("AZ-002"),
+                    resource_type: Some(ResourceType::MachinePool),
+                    resource_id: Some(mp.id.clone()),
+                    evidence: None,
+                });
+            } else {
+                results.push(VerificationResult {
+                    message: format!(
+                        "Instance type {} (machine pool {}) is not offered in availability zones: {}",
+                        instance_type,
+                        mp.id,
+                        missing.join(", ")
+                    ),
+                    severity: Severity::Critical,
+                    code: Some(FindingCode::InstanceTypeNotOffered),
+                    check_id: Some("AZ-002"),
+                    resource_type: Some(ResourceType::MachinePool),
+                    resource_id: Some(mp.id.clone()),
+                    evidence: None,
+                });
+            }
+        }
+        results
+    }
+}
+
+impl<'a> Verifier for AvailabilityZoneChecks<'a> {
+    fn verify(&self) -> Vec<VerificationResult> {
+        let mut results = vec![];
+        results.extend(self.verify_zones_enabled());
+        results.extend(self.verify_instance_type_offerings());
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{InstanceType, LocationType};
+
+    use crate::types::{MachinePool, MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    fn make_zone(name: &str, opt_in: AvailabilityZoneOptInStatus) -> AvailabilityZone {
+        AvailabilityZone::builder()
+            .zone_name(name)
+            .state(AvailabilityZoneState::Available)
+            .opt_in_status(opt_in)
+            .build()
+    }
+
+    fn make_offering(instance_type: &str, zone: &str) -> InstanceTypeOffering {
+        InstanceTypeOffering::builder()
+            .instance_type(InstanceType::from(instance_type))
+            .location_type(LocationType::AvailabilityZone)
+            .location(zone)
+            .build()
+    }
+
+    #[test]
+    fn test_verify_zones_enabled_not_opted_in() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_pools(vec![MachinePool {
+                id: "worker".to_string(),
+                instance_type: Some("m5.xlarge".to_string()),
+                availability_zones: vec!["us-west-2-lax-1a".to_string()],
+                subnets: vec![],
+                max_replicas: None,
+            }])
+            .build()
+            .unwrap();
+        let azc = AvailabilityZoneChecksBuilder::default()
+            .cluster_info(&mci)
+            .availability_zones(vec![make_zone(
+                "us-west-2-lax-1a",
+                AvailabilityZoneOptInStatus::NotOptedIn,
+            )])
+            .build()
+            .unwrap();
+        assert_eq!(
+            azc.verify_zones_enabled(),
+            vec![VerificationResult {
+                message: "Availability zone us-west-2-lax-1a used by the cluster is not enabled for this account".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::AzNotOptedIn),
+                check_id: Some("AZ-001"),
+                resource_type: Some(ResourceType::AvailabilityZone),
+                resource_id: Some("us-west-2-lax-1a".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+
+    #[test]
+    fn test_verify_instance_type_not_offered() {
+        let mci = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .machine_pools(vec![MachinePool {
+                id: "worker".to_string(),
+                instance_type: Some("m5.xlarge".to_string()),
+                availability_zones: vec!["us-east-1a".to_string(), "us-east-1e".to_string()],
+                subnets: vec![],
+                max_replicas: None,
+            }])
+            .build()
+            .unwrap();
+        let azc = AvailabilityZoneChecksBuilder::default()
+            .cluster_info(&mci)
+            .instance_type_offerings(vec![make_offering("m5.xlarge", "us-east-1a")])
+            .build()
+            .unwrap();
+        assert_eq!(
+            azc.verify_instance_type_offerings(),
+            vec![VerificationResult {
+                message: "Instance type m5.xlarge (machine pool worker) is not offered in availability zones: us-east-1e".to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::InstanceTypeNotOffered),
+                check_id: Some("AZ-002"),
+                resource_type: Some(ResourceType::MachinePool),
+                resource_id: Some("worker".to_string()),
+                evidence: None,
+            }]
+        )
+    }
+}
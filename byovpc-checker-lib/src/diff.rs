@@ -0,0 +1,239 @@
+//! Compares two snapshots of a cluster, to answer what changed in the VPC
+//! since the cluster last worked.
+//!
+//! Resources are compared in the format of the snapshot and matched by their
+//! ID - resources without an ID (e.g. instance type offerings) are matched by
+//! their whole content, so a change shows up as a removed and an added one.
+//! Findings are matched like in a baseline: by check ID, code and the AWS
+//! resources named in their message.
+
+use std::fmt::Display;
+
+use itertools::Itertools;
+use serde_json::{Map, Value};
+
+use crate::{
+    baseline::Baseline,
+    gatherer::aws::snapshot::Snapshot,
+    types::{Severity, VerificationResult},
+};
+
+/// The fields identifying a resource, in the order they are looked up - the
+/// IDs of referenced resources (e.g. the `subnet_id` of a NAT gateway) come
+/// after the IDs of the resources referencing them.
+const ID_FIELDS: [&str; 19] = [
+    "network_interface_id",
+    "nat_gateway_id",
+    "route_table_id",
+    "network_acl_id",
+    "vpc_endpoint_id",
+    "egress_only_internet_gateway_id",
+    "allocation_id",
+    "instance_id",
+    "target_group_arn",
+    "load_balancer_arn",
+    "load_balancer_name",
+    "dhcp_options_id",
+    "subnet_id",
+    "vpc_id",
+    "zone_name",
+    "arn",
+    "id",
+    "load_balancer",
+    "name",
+];
+
+/// Fields of a snapshot that describe the run instead of the cluster.
+const IGNORED_FIELDS: [&str; 3] = ["version", "environment", "failed"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// The fields of the resource that changed.
+    Changed(Vec<String>),
+}
+
+/// A resource that differs between two snapshots.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceChange {
+    /// The field of the snapshot holding the resource, e.g. `subnets`.
+    pub data_set: String,
+    pub resource: String,
+    pub change: Change,
+}
+
+impl Display for ResourceChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.change {
+            Change::Added => write!(f, "+ {} {}", self.data_set, self.resource),
+            Change::Removed => write!(f, "- {} {}", self.data_set, self.resource),
+            Change::Changed(ref fields) => write!(
+                f,
+                "~ {} {}: {}",
+                self.data_set,
+                self.resource,
+                fields.join(", ")
+            ),
+        }
+    }
+}
+
+/// The resource inside the variant of an enum, e.g. the `Modern` load
+/// balancers.
+fn unwrap_variant(resource: &Value) -> &Value {
+    match resource.as_object() {
+        Some(map) if map.len() == 1 => map
+            .values()
+            .next()
+            .filter(|v| v.is_object())
+            .unwrap_or(resource),
+        _ => resource,
+    }
+}
+
+/// The ID of the resource - its whole content if it has none.
+fn resource_id(resource: &Value) -> String {
+    let inner = unwrap_variant(resource);
+    ID_FIELDS
+        .iter()
+        .find_map(|field| inner.get(field).and_then(|v| v.as_str()))
+        .map_or_else(|| resource.to_string(), str::to_string)
+}
+
+/// The fields of two versions of a resource that differ.
+fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let empty = Map::new();
+    let before = unwrap_variant(before).as_object().unwrap_or(&empty);
+    let after = unwrap_variant(after).as_object().unwrap_or(&empty);
+    before
+        .keys()
+        .chain(after.keys())
+        .unique()
+        .filter(|k| before.get(*k) != after.get(*k))
+        .cloned()
+        .collect()
+}
+
+/// The resources of a data set that differ, in the order of the snapshots.
+fn diff_data_set(data_set: &str, before: &[Value], after: &[Value]) -> Vec<ResourceChange> {
+    let change = |resource: String, change| ResourceChange {
+        data_set: data_set.to_string(),
+        resource,
+        change,
+    };
+    let mut changes = vec![];
+    for old in before {
+        let id = resource_id(old);
+        match after.iter().find(|new| resource_id(new) == id) {
+            None => changes.push(change(id, Change::Removed)),
+            Some(new) if new != old => {
+                changes.push(change(id, Change::Changed(changed_fields(old, new))))
+            }
+            Some(_) => {}
+        }
+    }
+    for new in after {
+        let id = resource_id(new);
+        if !before.iter().any(|old| resource_id(old) == id) {
+            changes.push(change(id, Change::Added));
+        }
+    }
+    changes
+}
+
+/// The resources that differ between the snapshots in their JSON format.
+pub fn diff_values(before: &Value, after: &Value) -> Vec<ResourceChange> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut changes = vec![];
+    for field in before.keys().chain(after.keys()).unique() {
+        if IGNORED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let (old, new) = (&before.get(field), &after.get(field));
+        match (old, new) {
+            (Some(Value::Array(old)), Some(Value::Array(new))) => {
+                changes.extend(diff_data_set(field, old, new))
+            }
+            (Some(Value::Array(old)), None) => changes.extend(diff_data_set(field, old, &[])),
+            (None, Some(Value::Array(new))) => changes.extend(diff_data_set(field, &[], new)),
+            // Single objects, e.g. the cluster information.
+            (Some(old), Some(new)) if old != new => changes.push(ResourceChange {
+                data_set: field.clone(),
+                resource: field.clone(),
+                change: Change::Changed(changed_fields(old, new)),
+            }),
+            _ => {}
+        }
+    }
+    changes
+}
+
+/// The resources that differ between the snapshots.
+pub fn diff_snapshots(before: &Snapshot, after: &Snapshot) -> Vec<ResourceChange> {
+    diff_values(
+        &serde_json::to_value(before).unwrap_or_default(),
+        &serde_json::to_value(after).unwrap_or_default(),
+    )
+}
+
+/// The findings that are new in the results after and the ones that were
+/// resolved since the results before.
+pub fn diff_findings(
+    before: &[VerificationResult],
+    after: &[VerificationResult],
+) -> (Vec<VerificationResult>, Vec<VerificationResult>) {
+    let findings = |results: &[VerificationResult], known: &Baseline| {
+        results
+            .iter()
+            .filter(|r| r.severity != Severity::Ok && r.check_id.is_some() && !known.contains(r))
+            .cloned()
+            .collect()
+    };
+    (
+        findings(after, &Baseline::record(before)),
+        findings(before, &Baseline::record(after)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_diff_values() {
+        let before = json!({
+            "version": 1,
+            "environment": {"gathered_at": "yesterday"},
+            "subnets": [
+                {"subnet_id": "subnet-1", "vpc_id": "vpc-1", "tags": []},
+                {"subnet_id": "subnet-2", "vpc_id": "vpc-1", "tags": []},
+            ],
+            "load_balancers": [{"Modern": {"load_balancer_arn": "arn:lb", "state": "active"}}],
+        });
+        let after = json!({
+            "version": 1,
+            "environment": {"gathered_at": "today"},
+            "subnets": [
+                {"subnet_id": "subnet-1", "vpc_id": "vpc-1", "tags": [{"key": "Name", "value": "a"}]},
+                {"subnet_id": "subnet-3", "vpc_id": "vpc-1", "tags": []},
+            ],
+            "load_balancers": [{"Modern": {"load_balancer_arn": "arn:lb", "state": "active"}}],
+        });
+        assert_eq!(
+            diff_values(&before, &after)
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "~ subnets subnet-1: tags",
+                "- subnets subnet-2",
+                "+ subnets subnet-3",
+            ]
+        );
+    }
+}
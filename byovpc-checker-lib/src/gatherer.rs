@@ -1,9 +1,13 @@
 use async_trait::async_trait;
 use std::error::Error;
 pub mod aws;
+pub mod azure;
+pub mod k8s;
+pub mod local;
+pub mod ocm;
 
 #[async_trait]
 pub trait Gatherer {
     type Resource;
     async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>>;
-}
\ No newline at end of file
+}
@@ -0,0 +1,1784 @@
+pub mod credentials;
+pub mod dns;
+pub mod ec2;
+pub mod health;
+pub mod iam;
+pub mod loadbalancer;
+pub mod loadbalancerv2;
+pub mod overrides;
+pub mod patch;
+pub mod progress;
+pub mod quotas;
+pub mod resolver;
+pub mod retry;
+pub mod s3;
+pub mod shared_types;
+pub mod snapshot;
+
+pub use crate::gatherer::aws::loadbalancer::get_classic_load_balancers;
+use crate::types::{DataSet, GatherFailure, InvariantError, MinimalClusterInfo};
+
+use crate::gatherer::Gatherer;
+use aws_config::meta::region::RegionProviderChain;
+use aws_config::retry::RetryConfig;
+use aws_config::BehaviorVersion;
+use aws_config::Region;
+use aws_config::SdkConfig;
+use aws_credential_types::provider::ProvideCredentials;
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use aws_sdk_ec2::Client as EC2Client;
+use aws_sdk_elasticloadbalancing::Client as ELBv1Client;
+use aws_sdk_elasticloadbalancingv2::Client as ELBv2Client;
+use aws_sdk_route53::types::HostedZone;
+use aws_sdk_route53::Client as Route53Client;
+use aws_sdk_route53resolver::Client as ResolverClient;
+use aws_sdk_s3::Client as S3Client;
+use aws_sigv4::http_request::{sign, SignableBody, SignableRequest, SigningSettings};
+use aws_sigv4::sign::v4;
+use credentials::{with_expiry_detection, PolicyDenial};
+use headers::Authorization;
+use health::HealthEvent;
+use hyper::client::HttpConnector;
+use hyper::Uri;
+use hyper_proxy::{Intercept, Proxy, ProxyConnector};
+use iam::InstanceProfile;
+use itertools::Itertools;
+use log::debug;
+use log::error;
+use log::info;
+use overrides::{Overridable, Override};
+use quotas::ServiceQuota;
+use serde::{Deserialize, Serialize};
+use shared_types::AWSInstance;
+use shared_types::AWSLoadBalancer;
+use shared_types::AWSLoadBalancerAttributes;
+use shared_types::AWSLoadBalancerEni;
+use shared_types::AWSTargetGroup;
+use shared_types::AccessLogBucket;
+use shared_types::HostedZoneWithRecords;
+use shared_types::ResolverQueryLogEntry;
+use shared_types::VpcAttributes;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use url::Url;
+
+/// Struct that holds all data available in AWS once we gathered it.
+#[derive(Clone, Debug)]
+pub struct AWSClusterData {
+    pub subnets: Vec<aws_sdk_ec2::types::Subnet>,
+    pub routetables: Vec<aws_sdk_ec2::types::RouteTable>,
+    pub load_balancers: Vec<AWSLoadBalancer>,
+    pub load_balancer_enis: Vec<AWSLoadBalancerEni>,
+    pub instances: Vec<AWSInstance>,
+    pub hosted_zones: Vec<HostedZoneWithRecords>,
+    pub availability_zones: Vec<aws_sdk_ec2::types::AvailabilityZone>,
+    pub instance_type_offerings: Vec<aws_sdk_ec2::types::InstanceTypeOffering>,
+    pub resolver_query_log_configs: Vec<aws_sdk_route53resolver::types::ResolverQueryLogConfig>,
+    pub resolver_query_logs: Vec<ResolverQueryLogEntry>,
+    pub nat_gateways: Vec<aws_sdk_ec2::types::NatGateway>,
+    pub internet_gateways: Vec<aws_sdk_ec2::types::InternetGateway>,
+    pub egress_only_internet_gateways: Vec<aws_sdk_ec2::types::EgressOnlyInternetGateway>,
+    pub elastic_ips: Vec<aws_sdk_ec2::types::Address>,
+    pub target_groups: Vec<AWSTargetGroup>,
+    pub load_balancer_attributes: Vec<AWSLoadBalancerAttributes>,
+    /// The buckets of the load balancers with enabled access logs.
+    pub access_log_buckets: Vec<AccessLogBucket>,
+    pub vpc_endpoints: Vec<aws_sdk_ec2::types::VpcEndpoint>,
+    pub network_acls: Vec<aws_sdk_ec2::types::NetworkAcl>,
+    pub vpcs: Vec<aws_sdk_ec2::types::Vpc>,
+    pub dhcp_options: Vec<aws_sdk_ec2::types::DhcpOptions>,
+    pub vpc_attributes: Vec<VpcAttributes>,
+    /// All ENIs in the subnets, including the ENIs of other AWS services.
+    pub subnet_network_interfaces: Vec<aws_sdk_ec2::types::NetworkInterface>,
+    /// API calls denied by a policy while gathering.
+    pub denials: Vec<PolicyDenial>,
+    /// Open events of the AWS Health API - only gathered if requested.
+    pub health_events: Vec<HealthEvent>,
+    /// The instance profiles of the instances - only gathered if requested.
+    pub instance_profiles: Vec<InstanceProfile>,
+    /// The quotas of the region - only gathered if requested.
+    pub service_quotas: Vec<ServiceQuota>,
+    /// The flow logs of the VPCs and subnets - only gathered if requested.
+    pub flow_logs: Vec<aws_sdk_ec2::types::FlowLog>,
+    /// The account the data was gathered with, if it could be determined.
+    pub account_id: Option<String>,
+    /// Data sets that could not be gathered.
+    pub failed: Vec<GatherFailure>,
+}
+
+impl AWSClusterData {
+    fn is_empty(&self, set: DataSet) -> bool {
+        match set {
+            DataSet::Subnets => self.subnets.is_empty(),
+            DataSet::RouteTables => self.routetables.is_empty(),
+            DataSet::NetworkAcls => self.network_acls.is_empty(),
+            DataSet::NatGateways => self.nat_gateways.is_empty(),
+            DataSet::InternetGateways => self.internet_gateways.is_empty(),
+            DataSet::EgressOnlyInternetGateways => self.egress_only_internet_gateways.is_empty(),
+            DataSet::ElasticIps => self.elastic_ips.is_empty(),
+            DataSet::VpcEndpoints => self.vpc_endpoints.is_empty(),
+            DataSet::SubnetNetworkInterfaces => self.subnet_network_interfaces.is_empty(),
+            DataSet::LoadBalancers => self.load_balancers.is_empty(),
+            DataSet::LoadBalancerEnis => self.load_balancer_enis.is_empty(),
+            DataSet::LoadBalancerAttributes => self.load_balancer_attributes.is_empty(),
+            DataSet::AccessLogBuckets => self.access_log_buckets.is_empty(),
+            DataSet::TargetGroups => self.target_groups.is_empty(),
+            DataSet::HostedZones => self.hosted_zones.is_empty(),
+            DataSet::AvailabilityZones => self.availability_zones.is_empty(),
+            DataSet::InstanceTypeOfferings => self.instance_type_offerings.is_empty(),
+            DataSet::ResolverQueryLogs => self.resolver_query_logs.is_empty(),
+            DataSet::HealthEvents => self.health_events.is_empty(),
+            DataSet::Vpcs => self.vpcs.is_empty(),
+            DataSet::DhcpOptions => self.dhcp_options.is_empty(),
+            DataSet::VpcAttributes => self.vpc_attributes.is_empty(),
+            DataSet::Instances => self.instances.is_empty(),
+            DataSet::InstanceProfiles => self.instance_profiles.is_empty(),
+            DataSet::ServiceQuotas => self.service_quotas.is_empty(),
+            DataSet::FlowLogs => self.flow_logs.is_empty(),
+        }
+    }
+
+    /// The data sets the checks can not rely on: data sets that could not be
+    /// gathered, that are empty although a cluster always has some, or that
+    /// are gathered from a missing data set.
+    pub fn missing_data_sets(&self) -> Vec<GatherFailure> {
+        let mut missing: Vec<GatherFailure> = vec![];
+        for set in DataSet::ALL {
+            let (reason, empty) = if let Some(f) = self.failed.iter().find(|f| f.set == set) {
+                (f.reason.clone(), false)
+            } else if set.never_empty() && self.is_empty(set) {
+                ("none found".to_string(), true)
+            } else if let Some(u) = set
+                .upstream()
+                .iter()
+                .find(|u| missing.iter().any(|m| m.set == **u))
+            {
+                (format!("{} missing", u), false)
+            } else {
+                continue;
+            };
+            missing.push(GatherFailure { set, reason, empty });
+        }
+        missing
+    }
+}
+
+tokio::task_local! {
+    /// Data sets that could not be gathered - the gathering tasks record them
+    /// here instead of aborting, so the remaining checks still run. Every
+    /// gathering run has its own list, so several clusters can be gathered at
+    /// the same time.
+    static FAILED: Arc<Mutex<Vec<GatherFailure>>>;
+
+    /// Limits how many gathering steps of a gathering run call the AWS APIs
+    /// at the same time.
+    static CONCURRENCY: Arc<Semaphore>;
+}
+
+/// Waits until the gathering run allows another gathering step to call the
+/// AWS APIs - steps outside of a gathering run, e.g. of the preflight checks,
+/// are not limited.
+pub(crate) async fn concurrency_permit() -> Option<OwnedSemaphorePermit> {
+    let semaphore = CONCURRENCY.try_with(Arc::clone).ok()?;
+    semaphore.acquire_owned().await.ok()
+}
+
+/// Logs that a data set could not be gathered and continues without it. The
+/// AWS error code, e.g. `AccessDenied`, is kept as the reason if there is one.
+fn gathering_failed<T, E: Display + Debug>(set: DataSet, what: &str, e: E) -> Vec<T> {
+    progress::clear();
+    error!("Could not retrieve {}: {}", what, e);
+    let reason = credentials::error_code(&format!("{:?}", e)).unwrap_or_else(|| e.to_string());
+    FAILED.with(|failed| {
+        let mut failed = failed.lock().unwrap();
+        if !failed.iter().any(|f| f.set == set) {
+            failed.push(GatherFailure {
+                set,
+                reason,
+                empty: false,
+            });
+        }
+    });
+    vec![]
+}
+
+/// Whether gathering the data set failed - the data sets gathered from it are
+/// skipped then, as they can not be narrowed down to the cluster.
+fn has_failed(set: DataSet) -> bool {
+    FAILED.with(|failed| failed.lock().unwrap().iter().any(|f| f.set == set))
+}
+
+fn take_failures() -> Vec<GatherFailure> {
+    FAILED.with(|failed| std::mem::take(&mut *failed.lock().unwrap()))
+}
+
+/// Spawns a gathering task that records its failures and denials in the
+/// gathering run of the task spawning it.
+fn spawn_in_run<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let failed = FAILED.with(Arc::clone);
+    let denials = credentials::DENIALS.with(Arc::clone);
+    let expired = credentials::EXPIRED.with(Arc::clone);
+    let concurrency = CONCURRENCY.with(Arc::clone);
+    tokio::spawn(FAILED.scope(
+        failed,
+        credentials::DENIALS.scope(
+            denials,
+            credentials::EXPIRED.scope(expired, CONCURRENCY.scope(concurrency, future)),
+        ),
+    ))
+}
+
+/// The proxy set in `HTTPS_PROXY` or `https_proxy`.
+pub fn https_proxy_from_env() -> Option<String> {
+    std::env::var("HTTPS_PROXY")
+        .or_else(|_v| std::env::var("https_proxy"))
+        .ok()
+}
+
+/// A client for the HTTP requests made besides the AWS SDK, sending them
+/// through the proxy if one is given.
+pub fn http_client(proxy: Option<&str>) -> reqwest::Client {
+    let Some(proxy) = proxy else {
+        return reqwest::Client::new();
+    };
+    match reqwest::Proxy::all(proxy).and_then(|p| reqwest::Client::builder().proxy(p).build()) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("Configured proxy {} can not be used: {}", proxy, e);
+            reqwest::Client::new()
+        }
+    }
+}
+
+/// Returns a `ProxyConnector<HttpConnector>` sending all requests through the
+/// proxy - `None` if the proxy is not a valid URL.
+pub fn proxy_connector(proxy: &str) -> Option<ProxyConnector<HttpConnector>> {
+    let proxy_url: Url = proxy.parse().ok()?;
+    let mut proxy_uri: Uri = proxy.parse().ok()?;
+    if proxy_uri.scheme().is_none() {
+        error!("Configured proxy did not specify a scheme - falling back to HTTP.");
+        proxy_uri = format!("http://{}", proxy).parse().ok()?;
+    }
+    let mut proxy = Proxy::new(Intercept::All, proxy_uri);
+
+    if let Some(password) = proxy_url.password() {
+        proxy.set_authorization(Authorization::basic(proxy_url.username(), password));
+    }
+
+    let connector = HttpConnector::new();
+    Some(ProxyConnector::from_proxy(connector, proxy).unwrap())
+}
+
+/// Returns the proxy without its credentials.
+pub fn proxy_without_credentials(proxy: &str) -> Option<String> {
+    let mut proxy_url: Url = proxy.parse().ok()?;
+    let _ = proxy_url.set_username("");
+    let _ = proxy_url.set_password(None);
+    Some(proxy_url.to_string())
+}
+
+/// Describes how the data was gathered, so the results can be interpreted by
+/// someone who did not run the tool.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Environment {
+    pub version: String,
+    pub region: Option<String>,
+    pub credentials: String,
+    pub proxy: Option<String>,
+    /// When the data was gathered, in seconds since the Unix epoch. The
+    /// results describe AWS at that time - for a snapshot this can be long
+    /// before the checks ran.
+    #[serde(default)]
+    pub gathered_at: Option<u64>,
+    /// Whether the data was read from a snapshot instead of gathered live.
+    #[serde(default)]
+    pub from_snapshot: bool,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a duration in seconds as the two most significant units, e.g.
+/// `2h 5m`.
+pub fn format_age(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+impl Environment {
+    pub fn detect(aws_config: &SdkConfig, profile: &str, proxy: Option<&str>) -> Self {
+        Environment {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            region: aws_config.region().map(|r| r.to_string()),
+            credentials: credentials::credentials_source(profile),
+            proxy: proxy.and_then(proxy_without_credentials),
+            gathered_at: Some(now()),
+            from_snapshot: false,
+        }
+    }
+
+    /// Where the data comes from: `live` or `snapshot`.
+    pub fn source(&self) -> &'static str {
+        if self.from_snapshot {
+            "snapshot"
+        } else {
+            "live"
+        }
+    }
+
+    /// When the data was gathered as an RFC 3339 timestamp.
+    pub fn gathered_at_utc(&self) -> Option<String> {
+        let gathered_at = i64::try_from(self.gathered_at?).ok()?;
+        aws_sdk_ec2::primitives::DateTime::from_secs(gathered_at)
+            .fmt(aws_sdk_ec2::primitives::DateTimeFormat::DateTime)
+            .ok()
+    }
+
+    /// Describes how fresh the data is: live, or how long ago the snapshot
+    /// was gathered.
+    pub fn freshness(&self) -> String {
+        if !self.from_snapshot {
+            return "live".to_string();
+        }
+        match self.gathered_at {
+            Some(gathered_at) => format!(
+                "snapshot gathered {} ago",
+                format_age(now().saturating_sub(gathered_at))
+            ),
+            None => "snapshot gathered at an unknown time".to_string(),
+        }
+    }
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Version: {}", self.version)?;
+        writeln!(f, "Region: {}", self.region.as_deref().unwrap_or("unknown"))?;
+        writeln!(f, "Credentials: {}", self.credentials)?;
+        writeln!(f, "Proxy: {}", self.proxy.as_deref().unwrap_or("none"))?;
+        write!(f, "Data: {}", self.freshness())
+    }
+}
+
+/// Will setup the SdkConfig with a proxy if one is given.
+/// If a profile is given it is used instead of the default profile, if a
+/// region is given it is used instead of the region of the AWS configuration.
+pub async fn aws_setup(
+    profile: Option<&str>,
+    region: Option<&str>,
+    proxy: Option<&str>,
+) -> SdkConfig {
+    let region_provider =
+        RegionProviderChain::first_try(region.map(|r| Region::new(r.to_string())))
+            .or_default_provider()
+            .or_else("us-east-1");
+    debug!("Using region: {}", region_provider.region().await.unwrap());
+    // Failed requests are retried by the gatherers, see `retry`.
+    let mut loader = aws_config::defaults(BehaviorVersion::latest())
+        .region(region_provider)
+        .retry_config(RetryConfig::disabled());
+    if let Some(profile) = profile {
+        debug!("Using profile: {}", profile);
+        loader = loader.profile_name(profile);
+    }
+    let config = if let Some(proxy) = proxy.and_then(proxy_connector) {
+        debug!("Using proxy");
+        let client =
+            aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder::new().build(proxy);
+        loader
+            .load()
+            .await
+            .into_builder()
+            .http_client(client.clone())
+            .build()
+    } else {
+        debug!("Not using a proxy");
+        loader.load().await
+    };
+    return config;
+}
+
+pub const DEFAULT_MAX_SUBNETS: usize = 500;
+pub const DEFAULT_MAX_LOAD_BALANCERS: usize = 500;
+pub const DEFAULT_MAX_RECORDS: usize = 10000;
+/// How many gathering steps call the AWS APIs at the same time by default -
+/// enough to gather large clusters quickly without being throttled.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Settings that influence how much data is gathered.
+#[derive(Clone, Debug)]
+pub struct GatherOptions {
+    /// Gather all records of the hosted zones instead of only the records
+    /// belonging to the cluster.
+    pub all_records: bool,
+    pub max_subnets: usize,
+    /// Upper bound of load balancers in the account - all of them are
+    /// inspected to find the ones of the cluster.
+    pub max_load_balancers: usize,
+    pub max_records: usize,
+    /// Data sets read from files instead of being gathered.
+    pub overrides: Vec<Override>,
+    /// Gather the open events of the AWS Health API.
+    pub health_events: bool,
+    /// Gather the instance profiles of the instances and their IAM policies.
+    pub instance_profiles: bool,
+    /// Gather the service quotas of the region.
+    pub service_quotas: bool,
+    /// Gather the flow logs of the VPCs and subnets.
+    pub flow_logs: bool,
+    /// Show the progress of the gatherers on stderr.
+    pub progress: bool,
+    /// How many gathering steps call the AWS APIs at the same time.
+    pub max_concurrency: usize,
+    /// The data sets the selected checks use - all data sets are gathered if
+    /// none are given.
+    pub data_sets: Option<Vec<DataSet>>,
+    /// The proxy the requests made besides the AWS SDK are sent through.
+    pub proxy: Option<String>,
+}
+
+impl Default for GatherOptions {
+    fn default() -> Self {
+        GatherOptions {
+            all_records: false,
+            max_subnets: DEFAULT_MAX_SUBNETS,
+            max_load_balancers: DEFAULT_MAX_LOAD_BALANCERS,
+            max_records: DEFAULT_MAX_RECORDS,
+            overrides: vec![],
+            health_events: false,
+            instance_profiles: false,
+            service_quotas: false,
+            flow_logs: false,
+            progress: false,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            data_sets: None,
+            proxy: None,
+        }
+    }
+}
+
+impl GatherOptions {
+    /// Whether the data set, or a data set gathered from it, is used.
+    pub fn gathers(&self, data_set: DataSet) -> bool {
+        self.data_sets.as_ref().is_none_or(|sets| {
+            sets.iter()
+                .any(|s| *s == data_set || s.depends_on(data_set))
+        })
+    }
+
+    fn override_for(&self, data_set: DataSet) -> Option<&Override> {
+        let o = self.overrides.iter().find(|o| o.data_set == data_set)?;
+        info!("Reading {} from {}", data_set, o.path.display());
+        Some(o)
+    }
+}
+
+/// Gathers the data set, or reads it from its override file if one is given.
+async fn gather_or_override<T, F, Fut>(
+    options: &GatherOptions,
+    data_set: DataSet,
+    step: F,
+) -> Result<Vec<T>, Box<dyn std::error::Error>>
+where
+    T: Overridable,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, Box<dyn std::error::Error>>>,
+{
+    match options.override_for(data_set) {
+        Some(o) => o.load(),
+        None => with_expiry_detection(data_set.as_str(), step).await,
+    }
+}
+
+/// Guards against running against huge shared accounts, where gathering would
+/// take forever and use unbounded memory.
+pub fn ensure_within_limit(
+    what: &str,
+    count: usize,
+    limit: usize,
+    flag: &str,
+) -> Result<(), InvariantError> {
+    if count <= limit {
+        return Ok(());
+    }
+    Err(InvariantError {
+        msg: format!(
+            "The account is too large: found more than {} {} (found {}). Please filter by VPC or raise the limit with {}.",
+            limit, what, count, flag
+        ),
+    })
+}
+
+/// The most names or ARNs a single `describe_tags` call of the ELB APIs accepts.
+pub const DESCRIBE_TAGS_BATCH_SIZE: usize = 20;
+/// How many `describe_tags` calls run at the same time - high enough to be
+/// fast in accounts with hundreds of load balancers, low enough to stay clear
+/// of the API rate limits.
+pub const DESCRIBE_TAGS_CONCURRENCY: usize = 4;
+
+/// Splits the items into batches of at most `batch_size` and runs `call` for
+/// every batch, with at most `concurrency` calls running at the same time.
+/// The outputs are returned in the order the calls finish.
+pub async fn in_batches<T, O, E, F, Fut>(
+    items: Vec<T>,
+    batch_size: usize,
+    concurrency: usize,
+    call: F,
+) -> Result<Vec<O>, Box<dyn std::error::Error>>
+where
+    T: Clone,
+    F: Fn(Vec<T>) -> Fut,
+    Fut: Future<Output = Result<O, E>> + Send + 'static,
+    O: Send + 'static,
+    E: std::error::Error + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency));
+    let mut calls = JoinSet::new();
+    for batch in items.chunks(batch_size) {
+        let semaphore = semaphore.clone();
+        let future = call(batch.to_vec());
+        calls.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            future.await
+        });
+    }
+    let mut outputs = vec![];
+    while let Some(output) = calls.join_next().await {
+        match output? {
+            Ok(output) => outputs.push(output),
+            Err(err) => {
+                calls.abort_all();
+                return Err(Box::new(err));
+            }
+        }
+    }
+    Ok(outputs)
+}
+
+/// An AWS API without an SDK crate in use, called with signed POST requests.
+pub struct SignedApi<'a> {
+    /// The signing name of the service, e.g. `iam`.
+    pub service: &'a str,
+    pub region: &'a str,
+    pub endpoint: &'a str,
+}
+
+/// Sends a POST request to the API, signed with SigV4 and the credentials of
+/// the AWS configuration.
+pub async fn send_signed(
+    aws_config: &SdkConfig,
+    client: &reqwest::Client,
+    api: &SignedApi<'_>,
+    headers: &[(&str, &str)],
+    body: Vec<u8>,
+) -> Result<reqwest::Response, Box<dyn std::error::Error>> {
+    let Some(provider) = aws_config.credentials_provider() else {
+        return Err(Box::new(InvariantError {
+            msg: "No AWS credentials configured".to_string(),
+        }));
+    };
+    let identity = provider.provide_credentials().await?.into();
+    let params = v4::SigningParams::builder()
+        .identity(&identity)
+        .region(api.region)
+        .name(api.service)
+        .time(SystemTime::now())
+        .settings(SigningSettings::default())
+        .build()?
+        .into();
+    let signable = SignableRequest::new(
+        "POST",
+        api.endpoint,
+        headers.iter().copied(),
+        SignableBody::Bytes(&body),
+    )?;
+    let (instructions, _) = sign(signable, &params)?.into_parts();
+    let mut request = client.post(api.endpoint);
+    for (name, value) in headers.iter().copied().chain(instructions.headers()) {
+        request = request.header(name, value);
+    }
+    Ok(request.body(body).send().await?)
+}
+
+/// An error that makes running any check pointless - the caller decides
+/// whether to abort the run.
+#[derive(Debug)]
+pub enum GatherError {
+    /// Data every check needs could not be gathered, or there is more of it
+    /// than the limits allow.
+    Failed {
+        what: String,
+        message: String,
+        /// The policy that denied gathering the data, if it was denied.
+        denial: Option<PolicyDenial>,
+    },
+    /// The AWS credentials expired while gathering - refreshing them and
+    /// gathering again is up to the caller.
+    CredentialsExpired {
+        what: String,
+        /// The data gathered before the credentials expired, the data sets
+        /// that could not be gathered are recorded as failed.
+        partial: Option<Box<AWSClusterData>>,
+    },
+}
+
+impl GatherError {
+    fn failed<E: Display + Debug>(what: &str, e: E) -> Self {
+        if credentials::is_expired_credentials_error(&e) {
+            return GatherError::CredentialsExpired {
+                what: what.to_string(),
+                partial: None,
+            };
+        }
+        GatherError::Failed {
+            what: what.to_string(),
+            message: e.to_string(),
+            denial: credentials::parse_denial(what, &format!("{:?}", e)),
+        }
+    }
+}
+
+impl Display for GatherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GatherError::Failed {
+                denial: Some(denial),
+                ..
+            } => write!(
+                f,
+                "{} - request an exemption for it to run the checks.",
+                denial
+            ),
+            GatherError::Failed { what, message, .. } => {
+                write!(f, "Could not retrieve {}: {}", what, message)
+            }
+            GatherError::CredentialsExpired { what, .. } => {
+                write!(f, "The AWS credentials expired while gathering {}", what)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GatherError {}
+
+/// Gathers all required data associated with the cluster from AWS.
+/// If the credentials expire while gathering, the data gathered so far is
+/// returned in a [`GatherError::CredentialsExpired`].
+pub async fn gather(
+    cluster_info: &MinimalClusterInfo,
+    aws_config: &SdkConfig,
+    options: &GatherOptions,
+) -> Result<AWSClusterData, GatherError> {
+    FAILED
+        .scope(
+            Arc::default(),
+            credentials::DENIALS.scope(
+                Arc::default(),
+                credentials::EXPIRED.scope(
+                    Arc::default(),
+                    CONCURRENCY.scope(
+                        Arc::new(Semaphore::new(options.max_concurrency.max(1))),
+                        async {
+                            let data = gather_run(cluster_info, aws_config, options).await?;
+                            match credentials::take_expired() {
+                                Some(what) => Err(GatherError::CredentialsExpired {
+                                    what,
+                                    partial: Some(Box::new(data)),
+                                }),
+                                None => Ok(data),
+                            }
+                        },
+                    ),
+                ),
+            ),
+        )
+        .await
+}
+
+async fn gather_run(
+    cluster_info: &MinimalClusterInfo,
+    aws_config: &SdkConfig,
+    options: &GatherOptions,
+) -> Result<AWSClusterData, GatherError> {
+    let ec2_client = EC2Client::new(aws_config);
+    let elbv2_client = ELBv2Client::new(aws_config);
+    let elbv1_client = ELBv1Client::new(aws_config);
+    let route53_client = Route53Client::new(aws_config);
+    let resolver_client = ResolverClient::new(aws_config);
+    let logs_client = LogsClient::new(aws_config);
+    let s3_client = S3Client::new(aws_config);
+    if options.progress {
+        progress::enable();
+    }
+
+    // The data sets are gathered as a dependency graph: every task gathers a
+    // data set and then the data sets depending on it, with the independent
+    // steps of a task running at the same time. How many API calls run at the
+    // same time is limited by `max_concurrency`.
+    info!("Fetching LoadBalancer data");
+    let h1 = spawn_in_run({
+        let cluster_info = cluster_info.clone();
+        let ec2_client = ec2_client.clone();
+        let options = options.clone();
+        async move {
+            if !options.gathers(DataSet::LoadBalancers) {
+                return Default::default();
+            }
+            info!("Fetching load balancers");
+            let lbg = crate::gatherer::aws::loadbalancerv2::LoadBalancerGatherer {
+                client: &elbv2_client,
+                cluster_info: &cluster_info,
+                max_load_balancers: options.max_load_balancers,
+            };
+            let all_lbs = match options.override_for(DataSet::LoadBalancers) {
+                Some(o) => o.load().unwrap_or_else(|e| {
+                    gathering_failed(DataSet::LoadBalancers, "load balancers", e)
+                }),
+                None => {
+                    let (lbs, classic_lbs) = tokio::join!(
+                        async {
+                            with_expiry_detection("load balancers", || lbg.gather())
+                                .await
+                                .unwrap_or_else(|e| {
+                                    gathering_failed(DataSet::LoadBalancers, "load balancers", e)
+                                })
+                        },
+                        async {
+                            with_expiry_detection("classic load balancers", || {
+                                crate::gatherer::aws::get_classic_load_balancers(
+                                    &elbv1_client,
+                                    &cluster_info,
+                                    options.max_load_balancers,
+                                )
+                            })
+                            .await
+                            .unwrap_or_else(|e| {
+                                gathering_failed(
+                                    DataSet::LoadBalancers,
+                                    "classic load balancers",
+                                    e,
+                                )
+                            })
+                        }
+                    );
+                    let mut all_lbs = vec![];
+                    all_lbs.extend(lbs);
+                    all_lbs.extend(classic_lbs);
+                    all_lbs
+                }
+            };
+            if has_failed(DataSet::LoadBalancers) {
+                return Default::default();
+            }
+            let enig = crate::gatherer::aws::ec2::NetworkInterfaceGatherer {
+                client: &ec2_client,
+                loadbalancers: &all_lbs,
+            };
+            let tgg = crate::gatherer::aws::loadbalancerv2::TargetGroupGatherer {
+                client: &elbv2_client,
+                loadbalancers: &all_lbs,
+            };
+            let lbag = crate::gatherer::aws::loadbalancerv2::LoadBalancerAttributeGatherer {
+                client: &elbv2_client,
+                loadbalancers: &all_lbs,
+            };
+            let (eni_lbs, target_groups, (load_balancer_attributes, access_log_buckets)) = tokio::join!(
+                async {
+                    if !options.gathers(DataSet::LoadBalancerEnis) {
+                        return vec![];
+                    }
+                    with_expiry_detection("ENIs", || enig.gather())
+                        .await
+                        .unwrap_or_else(|e| gathering_failed(DataSet::LoadBalancerEnis, "ENIs", e))
+                },
+                async {
+                    if !options.gathers(DataSet::TargetGroups) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::TargetGroups, || tgg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::TargetGroups, "target groups", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::LoadBalancerAttributes) {
+                        return (vec![], vec![]);
+                    }
+                    let load_balancer_attributes = match options
+                        .override_for(DataSet::LoadBalancerAttributes)
+                    {
+                        Some(o) => o.load().unwrap_or_else(|e| {
+                            gathering_failed(
+                                DataSet::LoadBalancerAttributes,
+                                "load balancer attributes",
+                                e,
+                            )
+                        }),
+                        None => {
+                            let (mut load_balancer_attributes, classic_attributes) = tokio::join!(
+                                async {
+                                    with_expiry_detection("load balancer attributes", || {
+                                        lbag.gather()
+                                    })
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        gathering_failed(
+                                            DataSet::LoadBalancerAttributes,
+                                            "load balancer attributes",
+                                            e,
+                                        )
+                                    })
+                                },
+                                async {
+                                    with_expiry_detection("classic load balancer attributes", || {
+                                        crate::gatherer::aws::loadbalancer::get_classic_load_balancer_attributes(
+                                            &elbv1_client,
+                                            &all_lbs,
+                                        )
+                                    })
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        gathering_failed(
+                                            DataSet::LoadBalancerAttributes,
+                                            "classic load balancer attributes",
+                                            e,
+                                        )
+                                    })
+                                }
+                            );
+                            load_balancer_attributes.extend(classic_attributes);
+                            load_balancer_attributes
+                        }
+                    };
+                    if !options.gathers(DataSet::AccessLogBuckets) {
+                        return (load_balancer_attributes, vec![]);
+                    }
+                    info!("Fetching access log buckets");
+                    let albg = crate::gatherer::aws::s3::AccessLogBucketGatherer {
+                        client: &s3_client,
+                        load_balancer_attributes: &load_balancer_attributes,
+                    };
+                    let access_log_buckets =
+                        gather_or_override(&options, DataSet::AccessLogBuckets, || albg.gather())
+                            .await
+                            .unwrap_or_else(|e| {
+                                gathering_failed(DataSet::AccessLogBuckets, "access log buckets", e)
+                            });
+                    (load_balancer_attributes, access_log_buckets)
+                }
+            );
+            (
+                all_lbs,
+                eni_lbs,
+                target_groups,
+                load_balancer_attributes,
+                access_log_buckets,
+            )
+        }
+    });
+
+    info!("Fetching Subnet data");
+    let h2 = spawn_in_run({
+        let cluster_info = cluster_info.clone();
+        let ec2_client = ec2_client.clone();
+        let options = options.clone();
+        async move {
+            if !options.gathers(DataSet::Subnets) {
+                return Ok(Default::default());
+            }
+            let sg = crate::gatherer::aws::ec2::ConfiguredSubnetGatherer {
+                client: &ec2_client,
+                cluster_info: &cluster_info,
+            };
+            let all_subnets = gather_or_override(&options, DataSet::Subnets, || sg.gather())
+                .await
+                .unwrap_or_else(|e| gathering_failed(DataSet::Subnets, "subnets", e));
+            if has_failed(DataSet::Subnets) {
+                return Ok(Default::default());
+            }
+            if let Err(e) = ensure_within_limit(
+                "subnets",
+                all_subnets.len(),
+                options.max_subnets,
+                "--max-subnets",
+            ) {
+                return Err(GatherError::failed("subnets", e));
+            }
+            let subnet_ids: Vec<String> = all_subnets
+                .iter()
+                .map(|s| s.subnet_id.as_ref().unwrap().clone())
+                .collect();
+            let vpc_ids: Vec<String> = all_subnets
+                .iter()
+                .filter_map(|s| s.vpc_id.clone())
+                .unique()
+                .collect();
+            let rtg = crate::gatherer::aws::ec2::RouteTableGatherer {
+                client: &ec2_client,
+                subnet_ids: &subnet_ids,
+            };
+            let nag = crate::gatherer::aws::ec2::NetworkAclGatherer {
+                client: &ec2_client,
+                subnet_ids: &subnet_ids,
+            };
+            let snig = crate::gatherer::aws::ec2::SubnetNetworkInterfaceGatherer {
+                client: &ec2_client,
+                subnet_ids: &subnet_ids,
+            };
+            let ngg = crate::gatherer::aws::ec2::NatGatewayGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let igg = crate::gatherer::aws::ec2::InternetGatewayGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let eoigg = crate::gatherer::aws::ec2::EgressOnlyInternetGatewayGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let veg = crate::gatherer::aws::ec2::VpcEndpointGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let vag = crate::gatherer::aws::ec2::VpcAttributeGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let vg = crate::gatherer::aws::ec2::VpcGatherer {
+                client: &ec2_client,
+                vpc_ids: &vpc_ids,
+            };
+            let flow_log_resource_ids: Vec<String> =
+                vpc_ids.iter().chain(subnet_ids.iter()).cloned().collect();
+            let flg = crate::gatherer::aws::ec2::FlowLogGatherer {
+                client: &ec2_client,
+                resource_ids: &flow_log_resource_ids,
+            };
+            let rqlcg = crate::gatherer::aws::resolver::ResolverQueryLogConfigGatherer {
+                client: &resolver_client,
+                vpc_ids: &vpc_ids,
+            };
+            info!("Fetching routetables, NAT gateways, VPC endpoints and resolver query logs");
+            let (
+                routetables,
+                network_acls,
+                subnet_network_interfaces,
+                nat_gateways,
+                (internet_gateways, egress_only_internet_gateways),
+                vpc_endpoints,
+                vpc_attributes,
+                (vpcs, dhcp_options),
+                (resolver_query_log_configs, resolver_query_logs),
+                flow_logs,
+            ) = tokio::join!(
+                async {
+                    if !options.gathers(DataSet::RouteTables) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::RouteTables, || rtg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::RouteTables, "routetables", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::NetworkAcls) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::NetworkAcls, || nag.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::NetworkAcls, "network ACLs", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::SubnetNetworkInterfaces) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::SubnetNetworkInterfaces, || snig.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::SubnetNetworkInterfaces, "subnet ENIs", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::NatGateways) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::NatGateways, || ngg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::NatGateways, "NAT gateways", e)
+                        })
+                },
+                async {
+                    tokio::join!(
+                        async {
+                            if !options.gathers(DataSet::InternetGateways) {
+                                return vec![];
+                            }
+                            gather_or_override(&options, DataSet::InternetGateways, || igg.gather())
+                                .await
+                                .unwrap_or_else(|e| {
+                                    gathering_failed(
+                                        DataSet::InternetGateways,
+                                        "internet gateways",
+                                        e,
+                                    )
+                                })
+                        },
+                        async {
+                            if !options.gathers(DataSet::EgressOnlyInternetGateways) {
+                                return vec![];
+                            }
+                            gather_or_override(
+                                &options,
+                                DataSet::EgressOnlyInternetGateways,
+                                || eoigg.gather(),
+                            )
+                            .await
+                            .unwrap_or_else(|e| {
+                                gathering_failed(
+                                    DataSet::EgressOnlyInternetGateways,
+                                    "egress-only internet gateways",
+                                    e,
+                                )
+                            })
+                        }
+                    )
+                },
+                async {
+                    if !options.gathers(DataSet::VpcEndpoints) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::VpcEndpoints, || veg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::VpcEndpoints, "VPC endpoints", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::VpcAttributes) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::VpcAttributes, || vag.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::VpcAttributes, "VPC attributes", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::Vpcs) {
+                        return (vec![], vec![]);
+                    }
+                    let vpcs = gather_or_override(&options, DataSet::Vpcs, || vg.gather())
+                        .await
+                        .unwrap_or_else(|e| gathering_failed(DataSet::Vpcs, "VPCs", e));
+                    // VPCs without a DHCP options set report it as `default`.
+                    let dhcp_options_ids: Vec<String> = vpcs
+                        .iter()
+                        .filter_map(|v| v.dhcp_options_id.clone())
+                        .filter(|id| id != "default")
+                        .unique()
+                        .collect();
+                    if !options.gathers(DataSet::DhcpOptions) {
+                        return (vpcs, vec![]);
+                    }
+                    let dog = crate::gatherer::aws::ec2::DhcpOptionsGatherer {
+                        client: &ec2_client,
+                        dhcp_options_ids: &dhcp_options_ids,
+                    };
+                    let dhcp_options =
+                        gather_or_override(&options, DataSet::DhcpOptions, || dog.gather())
+                            .await
+                            .unwrap_or_else(|e| {
+                                gathering_failed(DataSet::DhcpOptions, "DHCP options", e)
+                            });
+                    (vpcs, dhcp_options)
+                },
+                async {
+                    if !options.gathers(DataSet::ResolverQueryLogs) {
+                        return (vec![], vec![]);
+                    }
+                    let resolver_query_log_configs =
+                        with_expiry_detection("resolver query log configs", || rqlcg.gather())
+                            .await
+                            .unwrap_or_else(|e| {
+                                gathering_failed(
+                                    DataSet::ResolverQueryLogs,
+                                    "resolver query log configs",
+                                    e,
+                                )
+                            });
+                    let rqlg = crate::gatherer::aws::resolver::ResolverQueryLogGatherer {
+                        client: &logs_client,
+                        configs: &resolver_query_log_configs,
+                    };
+                    let resolver_query_logs =
+                        match options.override_for(DataSet::ResolverQueryLogs) {
+                            Some(o) => o.load(),
+                            None => {
+                                with_expiry_detection("resolver query logs", || rqlg.gather()).await
+                            }
+                        }
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::ResolverQueryLogs, "resolver query logs", e)
+                        });
+                    (resolver_query_log_configs, resolver_query_logs)
+                },
+                async {
+                    if !options.flow_logs {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::FlowLogs, || flg.gather())
+                        .await
+                        .unwrap_or_else(|e| gathering_failed(DataSet::FlowLogs, "flow logs", e))
+                }
+            );
+            Ok((
+                all_subnets,
+                routetables,
+                nat_gateways,
+                (internet_gateways, egress_only_internet_gateways),
+                vpc_endpoints,
+                network_acls,
+                subnet_network_interfaces,
+                vpcs,
+                dhcp_options,
+                vpc_attributes,
+                (resolver_query_log_configs, resolver_query_logs),
+                flow_logs,
+            ))
+        }
+    });
+
+    info!("Fetching instances and security groups");
+    let h3 = spawn_in_run({
+        let cluster_info = cluster_info.clone();
+        let ec2_client = ec2_client.clone();
+        let aws_config = aws_config.clone();
+        let options = options.clone();
+        async move {
+            if !options.gathers(DataSet::Instances) {
+                return Default::default();
+            }
+            let ig = crate::gatherer::aws::ec2::InstanceGatherer {
+                client: &ec2_client,
+                cluster_info: &cluster_info,
+            };
+            let instances = with_expiry_detection("instances", || ig.gather())
+                .await
+                .unwrap_or_else(|e| gathering_failed(DataSet::Instances, "instances", e));
+            let instance_profiles = if options.instance_profiles {
+                info!("Fetching instance profiles");
+                let ipg = crate::gatherer::aws::iam::InstanceProfileGatherer {
+                    aws_config: &aws_config,
+                    client: &http_client(options.proxy.as_deref()),
+                    instances: &instances,
+                };
+                gather_or_override(&options, DataSet::InstanceProfiles, || ipg.gather())
+                    .await
+                    .unwrap_or_else(|e| {
+                        gathering_failed(DataSet::InstanceProfiles, "instance profiles", e)
+                    })
+            } else {
+                vec![]
+            };
+            (instances, instance_profiles)
+        }
+    });
+
+    info!("Fetching hostedzones");
+    let h4 = spawn_in_run({
+        let cluster_info = cluster_info.clone();
+        let route53_client = route53_client.clone();
+        let options = options.clone();
+        async move {
+            if !options.gathers(DataSet::HostedZones) {
+                return vec![];
+            }
+            if let Some(o) = options.override_for(DataSet::HostedZones) {
+                return o
+                    .load()
+                    .unwrap_or_else(|e| gathering_failed(DataSet::HostedZones, "hosted zones", e));
+            }
+            let hzg = crate::gatherer::aws::dns::HostedZoneGatherer {
+                client: &route53_client,
+                cluster_info: &cluster_info,
+            };
+            let hosted_zones = with_expiry_detection("hosted zones", || hzg.gather())
+                .await
+                .unwrap_or_else(|e| gathering_failed(DataSet::HostedZones, "hosted zones", e));
+            let rrg = crate::gatherer::aws::dns::ResourceRecordGatherer {
+                client: &route53_client,
+                hosted_zones: &hosted_zones,
+                all_records: options.all_records,
+                ingress_domains: &cluster_info.ingress_domains,
+                max_records: options.max_records,
+            };
+            let mut zones = with_expiry_detection("resource records", || rrg.gather())
+                .await
+                .unwrap_or_else(|e| gathering_failed(DataSet::HostedZones, "resource records", e));
+            crate::gatherer::aws::dns::resolve_delegations(
+                &http_client(options.proxy.as_deref()),
+                &mut zones,
+            )
+            .await;
+            zones
+        }
+    });
+
+    info!("Fetching availability zones and instance type offerings");
+    let h5 = spawn_in_run({
+        let cluster_info = cluster_info.clone();
+        let ec2_client = ec2_client.clone();
+        let options = options.clone();
+        async move {
+            let azg = crate::gatherer::aws::ec2::AvailabilityZoneGatherer {
+                client: &ec2_client,
+            };
+            let itog = crate::gatherer::aws::ec2::InstanceTypeOfferingGatherer {
+                client: &ec2_client,
+                cluster_info: &cluster_info,
+            };
+            tokio::join!(
+                async {
+                    if !options.gathers(DataSet::AvailabilityZones) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::AvailabilityZones, || azg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::AvailabilityZones, "availability zones", e)
+                        })
+                },
+                async {
+                    if !options.gathers(DataSet::InstanceTypeOfferings) {
+                        return vec![];
+                    }
+                    with_expiry_detection("instance type offerings", || itog.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(
+                                DataSet::InstanceTypeOfferings,
+                                "instance type offerings",
+                                e,
+                            )
+                        })
+                }
+            )
+        }
+    });
+
+    info!("Fetching elastic IPs and the data of the region");
+    let h6 = spawn_in_run({
+        let ec2_client = ec2_client.clone();
+        let aws_config = aws_config.clone();
+        let options = options.clone();
+        async move {
+            let region = aws_config.region().map_or("us-east-1", |r| r.as_ref());
+            let client = http_client(options.proxy.as_deref());
+            let eipg = crate::gatherer::aws::ec2::ElasticIpGatherer {
+                client: &ec2_client,
+            };
+            let heg = crate::gatherer::aws::health::HealthEventGatherer {
+                aws_config: &aws_config,
+                client: &client,
+                region,
+            };
+            let sqg = crate::gatherer::aws::quotas::ServiceQuotaGatherer {
+                aws_config: &aws_config,
+                client: &client,
+                region,
+            };
+            tokio::join!(
+                async {
+                    if !options.gathers(DataSet::ElasticIps) {
+                        return vec![];
+                    }
+                    gather_or_override(&options, DataSet::ElasticIps, || eipg.gather())
+                        .await
+                        .unwrap_or_else(|e| gathering_failed(DataSet::ElasticIps, "elastic IPs", e))
+                },
+                async {
+                    if !options.health_events {
+                        return vec![];
+                    }
+                    info!("Fetching AWS Health events");
+                    gather_or_override(&options, DataSet::HealthEvents, || heg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::HealthEvents, "AWS Health events", e)
+                        })
+                },
+                async {
+                    if !options.service_quotas {
+                        return vec![];
+                    }
+                    info!("Fetching service quotas");
+                    gather_or_override(&options, DataSet::ServiceQuotas, || sqg.gather())
+                        .await
+                        .unwrap_or_else(|e| {
+                            gathering_failed(DataSet::ServiceQuotas, "service quotas", e)
+                        })
+                },
+                credentials::caller_account(&aws_config)
+            )
+        }
+    });
+
+    let (
+        load_balancers,
+        load_balancer_enis,
+        target_groups,
+        load_balancer_attributes,
+        access_log_buckets,
+    ) = h1.await.unwrap();
+    let subnet_data = h2.await.unwrap();
+    let (instances, instance_profiles) = h3.await.unwrap();
+    let hosted_zones = h4.await.unwrap();
+    let (availability_zones, instance_type_offerings) = h5.await.unwrap();
+    let (elastic_ips, health_events, service_quotas, account_id) = h6.await.unwrap();
+    progress::disable();
+    let (
+        subnets,
+        routetables,
+        nat_gateways,
+        (internet_gateways, egress_only_internet_gateways),
+        vpc_endpoints,
+        network_acls,
+        subnet_network_interfaces,
+        vpcs,
+        dhcp_options,
+        vpc_attributes,
+        (resolver_query_log_configs, resolver_query_logs),
+        flow_logs,
+    ) = subnet_data?;
+
+    Ok(AWSClusterData {
+        subnets,
+        routetables,
+        load_balancers,
+        load_balancer_enis,
+        instances,
+        hosted_zones,
+        availability_zones,
+        instance_type_offerings,
+        resolver_query_log_configs,
+        resolver_query_logs,
+        nat_gateways,
+        internet_gateways,
+        egress_only_internet_gateways,
+        elastic_ips,
+        target_groups,
+        load_balancer_attributes,
+        access_log_buckets,
+        vpc_endpoints,
+        network_acls,
+        subnet_network_interfaces,
+        vpcs,
+        dhcp_options,
+        vpc_attributes,
+        denials: credentials::take_denials(),
+        health_events,
+        instance_profiles,
+        service_quotas,
+        flow_logs,
+        account_id,
+        failed: take_failures(),
+    })
+}
+
+/// Struct that holds the data of a VPC that no cluster has been installed into
+/// yet.
+#[derive(Clone, Debug)]
+pub struct AWSPreflightData {
+    pub subnets: Vec<aws_sdk_ec2::types::Subnet>,
+    pub routetables: Vec<aws_sdk_ec2::types::RouteTable>,
+    pub nat_gateways: Vec<aws_sdk_ec2::types::NatGateway>,
+    pub vpc_endpoints: Vec<aws_sdk_ec2::types::VpcEndpoint>,
+    pub elastic_ips: Vec<aws_sdk_ec2::types::Address>,
+    pub account_attributes: Vec<aws_sdk_ec2::types::AccountAttribute>,
+    pub hosted_zones: Vec<HostedZone>,
+}
+
+/// Gathers the data required to verify a VPC before a cluster is installed.
+pub async fn gather_preflight(
+    vpc_id: Option<&String>,
+    subnet_ids: &Vec<String>,
+    base_domain: Option<&String>,
+    aws_config: &SdkConfig,
+) -> Result<AWSPreflightData, GatherError> {
+    let ec2_client = EC2Client::new(aws_config);
+    let route53_client = Route53Client::new(aws_config);
+
+    info!("Fetching subnets");
+    let sg = crate::gatherer::aws::ec2::VpcSubnetGatherer {
+        client: &ec2_client,
+        vpc_id,
+        subnet_ids,
+    };
+    let subnets = with_expiry_detection("subnets", || sg.gather())
+        .await
+        .map_err(|e| GatherError::failed("subnets", e))?;
+    let vpc_ids: Vec<String> = match vpc_id {
+        Some(vpc_id) => vec![vpc_id.clone()],
+        None => subnets
+            .iter()
+            .filter_map(|s| s.vpc_id.clone())
+            .unique()
+            .collect(),
+    };
+
+    info!("Fetching routetables, NAT gateways and VPC endpoints");
+    let rtg = crate::gatherer::aws::ec2::VpcRouteTableGatherer {
+        client: &ec2_client,
+        vpc_ids: &vpc_ids,
+    };
+    let routetables = with_expiry_detection("routetables", || rtg.gather())
+        .await
+        .map_err(|e| GatherError::failed("routetables", e))?;
+    let ngg = crate::gatherer::aws::ec2::NatGatewayGatherer {
+        client: &ec2_client,
+        vpc_ids: &vpc_ids,
+    };
+    let nat_gateways = with_expiry_detection("NAT gateways", || ngg.gather())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Could not retrieve NAT gateways: {}", e);
+            vec![]
+        });
+    let veg = crate::gatherer::aws::ec2::VpcEndpointGatherer {
+        client: &ec2_client,
+        vpc_ids: &vpc_ids,
+    };
+    let vpc_endpoints = with_expiry_detection("VPC endpoints", || veg.gather())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Could not retrieve VPC endpoints: {}", e);
+            vec![]
+        });
+
+    info!("Fetching elastic IPs and account attributes");
+    let eipg = crate::gatherer::aws::ec2::ElasticIpGatherer {
+        client: &ec2_client,
+    };
+    let elastic_ips = with_expiry_detection("elastic IPs", || eipg.gather())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Could not retrieve elastic IPs: {}", e);
+            vec![]
+        });
+    let aag = crate::gatherer::aws::ec2::AccountAttributeGatherer {
+        client: &ec2_client,
+    };
+    let account_attributes = with_expiry_detection("account attributes", || aag.gather())
+        .await
+        .unwrap_or_else(|e| {
+            error!("Could not retrieve account attributes: {}", e);
+            vec![]
+        });
+
+    let hosted_zones = match base_domain {
+        Some(base_domain) => {
+            info!("Fetching hostedzones");
+            let cluster_info = MinimalClusterInfo {
+                cluster_id: "preflight".to_string(),
+                cluster_infra_name: "".to_string(),
+                cluster_type: crate::types::ClusterType::Rosa,
+                cloud_provider: "aws".to_string(),
+                subnets: subnet_ids.clone(),
+                base_domain: Some(base_domain.clone()),
+                machine_pools: vec![],
+                ingress_domains: vec![],
+                private: false,
+                ingress_private: false,
+                zero_egress: false,
+                network_type: None,
+                region: aws_config.region().map(|r| r.to_string()),
+                machine_cidr: None,
+                service_cidr: None,
+                pod_cidr: None,
+                multi_az: false,
+                proxy: None,
+                azure: None,
+            };
+            let hzg = crate::gatherer::aws::dns::HostedZoneGatherer {
+                client: &route53_client,
+                cluster_info: &cluster_info,
+            };
+            with_expiry_detection("hosted zones", || hzg.gather())
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Could not retrieve hosted zones: {}", e);
+                    vec![]
+                })
+        }
+        None => vec![],
+    };
+
+    Ok(AWSPreflightData {
+        subnets,
+        routetables,
+        nat_gateways,
+        vpc_endpoints,
+        elastic_ips,
+        account_attributes,
+        hosted_zones,
+    })
+}
+
+/// Struct that holds the resources still tagged for a deleted cluster.
+#[derive(Clone, Debug)]
+pub struct AWSLeftoverData {
+    pub load_balancers: Vec<AWSLoadBalancer>,
+    pub network_interfaces: Vec<aws_sdk_ec2::types::NetworkInterface>,
+    pub security_groups: Vec<aws_sdk_ec2::types::SecurityGroup>,
+    pub elastic_ips: Vec<aws_sdk_ec2::types::Address>,
+    pub hosted_zones: Vec<HostedZoneWithRecords>,
+}
+
+/// Gathers all resources that are still tagged for the infra ID of a deleted
+/// cluster.
+pub async fn gather_leftovers(
+    infra_id: &String,
+    aws_config: &SdkConfig,
+    options: &GatherOptions,
+) -> Result<AWSLeftoverData, GatherError> {
+    let ec2_client = EC2Client::new(aws_config);
+    let elbv2_client = ELBv2Client::new(aws_config);
+    let elbv1_client = ELBv1Client::new(aws_config);
+    let route53_client = Route53Client::new(aws_config);
+    let tag_key = format!("{}{}", shared_types::CLUSTER_TAG_PREFIX, infra_id);
+    // The load balancer gatherers match on the cluster tags, so a cluster
+    // that only consists of its infra ID is enough to find them.
+    let cluster_info = MinimalClusterInfo {
+        cluster_id: infra_id.clone(),
+        cluster_infra_name: infra_id.clone(),
+        cluster_type: crate::types::ClusterType::Osd,
+        cloud_provider: "aws".to_string(),
+        subnets: vec![],
+        base_domain: None,
+        machine_pools: vec![],
+        ingress_domains: vec![],
+        private: false,
+        ingress_private: false,
+        zero_egress: false,
+        network_type: None,
+        region: aws_config.region().map(|r| r.to_string()),
+        machine_cidr: None,
+        service_cidr: None,
+        pod_cidr: None,
+        multi_az: false,
+        proxy: None,
+        azure: None,
+    };
+
+    info!("Fetching load balancers tagged for {}", infra_id);
+    let lbg = crate::gatherer::aws::loadbalancerv2::LoadBalancerGatherer {
+        client: &elbv2_client,
+        cluster_info: &cluster_info,
+        max_load_balancers: options.max_load_balancers,
+    };
+    let mut load_balancers = with_expiry_detection("load balancers", || lbg.gather())
+        .await
+        .map_err(|e| GatherError::failed("load balancers", e))?;
+    let classic_lbs = with_expiry_detection("classic load balancers", || {
+        crate::gatherer::aws::get_classic_load_balancers(
+            &elbv1_client,
+            &cluster_info,
+            options.max_load_balancers,
+        )
+    })
+    .await
+    .map_err(|e| GatherError::failed("classic load balancers", e))?;
+    load_balancers.extend(classic_lbs);
+
+    info!(
+        "Fetching ENIs, security groups and elastic IPs tagged for {}",
+        infra_id
+    );
+    let enig = crate::gatherer::aws::ec2::TaggedNetworkInterfaceGatherer {
+        client: &ec2_client,
+        tag_key: &tag_key,
+    };
+    let network_interfaces = with_expiry_detection("ENIs", || enig.gather())
+        .await
+        .map_err(|e| GatherError::failed("ENIs", e))?;
+    let sgg = crate::gatherer::aws::ec2::TaggedSecurityGroupGatherer {
+        client: &ec2_client,
+        tag_key: &tag_key,
+    };
+    let security_groups = with_expiry_detection("security groups", || sgg.gather())
+        .await
+        .map_err(|e| GatherError::failed("security groups", e))?;
+    let eipg = crate::gatherer::aws::ec2::TaggedElasticIpGatherer {
+        client: &ec2_client,
+        tag_key: &tag_key,
+    };
+    let elastic_ips = with_expiry_detection("elastic IPs", || eipg.gather())
+        .await
+        .map_err(|e| GatherError::failed("elastic IPs", e))?;
+
+    info!("Fetching hosted zones tagged for {}", infra_id);
+    let hzg = crate::gatherer::aws::dns::TaggedHostedZoneGatherer {
+        client: &route53_client,
+        tag_key: &tag_key,
+    };
+    let zones = with_expiry_detection("hosted zones", || hzg.gather())
+        .await
+        .map_err(|e| GatherError::failed("hosted zones", e))?;
+    // Every record has to be deleted before the zone can be deleted.
+    let rrg = crate::gatherer::aws::dns::ResourceRecordGatherer {
+        client: &route53_client,
+        hosted_zones: &zones,
+        all_records: true,
+        ingress_domains: &cluster_info.ingress_domains,
+        max_records: options.max_records,
+    };
+    let hosted_zones = with_expiry_detection("resource records", || rrg.gather())
+        .await
+        .map_err(|e| GatherError::failed("resource records", e))?;
+
+    Ok(AWSLeftoverData {
+        load_balancers,
+        network_interfaces,
+        security_groups,
+        elastic_ips,
+        hosted_zones,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(59), "0m");
+        assert_eq!(format_age(2 * 3600 + 5 * 60 + 7), "2h 5m");
+        assert_eq!(format_age(3 * 86400 + 4 * 3600 + 59), "3d 4h");
+    }
+
+    #[test]
+    fn test_gather_error() {
+        let too_large = ensure_within_limit("subnets", 3, 2, "--max-subnets").unwrap_err();
+        assert_eq!(
+            GatherError::failed("subnets", too_large).to_string(),
+            "Could not retrieve subnets: The account is too large: found more than 2 subnets (found 3). Please filter by VPC or raise the limit with --max-subnets."
+        );
+        let denied = InvariantError {
+            msg: "User: arn:aws:sts::123456789012:assumed-role/ops/me is not authorized to perform: ec2:DescribeSubnets with an explicit deny in a service control policy".to_string(),
+        };
+        assert!(matches!(
+            GatherError::failed("subnets", denied),
+            GatherError::Failed {
+                denial: Some(_),
+                ..
+            }
+        ));
+        let expired = InvariantError {
+            msg: "ExpiredToken: The security token included in the request is expired".to_string(),
+        };
+        assert_eq!(
+            GatherError::failed("subnets", expired).to_string(),
+            "The AWS credentials expired while gathering subnets"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_credentials_are_recorded() {
+        let expired = credentials::EXPIRED
+            .scope(Arc::default(), async {
+                let result = with_expiry_detection("subnets", || async {
+                    Err::<Vec<u32>, _>(InvariantError {
+                        msg: "ExpiredToken".to_string(),
+                    })
+                })
+                .await;
+                assert!(result.is_err());
+                credentials::take_expired()
+            })
+            .await;
+        assert_eq!(expired, Some("subnets".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_in_batches() {
+        let items: Vec<u32> = (0..45).collect();
+        let mut batches = in_batches(items, 20, 2, |batch| async move {
+            Ok::<_, InvariantError>(batch)
+        })
+        .await
+        .unwrap();
+        batches.sort();
+        assert_eq!(
+            batches.iter().map(|b| b.len()).collect::<Vec<_>>(),
+            vec![20, 20, 5]
+        );
+        let failed = in_batches(vec![1, 2, 3], 1, 2, |batch| async move {
+            match batch[0] {
+                2 => Err(InvariantError {
+                    msg: "denied".to_string(),
+                }),
+                _ => Ok(batch),
+            }
+        })
+        .await;
+        assert!(failed.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_permit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let step = |what: &'static str| {
+            let running = running.clone();
+            let most = most.clone();
+            with_expiry_detection(what, move || {
+                let running = running.clone();
+                let most = most.clone();
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, InvariantError>(vec![what])
+                }
+            })
+        };
+        let (a, b, c) = CONCURRENCY
+            .scope(Arc::new(Semaphore::new(2)), async {
+                tokio::join!(step("a"), step("b"), step("c"))
+            })
+            .await;
+        assert_eq!(
+            (a.unwrap(), b.unwrap(), c.unwrap()),
+            (vec!["a"], vec!["b"], vec!["c"])
+        );
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,448 @@
+//! Helpers to detect how credentials are provided and to verify they are
+//! usable before any data is gathered.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use aws_config::SdkConfig;
+use aws_credential_types::provider::ProvideCredentials;
+use aws_sdk_ec2::error::DisplayErrorContext;
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::gatherer::aws::{concurrency_permit, progress, retry};
+use crate::types::InvariantError;
+
+/// A minimal view on the sections of the AWS shared config file.
+/// Keys are the section headers as written in the file, e.g. `profile dev`,
+/// `default` or `sso-session my-sso`.
+type ConfigSections = HashMap<String, HashMap<String, String>>;
+
+/// Upper bound of how long gathering data usually takes. Credentials expiring
+/// earlier than this are rejected before any data is gathered.
+pub const ESTIMATED_RUN_DURATION: Duration = Duration::from_secs(300);
+
+/// Error codes/messages AWS returns when the credentials used for a request
+/// have expired.
+const EXPIRED_CREDENTIAL_MARKERS: [&str; 4] = [
+    "ExpiredToken",
+    "RequestExpired",
+    "security token included in the request is expired",
+    "SSO token has expired",
+];
+
+/// The policies AWS names in the message of an `AccessDenied` error, e.g.
+/// "... is not authorized to perform: ec2:DescribeSubnets with an explicit
+/// deny in a service control policy".
+const DENYING_POLICY_TYPES: [&str; 6] = [
+    "service control policy",
+    "resource control policy",
+    "permissions boundary",
+    "session policy",
+    "identity-based policy",
+    "resource-based policy",
+];
+
+tokio::task_local! {
+    /// Denials seen while gathering - gathering continues without the denied
+    /// data, so they are reported as findings afterwards. Every gathering run
+    /// has its own list, see [`super::gather`].
+    pub(super) static DENIALS: Arc<Mutex<Vec<PolicyDenial>>>;
+
+    /// What was being gathered when the credentials expired - the gathering
+    /// run is reported as expired together with the data gathered so far.
+    pub(super) static EXPIRED: Arc<Mutex<Option<String>>>;
+}
+
+/// An AWS API call denied by a policy, e.g. a service control policy of the
+/// organization.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyDenial {
+    /// What was being gathered when the call was denied.
+    pub what: String,
+    /// The denied action, e.g. `ec2:DescribeSubnets`.
+    pub action: Option<String>,
+    pub policy_type: String,
+    /// The ARN of the policy - AWS only includes it for some policy types.
+    pub policy_arn: Option<String>,
+}
+
+impl Display for PolicyDenial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Retrieving {} was denied by a {}",
+            self.what, self.policy_type
+        )?;
+        if let Some(ref arn) = self.policy_arn {
+            write!(f, " ({})", arn)?;
+        }
+        if let Some(ref action) = self.action {
+            write!(f, " for action {}", action)?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the denied action and the denying policy from the description of
+/// an error.
+pub fn parse_denial(what: &str, description: &str) -> Option<PolicyDenial> {
+    let policy_type = DENYING_POLICY_TYPES
+        .iter()
+        .find(|t| description.contains(*t))?;
+    let trim = |w: &str| {
+        w.trim_matches(|c: char| {
+            c == '"' || c == '\'' || c == ',' || c == ')' || c == '.' || c == '\\'
+        })
+        .to_string()
+    };
+    let action = description
+        .split("perform: ")
+        .nth(1)
+        .and_then(|s| s.split_whitespace().next())
+        .map(trim);
+    let policy_arn = description
+        .split_whitespace()
+        .find(|w| w.contains("arn:aws:organizations::"))
+        .map(|w| trim(&w[w.find("arn:").unwrap_or(0)..]));
+    Some(PolicyDenial {
+        what: what.to_string(),
+        action,
+        policy_type: policy_type.to_string(),
+        policy_arn,
+    })
+}
+
+/// Extracts the AWS error code, e.g. `AccessDenied`, from the description of
+/// an error.
+pub fn error_code(description: &str) -> Option<String> {
+    let marker = "code: Some(\"";
+    let start = description.find(marker)? + marker.len();
+    let len = description[start..].find('"')?;
+    Some(description[start..start + len].to_string())
+}
+
+/// Returns what was being gathered when the credentials expired during the
+/// gathering run, if they did.
+pub(super) fn take_expired() -> Option<String> {
+    EXPIRED.with(|expired| expired.lock().unwrap().take())
+}
+
+/// Returns the denials seen so far in the gathering run and forgets them.
+pub fn take_denials() -> Vec<PolicyDenial> {
+    DENIALS.with(|denials| std::mem::take(&mut *denials.lock().unwrap()))
+}
+
+fn config_file_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("AWS_CONFIG_FILE") {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".aws").join("config"))
+}
+
+fn parse_config(content: &str) -> ConfigSections {
+    let mut sections: ConfigSections = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].trim().to_string();
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+        } else if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections
+                .entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    sections
+}
+
+fn load_config() -> ConfigSections {
+    config_file_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .map(|c| parse_config(&c))
+        .unwrap_or_default()
+}
+
+fn profile_section<'a>(
+    sections: &'a ConfigSections,
+    profile: &str,
+) -> Option<&'a HashMap<String, String>> {
+    if profile == "default" {
+        sections
+            .get("default")
+            .or_else(|| sections.get("profile default"))
+    } else {
+        sections.get(&format!("profile {}", profile))
+    }
+}
+
+/// Returns the name of the profile that will be used by the default provider
+/// chain.
+pub fn active_profile() -> String {
+    std::env::var("AWS_PROFILE").unwrap_or_else(|_| "default".to_string())
+}
+
+/// Returns the first profile referencing the given `sso-session`.
+pub fn profile_for_sso_session(sso_session: &str) -> Option<String> {
+    let sections = load_config();
+    let mut profiles: Vec<(&String, &HashMap<String, String>)> = sections
+        .iter()
+        .filter(|(_, values)| values.get("sso_session").is_some_and(|s| s == sso_session))
+        .collect();
+    profiles.sort_by_key(|(name, _)| *name);
+    profiles.first().map(|(name, _)| {
+        name.strip_prefix("profile ")
+            .unwrap_or(name.as_str())
+            .to_string()
+    })
+}
+
+/// Describes how an SSO profile is logged in, so the user can be told how to
+/// refresh the session.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SsoLogin {
+    /// The profile references a `[sso-session]` section.
+    Session(String),
+    /// The profile uses the legacy `sso_start_url` configuration.
+    Legacy(String),
+}
+
+impl SsoLogin {
+    pub fn login_command(&self) -> String {
+        match self {
+            SsoLogin::Session(session) => format!("aws sso login --sso-session {}", session),
+            SsoLogin::Legacy(profile) => format!("aws sso login --profile {}", profile),
+        }
+    }
+}
+
+/// Detects if the given profile is using AWS IAM Identity Center (SSO).
+pub fn sso_login_for_profile(profile: &str) -> Option<SsoLogin> {
+    let sections = load_config();
+    let values = profile_section(&sections, profile)?;
+    if let Some(session) = values.get("sso_session") {
+        Some(SsoLogin::Session(session.clone()))
+    } else if values.contains_key("sso_start_url") {
+        Some(SsoLogin::Legacy(profile.to_string()))
+    } else {
+        None
+    }
+}
+
+/// Describes where the credentials come from, e.g. `profile dev (SSO)` or
+/// `profile ops (assumes role arn:aws:iam::123456789012:role/ops)`.
+pub fn credentials_source(profile: &str) -> String {
+    if std::env::var("AWS_ACCESS_KEY_ID").is_ok() {
+        return "environment variables".to_string();
+    }
+    let sections = load_config();
+    let values = profile_section(&sections, profile);
+    if let Some(role_arn) = values.and_then(|v| v.get("role_arn")) {
+        format!("profile {} (assumes role {})", profile, role_arn)
+    } else if sso_login_for_profile(profile).is_some() {
+        format!("profile {} (SSO)", profile)
+    } else {
+        format!("profile {}", profile)
+    }
+}
+
+/// Verifies credentials can be loaded for the configuration. If they can not,
+/// the error will explain how to re-authenticate for SSO profiles.
+pub async fn verify_credentials(config: &SdkConfig, profile: &str) -> Result<(), InvariantError> {
+    let Some(provider) = config.credentials_provider() else {
+        return Err(InvariantError {
+            msg: "No AWS credentials provider is configured".to_string(),
+        });
+    };
+    match provider.provide_credentials().await {
+        Ok(credentials) => {
+            debug!("Credentials for profile {} are valid", profile);
+            let Some(expiry) = credentials.expiry() else {
+                return Ok(());
+            };
+            let remaining = expiry
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO);
+            debug!("Credentials expire in {}s", remaining.as_secs());
+            if remaining < ESTIMATED_RUN_DURATION {
+                let refresh = sso_login_for_profile(profile)
+                    .map_or("refresh your credentials".to_string(), |l| {
+                        format!("run `{}`", l.login_command())
+                    });
+                return Err(InvariantError {
+                    msg: format!(
+                        "The AWS credentials for profile '{}' expire in {}s, which is less than the estimated run duration of {}s. Please {} and try again.",
+                        profile,
+                        remaining.as_secs(),
+                        ESTIMATED_RUN_DURATION.as_secs(),
+                        refresh
+                    ),
+                });
+            }
+            Ok(())
+        }
+        Err(err) => {
+            let msg = match sso_login_for_profile(profile) {
+                Some(login) => format!(
+                    "The AWS SSO session for profile '{}' has expired or is not logged in. Please run `{}` and try again. ({})",
+                    profile,
+                    login.login_command(),
+                    DisplayErrorContext(&err)
+                ),
+                None => format!(
+                    "Could not load AWS credentials for profile '{}': {}",
+                    profile,
+                    DisplayErrorContext(&err)
+                ),
+            };
+            Err(InvariantError { msg })
+        }
+    }
+}
+
+/// The account of the credentials - subnets owned by another account are
+/// shared with it through RAM.
+pub async fn caller_account(config: &SdkConfig) -> Option<String> {
+    match aws_sdk_sts::Client::new(config)
+        .get_caller_identity()
+        .send()
+        .await
+    {
+        Ok(identity) => identity.account,
+        Err(e) => {
+            warn!(
+                "Could not determine the AWS account: {}",
+                DisplayErrorContext(&e)
+            );
+            None
+        }
+    }
+}
+
+/// Returns true if the error was caused by expired credentials.
+pub fn is_expired_credentials_error<E: Debug>(err: &E) -> bool {
+    let description = format!("{:?}", err);
+    EXPIRED_CREDENTIAL_MARKERS
+        .iter()
+        .any(|m| description.contains(m))
+}
+
+/// Runs a single attempt of a gathering step with a permit of the gathering
+/// run - the permit is released before waiting for a retry, so other steps
+/// can call the APIs in the meantime.
+async fn attempt<T, E, Fut>(step: Fut) -> Result<Vec<T>, E>
+where
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    let _permit = concurrency_permit().await;
+    step.await
+}
+
+/// Runs a gathering step and detects if credentials expired while it ran.
+/// The expiry is recorded for the gathering run and the error is returned
+/// right away, so [`super::gather`] can report the expired credentials
+/// together with the data gathered so far instead of every following
+/// gatherer failing with a misleading error.
+/// Steps that were throttled or failed with a transient error are retried
+/// after a backoff, see [`retry`].
+/// The step is reported to the progress line while it runs. Every attempt
+/// waits for a permit of the gathering run, see
+/// [`concurrency_permit`](crate::gatherer::aws::concurrency_permit).
+pub async fn with_expiry_detection<T, E, F, Fut>(what: &str, step: F) -> Result<Vec<T>, E>
+where
+    E: Debug,
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    progress::start(what);
+    let mut retries = 0;
+    loop {
+        // The error may not be `Send`, so it must not be held while waiting
+        // for the retry.
+        match attempt(step()).await {
+            Err(e) if is_expired_credentials_error(&e) => {
+                progress::finish(what, 0);
+                error!("AWS credentials expired while gathering {}", what);
+                // Outside of a gathering run, e.g. for the preflight checks,
+                // the caller sees the expiry in the returned error.
+                let _ = EXPIRED.try_with(|expired| {
+                    expired
+                        .lock()
+                        .unwrap()
+                        .get_or_insert_with(|| what.to_string());
+                });
+                return Err(e);
+            }
+            Err(e) if retries < retry::max_retries() && retry::is_retryable_error(&e) => {}
+            Err(e) => {
+                progress::finish(what, 0);
+                if let Some(denial) = parse_denial(what, &format!("{:?}", e)) {
+                    warn!("{}", denial);
+                    // Outside of a gathering run, e.g. for the preflight
+                    // checks, the denial is only logged.
+                    let _ = DENIALS.try_with(|denials| denials.lock().unwrap().push(denial));
+                }
+                return Err(e);
+            }
+            Ok(items) => {
+                progress::finish(what, items.len());
+                return Ok(items);
+            }
+        }
+        let delay = retry::backoff(retries);
+        retries += 1;
+        warn!(
+            "Gathering {} was throttled or failed transiently - retry {} of {} in {:.1}s",
+            what,
+            retries,
+            retry::max_retries(),
+            delay.as_secs_f64()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_denial() {
+        let description = r#"ServiceError { message: Some("User: arn:aws:sts::123456789012:assumed-role/ops/me is not authorized to perform: ec2:DescribeNatGateways with an explicit deny in a service control policy: arn:aws:organizations::111111111111:policy/o-abc/service_control_policy/p-123") }"#;
+        assert_eq!(
+            parse_denial("NAT gateways", description),
+            Some(PolicyDenial {
+                what: "NAT gateways".to_string(),
+                action: Some("ec2:DescribeNatGateways".to_string()),
+                policy_type: "service control policy".to_string(),
+                policy_arn: Some(
+                    "arn:aws:organizations::111111111111:policy/o-abc/service_control_policy/p-123"
+                        .to_string()
+                ),
+            })
+        );
+        assert_eq!(parse_denial("subnets", "ExpiredToken"), None);
+    }
+
+    #[test]
+    fn test_error_code() {
+        let description = r#"ServiceError(ServiceError { source: Unhandled(Unhandled { meta: ErrorMetadata { code: Some("UnauthorizedOperation"), message: Some("You are not authorized to perform this operation.") } }) })"#;
+        assert_eq!(
+            error_code(description),
+            Some("UnauthorizedOperation".to_string())
+        );
+        assert_eq!(error_code("dispatch failure"), None);
+    }
+}
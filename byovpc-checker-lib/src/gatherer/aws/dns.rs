@@ -0,0 +1,329 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use aws_sdk_route53::{
+    types::{HostedZone, RrType, TagResourceType},
+    Client,
+};
+use itertools::Itertools;
+use log::{debug, error};
+use serde::Deserialize;
+
+use crate::{
+    gatherer::Gatherer,
+    types::{InvariantError, MinimalClusterInfo},
+};
+
+use super::ensure_within_limit;
+use super::shared_types::{HostedZoneWithRecords, CLUSTER_TAG_PREFIX};
+
+pub struct HostedZoneGatherer<'a> {
+    pub client: &'a Client,
+    pub cluster_info: &'a MinimalClusterInfo,
+}
+
+impl<'a> HostedZoneGatherer<'a> {
+    async fn get_hosted_zones(&self) -> Result<Vec<HostedZone>, Box<dyn Error>> {
+        let infra_name = &self.cluster_info.cluster_infra_name;
+        if self.cluster_info.base_domain.is_none() && infra_name.is_empty() {
+            return Err(Box::new(InvariantError {
+                msg: "base_domain for cluster was empty - could not retrieve HostedZones"
+                    .to_string(),
+            }));
+        };
+        let zones = list_hosted_zones(self.client).await?;
+        let mut cluster_zones = vec![];
+        if let Some(ref domain) = self.cluster_info.base_domain {
+            debug!("Fetching hosted zone for base domain: {}", domain);
+            cluster_zones.extend(zones.iter().filter(|z| z.name.contains(domain)).cloned());
+        }
+        // Managed clusters tag their zones, which finds zones that do not
+        // follow the expected domain layout.
+        if !infra_name.is_empty() {
+            let tag_key = format!("{}{}", CLUSTER_TAG_PREFIX, infra_name);
+            debug!("Fetching hosted zones tagged with: {}", tag_key);
+            cluster_zones.extend(tagged_hosted_zones(self.client, &zones, &tag_key).await?);
+        }
+        Ok(cluster_zones
+            .into_iter()
+            .unique_by(|z| z.id.clone())
+            .collect())
+    }
+}
+
+async fn list_hosted_zones(client: &Client) -> Result<Vec<HostedZone>, Box<dyn Error>> {
+    let mut zones = vec![];
+    let mut paginator = client.list_hosted_zones().into_paginator().send();
+    while let Some(res) = paginator.next().await {
+        match res {
+            Ok(output) => zones.extend(output.hosted_zones),
+            Err(e) => {
+                error!("Failed to fetch hosted zones: {}", e);
+                return Err(Box::new(e));
+            }
+        }
+    }
+    Ok(zones)
+}
+
+/// Returns the zones carrying the tag key.
+async fn tagged_hosted_zones(
+    client: &Client,
+    zones: &[HostedZone],
+    tag_key: &str,
+) -> Result<Vec<HostedZone>, Box<dyn Error>> {
+    let mut tagged_zones = vec![];
+    // Tags can be retrieved for at most 10 zones per request.
+    for chunk in zones.chunks(10) {
+        let ids: Vec<String> = chunk
+            .iter()
+            .map(|z| z.id.trim_start_matches("/hostedzone/").to_string())
+            .collect();
+        let tag_sets = match client
+            .list_tags_for_resources()
+            .resource_type(TagResourceType::Hostedzone)
+            .set_resource_ids(Some(ids))
+            .send()
+            .await
+        {
+            Ok(output) => output.resource_tag_sets,
+            Err(e) => {
+                error!("Failed to fetch hosted zone tags: {}", e);
+                return Err(Box::new(e));
+            }
+        };
+        for tag_set in tag_sets {
+            let tagged = tag_set.tags().iter().any(|t| t.key() == Some(tag_key));
+            if !tagged {
+                continue;
+            }
+            let Some(id) = tag_set.resource_id() else {
+                continue;
+            };
+            tagged_zones.extend(
+                chunk
+                    .iter()
+                    .filter(|z| z.id.trim_start_matches("/hostedzone/") == id)
+                    .cloned(),
+            );
+        }
+    }
+    Ok(tagged_zones)
+}
+
+#[async_trait]
+impl<'a> Gatherer for HostedZoneGatherer<'a> {
+    type Resource = HostedZone;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Fetching hosted zones");
+        self.get_hosted_zones().await
+    }
+}
+
+/// The first labels of the records the cluster creates in its hosted zones.
+pub const CLUSTER_RECORD_PREFIXES: [&str; 2] = ["api.", "api-int."];
+
+/// Returns true if the record belongs to the cluster: the API records, the
+/// default ingress (`*.apps`) or one of the configured ingress domains.
+pub fn is_cluster_record(name: &str, ingress_domains: &[String]) -> bool {
+    let name = name.trim_end_matches('.');
+    CLUSTER_RECORD_PREFIXES.iter().any(|p| name.starts_with(p))
+        || name.split('.').nth(1) == Some("apps")
+        || name.starts_with("apps.")
+        || ingress_domains
+            .iter()
+            .map(|d| d.trim_end_matches('.'))
+            .any(|d| name == d || name.ends_with(&format!(".{}", d)))
+}
+
+pub struct ResourceRecordGatherer<'a> {
+    pub client: &'a Client,
+    pub hosted_zones: &'a Vec<HostedZone>,
+    /// Keep all records instead of only the ones belonging to the cluster.
+    pub all_records: bool,
+    pub ingress_domains: &'a Vec<String>,
+    /// Upper bound of records kept over all zones.
+    pub max_records: usize,
+}
+
+impl<'a> ResourceRecordGatherer<'a> {
+    async fn get_resource_records(&self) -> Result<Vec<HostedZoneWithRecords>, Box<dyn Error>> {
+        let mut hzrs = vec![];
+        let mut total_records = 0;
+        for hz in self.hosted_zones {
+            debug!("Fetching resource record set for hosted zone: {}", hz.id);
+            let mut resource_records = vec![];
+            let mut start: Option<(String, RrType, Option<String>)> = None;
+            loop {
+                let mut request = self
+                    .client
+                    .list_resource_record_sets()
+                    .hosted_zone_id(&hz.id);
+                if let Some((name, rr_type, identifier)) = start.take() {
+                    request = request
+                        .start_record_name(name)
+                        .start_record_type(rr_type)
+                        .set_start_record_identifier(identifier);
+                }
+                let r = match request.send().await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        error!("Failed to fetch resource records: {}", e);
+                        return Err(Box::new(e));
+                    }
+                };
+                // Filter every page right away, so huge zones are never held
+                // in memory completely.
+                resource_records.extend(
+                    r.resource_record_sets
+                        .iter()
+                        .filter(|rrs| {
+                            self.all_records || is_cluster_record(&rrs.name, self.ingress_domains)
+                        })
+                        .cloned(),
+                );
+                ensure_within_limit(
+                    "records",
+                    total_records + resource_records.len(),
+                    self.max_records,
+                    "--max-records",
+                )?;
+                if !r.is_truncated {
+                    break;
+                }
+                match (r.next_record_name, r.next_record_type) {
+                    (Some(name), Some(rr_type)) => {
+                        start = Some((name, rr_type, r.next_record_identifier))
+                    }
+                    _ => break,
+                }
+            }
+            total_records += resource_records.len();
+            debug!("Fetching VPCs and name servers for hosted zone: {}", hz.id);
+            let zone = match self.client.get_hosted_zone().id(&hz.id).send().await {
+                Ok(zone) => zone,
+                Err(e) => {
+                    error!("Failed to fetch hosted zone: {}", e);
+                    return Err(Box::new(e));
+                }
+            };
+            hzrs.push(HostedZoneWithRecords {
+                hosted_zone: hz.clone(),
+                resource_records,
+                vpcs: zone
+                    .vpcs()
+                    .iter()
+                    .filter_map(|v| v.vpc_id.clone())
+                    .collect(),
+                name_servers: zone
+                    .delegation_set()
+                    .map(|d| d.name_servers.clone())
+                    .unwrap_or_default(),
+                delegated_name_servers: None,
+            });
+        }
+        Ok(hzrs)
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for ResourceRecordGatherer<'a> {
+    type Resource = HostedZoneWithRecords;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Fetching resource record sets");
+        self.get_resource_records().await
+    }
+}
+
+/// The DNS-over-HTTPS endpoint used to resolve how zones are delegated on the
+/// internet - independent of the resolver of the local network.
+const DNS_OVER_HTTPS_URL: &str = "https://dns.google/resolve";
+/// The DNS response code of a name that does not exist.
+const NXDOMAIN: u32 = 3;
+/// The type code of NS records.
+const NS_TYPE: u32 = 2;
+
+#[derive(Debug, Deserialize)]
+struct DnsResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DnsAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsAnswer {
+    #[serde(rename = "type")]
+    rr_type: u32,
+    data: String,
+}
+
+/// Resolves the NS records of the name with a live DNS query, i.e. the name
+/// servers its parent domain delegates it to. Returns no name servers if the
+/// name is not delegated.
+pub async fn resolve_name_servers(
+    client: &reqwest::Client,
+    name: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    debug!("Resolving name servers of {}", name);
+    let response: DnsResponse = client
+        .get(DNS_OVER_HTTPS_URL)
+        .query(&[("name", name), ("type", "NS")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if response.status != 0 && response.status != NXDOMAIN {
+        return Err(Box::new(InvariantError {
+            msg: format!(
+                "DNS query for {} failed with status {}",
+                name, response.status
+            ),
+        }));
+    }
+    Ok(response
+        .answer
+        .into_iter()
+        .filter(|a| a.rr_type == NS_TYPE)
+        .map(|a| a.data)
+        .collect())
+}
+
+/// Resolves the delegation of the public zones. Zones whose delegation can
+/// not be resolved are left unresolved, they are not verified.
+pub async fn resolve_delegations(client: &reqwest::Client, zones: &mut [HostedZoneWithRecords]) {
+    for hz in zones.iter_mut() {
+        let private = hz.hosted_zone.config().is_some_and(|c| c.private_zone);
+        if private {
+            continue;
+        }
+        match resolve_name_servers(client, &hz.hosted_zone.name).await {
+            Ok(name_servers) => hz.delegated_name_servers = Some(name_servers),
+            Err(e) => error!(
+                "Could not resolve the delegation of {}: {}",
+                hz.hosted_zone.name, e
+            ),
+        }
+    }
+}
+
+/// Gathers the hosted zones carrying a tag key, e.g. the private zone of a
+/// deleted cluster.
+pub struct TaggedHostedZoneGatherer<'a> {
+    pub client: &'a Client,
+    pub tag_key: &'a String,
+}
+
+#[async_trait]
+impl<'a> Gatherer for TaggedHostedZoneGatherer<'a> {
+    type Resource = HostedZone;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Fetching hosted zones tagged with: {}", self.tag_key);
+        let zones = list_hosted_zones(self.client).await?;
+        tagged_hosted_zones(self.client, &zones, self.tag_key).await
+    }
+}
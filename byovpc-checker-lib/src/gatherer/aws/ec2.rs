@@ -0,0 +1,1131 @@
+use async_trait::async_trait;
+use aws_sdk_ec2::{
+    operation::describe_vpc_attribute::DescribeVpcAttributeOutput,
+    types::{
+        AccountAttribute, Address, AvailabilityZone, DhcpOptions, EgressOnlyInternetGateway,
+        Filter, FlowLog, GroupIdentifier, Instance, InstanceTypeOffering, InternetGateway,
+        LocationType, NatGateway, NetworkAcl, NetworkInterface, RouteTable, SecurityGroup, Subnet,
+        Vpc, VpcAttributeName, VpcEndpoint,
+    },
+    Client,
+};
+use itertools::Itertools;
+use log::{debug, error, info};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+};
+
+use crate::gatherer::Gatherer;
+use crate::types::MinimalClusterInfo;
+
+use super::shared_types::{
+    AWSInstance, AWSLoadBalancer, AWSLoadBalancerEni, VpcAttributes, CLUSTER_TAG_PREFIX,
+};
+
+/// Retrieves the subnets
+/// This gatherer will retrieve:
+/// - All configured subnets
+/// - All subnets in the same VPC as the configured subnets
+/// - All subnets tagged for the cluster
+pub struct ConfiguredSubnetGatherer<'a> {
+    pub client: &'a Client,
+    pub cluster_info: &'a MinimalClusterInfo,
+}
+
+impl<'a> ConfiguredSubnetGatherer<'a> {
+    async fn get_subnets_configured(&self) -> Result<Vec<Subnet>, Box<dyn Error>> {
+        info!("Fetching subnets via IDs");
+        if !self.cluster_info.subnets.is_empty() {
+            let mut subnets = vec![];
+            let mut paginator = self
+                .client
+                .describe_subnets()
+                .set_subnet_ids(Some(self.cluster_info.subnets.clone()))
+                .into_paginator()
+                .send();
+            while let Some(res) = paginator.next().await {
+                match res {
+                    Ok(success) => subnets.extend(success.subnets.unwrap_or_default()),
+                    Err(err) => {
+                        error!("Failed to fetch configured subnets: {}", err);
+                        return Err(Box::new(err));
+                    }
+                }
+            }
+            Ok(subnets)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    async fn get_subnets_by_tag(&self) -> Result<Vec<Subnet>, Box<dyn Error>> {
+        let cluster_name_tag = format!(
+            "{}{}",
+            CLUSTER_TAG_PREFIX, self.cluster_info.cluster_infra_name
+        );
+        info!("Fetching subnets via tags");
+        let mut subnets = vec![];
+        let mut paginator = self
+            .client
+            .describe_subnets()
+            .filters(
+                Filter::builder()
+                    .name("tag-key")
+                    .values(cluster_name_tag)
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(success) => subnets.extend(success.subnets.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch subnets by tags: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(subnets)
+    }
+
+    async fn get_subnets_by_vpc(&self, vpcid: String) -> Result<Vec<Subnet>, Box<dyn Error>> {
+        debug!("Retrieving subnets for VPC: {}", vpcid);
+        let subnets_filter = Filter::builder().name("vpc-id").values(vpcid).build();
+        let mut subnets = vec![];
+        let mut paginator = self
+            .client
+            .describe_subnets()
+            .set_filters(Some(vec![subnets_filter]))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(success) => subnets.extend(success.subnets.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch subnets by VPCID: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(subnets)
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for ConfiguredSubnetGatherer<'a> {
+    type Resource = Subnet;
+
+    async fn gather(&self) -> Result<Vec<Subnet>, Box<dyn Error>> {
+        let mut all_subnets = self.get_subnets_configured().await?;
+        all_subnets.extend(self.get_subnets_by_tag().await?);
+        // The cluster subnets should all be in one VPC, but the subnets of
+        // every VPC are gathered so a cluster spanning several VPCs can be
+        // reported instead of losing the subnets of all but one.
+        let vpc_ids: Vec<String> = all_subnets
+            .iter()
+            .filter_map(|s| s.vpc_id.clone())
+            .unique()
+            .collect();
+        for vpcid in vpc_ids {
+            all_subnets.extend(self.get_subnets_by_vpc(vpcid).await?);
+        }
+        Ok(all_subnets
+            .into_iter()
+            .unique_by(|s| s.subnet_id.clone())
+            .collect())
+    }
+}
+
+/// Gather the routetables associated with the subnets.
+pub struct RouteTableGatherer<'a> {
+    pub client: &'a Client,
+    pub subnet_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for RouteTableGatherer<'a> {
+    type Resource = RouteTable;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!(
+            "Retrieving route tables for subnets: {}",
+            self.subnet_ids.join(",")
+        );
+        let rtb_filter = Filter::builder()
+            .name("association.subnet-id")
+            .set_values(Some(self.subnet_ids.clone()))
+            .build();
+        let mut route_tables = vec![];
+        let mut paginator = self
+            .client
+            .describe_route_tables()
+            .set_filters(Some(vec![rtb_filter]))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(success) => route_tables.extend(success.route_tables.unwrap_or_default()),
+                Err(err) => return Err(Box::new(err)),
+            }
+        }
+        Ok(route_tables)
+    }
+}
+
+/// Gathers all ENIs in the subnets - including the ENIs other AWS services
+/// created in them.
+pub struct SubnetNetworkInterfaceGatherer<'a> {
+    pub client: &'a Client,
+    pub subnet_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for SubnetNetworkInterfaceGatherer<'a> {
+    type Resource = NetworkInterface;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.subnet_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!("Retrieving ENIs for subnets: {}", self.subnet_ids.join(","));
+        let mut network_interfaces = vec![];
+        let mut paginator = self
+            .client
+            .describe_network_interfaces()
+            .filters(
+                Filter::builder()
+                    .name("subnet-id")
+                    .set_values(Some(self.subnet_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => {
+                    network_interfaces.extend(output.network_interfaces.unwrap_or_default())
+                }
+                Err(err) => {
+                    error!("Failed to fetch ENIs of subnets: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(network_interfaces)
+    }
+}
+
+/// Gathers the network ACLs associated with the subnets.
+pub struct NetworkAclGatherer<'a> {
+    pub client: &'a Client,
+    pub subnet_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for NetworkAclGatherer<'a> {
+    type Resource = NetworkAcl;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.subnet_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving network ACLs for subnets: {}",
+            self.subnet_ids.join(",")
+        );
+        let mut acls = vec![];
+        let mut paginator = self
+            .client
+            .describe_network_acls()
+            .filters(
+                Filter::builder()
+                    .name("association.subnet-id")
+                    .set_values(Some(self.subnet_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => acls.extend(output.network_acls.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch network ACLs: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(acls)
+    }
+}
+
+pub struct InstanceGatherer<'a> {
+    pub client: &'a Client,
+    pub cluster_info: &'a MinimalClusterInfo,
+}
+
+impl<'a> InstanceGatherer<'a> {
+    /// Returns the security groups in use by instances of the cluster.
+    pub async fn get_security_groups(
+        &self,
+        instances: &Vec<Instance>,
+    ) -> Result<Vec<SecurityGroup>, Box<dyn Error>> {
+        // Without group IDs all security groups of the region would be
+        // returned.
+        if instances.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut sgs: Vec<GroupIdentifier> = instances
+            .into_iter()
+            .map(|i| i.security_groups.clone().unwrap())
+            .flatten()
+            .collect();
+        sgs.dedup();
+        let mut security_groups = vec![];
+        let mut paginator = self
+            .client
+            .describe_security_groups()
+            .set_group_ids(Some(
+                sgs.into_iter().map(|sg| sg.group_id.unwrap()).collect(),
+            ))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(sg) => security_groups.extend(sg.security_groups.unwrap_or_default()),
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+        Ok(security_groups)
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for InstanceGatherer<'a> {
+    type Resource = AWSInstance;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let cluster_tag = format!(
+            "tag:{}{}",
+            CLUSTER_TAG_PREFIX, self.cluster_info.cluster_infra_name
+        );
+        let mut openshift_instances: Vec<AWSInstance> = vec![];
+        let mut aws_instances: Vec<Instance> = vec![];
+        let mut paginator = self
+            .client
+            .describe_instances()
+            .filters(Filter::builder().name(cluster_tag).values("owned").build())
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(instance_output) => aws_instances.extend(
+                    instance_output
+                        .reservations
+                        .unwrap_or_default()
+                        .into_iter()
+                        .flat_map(|r| r.instances.unwrap_or_default()),
+                ),
+                Err(err) => return Err(Box::new(err)),
+            }
+        }
+        let security_groups = self.get_security_groups(&aws_instances).await?;
+        for instance in aws_instances.iter() {
+            let mut awsi = AWSInstance {
+                instance: instance.clone(),
+                security_groups: vec![],
+            };
+            for sg in security_groups.iter() {
+                let group_identifiers: Vec<&String> = instance
+                    .security_groups()
+                    .iter()
+                    .map(|gi| gi.group_id.as_ref().unwrap())
+                    .collect();
+                if group_identifiers.contains(&sg.group_id.as_ref().unwrap()) {
+                    awsi.security_groups.push(sg.clone());
+                }
+            }
+            openshift_instances.push(awsi);
+        }
+        Ok(openshift_instances)
+    }
+}
+
+/// The most values EC2 accepts for a single filter.
+const MAX_FILTER_VALUES: usize = 200;
+
+/// Gathers the ENIs AWS created for the load balancers and attributes each to
+/// its load balancer by its description (see
+/// [`AWSLoadBalancer::eni_description`]). The ENIs are looked up per interface
+/// type, so ENIs of other services with a similar description are not picked
+/// up.
+pub struct NetworkInterfaceGatherer<'a> {
+    pub client: &'a Client,
+    pub loadbalancers: &'a Vec<AWSLoadBalancer>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for NetworkInterfaceGatherer<'a> {
+    type Resource = AWSLoadBalancerEni;
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving ENIs for LoadBalancers");
+        let load_balancers: HashMap<String, &AWSLoadBalancer> = self
+            .loadbalancers
+            .iter()
+            .filter_map(|lb| Some((lb.eni_description()?, lb)))
+            .collect();
+        let descriptions_per_type: BTreeMap<String, Vec<String>> = self
+            .loadbalancers
+            .iter()
+            .filter_map(|lb| {
+                Some((
+                    lb.eni_interface_type().as_str().to_string(),
+                    lb.eni_description()?,
+                ))
+            })
+            .into_group_map()
+            .into_iter()
+            .collect();
+        let mut network_interfaces = vec![];
+        for (interface_type, descriptions) in descriptions_per_type {
+            for descriptions in descriptions.chunks(MAX_FILTER_VALUES) {
+                let mut paginator = self
+                    .client
+                    .describe_network_interfaces()
+                    .filters(
+                        Filter::builder()
+                            .name("interface-type")
+                            .values(interface_type.as_str())
+                            .build(),
+                    )
+                    .filters(
+                        Filter::builder()
+                            .name("description")
+                            .set_values(Some(descriptions.to_vec()))
+                            .build(),
+                    )
+                    .into_paginator()
+                    .send();
+                while let Some(result) = paginator.next().await {
+                    match result {
+                        Ok(success) => {
+                            debug!("Found ENIs: {:?}", success);
+                            network_interfaces.extend(
+                                success
+                                    .network_interfaces
+                                    .unwrap_or_default()
+                                    .into_iter()
+                                    .filter_map(|eni| {
+                                        let lb = load_balancers.get(eni.description()?)?;
+                                        Some(AWSLoadBalancerEni {
+                                            load_balancer: lb.identifier().to_string(),
+                                            eni,
+                                        })
+                                    }),
+                            )
+                        }
+                        Err(err) => return Err(Box::new(err)),
+                    }
+                }
+            }
+        }
+        Ok(network_interfaces)
+    }
+}
+
+/// Gathers all availability zones of the region, including the ones the
+/// account has not opted into.
+pub struct AvailabilityZoneGatherer<'a> {
+    pub client: &'a Client,
+}
+
+#[async_trait]
+impl<'a> Gatherer for AvailabilityZoneGatherer<'a> {
+    type Resource = AvailabilityZone;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving availability zones");
+        match self
+            .client
+            .describe_availability_zones()
+            .all_availability_zones(true)
+            .send()
+            .await
+        {
+            Ok(success) => Ok(success.availability_zones.unwrap_or_default()),
+            Err(err) => {
+                error!("Failed to fetch availability zones: {}", err);
+                Err(Box::new(err))
+            }
+        }
+    }
+}
+
+/// Gathers the availability zone offerings for the instance types used by the
+/// cluster's machine pools.
+pub struct InstanceTypeOfferingGatherer<'a> {
+    pub client: &'a Client,
+    pub cluster_info: &'a MinimalClusterInfo,
+}
+
+#[async_trait]
+impl<'a> Gatherer for InstanceTypeOfferingGatherer<'a> {
+    type Resource = InstanceTypeOffering;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let instance_types: Vec<String> = self
+            .cluster_info
+            .machine_pools
+            .iter()
+            .filter_map(|mp| mp.instance_type.clone())
+            .unique()
+            .collect();
+        if instance_types.is_empty() {
+            debug!("No instance types configured - not retrieving offerings");
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving instance type offerings for: {}",
+            instance_types.join(",")
+        );
+        let mut offerings = vec![];
+        let mut paginator = self
+            .client
+            .describe_instance_type_offerings()
+            .location_type(LocationType::AvailabilityZone)
+            .filters(
+                Filter::builder()
+                    .name("instance-type")
+                    .set_values(Some(instance_types))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => offerings.extend(output.instance_type_offerings.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch instance type offerings: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(offerings)
+    }
+}
+
+/// Gathers the NAT gateways in the VPCs of the cluster.
+pub struct NatGatewayGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for NatGatewayGatherer<'a> {
+    type Resource = NatGateway;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving NAT gateways for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut nat_gateways = vec![];
+        let mut paginator = self
+            .client
+            .describe_nat_gateways()
+            .filter(
+                Filter::builder()
+                    .name("vpc-id")
+                    .set_values(Some(self.vpc_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => nat_gateways.extend(output.nat_gateways.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch NAT gateways: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(nat_gateways)
+    }
+}
+
+/// Retrieves the internet gateways attached to the VPCs.
+pub struct InternetGatewayGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for InternetGatewayGatherer<'a> {
+    type Resource = InternetGateway;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving internet gateways for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut gateways = vec![];
+        let mut paginator = self
+            .client
+            .describe_internet_gateways()
+            .filters(
+                Filter::builder()
+                    .name("attachment.vpc-id")
+                    .set_values(Some(self.vpc_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => gateways.extend(output.internet_gateways.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch internet gateways: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(gateways)
+    }
+}
+
+/// Retrieves the egress-only internet gateways attached to the VPCs - the
+/// API can not filter by VPC.
+pub struct EgressOnlyInternetGatewayGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for EgressOnlyInternetGatewayGatherer<'a> {
+    type Resource = EgressOnlyInternetGateway;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving egress-only internet gateways for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut gateways = vec![];
+        let mut paginator = self
+            .client
+            .describe_egress_only_internet_gateways()
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => gateways.extend(
+                    output
+                        .egress_only_internet_gateways
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|g| {
+                            g.attachments().iter().any(|a| {
+                                a.vpc_id()
+                                    .is_some_and(|id| self.vpc_ids.iter().any(|v| v == id))
+                            })
+                        }),
+                ),
+                Err(err) => {
+                    error!("Failed to fetch egress-only internet gateways: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(gateways)
+    }
+}
+
+/// Gathers the elastic IPs of the region.
+pub struct ElasticIpGatherer<'a> {
+    pub client: &'a Client,
+}
+
+#[async_trait]
+impl<'a> Gatherer for ElasticIpGatherer<'a> {
+    type Resource = Address;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving elastic IPs");
+        match self
+            .client
+            .describe_addresses()
+            .filters(Filter::builder().name("domain").values("vpc").build())
+            .send()
+            .await
+        {
+            Ok(success) => Ok(success.addresses.unwrap_or_default()),
+            Err(err) => {
+                error!("Failed to fetch elastic IPs: {}", err);
+                Err(Box::new(err))
+            }
+        }
+    }
+}
+
+/// Retrieves the subnets for a VPC that has no cluster yet: the given subnets
+/// and all subnets of the given VPC.
+pub struct VpcSubnetGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_id: Option<&'a String>,
+    pub subnet_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for VpcSubnetGatherer<'a> {
+    type Resource = Subnet;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let mut subnets = vec![];
+        if !self.subnet_ids.is_empty() {
+            debug!("Retrieving subnets: {}", self.subnet_ids.join(","));
+            let mut paginator = self
+                .client
+                .describe_subnets()
+                .set_subnet_ids(Some(self.subnet_ids.clone()))
+                .into_paginator()
+                .send();
+            while let Some(res) = paginator.next().await {
+                match res {
+                    Ok(success) => subnets.extend(success.subnets.unwrap_or_default()),
+                    Err(err) => {
+                        error!("Failed to fetch subnets: {}", err);
+                        return Err(Box::new(err));
+                    }
+                }
+            }
+        }
+        let vpc_ids: Vec<String> = match self.vpc_id {
+            Some(vpc_id) => vec![vpc_id.clone()],
+            None => subnets
+                .iter()
+                .filter_map(|s| s.vpc_id.clone())
+                .unique()
+                .collect(),
+        };
+        if vpc_ids.is_empty() {
+            return Ok(subnets);
+        }
+        debug!("Retrieving subnets for VPCs: {}", vpc_ids.join(","));
+        let mut paginator = self
+            .client
+            .describe_subnets()
+            .filters(
+                Filter::builder()
+                    .name("vpc-id")
+                    .set_values(Some(vpc_ids))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => subnets.extend(output.subnets.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch subnets by VPC: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(subnets
+            .into_iter()
+            .unique_by(|s| s.subnet_id.clone())
+            .collect())
+    }
+}
+
+/// Gathers all route tables of the VPCs, including the main route tables that
+/// apply to subnets without an explicit association.
+pub struct VpcRouteTableGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for VpcRouteTableGatherer<'a> {
+    type Resource = RouteTable;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving route tables for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut routetables = vec![];
+        let mut paginator = self
+            .client
+            .describe_route_tables()
+            .filters(
+                Filter::builder()
+                    .name("vpc-id")
+                    .set_values(Some(self.vpc_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => routetables.extend(output.route_tables.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch route tables: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(routetables)
+    }
+}
+
+/// Gathers the VPCs, including all their associated CIDR blocks.
+pub struct VpcGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for VpcGatherer<'a> {
+    type Resource = Vpc;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!("Retrieving VPCs: {}", self.vpc_ids.join(","));
+        let mut vpcs = vec![];
+        let mut paginator = self
+            .client
+            .describe_vpcs()
+            .set_vpc_ids(Some(self.vpc_ids.clone()))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => vpcs.extend(output.vpcs.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch VPCs: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(vpcs)
+    }
+}
+
+/// Gathers the DNS attributes of the VPCs - they are not part of the VPCs
+/// returned by `DescribeVpcs` and have to be read one by one.
+pub struct VpcAttributeGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+impl<'a> VpcAttributeGatherer<'a> {
+    async fn describe(
+        &self,
+        vpc_id: &str,
+        attribute: VpcAttributeName,
+    ) -> Result<DescribeVpcAttributeOutput, Box<dyn Error>> {
+        let output = self
+            .client
+            .describe_vpc_attribute()
+            .vpc_id(vpc_id)
+            .attribute(attribute.clone())
+            .send()
+            .await
+            .map_err(|err| {
+                error!(
+                    "Failed to fetch attribute {} of VPC {}: {}",
+                    attribute.as_str(),
+                    vpc_id,
+                    err
+                );
+                Box::new(err)
+            })?;
+        Ok(output)
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for VpcAttributeGatherer<'a> {
+    type Resource = VpcAttributes;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving attributes of VPCs: {}", self.vpc_ids.join(","));
+        let mut attributes = vec![];
+        for vpc_id in self.vpc_ids.iter() {
+            let dns_support = self
+                .describe(vpc_id, VpcAttributeName::EnableDnsSupport)
+                .await?;
+            let dns_hostnames = self
+                .describe(vpc_id, VpcAttributeName::EnableDnsHostnames)
+                .await?;
+            attributes.push(VpcAttributes {
+                vpc_id: vpc_id.clone(),
+                enable_dns_support: dns_support.enable_dns_support().and_then(|v| v.value()),
+                enable_dns_hostnames: dns_hostnames.enable_dns_hostnames().and_then(|v| v.value()),
+            });
+        }
+        Ok(attributes)
+    }
+}
+
+/// Gathers the DHCP options sets of the VPCs.
+pub struct DhcpOptionsGatherer<'a> {
+    pub client: &'a Client,
+    pub dhcp_options_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for DhcpOptionsGatherer<'a> {
+    type Resource = DhcpOptions;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.dhcp_options_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving DHCP options: {}",
+            self.dhcp_options_ids.join(",")
+        );
+        let mut dhcp_options = vec![];
+        let mut paginator = self
+            .client
+            .describe_dhcp_options()
+            .set_dhcp_options_ids(Some(self.dhcp_options_ids.clone()))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => dhcp_options.extend(output.dhcp_options.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch DHCP options: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(dhcp_options)
+    }
+}
+
+/// Gathers the VPC endpoints of the VPCs.
+pub struct VpcEndpointGatherer<'a> {
+    pub client: &'a Client,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for VpcEndpointGatherer<'a> {
+    type Resource = VpcEndpoint;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving VPC endpoints for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut endpoints = vec![];
+        let mut paginator = self
+            .client
+            .describe_vpc_endpoints()
+            .filters(
+                Filter::builder()
+                    .name("vpc-id")
+                    .set_values(Some(self.vpc_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => endpoints.extend(output.vpc_endpoints.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch VPC endpoints: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(endpoints)
+    }
+}
+
+/// Retrieves the flow logs of the VPCs and of the subnets.
+pub struct FlowLogGatherer<'a> {
+    pub client: &'a Client,
+    /// The VPCs and subnets - flow logs of single network interfaces are not
+    /// gathered.
+    pub resource_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for FlowLogGatherer<'a> {
+    type Resource = FlowLog;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.resource_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving flow logs for resources: {}",
+            self.resource_ids.join(",")
+        );
+        let mut flow_logs = vec![];
+        let mut paginator = self
+            .client
+            .describe_flow_logs()
+            .filter(
+                Filter::builder()
+                    .name("resource-id")
+                    .set_values(Some(self.resource_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => flow_logs.extend(output.flow_logs.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch flow logs: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(flow_logs)
+    }
+}
+
+/// Gathers the EC2 account attributes, which include quotas like
+/// `vpc-max-elastic-ips`.
+pub struct AccountAttributeGatherer<'a> {
+    pub client: &'a Client,
+}
+
+#[async_trait]
+impl<'a> Gatherer for AccountAttributeGatherer<'a> {
+    type Resource = AccountAttribute;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving account attributes");
+        match self.client.describe_account_attributes().send().await {
+            Ok(success) => Ok(success.account_attributes.unwrap_or_default()),
+            Err(err) => {
+                error!("Failed to fetch account attributes: {}", err);
+                Err(Box::new(err))
+            }
+        }
+    }
+}
+
+fn tag_key_filter(tag_key: &str) -> Filter {
+    Filter::builder().name("tag-key").values(tag_key).build()
+}
+
+/// Gathers the ENIs carrying a tag key, e.g. the cluster tag of a deleted
+/// cluster.
+pub struct TaggedNetworkInterfaceGatherer<'a> {
+    pub client: &'a Client,
+    pub tag_key: &'a String,
+}
+
+#[async_trait]
+impl<'a> Gatherer for TaggedNetworkInterfaceGatherer<'a> {
+    type Resource = NetworkInterface;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving ENIs tagged with: {}", self.tag_key);
+        let mut network_interfaces = vec![];
+        let mut paginator = self
+            .client
+            .describe_network_interfaces()
+            .filters(tag_key_filter(self.tag_key))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => {
+                    network_interfaces.extend(output.network_interfaces.unwrap_or_default())
+                }
+                Err(err) => {
+                    error!("Failed to fetch tagged ENIs: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(network_interfaces)
+    }
+}
+
+/// Gathers the security groups carrying a tag key.
+pub struct TaggedSecurityGroupGatherer<'a> {
+    pub client: &'a Client,
+    pub tag_key: &'a String,
+}
+
+#[async_trait]
+impl<'a> Gatherer for TaggedSecurityGroupGatherer<'a> {
+    type Resource = SecurityGroup;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving security groups tagged with: {}", self.tag_key);
+        let mut security_groups = vec![];
+        let mut paginator = self
+            .client
+            .describe_security_groups()
+            .filters(tag_key_filter(self.tag_key))
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => security_groups.extend(output.security_groups.unwrap_or_default()),
+                Err(err) => {
+                    error!("Failed to fetch tagged security groups: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(security_groups)
+    }
+}
+
+/// Gathers the elastic IPs carrying a tag key.
+pub struct TaggedElasticIpGatherer<'a> {
+    pub client: &'a Client,
+    pub tag_key: &'a String,
+}
+
+#[async_trait]
+impl<'a> Gatherer for TaggedElasticIpGatherer<'a> {
+    type Resource = Address;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving elastic IPs tagged with: {}", self.tag_key);
+        match self
+            .client
+            .describe_addresses()
+            .filters(tag_key_filter(self.tag_key))
+            .send()
+            .await
+        {
+            Ok(success) => Ok(success.addresses.unwrap_or_default()),
+            Err(err) => {
+                error!("Failed to fetch tagged elastic IPs: {}", err);
+                Err(Box::new(err))
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Gathers the open events of the AWS Health API, to answer "is AWS itself
+//! degraded?" in the same report.
+//!
+//! There is no SDK crate for the Health API in use, so the requests are signed
+//! with SigV4 and sent as plain JSON requests. The API is only available with a
+//! Business, Enterprise On-Ramp or Enterprise support plan.
+
+use std::error::Error;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::gatherer::aws::{send_signed, SignedApi};
+use crate::gatherer::Gatherer;
+use crate::types::InvariantError;
+
+/// The Health API is global, but served from us-east-1.
+const HEALTH_API: SignedApi = SignedApi {
+    service: "health",
+    region: "us-east-1",
+    endpoint: "https://health.us-east-1.amazonaws.com/",
+};
+const DESCRIBE_EVENTS: &str = "AWSHealth_20160804.DescribeEvents";
+/// The services whose events can affect the cluster network.
+pub const HEALTH_SERVICES: [&str; 3] = ["EC2", "ELASTICLOADBALANCING", "ROUTE53"];
+
+/// An event of the AWS Health API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthEvent {
+    pub arn: String,
+    pub service: String,
+    pub event_type_code: String,
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Only set for events affecting a single availability zone.
+    #[serde(default)]
+    pub availability_zone: Option<String>,
+    /// Seconds since the epoch.
+    #[serde(default)]
+    pub start_time: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DescribeEventsResponse {
+    #[serde(default)]
+    events: Vec<HealthEvent>,
+    next_token: Option<String>,
+}
+
+/// Gathers the open events of the network related services in a region.
+pub struct HealthEventGatherer<'a> {
+    pub aws_config: &'a SdkConfig,
+    pub client: &'a reqwest::Client,
+    pub region: &'a str,
+}
+
+impl<'a> HealthEventGatherer<'a> {
+    /// Sends a request to the Health API, signed with the credentials of the
+    /// AWS configuration.
+    async fn send(
+        &self,
+        target: &str,
+        body: &serde_json::Value,
+    ) -> Result<reqwest::Response, Box<dyn Error>> {
+        let headers = [
+            ("content-type", "application/x-amz-json-1.1"),
+            ("x-amz-target", target),
+        ];
+        send_signed(
+            self.aws_config,
+            self.client,
+            &HEALTH_API,
+            &headers,
+            serde_json::to_vec(body)?,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for HealthEventGatherer<'a> {
+    type Resource = HealthEvent;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving open AWS Health events for {}", self.region);
+        let mut events = vec![];
+        let mut next_token: Option<String> = None;
+        loop {
+            let mut body = json!({
+                "filter": {
+                    "services": HEALTH_SERVICES,
+                    "regions": [self.region],
+                    "eventStatusCodes": ["open"],
+                },
+                "maxResults": 100,
+            });
+            if let Some(ref token) = next_token {
+                body["nextToken"] = json!(token);
+            }
+            let response = self.send(DESCRIBE_EVENTS, &body).await?;
+            let status = response.status();
+            if !status.is_success() {
+                let text = response.text().await.unwrap_or_default();
+                error!("Failed to fetch AWS Health events: {} {}", status, text);
+                return Err(Box::new(InvariantError {
+                    msg: format!("AWS Health API returned {}: {}", status, text),
+                }));
+            }
+            let output: DescribeEventsResponse = response.json().await?;
+            events.extend(output.events);
+            match output.next_token {
+                Some(token) => next_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,223 @@
+//! Gathers the instance profiles of the cluster instances with the policies of
+//! their roles, to verify the nodes can call the AWS APIs they need.
+//!
+//! There is no SDK crate for IAM in use, so the requests are signed with SigV4
+//! and sent to the query API, which answers in JSON if asked to.
+
+use std::error::Error;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use itertools::Itertools;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::gatherer::aws::shared_types::AWSInstance;
+use crate::gatherer::aws::{send_signed, SignedApi};
+use crate::gatherer::Gatherer;
+use crate::types::InvariantError;
+
+/// IAM is global, but signed for us-east-1.
+const IAM_API: SignedApi = SignedApi {
+    service: "iam",
+    region: "us-east-1",
+    endpoint: "https://iam.amazonaws.com/",
+};
+const IAM_VERSION: &str = "2010-05-08";
+
+/// A policy of a role - either attached or inline.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IamPolicy {
+    pub name: String,
+    /// The ARN of an attached policy - `None` for inline policies.
+    #[serde(default)]
+    pub arn: Option<String>,
+    /// The policy document - `None` if it could not be read.
+    #[serde(default)]
+    pub document: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IamRole {
+    pub name: String,
+    #[serde(default)]
+    pub policies: Vec<IamPolicy>,
+}
+
+/// The instance profile of cluster instances with its roles.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceProfile {
+    pub arn: String,
+    #[serde(default)]
+    pub roles: Vec<IamRole>,
+}
+
+/// The name of an instance profile, the last part of its ARN, e.g.
+/// `arn:aws:iam::123456789012:instance-profile/mycluster-abcde-worker-profile`.
+fn profile_name(arn: &str) -> &str {
+    arn.rsplit('/').next().unwrap_or(arn)
+}
+
+/// Policy documents are returned URL encoded.
+fn decode_document(document: &str) -> Option<String> {
+    percent_encoding::percent_decode_str(document)
+        .decode_utf8()
+        .ok()
+        .map(|d| d.to_string())
+}
+
+/// Gathers the instance profiles used by the instances.
+pub struct InstanceProfileGatherer<'a> {
+    pub aws_config: &'a SdkConfig,
+    pub client: &'a reqwest::Client,
+    pub instances: &'a [AWSInstance],
+}
+
+impl<'a> InstanceProfileGatherer<'a> {
+    /// Calls an action of the IAM query API, signed with the credentials of
+    /// the AWS configuration, and returns the result of the response.
+    async fn call(&self, action: &str, params: &[(&str, &str)]) -> Result<Value, Box<dyn Error>> {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("Action", action)
+            .append_pair("Version", IAM_VERSION)
+            .extend_pairs(params)
+            .finish()
+            .into_bytes();
+        let headers = [
+            (
+                "content-type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            ),
+            ("accept", "application/json"),
+        ];
+        let response = send_signed(self.aws_config, self.client, &IAM_API, &headers, body).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            error!("IAM {} failed: {} {}", action, status, text);
+            return Err(Box::new(InvariantError {
+                msg: format!("IAM {} returned {}: {}", action, status, text),
+            }));
+        }
+        let mut output: Value = response.json().await?;
+        Ok(output[format!("{}Response", action)][format!("{}Result", action)].take())
+    }
+
+    /// Calls a list action until all pages are read and returns the items of
+    /// the key.
+    async fn list(
+        &self,
+        action: &str,
+        role: &str,
+        key: &str,
+    ) -> Result<Vec<Value>, Box<dyn Error>> {
+        let mut items = vec![];
+        let mut marker: Option<String> = None;
+        loop {
+            let mut params = vec![("RoleName", role)];
+            if let Some(ref marker) = marker {
+                params.push(("Marker", marker));
+            }
+            let mut result = self.call(action, &params).await?;
+            if let Value::Array(page) = result[key].take() {
+                items.extend(page);
+            }
+            match result["Marker"].as_str() {
+                Some(m) if result["IsTruncated"].as_bool() == Some(true) => {
+                    marker = Some(m.to_string())
+                }
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+
+    async fn attached_policy(&self, name: &str, arn: &str) -> Result<IamPolicy, Box<dyn Error>> {
+        let policy = self.call("GetPolicy", &[("PolicyArn", arn)]).await?;
+        let version = policy["Policy"]["DefaultVersionId"]
+            .as_str()
+            .unwrap_or_default();
+        let version = self
+            .call(
+                "GetPolicyVersion",
+                &[("PolicyArn", arn), ("VersionId", version)],
+            )
+            .await?;
+        Ok(IamPolicy {
+            name: name.to_string(),
+            arn: Some(arn.to_string()),
+            document: version["PolicyVersion"]["Document"]
+                .as_str()
+                .and_then(decode_document),
+        })
+    }
+
+    async fn role(&self, name: &str) -> Result<IamRole, Box<dyn Error>> {
+        let mut policies = vec![];
+        let attached_policies = self
+            .list("ListAttachedRolePolicies", name, "AttachedPolicies")
+            .await?;
+        for attached in attached_policies {
+            let policy_name = attached["PolicyName"].as_str().unwrap_or_default();
+            let arn = attached["PolicyArn"].as_str().unwrap_or_default();
+            policies.push(self.attached_policy(policy_name, arn).await?);
+        }
+        let inline_policies = self.list("ListRolePolicies", name, "PolicyNames").await?;
+        for inline in inline_policies {
+            let policy_name = inline.as_str().unwrap_or_default();
+            let policy = self
+                .call(
+                    "GetRolePolicy",
+                    &[("RoleName", name), ("PolicyName", policy_name)],
+                )
+                .await?;
+            policies.push(IamPolicy {
+                name: policy_name.to_string(),
+                arn: None,
+                document: policy["PolicyDocument"].as_str().and_then(decode_document),
+            });
+        }
+        Ok(IamRole {
+            name: name.to_string(),
+            policies,
+        })
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for InstanceProfileGatherer<'a> {
+    type Resource = InstanceProfile;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let arns: Vec<String> = self
+            .instances
+            .iter()
+            .filter_map(|i| i.instance.iam_instance_profile())
+            .filter_map(|p| p.arn())
+            .unique()
+            .map(|arn| arn.to_string())
+            .collect();
+        let mut profiles = vec![];
+        for arn in arns {
+            debug!("Retrieving instance profile {}", arn);
+            let profile = self
+                .call(
+                    "GetInstanceProfile",
+                    &[("InstanceProfileName", profile_name(&arn))],
+                )
+                .await?;
+            let mut roles = vec![];
+            for role in profile["InstanceProfile"]["Roles"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+            {
+                let name = role["RoleName"].as_str().unwrap_or_default();
+                roles.push(self.role(name).await?);
+            }
+            profiles.push(InstanceProfile { arn, roles });
+        }
+        Ok(profiles)
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use aws_sdk_elasticloadbalancing::Client as ELBClient;
+use log::debug;
+
+use super::shared_types::Collector;
+use super::shared_types::DefaultCollector;
+use super::shared_types::HypershiftCollector;
+use crate::gatherer::aws::shared_types::{AWSLoadBalancer, AWSLoadBalancerAttributes};
+use crate::gatherer::aws::{
+    ensure_within_limit, in_batches, DESCRIBE_TAGS_BATCH_SIZE, DESCRIBE_TAGS_CONCURRENCY,
+};
+use crate::types::MinimalClusterInfo;
+
+pub async fn get_classic_load_balancers(
+    elb_client: &ELBClient,
+    cluster_info: &MinimalClusterInfo,
+    max_load_balancers: usize,
+) -> Result<Vec<AWSLoadBalancer>, Box<dyn Error>> {
+    let mut cluster_lbs = vec![];
+    debug!("Retrieving classic LoadBalancers");
+    let collector: Box<dyn Collector + Send> = match cluster_info.cluster_type {
+        crate::types::ClusterType::Hypershift => {
+            debug!("Using hypershift collector");
+            Box::new(HypershiftCollector {})
+        }
+        _ => {
+            debug!("Using default collector");
+            Box::new(DefaultCollector {
+                cluster_id: &cluster_info.cluster_id,
+                cluster_infra_name: &cluster_info.cluster_infra_name,
+            })
+        }
+    };
+    let mut lb_names = HashMap::new();
+    let mut paginator = elb_client.describe_load_balancers().into_paginator().send();
+    while let Some(res) = paginator.next().await {
+        let lb_out = match res {
+            Ok(success) => success,
+            Err(err) => return Err(Box::new(err)),
+        };
+        if let Some(lbs) = lb_out.load_balancer_descriptions {
+            for lb in lbs {
+                let lb_name = lb.load_balancer_name.as_ref().unwrap().clone();
+                lb_names.insert(lb_name, lb);
+            }
+        }
+    }
+    ensure_within_limit(
+        "classic load balancers",
+        lb_names.len(),
+        max_load_balancers,
+        "--max-load-balancers",
+    )?;
+    let names: Vec<String> = lb_names.keys().cloned().collect();
+    let outputs = in_batches(
+        names,
+        DESCRIBE_TAGS_BATCH_SIZE,
+        DESCRIBE_TAGS_CONCURRENCY,
+        |batch| {
+            debug!("Retrieving tags for loadbalancers: {:?}", batch);
+            elb_client
+                .describe_tags()
+                .set_load_balancer_names(Some(batch))
+                .send()
+        },
+    )
+    .await?;
+    for td in outputs
+        .into_iter()
+        .flat_map(|o| o.tag_descriptions.unwrap_or_default())
+    {
+        let Some(lb_val) = td.load_balancer_name().and_then(|n| lb_names.get(n)) else {
+            continue;
+        };
+        let tags: Vec<crate::gatherer::aws::shared_types::Tag> =
+            td.tags().iter().map(|t| t.clone().into()).collect();
+        if tags.iter().any(|t| collector.match_tag(t.clone())) {
+            debug!(
+                "Tag matched for loadbalancer: {}",
+                lb_val.load_balancer_name().unwrap_or_default()
+            );
+            cluster_lbs.push(AWSLoadBalancer::ClassicLoadBalancer((lb_val.clone(), tags)))
+        }
+    }
+    return Ok(cluster_lbs);
+}
+
+/// Retrieves the attributes of the classic load balancers, using the attribute
+/// keys of modern load balancers.
+pub async fn get_classic_load_balancer_attributes(
+    elb_client: &ELBClient,
+    lbs: &[AWSLoadBalancer],
+) -> Result<Vec<AWSLoadBalancerAttributes>, aws_sdk_elasticloadbalancing::Error> {
+    let mut attributes = vec![];
+    for lb in lbs.iter() {
+        let AWSLoadBalancer::ClassicLoadBalancer((lb, _)) = lb else {
+            continue;
+        };
+        let Some(name) = lb.load_balancer_name() else {
+            continue;
+        };
+        debug!("Retrieving attributes for classic loadbalancer: {}", name);
+        let output = match elb_client
+            .describe_load_balancer_attributes()
+            .load_balancer_name(name)
+            .send()
+            .await
+        {
+            Ok(success) => success,
+            Err(err) => return Err(aws_sdk_elasticloadbalancing::Error::from(err)),
+        };
+        let mut lb_attributes = BTreeMap::new();
+        if let Some(a) = output.load_balancer_attributes {
+            if let Some(cz) = a.cross_zone_load_balancing {
+                lb_attributes.insert(
+                    "load_balancing.cross_zone.enabled".to_string(),
+                    cz.enabled.to_string(),
+                );
+            }
+            if let Some(cs) = a.connection_settings {
+                lb_attributes.insert(
+                    "idle_timeout.timeout_seconds".to_string(),
+                    cs.idle_timeout.to_string(),
+                );
+            }
+            if let Some(al) = a.access_log {
+                lb_attributes.insert("access_logs.s3.enabled".to_string(), al.enabled.to_string());
+                if let Some(bucket) = al.s3_bucket_name {
+                    lb_attributes.insert("access_logs.s3.bucket".to_string(), bucket);
+                }
+                if let Some(prefix) = al.s3_bucket_prefix {
+                    lb_attributes.insert("access_logs.s3.prefix".to_string(), prefix);
+                }
+            }
+            if let Some(cd) = a.connection_draining {
+                lb_attributes.insert(
+                    "connection_draining.enabled".to_string(),
+                    cd.enabled.to_string(),
+                );
+            }
+        }
+        attributes.push(AWSLoadBalancerAttributes {
+            load_balancer: name.to_string(),
+            attributes: lb_attributes,
+        });
+    }
+    Ok(attributes)
+}
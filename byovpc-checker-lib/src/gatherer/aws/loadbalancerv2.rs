@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancer;
+use aws_sdk_elasticloadbalancingv2::Client as ELBv2Client;
+use log::debug;
+use std::error::Error;
+
+use crate::gatherer::aws::shared_types::{Collector, DefaultCollector, HypershiftCollector};
+use crate::gatherer::aws::{
+    ensure_within_limit, in_batches, DESCRIBE_TAGS_BATCH_SIZE, DESCRIBE_TAGS_CONCURRENCY,
+};
+use crate::gatherer::Gatherer;
+use crate::types::MinimalClusterInfo;
+
+use super::shared_types::{AWSLoadBalancer, AWSLoadBalancerAttributes, AWSTargetGroup};
+
+pub struct LoadBalancerGatherer<'a> {
+    pub client: &'a ELBv2Client,
+    pub cluster_info: &'a MinimalClusterInfo,
+    pub max_load_balancers: usize,
+}
+
+#[async_trait]
+impl<'a> Gatherer for LoadBalancerGatherer<'a> {
+    type Resource = AWSLoadBalancer;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving LoadBalancers");
+        let mut lb_arns = HashMap::new();
+        let collector: Box<dyn Collector + Send> = match self.cluster_info.cluster_type {
+            crate::types::ClusterType::Hypershift => {
+                debug!("Using hypershift collector");
+                Box::new(HypershiftCollector {})
+            }
+            _ => {
+                debug!("Using default collector");
+                Box::new(DefaultCollector {
+                    cluster_id: &self.cluster_info.cluster_id,
+                    cluster_infra_name: &self.cluster_info.cluster_infra_name,
+                })
+            }
+        };
+        let mut cluster_lbs = vec![];
+        let mut paginator = self
+            .client
+            .describe_load_balancers()
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            let lb_out = match res {
+                Ok(success) => success,
+                Err(err) => return Err(Box::new(err)),
+            };
+            if let Some(lbs) = lb_out.load_balancers {
+                for lb in lbs {
+                    let arn = lb.load_balancer_arn.as_ref().unwrap().clone();
+                    lb_arns.insert(arn, lb);
+                }
+            }
+        }
+        ensure_within_limit(
+            "load balancers",
+            lb_arns.len(),
+            self.max_load_balancers,
+            "--max-load-balancers",
+        )?;
+        let arns: Vec<String> = lb_arns.keys().cloned().collect();
+        let outputs = in_batches(
+            arns,
+            DESCRIBE_TAGS_BATCH_SIZE,
+            DESCRIBE_TAGS_CONCURRENCY,
+            |batch| {
+                debug!("Retrieving tags for loadbalancers: {:?}", batch);
+                self.client
+                    .describe_tags()
+                    .set_resource_arns(Some(batch))
+                    .send()
+            },
+        )
+        .await?;
+        for td in outputs
+            .into_iter()
+            .flat_map(|o| o.tag_descriptions.unwrap_or_default())
+        {
+            let Some(lb_val) = td.resource_arn().and_then(|arn| lb_arns.get(arn)) else {
+                continue;
+            };
+            let tags: Vec<crate::gatherer::aws::shared_types::Tag> =
+                td.tags().iter().map(|t| t.clone().into()).collect();
+            if tags.iter().any(|t| collector.match_tag(t.clone())) {
+                debug!(
+                    "Tag matched for loadbalancer: {}",
+                    lb_val.load_balancer_arn().unwrap_or_default()
+                );
+                cluster_lbs.push(AWSLoadBalancer::ModernLoadBalancer((lb_val.clone(), tags)))
+            }
+        }
+        Ok(cluster_lbs)
+    }
+}
+
+/// Gathers the target groups of the modern load balancers together with the
+/// health of their registered targets.
+pub struct TargetGroupGatherer<'a> {
+    pub client: &'a ELBv2Client,
+    pub loadbalancers: &'a Vec<AWSLoadBalancer>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for TargetGroupGatherer<'a> {
+    type Resource = AWSTargetGroup;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let mut target_groups = vec![];
+        for lb in self.loadbalancers.iter() {
+            let AWSLoadBalancer::ModernLoadBalancer((lb, _)) = lb else {
+                continue;
+            };
+            let Some(arn) = lb.load_balancer_arn() else {
+                continue;
+            };
+            debug!("Retrieving target groups for: {}", arn);
+            let mut paginator = self
+                .client
+                .describe_target_groups()
+                .load_balancer_arn(arn)
+                .into_paginator()
+                .send();
+            while let Some(res) = paginator.next().await {
+                let tgs = match res {
+                    Ok(output) => output.target_groups.unwrap_or_default(),
+                    Err(err) => return Err(Box::new(err)),
+                };
+                for tg in tgs {
+                    let health = match self
+                        .client
+                        .describe_target_health()
+                        .set_target_group_arn(tg.target_group_arn.clone())
+                        .send()
+                        .await
+                    {
+                        Ok(output) => output.target_health_descriptions.unwrap_or_default(),
+                        Err(err) => return Err(Box::new(err)),
+                    };
+                    target_groups.push(AWSTargetGroup {
+                        target_group: tg,
+                        targets: health,
+                    });
+                }
+            }
+        }
+        Ok(target_groups)
+    }
+}
+
+/// Gathers the attributes of the modern load balancers.
+pub struct LoadBalancerAttributeGatherer<'a> {
+    pub client: &'a ELBv2Client,
+    pub loadbalancers: &'a Vec<AWSLoadBalancer>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for LoadBalancerAttributeGatherer<'a> {
+    type Resource = AWSLoadBalancerAttributes;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let mut attributes = vec![];
+        for lb in self.loadbalancers.iter() {
+            let AWSLoadBalancer::ModernLoadBalancer((lb, _)) = lb else {
+                continue;
+            };
+            debug!("Retrieving attributes for: {:?}", lb.load_balancer_arn());
+            match self
+                .client
+                .describe_load_balancer_attributes()
+                .set_load_balancer_arn(lb.load_balancer_arn.clone())
+                .send()
+                .await
+            {
+                Ok(output) => attributes.push(AWSLoadBalancerAttributes {
+                    load_balancer: lb.load_balancer_arn.clone().unwrap_or_default(),
+                    attributes: output
+                        .attributes()
+                        .iter()
+                        .filter_map(|a| Some((a.key.clone()?, a.value.clone()?)))
+                        .collect(),
+                }),
+                Err(err) => return Err(Box::new(err)),
+            }
+        }
+        Ok(attributes)
+    }
+}
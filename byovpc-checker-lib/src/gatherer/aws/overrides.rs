@@ -0,0 +1,239 @@
+//! Overrides replace the output of single gatherers with data read from a file,
+//! while everything else is still gathered live. This allows running the
+//! checks when a single API is blocked (e.g. by a service control policy), but
+//! the customer can export that data themselves.
+//!
+//! An override is given as `<data set>=<file>`, e.g.
+//! `--override subnets=./subnets.json`. The file contains a JSON array in the
+//! format the data set has in a snapshot - e.g. the `subnets` of a snapshot
+//! written with `--snapshot`.
+
+use std::{error::Error, fs::File, io::BufReader, path::PathBuf, str::FromStr};
+
+use aws_sdk_ec2::types::{
+    Address, AvailabilityZone, DhcpOptions, EgressOnlyInternetGateway, FlowLog, InternetGateway,
+    NatGateway, NetworkAcl, NetworkInterface, RouteTable, Subnet, Vpc, VpcEndpoint,
+};
+use serde::de::DeserializeOwned;
+
+use super::{
+    health::HealthEvent,
+    iam::InstanceProfile,
+    quotas::ServiceQuota,
+    shared_types::{
+        AWSLoadBalancer, AWSLoadBalancerAttributes, AWSTargetGroup, AccessLogBucket,
+        HostedZoneWithRecords, ResolverQueryLogEntry, VpcAttributes,
+    },
+    snapshot::{
+        AddressSnapshot, AvailabilityZoneSnapshot, DhcpOptionsSnapshot,
+        EgressOnlyInternetGatewaySnapshot, FlowLogSnapshot, HostedZoneSnapshot,
+        InternetGatewaySnapshot, LoadBalancerSnapshot, NatGatewaySnapshot, NetworkAclSnapshot,
+        NetworkInterfaceSnapshot, RouteTableSnapshot, SubnetSnapshot, TargetGroupSnapshot,
+        VpcEndpointSnapshot, VpcSnapshot,
+    },
+};
+use crate::types::{DataSet, InvariantError};
+
+/// Replaces the data set with the content of the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Override {
+    pub data_set: DataSet,
+    pub path: PathBuf,
+}
+
+impl FromStr for Override {
+    type Err = InvariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, path)) = s.split_once('=') else {
+            return Err(InvariantError {
+                msg: format!("Override {} is not of the form <data set>=<file>", s),
+            });
+        };
+        let data_set = serde_json::from_value(serde_json::Value::String(name.to_string()))
+            .map_err(|_| InvariantError {
+                msg: format!(
+                    "Unknown data set {} - expected one of: {}",
+                    name,
+                    DataSet::ALL
+                        .iter()
+                        .map(|d| serde_json::to_value(d)
+                            .unwrap()
+                            .as_str()
+                            .unwrap()
+                            .to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            })?;
+        Ok(Override {
+            data_set,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
+/// Types that can be read from an override file - the file contains the
+/// serializable mirror of the type.
+pub trait Overridable: Sized {
+    type Model: DeserializeOwned + Into<Self>;
+
+    /// Rejects entries lacking fields the gathering relies on - the API
+    /// always returns them, but a hand-written file may not.
+    fn validate(_model: &Self::Model) -> Result<(), InvariantError> {
+        Ok(())
+    }
+}
+
+impl Overridable for Subnet {
+    type Model = SubnetSnapshot;
+
+    fn validate(model: &SubnetSnapshot) -> Result<(), InvariantError> {
+        if model.subnet_id.is_none() {
+            return Err(InvariantError {
+                msg: String::from("Subnet override entry without subnet_id"),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Overridable for RouteTable {
+    type Model = RouteTableSnapshot;
+}
+
+impl Overridable for NetworkAcl {
+    type Model = NetworkAclSnapshot;
+}
+
+impl Overridable for NatGateway {
+    type Model = NatGatewaySnapshot;
+}
+
+impl Overridable for Address {
+    type Model = AddressSnapshot;
+}
+
+impl Overridable for VpcEndpoint {
+    type Model = VpcEndpointSnapshot;
+}
+
+impl Overridable for NetworkInterface {
+    type Model = NetworkInterfaceSnapshot;
+}
+
+impl Overridable for AWSLoadBalancer {
+    type Model = LoadBalancerSnapshot;
+}
+
+impl Overridable for AWSLoadBalancerAttributes {
+    type Model = AWSLoadBalancerAttributes;
+}
+
+impl Overridable for AWSTargetGroup {
+    type Model = TargetGroupSnapshot;
+}
+
+impl Overridable for HostedZoneWithRecords {
+    type Model = HostedZoneSnapshot;
+}
+
+impl Overridable for AvailabilityZone {
+    type Model = AvailabilityZoneSnapshot;
+}
+
+impl Overridable for ResolverQueryLogEntry {
+    type Model = ResolverQueryLogEntry;
+}
+
+impl Overridable for Vpc {
+    type Model = VpcSnapshot;
+}
+
+impl Overridable for DhcpOptions {
+    type Model = DhcpOptionsSnapshot;
+}
+
+impl Overridable for VpcAttributes {
+    type Model = VpcAttributes;
+}
+
+impl Overridable for AccessLogBucket {
+    type Model = AccessLogBucket;
+}
+
+impl Overridable for InternetGateway {
+    type Model = InternetGatewaySnapshot;
+}
+
+impl Overridable for EgressOnlyInternetGateway {
+    type Model = EgressOnlyInternetGatewaySnapshot;
+}
+
+impl Overridable for HealthEvent {
+    type Model = HealthEvent;
+}
+
+impl Overridable for InstanceProfile {
+    type Model = InstanceProfile;
+}
+
+impl Overridable for ServiceQuota {
+    type Model = ServiceQuota;
+}
+
+impl Overridable for FlowLog {
+    type Model = FlowLogSnapshot;
+}
+
+impl Override {
+    /// Reads the resources of the override file.
+    pub fn load<T: Overridable>(&self) -> Result<Vec<T>, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        let models: Vec<T::Model> = serde_json::from_reader(reader)?;
+        for model in &models {
+            T::validate(model)?;
+        }
+        Ok(models.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override() {
+        assert_eq!(
+            "route_tables=./rt.json".parse::<Override>().unwrap(),
+            Override {
+                data_set: DataSet::RouteTables,
+                path: PathBuf::from("./rt.json"),
+            }
+        );
+        assert!("routes=./rt.json".parse::<Override>().is_err());
+        assert!("./rt.json".parse::<Override>().is_err());
+    }
+
+    #[test]
+    fn test_load_subnet_without_id() {
+        let path = std::env::temp_dir().join(format!(
+            "byovpc-override-subnets-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            r#"[{"vpc_id": "vpc-1", "availability_zone": null, "availability_zone_id": null,
+                "cidr_block": null, "available_ip_address_count": null,
+                "map_public_ip_on_launch": null, "state": null, "tags": []}]"#,
+        )
+        .unwrap();
+        let o = Override {
+            data_set: DataSet::Subnets,
+            path: path.clone(),
+        };
+        let loaded = o.load::<Subnet>();
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+    }
+}
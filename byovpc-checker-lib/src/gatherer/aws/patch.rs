@@ -0,0 +1,318 @@
+//! Patches describe planned changes to the resources of a cluster, e.g. adding
+//! a missing tag to a subnet or deleting a NAT gateway. They are applied to a
+//! [`Snapshot`], so the checks can predict the impact of a change before it is
+//! made in AWS.
+//!
+//! A patch is a JSON array of changes:
+//!
+//! ```json
+//! [
+//!   {"op": "add_tag", "resource": "subnet-1", "key": "kubernetes.io/role/elb", "value": "1"},
+//!   {"op": "remove_tag", "resource": "subnet-2", "key": "kubernetes.io/role/elb"},
+//!   {"op": "delete", "resource": "nat-1"}
+//! ]
+//! ```
+
+use std::{error::Error, fmt::Display, fs::File, io::BufReader, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    shared_types::Tag,
+    snapshot::{LoadBalancerSnapshot, Snapshot},
+};
+use crate::types::InvariantError;
+
+/// A single change to a resource. Resources are identified by their ID, load
+/// balancers by their name or ARN.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Change {
+    /// Adds a tag to a resource or replaces the value of an existing tag.
+    AddTag {
+        resource: String,
+        key: String,
+        value: String,
+    },
+    RemoveTag {
+        resource: String,
+        key: String,
+    },
+    Delete {
+        resource: String,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::AddTag {
+                resource,
+                key,
+                value,
+            } => write!(f, "add tag {}={} to {}", key, value, resource),
+            Change::RemoveTag { resource, key } => {
+                write!(f, "remove tag {} from {}", key, resource)
+            }
+            Change::Delete { resource } => write!(f, "delete {}", resource),
+        }
+    }
+}
+
+/// Reads the changes of a patch file.
+pub fn read_patch(path: &Path) -> Result<Vec<Change>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+impl LoadBalancerSnapshot {
+    fn identified_by(&self, resource: &str) -> bool {
+        match self {
+            LoadBalancerSnapshot::Classic {
+                load_balancer_name, ..
+            } => load_balancer_name.as_deref() == Some(resource),
+            LoadBalancerSnapshot::Modern {
+                load_balancer_arn,
+                load_balancer_name,
+                ..
+            } => {
+                load_balancer_arn.as_deref() == Some(resource)
+                    || load_balancer_name.as_deref() == Some(resource)
+            }
+        }
+    }
+
+    fn tags_mut(&mut self) -> &mut Vec<Tag> {
+        match self {
+            LoadBalancerSnapshot::Classic { tags, .. } => tags,
+            LoadBalancerSnapshot::Modern { tags, .. } => tags,
+        }
+    }
+}
+
+/// Removes all items matching the predicate and returns how many were removed.
+fn remove<T>(items: &mut Vec<T>, matches: impl Fn(&T) -> bool) -> usize {
+    let before = items.len();
+    items.retain(|i| !matches(i));
+    before - items.len()
+}
+
+impl Snapshot {
+    /// The tags of every copy of the resource - security groups are stored
+    /// once per instance they are attached to.
+    fn tags_mut(&mut self, resource: &str) -> Vec<&mut Vec<Tag>> {
+        let id = Some(resource);
+        let mut tags = vec![];
+        tags.extend(
+            self.subnets
+                .iter_mut()
+                .filter(|s| s.subnet_id.as_deref() == id)
+                .map(|s| &mut s.tags),
+        );
+        tags.extend(
+            self.routetables
+                .iter_mut()
+                .filter(|rt| rt.route_table_id.as_deref() == id)
+                .map(|rt| &mut rt.tags),
+        );
+        tags.extend(
+            self.load_balancers
+                .iter_mut()
+                .filter(|lb| lb.identified_by(resource))
+                .map(|lb| lb.tags_mut()),
+        );
+        tags.extend(
+            self.load_balancer_enis
+                .iter_mut()
+                .filter(|lb_eni| lb_eni.eni.network_interface_id.as_deref() == id)
+                .map(|lb_eni| &mut lb_eni.eni.tags),
+        );
+        tags.extend(
+            self.subnet_network_interfaces
+                .iter_mut()
+                .filter(|eni| eni.network_interface_id.as_deref() == id)
+                .map(|eni| &mut eni.tags),
+        );
+        for i in self.instances.iter_mut() {
+            if i.instance_id.as_deref() == id {
+                tags.push(&mut i.tags);
+            }
+            tags.extend(
+                i.security_groups
+                    .iter_mut()
+                    .filter(|sg| sg.group_id.as_deref() == id)
+                    .map(|sg| &mut sg.tags),
+            );
+        }
+        tags.extend(
+            self.nat_gateways
+                .iter_mut()
+                .filter(|n| n.nat_gateway_id.as_deref() == id)
+                .map(|n| &mut n.tags),
+        );
+        tags.extend(
+            self.elastic_ips
+                .iter_mut()
+                .filter(|a| a.allocation_id.as_deref() == id)
+                .map(|a| &mut a.tags),
+        );
+        tags
+    }
+
+    /// Deletes the resource and returns how many copies were removed.
+    fn delete(&mut self, resource: &str) -> usize {
+        let id = Some(resource);
+        let mut removed = 0;
+        removed += remove(&mut self.subnets, |s| s.subnet_id.as_deref() == id);
+        removed += remove(&mut self.routetables, |rt| {
+            rt.route_table_id.as_deref() == id
+        });
+        removed += remove(&mut self.load_balancers, |lb| lb.identified_by(resource));
+        removed += remove(&mut self.load_balancer_enis, |lb_eni| {
+            lb_eni.eni.network_interface_id.as_deref() == id
+        });
+        removed += remove(&mut self.subnet_network_interfaces, |eni| {
+            eni.network_interface_id.as_deref() == id
+        });
+        removed += remove(&mut self.instances, |i| i.instance_id.as_deref() == id);
+        for i in self.instances.iter_mut() {
+            removed += remove(&mut i.security_groups, |sg| sg.group_id.as_deref() == id);
+        }
+        removed += remove(&mut self.hosted_zones, |hz| {
+            hz.id == resource || hz.id.trim_start_matches("/hostedzone/") == resource
+        });
+        removed += remove(&mut self.nat_gateways, |n| {
+            n.nat_gateway_id.as_deref() == id
+        });
+        removed += remove(&mut self.elastic_ips, |a| a.allocation_id.as_deref() == id);
+        removed += remove(&mut self.vpc_endpoints, |e| {
+            e.vpc_endpoint_id.as_deref() == id
+        });
+        removed += remove(&mut self.network_acls, |acl| {
+            acl.network_acl_id.as_deref() == id
+        });
+        removed
+    }
+
+    /// Applies the change, failing if the resource it refers to does not
+    /// exist - a typo in a patch must not silently simulate nothing.
+    pub fn apply(&mut self, change: &Change) -> Result<(), Box<dyn Error>> {
+        let resource = match change {
+            Change::AddTag { resource, .. }
+            | Change::RemoveTag { resource, .. }
+            | Change::Delete { resource } => resource,
+        };
+        let found = match change {
+            Change::AddTag { key, value, .. } => {
+                let tags = self.tags_mut(resource);
+                let found = !tags.is_empty();
+                for tags in tags {
+                    tags.retain(|t| t.key.as_ref() != Some(key));
+                    tags.push(Tag {
+                        key: Some(key.clone()),
+                        value: Some(value.clone()),
+                    });
+                }
+                found
+            }
+            Change::RemoveTag { key, .. } => {
+                let tags = self.tags_mut(resource);
+                let found = !tags.is_empty();
+                for tags in tags {
+                    tags.retain(|t| t.key.as_ref() != Some(key));
+                }
+                found
+            }
+            Change::Delete { .. } => self.delete(resource) > 0,
+        };
+        if !found {
+            return Err(Box::new(InvariantError {
+                msg: format!("Can not {}: resource not found", change),
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::{NatGateway, Subnet};
+
+    use crate::{gatherer::aws::AWSClusterData, types::MinimalClusterInfoBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_apply_changes() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        let data = AWSClusterData {
+            subnets: vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .tags(
+                    aws_sdk_ec2::types::Tag::builder()
+                        .key("kubernetes.io/role/elb")
+                        .value("1")
+                        .build(),
+                )
+                .build()],
+            routetables: vec![],
+            load_balancers: vec![],
+            load_balancer_enis: vec![],
+            instances: vec![],
+            hosted_zones: vec![],
+            availability_zones: vec![],
+            instance_type_offerings: vec![],
+            resolver_query_log_configs: vec![],
+            resolver_query_logs: vec![],
+            nat_gateways: vec![NatGateway::builder().nat_gateway_id("nat-1").build()],
+            elastic_ips: vec![],
+            target_groups: vec![],
+            load_balancer_attributes: vec![],
+            vpc_endpoints: vec![],
+            network_acls: vec![],
+            subnet_network_interfaces: vec![],
+            denials: vec![],
+            vpcs: vec![],
+            dhcp_options: vec![],
+            vpc_attributes: vec![],
+            access_log_buckets: vec![],
+            health_events: vec![],
+            internet_gateways: vec![],
+            egress_only_internet_gateways: vec![],
+            instance_profiles: vec![],
+            service_quotas: vec![],
+            flow_logs: vec![],
+            account_id: None,
+            failed: vec![],
+        };
+        let mut snapshot = Snapshot::new(&cluster_info, &data);
+        let changes: Vec<Change> = serde_json::from_str(
+            r#"[
+                {"op": "remove_tag", "resource": "subnet-1", "key": "kubernetes.io/role/elb"},
+                {"op": "add_tag", "resource": "subnet-1", "key": "kubernetes.io/role/internal-elb", "value": "1"},
+                {"op": "delete", "resource": "nat-1"}
+            ]"#,
+        )
+        .unwrap();
+        for change in changes.iter() {
+            snapshot.apply(change).unwrap();
+        }
+        assert!(snapshot
+            .apply(&Change::Delete {
+                resource: "nat-1".to_string()
+            })
+            .is_err());
+        let (_, data) = snapshot.into_parts();
+        assert!(data.nat_gateways.is_empty());
+        assert_eq!(
+            data.subnets[0].tags(),
+            [aws_sdk_ec2::types::Tag::builder()
+                .key("kubernetes.io/role/internal-elb")
+                .value("1")
+                .build()]
+        );
+    }
+}
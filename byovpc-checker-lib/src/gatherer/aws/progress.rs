@@ -0,0 +1,116 @@
+//! Reports which data sets are being gathered, as gathering can take minutes
+//! in large accounts.
+//!
+//! Progress is drawn as a single status line on stderr that is redrawn
+//! whenever a gatherer starts or finishes, and removed once gathering is done.
+//! It is only shown if enabled - when the results go to a terminal in a
+//! human readable format and no log output would interleave with it.
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use itertools::Itertools;
+
+/// The gatherers started while progress is enabled - `None` if it is disabled.
+static PROGRESS: Mutex<Option<Progress>> = Mutex::new(None);
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Progress {
+    /// What is being gathered right now, in the order it started.
+    running: Vec<String>,
+    /// How many gatherers finished.
+    finished: usize,
+    /// The last gatherer that finished with the number of items it returned.
+    last: Option<(String, usize)>,
+}
+
+impl Progress {
+    fn start(&mut self, what: &str) {
+        self.running.push(what.to_string());
+    }
+
+    fn finish(&mut self, what: &str, count: usize) {
+        if let Some(position) = self.running.iter().position(|r| r == what) {
+            self.running.remove(position);
+        }
+        self.finished += 1;
+        self.last = Some((what.to_string(), count));
+    }
+
+    /// The status line, e.g.
+    /// `Gathering [4/6] routetables, hosted zones (subnets: 12)`.
+    fn line(&self) -> String {
+        let mut line = format!(
+            "Gathering [{}/{}] {}",
+            self.finished,
+            self.finished + self.running.len(),
+            self.running.iter().join(", ")
+        );
+        if let Some((what, count)) = &self.last {
+            line.push_str(&format!(" ({}: {})", what, count));
+        }
+        line
+    }
+}
+
+/// Redraws the status line - `\r` and `ESC [2K` replace the previous line.
+fn draw(line: &str) {
+    let mut stderr = std::io::stderr().lock();
+    let _ = write!(stderr, "\r\x1b[2K{}", line);
+    let _ = stderr.flush();
+}
+
+/// Shows the progress of the gatherers from now on.
+pub fn enable() {
+    *PROGRESS.lock().unwrap() = Some(Progress::default());
+}
+
+/// Records that a gatherer started.
+pub fn start(what: &str) {
+    if let Some(progress) = PROGRESS.lock().unwrap().as_mut() {
+        progress.start(what);
+        draw(&progress.line());
+    }
+}
+
+/// Records that a gatherer finished with the number of items it returned.
+pub fn finish(what: &str, count: usize) {
+    if let Some(progress) = PROGRESS.lock().unwrap().as_mut() {
+        progress.finish(what, count);
+        draw(&progress.line());
+    }
+}
+
+/// Removes the status line, e.g. before a prompt or once gathering is done.
+/// Progress stays enabled and is redrawn by the next gatherer.
+pub fn clear() {
+    if PROGRESS.lock().unwrap().is_some() {
+        draw("");
+    }
+}
+
+/// Removes the status line and stops showing progress.
+pub fn disable() {
+    if PROGRESS.lock().unwrap().take().is_some() {
+        draw("");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line() {
+        let mut progress = Progress::default();
+        progress.start("subnets");
+        progress.start("load balancers");
+        assert_eq!(progress.line(), "Gathering [0/2] subnets, load balancers");
+        progress.finish("subnets", 12);
+        progress.start("routetables");
+        assert_eq!(
+            progress.line(),
+            "Gathering [1/3] load balancers, routetables (subnets: 12)"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Gathers the service quotas that commonly block installing into an existing
+//! VPC, so the checks can compare them to the usage.
+//!
+//! There is no SDK crate for the Service Quotas API in use, so the requests
+//! are signed with SigV4 and sent as plain JSON requests, like the ones of the
+//! Health API. Quotas that were never adjusted are not returned by
+//! `GetServiceQuota`, their AWS default is used then.
+
+use std::error::Error;
+use std::fmt::Display;
+
+use async_trait::async_trait;
+use aws_config::SdkConfig;
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::gatherer::aws::{send_signed, SignedApi};
+use crate::gatherer::Gatherer;
+use crate::types::InvariantError;
+
+const GET_SERVICE_QUOTA: &str = "ServiceQuotasV20190624.GetServiceQuota";
+const GET_DEFAULT_SERVICE_QUOTA: &str = "ServiceQuotasV20190624.GetAWSDefaultServiceQuota";
+
+/// The quotas that are verified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    ElasticIps,
+    NatGatewaysPerZone,
+    NetworkInterfaces,
+    RulesPerSecurityGroup,
+    ApplicationLoadBalancers,
+    NetworkLoadBalancers,
+    ClassicLoadBalancers,
+}
+
+impl QuotaKind {
+    pub const ALL: [QuotaKind; 7] = [
+        QuotaKind::ElasticIps,
+        QuotaKind::NatGatewaysPerZone,
+        QuotaKind::NetworkInterfaces,
+        QuotaKind::RulesPerSecurityGroup,
+        QuotaKind::ApplicationLoadBalancers,
+        QuotaKind::NetworkLoadBalancers,
+        QuotaKind::ClassicLoadBalancers,
+    ];
+
+    /// The service code and quota code of the quota in the Service Quotas
+    /// API.
+    pub fn codes(&self) -> (&'static str, &'static str) {
+        match self {
+            QuotaKind::ElasticIps => ("ec2", "L-0263D0A3"),
+            QuotaKind::NatGatewaysPerZone => ("vpc", "L-FE5A380F"),
+            QuotaKind::NetworkInterfaces => ("vpc", "L-DF5E4CA3"),
+            QuotaKind::RulesPerSecurityGroup => ("vpc", "L-0EA8095F"),
+            QuotaKind::ApplicationLoadBalancers => ("elasticloadbalancing", "L-53DA6B97"),
+            QuotaKind::NetworkLoadBalancers => ("elasticloadbalancing", "L-69A177A2"),
+            QuotaKind::ClassicLoadBalancers => ("elasticloadbalancing", "L-E9E9831D"),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuotaKind::ElasticIps => "elastic IPs per region",
+            QuotaKind::NatGatewaysPerZone => "NAT gateways per availability zone",
+            QuotaKind::NetworkInterfaces => "network interfaces per region",
+            QuotaKind::RulesPerSecurityGroup => "inbound or outbound rules per security group",
+            QuotaKind::ApplicationLoadBalancers => "application load balancers per region",
+            QuotaKind::NetworkLoadBalancers => "network load balancers per region",
+            QuotaKind::ClassicLoadBalancers => "classic load balancers per region",
+        }
+    }
+}
+
+impl Display for QuotaKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The value of a quota in the region.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServiceQuota {
+    pub kind: QuotaKind,
+    pub value: f64,
+}
+
+/// Gathers the quotas of `QuotaKind::ALL` in a region.
+pub struct ServiceQuotaGatherer<'a> {
+    pub aws_config: &'a SdkConfig,
+    pub client: &'a reqwest::Client,
+    pub region: &'a str,
+}
+
+impl<'a> ServiceQuotaGatherer<'a> {
+    /// Sends a request to the Service Quotas API, signed with the credentials
+    /// of the AWS configuration.
+    async fn send(&self, target: &str, body: &Value) -> Result<reqwest::Response, Box<dyn Error>> {
+        let endpoint = format!("https://servicequotas.{}.amazonaws.com/", self.region);
+        let api = SignedApi {
+            service: "servicequotas",
+            region: self.region,
+            endpoint: &endpoint,
+        };
+        let headers = [
+            ("content-type", "application/x-amz-json-1.1"),
+            ("x-amz-target", target),
+        ];
+        send_signed(
+            self.aws_config,
+            self.client,
+            &api,
+            &headers,
+            serde_json::to_vec(body)?,
+        )
+        .await
+    }
+
+    /// The applied value of the quota, or its AWS default if it was never
+    /// adjusted.
+    async fn quota(&self, kind: QuotaKind) -> Result<f64, Box<dyn Error>> {
+        let (service_code, quota_code) = kind.codes();
+        let body = json!({"ServiceCode": service_code, "QuotaCode": quota_code});
+        let mut response = self.send(GET_SERVICE_QUOTA, &body).await?;
+        if response.status() == reqwest::StatusCode::BAD_REQUEST {
+            debug!("Quota {} was never adjusted, using its default", quota_code);
+            response = self.send(GET_DEFAULT_SERVICE_QUOTA, &body).await?;
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            error!("Failed to fetch quota {}: {} {}", quota_code, status, text);
+            return Err(Box::new(InvariantError {
+                msg: format!("Service Quotas API returned {}: {}", status, text),
+            }));
+        }
+        let output: Value = response.json().await?;
+        output["Quota"]["Value"].as_f64().ok_or_else(|| {
+            Box::new(InvariantError {
+                msg: format!("Quota {} has no value", quota_code),
+            }) as Box<dyn Error>
+        })
+    }
+}
+
+#[async_trait]
+impl<'a> Gatherer for ServiceQuotaGatherer<'a> {
+    type Resource = ServiceQuota;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        debug!("Retrieving service quotas for {}", self.region);
+        let mut quotas = vec![];
+        for kind in QuotaKind::ALL {
+            quotas.push(ServiceQuota {
+                kind,
+                value: self.quota(kind).await?,
+            });
+        }
+        Ok(quotas)
+    }
+}
@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use aws_sdk_cloudwatchlogs::Client as LogsClient;
+use aws_sdk_route53resolver::{
+    types::{Filter, ResolverQueryLogConfig},
+    Client as ResolverClient,
+};
+use log::{debug, error, info};
+
+use crate::gatherer::Gatherer;
+
+use super::shared_types::ResolverQueryLogEntry;
+
+/// How far back query logs are inspected.
+pub const QUERY_LOG_WINDOW: Duration = Duration::from_secs(60 * 60);
+/// Upper bound of failed queries that are retrieved from the logs.
+pub const MAX_QUERY_LOG_EVENTS: usize = 1000;
+
+/// Gathers the Route53 Resolver query log configurations associated with the
+/// VPCs.
+pub struct ResolverQueryLogConfigGatherer<'a> {
+    pub client: &'a ResolverClient,
+    pub vpc_ids: &'a Vec<String>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for ResolverQueryLogConfigGatherer<'a> {
+    type Resource = ResolverQueryLogConfig;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        if self.vpc_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        debug!(
+            "Retrieving resolver query log associations for VPCs: {}",
+            self.vpc_ids.join(",")
+        );
+        let mut config_ids = vec![];
+        let mut paginator = self
+            .client
+            .list_resolver_query_log_config_associations()
+            .filters(
+                Filter::builder()
+                    .name("ResourceId")
+                    .set_values(Some(self.vpc_ids.clone()))
+                    .build(),
+            )
+            .into_paginator()
+            .send();
+        while let Some(res) = paginator.next().await {
+            match res {
+                Ok(output) => {
+                    for association in output.resolver_query_log_config_associations() {
+                        if let Some(id) = association.resolver_query_log_config_id() {
+                            config_ids.push(id.to_string());
+                        }
+                    }
+                }
+                Err(err) => {
+                    error!("Failed to fetch resolver query log associations: {}", err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        let mut configs = vec![];
+        for id in config_ids {
+            match self
+                .client
+                .get_resolver_query_log_config()
+                .resolver_query_log_config_id(&id)
+                .send()
+                .await
+            {
+                Ok(output) => configs.extend(output.resolver_query_log_config),
+                Err(err) => {
+                    error!("Failed to fetch resolver query log config {}: {}", id, err);
+                    return Err(Box::new(err));
+                }
+            }
+        }
+        Ok(configs)
+    }
+}
+
+/// Returns the CloudWatch log group for a query log destination - other
+/// destinations (S3, Firehose) can not be queried.
+pub fn log_group_name(destination_arn: &str) -> Option<String> {
+    if !destination_arn.starts_with("arn:aws") || !destination_arn.contains(":logs:") {
+        return None;
+    }
+    destination_arn
+        .split_once(":log-group:")
+        .map(|(_, name)| name.trim_end_matches(":*").to_string())
+}
+
+/// Gathers failed (NXDOMAIN/SERVFAIL) queries from the CloudWatch log groups
+/// the query logs are delivered to.
+pub struct ResolverQueryLogGatherer<'a> {
+    pub client: &'a LogsClient,
+    pub configs: &'a Vec<ResolverQueryLogConfig>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for ResolverQueryLogGatherer<'a> {
+    type Resource = ResolverQueryLogEntry;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .saturating_sub(QUERY_LOG_WINDOW)
+            .as_millis() as i64;
+        let mut entries = vec![];
+        for config in self.configs {
+            let Some(log_group) = config.destination_arn().and_then(log_group_name) else {
+                info!(
+                    "Query log config {} is not delivered to CloudWatch Logs - skipping",
+                    config.id().unwrap_or_default()
+                );
+                continue;
+            };
+            debug!("Retrieving failed queries from log group: {}", log_group);
+            let mut paginator = self
+                .client
+                .filter_log_events()
+                .log_group_name(&log_group)
+                .start_time(start_time)
+                .filter_pattern(r#"{ ($.rcode = "NXDOMAIN") || ($.rcode = "SERVFAIL") }"#)
+                .into_paginator()
+                .send();
+            while let Some(res) = paginator.next().await {
+                match res {
+                    Ok(output) => {
+                        entries.extend(
+                            output
+                                .events()
+                                .iter()
+                                .filter_map(|e| e.message())
+                                .filter_map(ResolverQueryLogEntry::from_log_message),
+                        );
+                    }
+                    Err(err) => {
+                        error!("Failed to fetch query logs from {}: {}", log_group, err);
+                        return Err(Box::new(err));
+                    }
+                }
+                if entries.len() >= MAX_QUERY_LOG_EVENTS {
+                    info!(
+                        "Reached {} failed queries - not retrieving more",
+                        MAX_QUERY_LOG_EVENTS
+                    );
+                    entries.truncate(MAX_QUERY_LOG_EVENTS);
+                    return Ok(entries);
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,94 @@
+//! Retries gathering steps that failed because AWS throttled the requests or
+//! had a transient server error.
+//!
+//! The retries of the AWS SDK are disabled, so all gatherers - including the
+//! ones calling the APIs without the SDK - retry the same way: with an
+//! exponential backoff with full jitter, up to the configured number of
+//! retries. A retry repeats the whole step, e.g. all pages of a listing.
+
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// The delay before the first retry - it doubles with every retry.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(20);
+
+/// Error codes/messages of throttled requests.
+const THROTTLING_MARKERS: [&str; 7] = [
+    "Throttling",
+    "RequestLimitExceeded",
+    "TooManyRequestsException",
+    "RequestThrottled",
+    "SlowDown",
+    "PriorRequestNotComplete",
+    "Rate exceeded",
+];
+
+/// Error codes/messages of transient server errors.
+const TRANSIENT_MARKERS: [&str; 6] = [
+    "InternalError",
+    "InternalFailure",
+    "ServiceUnavailable",
+    "503 Service Unavailable",
+    "502 Bad Gateway",
+    "504 Gateway Timeout",
+];
+
+/// How often a failed step is retried - set from the gather options.
+static MAX_RETRIES: AtomicU32 = AtomicU32::new(DEFAULT_MAX_RETRIES);
+
+pub fn set_max_retries(max_retries: u32) {
+    MAX_RETRIES.store(max_retries, Ordering::Relaxed);
+}
+
+pub fn max_retries() -> u32 {
+    MAX_RETRIES.load(Ordering::Relaxed)
+}
+
+/// Returns true if the error was caused by throttling or a transient server
+/// error, so the request may succeed if it is sent again.
+pub fn is_retryable_error<E: Debug>(err: &E) -> bool {
+    let description = format!("{:?}", err);
+    THROTTLING_MARKERS
+        .iter()
+        .chain(TRANSIENT_MARKERS.iter())
+        .any(|m| description.contains(m))
+}
+
+/// The upper bound of the delay before the retry: the base delay doubled for
+/// every earlier retry, capped at the maximum delay.
+fn max_delay(retry: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry))
+        .min(MAX_DELAY)
+}
+
+/// The delay before the retry, chosen randomly up to its upper bound so
+/// concurrent gatherers do not retry at the same time.
+pub fn backoff(retry: u32) -> Duration {
+    let random = RandomState::new().build_hasher().finish();
+    max_delay(retry).mul_f64(random as f64 / u64::MAX as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert!(is_retryable_error(
+            &"ServiceError { code: Some(\"RequestLimitExceeded\") }"
+        ));
+        assert!(is_retryable_error(&"ThrottlingException: Rate exceeded"));
+        assert!(!is_retryable_error(&"UnauthorizedOperation"));
+        assert_eq!(max_delay(0), Duration::from_millis(500));
+        assert_eq!(max_delay(3), Duration::from_secs(4));
+        assert_eq!(max_delay(10), MAX_DELAY);
+        assert!(backoff(2) <= Duration::from_secs(2));
+    }
+}
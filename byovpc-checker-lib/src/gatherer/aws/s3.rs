@@ -0,0 +1,72 @@
+use async_trait::async_trait;
+use aws_sdk_s3::Client as S3Client;
+use itertools::Itertools;
+use log::debug;
+use std::error::Error;
+
+use crate::gatherer::Gatherer;
+
+use super::shared_types::{AWSLoadBalancerAttributes, AccessLogBucket};
+
+/// The attribute enabling the access logs of a load balancer.
+pub const ACCESS_LOGS_ENABLED_ATTRIBUTE: &str = "access_logs.s3.enabled";
+/// The attribute naming the bucket the access logs are written to.
+pub const ACCESS_LOGS_BUCKET_ATTRIBUTE: &str = "access_logs.s3.bucket";
+
+/// Gathers the S3 buckets the load balancers with enabled access logs write
+/// to.
+pub struct AccessLogBucketGatherer<'a> {
+    pub client: &'a S3Client,
+    pub load_balancer_attributes: &'a Vec<AWSLoadBalancerAttributes>,
+}
+
+#[async_trait]
+impl<'a> Gatherer for AccessLogBucketGatherer<'a> {
+    type Resource = AccessLogBucket;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let buckets = self
+            .load_balancer_attributes
+            .iter()
+            .filter(|a| {
+                a.attributes
+                    .get(ACCESS_LOGS_ENABLED_ATTRIBUTE)
+                    .is_some_and(|v| v == "true")
+            })
+            .filter_map(|a| a.attributes.get(ACCESS_LOGS_BUCKET_ATTRIBUTE))
+            .filter(|b| !b.is_empty())
+            .unique()
+            .cloned()
+            .collect::<Vec<String>>();
+        let mut access_log_buckets = vec![];
+        for bucket in buckets {
+            debug!("Retrieving access log bucket: {}", bucket);
+            // Buckets of other accounts or without permissions can not be
+            // read - that does not mean they do not exist.
+            let exists = match self.client.head_bucket().bucket(&bucket).send().await {
+                Ok(_) => Some(true),
+                Err(err) if err.as_service_error().is_some_and(|e| e.is_not_found()) => Some(false),
+                Err(err) => {
+                    debug!("Could not retrieve bucket {}: {}", bucket, err);
+                    None
+                }
+            };
+            let policy = match exists {
+                Some(false) => None,
+                _ => match self.client.get_bucket_policy().bucket(&bucket).send().await {
+                    Ok(output) => output.policy,
+                    Err(err) => {
+                        debug!("Could not retrieve policy of bucket {}: {}", bucket, err);
+                        None
+                    }
+                },
+            };
+            access_log_buckets.push(AccessLogBucket {
+                name: bucket,
+                exists,
+                policy,
+            });
+        }
+        Ok(access_log_buckets)
+    }
+}
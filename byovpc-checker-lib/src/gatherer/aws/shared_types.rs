@@ -0,0 +1,292 @@
+use aws_sdk_ec2::types::Instance;
+use aws_sdk_ec2::types::NetworkInterface;
+use aws_sdk_ec2::types::NetworkInterfaceType;
+use aws_sdk_ec2::types::SecurityGroup;
+use aws_sdk_elasticloadbalancing::types::LoadBalancerDescription;
+use aws_sdk_elasticloadbalancing::types::Tag as TagV1;
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancer;
+use aws_sdk_elasticloadbalancingv2::types::LoadBalancerTypeEnum;
+use aws_sdk_elasticloadbalancingv2::types::Tag as TagV2;
+use aws_sdk_elasticloadbalancingv2::types::TargetGroup;
+use aws_sdk_elasticloadbalancingv2::types::TargetHealthDescription;
+use aws_sdk_route53::types::HostedZone;
+use aws_sdk_route53::types::ResourceRecordSet;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_ROUTER_TAG_HYPERSHIFT: &str = "kubernetes.io/service-name";
+pub const DEFAULT_ROUTER_VALUE_HYPERSHIFT: &str = "openshift-ingress/router-default";
+pub const DEFAULT_ROUTER_TAG: &str = "openshift-ingress/router-default";
+pub const CLUSTER_TAG_PREFIX: &str = "kubernetes.io/cluster/";
+
+/// The DNS attributes of a VPC - `None` if the attribute could not be read.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VpcAttributes {
+    pub vpc_id: String,
+    #[serde(default)]
+    pub enable_dns_support: Option<bool>,
+    #[serde(default)]
+    pub enable_dns_hostnames: Option<bool>,
+}
+
+/// The S3 bucket a load balancer writes its access logs to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessLogBucket {
+    pub name: String,
+    /// `None` if it could not be determined, e.g. for buckets of other
+    /// accounts.
+    #[serde(default)]
+    pub exists: Option<bool>,
+    /// The bucket policy document - `None` if the bucket has none or it could
+    /// not be read.
+    #[serde(default)]
+    pub policy: Option<String>,
+}
+
+// Abstracts over classic and modern loadbalancers where needed.
+// Allows the method to dispatch using match where needed.
+#[derive(Clone, Debug)]
+pub enum AWSLoadBalancer {
+    ClassicLoadBalancer((LoadBalancerDescription, Vec<Tag>)),
+    ModernLoadBalancer((LoadBalancer, Vec<Tag>)),
+}
+
+impl AWSLoadBalancer {
+    /// The name of the load balancer.
+    pub fn name(&self) -> &str {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => {
+                c.load_balancer_name().unwrap_or_default()
+            }
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => {
+                m.load_balancer_name().unwrap_or_default()
+            }
+        }
+    }
+
+    /// Uniquely identifies the load balancer: the ARN for modern and the name
+    /// for classic load balancers.
+    pub fn identifier(&self) -> &str {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => {
+                c.load_balancer_name().unwrap_or_default()
+            }
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => {
+                m.load_balancer_arn().unwrap_or_default()
+            }
+        }
+    }
+
+    /// The availability zones the load balancer is enabled in.
+    pub fn availability_zones(&self) -> Vec<String> {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => c.availability_zones().to_vec(),
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => m
+                .availability_zones()
+                .iter()
+                .filter_map(|az| az.zone_name.clone())
+                .collect(),
+        }
+    }
+
+    /// The description AWS sets on the ENIs it creates for the load balancer:
+    /// `ELB <name>` for classic and `ELB <type>/<name>/<id>` for modern load
+    /// balancers - `None` if the name or ARN is unknown.
+    pub fn eni_description(&self) -> Option<String> {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => c
+                .load_balancer_name()
+                .filter(|n| !n.is_empty())
+                .map(|n| format!("ELB {}", n)),
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => m
+                .load_balancer_arn()
+                .and_then(|arn| arn.split_once(":loadbalancer/"))
+                .map(|(_, identifier)| format!("ELB {}", identifier)),
+        }
+    }
+
+    /// The type of the ENIs AWS creates for the load balancer: network and
+    /// gateway load balancers have their own, classic and application load
+    /// balancers use plain interfaces.
+    pub fn eni_interface_type(&self) -> NetworkInterfaceType {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer(_) => NetworkInterfaceType::Interface,
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => match m.r#type() {
+                Some(LoadBalancerTypeEnum::Network) => NetworkInterfaceType::NetworkLoadBalancer,
+                Some(LoadBalancerTypeEnum::Gateway) => NetworkInterfaceType::GatewayLoadBalancer,
+                _ => NetworkInterfaceType::Interface,
+            },
+        }
+    }
+
+    /// The DNS name AWS assigned to the load balancer.
+    pub fn dns_name(&self) -> Option<&str> {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => c.dns_name(),
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => m.dns_name(),
+        }
+    }
+
+    /// The scheme of the load balancer: `internal` or `internet-facing`.
+    pub fn scheme(&self) -> Option<&str> {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((c, _)) => c.scheme(),
+            AWSLoadBalancer::ModernLoadBalancer((m, _)) => m.scheme().map(|s| s.as_str()),
+        }
+    }
+
+    pub fn tags(&self) -> &Vec<Tag> {
+        match self {
+            AWSLoadBalancer::ClassicLoadBalancer((_, tags)) => tags,
+            AWSLoadBalancer::ModernLoadBalancer((_, tags)) => tags,
+        }
+    }
+}
+
+/// The attributes of a load balancer. Attributes of classic load balancers are
+/// mapped to the keys used by modern load balancers (e.g.
+/// `idle_timeout.timeout_seconds`), so they can be checked the same way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AWSLoadBalancerAttributes {
+    /// The identifier of the load balancer, see [`AWSLoadBalancer::identifier`].
+    pub load_balancer: String,
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tag {
+    /// <p>The key of the tag.</p>
+    pub key: Option<String>,
+    /// <p>The value of the tag.</p>
+    pub value: Option<String>,
+}
+
+impl From<TagV1> for Tag {
+    fn from(value: TagV1) -> Self {
+        Tag {
+            key: Some(value.key),
+            value: value.value,
+        }
+    }
+}
+
+impl From<TagV2> for Tag {
+    fn from(value: TagV2) -> Self {
+        Tag {
+            key: value.key,
+            value: value.value,
+        }
+    }
+}
+
+pub trait Collector {
+    fn match_tag(&self, t: Tag) -> bool;
+}
+
+pub struct HypershiftCollector;
+
+pub struct DefaultCollector<'a> {
+    pub cluster_id: &'a String,
+    pub cluster_infra_name: &'a String,
+}
+
+impl Collector for HypershiftCollector {
+    fn match_tag(&self, t: Tag) -> bool {
+        debug!(
+            "Checking if {:?} matches {} with value {}",
+            t, DEFAULT_ROUTER_TAG_HYPERSHIFT, DEFAULT_ROUTER_VALUE_HYPERSHIFT
+        );
+        t.key.is_some_and(|t| t == DEFAULT_ROUTER_TAG_HYPERSHIFT)
+            && t.value
+                .is_some_and(|t| t == DEFAULT_ROUTER_VALUE_HYPERSHIFT)
+    }
+}
+
+impl<'a> Collector for DefaultCollector<'a> {
+    fn match_tag(&self, t: Tag) -> bool {
+        let cluster_id_tag = format!("{}{}", CLUSTER_TAG_PREFIX, self.cluster_id);
+        let cluster_name_tag = format!("{}{}", CLUSTER_TAG_PREFIX, self.cluster_infra_name);
+        debug!(
+            "Checking if {:?} matches {} or {}",
+            t, cluster_id_tag, cluster_name_tag
+        );
+        t.key
+            .is_some_and(|t| t.contains(&cluster_id_tag) || t.contains(&cluster_name_tag))
+            && t.value.is_some_and(|t| t == "owned" || t == "shared")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AWSInstance {
+    pub instance: Instance,
+    pub security_groups: Vec<SecurityGroup>,
+}
+
+impl AWSInstance {
+    /// Whether the instance is a control plane node, named
+    /// `<infra name>-master-<n>`.
+    pub fn is_control_plane(&self, infra_name: &str) -> bool {
+        let prefix = format!("{}-master-", infra_name);
+        self.instance
+            .tags()
+            .iter()
+            .any(|t| t.key() == Some("Name") && t.value().is_some_and(|v| v.starts_with(&prefix)))
+    }
+}
+
+/// An ENI AWS created for a load balancer.
+#[derive(Clone, Debug)]
+pub struct AWSLoadBalancerEni {
+    /// The identifier of the load balancer, see [`AWSLoadBalancer::identifier`].
+    pub load_balancer: String,
+    pub eni: NetworkInterface,
+}
+
+/// A target group of a modern load balancer with the health of its targets.
+#[derive(Clone, Debug)]
+pub struct AWSTargetGroup {
+    pub target_group: TargetGroup,
+    pub targets: Vec<TargetHealthDescription>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HostedZoneWithRecords {
+    pub hosted_zone: HostedZone,
+    pub resource_records: Vec<ResourceRecordSet>,
+    /// The VPCs a private zone is associated with.
+    pub vpcs: Vec<String>,
+    /// The name servers Route53 assigned to a public zone.
+    pub name_servers: Vec<String>,
+    /// The name servers a public zone is delegated to by its parent domain,
+    /// as resolved by a live DNS query - `None` if it was not resolved.
+    pub delegated_name_servers: Option<Vec<String>>,
+}
+
+pub struct TaggedResource<T> {
+    t: T,
+    tags: Vec<Tag>,
+}
+
+/// A single record of the Route53 Resolver query logs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolverQueryLogEntry {
+    pub query_name: String,
+    pub query_type: String,
+    pub rcode: String,
+    pub srcaddr: String,
+}
+
+impl ResolverQueryLogEntry {
+    /// Parses the JSON log message Route53 Resolver writes for every query.
+    pub fn from_log_message(message: &str) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(message).ok()?;
+        let field = |name: &str| json.get(name).and_then(|v| v.as_str()).map(String::from);
+        Some(ResolverQueryLogEntry {
+            query_name: field("query_name")?,
+            query_type: field("query_type").unwrap_or_default(),
+            rcode: field("rcode")?,
+            srcaddr: field("srcaddr").unwrap_or_default(),
+        })
+    }
+}
@@ -0,0 +1,1934 @@
+//! Snapshots persist the data gathered for a cluster, so the checks can be run
+//! again later without any access to OCM or AWS.
+//!
+//! The AWS SDK types can not be serialized, so every type is mirrored by a
+//! serializable type that keeps the fields the checks rely on. Enums are
+//! stored as the strings AWS uses for them.
+
+use std::{error::Error, fs::File, io::BufReader, io::BufWriter, path::Path};
+
+use aws_sdk_ec2::types::{
+    Address, AttachmentStatus, AttributeValue, AvailabilityZone, AvailabilityZoneOptInStatus,
+    AvailabilityZoneState, DhcpConfiguration, DhcpOptions, DomainType, EgressOnlyInternetGateway,
+    FlowLog, GroupIdentifier, Instance, InstanceNetworkInterface, InstancePrivateIpAddress,
+    InstanceState, InstanceStateName, InstanceType, InstanceTypeOffering, InternetGateway,
+    InternetGatewayAttachment, IpPermission, IpRange, LocationType, LogDestinationType, NatGateway,
+    NatGatewayAddress, NatGatewayState, NetworkAcl, NetworkAclAssociation, NetworkAclEntry,
+    NetworkInterface, NetworkInterfaceStatus, NetworkInterfaceType, Placement, PortRange, Route,
+    RouteState, RouteTable, RouteTableAssociation, RouteTableAssociationState,
+    RouteTableAssociationStateCode, RuleAction, SecurityGroup, State, Subnet, SubnetCidrBlockState,
+    SubnetCidrBlockStateCode, SubnetIpv6CidrBlockAssociation, SubnetState, TrafficType, Vpc,
+    VpcCidrBlockAssociation, VpcCidrBlockStateCode, VpcEndpoint, VpcEndpointType,
+};
+use aws_sdk_elasticloadbalancing::types::LoadBalancerDescription;
+use aws_sdk_elasticloadbalancingv2::types::{
+    LoadBalancer, LoadBalancerSchemeEnum, LoadBalancerState, LoadBalancerStateEnum,
+    LoadBalancerTypeEnum, ProtocolEnum, TargetDescription, TargetGroup, TargetHealth,
+    TargetHealthDescription, TargetHealthStateEnum, TargetTypeEnum,
+};
+use aws_sdk_route53::types::{
+    AliasTarget, HostedZone, HostedZoneConfig, ResourceRecord, ResourceRecordSet, RrType,
+};
+use aws_sdk_route53resolver::types::{ResolverQueryLogConfig, ResolverQueryLogConfigStatus};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{
+    credentials::PolicyDenial,
+    health::HealthEvent,
+    iam::InstanceProfile,
+    quotas::ServiceQuota,
+    shared_types::{
+        AWSInstance, AWSLoadBalancer, AWSLoadBalancerAttributes, AWSLoadBalancerEni,
+        AWSTargetGroup, AccessLogBucket, HostedZoneWithRecords, ResolverQueryLogEntry, Tag,
+        VpcAttributes,
+    },
+    AWSClusterData, Environment,
+};
+use crate::types::{DataSet, GatherFailure, InvariantError, MinimalClusterInfo};
+
+/// Bumped whenever the layout of the snapshot changes in an incompatible way.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything the checks need: the cluster information from OCM and the data
+/// gathered from AWS.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// How the data was gathered.
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    pub cluster_info: MinimalClusterInfo,
+    pub subnets: Vec<SubnetSnapshot>,
+    pub routetables: Vec<RouteTableSnapshot>,
+    pub load_balancers: Vec<LoadBalancerSnapshot>,
+    pub load_balancer_enis: Vec<LoadBalancerEniSnapshot>,
+    pub instances: Vec<InstanceSnapshot>,
+    pub hosted_zones: Vec<HostedZoneSnapshot>,
+    pub availability_zones: Vec<AvailabilityZoneSnapshot>,
+    pub instance_type_offerings: Vec<InstanceTypeOfferingSnapshot>,
+    pub resolver_query_log_configs: Vec<ResolverQueryLogConfigSnapshot>,
+    pub resolver_query_logs: Vec<ResolverQueryLogEntry>,
+    pub nat_gateways: Vec<NatGatewaySnapshot>,
+    #[serde(default)]
+    pub internet_gateways: Vec<InternetGatewaySnapshot>,
+    #[serde(default)]
+    pub egress_only_internet_gateways: Vec<EgressOnlyInternetGatewaySnapshot>,
+    pub elastic_ips: Vec<AddressSnapshot>,
+    pub target_groups: Vec<TargetGroupSnapshot>,
+    pub load_balancer_attributes: Vec<AWSLoadBalancerAttributes>,
+    #[serde(default)]
+    pub vpc_endpoints: Vec<VpcEndpointSnapshot>,
+    #[serde(default)]
+    pub network_acls: Vec<NetworkAclSnapshot>,
+    #[serde(default)]
+    pub subnet_network_interfaces: Vec<NetworkInterfaceSnapshot>,
+    #[serde(default)]
+    pub denials: Vec<PolicyDenial>,
+    #[serde(default)]
+    pub vpcs: Vec<VpcSnapshot>,
+    #[serde(default)]
+    pub health_events: Vec<HealthEvent>,
+    #[serde(default)]
+    pub dhcp_options: Vec<DhcpOptionsSnapshot>,
+    #[serde(default)]
+    pub vpc_attributes: Vec<VpcAttributes>,
+    #[serde(default)]
+    pub access_log_buckets: Vec<AccessLogBucket>,
+    #[serde(default)]
+    pub instance_profiles: Vec<InstanceProfile>,
+    #[serde(default)]
+    pub service_quotas: Vec<ServiceQuota>,
+    #[serde(default)]
+    pub flow_logs: Vec<FlowLogSnapshot>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_failures")]
+    pub failed: Vec<GatherFailure>,
+}
+
+/// Reads the failed data sets, including the ones of older snapshots that
+/// only recorded the data set without the reason.
+fn deserialize_failures<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<GatherFailure>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Failure {
+        WithReason(GatherFailure),
+        DataSet(DataSet),
+    }
+    Ok(Vec::<Failure>::deserialize(deserializer)?
+        .into_iter()
+        .map(|f| match f {
+            Failure::WithReason(f) => f,
+            Failure::DataSet(set) => GatherFailure {
+                set,
+                reason: "unknown".to_string(),
+                empty: false,
+            },
+        })
+        .collect())
+}
+
+impl Snapshot {
+    pub fn new(cluster_info: &MinimalClusterInfo, data: &AWSClusterData) -> Self {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            environment: None,
+            cluster_info: cluster_info.clone(),
+            subnets: data.subnets.iter().map(Into::into).collect(),
+            routetables: data.routetables.iter().map(Into::into).collect(),
+            load_balancers: data.load_balancers.iter().map(Into::into).collect(),
+            load_balancer_enis: data.load_balancer_enis.iter().map(Into::into).collect(),
+            instances: data.instances.iter().map(Into::into).collect(),
+            hosted_zones: data.hosted_zones.iter().map(Into::into).collect(),
+            availability_zones: data.availability_zones.iter().map(Into::into).collect(),
+            instance_type_offerings: data
+                .instance_type_offerings
+                .iter()
+                .map(Into::into)
+                .collect(),
+            resolver_query_log_configs: data
+                .resolver_query_log_configs
+                .iter()
+                .map(Into::into)
+                .collect(),
+            resolver_query_logs: data.resolver_query_logs.clone(),
+            nat_gateways: data.nat_gateways.iter().map(Into::into).collect(),
+            internet_gateways: data.internet_gateways.iter().map(Into::into).collect(),
+            egress_only_internet_gateways: data
+                .egress_only_internet_gateways
+                .iter()
+                .map(Into::into)
+                .collect(),
+            elastic_ips: data.elastic_ips.iter().map(Into::into).collect(),
+            target_groups: data.target_groups.iter().map(Into::into).collect(),
+            load_balancer_attributes: data.load_balancer_attributes.clone(),
+            vpc_endpoints: data.vpc_endpoints.iter().map(Into::into).collect(),
+            network_acls: data.network_acls.iter().map(Into::into).collect(),
+            subnet_network_interfaces: data
+                .subnet_network_interfaces
+                .iter()
+                .map(Into::into)
+                .collect(),
+            denials: data.denials.clone(),
+            vpcs: data.vpcs.iter().map(Into::into).collect(),
+            dhcp_options: data.dhcp_options.iter().map(Into::into).collect(),
+            vpc_attributes: data.vpc_attributes.clone(),
+            access_log_buckets: data.access_log_buckets.clone(),
+            health_events: data.health_events.clone(),
+            instance_profiles: data.instance_profiles.clone(),
+            service_quotas: data.service_quotas.clone(),
+            flow_logs: data.flow_logs.iter().map(Into::into).collect(),
+            account_id: data.account_id.clone(),
+            failed: data.failed.clone(),
+        }
+    }
+
+    /// Converts the snapshot back into the data the checks are set up with.
+    pub fn into_parts(self) -> (MinimalClusterInfo, AWSClusterData) {
+        let load_balancers: Vec<AWSLoadBalancer> =
+            self.load_balancers.into_iter().map(Into::into).collect();
+        let load_balancer_enis = self
+            .load_balancer_enis
+            .into_iter()
+            .filter_map(|eni| eni.into_lb_eni(&load_balancers))
+            .collect();
+        let data = AWSClusterData {
+            subnets: self.subnets.into_iter().map(Into::into).collect(),
+            routetables: self.routetables.into_iter().map(Into::into).collect(),
+            load_balancers,
+            load_balancer_enis,
+            instances: self.instances.into_iter().map(Into::into).collect(),
+            hosted_zones: self.hosted_zones.into_iter().map(Into::into).collect(),
+            availability_zones: self
+                .availability_zones
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            instance_type_offerings: self
+                .instance_type_offerings
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            resolver_query_log_configs: self
+                .resolver_query_log_configs
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            resolver_query_logs: self.resolver_query_logs,
+            nat_gateways: self.nat_gateways.into_iter().map(Into::into).collect(),
+            internet_gateways: self.internet_gateways.into_iter().map(Into::into).collect(),
+            egress_only_internet_gateways: self
+                .egress_only_internet_gateways
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            elastic_ips: self.elastic_ips.into_iter().map(Into::into).collect(),
+            target_groups: self.target_groups.into_iter().map(Into::into).collect(),
+            load_balancer_attributes: self.load_balancer_attributes,
+            vpc_endpoints: self.vpc_endpoints.into_iter().map(Into::into).collect(),
+            network_acls: self.network_acls.into_iter().map(Into::into).collect(),
+            subnet_network_interfaces: self
+                .subnet_network_interfaces
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            denials: self.denials,
+            vpcs: self.vpcs.into_iter().map(Into::into).collect(),
+            dhcp_options: self.dhcp_options.into_iter().map(Into::into).collect(),
+            vpc_attributes: self.vpc_attributes,
+            access_log_buckets: self.access_log_buckets,
+            health_events: self.health_events,
+            instance_profiles: self.instance_profiles,
+            service_quotas: self.service_quotas,
+            flow_logs: self.flow_logs.into_iter().map(Into::into).collect(),
+            account_id: self.account_id,
+            failed: self.failed,
+        };
+        (self.cluster_info, data)
+    }
+
+    /// Writes the snapshot as JSON.
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`Snapshot::write`].
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let snapshot: Snapshot = serde_json::from_reader(reader)?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(Box::new(InvariantError {
+                msg: format!(
+                    "Snapshot {} has version {} but only version {} is supported",
+                    path.display(),
+                    snapshot.version,
+                    SNAPSHOT_VERSION
+                ),
+            }));
+        }
+        Ok(snapshot)
+    }
+}
+
+fn from_ec2_tags(tags: &[aws_sdk_ec2::types::Tag]) -> Vec<Tag> {
+    tags.iter()
+        .map(|t| Tag {
+            key: t.key.clone(),
+            value: t.value.clone(),
+        })
+        .collect()
+}
+
+fn to_ec2_tags(tags: Vec<Tag>) -> Option<Vec<aws_sdk_ec2::types::Tag>> {
+    if tags.is_empty() {
+        return None;
+    }
+    Some(
+        tags.into_iter()
+            .map(|t| {
+                aws_sdk_ec2::types::Tag::builder()
+                    .set_key(t.key)
+                    .set_value(t.value)
+                    .build()
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubnetSnapshot {
+    pub subnet_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub availability_zone: Option<String>,
+    pub availability_zone_id: Option<String>,
+    pub cidr_block: Option<String>,
+    pub available_ip_address_count: Option<i32>,
+    pub map_public_ip_on_launch: Option<bool>,
+    pub state: Option<String>,
+    /// The account owning the subnet - another account than the one of the
+    /// cluster for subnets shared through RAM.
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    pub tags: Vec<Tag>,
+    /// The associated IPv6 CIDR blocks.
+    #[serde(default)]
+    pub ipv6_cidr_blocks: Vec<String>,
+}
+
+impl From<&Subnet> for SubnetSnapshot {
+    fn from(s: &Subnet) -> Self {
+        SubnetSnapshot {
+            subnet_id: s.subnet_id.clone(),
+            vpc_id: s.vpc_id.clone(),
+            availability_zone: s.availability_zone.clone(),
+            availability_zone_id: s.availability_zone_id.clone(),
+            cidr_block: s.cidr_block.clone(),
+            available_ip_address_count: s.available_ip_address_count,
+            map_public_ip_on_launch: s.map_public_ip_on_launch,
+            state: s.state().map(|s| s.as_str().to_string()),
+            owner_id: s.owner_id.clone(),
+            tags: from_ec2_tags(s.tags()),
+            ipv6_cidr_blocks: s
+                .ipv6_cidr_block_association_set()
+                .iter()
+                .filter(|a| {
+                    a.ipv6_cidr_block_state().and_then(|s| s.state())
+                        == Some(&SubnetCidrBlockStateCode::Associated)
+                })
+                .filter_map(|a| a.ipv6_cidr_block.clone())
+                .collect(),
+        }
+    }
+}
+
+impl From<SubnetSnapshot> for Subnet {
+    fn from(s: SubnetSnapshot) -> Self {
+        Subnet::builder()
+            .set_subnet_id(s.subnet_id)
+            .set_vpc_id(s.vpc_id)
+            .set_availability_zone(s.availability_zone)
+            .set_availability_zone_id(s.availability_zone_id)
+            .set_cidr_block(s.cidr_block)
+            .set_available_ip_address_count(s.available_ip_address_count)
+            .set_map_public_ip_on_launch(s.map_public_ip_on_launch)
+            .set_state(s.state.as_deref().map(SubnetState::from))
+            .set_owner_id(s.owner_id)
+            .set_tags(to_ec2_tags(s.tags))
+            .set_ipv6_cidr_block_association_set((!s.ipv6_cidr_blocks.is_empty()).then(|| {
+                s.ipv6_cidr_blocks
+                    .into_iter()
+                    .map(|cidr| {
+                        SubnetIpv6CidrBlockAssociation::builder()
+                            .ipv6_cidr_block(cidr)
+                            .ipv6_cidr_block_state(
+                                SubnetCidrBlockState::builder()
+                                    .state(SubnetCidrBlockStateCode::Associated)
+                                    .build(),
+                            )
+                            .build()
+                    })
+                    .collect()
+            }))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RouteTableAssociationSnapshot {
+    pub route_table_association_id: Option<String>,
+    pub route_table_id: Option<String>,
+    pub subnet_id: Option<String>,
+    pub gateway_id: Option<String>,
+    pub main: Option<bool>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RouteSnapshot {
+    pub destination_cidr_block: Option<String>,
+    pub destination_ipv6_cidr_block: Option<String>,
+    pub destination_prefix_list_id: Option<String>,
+    pub gateway_id: Option<String>,
+    pub nat_gateway_id: Option<String>,
+    #[serde(default)]
+    pub egress_only_internet_gateway_id: Option<String>,
+    pub transit_gateway_id: Option<String>,
+    pub vpc_peering_connection_id: Option<String>,
+    pub network_interface_id: Option<String>,
+    pub instance_id: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RouteTableSnapshot {
+    pub route_table_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub associations: Vec<RouteTableAssociationSnapshot>,
+    pub routes: Vec<RouteSnapshot>,
+    pub tags: Vec<Tag>,
+}
+
+impl From<&RouteTable> for RouteTableSnapshot {
+    fn from(rt: &RouteTable) -> Self {
+        RouteTableSnapshot {
+            route_table_id: rt.route_table_id.clone(),
+            vpc_id: rt.vpc_id.clone(),
+            associations: rt
+                .associations()
+                .iter()
+                .map(|a| RouteTableAssociationSnapshot {
+                    route_table_association_id: a.route_table_association_id.clone(),
+                    route_table_id: a.route_table_id.clone(),
+                    subnet_id: a.subnet_id.clone(),
+                    gateway_id: a.gateway_id.clone(),
+                    main: a.main,
+                    state: a
+                        .association_state()
+                        .and_then(|s| s.state())
+                        .map(|s| s.as_str().to_string()),
+                })
+                .collect(),
+            routes: rt
+                .routes()
+                .iter()
+                .map(|r| RouteSnapshot {
+                    destination_cidr_block: r.destination_cidr_block.clone(),
+                    destination_ipv6_cidr_block: r.destination_ipv6_cidr_block.clone(),
+                    destination_prefix_list_id: r.destination_prefix_list_id.clone(),
+                    gateway_id: r.gateway_id.clone(),
+                    nat_gateway_id: r.nat_gateway_id.clone(),
+                    egress_only_internet_gateway_id: r.egress_only_internet_gateway_id.clone(),
+                    transit_gateway_id: r.transit_gateway_id.clone(),
+                    vpc_peering_connection_id: r.vpc_peering_connection_id.clone(),
+                    network_interface_id: r.network_interface_id.clone(),
+                    instance_id: r.instance_id.clone(),
+                    state: r.state().map(|s| s.as_str().to_string()),
+                })
+                .collect(),
+            tags: from_ec2_tags(rt.tags()),
+        }
+    }
+}
+
+impl From<RouteTableSnapshot> for RouteTable {
+    fn from(rt: RouteTableSnapshot) -> Self {
+        let associations = rt
+            .associations
+            .into_iter()
+            .map(|a| {
+                RouteTableAssociation::builder()
+                    .set_route_table_association_id(a.route_table_association_id)
+                    .set_route_table_id(a.route_table_id)
+                    .set_subnet_id(a.subnet_id)
+                    .set_gateway_id(a.gateway_id)
+                    .set_main(a.main)
+                    .set_association_state(a.state.as_deref().map(|s| {
+                        RouteTableAssociationState::builder()
+                            .state(RouteTableAssociationStateCode::from(s))
+                            .build()
+                    }))
+                    .build()
+            })
+            .collect();
+        let routes = rt
+            .routes
+            .into_iter()
+            .map(|r| {
+                Route::builder()
+                    .set_destination_cidr_block(r.destination_cidr_block)
+                    .set_destination_ipv6_cidr_block(r.destination_ipv6_cidr_block)
+                    .set_destination_prefix_list_id(r.destination_prefix_list_id)
+                    .set_gateway_id(r.gateway_id)
+                    .set_nat_gateway_id(r.nat_gateway_id)
+                    .set_egress_only_internet_gateway_id(r.egress_only_internet_gateway_id)
+                    .set_transit_gateway_id(r.transit_gateway_id)
+                    .set_vpc_peering_connection_id(r.vpc_peering_connection_id)
+                    .set_network_interface_id(r.network_interface_id)
+                    .set_instance_id(r.instance_id)
+                    .set_state(r.state.as_deref().map(RouteState::from))
+                    .build()
+            })
+            .collect();
+        RouteTable::builder()
+            .set_route_table_id(rt.route_table_id)
+            .set_vpc_id(rt.vpc_id)
+            .set_associations(Some(associations))
+            .set_routes(Some(routes))
+            .set_tags(to_ec2_tags(rt.tags))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadBalancerAvailabilityZoneSnapshot {
+    pub zone_name: Option<String>,
+    pub subnet_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum LoadBalancerSnapshot {
+    Classic {
+        load_balancer_name: Option<String>,
+        dns_name: Option<String>,
+        scheme: Option<String>,
+        vpc_id: Option<String>,
+        availability_zones: Vec<String>,
+        subnets: Vec<String>,
+        instances: Vec<String>,
+        security_groups: Vec<String>,
+        tags: Vec<Tag>,
+    },
+    Modern {
+        load_balancer_arn: Option<String>,
+        load_balancer_name: Option<String>,
+        dns_name: Option<String>,
+        scheme: Option<String>,
+        vpc_id: Option<String>,
+        r#type: Option<String>,
+        state: Option<String>,
+        availability_zones: Vec<LoadBalancerAvailabilityZoneSnapshot>,
+        security_groups: Vec<String>,
+        tags: Vec<Tag>,
+    },
+}
+
+impl From<&AWSLoadBalancer> for LoadBalancerSnapshot {
+    fn from(lb: &AWSLoadBalancer) -> Self {
+        match lb {
+            AWSLoadBalancer::ClassicLoadBalancer((c, tags)) => LoadBalancerSnapshot::Classic {
+                load_balancer_name: c.load_balancer_name.clone(),
+                dns_name: c.dns_name.clone(),
+                scheme: c.scheme.clone(),
+                vpc_id: c.vpc_id.clone(),
+                availability_zones: c.availability_zones().to_vec(),
+                subnets: c.subnets().to_vec(),
+                instances: c
+                    .instances()
+                    .iter()
+                    .filter_map(|i| i.instance_id.clone())
+                    .collect(),
+                security_groups: c.security_groups().to_vec(),
+                tags: tags.clone(),
+            },
+            AWSLoadBalancer::ModernLoadBalancer((m, tags)) => LoadBalancerSnapshot::Modern {
+                load_balancer_arn: m.load_balancer_arn.clone(),
+                load_balancer_name: m.load_balancer_name.clone(),
+                dns_name: m.dns_name.clone(),
+                scheme: m.scheme().map(|s| s.as_str().to_string()),
+                vpc_id: m.vpc_id.clone(),
+                r#type: m.r#type().map(|t| t.as_str().to_string()),
+                state: m
+                    .state()
+                    .and_then(|s| s.code())
+                    .map(|c| c.as_str().to_string()),
+                availability_zones: m
+                    .availability_zones()
+                    .iter()
+                    .map(|az| LoadBalancerAvailabilityZoneSnapshot {
+                        zone_name: az.zone_name.clone(),
+                        subnet_id: az.subnet_id.clone(),
+                    })
+                    .collect(),
+                security_groups: m.security_groups().to_vec(),
+                tags: tags.clone(),
+            },
+        }
+    }
+}
+
+impl From<LoadBalancerSnapshot> for AWSLoadBalancer {
+    fn from(lb: LoadBalancerSnapshot) -> Self {
+        match lb {
+            LoadBalancerSnapshot::Classic {
+                load_balancer_name,
+                dns_name,
+                scheme,
+                vpc_id,
+                availability_zones,
+                subnets,
+                instances,
+                security_groups,
+                tags,
+            } => AWSLoadBalancer::ClassicLoadBalancer((
+                LoadBalancerDescription::builder()
+                    .set_load_balancer_name(load_balancer_name)
+                    .set_dns_name(dns_name)
+                    .set_scheme(scheme)
+                    .set_vpc_id(vpc_id)
+                    .set_availability_zones(Some(availability_zones))
+                    .set_subnets(Some(subnets))
+                    .set_instances(Some(
+                        instances
+                            .into_iter()
+                            .map(|i| {
+                                aws_sdk_elasticloadbalancing::types::Instance::builder()
+                                    .instance_id(i)
+                                    .build()
+                            })
+                            .collect(),
+                    ))
+                    .set_security_groups(Some(security_groups))
+                    .build(),
+                tags,
+            )),
+            LoadBalancerSnapshot::Modern {
+                load_balancer_arn,
+                load_balancer_name,
+                dns_name,
+                scheme,
+                vpc_id,
+                r#type,
+                state,
+                availability_zones,
+                security_groups,
+                tags,
+            } => AWSLoadBalancer::ModernLoadBalancer((
+                LoadBalancer::builder()
+                    .set_load_balancer_arn(load_balancer_arn)
+                    .set_load_balancer_name(load_balancer_name)
+                    .set_dns_name(dns_name)
+                    .set_scheme(scheme.as_deref().map(LoadBalancerSchemeEnum::from))
+                    .set_vpc_id(vpc_id)
+                    .set_type(r#type.as_deref().map(LoadBalancerTypeEnum::from))
+                    .set_state(state.as_deref().map(|s| {
+                        LoadBalancerState::builder()
+                            .code(LoadBalancerStateEnum::from(s))
+                            .build()
+                    }))
+                    .set_availability_zones(Some(
+                        availability_zones
+                            .into_iter()
+                            .map(|az| {
+                                aws_sdk_elasticloadbalancingv2::types::AvailabilityZone::builder()
+                                    .set_zone_name(az.zone_name)
+                                    .set_subnet_id(az.subnet_id)
+                                    .build()
+                            })
+                            .collect(),
+                    ))
+                    .set_security_groups(Some(security_groups))
+                    .build(),
+                tags,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkInterfaceSnapshot {
+    pub network_interface_id: Option<String>,
+    pub description: Option<String>,
+    pub subnet_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub availability_zone: Option<String>,
+    pub private_ip_address: Option<String>,
+    pub status: Option<String>,
+    pub groups: Vec<GroupIdentifierSnapshot>,
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub interface_type: Option<String>,
+    #[serde(default)]
+    pub requester_id: Option<String>,
+}
+
+impl From<&NetworkInterface> for NetworkInterfaceSnapshot {
+    fn from(eni: &NetworkInterface) -> Self {
+        NetworkInterfaceSnapshot {
+            network_interface_id: eni.network_interface_id.clone(),
+            description: eni.description.clone(),
+            subnet_id: eni.subnet_id.clone(),
+            vpc_id: eni.vpc_id.clone(),
+            availability_zone: eni.availability_zone.clone(),
+            private_ip_address: eni.private_ip_address.clone(),
+            status: eni.status().map(|s| s.as_str().to_string()),
+            groups: eni.groups().iter().map(Into::into).collect(),
+            tags: from_ec2_tags(eni.tag_set()),
+            interface_type: eni.interface_type().map(|t| t.as_str().to_string()),
+            requester_id: eni.requester_id.clone(),
+        }
+    }
+}
+
+impl From<NetworkInterfaceSnapshot> for NetworkInterface {
+    fn from(eni: NetworkInterfaceSnapshot) -> Self {
+        NetworkInterface::builder()
+            .set_network_interface_id(eni.network_interface_id)
+            .set_description(eni.description)
+            .set_subnet_id(eni.subnet_id)
+            .set_vpc_id(eni.vpc_id)
+            .set_availability_zone(eni.availability_zone)
+            .set_private_ip_address(eni.private_ip_address)
+            .set_status(eni.status.as_deref().map(NetworkInterfaceStatus::from))
+            .set_groups(Some(eni.groups.into_iter().map(Into::into).collect()))
+            .set_tag_set(to_ec2_tags(eni.tags))
+            .set_interface_type(
+                eni.interface_type
+                    .as_deref()
+                    .map(NetworkInterfaceType::from),
+            )
+            .set_requester_id(eni.requester_id)
+            .build()
+    }
+}
+
+/// An ENI of a load balancer. Older snapshots did not record the load
+/// balancer, those ENIs are attributed by their description.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadBalancerEniSnapshot {
+    #[serde(default)]
+    pub load_balancer: Option<String>,
+    #[serde(flatten)]
+    pub eni: NetworkInterfaceSnapshot,
+}
+
+impl From<&AWSLoadBalancerEni> for LoadBalancerEniSnapshot {
+    fn from(eni: &AWSLoadBalancerEni) -> Self {
+        LoadBalancerEniSnapshot {
+            load_balancer: Some(eni.load_balancer.clone()),
+            eni: (&eni.eni).into(),
+        }
+    }
+}
+
+impl LoadBalancerEniSnapshot {
+    /// Converts the ENI back, dropping ENIs that can not be attributed to any
+    /// of the load balancers.
+    fn into_lb_eni(self, load_balancers: &[AWSLoadBalancer]) -> Option<AWSLoadBalancerEni> {
+        let load_balancer = match self.load_balancer {
+            Some(lb) => lb,
+            None => load_balancers
+                .iter()
+                .find(|lb| {
+                    lb.eni_description().is_some() && lb.eni_description() == self.eni.description
+                })
+                .map(|lb| lb.identifier().to_string())?,
+        };
+        Some(AWSLoadBalancerEni {
+            load_balancer,
+            eni: self.eni.into(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupIdentifierSnapshot {
+    pub group_id: Option<String>,
+    pub group_name: Option<String>,
+}
+
+impl From<&GroupIdentifier> for GroupIdentifierSnapshot {
+    fn from(g: &GroupIdentifier) -> Self {
+        GroupIdentifierSnapshot {
+            group_id: g.group_id.clone(),
+            group_name: g.group_name.clone(),
+        }
+    }
+}
+
+impl From<GroupIdentifierSnapshot> for GroupIdentifier {
+    fn from(g: GroupIdentifierSnapshot) -> Self {
+        GroupIdentifier::builder()
+            .set_group_id(g.group_id)
+            .set_group_name(g.group_name)
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceNetworkInterfaceSnapshot {
+    pub network_interface_id: Option<String>,
+    pub private_ip_addresses: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecurityGroupSnapshot {
+    pub group_id: Option<String>,
+    pub group_name: Option<String>,
+    pub description: Option<String>,
+    pub vpc_id: Option<String>,
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub ip_permissions: Vec<IpPermissionSnapshot>,
+    #[serde(default)]
+    pub ip_permissions_egress: Vec<IpPermissionSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IpPermissionSnapshot {
+    pub ip_protocol: Option<String>,
+    pub from_port: Option<i32>,
+    pub to_port: Option<i32>,
+    pub cidr_ips: Vec<String>,
+}
+
+impl From<&IpPermission> for IpPermissionSnapshot {
+    fn from(p: &IpPermission) -> Self {
+        IpPermissionSnapshot {
+            ip_protocol: p.ip_protocol.clone(),
+            from_port: p.from_port,
+            to_port: p.to_port,
+            cidr_ips: p
+                .ip_ranges()
+                .iter()
+                .filter_map(|r| r.cidr_ip.clone())
+                .collect(),
+        }
+    }
+}
+
+impl From<IpPermissionSnapshot> for IpPermission {
+    fn from(p: IpPermissionSnapshot) -> Self {
+        IpPermission::builder()
+            .set_ip_protocol(p.ip_protocol)
+            .set_from_port(p.from_port)
+            .set_to_port(p.to_port)
+            .set_ip_ranges(Some(
+                p.cidr_ips
+                    .into_iter()
+                    .map(|c| IpRange::builder().cidr_ip(c).build())
+                    .collect(),
+            ))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceSnapshot {
+    pub instance_id: Option<String>,
+    pub instance_type: Option<String>,
+    pub state: Option<String>,
+    pub subnet_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub availability_zone: Option<String>,
+    pub private_ip_address: Option<String>,
+    pub private_dns_name: Option<String>,
+    pub network_interfaces: Vec<InstanceNetworkInterfaceSnapshot>,
+    pub instance_security_groups: Vec<GroupIdentifierSnapshot>,
+    pub tags: Vec<Tag>,
+    /// The security groups attached to the instance, see
+    /// [`AWSInstance::security_groups`].
+    pub security_groups: Vec<SecurityGroupSnapshot>,
+}
+
+impl From<&AWSInstance> for InstanceSnapshot {
+    fn from(i: &AWSInstance) -> Self {
+        let instance = &i.instance;
+        InstanceSnapshot {
+            instance_id: instance.instance_id.clone(),
+            instance_type: instance.instance_type().map(|t| t.as_str().to_string()),
+            state: instance
+                .state()
+                .and_then(|s| s.name())
+                .map(|s| s.as_str().to_string()),
+            subnet_id: instance.subnet_id.clone(),
+            vpc_id: instance.vpc_id.clone(),
+            availability_zone: instance
+                .placement()
+                .and_then(|p| p.availability_zone.clone()),
+            private_ip_address: instance.private_ip_address.clone(),
+            private_dns_name: instance.private_dns_name.clone(),
+            network_interfaces: instance
+                .network_interfaces()
+                .iter()
+                .map(|eni| InstanceNetworkInterfaceSnapshot {
+                    network_interface_id: eni.network_interface_id.clone(),
+                    private_ip_addresses: eni
+                        .private_ip_addresses()
+                        .iter()
+                        .filter_map(|ip| ip.private_ip_address.clone())
+                        .collect(),
+                })
+                .collect(),
+            instance_security_groups: instance.security_groups().iter().map(Into::into).collect(),
+            tags: from_ec2_tags(instance.tags()),
+            security_groups: i
+                .security_groups
+                .iter()
+                .map(|sg| SecurityGroupSnapshot {
+                    group_id: sg.group_id.clone(),
+                    group_name: sg.group_name.clone(),
+                    description: sg.description.clone(),
+                    vpc_id: sg.vpc_id.clone(),
+                    tags: from_ec2_tags(sg.tags()),
+                    ip_permissions: sg.ip_permissions().iter().map(Into::into).collect(),
+                    ip_permissions_egress: sg
+                        .ip_permissions_egress()
+                        .iter()
+                        .map(Into::into)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<InstanceSnapshot> for AWSInstance {
+    fn from(i: InstanceSnapshot) -> Self {
+        let network_interfaces = i
+            .network_interfaces
+            .into_iter()
+            .map(|eni| {
+                InstanceNetworkInterface::builder()
+                    .set_network_interface_id(eni.network_interface_id)
+                    .set_private_ip_addresses(Some(
+                        eni.private_ip_addresses
+                            .into_iter()
+                            .map(|ip| {
+                                InstancePrivateIpAddress::builder()
+                                    .private_ip_address(ip)
+                                    .build()
+                            })
+                            .collect(),
+                    ))
+                    .build()
+            })
+            .collect();
+        AWSInstance {
+            instance: Instance::builder()
+                .set_instance_id(i.instance_id)
+                .set_instance_type(i.instance_type.as_deref().map(InstanceType::from))
+                .set_state(i.state.as_deref().map(|s| {
+                    InstanceState::builder()
+                        .name(InstanceStateName::from(s))
+                        .build()
+                }))
+                .set_subnet_id(i.subnet_id)
+                .set_vpc_id(i.vpc_id)
+                .set_placement(
+                    i.availability_zone
+                        .map(|az| Placement::builder().availability_zone(az).build()),
+                )
+                .set_private_ip_address(i.private_ip_address)
+                .set_private_dns_name(i.private_dns_name)
+                .set_network_interfaces(Some(network_interfaces))
+                .set_security_groups(Some(
+                    i.instance_security_groups
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                ))
+                .set_tags(to_ec2_tags(i.tags))
+                .build(),
+            security_groups: i
+                .security_groups
+                .into_iter()
+                .map(|sg| {
+                    SecurityGroup::builder()
+                        .set_group_id(sg.group_id)
+                        .set_group_name(sg.group_name)
+                        .set_description(sg.description)
+                        .set_vpc_id(sg.vpc_id)
+                        .set_tags(to_ec2_tags(sg.tags))
+                        .set_ip_permissions(Some(
+                            sg.ip_permissions.into_iter().map(Into::into).collect(),
+                        ))
+                        .set_ip_permissions_egress(Some(
+                            sg.ip_permissions_egress
+                                .into_iter()
+                                .map(Into::into)
+                                .collect(),
+                        ))
+                        .build()
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AliasTargetSnapshot {
+    pub hosted_zone_id: String,
+    pub dns_name: String,
+    pub evaluate_target_health: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResourceRecordSetSnapshot {
+    pub name: String,
+    pub r#type: String,
+    pub set_identifier: Option<String>,
+    pub ttl: Option<i64>,
+    pub values: Vec<String>,
+    pub alias_target: Option<AliasTargetSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostedZoneSnapshot {
+    pub id: String,
+    pub name: String,
+    pub caller_reference: String,
+    pub private_zone: Option<bool>,
+    pub comment: Option<String>,
+    pub resource_record_set_count: Option<i64>,
+    pub resource_records: Vec<ResourceRecordSetSnapshot>,
+    #[serde(default)]
+    pub vpcs: Vec<String>,
+    #[serde(default)]
+    pub name_servers: Vec<String>,
+    #[serde(default)]
+    pub delegated_name_servers: Option<Vec<String>>,
+}
+
+impl From<&HostedZoneWithRecords> for HostedZoneSnapshot {
+    fn from(hz: &HostedZoneWithRecords) -> Self {
+        let zone = &hz.hosted_zone;
+        HostedZoneSnapshot {
+            id: zone.id.clone(),
+            name: zone.name.clone(),
+            caller_reference: zone.caller_reference.clone(),
+            private_zone: zone.config().map(|c| c.private_zone),
+            comment: zone.config().and_then(|c| c.comment.clone()),
+            resource_record_set_count: zone.resource_record_set_count,
+            resource_records: hz
+                .resource_records
+                .iter()
+                .map(|r| ResourceRecordSetSnapshot {
+                    name: r.name.clone(),
+                    r#type: r.r#type.as_str().to_string(),
+                    set_identifier: r.set_identifier.clone(),
+                    ttl: r.ttl,
+                    values: r
+                        .resource_records()
+                        .iter()
+                        .map(|rr| rr.value.clone())
+                        .collect(),
+                    alias_target: r.alias_target().map(|a| AliasTargetSnapshot {
+                        hosted_zone_id: a.hosted_zone_id.clone(),
+                        dns_name: a.dns_name.clone(),
+                        evaluate_target_health: a.evaluate_target_health,
+                    }),
+                })
+                .collect(),
+            vpcs: hz.vpcs.clone(),
+            name_servers: hz.name_servers.clone(),
+            delegated_name_servers: hz.delegated_name_servers.clone(),
+        }
+    }
+}
+
+impl From<HostedZoneSnapshot> for HostedZoneWithRecords {
+    fn from(hz: HostedZoneSnapshot) -> Self {
+        let config = hz.private_zone.map(|private_zone| {
+            HostedZoneConfig::builder()
+                .private_zone(private_zone)
+                .set_comment(hz.comment)
+                .build()
+        });
+        let resource_records = hz
+            .resource_records
+            .into_iter()
+            .map(|r| {
+                // Alias records have no values.
+                let values: Vec<_> = r
+                    .values
+                    .into_iter()
+                    .map(|v| {
+                        ResourceRecord::builder()
+                            .value(v)
+                            .build()
+                            .expect("value is set")
+                    })
+                    .collect();
+                let alias_target = r.alias_target.map(|a| {
+                    AliasTarget::builder()
+                        .hosted_zone_id(a.hosted_zone_id)
+                        .dns_name(a.dns_name)
+                        .evaluate_target_health(a.evaluate_target_health)
+                        .build()
+                        .expect("all alias target fields are set")
+                });
+                ResourceRecordSet::builder()
+                    .name(r.name)
+                    .r#type(RrType::from(r.r#type.as_str()))
+                    .set_set_identifier(r.set_identifier)
+                    .set_ttl(r.ttl)
+                    .set_resource_records((!values.is_empty()).then_some(values))
+                    .set_alias_target(alias_target)
+                    .build()
+                    .expect("name and type are set")
+            })
+            .collect();
+        HostedZoneWithRecords {
+            hosted_zone: HostedZone::builder()
+                .id(hz.id)
+                .name(hz.name)
+                .caller_reference(hz.caller_reference)
+                .set_config(config)
+                .set_resource_record_set_count(hz.resource_record_set_count)
+                .build()
+                .expect("id, name and caller reference are set"),
+            resource_records,
+            vpcs: hz.vpcs,
+            name_servers: hz.name_servers,
+            delegated_name_servers: hz.delegated_name_servers,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AvailabilityZoneSnapshot {
+    pub zone_name: Option<String>,
+    pub zone_id: Option<String>,
+    pub zone_type: Option<String>,
+    pub region_name: Option<String>,
+    pub state: Option<String>,
+    pub opt_in_status: Option<String>,
+}
+
+impl From<&AvailabilityZone> for AvailabilityZoneSnapshot {
+    fn from(az: &AvailabilityZone) -> Self {
+        AvailabilityZoneSnapshot {
+            zone_name: az.zone_name.clone(),
+            zone_id: az.zone_id.clone(),
+            zone_type: az.zone_type.clone(),
+            region_name: az.region_name.clone(),
+            state: az.state().map(|s| s.as_str().to_string()),
+            opt_in_status: az.opt_in_status().map(|s| s.as_str().to_string()),
+        }
+    }
+}
+
+impl From<AvailabilityZoneSnapshot> for AvailabilityZone {
+    fn from(az: AvailabilityZoneSnapshot) -> Self {
+        AvailabilityZone::builder()
+            .set_zone_name(az.zone_name)
+            .set_zone_id(az.zone_id)
+            .set_zone_type(az.zone_type)
+            .set_region_name(az.region_name)
+            .set_state(az.state.as_deref().map(AvailabilityZoneState::from))
+            .set_opt_in_status(
+                az.opt_in_status
+                    .as_deref()
+                    .map(AvailabilityZoneOptInStatus::from),
+            )
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceTypeOfferingSnapshot {
+    pub instance_type: Option<String>,
+    pub location_type: Option<String>,
+    pub location: Option<String>,
+}
+
+impl From<&InstanceTypeOffering> for InstanceTypeOfferingSnapshot {
+    fn from(o: &InstanceTypeOffering) -> Self {
+        InstanceTypeOfferingSnapshot {
+            instance_type: o.instance_type().map(|t| t.as_str().to_string()),
+            location_type: o.location_type().map(|t| t.as_str().to_string()),
+            location: o.location.clone(),
+        }
+    }
+}
+
+impl From<InstanceTypeOfferingSnapshot> for InstanceTypeOffering {
+    fn from(o: InstanceTypeOfferingSnapshot) -> Self {
+        InstanceTypeOffering::builder()
+            .set_instance_type(o.instance_type.as_deref().map(InstanceType::from))
+            .set_location_type(o.location_type.as_deref().map(LocationType::from))
+            .set_location(o.location)
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResolverQueryLogConfigSnapshot {
+    pub id: Option<String>,
+    pub arn: Option<String>,
+    pub name: Option<String>,
+    pub status: Option<String>,
+    pub destination_arn: Option<String>,
+}
+
+impl From<&ResolverQueryLogConfig> for ResolverQueryLogConfigSnapshot {
+    fn from(c: &ResolverQueryLogConfig) -> Self {
+        ResolverQueryLogConfigSnapshot {
+            id: c.id.clone(),
+            arn: c.arn.clone(),
+            name: c.name.clone(),
+            status: c.status().map(|s| s.as_str().to_string()),
+            destination_arn: c.destination_arn.clone(),
+        }
+    }
+}
+
+impl From<ResolverQueryLogConfigSnapshot> for ResolverQueryLogConfig {
+    fn from(c: ResolverQueryLogConfigSnapshot) -> Self {
+        ResolverQueryLogConfig::builder()
+            .set_id(c.id)
+            .set_arn(c.arn)
+            .set_name(c.name)
+            .set_status(c.status.as_deref().map(ResolverQueryLogConfigStatus::from))
+            .set_destination_arn(c.destination_arn)
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NatGatewayAddressSnapshot {
+    pub allocation_id: Option<String>,
+    pub network_interface_id: Option<String>,
+    pub private_ip: Option<String>,
+    pub public_ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InternetGatewayAttachmentSnapshot {
+    pub vpc_id: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EgressOnlyInternetGatewaySnapshot {
+    pub egress_only_internet_gateway_id: Option<String>,
+    pub attachments: Vec<InternetGatewayAttachmentSnapshot>,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InternetGatewaySnapshot {
+    pub internet_gateway_id: Option<String>,
+    pub attachments: Vec<InternetGatewayAttachmentSnapshot>,
+    pub tags: Vec<Tag>,
+}
+
+impl From<&InternetGateway> for InternetGatewaySnapshot {
+    fn from(g: &InternetGateway) -> Self {
+        InternetGatewaySnapshot {
+            internet_gateway_id: g.internet_gateway_id.clone(),
+            attachments: g
+                .attachments()
+                .iter()
+                .map(|a| InternetGatewayAttachmentSnapshot {
+                    vpc_id: a.vpc_id.clone(),
+                    state: a.state().map(|s| s.as_str().to_string()),
+                })
+                .collect(),
+            tags: from_ec2_tags(g.tags()),
+        }
+    }
+}
+
+impl From<InternetGatewaySnapshot> for InternetGateway {
+    fn from(g: InternetGatewaySnapshot) -> Self {
+        InternetGateway::builder()
+            .set_internet_gateway_id(g.internet_gateway_id)
+            .set_attachments(Some(
+                g.attachments
+                    .into_iter()
+                    .map(|a| {
+                        InternetGatewayAttachment::builder()
+                            .set_vpc_id(a.vpc_id)
+                            .set_state(a.state.as_deref().map(AttachmentStatus::from))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .set_tags(to_ec2_tags(g.tags))
+            .build()
+    }
+}
+
+impl From<&EgressOnlyInternetGateway> for EgressOnlyInternetGatewaySnapshot {
+    fn from(g: &EgressOnlyInternetGateway) -> Self {
+        EgressOnlyInternetGatewaySnapshot {
+            egress_only_internet_gateway_id: g.egress_only_internet_gateway_id.clone(),
+            attachments: g
+                .attachments()
+                .iter()
+                .map(|a| InternetGatewayAttachmentSnapshot {
+                    vpc_id: a.vpc_id.clone(),
+                    state: a.state().map(|s| s.as_str().to_string()),
+                })
+                .collect(),
+            tags: from_ec2_tags(g.tags()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlowLogSnapshot {
+    pub flow_log_id: Option<String>,
+    /// The VPC or subnet the flow log captures.
+    pub resource_id: Option<String>,
+    pub flow_log_status: Option<String>,
+    pub deliver_logs_status: Option<String>,
+    pub deliver_logs_error_message: Option<String>,
+    pub traffic_type: Option<String>,
+    pub log_destination_type: Option<String>,
+    pub log_destination: Option<String>,
+    pub log_group_name: Option<String>,
+}
+
+impl From<&FlowLog> for FlowLogSnapshot {
+    fn from(f: &FlowLog) -> Self {
+        FlowLogSnapshot {
+            flow_log_id: f.flow_log_id.clone(),
+            resource_id: f.resource_id.clone(),
+            flow_log_status: f.flow_log_status.clone(),
+            deliver_logs_status: f.deliver_logs_status.clone(),
+            deliver_logs_error_message: f.deliver_logs_error_message.clone(),
+            traffic_type: f.traffic_type().map(|t| t.as_str().to_string()),
+            log_destination_type: f.log_destination_type().map(|t| t.as_str().to_string()),
+            log_destination: f.log_destination.clone(),
+            log_group_name: f.log_group_name.clone(),
+        }
+    }
+}
+
+impl From<FlowLogSnapshot> for FlowLog {
+    fn from(f: FlowLogSnapshot) -> Self {
+        FlowLog::builder()
+            .set_flow_log_id(f.flow_log_id)
+            .set_resource_id(f.resource_id)
+            .set_flow_log_status(f.flow_log_status)
+            .set_deliver_logs_status(f.deliver_logs_status)
+            .set_deliver_logs_error_message(f.deliver_logs_error_message)
+            .set_traffic_type(f.traffic_type.as_deref().map(TrafficType::from))
+            .set_log_destination_type(
+                f.log_destination_type
+                    .as_deref()
+                    .map(LogDestinationType::from),
+            )
+            .set_log_destination(f.log_destination)
+            .set_log_group_name(f.log_group_name)
+            .build()
+    }
+}
+
+impl From<EgressOnlyInternetGatewaySnapshot> for EgressOnlyInternetGateway {
+    fn from(g: EgressOnlyInternetGatewaySnapshot) -> Self {
+        EgressOnlyInternetGateway::builder()
+            .set_egress_only_internet_gateway_id(g.egress_only_internet_gateway_id)
+            .set_attachments(Some(
+                g.attachments
+                    .into_iter()
+                    .map(|a| {
+                        InternetGatewayAttachment::builder()
+                            .set_vpc_id(a.vpc_id)
+                            .set_state(a.state.as_deref().map(AttachmentStatus::from))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .set_tags(to_ec2_tags(g.tags))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NatGatewaySnapshot {
+    pub nat_gateway_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub subnet_id: Option<String>,
+    pub state: Option<String>,
+    pub addresses: Vec<NatGatewayAddressSnapshot>,
+    pub tags: Vec<Tag>,
+}
+
+impl From<&NatGateway> for NatGatewaySnapshot {
+    fn from(n: &NatGateway) -> Self {
+        NatGatewaySnapshot {
+            nat_gateway_id: n.nat_gateway_id.clone(),
+            vpc_id: n.vpc_id.clone(),
+            subnet_id: n.subnet_id.clone(),
+            state: n.state().map(|s| s.as_str().to_string()),
+            addresses: n
+                .nat_gateway_addresses()
+                .iter()
+                .map(|a| NatGatewayAddressSnapshot {
+                    allocation_id: a.allocation_id.clone(),
+                    network_interface_id: a.network_interface_id.clone(),
+                    private_ip: a.private_ip.clone(),
+                    public_ip: a.public_ip.clone(),
+                })
+                .collect(),
+            tags: from_ec2_tags(n.tags()),
+        }
+    }
+}
+
+impl From<NatGatewaySnapshot> for NatGateway {
+    fn from(n: NatGatewaySnapshot) -> Self {
+        NatGateway::builder()
+            .set_nat_gateway_id(n.nat_gateway_id)
+            .set_vpc_id(n.vpc_id)
+            .set_subnet_id(n.subnet_id)
+            .set_state(n.state.as_deref().map(NatGatewayState::from))
+            .set_nat_gateway_addresses(Some(
+                n.addresses
+                    .into_iter()
+                    .map(|a| {
+                        NatGatewayAddress::builder()
+                            .set_allocation_id(a.allocation_id)
+                            .set_network_interface_id(a.network_interface_id)
+                            .set_private_ip(a.private_ip)
+                            .set_public_ip(a.public_ip)
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .set_tags(to_ec2_tags(n.tags))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressSnapshot {
+    pub allocation_id: Option<String>,
+    pub association_id: Option<String>,
+    pub public_ip: Option<String>,
+    pub private_ip_address: Option<String>,
+    pub network_interface_id: Option<String>,
+    pub instance_id: Option<String>,
+    pub domain: Option<String>,
+    pub tags: Vec<Tag>,
+}
+
+impl From<&Address> for AddressSnapshot {
+    fn from(a: &Address) -> Self {
+        AddressSnapshot {
+            allocation_id: a.allocation_id.clone(),
+            association_id: a.association_id.clone(),
+            public_ip: a.public_ip.clone(),
+            private_ip_address: a.private_ip_address.clone(),
+            network_interface_id: a.network_interface_id.clone(),
+            instance_id: a.instance_id.clone(),
+            domain: a.domain().map(|d| d.as_str().to_string()),
+            tags: from_ec2_tags(a.tags()),
+        }
+    }
+}
+
+impl From<AddressSnapshot> for Address {
+    fn from(a: AddressSnapshot) -> Self {
+        Address::builder()
+            .set_allocation_id(a.allocation_id)
+            .set_association_id(a.association_id)
+            .set_public_ip(a.public_ip)
+            .set_private_ip_address(a.private_ip_address)
+            .set_network_interface_id(a.network_interface_id)
+            .set_instance_id(a.instance_id)
+            .set_domain(a.domain.as_deref().map(DomainType::from))
+            .set_tags(to_ec2_tags(a.tags))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetSnapshot {
+    pub id: Option<String>,
+    pub port: Option<i32>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetGroupSnapshot {
+    pub target_group_arn: Option<String>,
+    pub target_group_name: Option<String>,
+    pub protocol: Option<String>,
+    pub port: Option<i32>,
+    pub vpc_id: Option<String>,
+    pub target_type: Option<String>,
+    pub load_balancer_arns: Vec<String>,
+    pub targets: Vec<TargetSnapshot>,
+}
+
+impl From<&AWSTargetGroup> for TargetGroupSnapshot {
+    fn from(tg: &AWSTargetGroup) -> Self {
+        let group = &tg.target_group;
+        TargetGroupSnapshot {
+            target_group_arn: group.target_group_arn.clone(),
+            target_group_name: group.target_group_name.clone(),
+            protocol: group.protocol().map(|p| p.as_str().to_string()),
+            port: group.port,
+            vpc_id: group.vpc_id.clone(),
+            target_type: group.target_type().map(|t| t.as_str().to_string()),
+            load_balancer_arns: group.load_balancer_arns().to_vec(),
+            targets: tg
+                .targets
+                .iter()
+                .map(|t| TargetSnapshot {
+                    id: t.target().and_then(|t| t.id.clone()),
+                    port: t.target().and_then(|t| t.port),
+                    state: t
+                        .target_health()
+                        .and_then(|h| h.state())
+                        .map(|s| s.as_str().to_string()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<TargetGroupSnapshot> for AWSTargetGroup {
+    fn from(tg: TargetGroupSnapshot) -> Self {
+        AWSTargetGroup {
+            target_group: TargetGroup::builder()
+                .set_target_group_arn(tg.target_group_arn)
+                .set_target_group_name(tg.target_group_name)
+                .set_protocol(tg.protocol.as_deref().map(ProtocolEnum::from))
+                .set_port(tg.port)
+                .set_vpc_id(tg.vpc_id)
+                .set_target_type(tg.target_type.as_deref().map(TargetTypeEnum::from))
+                .set_load_balancer_arns(Some(tg.load_balancer_arns))
+                .build(),
+            targets: tg
+                .targets
+                .into_iter()
+                .map(|t| {
+                    TargetHealthDescription::builder()
+                        .target(
+                            TargetDescription::builder()
+                                .set_id(t.id)
+                                .set_port(t.port)
+                                .build(),
+                        )
+                        .set_target_health(t.state.as_deref().map(|s| {
+                            TargetHealth::builder()
+                                .state(TargetHealthStateEnum::from(s))
+                                .build()
+                        }))
+                        .build()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A VPC with the CIDR blocks associated with it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VpcSnapshot {
+    pub vpc_id: Option<String>,
+    pub cidr_blocks: Vec<String>,
+    #[serde(default)]
+    pub dhcp_options_id: Option<String>,
+}
+
+impl From<&Vpc> for VpcSnapshot {
+    fn from(v: &Vpc) -> Self {
+        let mut cidr_blocks: Vec<String> = v
+            .cidr_block_association_set()
+            .iter()
+            .filter(|a| {
+                a.cidr_block_state()
+                    .and_then(|s| s.state())
+                    .is_none_or(|s| *s == VpcCidrBlockStateCode::Associated)
+            })
+            .filter_map(|a| a.cidr_block.clone())
+            .collect();
+        if cidr_blocks.is_empty() {
+            cidr_blocks.extend(v.cidr_block.clone());
+        }
+        VpcSnapshot {
+            vpc_id: v.vpc_id.clone(),
+            cidr_blocks,
+            dhcp_options_id: v.dhcp_options_id.clone(),
+        }
+    }
+}
+
+impl From<VpcSnapshot> for Vpc {
+    fn from(v: VpcSnapshot) -> Self {
+        Vpc::builder()
+            .set_vpc_id(v.vpc_id)
+            .set_dhcp_options_id(v.dhcp_options_id)
+            .set_cidr_block(v.cidr_blocks.first().cloned())
+            .set_cidr_block_association_set(Some(
+                v.cidr_blocks
+                    .into_iter()
+                    .map(|c| VpcCidrBlockAssociation::builder().cidr_block(c).build())
+                    .collect(),
+            ))
+            .build()
+    }
+}
+
+/// A DHCP options set with the values of every option, e.g.
+/// `domain-name-servers`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DhcpOptionsSnapshot {
+    pub dhcp_options_id: Option<String>,
+    pub configurations: Vec<(String, Vec<String>)>,
+}
+
+impl From<&DhcpOptions> for DhcpOptionsSnapshot {
+    fn from(d: &DhcpOptions) -> Self {
+        DhcpOptionsSnapshot {
+            dhcp_options_id: d.dhcp_options_id.clone(),
+            configurations: d
+                .dhcp_configurations()
+                .iter()
+                .filter_map(|c| {
+                    Some((
+                        c.key()?.to_string(),
+                        c.values().iter().filter_map(|v| v.value.clone()).collect(),
+                    ))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<DhcpOptionsSnapshot> for DhcpOptions {
+    fn from(d: DhcpOptionsSnapshot) -> Self {
+        DhcpOptions::builder()
+            .set_dhcp_options_id(d.dhcp_options_id)
+            .set_dhcp_configurations(Some(
+                d.configurations
+                    .into_iter()
+                    .map(|(key, values)| {
+                        DhcpConfiguration::builder()
+                            .key(key)
+                            .set_values(Some(
+                                values
+                                    .into_iter()
+                                    .map(|v| AttributeValue::builder().value(v).build())
+                                    .collect(),
+                            ))
+                            .build()
+                    })
+                    .collect(),
+            ))
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VpcEndpointSnapshot {
+    pub vpc_endpoint_id: Option<String>,
+    pub vpc_endpoint_type: Option<String>,
+    pub vpc_id: Option<String>,
+    pub service_name: Option<String>,
+    pub state: Option<String>,
+    pub route_table_ids: Vec<String>,
+    pub subnet_ids: Vec<String>,
+    pub private_dns_enabled: Option<bool>,
+}
+
+impl From<&VpcEndpoint> for VpcEndpointSnapshot {
+    fn from(e: &VpcEndpoint) -> Self {
+        VpcEndpointSnapshot {
+            vpc_endpoint_id: e.vpc_endpoint_id.clone(),
+            vpc_endpoint_type: e.vpc_endpoint_type().map(|t| t.as_str().to_string()),
+            vpc_id: e.vpc_id.clone(),
+            service_name: e.service_name.clone(),
+            state: e.state().map(|s| s.as_str().to_string()),
+            route_table_ids: e.route_table_ids().to_vec(),
+            subnet_ids: e.subnet_ids().to_vec(),
+            private_dns_enabled: e.private_dns_enabled,
+        }
+    }
+}
+
+impl From<VpcEndpointSnapshot> for VpcEndpoint {
+    fn from(e: VpcEndpointSnapshot) -> Self {
+        VpcEndpoint::builder()
+            .set_vpc_endpoint_id(e.vpc_endpoint_id)
+            .set_vpc_endpoint_type(e.vpc_endpoint_type.as_deref().map(VpcEndpointType::from))
+            .set_vpc_id(e.vpc_id)
+            .set_service_name(e.service_name)
+            .set_state(e.state.as_deref().map(State::from))
+            .set_route_table_ids(Some(e.route_table_ids))
+            .set_subnet_ids(Some(e.subnet_ids))
+            .set_private_dns_enabled(e.private_dns_enabled)
+            .build()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkAclEntrySnapshot {
+    pub rule_number: Option<i32>,
+    pub protocol: Option<String>,
+    pub rule_action: Option<String>,
+    pub egress: Option<bool>,
+    pub cidr_block: Option<String>,
+    pub port_from: Option<i32>,
+    pub port_to: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkAclSnapshot {
+    pub network_acl_id: Option<String>,
+    pub vpc_id: Option<String>,
+    pub subnet_ids: Vec<String>,
+    pub entries: Vec<NetworkAclEntrySnapshot>,
+}
+
+impl From<&NetworkAcl> for NetworkAclSnapshot {
+    fn from(acl: &NetworkAcl) -> Self {
+        NetworkAclSnapshot {
+            network_acl_id: acl.network_acl_id.clone(),
+            vpc_id: acl.vpc_id.clone(),
+            subnet_ids: acl
+                .associations()
+                .iter()
+                .filter_map(|a| a.subnet_id.clone())
+                .collect(),
+            entries: acl
+                .entries()
+                .iter()
+                .map(|e| NetworkAclEntrySnapshot {
+                    rule_number: e.rule_number,
+                    protocol: e.protocol.clone(),
+                    rule_action: e.rule_action().map(|a| a.as_str().to_string()),
+                    egress: e.egress,
+                    cidr_block: e.cidr_block.clone(),
+                    port_from: e.port_range().and_then(|p| p.from),
+                    port_to: e.port_range().and_then(|p| p.to),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<NetworkAclSnapshot> for NetworkAcl {
+    fn from(acl: NetworkAclSnapshot) -> Self {
+        let associations = acl
+            .subnet_ids
+            .into_iter()
+            .map(|id| NetworkAclAssociation::builder().subnet_id(id).build())
+            .collect();
+        let entries = acl
+            .entries
+            .into_iter()
+            .map(|e| {
+                let port_range = (e.port_from.is_some() || e.port_to.is_some()).then(|| {
+                    PortRange::builder()
+                        .set_from(e.port_from)
+                        .set_to(e.port_to)
+                        .build()
+                });
+                NetworkAclEntry::builder()
+                    .set_rule_number(e.rule_number)
+                    .set_protocol(e.protocol)
+                    .set_rule_action(e.rule_action.as_deref().map(RuleAction::from))
+                    .set_egress(e.egress)
+                    .set_cidr_block(e.cidr_block)
+                    .set_port_range(port_range)
+                    .build()
+            })
+            .collect();
+        NetworkAcl::builder()
+            .set_network_acl_id(acl.network_acl_id)
+            .set_vpc_id(acl.vpc_id)
+            .set_associations(Some(associations))
+            .set_entries(Some(entries))
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::MinimalClusterInfoBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .subnets(vec!["subnet-1".to_string()])
+            .build()
+            .unwrap();
+        let data = AWSClusterData {
+            subnets: vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .availability_zone("us-east-1a")
+                .state(SubnetState::Available)
+                .owner_id("123456789012")
+                .tags(
+                    aws_sdk_ec2::types::Tag::builder()
+                        .key("kubernetes.io/role/internal-elb")
+                        .value("1")
+                        .build(),
+                )
+                .build()],
+            routetables: vec![RouteTable::builder()
+                .route_table_id("rtb-1")
+                .associations(
+                    RouteTableAssociation::builder()
+                        .subnet_id("subnet-1")
+                        .association_state(
+                            RouteTableAssociationState::builder()
+                                .state(RouteTableAssociationStateCode::Associated)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .routes(
+                    Route::builder()
+                        .destination_cidr_block("0.0.0.0/0")
+                        .nat_gateway_id("nat-1")
+                        .build(),
+                )
+                .build()],
+            load_balancers: vec![AWSLoadBalancer::ModernLoadBalancer((
+                LoadBalancer::builder()
+                    .load_balancer_name("mycluster-abcde-int")
+                    .load_balancer_arn("arn:int")
+                    .r#type(LoadBalancerTypeEnum::Network)
+                    .build(),
+                vec![Tag {
+                    key: Some("kubernetes.io/cluster/mycluster-abcde".to_string()),
+                    value: Some("owned".to_string()),
+                }],
+            ))],
+            load_balancer_enis: vec![],
+            instances: vec![],
+            hosted_zones: vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("/hostedzone/Z123")
+                    .name("mycluster.example.com.")
+                    .caller_reference("ref")
+                    .config(HostedZoneConfig::builder().private_zone(true).build())
+                    .build()
+                    .unwrap(),
+                resource_records: vec![ResourceRecordSet::builder()
+                    .name("api.mycluster.example.com.")
+                    .r#type(RrType::A)
+                    .alias_target(
+                        AliasTarget::builder()
+                            .hosted_zone_id("Z456")
+                            .dns_name("mycluster-abcde-int.elb.amazonaws.com")
+                            .evaluate_target_health(false)
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap()],
+                vpcs: vec!["vpc-1".to_string()],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }],
+            availability_zones: vec![],
+            instance_type_offerings: vec![],
+            resolver_query_log_configs: vec![],
+            resolver_query_logs: vec![],
+            nat_gateways: vec![],
+            internet_gateways: vec![],
+            egress_only_internet_gateways: vec![],
+            elastic_ips: vec![],
+            target_groups: vec![],
+            load_balancer_attributes: vec![],
+            vpc_endpoints: vec![],
+            network_acls: vec![],
+            subnet_network_interfaces: vec![],
+            denials: vec![],
+            vpcs: vec![],
+            dhcp_options: vec![],
+            vpc_attributes: vec![],
+            access_log_buckets: vec![],
+            health_events: vec![],
+            instance_profiles: vec![],
+            service_quotas: vec![],
+            flow_logs: vec![],
+            account_id: Some("123456789012".to_string()),
+            failed: vec![],
+        };
+        let json = serde_json::to_string(&Snapshot::new(&cluster_info, &data)).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        let (restored_info, restored) = snapshot.into_parts();
+        assert_eq!(restored_info.subnets, cluster_info.subnets);
+        assert_eq!(restored.subnets, data.subnets);
+        assert_eq!(restored.routetables, data.routetables);
+        assert_eq!(restored.account_id, data.account_id);
+        assert_eq!(
+            restored.hosted_zones[0].resource_records,
+            data.hosted_zones[0].resource_records
+        );
+        assert_eq!(
+            restored.hosted_zones[0].hosted_zone,
+            data.hosted_zones[0].hosted_zone
+        );
+        assert_eq!(restored.hosted_zones[0].vpcs, data.hosted_zones[0].vpcs);
+        assert_eq!(
+            restored.load_balancers[0].eni_description(),
+            data.load_balancers[0].eni_description()
+        );
+    }
+
+    #[test]
+    fn test_legacy_load_balancer_eni() {
+        let load_balancers = vec![AWSLoadBalancer::ModernLoadBalancer((
+            LoadBalancer::builder()
+                .load_balancer_name("lb")
+                .load_balancer_arn(
+                    "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/net/lb/abc",
+                )
+                .build(),
+            vec![],
+        ))];
+        let legacy = |description: &str| -> LoadBalancerEniSnapshot {
+            serde_json::from_value(serde_json::json!({
+                "network_interface_id": "eni-1",
+                "description": description,
+                "subnet_id": null,
+                "vpc_id": null,
+                "availability_zone": null,
+                "private_ip_address": null,
+                "status": null,
+                "groups": [],
+                "tags": [],
+            }))
+            .unwrap()
+        };
+        let eni = legacy("ELB net/lb/abc")
+            .into_lb_eni(&load_balancers)
+            .unwrap();
+        assert_eq!(
+            eni.load_balancer,
+            "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/net/lb/abc"
+        );
+        assert_eq!(eni.eni.network_interface_id(), Some("eni-1"));
+        assert!(legacy("ELB net/other/def")
+            .into_lb_eni(&load_balancers)
+            .is_none());
+    }
+}
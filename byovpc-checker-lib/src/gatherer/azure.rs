@@ -0,0 +1,599 @@
+//! Gathers the network resources of clusters on Azure from the Azure Resource
+//! Manager (ARM) API. It authenticates with the access token of the `az` CLI,
+//! so the CLI has to be logged in to the subscription of the cluster.
+//!
+//! ARM resource IDs are case insensitive - they are compared ignoring case
+//! throughout.
+
+use std::{collections::BTreeSet, error::Error, process::Command};
+
+use async_trait::async_trait;
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    gatherer::Gatherer,
+    types::{AzureClusterInfo, InvariantError},
+};
+
+const ARM_URL: &str = "https://management.azure.com";
+const NETWORK_API_VERSION: &str = "2023-09-01";
+const PRIVATE_DNS_API_VERSION: &str = "2020-06-01";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessToken {
+    access_token: String,
+}
+
+/// A page of a list of ARM resources.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceList {
+    #[serde(default)]
+    value: Vec<Value>,
+    next_link: Option<String>,
+}
+
+/// Whether two ARM resource IDs name the same resource.
+pub fn same_resource(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/')
+        .eq_ignore_ascii_case(b.trim_end_matches('/'))
+}
+
+/// The ID of the virtual network a subnet ID belongs to.
+pub fn virtual_network_of(subnet_id: &str) -> Option<&str> {
+    let index = subnet_id.to_ascii_lowercase().rfind("/subnets/")?;
+    Some(&subnet_id[..index])
+}
+
+/// The last segment of an ARM resource ID - the name of the resource.
+pub fn resource_name(id: &str) -> &str {
+    id.rsplit('/').next().unwrap_or(id)
+}
+
+fn string(value: &Value, pointer: &str) -> Option<String> {
+    value
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// ARM properties that are either set as a single value or as a list, e.g.
+/// `addressPrefix` and `addressPrefixes`.
+fn strings(value: &Value, single: &str, list: &str) -> Vec<String> {
+    let mut values: Vec<String> = value
+        .pointer(list)
+        .and_then(|v| v.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    values.extend(string(value, single));
+    values
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureSubnet {
+    pub id: String,
+    pub name: String,
+    pub address_prefixes: Vec<String>,
+    /// The ID of the network security group attached to the subnet.
+    pub network_security_group: Option<String>,
+    pub route_table: Option<String>,
+    pub nat_gateway: Option<String>,
+}
+
+impl AzureSubnet {
+    fn from_arm(value: &Value) -> Self {
+        AzureSubnet {
+            id: string(value, "/id").unwrap_or_default(),
+            name: string(value, "/name").unwrap_or_default(),
+            address_prefixes: strings(
+                value,
+                "/properties/addressPrefix",
+                "/properties/addressPrefixes",
+            ),
+            network_security_group: string(value, "/properties/networkSecurityGroup/id"),
+            route_table: string(value, "/properties/routeTable/id"),
+            nat_gateway: string(value, "/properties/natGateway/id"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureVirtualNetwork {
+    pub id: String,
+    pub name: String,
+    pub address_prefixes: Vec<String>,
+    pub subnets: Vec<AzureSubnet>,
+}
+
+impl AzureVirtualNetwork {
+    fn from_arm(value: &Value) -> Self {
+        AzureVirtualNetwork {
+            id: string(value, "/id").unwrap_or_default(),
+            name: string(value, "/name").unwrap_or_default(),
+            address_prefixes: strings(
+                value,
+                "/properties/addressSpace/addressPrefix",
+                "/properties/addressSpace/addressPrefixes",
+            ),
+            subnets: value
+                .pointer("/properties/subnets")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().map(AzureSubnet::from_arm).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureSecurityRule {
+    pub name: String,
+    pub priority: u64,
+    /// `Inbound` or `Outbound`.
+    pub direction: String,
+    /// `Allow` or `Deny`.
+    pub access: String,
+    /// `Tcp`, `Udp`, `Icmp` or `*`.
+    pub protocol: String,
+    pub source_address_prefixes: Vec<String>,
+    /// Single ports, ranges like `80-443` or `*`.
+    pub destination_port_ranges: Vec<String>,
+}
+
+impl AzureSecurityRule {
+    fn from_arm(value: &Value) -> Self {
+        AzureSecurityRule {
+            name: string(value, "/name").unwrap_or_default(),
+            priority: value
+                .pointer("/properties/priority")
+                .and_then(|v| v.as_u64())
+                .unwrap_or_default(),
+            direction: string(value, "/properties/direction").unwrap_or_default(),
+            access: string(value, "/properties/access").unwrap_or_default(),
+            protocol: string(value, "/properties/protocol").unwrap_or_default(),
+            source_address_prefixes: strings(
+                value,
+                "/properties/sourceAddressPrefix",
+                "/properties/sourceAddressPrefixes",
+            ),
+            destination_port_ranges: strings(
+                value,
+                "/properties/destinationPortRange",
+                "/properties/destinationPortRanges",
+            ),
+        }
+    }
+
+    /// Whether the rule applies to TCP traffic to the port.
+    pub fn matches_tcp_port(&self, port: u16) -> bool {
+        (self.protocol == "*" || self.protocol.eq_ignore_ascii_case("tcp"))
+            && self.destination_port_ranges.iter().any(|r| {
+                if r == "*" {
+                    return true;
+                }
+                match r.split_once('-') {
+                    Some((from, to)) => from
+                        .trim()
+                        .parse::<u16>()
+                        .ok()
+                        .zip(to.trim().parse::<u16>().ok())
+                        .is_some_and(|(from, to)| from <= port && port <= to),
+                    None => r.trim().parse::<u16>().ok() == Some(port),
+                }
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureNetworkSecurityGroup {
+    pub id: String,
+    pub name: String,
+    /// The rules added to the group - the default rules of Azure are not
+    /// included.
+    pub security_rules: Vec<AzureSecurityRule>,
+}
+
+impl AzureNetworkSecurityGroup {
+    fn from_arm(value: &Value) -> Self {
+        AzureNetworkSecurityGroup {
+            id: string(value, "/id").unwrap_or_default(),
+            name: string(value, "/name").unwrap_or_default(),
+            security_rules: value
+                .pointer("/properties/securityRules")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().map(AzureSecurityRule::from_arm).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureFrontendIp {
+    pub name: String,
+    pub private_ip_address: Option<String>,
+    /// The ID of the public IP address - `None` for internal load balancers.
+    pub public_ip_address: Option<String>,
+    /// The subnet of internal load balancers.
+    pub subnet: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureLoadBalancer {
+    pub id: String,
+    pub name: String,
+    pub frontend_ips: Vec<AzureFrontendIp>,
+}
+
+impl AzureLoadBalancer {
+    fn from_arm(value: &Value) -> Self {
+        AzureLoadBalancer {
+            id: string(value, "/id").unwrap_or_default(),
+            name: string(value, "/name").unwrap_or_default(),
+            frontend_ips: value
+                .pointer("/properties/frontendIPConfigurations")
+                .and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .map(|f| AzureFrontendIp {
+                            name: string(f, "/name").unwrap_or_default(),
+                            private_ip_address: string(f, "/properties/privateIPAddress"),
+                            public_ip_address: string(f, "/properties/publicIPAddress/id"),
+                            subnet: string(f, "/properties/subnet/id"),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzurePrivateDnsZone {
+    pub id: String,
+    pub name: String,
+    /// The IDs of the virtual networks linked to the zone - only linked
+    /// networks resolve its records.
+    pub virtual_networks: Vec<String>,
+}
+
+/// All data available in Azure once we gathered it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureClusterData {
+    pub virtual_networks: Vec<AzureVirtualNetwork>,
+    pub network_security_groups: Vec<AzureNetworkSecurityGroup>,
+    pub load_balancers: Vec<AzureLoadBalancer>,
+    pub private_dns_zones: Vec<AzurePrivateDnsZone>,
+    /// The data that could not be gathered, e.g. `load balancers`.
+    pub failed: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct AzureClient {
+    client: reqwest::Client,
+    access_token: String,
+}
+
+impl AzureClient {
+    /// Creates a client with the access token of `az login`.
+    pub fn from_cli() -> Result<Self, Box<dyn Error>> {
+        let output = Command::new("az")
+            .args(["account", "get-access-token", "--output", "json"])
+            .args(["--resource", ARM_URL])
+            .output()?;
+        if !output.status.success() {
+            return Err(Box::new(InvariantError {
+                msg: format!(
+                    "Could not retrieve an Azure access token - run `az login`: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            }));
+        }
+        let token: AccessToken = serde_json::from_slice(&output.stdout)?;
+        Ok(AzureClient {
+            client: reqwest::Client::new(),
+            access_token: token.access_token,
+        })
+    }
+
+    async fn get_url(&self, url: &str) -> Result<Value, Box<dyn Error>> {
+        debug!("Retrieving {}", url);
+        let json = self
+            .client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(json)
+    }
+
+    /// Retrieves an ARM resource by its ID.
+    pub async fn get(&self, id: &str, api_version: &str) -> Result<Value, Box<dyn Error>> {
+        self.get_url(&format!("{}{}?api-version={}", ARM_URL, id, api_version))
+            .await
+    }
+
+    /// Lists the ARM resources below a path, following all pages.
+    pub async fn list(&self, path: &str, api_version: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+        let mut url = format!("{}{}?api-version={}", ARM_URL, path, api_version);
+        let mut resources = vec![];
+        loop {
+            let page: ResourceList = serde_json::from_value(self.get_url(&url).await?)?;
+            resources.extend(page.value);
+            match page.next_link {
+                Some(next) => url = next,
+                None => return Ok(resources),
+            }
+        }
+    }
+}
+
+/// Gathers the virtual networks the cluster subnets are in.
+pub struct VirtualNetworkGatherer<'a> {
+    pub client: &'a AzureClient,
+    pub cluster_info: &'a AzureClusterInfo,
+}
+
+#[async_trait]
+impl<'a> Gatherer for VirtualNetworkGatherer<'a> {
+    type Resource = AzureVirtualNetwork;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let mut ids: BTreeSet<String> = BTreeSet::new();
+        for subnet in self.cluster_info.subnet_ids.iter() {
+            match virtual_network_of(subnet) {
+                Some(id) => {
+                    ids.insert(id.to_ascii_lowercase());
+                }
+                None => error!("{} is not the ID of a subnet", subnet),
+            }
+        }
+        let mut networks = vec![];
+        for id in ids {
+            let network = self.client.get(&id, NETWORK_API_VERSION).await?;
+            networks.push(AzureVirtualNetwork::from_arm(&network));
+        }
+        Ok(networks)
+    }
+}
+
+/// Gathers the network security groups of the cluster and of its subnets.
+pub struct NetworkSecurityGroupGatherer<'a> {
+    pub client: &'a AzureClient,
+    pub cluster_info: &'a AzureClusterInfo,
+    pub virtual_networks: &'a [AzureVirtualNetwork],
+}
+
+#[async_trait]
+impl<'a> Gatherer for NetworkSecurityGroupGatherer<'a> {
+    type Resource = AzureNetworkSecurityGroup;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let mut ids: BTreeSet<String> = self
+            .virtual_networks
+            .iter()
+            .flat_map(|n| n.subnets.iter())
+            .filter(|s| {
+                self.cluster_info
+                    .subnet_ids
+                    .iter()
+                    .any(|id| same_resource(id, &s.id))
+            })
+            .filter_map(|s| s.network_security_group.as_ref())
+            .map(|id| id.to_ascii_lowercase())
+            .collect();
+        ids.extend(
+            self.cluster_info
+                .network_security_group_id
+                .iter()
+                .map(|id| id.to_ascii_lowercase()),
+        );
+        let mut groups = vec![];
+        for id in ids {
+            let group = self.client.get(&id, NETWORK_API_VERSION).await?;
+            groups.push(AzureNetworkSecurityGroup::from_arm(&group));
+        }
+        Ok(groups)
+    }
+}
+
+/// Gathers the load balancers of the managed resource group of the cluster.
+pub struct LoadBalancerGatherer<'a> {
+    pub client: &'a AzureClient,
+    pub cluster_info: &'a AzureClusterInfo,
+}
+
+#[async_trait]
+impl<'a> Gatherer for LoadBalancerGatherer<'a> {
+    type Resource = AzureLoadBalancer;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let Some(ref resource_group) = self.cluster_info.managed_resource_group else {
+            return Ok(vec![]);
+        };
+        let path = format!(
+            "/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/loadBalancers",
+            self.cluster_info.subscription_id, resource_group
+        );
+        Ok(self
+            .client
+            .list(&path, NETWORK_API_VERSION)
+            .await?
+            .iter()
+            .map(AzureLoadBalancer::from_arm)
+            .collect())
+    }
+}
+
+/// Gathers the private DNS zones of the subscription for the base domain of
+/// the cluster and the virtual networks linked to them.
+pub struct PrivateDnsZoneGatherer<'a> {
+    pub client: &'a AzureClient,
+    pub cluster_info: &'a AzureClusterInfo,
+    pub base_domain: &'a str,
+}
+
+#[async_trait]
+impl<'a> Gatherer for PrivateDnsZoneGatherer<'a> {
+    type Resource = AzurePrivateDnsZone;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let path = format!(
+            "/subscriptions/{}/providers/Microsoft.Network/privateDnsZones",
+            self.cluster_info.subscription_id
+        );
+        let base_domain = self.base_domain.trim_end_matches('.').to_ascii_lowercase();
+        let all_zones = self.client.list(&path, PRIVATE_DNS_API_VERSION).await?;
+        let mut zones = vec![];
+        for zone in all_zones {
+            let id = string(&zone, "/id").unwrap_or_default();
+            let name = string(&zone, "/name").unwrap_or_default();
+            let zone_name = name.trim_end_matches('.').to_ascii_lowercase();
+            if base_domain != zone_name && !base_domain.ends_with(&format!(".{}", zone_name)) {
+                continue;
+            }
+            let links = self
+                .client
+                .list(
+                    &format!("{}/virtualNetworkLinks", id),
+                    PRIVATE_DNS_API_VERSION,
+                )
+                .await?;
+            zones.push(AzurePrivateDnsZone {
+                id,
+                name,
+                virtual_networks: links
+                    .iter()
+                    .filter_map(|l| string(l, "/properties/virtualNetwork/id"))
+                    .collect(),
+            });
+        }
+        Ok(zones)
+    }
+}
+
+/// Gathers the network resources of a cluster on Azure - data that can not be
+/// retrieved is left empty and recorded in `failed`.
+pub async fn gather(
+    client: &AzureClient,
+    cluster_info: &AzureClusterInfo,
+    base_domain: Option<&str>,
+) -> AzureClusterData {
+    let mut failed = vec![];
+    let mut gathering_failed = |what: &str, e: Box<dyn Error>| {
+        error!("Could not retrieve {}: {}", what, e);
+        failed.push(what.to_string());
+    };
+    info!("Fetching virtual networks");
+    let virtual_networks = VirtualNetworkGatherer {
+        client,
+        cluster_info,
+    }
+    .gather()
+    .await
+    .unwrap_or_else(|e| {
+        gathering_failed("virtual networks", e);
+        vec![]
+    });
+    info!("Fetching network security groups");
+    let network_security_groups = NetworkSecurityGroupGatherer {
+        client,
+        cluster_info,
+        virtual_networks: &virtual_networks,
+    }
+    .gather()
+    .await
+    .unwrap_or_else(|e| {
+        gathering_failed("network security groups", e);
+        vec![]
+    });
+    info!("Fetching load balancers");
+    let load_balancers = LoadBalancerGatherer {
+        client,
+        cluster_info,
+    }
+    .gather()
+    .await
+    .unwrap_or_else(|e| {
+        gathering_failed("load balancers", e);
+        vec![]
+    });
+    let private_dns_zones = match base_domain {
+        Some(base_domain) => {
+            info!("Fetching private DNS zones");
+            PrivateDnsZoneGatherer {
+                client,
+                cluster_info,
+                base_domain,
+            }
+            .gather()
+            .await
+            .unwrap_or_else(|e| {
+                gathering_failed("private DNS zones", e);
+                vec![]
+            })
+        }
+        None => vec![],
+    };
+    AzureClusterData {
+        virtual_networks,
+        network_security_groups,
+        load_balancers,
+        private_dns_zones,
+        failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_from_arm() {
+        let network = AzureVirtualNetwork::from_arm(&json!({
+            "id": "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/virtualNetworks/vnet",
+            "name": "vnet",
+            "properties": {
+                "addressSpace": {"addressPrefixes": ["10.0.0.0/16"]},
+                "subnets": [{
+                    "id": "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/virtualNetworks/vnet/subnets/nodes",
+                    "name": "nodes",
+                    "properties": {
+                        "addressPrefix": "10.0.0.0/24",
+                        "networkSecurityGroup": {"id": "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/networkSecurityGroups/nsg"}
+                    }
+                }]
+            }
+        }));
+        assert_eq!(network.address_prefixes, vec!["10.0.0.0/16"]);
+        assert_eq!(network.subnets[0].address_prefixes, vec!["10.0.0.0/24"]);
+        assert_eq!(
+            virtual_network_of(&network.subnets[0].id.to_uppercase())
+                .map(|id| same_resource(id, &network.id)),
+            Some(true)
+        );
+        let rule = AzureSecurityRule::from_arm(&json!({
+            "name": "deny-api",
+            "properties": {
+                "priority": 100,
+                "direction": "Inbound",
+                "access": "Deny",
+                "protocol": "Tcp",
+                "sourceAddressPrefix": "*",
+                "destinationPortRanges": ["22", "6000-7000"]
+            }
+        }));
+        assert!(rule.matches_tcp_port(6443));
+        assert!(!rule.matches_tcp_port(443));
+    }
+}
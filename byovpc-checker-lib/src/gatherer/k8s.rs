@@ -0,0 +1,316 @@
+//! A minimal client for the Kubernetes API of the cluster. It reads the
+//! credentials of a kubeconfig - e.g. written by `oc login` - and gathers the
+//! objects that reference AWS resources, so they can be cross-checked with
+//! what exists in AWS.
+//!
+//! Only token and client certificate authentication are supported, exec
+//! plugins are not.
+
+use std::{error::Error, path::Path};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{gatherer::Gatherer, types::InvariantError};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Kubeconfig {
+    current_context: Option<String>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedContext {
+    name: String,
+    context: Context,
+}
+
+#[derive(Debug, Deserialize)]
+struct Context {
+    cluster: String,
+    user: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: ClusterConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ClusterConfig {
+    server: String,
+    certificate_authority_data: Option<String>,
+    certificate_authority: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedUser {
+    name: String,
+    user: User,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct User {
+    token: Option<String>,
+    client_certificate_data: Option<String>,
+    client_key_data: Option<String>,
+    client_certificate: Option<String>,
+    client_key: Option<String>,
+}
+
+/// Reads PEM data that is either inlined base64 encoded or stored in a file.
+fn pem(data: &Option<String>, path: &Option<String>) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    match (data, path) {
+        (Some(data), _) => Ok(Some(STANDARD.decode(data.trim())?)),
+        (None, Some(path)) => Ok(Some(std::fs::read(path)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+#[derive(Debug)]
+pub struct KubeClient {
+    client: reqwest::Client,
+    server: String,
+    token: Option<String>,
+}
+
+impl KubeClient {
+    /// Creates a client for the current context of the kubeconfig.
+    pub fn from_kubeconfig(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let invalid = |msg: String| InvariantError {
+            msg: format!("Invalid kubeconfig {}: {}", path.display(), msg),
+        };
+        let config: Kubeconfig = serde_yaml::from_str(&std::fs::read_to_string(path)?)?;
+        let current = config
+            .current_context
+            .ok_or_else(|| invalid("no current-context".to_string()))?;
+        let context = config
+            .contexts
+            .iter()
+            .find(|c| c.name == current)
+            .ok_or_else(|| invalid(format!("context {} not found", current)))?;
+        let cluster = config
+            .clusters
+            .iter()
+            .find(|c| c.name == context.context.cluster)
+            .ok_or_else(|| invalid(format!("cluster {} not found", context.context.cluster)))?;
+        let user = config
+            .users
+            .into_iter()
+            .find(|u| u.name == context.context.user)
+            .map(|u| u.user)
+            .unwrap_or_default();
+        debug!(
+            "Using context {} of kubeconfig {} for {}",
+            current,
+            path.display(),
+            cluster.cluster.server
+        );
+        let mut builder = reqwest::Client::builder()
+            .danger_accept_invalid_certs(cluster.cluster.insecure_skip_tls_verify);
+        if let Some(ca) = pem(
+            &cluster.cluster.certificate_authority_data,
+            &cluster.cluster.certificate_authority,
+        )? {
+            for certificate in reqwest::Certificate::from_pem_bundle(&ca)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let (Some(mut certificate), Some(key)) = (
+            pem(&user.client_certificate_data, &user.client_certificate)?,
+            pem(&user.client_key_data, &user.client_key)?,
+        ) {
+            certificate.extend(key);
+            builder = builder.identity(reqwest::Identity::from_pem(&certificate)?);
+        } else if user.token.is_none() {
+            return Err(Box::new(invalid(format!(
+                "user {} has neither a token nor a client certificate",
+                context.context.user
+            ))));
+        }
+        Ok(KubeClient {
+            client: builder.build()?,
+            server: cluster.cluster.server.trim_end_matches('/').to_string(),
+            token: user.token,
+        })
+    }
+
+    /// Retrieves an API path, e.g. `/api/v1/nodes`.
+    pub async fn get(&self, path: &str) -> Result<Value, Box<dyn Error>> {
+        let mut request = self.client.get(format!("{}{}", self.server, path));
+        if let Some(ref token) = self.token {
+            request = request.bearer_auth(token);
+        }
+        let json = request.send().await?.error_for_status()?.json().await?;
+        Ok(json)
+    }
+
+    async fn items(&self, path: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+        Ok(self.get(path).await?["items"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// A Service of type `LoadBalancer`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadBalancerService {
+    pub namespace: String,
+    pub name: String,
+    /// The DNS names of the load balancers in the status of the Service -
+    /// empty while the load balancer is provisioned.
+    pub hostnames: Vec<String>,
+}
+
+impl LoadBalancerService {
+    fn from_json(service: &Value) -> Option<Self> {
+        if service["spec"]["type"] != "LoadBalancer" {
+            return None;
+        }
+        Some(LoadBalancerService {
+            namespace: service["metadata"]["namespace"].as_str()?.to_string(),
+            name: service["metadata"]["name"].as_str()?.to_string(),
+            hostnames: service["status"]["loadBalancer"]["ingress"]
+                .as_array()
+                .map(|ingress| {
+                    ingress
+                        .iter()
+                        .filter_map(|i| i["hostname"].as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// A node of the cluster.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Node {
+    pub name: String,
+    /// The ID of the EC2 instance, from the provider ID
+    /// `aws:///<zone>/<instance id>`.
+    pub instance_id: Option<String>,
+}
+
+impl Node {
+    fn from_json(node: &Value) -> Option<Self> {
+        Some(Node {
+            name: node["metadata"]["name"].as_str()?.to_string(),
+            instance_id: node["spec"]["providerID"]
+                .as_str()
+                .and_then(|p| p.strip_prefix("aws://"))
+                .and_then(|p| p.rsplit('/').next())
+                .filter(|id| id.starts_with("i-"))
+                .map(str::to_string),
+        })
+    }
+}
+
+pub struct LoadBalancerServiceGatherer<'a> {
+    pub client: &'a KubeClient,
+}
+
+#[async_trait]
+impl<'a> Gatherer for LoadBalancerServiceGatherer<'a> {
+    type Resource = LoadBalancerService;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let services = self.client.items("/api/v1/services").await?;
+        Ok(services
+            .iter()
+            .filter_map(LoadBalancerService::from_json)
+            .collect())
+    }
+}
+
+pub struct NodeGatherer<'a> {
+    pub client: &'a KubeClient,
+}
+
+#[async_trait]
+impl<'a> Gatherer for NodeGatherer<'a> {
+    type Resource = Node;
+
+    async fn gather(&self) -> Result<Vec<Self::Resource>, Box<dyn Error>> {
+        let nodes = self.client.items("/api/v1/nodes").await?;
+        Ok(nodes.iter().filter_map(Node::from_json).collect())
+    }
+}
+
+/// The objects of the cluster that reference AWS resources.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KubernetesData {
+    pub services: Vec<LoadBalancerService>,
+    pub nodes: Vec<Node>,
+    /// The default domain of the routes, from the cluster ingress config.
+    pub ingress_domain: Option<String>,
+}
+
+/// Gathers the objects of the cluster from the Kubernetes API.
+pub async fn gather(client: &KubeClient) -> Result<KubernetesData, Box<dyn Error>> {
+    let services = LoadBalancerServiceGatherer { client }.gather().await?;
+    let nodes = NodeGatherer { client }.gather().await?;
+    let ingress = client
+        .get("/apis/config.openshift.io/v1/ingresses/cluster")
+        .await?;
+    Ok(KubernetesData {
+        services,
+        nodes,
+        ingress_domain: ingress["spec"]["domain"].as_str().map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_objects() {
+        assert_eq!(
+            LoadBalancerService::from_json(&json!({
+                "metadata": {"namespace": "openshift-ingress", "name": "router-default"},
+                "spec": {"type": "LoadBalancer"},
+                "status": {"loadBalancer": {"ingress": [{"hostname": "a-1.elb.amazonaws.com"}]}},
+            })),
+            Some(LoadBalancerService {
+                namespace: "openshift-ingress".to_string(),
+                name: "router-default".to_string(),
+                hostnames: vec!["a-1.elb.amazonaws.com".to_string()],
+            })
+        );
+        assert_eq!(
+            LoadBalancerService::from_json(&json!({
+                "metadata": {"namespace": "default", "name": "kubernetes"},
+                "spec": {"type": "ClusterIP"},
+            })),
+            None
+        );
+        assert_eq!(
+            Node::from_json(&json!({
+                "metadata": {"name": "ip-10-0-1-2.ec2.internal"},
+                "spec": {"providerID": "aws:///us-east-1a/i-0123"},
+            })),
+            Some(Node {
+                name: "ip-10-0-1-2.ec2.internal".to_string(),
+                instance_id: Some("i-0123".to_string()),
+            })
+        );
+    }
+}
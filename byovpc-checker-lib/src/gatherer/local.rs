@@ -0,0 +1,195 @@
+//! Gathers facts about the machine the tool runs on, to diagnose problems of
+//! the local environment.
+
+use std::{future::poll_fn, process::Command, time::Duration};
+
+use hyper::{service::Service, Uri};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::{lookup_host, TcpStream},
+    time::timeout,
+};
+use url::Url;
+
+use crate::gatherer::aws::proxy_connector;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The endpoints outside of AWS every cluster needs to reach: OCM, the SSO
+/// and the registries of the release images.
+const REQUIRED_ENDPOINTS: [&str; 4] = [
+    "api.openshift.com",
+    "sso.redhat.com",
+    "quay.io",
+    "registry.redhat.io",
+];
+
+/// The AWS APIs the cluster needs to reach in its region - S3 stores the
+/// images of the internal registry.
+const REQUIRED_AWS_SERVICES: [&str; 5] = ["ec2", "elasticloadbalancing", "sts", "route53", "s3"];
+
+/// Returns the first line of `<binary> <arg>` - `None` if the binary is not
+/// installed or fails.
+pub fn binary_version(binary: &str, arg: &str) -> Option<String> {
+    debug!("Retrieving version of {}", binary);
+    let output = Command::new(binary).arg(arg).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|l| l.trim().to_string())
+}
+
+/// An endpoint the cluster needs to reach, directly or through its proxy.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressProbe {
+    /// `<host>:<port>`.
+    pub endpoint: String,
+    /// Why no connection could be opened (or tunneled through the proxy) -
+    /// `None` if it worked.
+    pub error: Option<String>,
+}
+
+/// The result of probing the egress through the proxy of the cluster.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyEgress {
+    /// The proxy without its credentials.
+    pub proxy: String,
+    /// Why the proxy itself is not reachable - the endpoints are not probed
+    /// then.
+    pub error: Option<String>,
+    pub probes: Vec<EgressProbe>,
+}
+
+/// The result of connecting to the endpoints the cluster needs from the
+/// machine the tool runs on, through the proxy of the cluster if it has one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveProbes {
+    /// The proxy without its credentials - `None` if the endpoints are
+    /// connected to directly.
+    pub proxy: Option<String>,
+    /// Why the proxy itself is not reachable - the endpoints are not probed
+    /// then.
+    pub error: Option<String>,
+    pub probes: Vec<EgressProbe>,
+}
+
+/// The endpoints the cluster needs to reach, including the AWS APIs of its
+/// region.
+pub fn required_endpoints(region: Option<&str>) -> Vec<String> {
+    let mut endpoints: Vec<String> = REQUIRED_ENDPOINTS
+        .iter()
+        .map(|e| format!("{}:443", e))
+        .collect();
+    if let Some(region) = region {
+        endpoints.extend(REQUIRED_AWS_SERVICES.iter().map(|s| match *s {
+            // Route 53 is a global service.
+            "route53" => "route53.amazonaws.com:443".to_string(),
+            s => format!("{}.{}.amazonaws.com:443", s, region),
+        }));
+    }
+    endpoints
+}
+
+/// Tunnels a connection with `CONNECT` through the proxy to the endpoint.
+async fn probe_endpoint(proxy: &str, endpoint: &str) -> Result<(), String> {
+    let mut connector = proxy_connector(proxy).ok_or("invalid proxy URL")?;
+    let uri: Uri = format!("https://{}", endpoint)
+        .parse()
+        .map_err(|e| format!("invalid endpoint: {}", e))?;
+    debug!("Connecting to {} through proxy {}", endpoint, proxy);
+    let connect = async {
+        poll_fn(|cx| connector.poll_ready(cx)).await?;
+        connector.call(uri).await
+    };
+    match timeout(PROBE_TIMEOUT, connect).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no connection within {}s", PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+/// Verifies the endpoints can be reached through the proxy - the proxy is
+/// reported without its credentials.
+pub async fn probe_egress(proxy: &str, endpoints: &[String]) -> ProxyEgress {
+    let mut proxy_url: Option<Url> = proxy.parse().ok();
+    if let Some(ref mut url) = proxy_url {
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+    }
+    let mut egress = ProxyEgress {
+        proxy: proxy_url.map_or(proxy.to_string(), |u| u.to_string()),
+        error: probe_proxy(proxy).await.err(),
+        probes: vec![],
+    };
+    if egress.error.is_some() {
+        return egress;
+    }
+    for endpoint in endpoints {
+        egress.probes.push(EgressProbe {
+            endpoint: endpoint.clone(),
+            error: probe_endpoint(proxy, endpoint).await.err(),
+        });
+    }
+    egress
+}
+
+/// Resolves the endpoint and opens a TCP connection to it.
+async fn connect_endpoint(endpoint: &str) -> Result<(), String> {
+    debug!("Connecting to {}", endpoint);
+    let addrs = match timeout(PROBE_TIMEOUT, lookup_host(endpoint)).await {
+        Ok(Ok(addrs)) => addrs.collect::<Vec<_>>(),
+        Ok(Err(e)) => return Err(format!("could not resolve: {}", e)),
+        Err(_) => return Err(format!("not resolved within {}s", PROBE_TIMEOUT.as_secs())),
+    };
+    let addr = addrs.first().ok_or("could not resolve: no addresses")?;
+    match timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("{} ({})", e, addr.ip())),
+        Err(_) => Err(format!(
+            "no connection to {} within {}s",
+            addr.ip(),
+            PROBE_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Connects to the endpoints, through the proxy if one is given.
+pub async fn run_active_probes(proxy: Option<&str>, endpoints: &[String]) -> ActiveProbes {
+    if let Some(proxy) = proxy {
+        let egress = probe_egress(proxy, endpoints).await;
+        return ActiveProbes {
+            proxy: Some(egress.proxy),
+            error: egress.error,
+            probes: egress.probes,
+        };
+    }
+    let mut probes = vec![];
+    for endpoint in endpoints {
+        probes.push(EgressProbe {
+            endpoint: endpoint.clone(),
+            error: connect_endpoint(endpoint).await.err(),
+        });
+    }
+    ActiveProbes {
+        proxy: None,
+        error: None,
+        probes,
+    }
+}
+
+/// Opens a connection to the proxy to verify it is reachable.
+pub async fn probe_proxy(proxy: &str) -> Result<(), String> {
+    let url: Url = proxy.parse().map_err(|e| format!("invalid URL: {}", e))?;
+    let host = url.host_str().ok_or("the URL has no host")?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    debug!("Connecting to proxy {}:{}", host, port);
+    match timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("no connection within {}s", PROBE_TIMEOUT.as_secs())),
+    }
+}
@@ -0,0 +1,190 @@
+//! A minimal client for the OCM API. It reuses the credentials the `ocm` CLI
+//! stores after `ocm login`, or an offline token in `OCM_TOKEN`, so the CLI
+//! does not have to be installed.
+
+use std::{env, error::Error, fs::File, io::BufReader, path::PathBuf, sync::Mutex};
+
+use log::debug;
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use crate::types::InvariantError;
+
+const DEFAULT_OCM_URL: &str = "https://api.openshift.com";
+const DEFAULT_TOKEN_URL: &str =
+    "https://sso.redhat.com/auth/realms/redhat-external/protocol/openid-connect/token";
+const DEFAULT_CLIENT_ID: &str = "cloud-services";
+
+/// The configuration written by `ocm login`.
+#[derive(Debug, Default, Deserialize)]
+struct OcmConfig {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    client_id: Option<String>,
+    token_url: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// The locations `ocm` reads its configuration from, in order.
+fn config_paths() -> Vec<PathBuf> {
+    if let Ok(path) = env::var("OCM_CONFIG") {
+        return vec![PathBuf::from(path)];
+    }
+    let mut paths = vec![];
+    if let Ok(config_home) = env::var("XDG_CONFIG_HOME") {
+        paths.push(PathBuf::from(config_home).join("ocm").join("ocm.json"));
+    }
+    if let Ok(home) = env::var("HOME") {
+        paths.push(
+            PathBuf::from(&home)
+                .join(".config")
+                .join("ocm")
+                .join("ocm.json"),
+        );
+        paths.push(PathBuf::from(home).join(".ocm.json"));
+    }
+    paths
+}
+
+#[derive(Debug)]
+pub struct OcmClient {
+    client: reqwest::Client,
+    config: OcmConfig,
+    access_token: Mutex<Option<String>>,
+}
+
+impl OcmClient {
+    /// Creates a client from the offline token in `OCM_TOKEN` or the
+    /// configuration of `ocm login` - returns `None` if neither is available.
+    pub fn from_config() -> Option<Self> {
+        if let Ok(token) = env::var("OCM_TOKEN").map(|t| t.trim().to_string()) {
+            if !token.is_empty() {
+                debug!("Using the OCM offline token from OCM_TOKEN");
+                return Some(OcmClient::with_config(OcmConfig {
+                    refresh_token: Some(token),
+                    ..Default::default()
+                }));
+            }
+        }
+        let path = config_paths().into_iter().find(|p| p.exists())?;
+        debug!("Reading OCM configuration from {}", path.display());
+        let reader = BufReader::new(File::open(&path).ok()?);
+        let config: OcmConfig = serde_json::from_reader(reader).ok()?;
+        if config.access_token.is_none() && config.refresh_token.is_none() {
+            return None;
+        }
+        Some(OcmClient::with_config(config))
+    }
+
+    fn with_config(config: OcmConfig) -> Self {
+        OcmClient {
+            client: reqwest::Client::new(),
+            access_token: Mutex::new(config.access_token.clone()),
+            config,
+        }
+    }
+
+    /// The URL of the OCM API.
+    pub fn url(&self) -> &str {
+        self.config.url.as_deref().unwrap_or(DEFAULT_OCM_URL)
+    }
+
+    /// Exchanges the refresh token for a new access token - access tokens
+    /// are only valid for a few minutes.
+    async fn refresh(&self) -> Result<String, Box<dyn Error>> {
+        let Some(ref refresh_token) = self.config.refresh_token else {
+            return Err(Box::new(InvariantError {
+                msg: "OCM access token expired and no refresh token is available - run `ocm login`"
+                    .to_string(),
+            }));
+        };
+        let token_url = self
+            .config
+            .token_url
+            .as_deref()
+            .unwrap_or(DEFAULT_TOKEN_URL);
+        let client_id = self
+            .config
+            .client_id
+            .as_deref()
+            .unwrap_or(DEFAULT_CLIENT_ID);
+        debug!("Refreshing OCM access token from {}", token_url);
+        let response: TokenResponse = self
+            .client
+            .post(token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("client_id", client_id),
+                ("refresh_token", refresh_token),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        *self.access_token.lock().unwrap() = Some(response.access_token.clone());
+        Ok(response.access_token)
+    }
+
+    async fn send(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        token: &str,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        self.client
+            .get(format!("{}{}", self.url().trim_end_matches('/'), path))
+            .query(query)
+            .bearer_auth(token)
+            .send()
+            .await
+    }
+
+    /// Retrieves an OCM API path, e.g. `/api/clusters_mgmt/v1/clusters/<id>`.
+    pub async fn get(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let token = self.access_token.lock().unwrap().clone();
+        let token = match token {
+            Some(token) => token,
+            None => self.refresh().await?,
+        };
+        let mut response = self.send(path, query, &token).await?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            let token = self.refresh().await?;
+            response = self.send(path, query, &token).await?;
+        }
+        let json = response.error_for_status()?.json().await?;
+        debug!("OCM information for {}: {:}", path, json);
+        Ok(json)
+    }
+
+    /// Finds a cluster by its ID, external ID or name - like `ocm describe
+    /// cluster` does.
+    pub async fn cluster(&self, clusterid: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let search = format!(
+            "id = '{0}' or external_id = '{0}' or name = '{0}'",
+            clusterid.replace('\'', "''")
+        );
+        let clusters = self
+            .get("/api/clusters_mgmt/v1/clusters", &[("search", &search)])
+            .await?;
+        let items = clusters["items"].as_array().cloned().unwrap_or_default();
+        match items.as_slice() {
+            [cluster] => Ok(cluster.clone()),
+            [] => Err(Box::new(InvariantError {
+                msg: format!("No cluster found for {}", clusterid),
+            })),
+            _ => Err(Box::new(InvariantError {
+                msg: format!("{} clusters found for {}", items.len(), clusterid),
+            })),
+        }
+    }
+}
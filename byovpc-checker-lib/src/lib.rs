@@ -0,0 +1,50 @@
+//! Gathers the AWS setup of OpenShift clusters and checks it for problems.
+//! It focuses on bring-your-own-VPC checks - meaning the networking setup was
+//! performed by the user, not the installer.
+//!
+//! The `byovpc-checker` binary is a command line interface to this crate.
+//! Other tools can embed the checks without running the binary:
+//!
+//! ```no_run
+//! use byovpc_checker_lib::{
+//!     evaluate, gather, Check, CheckSetup, GatherOptions, MinimalClusterInfo,
+//! };
+//!
+//! # async fn run(aws_config: aws_config::SdkConfig) -> Result<(), Box<dyn std::error::Error>> {
+//! let cluster_info = MinimalClusterInfo::get_cluster_info(&"<cluster id>".to_string()).await?;
+//! let data = gather(&cluster_info, &aws_config, &GatherOptions::default()).await?;
+//! let setup = CheckSetup {
+//!     cluster_info: &cluster_info,
+//!     data: &data,
+//!     cluster_tag_values: &[],
+//!     egress_ips: &[],
+//!     custom_rules: &[],
+//!     kubernetes: None,
+//!     proxy_egress: None,
+//!     active_probes: None,
+//! };
+//! // Checks whose data could not be gathered are skipped.
+//! for (check, result) in evaluate(&[Check::Network, Check::LoadBalancer], &setup, &[]) {
+//!     println!("{:?}: {}", check, serde_json::to_string(&result).unwrap());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod baseline;
+pub mod checks;
+pub mod diff;
+pub mod gatherer;
+pub mod output;
+pub mod selection;
+pub mod types;
+
+pub use gatherer::aws::{gather, AWSClusterData, GatherError, GatherOptions};
+pub use selection::{
+    evaluate, gathered_data_sets, required_data_sets, run_checks, setup_checks, Check, CheckSetup,
+    SeverityOverride,
+};
+pub use types::{MinimalClusterInfo, Severity, VerificationResult, Verifier};
+
+/// The name the tool reports itself with, e.g. in SARIF logs.
+pub const TOOL_NAME: &str = "byovpc-checker";
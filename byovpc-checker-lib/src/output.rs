@@ -0,0 +1,347 @@
+//! Renderers present the results of a run of the checks in one output format.
+//!
+//! Every format implements [`Renderer`] and receives the full
+//! [`CheckRunReport`], so adding a format does not touch the code running the
+//! checks. The flat terminal renderers live here, the other formats in their
+//! own module.
+
+pub mod html;
+pub mod junit;
+pub mod sarif;
+pub mod terraform;
+pub mod tree;
+
+use std::{collections::HashMap, io::Write};
+
+use itertools::Itertools;
+
+use crate::{
+    gatherer::aws::Environment,
+    types::{FindingCode, Severity, TagDiff, VerificationResult},
+};
+
+/// Everything known about a run of the checks.
+#[derive(Debug)]
+pub struct CheckRunReport {
+    /// How the data was gathered - `None` if it is unknown, e.g. for old
+    /// snapshots.
+    pub environment: Option<Environment>,
+    /// The results to report, after the policy was applied.
+    pub results: Vec<VerificationResult>,
+    /// The support articles describing how to fix the findings.
+    pub articles: HashMap<FindingCode, String>,
+}
+
+/// Writes a report in one output format.
+pub trait Renderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// An entry of a report: a single result, or all tag findings of a check for
+/// one resource, merged so the expected and actual tags can be shown side by
+/// side.
+pub enum Entry<'a> {
+    Result(&'a VerificationResult),
+    Tags {
+        results: Vec<&'a VerificationResult>,
+        diff: TagDiff,
+    },
+}
+
+impl<'a> Entry<'a> {
+    /// The result summarizing the entry.
+    pub fn summary(&self) -> VerificationResult {
+        match self {
+            Entry::Result(res) => VerificationResult {
+                message: res.message.clone(),
+                severity: res.severity,
+                code: res.code,
+                check_id: res.check_id,
+                resource_type: res.resource_type,
+                resource_id: res.resource_id.clone(),
+                evidence: res.evidence.clone(),
+            },
+            Entry::Tags { results, diff } => VerificationResult {
+                message: format!("Tags of {} differ from the expected tags", diff.resource),
+                severity: results
+                    .iter()
+                    .map(|r| r.severity)
+                    .max()
+                    .unwrap_or(Severity::Info),
+                code: None,
+                check_id: results[0].check_id,
+                resource_type: results[0].resource_type,
+                resource_id: Some(diff.resource.clone()),
+                evidence: None,
+            },
+        }
+    }
+
+    /// The codes of all results of the entry.
+    pub fn codes(&self) -> Vec<FindingCode> {
+        match self {
+            Entry::Result(res) => res.code.into_iter().collect(),
+            Entry::Tags { results, .. } => results.iter().filter_map(|r| r.code).unique().collect(),
+        }
+    }
+}
+
+/// Escapes text for element content and attribute values of the XML and HTML
+/// reports.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// The results grouped by check ID, in the order the checks reported them.
+pub fn group_by_check(
+    results: &[VerificationResult],
+) -> Vec<(Option<&'static str>, Vec<&VerificationResult>)> {
+    let mut groups: Vec<(Option<&'static str>, Vec<&VerificationResult>)> = vec![];
+    for res in results.iter() {
+        match groups.iter_mut().find(|(id, _)| *id == res.check_id) {
+            Some((_, group)) => group.push(res),
+            None => groups.push((res.check_id, vec![res])),
+        }
+    }
+    groups
+}
+
+/// The entries of the results, in the order the results were reported.
+pub fn entries<'a>(results: impl IntoIterator<Item = &'a VerificationResult>) -> Vec<Entry<'a>> {
+    let mut entries: Vec<Entry> = vec![];
+    for res in results {
+        let Some(diff) = TagDiff::of(res) else {
+            entries.push(Entry::Result(res));
+            continue;
+        };
+        let existing = entries.iter_mut().find_map(|e| match e {
+            Entry::Tags {
+                results,
+                diff: existing,
+            } if results[0].check_id == res.check_id && existing.resource == diff.resource => {
+                Some((results, existing))
+            }
+            _ => None,
+        });
+        match existing {
+            Some((results, existing)) => {
+                results.push(res);
+                existing.merge(diff);
+            }
+            None => entries.push(Entry::Tags {
+                results: vec![res],
+                diff,
+            }),
+        }
+    }
+    entries
+}
+
+/// The number of results of each severity, e.g. `1 warning, 2 ok`.
+pub fn severity_counts(severities: &[Severity]) -> String {
+    [
+        Severity::Critical,
+        Severity::Warning,
+        Severity::Info,
+        Severity::Ok,
+    ]
+    .iter()
+    .filter_map(|severity| {
+        let count = severities.iter().filter(|s| *s == severity).count();
+        (count > 0).then(|| format!("{} {}", count, severity.as_str()))
+    })
+    .join(", ")
+}
+
+/// Renders the results as colored lines for a terminal.
+pub struct TerminalRenderer;
+
+impl Renderer for TerminalRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        if let Some(ref environment) = report.environment {
+            writeln!(out, "{}\n", environment)?;
+        }
+        for entry in entries(report.results.iter()) {
+            write!(out, "{}", entry.summary())?;
+            if let Entry::Tags { ref diff, .. } = entry {
+                writeln!(out, " [{}]", entry.codes().iter().join(", "))?;
+                let width = diff.expected.keys().map(|k| k.len()).max().unwrap_or(0);
+                let value_width = diff.expected.values().map(|v| v.len()).max().unwrap_or(0);
+                writeln!(
+                    out,
+                    "    {:width$}  {:value_width$}  ACTUAL",
+                    "TAG",
+                    "EXPECTED",
+                    width = width.max(3),
+                    value_width = value_width.max(8)
+                )?;
+                for (key, expected) in diff.expected.iter() {
+                    writeln!(
+                        out,
+                        "    {:width$}  {:value_width$}  {}",
+                        key,
+                        expected,
+                        diff.actual.get(key).map_or("<missing>", |v| v.as_str()),
+                        width = width.max(3),
+                        value_width = value_width.max(8)
+                    )?;
+                }
+            } else {
+                writeln!(out)?;
+            }
+            for article in entry.codes().iter().filter_map(|c| report.articles.get(c)) {
+                writeln!(out, "    see: {}", article)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Renders one line per check with the number of results of each severity,
+/// so the few findings of a healthy cluster are not buried in its passing
+/// checks.
+pub struct SummaryRenderer;
+
+impl Renderer for SummaryRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        if let Some(ref environment) = report.environment {
+            writeln!(out, "{}\n", environment)?;
+        }
+        let mut checks: Vec<(&'static str, Vec<Severity>)> = vec![];
+        for res in report.results.iter() {
+            // Results that do not belong to a check are notes about the run,
+            // e.g. data that was not gathered, and are shown as they are.
+            let Some(id) = res.check_id else {
+                writeln!(out, "{}", res)?;
+                continue;
+            };
+            match checks.iter_mut().find(|(check, _)| *check == id) {
+                Some((_, severities)) => severities.push(res.severity),
+                None => checks.push((id, vec![res.severity])),
+            }
+        }
+        for (id, severities) in checks {
+            let line = VerificationResult {
+                message: severity_counts(&severities),
+                severity: severities.iter().copied().max().unwrap_or(Severity::Ok),
+                code: None,
+                check_id: Some(id),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            };
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_terminal_renderer() {
+        colored::control::set_override(false);
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![VerificationResult {
+                message: "Subnet subnet-1 is missing public ELB tag".to_string(),
+                severity: Severity::Warning,
+                code: Some(FindingCode::MissingPublicElbTag),
+                check_id: Some("NET-002"),
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            }],
+            articles: HashMap::from([(FindingCode::MissingPublicElbTag, "KCS 1".to_string())]),
+        };
+        let mut out = vec![];
+        TerminalRenderer.render(&report, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Ⓦ - NET-002: Subnet subnet-1 is missing public ELB tag [MISSING_PUBLIC_ELB_TAG]\n    see: KCS 1\n"
+        );
+    }
+
+    #[test]
+    fn test_summary_renderer() {
+        colored::control::set_override(false);
+        let result = |severity, check_id| VerificationResult {
+            message: String::new(),
+            severity,
+            code: None,
+            check_id,
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                result(Severity::Ok, Some("NET-001")),
+                result(Severity::Warning, Some("NET-002")),
+                result(Severity::Ok, Some("NET-002")),
+                result(Severity::Ok, Some("NET-002")),
+            ],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        SummaryRenderer.render(&report, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Ⓞ - NET-001: 1 ok\nⓌ - NET-002: 1 warning, 2 ok\n"
+        );
+    }
+
+    #[test]
+    fn test_terminal_renderer_tag_diff() {
+        colored::control::set_override(false);
+        let diff = TagDiff {
+            resource: "subnet-1".to_string(),
+            expected: BTreeMap::new(),
+            actual: BTreeMap::from([("Name".to_string(), "public".to_string())]),
+        };
+        let result = |code, key, value| VerificationResult {
+            message: String::new(),
+            severity: Severity::Info,
+            code: Some(code),
+            check_id: Some("NET-002"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(diff.evidence(key, value)),
+        };
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                result(
+                    FindingCode::MissingClusterTag,
+                    "kubernetes.io/cluster/infra-1",
+                    "owned or shared",
+                ),
+                result(
+                    FindingCode::MissingPublicElbTag,
+                    "kubernetes.io/role/elb",
+                    "1",
+                ),
+            ],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        TerminalRenderer.render(&report, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Ⓘ - NET-002: Tags of subnet-1 differ from the expected tags [MISSING_CLUSTER_TAG, MISSING_PUBLIC_ELB_TAG]
+    TAG                            EXPECTED         ACTUAL
+    kubernetes.io/cluster/infra-1  owned or shared  <missing>
+    kubernetes.io/role/elb         1                <missing>
+"
+        );
+    }
+}
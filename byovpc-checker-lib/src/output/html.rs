@@ -0,0 +1,226 @@
+//! Renders the results of the checks as a self-contained HTML report, so it
+//! can be attached to support cases and opened without the tool.
+//!
+//! Results are grouped by their check ID and color-coded by severity. The data
+//! that triggered a finding can be expanded below it.
+//! The tag findings of a resource are shown as one table of the expected and
+//! actual tags.
+
+use std::io::Write;
+
+use crate::{
+    checks::REGISTRY,
+    output::{entries, escape, group_by_check, CheckRunReport, Entry, Renderer},
+    types::Severity,
+};
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+h2 { font-size: 1.1em; border-bottom: 1px solid #ccc; }
+ul { list-style: none; padding: 0; }
+li { margin: 0.3em 0; padding: 0.4em 0.6em; border-left: 4px solid; }
+li.ok { border-color: #2e7d32; background: #edf7ed; }
+li.info { border-color: #0277bd; background: #e8f4fb; }
+li.warning { border-color: #ef6c00; background: #fff4e5; }
+li.critical { border-color: #c62828; background: #fdecea; }
+.severity { font-weight: bold; margin-right: 0.5em; }
+.code { font-family: monospace; color: #555; }
+.summary span { margin-right: 1em; }
+pre { background: #f5f5f5; padding: 0.5em; overflow-x: auto; }
+table.tags { border-collapse: collapse; margin-top: 0.4em; }
+table.tags th, table.tags td { text-align: left; padding: 0.1em 1em 0.1em 0; font-family: monospace; }";
+
+fn severity_class(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Ok => "ok",
+        Severity::Info => "info",
+        Severity::Warning => "warning",
+        Severity::Critical => "critical",
+    }
+}
+
+/// Renders the report as a single HTML page without external resources.
+pub struct HtmlRenderer;
+
+impl Renderer for HtmlRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{} report</title>", crate::TOOL_NAME)?;
+        writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+        writeln!(out, "<h1>{} report</h1>", crate::TOOL_NAME)?;
+        if let Some(ref environment) = report.environment {
+            writeln!(
+                out,
+                "<details>\n<summary>Environment</summary>\n<pre>{}</pre>\n</details>",
+                escape(&environment.to_string())
+            )?;
+        }
+        write!(out, "<p class=\"summary\">")?;
+        for severity in [
+            Severity::Critical,
+            Severity::Warning,
+            Severity::Info,
+            Severity::Ok,
+        ] {
+            let count = report
+                .results
+                .iter()
+                .filter(|r| r.severity == severity)
+                .count();
+            write!(
+                out,
+                "<span class=\"{0}\">{1} {0}</span>",
+                severity_class(severity),
+                count
+            )?;
+        }
+        writeln!(out, "</p>")?;
+        for (check_id, results) in group_by_check(&report.results) {
+            let title = match check_id {
+                Some(id) => match REGISTRY.iter().find(|c| c.id == id) {
+                    Some(c) => format!("{} - {}", id, c.description),
+                    None => id.to_string(),
+                },
+                None => "Summaries".to_string(),
+            };
+            writeln!(out, "<section>\n<h2>{}</h2>\n<ul>", escape(&title))?;
+            for entry in entries(results) {
+                let res = entry.summary();
+                write!(
+                    out,
+                    "<li class=\"{0}\"><span class=\"severity\">{0}</span>{1}",
+                    severity_class(res.severity),
+                    escape(&res.message)
+                )?;
+                for code in entry.codes() {
+                    write!(out, " <span class=\"code\">[{}]</span>", code)?;
+                    if let Some(article) = report.articles.get(&code) {
+                        write!(out, " - see: {}", escape(article))?;
+                    }
+                }
+                if let Entry::Tags { ref diff, .. } = entry {
+                    writeln!(
+                        out,
+                        "\n<table class=\"tags\">\n<tr><th>Tag</th><th>Expected</th><th>Actual</th></tr>"
+                    )?;
+                    for (key, expected) in diff.expected.iter() {
+                        writeln!(
+                            out,
+                            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                            escape(key),
+                            escape(expected),
+                            diff.actual
+                                .get(key)
+                                .map_or("&lt;missing&gt;".to_string(), |v| escape(v))
+                        )?;
+                    }
+                    writeln!(out, "</table>")?;
+                }
+                if let Some(ref evidence) = res.evidence {
+                    let evidence = serde_json::to_string_pretty(evidence)?;
+                    write!(
+                        out,
+                        "\n<details>\n<summary>Evidence</summary>\n<pre>{}</pre>\n</details>\n",
+                        escape(&evidence)
+                    )?;
+                }
+                writeln!(out, "</li>")?;
+            }
+            writeln!(out, "</ul>\n</section>")?;
+        }
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde_json::json;
+
+    use crate::types::{FindingCode, TagDiff, VerificationResult};
+
+    use super::*;
+
+    #[test]
+    fn test_html_renderer() {
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                VerificationResult {
+                    message: "Subnet <subnet-1> is missing public ELB tag".to_string(),
+                    severity: Severity::Warning,
+                    code: Some(FindingCode::MissingPublicElbTag),
+                    check_id: Some("NET-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: Some(json!({"Name": "public"})),
+                },
+                VerificationResult {
+                    message: "No LoadBalancer is unused".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: None,
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-2 is correctly setup".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("NET-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ],
+            articles: HashMap::from([(FindingCode::MissingPublicElbTag, "KCS 1".to_string())]),
+        };
+        let mut out = vec![];
+        HtmlRenderer.render(&report, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.contains(
+            "<span class=\"warning\">1 warning</span><span class=\"info\">0 info</span>"
+        ));
+        assert!(html.contains("<li class=\"warning\"><span class=\"severity\">warning</span>Subnet &lt;subnet-1&gt; is missing public ELB tag <span class=\"code\">[MISSING_PUBLIC_ELB_TAG]</span> - see: KCS 1"));
+        assert!(html.contains("<pre>{\n  &quot;Name&quot;: &quot;public&quot;\n}</pre>"));
+        assert_eq!(html.matches("<section>").count(), 2);
+        assert!(html.find("Subnet subnet-2").unwrap() < html.find("Summaries").unwrap());
+    }
+
+    #[test]
+    fn test_html_renderer_tag_diff() {
+        let diff = TagDiff {
+            resource: "mycluster-int".to_string(),
+            expected: BTreeMap::new(),
+            actual: BTreeMap::from([("red-hat-managed".to_string(), "false".to_string())]),
+        };
+        let result = |key, value| VerificationResult {
+            message: String::new(),
+            severity: Severity::Warning,
+            code: Some(FindingCode::LbTagsModified),
+            check_id: Some("LB-002"),
+            resource_type: None,
+            resource_id: None,
+            evidence: Some(diff.evidence(key, value)),
+        };
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                result("kubernetes.io/cluster/mycluster", "owned"),
+                result("red-hat-managed", "true"),
+            ],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        HtmlRenderer.render(&report, &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert_eq!(html.matches("<li ").count(), 1);
+        assert!(html.contains("Tags of mycluster-int differ from the expected tags <span class=\"code\">[LB_TAGS_MODIFIED]</span>"));
+        assert!(html.contains(
+            "<tr><td>kubernetes.io/cluster/mycluster</td><td>owned</td><td>&lt;missing&gt;</td></tr>"
+        ));
+        assert!(html.contains("<tr><td>red-hat-managed</td><td>true</td><td>false</td></tr>"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Renders the results of the checks as a JUnit XML report, so pipelines
+//! (Jenkins, Tekton) can show the verification as a test stage.
+//!
+//! Every check ID is a test case, classified by its checker prefix (e.g.
+//! `NET`). A check fails if any of its results is a warning or critical; all
+//! messages of the check are part of its output. Checks skipped because their
+//! data could not be gathered are skipped test cases.
+
+use std::io::Write;
+
+use crate::{
+    checks::REGISTRY,
+    output::{escape, group_by_check, CheckRunReport, Renderer},
+    types::{Severity, VerificationResult},
+};
+
+/// A test case: the results of one check.
+struct TestCase<'a> {
+    classname: String,
+    name: String,
+    results: Vec<&'a VerificationResult>,
+}
+
+impl<'a> TestCase<'a> {
+    fn new(check_id: Option<&str>, results: Vec<&'a VerificationResult>) -> Self {
+        let (classname, name) = match check_id {
+            Some(id) => {
+                let prefix = id.split('-').next().unwrap_or(id);
+                let name = match REGISTRY.iter().find(|c| c.id == id) {
+                    Some(c) => format!("{} - {}", id, c.description),
+                    None => id.to_string(),
+                };
+                (format!("{}.{}", crate::TOOL_NAME, prefix), name)
+            }
+            None => (crate::TOOL_NAME.to_string(), "Summaries".to_string()),
+        };
+        TestCase {
+            classname,
+            name,
+            results,
+        }
+    }
+
+    fn failures(&self) -> Vec<&VerificationResult> {
+        self.results
+            .iter()
+            .copied()
+            .filter(|r| r.severity >= Severity::Warning)
+            .collect()
+    }
+
+    /// The checks skipped because their data could not be gathered only
+    /// report that they were skipped.
+    fn is_skipped(&self) -> bool {
+        self.results
+            .iter()
+            .all(|r| r.severity == Severity::Info && r.message.contains(": skipped: "))
+    }
+
+    fn render(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "    <testcase classname=\"{}\" name=\"{}\">",
+            escape(&self.classname),
+            escape(&self.name)
+        )?;
+        let failures = self.failures();
+        if let Some(worst) = failures.iter().max_by_key(|r| r.severity) {
+            writeln!(
+                out,
+                "      <failure type=\"{}\" message=\"{}\">{}</failure>",
+                worst.severity.as_str(),
+                escape(&worst.message),
+                escape(
+                    &failures
+                        .iter()
+                        .map(|r| format!("[{}] {}", r.severity.as_str(), r.message))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )
+            )?;
+        } else if self.is_skipped() {
+            writeln!(
+                out,
+                "      <skipped message=\"{}\"/>",
+                escape(&self.results[0].message)
+            )?;
+        }
+        writeln!(
+            out,
+            "      <system-out>{}</system-out>",
+            escape(
+                &self
+                    .results
+                    .iter()
+                    .map(|r| format!("[{}] {}", r.severity.as_str(), r.message))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        )?;
+        writeln!(out, "    </testcase>")
+    }
+}
+
+/// Renders the report as a single JUnit test suite - where the data comes
+/// from and when it was gathered are properties of the suite.
+pub struct JunitRenderer;
+
+impl Renderer for JunitRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        let cases: Vec<TestCase> = group_by_check(&report.results)
+            .into_iter()
+            .map(|(check_id, results)| TestCase::new(check_id, results))
+            .collect();
+        let failures = cases.iter().filter(|c| !c.failures().is_empty()).count();
+        let skipped = cases
+            .iter()
+            .filter(|c| c.failures().is_empty() && c.is_skipped())
+            .count();
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<testsuites name=\"{0}\" tests=\"{1}\" failures=\"{2}\" skipped=\"{3}\">",
+            crate::TOOL_NAME,
+            cases.len(),
+            failures,
+            skipped
+        )?;
+        writeln!(
+            out,
+            "  <testsuite name=\"{0}\" tests=\"{1}\" failures=\"{2}\" errors=\"0\" skipped=\"{3}\">",
+            crate::TOOL_NAME,
+            cases.len(),
+            failures,
+            skipped
+        )?;
+        if let Some(environment) = &report.environment {
+            writeln!(out, "    <properties>")?;
+            let mut properties = vec![
+                ("data_source", environment.source().to_string()),
+                ("freshness", environment.freshness()),
+            ];
+            if let Some(gathered_at) = environment.gathered_at_utc() {
+                properties.push(("gathered_at", gathered_at));
+            }
+            for (name, value) in properties {
+                writeln!(
+                    out,
+                    "      <property name=\"{}\" value=\"{}\"/>",
+                    name,
+                    escape(&value)
+                )?;
+            }
+            writeln!(out, "    </properties>")?;
+        }
+        for case in cases.iter() {
+            case.render(out)?;
+        }
+        writeln!(out, "  </testsuite>\n</testsuites>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::gatherer::aws::Environment;
+
+    use super::*;
+
+    #[test]
+    fn test_junit_renderer() {
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                VerificationResult {
+                    message: "Subnet <subnet-1> is missing public ELB tag".to_string(),
+                    severity: Severity::Warning,
+                    code: None,
+                    check_id: Some("NET-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Subnet subnet-2 is correctly setup".to_string(),
+                    severity: Severity::Ok,
+                    code: None,
+                    check_id: Some("NET-002"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                VerificationResult {
+                    message: "Private subnets route through an available NAT gateway: skipped: could not gather NAT gateways".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: Some("NET-009"),
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+            ],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        JunitRenderer.render(&report, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<testsuite name=\"byovpc-checker\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"byovpc-checker.NET\" name=\"NET-002 - Subnets are tagged for the cluster and for load balancers\">"));
+        assert!(xml.contains("<failure type=\"warning\" message=\"Subnet &lt;subnet-1&gt; is missing public ELB tag\">[warning] Subnet &lt;subnet-1&gt; is missing public ELB tag</failure>"));
+        assert!(xml.contains("<skipped message=\"Private subnets route through an available NAT gateway: skipped: could not gather NAT gateways\"/>"));
+    }
+
+    #[test]
+    fn test_junit_renderer_environment() {
+        let report = CheckRunReport {
+            environment: Some(Environment {
+                version: "0.1.1".to_string(),
+                region: None,
+                credentials: "environment".to_string(),
+                proxy: None,
+                gathered_at: Some(1_700_000_000),
+                from_snapshot: false,
+            }),
+            results: vec![],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        JunitRenderer.render(&report, &mut out).unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains("<property name=\"data_source\" value=\"live\"/>"));
+        assert!(xml.contains("<property name=\"freshness\" value=\"live\"/>"));
+        assert!(xml.contains("<property name=\"gathered_at\" value=\"2023-11-14T22:13:20Z\"/>"));
+    }
+}
@@ -0,0 +1,290 @@
+//! Renders the results of the checks as a SARIF 2.1.0 log, so CI systems
+//! (GitHub code scanning, GitLab, security dashboards) can pick them up.
+//!
+//! Every check of the registry is a rule, every result a finding of the rule
+//! of its check ID. Severities map to the SARIF levels: critical to `error`,
+//! warning to `warning`, info to `note`. Successful results are passes. The
+//! resource of a result is its logical location. Where the data comes from
+//! and when it was gathered are properties of the run.
+
+use std::{collections::HashMap, io::Write};
+
+use serde::Serialize;
+
+use crate::{
+    checks::REGISTRY,
+    gatherer::aws::Environment,
+    output::{CheckRunReport, Renderer},
+    types::{FindingCode, Severity, VerificationResult},
+};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Debug, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+struct Run {
+    tool: Tool,
+    results: Vec<SarifResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<RunProperties>,
+}
+
+/// How fresh the data the results are based on is.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunProperties {
+    data_source: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gathered_at: Option<String>,
+    freshness: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Tool {
+    driver: Driver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    id: &'static str,
+    short_description: Message,
+}
+
+#[derive(Debug, Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<&'static str>,
+    kind: &'static str,
+    level: &'static str,
+    message: Message,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<Properties>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    logical_locations: Vec<LogicalLocation>,
+}
+
+/// The AWS resource a result is about - it has no physical location.
+#[derive(Debug, Serialize)]
+struct LogicalLocation {
+    name: String,
+    kind: &'static str,
+}
+
+/// The finding code, the support article describing the fix and the data
+/// that triggered the finding.
+#[derive(Debug, Serialize)]
+struct Properties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<FindingCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    article: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evidence: Option<serde_json::Value>,
+}
+
+fn level(severity: Severity) -> (&'static str, &'static str) {
+    match severity {
+        Severity::Ok => ("pass", "none"),
+        Severity::Info => ("informational", "note"),
+        Severity::Warning => ("fail", "warning"),
+        Severity::Critical => ("fail", "error"),
+    }
+}
+
+impl SarifLog {
+    pub fn new(
+        results: &[VerificationResult],
+        articles: &HashMap<FindingCode, String>,
+        environment: Option<&Environment>,
+    ) -> Self {
+        let rules = REGISTRY
+            .iter()
+            .map(|c| Rule {
+                id: c.id,
+                short_description: Message {
+                    text: c.description.to_string(),
+                },
+            })
+            .collect();
+        let results = results
+            .iter()
+            .map(|r| {
+                let (kind, level) = level(r.severity);
+                SarifResult {
+                    rule_id: r.check_id,
+                    kind,
+                    level,
+                    message: Message {
+                        text: r.message.clone(),
+                    },
+                    locations: r
+                        .resource_type
+                        .zip(r.resource_id.clone())
+                        .map(|(kind, name)| Location {
+                            logical_locations: vec![LogicalLocation {
+                                name,
+                                kind: kind.as_str(),
+                            }],
+                        })
+                        .into_iter()
+                        .collect(),
+                    properties: (r.code.is_some() || r.evidence.is_some()).then(|| Properties {
+                        code: r.code,
+                        article: r.code.and_then(|code| articles.get(&code).cloned()),
+                        evidence: r.evidence.clone(),
+                    }),
+                }
+            })
+            .collect();
+        SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![Run {
+                tool: Tool {
+                    driver: Driver {
+                        name: crate::TOOL_NAME,
+                        version: env!("CARGO_PKG_VERSION"),
+                        information_uri: env!("CARGO_PKG_REPOSITORY"),
+                        rules,
+                    },
+                },
+                results,
+                properties: environment.map(|e| RunProperties {
+                    data_source: e.source(),
+                    gathered_at: e.gathered_at_utc(),
+                    freshness: e.freshness(),
+                }),
+            }],
+        }
+    }
+}
+
+/// Renders the report as a SARIF log.
+pub struct SarifRenderer;
+
+impl Renderer for SarifRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        let log = SarifLog::new(
+            &report.results,
+            &report.articles,
+            report.environment.as_ref(),
+        );
+        serde_json::to_writer_pretty(&mut *out, &log)?;
+        writeln!(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::ResourceType;
+
+    use super::*;
+
+    #[test]
+    fn test_sarif_log() {
+        let results = vec![
+            VerificationResult {
+                message: "Subnet subnet-1 routes through NAT gateway nat-1 that does not exist"
+                    .to_string(),
+                severity: Severity::Critical,
+                code: Some(FindingCode::NatGwDeleted),
+                check_id: Some("NET-009"),
+                resource_type: Some(ResourceType::Subnet),
+                resource_id: Some("subnet-1".to_string()),
+                evidence: Some(serde_json::json!({"route": {"target": "nat-1"}})),
+            },
+            VerificationResult {
+                message: "Cluster subnets are not shared with other services".to_string(),
+                severity: Severity::Ok,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        ];
+        let articles = HashMap::from([(FindingCode::NatGwDeleted, "KCS 1234567".to_string())]);
+        let log = serde_json::to_value(SarifLog::new(&results, &articles, None)).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(
+            log["runs"][0]["tool"]["driver"]["rules"]
+                .as_array()
+                .unwrap()
+                .len(),
+            REGISTRY.len()
+        );
+        assert_eq!(
+            log["runs"][0]["results"],
+            serde_json::json!([
+                {
+                    "ruleId": "NET-009",
+                    "kind": "fail",
+                    "level": "error",
+                    "message": {"text": "Subnet subnet-1 routes through NAT gateway nat-1 that does not exist"},
+                    "locations": [{"logicalLocations": [{"name": "subnet-1", "kind": "subnet"}]}],
+                    "properties": {
+                        "code": "NAT_GW_DELETED",
+                        "article": "KCS 1234567",
+                        "evidence": {"route": {"target": "nat-1"}}
+                    }
+                },
+                {
+                    "kind": "pass",
+                    "level": "none",
+                    "message": {"text": "Cluster subnets are not shared with other services"}
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_sarif_log_environment() {
+        let environment = Environment {
+            version: "0.1.1".to_string(),
+            region: Some("us-east-1".to_string()),
+            credentials: "profile default".to_string(),
+            proxy: None,
+            gathered_at: Some(1_700_000_000),
+            from_snapshot: true,
+        };
+        let log =
+            serde_json::to_value(SarifLog::new(&[], &HashMap::new(), Some(&environment))).unwrap();
+        let properties = &log["runs"][0]["properties"];
+        assert_eq!(properties["dataSource"], "snapshot");
+        assert_eq!(properties["gatheredAt"], "2023-11-14T22:13:20Z");
+        assert!(properties["freshness"]
+            .as_str()
+            .unwrap()
+            .starts_with("snapshot gathered"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Renders the fixable findings of the checks as a Terraform file, so the tags
+//! the checks expect can be applied with the infrastructure code of the VPC.
+//!
+//! Only the tags of EC2 resources are fixable: every missing or incorrect
+//! cluster tag and load balancer role tag of a subnet becomes an
+//! `aws_ec2_tag` resource with the expected key and value. All other findings
+//! are left out.
+
+use std::{collections::BTreeMap, io::Write};
+
+use crate::{
+    output::{CheckRunReport, Renderer},
+    types::{FindingCode, ResourceType, TagDiff},
+};
+
+/// The findings that are fixed by setting the expected tags.
+const FIXABLE_CODES: [FindingCode; 4] = [
+    FindingCode::MissingClusterTag,
+    FindingCode::IncorrectClusterTag,
+    FindingCode::MissingInternalElbTag,
+    FindingCode::MissingPublicElbTag,
+];
+
+/// Escapes text for quoted HCL strings, including template sequences.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
+
+/// A Terraform resource name for the tag - names may only contain letters,
+/// digits, underscores and dashes.
+fn resource_name(resource: &str, key: &str) -> String {
+    format!("{}_{}", resource, key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Renders an `aws_ec2_tag` resource for every expected tag of the fixable
+/// findings of the report.
+pub struct TerraformRenderer;
+
+impl Renderer for TerraformRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        // Several checks can expect the same tag of a resource.
+        let tags: BTreeMap<(String, String), String> = report
+            .results
+            .iter()
+            .filter(|r| r.code.is_some_and(|c| FIXABLE_CODES.contains(&c)))
+            .filter(|r| r.resource_type == Some(ResourceType::Subnet))
+            .filter_map(TagDiff::of)
+            .flat_map(|diff| {
+                diff.expected
+                    .into_iter()
+                    .map(move |(key, value)| ((diff.resource.clone(), key), value))
+            })
+            .collect();
+        writeln!(
+            out,
+            "# The tags expected by {} - {} to apply.",
+            crate::TOOL_NAME,
+            tags.len()
+        )?;
+        for ((resource, key), value) in tags.iter() {
+            writeln!(
+                out,
+                "\nresource \"aws_ec2_tag\" \"{}\" {{",
+                resource_name(resource, key)
+            )?;
+            writeln!(out, "  resource_id = \"{}\"", escape(resource))?;
+            writeln!(out, "  key         = \"{}\"", escape(key))?;
+            writeln!(out, "  value       = \"{}\"", escape(value))?;
+            writeln!(out, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::types::{Severity, VerificationResult};
+
+    use super::*;
+
+    #[test]
+    fn test_terraform_renderer() {
+        let diff = TagDiff::new("subnet-1", [("Name", "private")]);
+        let finding = |code: FindingCode, key: &str, value: &str| VerificationResult {
+            message: format!("Subnet subnet-1 is missing tag {}", key),
+            severity: Severity::Warning,
+            code: Some(code),
+            check_id: Some("NET-002"),
+            resource_type: Some(ResourceType::Subnet),
+            resource_id: Some("subnet-1".to_string()),
+            evidence: Some(diff.evidence(key, value)),
+        };
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                finding(
+                    FindingCode::MissingClusterTag,
+                    "kubernetes.io/cluster/mycluster-abcde",
+                    "shared",
+                ),
+                finding(
+                    FindingCode::MissingInternalElbTag,
+                    "kubernetes.io/role/internal-elb",
+                    "1",
+                ),
+                // The same tag expected by another check
+                VerificationResult {
+                    check_id: Some("HCP-003"),
+                    ..finding(
+                        FindingCode::MissingClusterTag,
+                        "kubernetes.io/cluster/mycluster-abcde",
+                        "shared",
+                    )
+                },
+                // Load balancers can not be tagged with aws_ec2_tag
+                VerificationResult {
+                    resource_type: Some(ResourceType::LoadBalancer),
+                    ..finding(FindingCode::LbTagsModified, "red-hat-managed", "true")
+                },
+            ],
+            articles: HashMap::new(),
+        };
+        let mut out = vec![];
+        TerraformRenderer.render(&report, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"# The tags expected by byovpc-checker - 2 to apply.
+
+resource "aws_ec2_tag" "subnet_1_kubernetes_io_cluster_mycluster_abcde" {
+  resource_id = "subnet-1"
+  key         = "kubernetes.io/cluster/mycluster-abcde"
+  value       = "shared"
+}
+
+resource "aws_ec2_tag" "subnet_1_kubernetes_io_role_internal_elb" {
+  resource_id = "subnet-1"
+  key         = "kubernetes.io/role/internal-elb"
+  value       = "1"
+}
+"#
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Renders the results as a tree for a terminal: one branch per check, with
+//! the results of every resource grouped below it.
+//!
+//! Checks and resources show the highest severity of their results and how
+//! many results of each severity they have. A resource with several results
+//! that all passed is collapsed into its counts, so the findings of a big
+//! cluster are not interleaved with hundreds of passing results. Results are
+//! grouped by their resource, or else by the first AWS resource named in their
+//! message - results naming none are shown directly below their check.
+
+use std::io::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    baseline::resources_in,
+    checks::REGISTRY,
+    output::{entries, severity_counts, CheckRunReport, Entry, Renderer},
+    types::{Severity, VerificationResult},
+};
+
+const BRANCH: &str = "├── ";
+const LAST_BRANCH: &str = "└── ";
+const TRUNK: &str = "│   ";
+const NO_TRUNK: &str = "    ";
+
+/// The resource an entry is grouped by.
+fn resource_of(entry: &Entry) -> Option<String> {
+    match entry {
+        Entry::Tags { diff, .. } => Some(diff.resource.clone()),
+        Entry::Result(res) => res.resource_id.clone().or_else(|| {
+            resources_in(&res.message).and_then(|r| r.split(',').next().map(str::to_string))
+        }),
+    }
+}
+
+/// The severities of all results of an entry.
+fn severities(entry: &Entry) -> Vec<Severity> {
+    match entry {
+        Entry::Result(res) => vec![res.severity],
+        Entry::Tags { results, .. } => results.iter().map(|r| r.severity).collect(),
+    }
+}
+
+/// A line rolling up the severities of a group.
+fn rollup(
+    check_id: Option<&'static str>,
+    label: &str,
+    severities: &[Severity],
+) -> VerificationResult {
+    VerificationResult {
+        message: format!("{} ({})", label, severity_counts(severities))
+            .trim_start()
+            .to_string(),
+        severity: severities.iter().copied().max().unwrap_or(Severity::Ok),
+        code: None,
+        check_id,
+        resource_type: None,
+        resource_id: None,
+        evidence: None,
+    }
+}
+
+/// The prefixes of the line of a node and of the lines below it.
+fn branches(last: bool) -> (&'static str, &'static str) {
+    if last {
+        (LAST_BRANCH, NO_TRUNK)
+    } else {
+        (BRANCH, TRUNK)
+    }
+}
+
+pub struct TreeRenderer;
+
+impl TreeRenderer {
+    fn write_entry(
+        out: &mut dyn Write,
+        report: &CheckRunReport,
+        entry: &Entry,
+        prefix: &str,
+        indent: &str,
+    ) -> std::io::Result<()> {
+        let mut line = entry.summary();
+        // The check is the parent node already.
+        line.check_id = None;
+        write!(out, "{}{}", prefix, line)?;
+        if let Entry::Tags { ref diff, .. } = entry {
+            writeln!(out, " [{}]", entry.codes().iter().join(", "))?;
+            let width = diff
+                .expected
+                .keys()
+                .map(|k| k.len())
+                .max()
+                .unwrap_or(0)
+                .max(3);
+            let value_width = diff
+                .expected
+                .values()
+                .map(|v| v.len())
+                .max()
+                .unwrap_or(0)
+                .max(8);
+            writeln!(
+                out,
+                "{}  {:width$}  {:value_width$}  ACTUAL",
+                indent, "TAG", "EXPECTED"
+            )?;
+            for (key, expected) in diff.expected.iter() {
+                writeln!(
+                    out,
+                    "{}  {:width$}  {:value_width$}  {}",
+                    indent,
+                    key,
+                    expected,
+                    diff.actual.get(key).map_or("<missing>", |v| v.as_str())
+                )?;
+            }
+        } else {
+            writeln!(out)?;
+        }
+        for article in entry.codes().iter().filter_map(|c| report.articles.get(c)) {
+            writeln!(out, "{}  see: {}", indent, article)?;
+        }
+        Ok(())
+    }
+}
+
+impl Renderer for TreeRenderer {
+    fn render(&self, report: &CheckRunReport, out: &mut dyn Write) -> std::io::Result<()> {
+        if let Some(ref environment) = report.environment {
+            writeln!(out, "{}\n", environment)?;
+        }
+        let mut checks: Vec<(&'static str, Vec<Entry>)> = vec![];
+        for entry in entries(report.results.iter()) {
+            // Results that do not belong to a check are notes about the run,
+            // e.g. data that was not gathered, and are shown as they are.
+            let Some(id) = entry.summary().check_id else {
+                writeln!(out, "{}", entry.summary())?;
+                continue;
+            };
+            match checks.iter_mut().find(|(check, _)| *check == id) {
+                Some((_, entries)) => entries.push(entry),
+                None => checks.push((id, vec![entry])),
+            }
+        }
+        for (id, check_entries) in checks {
+            let description = REGISTRY
+                .iter()
+                .find(|c| c.id == id)
+                .map_or("", |c| c.description);
+            let all: Vec<Severity> = check_entries.iter().flat_map(severities).collect();
+            writeln!(out, "{}", rollup(Some(id), description, &all))?;
+            let mut groups: Vec<(Option<String>, Vec<&Entry>)> = vec![];
+            for entry in check_entries.iter() {
+                let resource = resource_of(entry);
+                match groups
+                    .iter_mut()
+                    .find(|(r, _)| resource.is_some() && *r == resource)
+                {
+                    Some((_, group)) => group.push(entry),
+                    None => groups.push((resource, vec![entry])),
+                }
+            }
+            let count = groups.len();
+            for (i, (resource, group)) in groups.into_iter().enumerate() {
+                let (prefix, indent) = branches(i + 1 == count);
+                let [entry] = group[..] else {
+                    let group_severities: Vec<Severity> =
+                        group.iter().copied().flat_map(severities).collect();
+                    writeln!(
+                        out,
+                        "{}{}",
+                        prefix,
+                        rollup(None, &resource.unwrap_or_default(), &group_severities)
+                    )?;
+                    if group_severities.iter().all(|s| *s == Severity::Ok) {
+                        continue;
+                    }
+                    for (j, entry) in group.iter().enumerate() {
+                        let (inner_prefix, inner_indent) = branches(j + 1 == group.len());
+                        Self::write_entry(
+                            out,
+                            report,
+                            entry,
+                            &format!("{}{}", indent, inner_prefix),
+                            &format!("{}{}", indent, inner_indent),
+                        )?;
+                    }
+                    continue;
+                };
+                Self::write_entry(out, report, entry, prefix, indent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::types::FindingCode;
+
+    use super::*;
+
+    #[test]
+    fn test_tree_renderer() {
+        colored::control::set_override(false);
+        let result = |severity, check_id, message: &str, code| VerificationResult {
+            message: message.to_string(),
+            severity,
+            code,
+            check_id: Some(check_id),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let report = CheckRunReport {
+            environment: None,
+            results: vec![
+                VerificationResult {
+                    message: "Subnets could not be gathered".to_string(),
+                    severity: Severity::Info,
+                    code: None,
+                    check_id: None,
+                    resource_type: None,
+                    resource_id: None,
+                    evidence: None,
+                },
+                result(
+                    Severity::Warning,
+                    "NET-002",
+                    "Subnet subnet-0a1b2c3d is missing public ELB tag",
+                    Some(FindingCode::MissingPublicElbTag),
+                ),
+                result(
+                    Severity::Info,
+                    "NET-002",
+                    "Subnet subnet-0a1b2c3d is missing private ELB tag",
+                    None,
+                ),
+                result(
+                    Severity::Ok,
+                    "NET-002",
+                    "Subnet subnet-4e5f6a7b is correct",
+                    None,
+                ),
+                result(
+                    Severity::Ok,
+                    "NET-002",
+                    "Subnet subnet-4e5f6a7b is tagged",
+                    None,
+                ),
+                result(Severity::Ok, "NET-001", "Every zone has subnets", None),
+            ],
+            articles: HashMap::from([(FindingCode::MissingPublicElbTag, "KCS 1".to_string())]),
+        };
+        let mut out = vec![];
+        TreeRenderer.render(&report, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Ⓘ - Subnets could not be gathered
+Ⓦ - NET-002: Subnets are tagged for the cluster and for load balancers (1 warning, 1 info, 2 ok)
+├── Ⓦ - subnet-0a1b2c3d (1 warning, 1 info)
+│   ├── Ⓦ - Subnet subnet-0a1b2c3d is missing public ELB tag [MISSING_PUBLIC_ELB_TAG]
+│   │     see: KCS 1
+│   └── Ⓘ - Subnet subnet-0a1b2c3d is missing private ELB tag
+└── Ⓞ - subnet-4e5f6a7b (2 ok)
+Ⓞ - NET-001: Every availability zone has a public and a private subnet (1 ok)
+└── Ⓞ - Every zone has subnets
+"
+        );
+    }
+}
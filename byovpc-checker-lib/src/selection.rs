@@ -0,0 +1,1095 @@
+//! The checkers that can be selected with `--checks` and how they are
+//! constructed from the gathered data.
+//!
+//! Adding a checker means adding its `Check` variant and registering it in
+//! `CHECKERS` with the IDs of its checks, a constructor and the data sets the
+//! constructor reads. Selection order, duplicates, the data sets to gather and
+//! skipping checks without their data are derived from the registry.
+
+use itertools::Itertools;
+
+use crate::{
+    checks::{
+        cidr::CidrChecksBuilder,
+        cost::CostChecksBuilder,
+        custom::{CustomChecksBuilder, Rule, CUSTOM_PREFIX},
+        delegation::DelegatedSubnetChecksBuilder,
+        dhcp::DhcpOptionsChecksBuilder,
+        dns::HostedZoneChecksBuilder,
+        egress::EgressIpChecksBuilder,
+        endpoints::EndpointChecksBuilder,
+        flowlogs::FlowLogChecksBuilder,
+        health::HealthEventChecksBuilder,
+        hypershift::HypershiftChecksBuilder,
+        iam::IamChecksBuilder,
+        instances::{InstanceChecksBuilder, SecurityGroupChecks},
+        ipv6::Ipv6ChecksBuilder,
+        kubernetes::KubernetesChecksBuilder,
+        loadbalancer::LoadBalancerChecksBuilder,
+        machinepools::MachinePoolChecksBuilder,
+        nacl::NetworkAclChecksBuilder,
+        network::ClusterNetworkBuilder,
+        permissions::PermissionChecksBuilder,
+        probes::ActiveProbeChecksBuilder,
+        proxy::ProxyChecksBuilder,
+        quotas::QuotaChecksBuilder,
+        resolver::ResolverQueryLogChecksBuilder,
+        scaling::ScalingChecksBuilder,
+        sharedvpc::SharedVpcChecksBuilder,
+        skip_missing_prerequisites,
+        ssm::SsmChecksBuilder,
+        targetgroups::TargetGroupChecksBuilder,
+        topology::TopologyChecksBuilder,
+        vpc::VpcChecksBuilder,
+        zones::AvailabilityZoneChecksBuilder,
+        REGISTRY,
+    },
+    gatherer::{
+        aws::{snapshot::Snapshot, AWSClusterData},
+        k8s::KubernetesData,
+        local::{ActiveProbes, ProxyEgress},
+    },
+    types::{
+        ClusterTagValue, DataSet, MinimalClusterInfo, Severity, TaggedResource, VerificationResult,
+        Verifier,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Check {
+    Network,
+    NetworkAcl,
+    DelegatedSubnet,
+    #[value(alias = "dns")]
+    HostedZone,
+    AvailabilityZone,
+    MachinePool,
+    ResolverQueryLogs,
+    Cost,
+    Scaling,
+    Ssm,
+    LoadBalancer,
+    Instance,
+    /// Only the security group checks of the instance checks.
+    SecurityGroup,
+    Endpoint,
+    Permission,
+    EgressIp,
+    HealthEvents,
+    Cidr,
+    Topology,
+    DhcpOptions,
+    Vpc,
+    TargetGroups,
+    Iam,
+    Ipv6,
+    /// Probes the endpoints the cluster needs through its proxy.
+    Proxy,
+    /// Connects to the endpoints the cluster needs, enabled with
+    /// `--active-probes`.
+    ActiveProbes,
+    Quotas,
+    /// Subnets shared from another account through RAM.
+    SharedVpc,
+    /// Reports the VPC flow logs of the cluster.
+    FlowLogs,
+    /// The resources hosted control planes need in the customer account.
+    Hypershift,
+    /// Cross-checks the cluster read with `--kubeconfig`.
+    Kubernetes,
+    /// The rules loaded with `--rules`.
+    Custom,
+}
+
+/// What the checkers are constructed from.
+pub struct CheckSetup<'a> {
+    pub cluster_info: &'a MinimalClusterInfo,
+    pub data: &'a AWSClusterData,
+    pub cluster_tag_values: &'a [ClusterTagValue],
+    pub egress_ips: &'a [String],
+    pub custom_rules: &'a [Rule],
+    /// The objects read from the Kubernetes API of the cluster, if any.
+    pub kubernetes: Option<&'a KubernetesData>,
+    /// The probes of the egress through the proxy of the cluster, if they
+    /// ran.
+    pub proxy_egress: Option<&'a ProxyEgress>,
+    /// The connections to the endpoints the cluster needs, if they were
+    /// probed.
+    pub active_probes: Option<&'a ActiveProbes>,
+}
+
+type Constructor = for<'a> fn(&CheckSetup<'a>) -> Box<dyn Verifier + 'a>;
+
+pub struct Checker {
+    pub check: Check,
+    /// The IDs of the checks it runs start with one of these prefixes.
+    pub ids: &'static [&'static str],
+    pub construct: Constructor,
+    /// The data sets the constructor reads - only the data sets of the
+    /// selected checkers are gathered.
+    pub gathers: &'static [DataSet],
+}
+
+impl Checker {
+    /// Whether every check of the checker is also run by the other one.
+    fn covered_by(&self, other: &Checker) -> bool {
+        self.ids
+            .iter()
+            .all(|id| other.ids.iter().any(|o| id.starts_with(o)))
+    }
+}
+
+/// All checkers, in the order they run.
+pub const CHECKERS: &[Checker] = &[
+    Checker {
+        check: Check::Network,
+        ids: &["NET-"],
+        construct: network,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::LoadBalancers,
+            DataSet::LoadBalancerEnis,
+            DataSet::NatGateways,
+            DataSet::InternetGateways,
+        ],
+    },
+    Checker {
+        check: Check::NetworkAcl,
+        ids: &["NACL-"],
+        construct: network_acl,
+        gathers: &[DataSet::Subnets, DataSet::NetworkAcls],
+    },
+    Checker {
+        check: Check::Cidr,
+        ids: &["CIDR-"],
+        construct: cidr,
+        gathers: &[DataSet::Subnets, DataSet::Vpcs],
+    },
+    Checker {
+        check: Check::Topology,
+        ids: &["TOPO-"],
+        construct: topology,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::NatGateways,
+            DataSet::LoadBalancers,
+            DataSet::Instances,
+        ],
+    },
+    Checker {
+        check: Check::DhcpOptions,
+        ids: &["DHCP-"],
+        construct: dhcp_options,
+        gathers: &[DataSet::Subnets, DataSet::Vpcs, DataSet::DhcpOptions],
+    },
+    Checker {
+        check: Check::Vpc,
+        ids: &["VPC-"],
+        construct: vpc,
+        gathers: &[DataSet::Subnets, DataSet::VpcAttributes],
+    },
+    Checker {
+        check: Check::TargetGroups,
+        ids: &["TG-"],
+        construct: target_groups,
+        gathers: &[DataSet::TargetGroups, DataSet::Instances],
+    },
+    Checker {
+        check: Check::DelegatedSubnet,
+        ids: &["DLG-"],
+        construct: delegated_subnet,
+        gathers: &[DataSet::Subnets, DataSet::SubnetNetworkInterfaces],
+    },
+    Checker {
+        check: Check::HostedZone,
+        ids: &["DNS-"],
+        construct: hosted_zone,
+        gathers: &[
+            DataSet::HostedZones,
+            DataSet::LoadBalancers,
+            DataSet::Subnets,
+        ],
+    },
+    Checker {
+        check: Check::AvailabilityZone,
+        ids: &["AZ-"],
+        construct: availability_zone,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::AvailabilityZones,
+            DataSet::InstanceTypeOfferings,
+        ],
+    },
+    Checker {
+        check: Check::MachinePool,
+        ids: &["MP-"],
+        construct: machine_pool,
+        gathers: &[DataSet::Subnets],
+    },
+    Checker {
+        check: Check::LoadBalancer,
+        ids: &["LB-"],
+        construct: load_balancer,
+        gathers: &[
+            DataSet::LoadBalancers,
+            DataSet::LoadBalancerAttributes,
+            DataSet::AccessLogBuckets,
+            DataSet::TargetGroups,
+            DataSet::Instances,
+        ],
+    },
+    Checker {
+        check: Check::Instance,
+        ids: &["INST-"],
+        construct: instance,
+        gathers: &[DataSet::Instances, DataSet::Subnets],
+    },
+    Checker {
+        check: Check::SecurityGroup,
+        ids: &["INST-003", "INST-007"],
+        construct: security_group,
+        gathers: &[DataSet::Instances, DataSet::Subnets],
+    },
+    Checker {
+        check: Check::Endpoint,
+        ids: &["EP-"],
+        construct: endpoint,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::VpcEndpoints,
+        ],
+    },
+    Checker {
+        check: Check::Permission,
+        ids: &["PERM-"],
+        construct: permission,
+        // The denials are recorded while gathering any of the data sets.
+        gathers: &DataSet::ALL,
+    },
+    Checker {
+        check: Check::EgressIp,
+        ids: &["EGR-"],
+        construct: egress_ip,
+        gathers: &[DataSet::Subnets, DataSet::NetworkAcls, DataSet::Instances],
+    },
+    Checker {
+        check: Check::ResolverQueryLogs,
+        ids: &["RES-"],
+        construct: resolver_query_logs,
+        gathers: &[DataSet::ResolverQueryLogs, DataSet::Instances],
+    },
+    Checker {
+        check: Check::Cost,
+        ids: &["COST-"],
+        construct: cost,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::NatGateways,
+            DataSet::ElasticIps,
+            DataSet::LoadBalancers,
+            DataSet::TargetGroups,
+        ],
+    },
+    Checker {
+        check: Check::Scaling,
+        ids: &["SCALE-"],
+        construct: scaling,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::NatGateways,
+            DataSet::Instances,
+        ],
+    },
+    Checker {
+        check: Check::Ssm,
+        ids: &["SSM-"],
+        construct: ssm,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::VpcEndpoints,
+        ],
+    },
+    Checker {
+        check: Check::HealthEvents,
+        ids: &["HEALTH-"],
+        construct: health_events,
+        gathers: &[DataSet::Subnets, DataSet::HealthEvents],
+    },
+    Checker {
+        check: Check::Iam,
+        ids: &["IAM-"],
+        construct: iam,
+        gathers: &[DataSet::Instances, DataSet::InstanceProfiles],
+    },
+    Checker {
+        check: Check::Ipv6,
+        ids: &["IPV6-"],
+        construct: ipv6,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::RouteTables,
+            DataSet::EgressOnlyInternetGateways,
+        ],
+    },
+    Checker {
+        check: Check::Proxy,
+        ids: &["PROXY-"],
+        construct: proxy,
+        gathers: &[],
+    },
+    Checker {
+        check: Check::ActiveProbes,
+        ids: &["PROBE-"],
+        construct: active_probes,
+        gathers: &[],
+    },
+    Checker {
+        check: Check::SharedVpc,
+        ids: &["SHARED-"],
+        construct: shared_vpc,
+        gathers: &[DataSet::Subnets, DataSet::RouteTables],
+    },
+    Checker {
+        check: Check::Quotas,
+        ids: &["QUOTA-"],
+        construct: quotas,
+        gathers: &[
+            DataSet::ServiceQuotas,
+            DataSet::ElasticIps,
+            DataSet::NatGateways,
+            DataSet::Subnets,
+            DataSet::SubnetNetworkInterfaces,
+            DataSet::Instances,
+            DataSet::LoadBalancers,
+        ],
+    },
+    Checker {
+        check: Check::Hypershift,
+        ids: &["HCP-"],
+        construct: hypershift,
+        gathers: &[
+            DataSet::Subnets,
+            DataSet::LoadBalancers,
+            DataSet::VpcEndpoints,
+        ],
+    },
+    Checker {
+        check: Check::FlowLogs,
+        ids: &["FLOW-"],
+        construct: flow_logs,
+        gathers: &[DataSet::Subnets, DataSet::FlowLogs],
+    },
+    Checker {
+        check: Check::Kubernetes,
+        ids: &["K8S-"],
+        construct: kubernetes,
+        gathers: &[
+            DataSet::LoadBalancers,
+            DataSet::Instances,
+            DataSet::HostedZones,
+        ],
+    },
+    Checker {
+        check: Check::Custom,
+        ids: &[CUSTOM_PREFIX],
+        construct: custom,
+        // The data sets of the rules are gathered for them.
+        gathers: &[],
+    },
+];
+
+impl Check {
+    pub fn checker(&self) -> &'static Checker {
+        CHECKERS
+            .iter()
+            .find(|c| c.check == *self)
+            .expect("every check is registered")
+    }
+
+    /// The prefixes of the IDs of the checks run by this checker.
+    pub fn ids(&self) -> &'static [&'static str] {
+        self.checker().ids
+    }
+}
+
+/// The selected checkers in the order they run, without duplicates and
+/// without checkers whose checks all run as part of another selected one.
+pub fn resolve(selected: &[Check]) -> Vec<&'static Checker> {
+    let checkers: Vec<&Checker> = CHECKERS
+        .iter()
+        .filter(|c| selected.contains(&c.check))
+        .collect();
+    checkers
+        .iter()
+        .filter(|c| {
+            !checkers
+                .iter()
+                .any(|o| o.check != c.check && c.covered_by(o))
+        })
+        .copied()
+        .collect()
+}
+
+/// The data sets the checks of the selected checkers require.
+pub fn required_data_sets(selected: &[Check]) -> Vec<DataSet> {
+    let ids: Vec<&str> = resolve(selected)
+        .iter()
+        .flat_map(|c| c.ids)
+        .copied()
+        .collect();
+    REGISTRY
+        .iter()
+        .filter(|c| ids.iter().any(|id| c.id.starts_with(id)))
+        .flat_map(|c| c.requires.iter().copied())
+        .collect()
+}
+
+/// The data sets the selected checkers are constructed from.
+pub fn gathered_data_sets(selected: &[Check]) -> Vec<DataSet> {
+    resolve(selected)
+        .iter()
+        .flat_map(|c| c.gathers.iter().copied())
+        .collect()
+}
+
+/// Constructs the selected checkers.
+pub fn setup_checks<'a>(
+    selected: &[Check],
+    setup: &CheckSetup<'a>,
+) -> Vec<(Check, Box<dyn Verifier + 'a>)> {
+    resolve(selected)
+        .into_iter()
+        .map(|c| (c.check, (c.construct)(setup)))
+        .collect()
+}
+
+/// Runs every check on its own thread - all data is gathered upfront, so the
+/// checks are independent of each other. Results are returned in the order of
+/// the checks.
+pub fn run_checks(
+    checks: Vec<(Check, Box<dyn Verifier + '_>)>,
+) -> Vec<(Check, Vec<VerificationResult>)> {
+    std::thread::scope(|s| {
+        let handles: Vec<_> = checks
+            .into_iter()
+            .map(|(check, verifier)| (check, s.spawn(move || verifier.verify())))
+            .collect();
+        handles
+            .into_iter()
+            .map(|(check, h)| (check, h.join().expect("check panicked")))
+            .collect()
+    })
+}
+
+/// Changes the severity of results of a check from one severity to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeverityOverride {
+    pub check: Check,
+    pub from: Severity,
+    pub to: Severity,
+}
+
+impl SeverityOverride {
+    /// Applies the first matching override to a result of the check.
+    pub fn apply(
+        overrides: &[SeverityOverride],
+        check: Check,
+        mut result: VerificationResult,
+    ) -> VerificationResult {
+        if let Some(o) = overrides
+            .iter()
+            .find(|o| o.check == check && o.from == result.severity)
+        {
+            result.severity = o.to;
+        }
+        result
+    }
+}
+
+/// Runs the selected checks against the data and returns their results with
+/// the checks whose data is missing skipped and the severity overrides
+/// applied.
+pub fn evaluate(
+    selected: &[Check],
+    setup: &CheckSetup,
+    overrides: &[SeverityOverride],
+) -> Vec<(Check, VerificationResult)> {
+    let missing = setup.data.missing_data_sets();
+    run_checks(setup_checks(selected, setup))
+        .into_iter()
+        .flat_map(|(check, results)| {
+            skip_missing_prerequisites(check.ids(), results, &missing)
+                .into_iter()
+                .map(move |res| (check, SeverityOverride::apply(overrides, check, res)))
+        })
+        .collect()
+}
+
+fn network<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let cn = ClusterNetworkBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .all_subnets(data.subnets.clone())
+        .routetables(data.routetables.clone())
+        .load_balancers(data.load_balancers.clone())
+        .load_balancer_enis(data.load_balancer_enis.clone())
+        .nat_gateways(data.nat_gateways.clone())
+        .internet_gateways(data.internet_gateways.clone())
+        .subnet_tag_value(ClusterTagValue::policy_for(
+            setup.cluster_tag_values,
+            TaggedResource::Subnets,
+        ))
+        .account_id(data.account_id.clone())
+        .build()
+        .unwrap();
+    Box::new(cn)
+}
+
+fn network_acl<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let na = NetworkAclChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .network_acls(setup.data.network_acls.clone())
+        .build()
+        .unwrap();
+    Box::new(na)
+}
+
+fn delegated_subnet<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ds = DelegatedSubnetChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .network_interfaces(setup.data.subnet_network_interfaces.clone())
+        .build()
+        .unwrap();
+    Box::new(ds)
+}
+
+fn hosted_zone<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let cluster_info = setup.cluster_info;
+    let data = setup.data;
+    let hz = HostedZoneChecksBuilder::default()
+        .hosted_zones(data.hosted_zones.clone())
+        .load_balancers(data.load_balancers.clone())
+        .region(cluster_info.region.clone())
+        .ingress_domains(cluster_info.ingress_domains.clone())
+        .infra_name(cluster_info.cluster_infra_name.clone())
+        .vpc_ids(
+            data.subnets
+                .iter()
+                .filter(|s| cluster_info.is_cluster_subnet(s))
+                .filter_map(|s| s.vpc_id.clone())
+                .unique()
+                .collect(),
+        )
+        .build()
+        .unwrap();
+    Box::new(hz)
+}
+
+fn availability_zone<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let az = AvailabilityZoneChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .availability_zones(setup.data.availability_zones.clone())
+        .instance_type_offerings(setup.data.instance_type_offerings.clone())
+        .build()
+        .unwrap();
+    Box::new(az)
+}
+
+fn machine_pool<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let mp = MachinePoolChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .build()
+        .unwrap();
+    Box::new(mp)
+}
+
+fn resolver_query_logs<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let rql = ResolverQueryLogChecksBuilder::default()
+        .query_log_configs(setup.data.resolver_query_log_configs.clone())
+        .query_logs(setup.data.resolver_query_logs.clone())
+        .instances(setup.data.instances.clone())
+        .build()
+        .unwrap();
+    Box::new(rql)
+}
+
+fn health_events<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let hec = HealthEventChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .events(setup.data.health_events.clone())
+        .build()
+        .unwrap();
+    Box::new(hec)
+}
+
+fn iam<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let iam = IamChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .instances(setup.data.instances.clone())
+        .instance_profiles(setup.data.instance_profiles.clone())
+        .build()
+        .unwrap();
+    Box::new(iam)
+}
+
+fn ipv6<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ic = Ipv6ChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .routetables(setup.data.routetables.clone())
+        .egress_only_internet_gateways(setup.data.egress_only_internet_gateways.clone())
+        .build()
+        .unwrap();
+    Box::new(ic)
+}
+
+fn shared_vpc<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let svc = SharedVpcChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .routetables(setup.data.routetables.clone())
+        .account_id(setup.data.account_id.clone())
+        .build()
+        .unwrap();
+    Box::new(svc)
+}
+
+fn hypershift<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let hc = HypershiftChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .load_balancers(setup.data.load_balancers.clone())
+        .vpc_endpoints(setup.data.vpc_endpoints.clone())
+        .build()
+        .unwrap();
+    Box::new(hc)
+}
+
+fn flow_logs<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let flc = FlowLogChecksBuilder::default()
+        .subnets(setup.data.subnets.clone())
+        .flow_logs(setup.data.flow_logs.clone())
+        .build()
+        .unwrap();
+    Box::new(flc)
+}
+
+fn quotas<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let qc = QuotaChecksBuilder::default()
+        .service_quotas(setup.data.service_quotas.clone())
+        .elastic_ips(setup.data.elastic_ips.clone())
+        .nat_gateways(setup.data.nat_gateways.clone())
+        .subnets(setup.data.subnets.clone())
+        .subnet_network_interfaces(setup.data.subnet_network_interfaces.clone())
+        .instances(setup.data.instances.clone())
+        .load_balancers(setup.data.load_balancers.clone())
+        .build()
+        .unwrap();
+    Box::new(qc)
+}
+
+fn proxy<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let pc = ProxyChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .egress(setup.proxy_egress)
+        .build()
+        .unwrap();
+    Box::new(pc)
+}
+
+fn active_probes<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let apc = ActiveProbeChecksBuilder::default()
+        .probes(setup.active_probes)
+        .build()
+        .unwrap();
+    Box::new(apc)
+}
+
+fn kubernetes<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let kc = KubernetesChecksBuilder::default()
+        .kubernetes(setup.kubernetes)
+        .load_balancers(setup.data.load_balancers.clone())
+        .instances(setup.data.instances.clone())
+        .hosted_zones(setup.data.hosted_zones.clone())
+        .build()
+        .unwrap();
+    Box::new(kc)
+}
+
+fn custom<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let snapshot = Snapshot::new(setup.cluster_info, setup.data);
+    let cc = CustomChecksBuilder::default()
+        .rules(setup.custom_rules.to_vec())
+        .data(serde_json::to_value(snapshot).unwrap_or_default())
+        .missing(setup.data.missing_data_sets())
+        .build()
+        .unwrap();
+    Box::new(cc)
+}
+
+fn cidr<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let cidr = CidrChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .vpcs(setup.data.vpcs.clone())
+        .build()
+        .unwrap();
+    Box::new(cidr)
+}
+
+fn dhcp_options<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let dc = DhcpOptionsChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .vpcs(setup.data.vpcs.clone())
+        .dhcp_options(setup.data.dhcp_options.clone())
+        .build()
+        .unwrap();
+    Box::new(dc)
+}
+
+fn vpc<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let vc = VpcChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .vpc_attributes(setup.data.vpc_attributes.clone())
+        .build()
+        .unwrap();
+    Box::new(vc)
+}
+
+fn target_groups<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let tgc = TargetGroupChecksBuilder::default()
+        .target_groups(setup.data.target_groups.clone())
+        .instances(setup.data.instances.clone())
+        .build()
+        .unwrap();
+    Box::new(tgc)
+}
+
+fn topology<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let tc = TopologyChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(data.subnets.clone())
+        .routetables(data.routetables.clone())
+        .nat_gateways(data.nat_gateways.clone())
+        .load_balancers(data.load_balancers.clone())
+        .instances(data.instances.clone())
+        .build()
+        .unwrap();
+    Box::new(tc)
+}
+
+fn cost<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let cc = CostChecksBuilder::default()
+        .subnets(data.subnets.clone())
+        .routetables(data.routetables.clone())
+        .nat_gateways(data.nat_gateways.clone())
+        .elastic_ips(data.elastic_ips.clone())
+        .load_balancers(data.load_balancers.clone())
+        .target_groups(data.target_groups.clone())
+        .build()
+        .unwrap();
+    Box::new(cc)
+}
+
+fn scaling<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let sc = ScalingChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(data.subnets.clone())
+        .routetables(data.routetables.clone())
+        .nat_gateways(data.nat_gateways.clone())
+        .instances(data.instances.clone())
+        .build()
+        .unwrap();
+    Box::new(sc)
+}
+
+fn ssm<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ssc = SsmChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .routetables(setup.data.routetables.clone())
+        .vpc_endpoints(setup.data.vpc_endpoints.clone())
+        .build()
+        .unwrap();
+    Box::new(ssc)
+}
+
+fn instance<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ic = InstanceChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .instances(setup.data.instances.clone())
+        .subnets(setup.data.subnets.clone())
+        .build()
+        .unwrap();
+    Box::new(ic)
+}
+
+fn security_group<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ic = InstanceChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .instances(setup.data.instances.clone())
+        .subnets(setup.data.subnets.clone())
+        .build()
+        .unwrap();
+    Box::new(SecurityGroupChecks(ic))
+}
+
+fn load_balancer<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let lbc = LoadBalancerChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .load_balancers(data.load_balancers.clone())
+        .load_balancer_attributes(data.load_balancer_attributes.clone())
+        .access_log_buckets(data.access_log_buckets.clone())
+        .target_groups(data.target_groups.clone())
+        .instances(data.instances.clone())
+        .cluster_tag_value(ClusterTagValue::policy_for(
+            setup.cluster_tag_values,
+            TaggedResource::LoadBalancers,
+        ))
+        .build()
+        .unwrap();
+    Box::new(lbc)
+}
+
+fn endpoint<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let ec = EndpointChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .subnets(setup.data.subnets.clone())
+        .routetables(setup.data.routetables.clone())
+        .vpc_endpoints(setup.data.vpc_endpoints.clone())
+        .build()
+        .unwrap();
+    Box::new(ec)
+}
+
+fn permission<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let pc = PermissionChecksBuilder::default()
+        .denials(setup.data.denials.clone())
+        .build()
+        .unwrap();
+    Box::new(pc)
+}
+
+fn egress_ip<'a>(setup: &CheckSetup<'a>) -> Box<dyn Verifier + 'a> {
+    let data = setup.data;
+    let eic = EgressIpChecksBuilder::default()
+        .cluster_info(setup.cluster_info)
+        .egress_ips(setup.egress_ips.to_vec())
+        .subnets(data.subnets.clone())
+        .network_acls(data.network_acls.clone())
+        .instances(data.instances.clone())
+        .build()
+        .unwrap();
+    Box::new(eic)
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_ec2::types::Subnet;
+    use aws_sdk_route53::types::{HostedZone, HostedZoneConfig};
+
+    use crate::{
+        gatherer::aws::shared_types::HostedZoneWithRecords,
+        types::{GatherFailure, MinimalClusterInfoBuilder, Severity},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_resolve_checks() {
+        assert_eq!(
+            resolve(&[Check::SecurityGroup, Check::Network, Check::Network])
+                .iter()
+                .map(|c| c.check)
+                .collect::<Vec<_>>(),
+            vec![Check::Network, Check::SecurityGroup]
+        );
+        assert_eq!(
+            resolve(&[Check::SecurityGroup, Check::Instance])
+                .iter()
+                .map(|c| c.check)
+                .collect::<Vec<_>>(),
+            vec![Check::Instance]
+        );
+        assert!(required_data_sets(&[Check::Iam]).contains(&DataSet::InstanceProfiles));
+        assert!(!required_data_sets(&[Check::Network]).contains(&DataSet::InstanceProfiles));
+        // Every check is run by a checker, except the checks of the
+        // subcommands and of clusters on Azure.
+        for c in REGISTRY.iter().filter(|c| {
+            !["DOC-", "PRE-", "LEFT-", "AZURE-"]
+                .iter()
+                .any(|p| c.id.starts_with(p))
+        }) {
+            assert!(
+                CHECKERS
+                    .iter()
+                    .any(|checker| checker.ids.iter().any(|id| c.id.starts_with(id))),
+                "{} is not run by any checker",
+                c.id
+            );
+        }
+    }
+
+    #[test]
+    fn test_gathered_data_sets() {
+        let gathered = gathered_data_sets(&[Check::HostedZone]);
+        assert!(gathered.contains(&DataSet::HostedZones));
+        assert!(!gathered.contains(&DataSet::Instances));
+        assert!(!gathered.contains(&DataSet::RouteTables));
+        // The checks must not be skipped because their data was not gathered.
+        for checker in CHECKERS.iter().filter(|c| c.check != Check::Custom) {
+            let gathered = gathered_data_sets(&[checker.check]);
+            for set in required_data_sets(&[checker.check]) {
+                assert!(
+                    gathered.contains(&set),
+                    "{:?} requires {} but does not gather it",
+                    checker.check,
+                    set
+                );
+            }
+        }
+    }
+
+    /// A cluster whose private zone is associated with another VPC than
+    /// the one of its subnets.
+    fn hosted_zone_data() -> AWSClusterData {
+        AWSClusterData {
+            subnets: vec![Subnet::builder()
+                .subnet_id("subnet-1")
+                .vpc_id("vpc-1")
+                .build()],
+            routetables: vec![],
+            load_balancers: vec![],
+            load_balancer_enis: vec![],
+            instances: vec![],
+            hosted_zones: vec![HostedZoneWithRecords {
+                hosted_zone: HostedZone::builder()
+                    .id("Z1")
+                    .name("mycluster.example.com.")
+                    .caller_reference("Z1")
+                    .config(HostedZoneConfig::builder().private_zone(true).build())
+                    .build()
+                    .unwrap(),
+                resource_records: vec![],
+                vpcs: vec!["vpc-2".to_string()],
+                name_servers: vec![],
+                delegated_name_servers: None,
+            }],
+            availability_zones: vec![],
+            instance_type_offerings: vec![],
+            resolver_query_log_configs: vec![],
+            resolver_query_logs: vec![],
+            nat_gateways: vec![],
+            elastic_ips: vec![],
+            target_groups: vec![],
+            load_balancer_attributes: vec![],
+            vpc_endpoints: vec![],
+            network_acls: vec![],
+            subnet_network_interfaces: vec![],
+            denials: vec![],
+            vpcs: vec![],
+            dhcp_options: vec![],
+            vpc_attributes: vec![],
+            access_log_buckets: vec![],
+            health_events: vec![],
+            internet_gateways: vec![],
+            egress_only_internet_gateways: vec![],
+            instance_profiles: vec![],
+            service_quotas: vec![],
+            flow_logs: vec![],
+            account_id: None,
+            failed: vec![],
+        }
+    }
+
+    #[test]
+    fn test_hosted_zone_vpcs_without_configured_subnets() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let data = hosted_zone_data();
+        let setup = CheckSetup {
+            cluster_info: &cluster_info,
+            data: &data,
+            cluster_tag_values: &[],
+            egress_ips: &[],
+            custom_rules: &[],
+            kubernetes: None,
+            proxy_egress: None,
+            active_probes: None,
+        };
+        let results: Vec<VerificationResult> = hosted_zone(&setup)
+            .verify()
+            .into_iter()
+            .filter(|r| r.check_id == Some("DNS-006"))
+            .collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("test".to_string())
+            .cluster_infra_name("mycluster-abcde".to_string())
+            .build()
+            .unwrap();
+        let mut data = hosted_zone_data();
+        let overrides = [SeverityOverride {
+            check: Check::HostedZone,
+            from: Severity::Critical,
+            to: Severity::Warning,
+        }];
+        let dns_006 = |data: &AWSClusterData| -> Vec<VerificationResult> {
+            let setup = CheckSetup {
+                cluster_info: &cluster_info,
+                data,
+                cluster_tag_values: &[],
+                egress_ips: &[],
+                custom_rules: &[],
+                kubernetes: None,
+                proxy_egress: None,
+                active_probes: None,
+            };
+            evaluate(&[Check::HostedZone], &setup, &overrides)
+                .into_iter()
+                .map(|(_, r)| r)
+                .filter(|r| r.check_id == Some("DNS-006"))
+                .collect()
+        };
+        let results = dns_006(&data);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].severity, Severity::Warning);
+        // Without the hosted zones the check is reported as skipped.
+        data.failed.push(GatherFailure {
+            set: DataSet::HostedZones,
+            reason: "AccessDenied".to_string(),
+            empty: false,
+        });
+        let results = dns_006(&data);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].severity, Severity::Info);
+        assert!(results[0]
+            .message
+            .ends_with("skipped: could not gather hosted zones (AccessDenied)"));
+    }
+}
@@ -0,0 +1,1306 @@
+//! Shared types that are used throughout the application.
+
+use crate::gatherer::ocm::OcmClient;
+use colored::Colorize;
+use derive_builder::Builder;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap, error::Error, fmt::Display, fs::File, io::BufReader, path::Path,
+    process::Command, str::FromStr,
+};
+
+/// Indicates an expected property did not hold - should indicate a failure.
+#[derive(Debug)]
+pub struct InvariantError {
+    pub msg: String,
+}
+
+impl Display for InvariantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl Error for InvariantError {
+    fn description(&self) -> &str {
+        &self.msg
+    }
+}
+
+/// Trait to wrap running the checks to be performed. Every check should return
+/// a number of VerificationResults that can be printed.
+/// Checks are independent of each other and run concurrently, so they must be
+/// `Send`.
+pub trait Verifier: Send {
+    fn verify(&self) -> Vec<VerificationResult>;
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ClusterType {
+    #[default]
+    #[serde(alias = "osd")]
+    Osd,
+    #[serde(alias = "rosa")]
+    Rosa,
+    #[serde(alias = "hypershift")]
+    Hypershift,
+}
+
+fn default_cloud_provider() -> String {
+    "aws".to_string()
+}
+
+#[derive(Builder, Clone, Debug, Serialize, Deserialize)]
+pub struct MinimalClusterInfo {
+    /// Defaults to the infra name in a cluster information file.
+    #[serde(default)]
+    pub cluster_id: String,
+    #[builder(default = "\"\".to_string()")]
+    pub cluster_infra_name: String,
+    #[builder(default = "ClusterType::Osd")]
+    #[serde(default)]
+    pub cluster_type: ClusterType,
+    #[builder(default = "\"AWS\".to_string()")]
+    #[serde(default = "default_cloud_provider")]
+    pub cloud_provider: String,
+    #[builder(default = "vec![]")]
+    #[serde(default)]
+    pub subnets: Vec<String>,
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub base_domain: Option<String>,
+    #[builder(default = "vec![]")]
+    #[serde(default)]
+    pub machine_pools: Vec<MachinePool>,
+    /// DNS names of additional ingresses configured in OCM.
+    #[builder(default = "vec![]")]
+    #[serde(default)]
+    pub ingress_domains: Vec<String>,
+    /// The API is only reachable from within the VPC - private and
+    /// PrivateLink clusters.
+    #[builder(default = "false")]
+    #[serde(default)]
+    pub private: bool,
+    /// The default router is only reachable from within the VPC - the default
+    /// ingress listens on `internal` in OCM.
+    #[builder(default = "false")]
+    #[serde(default)]
+    pub ingress_private: bool,
+    /// The cluster has no egress to the internet at all (egress lockdown) -
+    /// nodes reach AWS and the mirrored release images only through VPC
+    /// endpoints.
+    #[builder(default = "false")]
+    #[serde(default)]
+    pub zero_egress: bool,
+    /// The cluster network plugin, e.g. `OVNKubernetes`.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub network_type: Option<String>,
+    /// The AWS region the cluster is installed in.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub region: Option<String>,
+    /// The CIDR block the nodes are in - every subnet has to be part of it.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub machine_cidr: Option<String>,
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub service_cidr: Option<String>,
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub pod_cidr: Option<String>,
+    /// The control plane and the default machine pool are spread across 3
+    /// availability zones.
+    #[builder(default = "false")]
+    #[serde(default)]
+    pub multi_az: bool,
+    /// The HTTP(S) proxy the cluster sends its egress traffic through.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// The resources the cluster uses in Azure - `None` for clusters on
+    /// other cloud providers.
+    #[builder(default = "None")]
+    #[serde(default)]
+    pub azure: Option<AzureClusterInfo>,
+}
+
+/// The resources of a cluster on Azure (ARO and OSD on Azure) as configured in
+/// OCM. Resources are referenced by their ARM resource IDs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureClusterInfo {
+    pub subscription_id: String,
+    pub resource_group: String,
+    /// The resource group the cluster creates its own resources in, e.g. its
+    /// load balancers.
+    pub managed_resource_group: Option<String>,
+    /// The subnets of the customer virtual network the cluster is installed
+    /// into.
+    pub subnet_ids: Vec<String>,
+    /// The network security group the customer created for the cluster.
+    pub network_security_group_id: Option<String>,
+}
+
+/// A machine pool (or node pool for Hypershift) as configured in OCM.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MachinePool {
+    pub id: String,
+    pub instance_type: Option<String>,
+    pub availability_zones: Vec<String>,
+    pub subnets: Vec<String>,
+    /// The node count the pool can scale up to - the maximum of the
+    /// autoscaler, or the fixed replica count.
+    #[serde(default)]
+    pub max_replicas: Option<u64>,
+}
+
+impl MinimalClusterInfo {
+    fn get_cluster_json_cli(clusterid: &String) -> Result<serde_json::Value, Box<dyn Error>> {
+        let mut ocm = Command::new("ocm");
+        ocm.arg("describe")
+            .arg("cluster")
+            .arg("--json")
+            .arg(clusterid);
+
+        let output = ocm.output().map_err(|e| InvariantError {
+            msg: format!(
+                "Could not run the ocm CLI: {} - set OCM_CONFIG or OCM_TOKEN, or run `ocm login`",
+                e
+            ),
+        })?;
+        if !output.status.success() {
+            return Err(Box::new(InvariantError {
+                msg: format!(
+                    "The ocm CLI could not describe the cluster: {} - set OCM_CONFIG or OCM_TOKEN, or run `ocm login`",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ),
+            }));
+        }
+        let stdout_str = std::str::from_utf8(&output.stdout)?;
+        debug!("OCM Cluster information: {:}", stdout_str);
+        Ok(serde_json::from_str(stdout_str)?)
+    }
+
+    fn get_ocm_json_cli(path: &str) -> Option<serde_json::Value> {
+        let mut ocm = Command::new("ocm");
+        ocm.arg("get").arg(path);
+
+        let output = ocm.output().ok()?;
+        if !output.status.success() {
+            warn!(
+                "Could not retrieve {} from OCM: {}",
+                path,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return None;
+        }
+        let stdout_str = std::str::from_utf8(&output.stdout).ok()?;
+        debug!("OCM information for {}: {:}", path, stdout_str);
+        serde_json::from_str(stdout_str).ok()
+    }
+
+    /// Retrieves the cluster from the OCM API - falls back to the `ocm` CLI if
+    /// no API client is available or the request fails.
+    async fn get_cluster_json(
+        ocm: Option<&OcmClient>,
+        clusterid: &String,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        if let Some(ocm) = ocm {
+            match ocm.cluster(clusterid).await {
+                Ok(cluster_json) => return Ok(cluster_json),
+                Err(e) => warn!(
+                    "Could not retrieve cluster from the OCM API, using the ocm CLI: {}",
+                    e
+                ),
+            }
+        }
+        MinimalClusterInfo::get_cluster_json_cli(clusterid)
+    }
+
+    async fn get_ocm_json(ocm: Option<&OcmClient>, path: &str) -> Option<serde_json::Value> {
+        if let Some(ocm) = ocm {
+            match ocm.get(path, &[]).await {
+                Ok(json) => return Some(json),
+                Err(e) => warn!(
+                    "Could not retrieve {} from the OCM API, using the ocm CLI: {}",
+                    path, e
+                ),
+            }
+        }
+        MinimalClusterInfo::get_ocm_json_cli(path)
+    }
+
+    /// Whether the subnet is one of the configured subnets of the cluster -
+    /// every subnet is a cluster subnet if none are configured.
+    pub fn is_cluster_subnet(&self, subnet: &aws_sdk_ec2::types::Subnet) -> bool {
+        self.subnets.is_empty()
+            || subnet
+                .subnet_id()
+                .is_some_and(|id| self.subnets.iter().any(|s| s == id))
+    }
+
+    /// Reads the cluster information from a JSON file instead of OCM, for
+    /// accounts without access to it. The file has the format of the
+    /// `cluster_info` of a snapshot - only `cluster_infra_name` is required.
+    pub fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut cluster_info: MinimalClusterInfo = serde_json::from_reader(reader)?;
+        if cluster_info.cluster_infra_name.is_empty() {
+            return Err(Box::new(InvariantError {
+                msg: format!(
+                    "Cluster information {} has no cluster_infra_name",
+                    path.display()
+                ),
+            }));
+        }
+        if cluster_info.cluster_id.is_empty() {
+            cluster_info.cluster_id = cluster_info.cluster_infra_name.clone();
+        }
+        Ok(cluster_info)
+    }
+
+    /// Retrieves the cluster information from OCM - fails if the cluster
+    /// can not be retrieved or is missing data every check needs.
+    pub async fn get_cluster_info(clusterid: &String) -> Result<Self, Box<dyn Error>> {
+        let ocm = OcmClient::from_config();
+        if ocm.is_none() {
+            debug!("No OCM login found - using the ocm CLI");
+        }
+        let ocm = ocm.as_ref();
+        let cluster_json = MinimalClusterInfo::get_cluster_json(ocm, clusterid).await?;
+        let missing = |what: &str| InvariantError {
+            msg: format!("OCM returned no {} for cluster {}", what, clusterid),
+        };
+        let sxs = cluster_json
+            .get("aws")
+            .and_then(|v| v.get("subnet_ids"))
+            .and_then(|v| v.as_array());
+        let subnets: Vec<String> = if let Some(sx) = sxs {
+            if sx.is_empty() {
+                warn!("No subnet ids configured - this will make some checks relying on this useless.");
+                vec![]
+            } else {
+                sx.iter()
+                    .map(|v| {
+                        v.as_str()
+                            .map(|s| s.to_string())
+                            .ok_or_else(|| missing("valid subnet ids"))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+        } else {
+            warn!("No subnet ids configured - this will make some checks relying on this useless.");
+            vec![]
+        };
+        let cluster_type =
+            MinimalClusterInfo::cluster_type(&cluster_json).ok_or_else(|| InvariantError {
+                msg: "Could not determine product - only OSD, Rosa and Hypershift are supported."
+                    .to_string(),
+            })?;
+        debug!("Product is: {:?}", cluster_type);
+        let cluster_infra_name = match cluster_type {
+            ClusterType::Hypershift => cluster_json["id"].as_str().ok_or_else(|| missing("id"))?,
+            _ => cluster_json["infra_id"]
+                .as_str()
+                .ok_or_else(|| missing("infra id"))?,
+        };
+        let cloud_provider = cluster_json["cloud_provider"]["id"]
+            .as_str()
+            .ok_or_else(|| missing("cloud provider"))?;
+        let machine_pools =
+            MinimalClusterInfo::machine_pools(ocm, &cluster_json, &cluster_type).await;
+        let ingresses = MinimalClusterInfo::ingresses(ocm, &cluster_json).await;
+        Ok(MinimalClusterInfo {
+            cluster_id: clusterid.to_string(),
+            cluster_infra_name: cluster_infra_name.to_string(),
+            cluster_type,
+            cloud_provider: cloud_provider.to_string(),
+            subnets,
+            base_domain: MinimalClusterInfo::base_domain(&cluster_json),
+            machine_pools,
+            ingress_domains: MinimalClusterInfo::ingress_domains(&ingresses),
+            private: MinimalClusterInfo::private(&cluster_json),
+            ingress_private: MinimalClusterInfo::ingress_private(&ingresses),
+            zero_egress: MinimalClusterInfo::zero_egress(&cluster_json),
+            network_type: cluster_json["network"]["type"]
+                .as_str()
+                .map(|s| s.to_string()),
+            region: cluster_json["region"]["id"].as_str().map(|s| s.to_string()),
+            machine_cidr: cluster_json["network"]["machine_cidr"]
+                .as_str()
+                .map(|s| s.to_string()),
+            service_cidr: cluster_json["network"]["service_cidr"]
+                .as_str()
+                .map(|s| s.to_string()),
+            pod_cidr: cluster_json["network"]["pod_cidr"]
+                .as_str()
+                .map(|s| s.to_string()),
+            multi_az: cluster_json["multi_az"].as_bool() == Some(true),
+            proxy: MinimalClusterInfo::proxy(&cluster_json),
+            azure: MinimalClusterInfo::azure(&cluster_json),
+        })
+    }
+
+    fn azure(cluster_json: &serde_json::Value) -> Option<AzureClusterInfo> {
+        let azure = cluster_json.get("azure")?;
+        let string = |key: &str| {
+            azure[key]
+                .as_str()
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+        Some(AzureClusterInfo {
+            subscription_id: string("subscription_id")?,
+            resource_group: string("resource_group_name")?,
+            managed_resource_group: string("managed_resource_group_name"),
+            subnet_ids: string("subnet_resource_id").into_iter().collect(),
+            network_security_group_id: string("network_security_group_resource_id"),
+        })
+    }
+
+    /// The cluster-wide proxy - the HTTPS proxy is used for the endpoints
+    /// the cluster needs, the HTTP proxy only if there is none.
+    fn proxy(cluster_json: &serde_json::Value) -> Option<String> {
+        let proxy = &cluster_json["proxy"];
+        proxy["https_proxy"]
+            .as_str()
+            .or(proxy["http_proxy"].as_str())
+            .filter(|p| !p.is_empty())
+            .map(|p| p.to_string())
+    }
+
+    fn private(cluster_json: &serde_json::Value) -> bool {
+        cluster_json["aws"]["private_link"].as_bool() == Some(true)
+            || cluster_json["api"]["listening"].as_str() == Some("internal")
+    }
+
+    /// Zero egress is set as a property of the cluster, e.g. with `rosa create
+    /// cluster --properties zero_egress:true`.
+    fn zero_egress(cluster_json: &serde_json::Value) -> bool {
+        let property = &cluster_json["properties"]["zero_egress"];
+        property.as_str() == Some("true") || property.as_bool() == Some(true)
+    }
+
+    /// The ingresses of the cluster configured in OCM.
+    async fn ingresses(
+        ocm: Option<&OcmClient>,
+        cluster_json: &serde_json::Value,
+    ) -> Vec<serde_json::Value> {
+        let Some(id) = cluster_json.get("id").and_then(|v| v.as_str()) else {
+            return vec![];
+        };
+        let path = format!("/api/clusters_mgmt/v1/clusters/{}/ingresses", id);
+        let Some(ingresses_json) = MinimalClusterInfo::get_ocm_json(ocm, &path).await else {
+            return vec![];
+        };
+        ingresses_json
+            .get("items")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn ingress_domains(ingresses: &[serde_json::Value]) -> Vec<String> {
+        ingresses
+            .iter()
+            .filter_map(|i| i["dns_name"].as_str().map(|s| s.to_string()))
+            .collect()
+    }
+
+    fn ingress_private(ingresses: &[serde_json::Value]) -> bool {
+        ingresses
+            .iter()
+            .any(|i| i["default"].as_bool() == Some(true) && i["listening"] == "internal")
+    }
+
+    async fn machine_pools(
+        ocm: Option<&OcmClient>,
+        cluster_json: &serde_json::Value,
+        cluster_type: &ClusterType,
+    ) -> Vec<MachinePool> {
+        let Some(id) = cluster_json.get("id").and_then(|v| v.as_str()) else {
+            return vec![];
+        };
+        let pool_kind = match cluster_type {
+            ClusterType::Hypershift => "node_pools",
+            _ => "machine_pools",
+        };
+        let path = format!("/api/clusters_mgmt/v1/clusters/{}/{}", id, pool_kind);
+        let Some(pools_json) = MinimalClusterInfo::get_ocm_json(ocm, &path).await else {
+            warn!("No machine pools found - checks relying on them will be skipped.");
+            return vec![];
+        };
+        let strings = |v: Option<&serde_json::Value>| -> Vec<String> {
+            v.and_then(|v| v.as_array())
+                .map(|a| {
+                    a.iter()
+                        .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let items = pools_json
+            .get("items")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        items
+            .iter()
+            .map(|p| match cluster_type {
+                ClusterType::Hypershift => MachinePool {
+                    id: p["id"].as_str().unwrap_or_default().to_string(),
+                    instance_type: p["aws_node_pool"]["instance_type"]
+                        .as_str()
+                        .map(|s| s.to_string()),
+                    availability_zones: p["availability_zone"]
+                        .as_str()
+                        .map(|s| vec![s.to_string()])
+                        .unwrap_or_default(),
+                    subnets: p["subnet"]
+                        .as_str()
+                        .map(|s| vec![s.to_string()])
+                        .unwrap_or_default(),
+                    max_replicas: p["autoscaling"]["max_replica"]
+                        .as_u64()
+                        .or(p["replicas"].as_u64()),
+                },
+                _ => MachinePool {
+                    id: p["id"].as_str().unwrap_or_default().to_string(),
+                    instance_type: p["instance_type"].as_str().map(|s| s.to_string()),
+                    availability_zones: strings(p.get("availability_zones")),
+                    subnets: strings(p.get("subnets")),
+                    max_replicas: p["autoscaling"]["max_replicas"]
+                        .as_u64()
+                        .or(p["replicas"].as_u64()),
+                },
+            })
+            .collect()
+    }
+
+    fn cluster_type(cluster_json: &serde_json::Value) -> Option<ClusterType> {
+        debug!("Checking cluster type");
+        if let Some(hypershift) = cluster_json
+            .get("hypershift")
+            .and_then(|v| v.get("enabled"))
+        {
+            debug!("Checking hypershift: {}", hypershift);
+            if hypershift == true {
+                return Some(ClusterType::Hypershift);
+            }
+        }
+        if let Some(product) = cluster_json.get("product").and_then(|v| v.get("id")) {
+            debug!("Checking OSD|Rosa: {}", product);
+            if product == "osd" {
+                return Some(ClusterType::Osd);
+            } else if product == "rosa" {
+                return Some(ClusterType::Rosa);
+            }
+        }
+        return None;
+    }
+
+    /// The base domain of the cluster, derived from its API URL
+    /// (`https://api.<cluster>.<base domain>:6443`). `None` if the URL does
+    /// not have that form.
+    fn base_domain(cluster_json: &serde_json::Value) -> Option<String> {
+        let api_url = cluster_json
+            .get("api")
+            .and_then(|v| v.get("url"))
+            .and_then(|v| v.as_str())?;
+        let host = api_url.split_once("://").map_or(api_url, |(_, rest)| rest);
+        let host = host.split(['/', ':']).next().unwrap_or_default();
+        let parts: Vec<&str> = host.split('.').collect();
+        if parts.len() < 3 || parts.iter().any(|p| p.is_empty()) {
+            warn!("Can not derive the base domain from API URL {}", api_url);
+            return None;
+        }
+        let bd = parts[2..].join(".");
+        debug!("Base Domain calculated as: {}", bd);
+        Some(bd)
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// Well-known failure signatures of support cases. Findings that match one
+/// carry its code, so other tooling can route cases automatically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FindingCode {
+    MissingClusterTag,
+    IncorrectClusterTag,
+    MissingInternalElbTag,
+    MissingPublicElbTag,
+    TooManySubnetsPerAz,
+    MultipleRouteTableAssociations,
+    InactiveRouteTableAssociation,
+    LbInForeignSubnet,
+    LbProvisioningStuck,
+    LbEniLeaked,
+    NatGwDeleted,
+    NatGwUnavailable,
+    NatGwInForeignVpc,
+    NatGwInPrivateSubnet,
+    HostedZoneCount,
+    DuplicateHostedZone,
+    UnusedLoadBalancer,
+    RecordWithForeignLb,
+    RecordWithCrossRegionLb,
+    AzNotFound,
+    AzNotOptedIn,
+    AzUnavailable,
+    InstanceTypeNotOffered,
+    MachinePoolSubnetNotFound,
+    MachinePoolSubnetNotConfigured,
+    MachinePoolSubnetAzMismatch,
+    LbDeletionProtection,
+    LbTagsModified,
+    InstanceSecurityGroupNotFound,
+    MissingVpcEndpoint,
+    VpcEndpointUnavailable,
+    VpcEndpointNotAssociated,
+    VpcEndpointPrivateDnsDisabled,
+    EgressIpUnsupportedNetworkType,
+    EgressIpOutsideSubnets,
+    EgressIpSubnetExhausted,
+    EgressIpBlockedByNacl,
+    EgressIpBlockedBySecurityGroup,
+    NaclBlocksRequiredTraffic,
+    DeniedByPolicy,
+    IngressDomainRecordMissing,
+    IngressDomainWithForeignLb,
+    RecordWithWrongLb,
+    SubnetSharedWithService,
+    RouteBlackhole,
+    IgwDetached,
+    NoDefaultRoute,
+    MissingPrivateDnsName,
+    CustomDhcpDomainName,
+    SubnetOutsideMachineCidr,
+    CidrOverlap,
+    PartialAvailabilityZone,
+    CredentialsUnusable,
+    RegionNotConfigured,
+    OcmNotLoggedIn,
+    ProxyUnreachable,
+    BinaryMissing,
+    PrivateZoneNotAssociated,
+    ZoneNotDelegated,
+    ZoneDelegatedElsewhere,
+    DhcpNoDnsServers,
+    CustomDhcpDnsServers,
+    InvalidDhcpDomainName,
+    VpcDnsSupportDisabled,
+    VpcDnsHostnamesDisabled,
+    SubnetCapacityExceeded,
+    NatCapacityExceeded,
+    TargetUnhealthy,
+    TargetGroupEmpty,
+    HealthCheckPortBlocked,
+    AccessLogBucketMissing,
+    AccessLogBucketPolicyInvalid,
+    ApiTargetsDegraded,
+    LbSchemeMismatch,
+    InstanceProfileMissing,
+    InstanceProfileMissingPermissions,
+    SubnetMissingIpv6Cidr,
+    MissingIpv6DefaultRoute,
+    EgressOnlyIgwMissing,
+    ServiceLbMissing,
+    NodeInstanceMissing,
+    InstanceWithoutNode,
+    IngressRecordNotRouter,
+    ProxyEndpointUnreachable,
+    ControlPlaneNotSpread,
+    InstanceOutsideClusterVpc,
+    InstanceOutsideClusterSubnets,
+    QuotaNearlyExhausted,
+    QuotaExhausted,
+    SharedSubnet,
+    SharedSubnetTagMissing,
+    SharedSubnetTagNotShared,
+    SharedVpcWithoutInternetGateway,
+    LbCrossZoneDisabled,
+    LbIdleTimeoutTooShort,
+    AzureSubnetNotFound,
+    AzureSubnetWithoutNsg,
+    AzureSubnetNsgMismatch,
+    AzureNsgDeniesRequiredPort,
+    AzureLoadBalancersMissing,
+    AzurePrivateDnsZoneNotLinked,
+    FlowLogsDisabled,
+    FlowLogDeliveryFailed,
+    HostedServiceLbMissing,
+    PrivateLinkEndpointMissing,
+    PrivateLinkNotAccepted,
+    CnameAtZoneApex,
+    RecordNotAlias,
+    RecordTtlOutOfRange,
+    ConflictingRecords,
+    SecurityGroupOpenToInternet,
+    SecurityGroupRuleNarrowerThanMachineCidr,
+    SecurityGroupRuleOutsideMachineCidr,
+    EgressEndpointUnreachable,
+    RouterLbMissing,
+    MultipleVpcs,
+    DnsQueryFailed,
+}
+
+impl FindingCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingCode::MissingClusterTag => "MISSING_CLUSTER_TAG",
+            FindingCode::IncorrectClusterTag => "INCORRECT_CLUSTER_TAG",
+            FindingCode::MissingInternalElbTag => "MISSING_INTERNAL_ELB_TAG",
+            FindingCode::MissingPublicElbTag => "MISSING_PUBLIC_ELB_TAG",
+            FindingCode::TooManySubnetsPerAz => "TOO_MANY_SUBNETS_PER_AZ",
+            FindingCode::MultipleRouteTableAssociations => "MULTIPLE_ROUTE_TABLE_ASSOCIATIONS",
+            FindingCode::InactiveRouteTableAssociation => "INACTIVE_ROUTE_TABLE_ASSOCIATION",
+            FindingCode::LbInForeignSubnet => "LB_IN_FOREIGN_SUBNET",
+            FindingCode::LbProvisioningStuck => "LB_PROVISIONING_STUCK",
+            FindingCode::LbEniLeaked => "LB_ENI_LEAKED",
+            FindingCode::NatGwDeleted => "NAT_GW_DELETED",
+            FindingCode::NatGwUnavailable => "NAT_GW_UNAVAILABLE",
+            FindingCode::NatGwInForeignVpc => "NAT_GW_IN_FOREIGN_VPC",
+            FindingCode::NatGwInPrivateSubnet => "NAT_GW_IN_PRIVATE_SUBNET",
+            FindingCode::HostedZoneCount => "HOSTED_ZONE_COUNT",
+            FindingCode::DuplicateHostedZone => "DUPLICATE_HOSTED_ZONE",
+            FindingCode::UnusedLoadBalancer => "UNUSED_LOAD_BALANCER",
+            FindingCode::RecordWithForeignLb => "RECORD_WITH_FOREIGN_LB",
+            FindingCode::RecordWithCrossRegionLb => "RECORD_WITH_CROSS_REGION_LB",
+            FindingCode::AzNotFound => "AZ_NOT_FOUND",
+            FindingCode::AzNotOptedIn => "AZ_NOT_OPTED_IN",
+            FindingCode::AzUnavailable => "AZ_UNAVAILABLE",
+            FindingCode::InstanceTypeNotOffered => "INSTANCE_TYPE_NOT_OFFERED",
+            FindingCode::MachinePoolSubnetNotFound => "MACHINE_POOL_SUBNET_NOT_FOUND",
+            FindingCode::MachinePoolSubnetNotConfigured => "MACHINE_POOL_SUBNET_NOT_CONFIGURED",
+            FindingCode::MachinePoolSubnetAzMismatch => "MACHINE_POOL_SUBNET_AZ_MISMATCH",
+            FindingCode::LbDeletionProtection => "LB_DELETION_PROTECTION",
+            FindingCode::LbTagsModified => "LB_TAGS_MODIFIED",
+            FindingCode::InstanceSecurityGroupNotFound => "INSTANCE_SECURITY_GROUP_NOT_FOUND",
+            FindingCode::MissingVpcEndpoint => "MISSING_VPC_ENDPOINT",
+            FindingCode::VpcEndpointUnavailable => "VPC_ENDPOINT_UNAVAILABLE",
+            FindingCode::VpcEndpointNotAssociated => "VPC_ENDPOINT_NOT_ASSOCIATED",
+            FindingCode::VpcEndpointPrivateDnsDisabled => "VPC_ENDPOINT_PRIVATE_DNS_DISABLED",
+            FindingCode::EgressIpUnsupportedNetworkType => "EGRESS_IP_UNSUPPORTED_NETWORK_TYPE",
+            FindingCode::EgressIpOutsideSubnets => "EGRESS_IP_OUTSIDE_SUBNETS",
+            FindingCode::EgressIpSubnetExhausted => "EGRESS_IP_SUBNET_EXHAUSTED",
+            FindingCode::EgressIpBlockedByNacl => "EGRESS_IP_BLOCKED_BY_NACL",
+            FindingCode::EgressIpBlockedBySecurityGroup => "EGRESS_IP_BLOCKED_BY_SECURITY_GROUP",
+            FindingCode::NaclBlocksRequiredTraffic => "NACL_BLOCKS_REQUIRED_TRAFFIC",
+            FindingCode::DeniedByPolicy => "DENIED_BY_POLICY",
+            FindingCode::IngressDomainRecordMissing => "INGRESS_DOMAIN_RECORD_MISSING",
+            FindingCode::IngressDomainWithForeignLb => "INGRESS_DOMAIN_WITH_FOREIGN_LB",
+            FindingCode::RecordWithWrongLb => "RECORD_WITH_WRONG_LB",
+            FindingCode::SubnetSharedWithService => "SUBNET_SHARED_WITH_SERVICE",
+            FindingCode::RouteBlackhole => "ROUTE_BLACKHOLE",
+            FindingCode::IgwDetached => "IGW_DETACHED",
+            FindingCode::NoDefaultRoute => "NO_DEFAULT_ROUTE",
+            FindingCode::MissingPrivateDnsName => "MISSING_PRIVATE_DNS_NAME",
+            FindingCode::CustomDhcpDomainName => "CUSTOM_DHCP_DOMAIN_NAME",
+            FindingCode::SubnetOutsideMachineCidr => "SUBNET_OUTSIDE_MACHINE_CIDR",
+            FindingCode::CidrOverlap => "CIDR_OVERLAP",
+            FindingCode::PartialAvailabilityZone => "PARTIAL_AVAILABILITY_ZONE",
+            FindingCode::CredentialsUnusable => "CREDENTIALS_UNUSABLE",
+            FindingCode::RegionNotConfigured => "REGION_NOT_CONFIGURED",
+            FindingCode::OcmNotLoggedIn => "OCM_NOT_LOGGED_IN",
+            FindingCode::ProxyUnreachable => "PROXY_UNREACHABLE",
+            FindingCode::BinaryMissing => "BINARY_MISSING",
+            FindingCode::PrivateZoneNotAssociated => "PRIVATE_ZONE_NOT_ASSOCIATED",
+            FindingCode::ZoneNotDelegated => "ZONE_NOT_DELEGATED",
+            FindingCode::ZoneDelegatedElsewhere => "ZONE_DELEGATED_ELSEWHERE",
+            FindingCode::DhcpNoDnsServers => "DHCP_NO_DNS_SERVERS",
+            FindingCode::CustomDhcpDnsServers => "CUSTOM_DHCP_DNS_SERVERS",
+            FindingCode::InvalidDhcpDomainName => "INVALID_DHCP_DOMAIN_NAME",
+            FindingCode::VpcDnsSupportDisabled => "VPC_DNS_SUPPORT_DISABLED",
+            FindingCode::VpcDnsHostnamesDisabled => "VPC_DNS_HOSTNAMES_DISABLED",
+            FindingCode::SubnetCapacityExceeded => "SUBNET_CAPACITY_EXCEEDED",
+            FindingCode::NatCapacityExceeded => "NAT_CAPACITY_EXCEEDED",
+            FindingCode::TargetUnhealthy => "TARGET_UNHEALTHY",
+            FindingCode::TargetGroupEmpty => "TARGET_GROUP_EMPTY",
+            FindingCode::HealthCheckPortBlocked => "HEALTH_CHECK_PORT_BLOCKED",
+            FindingCode::AccessLogBucketMissing => "ACCESS_LOG_BUCKET_MISSING",
+            FindingCode::AccessLogBucketPolicyInvalid => "ACCESS_LOG_BUCKET_POLICY_INVALID",
+            FindingCode::ApiTargetsDegraded => "API_TARGETS_DEGRADED",
+            FindingCode::LbSchemeMismatch => "LB_SCHEME_MISMATCH",
+            FindingCode::InstanceProfileMissing => "INSTANCE_PROFILE_MISSING",
+            FindingCode::InstanceProfileMissingPermissions => {
+                "INSTANCE_PROFILE_MISSING_PERMISSIONS"
+            }
+            FindingCode::SubnetMissingIpv6Cidr => "SUBNET_MISSING_IPV6_CIDR",
+            FindingCode::MissingIpv6DefaultRoute => "MISSING_IPV6_DEFAULT_ROUTE",
+            FindingCode::EgressOnlyIgwMissing => "EGRESS_ONLY_IGW_MISSING",
+            FindingCode::ServiceLbMissing => "SERVICE_LB_MISSING",
+            FindingCode::NodeInstanceMissing => "NODE_INSTANCE_MISSING",
+            FindingCode::InstanceWithoutNode => "INSTANCE_WITHOUT_NODE",
+            FindingCode::IngressRecordNotRouter => "INGRESS_RECORD_NOT_ROUTER",
+            FindingCode::ProxyEndpointUnreachable => "PROXY_ENDPOINT_UNREACHABLE",
+            FindingCode::ControlPlaneNotSpread => "CONTROL_PLANE_NOT_SPREAD",
+            FindingCode::InstanceOutsideClusterVpc => "INSTANCE_OUTSIDE_CLUSTER_VPC",
+            FindingCode::InstanceOutsideClusterSubnets => "INSTANCE_OUTSIDE_CLUSTER_SUBNETS",
+            FindingCode::QuotaNearlyExhausted => "QUOTA_NEARLY_EXHAUSTED",
+            FindingCode::QuotaExhausted => "QUOTA_EXHAUSTED",
+            FindingCode::SharedSubnet => "SHARED_SUBNET",
+            FindingCode::SharedSubnetTagMissing => "SHARED_SUBNET_TAG_MISSING",
+            FindingCode::SharedSubnetTagNotShared => "SHARED_SUBNET_TAG_NOT_SHARED",
+            FindingCode::SharedVpcWithoutInternetGateway => "SHARED_VPC_WITHOUT_INTERNET_GATEWAY",
+            FindingCode::LbCrossZoneDisabled => "LB_CROSS_ZONE_DISABLED",
+            FindingCode::LbIdleTimeoutTooShort => "LB_IDLE_TIMEOUT_TOO_SHORT",
+            FindingCode::AzureSubnetNotFound => "AZURE_SUBNET_NOT_FOUND",
+            FindingCode::AzureSubnetWithoutNsg => "AZURE_SUBNET_WITHOUT_NSG",
+            FindingCode::AzureSubnetNsgMismatch => "AZURE_SUBNET_NSG_MISMATCH",
+            FindingCode::AzureNsgDeniesRequiredPort => "AZURE_NSG_DENIES_REQUIRED_PORT",
+            FindingCode::AzureLoadBalancersMissing => "AZURE_LOAD_BALANCERS_MISSING",
+            FindingCode::AzurePrivateDnsZoneNotLinked => "AZURE_PRIVATE_DNS_ZONE_NOT_LINKED",
+            FindingCode::FlowLogsDisabled => "FLOW_LOGS_DISABLED",
+            FindingCode::FlowLogDeliveryFailed => "FLOW_LOG_DELIVERY_FAILED",
+            FindingCode::HostedServiceLbMissing => "HOSTED_SERVICE_LB_MISSING",
+            FindingCode::PrivateLinkEndpointMissing => "PRIVATE_LINK_ENDPOINT_MISSING",
+            FindingCode::PrivateLinkNotAccepted => "PRIVATE_LINK_NOT_ACCEPTED",
+            FindingCode::CnameAtZoneApex => "CNAME_AT_ZONE_APEX",
+            FindingCode::RecordNotAlias => "RECORD_NOT_ALIAS",
+            FindingCode::RecordTtlOutOfRange => "RECORD_TTL_OUT_OF_RANGE",
+            FindingCode::ConflictingRecords => "CONFLICTING_RECORDS",
+            FindingCode::SecurityGroupOpenToInternet => "SECURITY_GROUP_OPEN_TO_INTERNET",
+            FindingCode::SecurityGroupRuleNarrowerThanMachineCidr => {
+                "SECURITY_GROUP_RULE_NARROWER_THAN_MACHINE_CIDR"
+            }
+            FindingCode::SecurityGroupRuleOutsideMachineCidr => {
+                "SECURITY_GROUP_RULE_OUTSIDE_MACHINE_CIDR"
+            }
+            FindingCode::EgressEndpointUnreachable => "EGRESS_ENDPOINT_UNREACHABLE",
+            FindingCode::RouterLbMissing => "ROUTER_LB_MISSING",
+            FindingCode::MultipleVpcs => "MULTIPLE_VPCS",
+            FindingCode::DnsQueryFailed => "DNS_QUERY_FAILED",
+        }
+    }
+
+    /// Whether the finding is about the tags of a resource - its evidence is a
+    /// [`TagDiff`].
+    pub fn is_tag_finding(&self) -> bool {
+        matches!(
+            self,
+            FindingCode::MissingClusterTag
+                | FindingCode::IncorrectClusterTag
+                | FindingCode::MissingInternalElbTag
+                | FindingCode::MissingPublicElbTag
+                | FindingCode::LbTagsModified
+        )
+    }
+}
+
+impl Display for FindingCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The data sets gathered from AWS that checks depend on, ordered so every
+/// data set comes after the data sets it is gathered from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSet {
+    Subnets,
+    RouteTables,
+    NetworkAcls,
+    NatGateways,
+    InternetGateways,
+    EgressOnlyInternetGateways,
+    ElasticIps,
+    VpcEndpoints,
+    SubnetNetworkInterfaces,
+    LoadBalancers,
+    LoadBalancerEnis,
+    LoadBalancerAttributes,
+    AccessLogBuckets,
+    TargetGroups,
+    HostedZones,
+    AvailabilityZones,
+    InstanceTypeOfferings,
+    ResolverQueryLogs,
+    HealthEvents,
+    Vpcs,
+    DhcpOptions,
+    VpcAttributes,
+    Instances,
+    InstanceProfiles,
+    ServiceQuotas,
+    FlowLogs,
+}
+
+impl DataSet {
+    pub const ALL: [DataSet; 26] = [
+        DataSet::Subnets,
+        DataSet::Vpcs,
+        DataSet::DhcpOptions,
+        DataSet::VpcAttributes,
+        DataSet::RouteTables,
+        DataSet::NetworkAcls,
+        DataSet::NatGateways,
+        DataSet::InternetGateways,
+        DataSet::EgressOnlyInternetGateways,
+        DataSet::ElasticIps,
+        DataSet::VpcEndpoints,
+        DataSet::SubnetNetworkInterfaces,
+        DataSet::LoadBalancers,
+        DataSet::LoadBalancerEnis,
+        DataSet::LoadBalancerAttributes,
+        DataSet::AccessLogBuckets,
+        DataSet::TargetGroups,
+        DataSet::HostedZones,
+        DataSet::AvailabilityZones,
+        DataSet::InstanceTypeOfferings,
+        DataSet::ResolverQueryLogs,
+        DataSet::HealthEvents,
+        DataSet::Instances,
+        DataSet::InstanceProfiles,
+        DataSet::ServiceQuotas,
+        DataSet::FlowLogs,
+    ];
+
+    /// The data sets this one is gathered from - it is missing if they are.
+    pub fn upstream(&self) -> &'static [DataSet] {
+        match self {
+            DataSet::RouteTables
+            | DataSet::NetworkAcls
+            | DataSet::NatGateways
+            | DataSet::InternetGateways
+            | DataSet::EgressOnlyInternetGateways
+            | DataSet::VpcEndpoints
+            | DataSet::SubnetNetworkInterfaces
+            | DataSet::ResolverQueryLogs
+            | DataSet::Vpcs
+            | DataSet::VpcAttributes
+            | DataSet::FlowLogs => &[DataSet::Subnets],
+            DataSet::DhcpOptions => &[DataSet::Vpcs],
+            DataSet::LoadBalancerEnis | DataSet::LoadBalancerAttributes | DataSet::TargetGroups => {
+                &[DataSet::LoadBalancers]
+            }
+            DataSet::AccessLogBuckets => &[DataSet::LoadBalancerAttributes],
+            DataSet::InstanceProfiles => &[DataSet::Instances],
+            _ => &[],
+        }
+    }
+
+    /// Whether the data set is gathered from the other one, directly or
+    /// through another data set.
+    pub fn depends_on(&self, other: DataSet) -> bool {
+        self.upstream()
+            .iter()
+            .any(|u| *u == other || u.depends_on(other))
+    }
+
+    /// Whether the data set is never empty for a cluster - if it is, the
+    /// discovery failed rather than the resources not existing.
+    pub fn never_empty(&self) -> bool {
+        matches!(
+            self,
+            DataSet::Subnets
+                | DataSet::Vpcs
+                | DataSet::RouteTables
+                | DataSet::NetworkAcls
+                | DataSet::LoadBalancers
+                | DataSet::HostedZones
+                | DataSet::AvailabilityZones
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataSet::Subnets => "subnets",
+            DataSet::RouteTables => "route tables",
+            DataSet::NetworkAcls => "network ACLs",
+            DataSet::NatGateways => "NAT gateways",
+            DataSet::InternetGateways => "internet gateways",
+            DataSet::EgressOnlyInternetGateways => "egress-only internet gateways",
+            DataSet::ElasticIps => "elastic IPs",
+            DataSet::VpcEndpoints => "VPC endpoints",
+            DataSet::SubnetNetworkInterfaces => "subnet ENIs",
+            DataSet::LoadBalancers => "load balancers",
+            DataSet::LoadBalancerEnis => "load balancer ENIs",
+            DataSet::LoadBalancerAttributes => "load balancer attributes",
+            DataSet::AccessLogBuckets => "access log buckets",
+            DataSet::TargetGroups => "target groups",
+            DataSet::HostedZones => "hosted zones",
+            DataSet::AvailabilityZones => "availability zones",
+            DataSet::InstanceTypeOfferings => "instance type offerings",
+            DataSet::ResolverQueryLogs => "resolver query logs",
+            DataSet::HealthEvents => "AWS Health events",
+            DataSet::Vpcs => "VPCs",
+            DataSet::DhcpOptions => "DHCP options",
+            DataSet::VpcAttributes => "VPC attributes",
+            DataSet::Instances => "instances",
+            DataSet::InstanceProfiles => "instance profiles",
+            DataSet::ServiceQuotas => "service quotas",
+            DataSet::FlowLogs => "flow logs",
+        }
+    }
+}
+
+impl Display for DataSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A data set the checks can not rely on, with the reason it is missing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GatherFailure {
+    pub set: DataSet,
+    /// Why the data set is missing, e.g. the error code of the failed call.
+    pub reason: String,
+    /// Whether the data set was gathered but is empty although a cluster
+    /// always has some, rather than gathering it failed.
+    #[serde(default)]
+    pub empty: bool,
+}
+
+impl Display for GatherFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.set, self.reason)
+    }
+}
+
+/// The resources whose cluster tag value can be configured.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaggedResource {
+    Subnets,
+    LoadBalancers,
+}
+
+/// The values the cluster tag (`kubernetes.io/cluster/<infra name>`) of a
+/// resource may have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagValuePolicy {
+    Owned,
+    Shared,
+    Either,
+}
+
+impl TagValuePolicy {
+    pub fn allows(&self, value: &str) -> bool {
+        match self {
+            TagValuePolicy::Owned => value == "owned",
+            TagValuePolicy::Shared => value == "shared",
+            TagValuePolicy::Either => value == "owned" || value == "shared",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagValuePolicy::Owned => "owned",
+            TagValuePolicy::Shared => "shared",
+            TagValuePolicy::Either => "owned or shared",
+        }
+    }
+
+    /// The value to set on a resource missing the tag: subnets of a BYOVPC
+    /// cluster are shared when either value is allowed.
+    pub fn suggested_value(&self) -> &'static str {
+        match self {
+            TagValuePolicy::Owned => "owned",
+            TagValuePolicy::Shared | TagValuePolicy::Either => "shared",
+        }
+    }
+}
+
+/// The expected cluster tag value of a resource type, given as
+/// `<resource>=<owned|shared|either>`, e.g. `subnets=shared` for environments
+/// that tag all subnets as shared, including the ones of the cluster.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClusterTagValue {
+    pub resource: TaggedResource,
+    pub policy: TagValuePolicy,
+}
+
+impl ClusterTagValue {
+    /// The policy configured for the resource, or the default: installer
+    /// created load balancers are owned, subnets may be either.
+    pub fn policy_for(values: &[ClusterTagValue], resource: TaggedResource) -> TagValuePolicy {
+        values
+            .iter()
+            .rev()
+            .find(|v| v.resource == resource)
+            .map(|v| v.policy)
+            .unwrap_or(match resource {
+                TaggedResource::Subnets => TagValuePolicy::Either,
+                TaggedResource::LoadBalancers => TagValuePolicy::Owned,
+            })
+    }
+}
+
+impl FromStr for ClusterTagValue {
+    type Err = InvariantError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            InvariantError {
+            msg: format!(
+                "Cluster tag value {} is not of the form <subnets|load_balancers>=<owned|shared|either>",
+                s
+            ),
+        }
+        };
+        let (resource, policy) = s.split_once('=').ok_or_else(invalid)?;
+        let resource = match resource {
+            "subnets" => TaggedResource::Subnets,
+            "load_balancers" => TaggedResource::LoadBalancers,
+            _ => return Err(invalid()),
+        };
+        let policy = match policy {
+            "owned" => TagValuePolicy::Owned,
+            "shared" => TagValuePolicy::Shared,
+            "either" => TagValuePolicy::Either,
+            _ => return Err(invalid()),
+        };
+        Ok(ClusterTagValue { resource, policy })
+    }
+}
+
+/// The kinds of resources results can be about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceType {
+    Subnet,
+    Vpc,
+    RouteTable,
+    NetworkAcl,
+    NatGateway,
+    InternetGateway,
+    EgressOnlyInternetGateway,
+    ElasticIp,
+    VpcEndpoint,
+    NetworkInterface,
+    SecurityGroup,
+    Instance,
+    InstanceProfile,
+    LoadBalancer,
+    TargetGroup,
+    HostedZone,
+    ResourceRecord,
+    DhcpOptions,
+    FlowLog,
+    AvailabilityZone,
+    HealthEvent,
+    ServiceQuota,
+    /// A `<host>:<port>` the cluster needs to reach.
+    Endpoint,
+    Proxy,
+    MachinePool,
+    KubernetesService,
+    KubernetesNode,
+    AzureSubnet,
+    AzureNetworkSecurityGroup,
+    AzurePrivateDnsZone,
+}
+
+impl ResourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResourceType::Subnet => "subnet",
+            ResourceType::Vpc => "vpc",
+            ResourceType::RouteTable => "route_table",
+            ResourceType::NetworkAcl => "network_acl",
+            ResourceType::NatGateway => "nat_gateway",
+            ResourceType::InternetGateway => "internet_gateway",
+            ResourceType::EgressOnlyInternetGateway => "egress_only_internet_gateway",
+            ResourceType::ElasticIp => "elastic_ip",
+            ResourceType::VpcEndpoint => "vpc_endpoint",
+            ResourceType::NetworkInterface => "network_interface",
+            ResourceType::SecurityGroup => "security_group",
+            ResourceType::Instance => "instance",
+            ResourceType::InstanceProfile => "instance_profile",
+            ResourceType::LoadBalancer => "load_balancer",
+            ResourceType::TargetGroup => "target_group",
+            ResourceType::HostedZone => "hosted_zone",
+            ResourceType::ResourceRecord => "resource_record",
+            ResourceType::DhcpOptions => "dhcp_options",
+            ResourceType::FlowLog => "flow_log",
+            ResourceType::AvailabilityZone => "availability_zone",
+            ResourceType::HealthEvent => "health_event",
+            ResourceType::ServiceQuota => "service_quota",
+            ResourceType::Endpoint => "endpoint",
+            ResourceType::Proxy => "proxy",
+            ResourceType::MachinePool => "machine_pool",
+            ResourceType::KubernetesService => "kubernetes_service",
+            ResourceType::KubernetesNode => "kubernetes_node",
+            ResourceType::AzureSubnet => "azure_subnet",
+            ResourceType::AzureNetworkSecurityGroup => "azure_network_security_group",
+            ResourceType::AzurePrivateDnsZone => "azure_private_dns_zone",
+        }
+    }
+}
+
+impl Display for ResourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// VerificationResult list all error conditions that can occur. These should be
+/// detailed enough to allow the user to fix the problem.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VerificationResult {
+    pub message: String,
+    pub severity: Severity,
+    /// Set if the finding matches a well-known failure signature.
+    pub code: Option<FindingCode>,
+    /// The stable ID of the check that produced the result, see
+    /// [`crate::checks::REGISTRY`].
+    pub check_id: Option<&'static str>,
+    /// The kind of resource the result is about, if it is about a single one.
+    pub resource_type: Option<ResourceType>,
+    /// The ID (or name) of the resource the result is about, e.g.
+    /// `subnet-0123456789abcdef0` - stable across versions, unlike the
+    /// message.
+    pub resource_id: Option<String>,
+    /// The raw data that triggered the finding (e.g. the offending route or
+    /// tag set), so it can be validated without gathering again.
+    pub evidence: Option<serde_json::Value>,
+}
+
+/// The evidence of a finding about the tags of a resource. The renderers merge
+/// the tag findings of a resource into one table of expected and actual tags.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagDiff {
+    pub resource: String,
+    /// The expected value of every tag the finding is about.
+    pub expected: BTreeMap<String, String>,
+    /// All tags of the resource.
+    pub actual: BTreeMap<String, String>,
+}
+
+impl TagDiff {
+    pub fn new<'a>(
+        resource: &str,
+        actual: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> TagDiff {
+        TagDiff {
+            resource: resource.to_string(),
+            expected: BTreeMap::new(),
+            actual: actual
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    /// The diff with only the expected tag, as the evidence of a finding.
+    pub fn evidence(&self, key: &str, value: &str) -> serde_json::Value {
+        let mut diff = self.clone();
+        diff.expected.insert(key.to_string(), value.to_string());
+        serde_json::to_value(diff).unwrap_or_default()
+    }
+
+    /// The diff of a tag finding, `None` for all other results.
+    pub fn of(result: &VerificationResult) -> Option<TagDiff> {
+        if !result.code.is_some_and(|c| c.is_tag_finding()) {
+            return None;
+        }
+        serde_json::from_value(result.evidence.clone()?).ok()
+    }
+
+    /// Adds the expected tags of another finding about the same resource.
+    pub fn merge(&mut self, other: TagDiff) {
+        self.expected.extend(other.expected);
+    }
+}
+
+impl Display for VerificationResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self.check_id {
+            Some(id) => format!("{}: {}", id, self.message),
+            None => self.message.clone(),
+        };
+        match self.severity {
+            Severity::Ok => f.write_str(&(format!("{} {}", "Ⓞ -".green(), message.green())))?,
+            Severity::Info => f.write_str(&format!("{} {}", "Ⓘ -".blue(), message.blue()))?,
+            Severity::Warning => {
+                f.write_str(&format!("{} {}", "Ⓦ -".yellow(), message.yellow()))?
+            }
+            Severity::Critical => f.write_str(&format!("{} {}", "Ⓔ -".red(), message.red()))?,
+        }
+        if let Some(code) = self.code {
+            write!(f, " [{}]", code)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cluster_info() {
+        let path = std::env::temp_dir().join("byovpc-checker-test-cluster-info.json");
+        std::fs::write(
+            &path,
+            r#"{"cluster_infra_name": "mycluster-abcde", "cluster_type": "rosa", "subnets": ["subnet-1"]}"#,
+        )
+        .unwrap();
+        let cluster_info = MinimalClusterInfo::read(&path).unwrap();
+        assert_eq!(cluster_info.cluster_id, "mycluster-abcde");
+        assert_eq!(cluster_info.cluster_type, ClusterType::Rosa);
+        assert_eq!(cluster_info.cloud_provider, "aws");
+        assert_eq!(cluster_info.subnets, vec!["subnet-1".to_string()]);
+
+        std::fs::write(&path, r#"{"subnets": ["subnet-1"]}"#).unwrap();
+        assert!(MinimalClusterInfo::read(&path).is_err());
+    }
+
+    #[test]
+    fn test_base_domain() {
+        let base_domain =
+            |url: &str| MinimalClusterInfo::base_domain(&serde_json::json!({"api": {"url": url}}));
+        assert_eq!(
+            base_domain("https://api.mycluster.abcd.p1.openshiftapps.com:6443"),
+            Some("abcd.p1.openshiftapps.com".to_string())
+        );
+        assert_eq!(
+            base_domain("https://api.mycluster.example.com"),
+            Some("example.com".to_string())
+        );
+        assert_eq!(base_domain("https://api.example:6443"), None);
+        assert_eq!(base_domain("api"), None);
+        assert_eq!(
+            MinimalClusterInfo::base_domain(&serde_json::json!({})),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_cluster_subnet() {
+        let subnet = |id: &str| aws_sdk_ec2::types::Subnet::builder().subnet_id(id).build();
+        let mut cluster_info = MinimalClusterInfoBuilder::default()
+            .cluster_id("1".to_string())
+            .build()
+            .unwrap();
+        assert!(cluster_info.is_cluster_subnet(&subnet("subnet-2")));
+        cluster_info.subnets = vec!["subnet-1".to_string()];
+        assert!(cluster_info.is_cluster_subnet(&subnet("subnet-1")));
+        assert!(!cluster_info.is_cluster_subnet(&subnet("subnet-2")));
+    }
+}
@@ -0,0 +1,171 @@
+//! Runs the checks against a fleet of clusters at once, e.g. all clusters of
+//! an organization, instead of calling the tool once per cluster.
+//!
+//! Every cluster is reported in its own section, followed by a summary of the
+//! findings of all clusters.
+
+use std::{io::Write, path::Path};
+
+use byovpc_checker_lib::types::{Severity, VerificationResult};
+
+/// The number of clusters gathered at the same time - every cluster already
+/// sends many requests concurrently, so more would mostly be throttled.
+pub const BATCH_CONCURRENCY: usize = 4;
+
+/// Reads the IDs of the clusters from a file with one ID per line. Empty
+/// lines and lines starting with `#` are skipped.
+pub fn read_cluster_ids(path: &Path) -> std::io::Result<Vec<String>> {
+    Ok(parse_cluster_ids(&std::fs::read_to_string(path)?))
+}
+
+fn parse_cluster_ids(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The results of the checks of one cluster of the batch.
+#[derive(Debug)]
+pub struct ClusterRun {
+    pub clusterid: String,
+    pub results: Vec<VerificationResult>,
+}
+
+impl ClusterRun {
+    fn count(&self, severity: Severity) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.severity == severity)
+            .count()
+    }
+
+    /// The highest severity of the results of the cluster.
+    pub fn worst(&self) -> Severity {
+        self.results
+            .iter()
+            .map(|r| r.severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+}
+
+/// The result reported for a cluster whose data could not be gathered, so
+/// the other clusters are still checked and it shows up in the summary.
+pub fn gathering_failed(error: &str) -> VerificationResult {
+    VerificationResult {
+        message: format!("Gathering failed: {}", error),
+        severity: Severity::Critical,
+        code: None,
+        check_id: None,
+        resource_type: None,
+        resource_id: None,
+        evidence: None,
+    }
+}
+
+/// Writes the number of findings per severity of every cluster and of all
+/// clusters together.
+pub fn write_summary(runs: &[ClusterRun], out: &mut dyn Write) -> std::io::Result<()> {
+    let total = format!("{} clusters", runs.len());
+    let width = runs
+        .iter()
+        .map(|r| r.clusterid.len())
+        .chain(["CLUSTER".len(), total.len()])
+        .max()
+        .unwrap_or(0);
+    writeln!(
+        out,
+        "{:width$}  CRITICAL  WARNING  INFO  RESULT",
+        "CLUSTER",
+        width = width
+    )?;
+    let mut totals = [0; 3];
+    for run in runs.iter() {
+        let counts = [
+            run.count(Severity::Critical),
+            run.count(Severity::Warning),
+            run.count(Severity::Info),
+        ];
+        for (total, count) in totals.iter_mut().zip(counts) {
+            *total += count;
+        }
+        writeln!(
+            out,
+            "{:width$}  {:>8}  {:>7}  {:>4}  {}",
+            run.clusterid,
+            counts[0],
+            counts[1],
+            counts[2],
+            run.worst().as_str(),
+            width = width
+        )?;
+    }
+    let worst = runs
+        .iter()
+        .map(ClusterRun::worst)
+        .max()
+        .unwrap_or(Severity::Ok);
+    writeln!(
+        out,
+        "{:width$}  {:>8}  {:>7}  {:>4}  {}",
+        total,
+        totals[0],
+        totals[1],
+        totals[2],
+        worst.as_str(),
+        width = width
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_summary() {
+        assert_eq!(
+            parse_cluster_ids("# production\nabc\n\n  def  \n"),
+            vec!["abc".to_string(), "def".to_string()]
+        );
+        let result = |severity| VerificationResult {
+            message: String::new(),
+            severity,
+            code: None,
+            check_id: Some("NET-001"),
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let runs = vec![
+            ClusterRun {
+                clusterid: "abc".to_string(),
+                results: vec![result(Severity::Critical), result(Severity::Warning)],
+            },
+            ClusterRun {
+                clusterid: "def".to_string(),
+                results: vec![result(Severity::Ok)],
+            },
+        ];
+        let mut out = vec![];
+        write_summary(&runs, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "CLUSTER     CRITICAL  WARNING  INFO  RESULT\n\
+             abc                1        1     0  critical\n\
+             def                0        0     0  ok\n\
+             2 clusters         1        1     0  critical\n"
+        );
+        let failed = ClusterRun {
+            clusterid: "ghi".to_string(),
+            results: vec![gathering_failed("Must set a clusterid to proceed.")],
+        };
+        assert_eq!(failed.worst(), Severity::Critical);
+        assert_eq!(
+            failed.results[0].message,
+            "Gathering failed: Must set a clusterid to proceed."
+        );
+    }
+}
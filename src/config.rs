@@ -0,0 +1,104 @@
+//! Defaults for the command line options, read from a TOML file. Teams can
+//! share and version their configuration instead of passing long command
+//! lines.
+//!
+//! Options given on the command line always take precedence over the file.
+//! The file is read from `--config`, or `$XDG_CONFIG_HOME/byovpc-checker.toml`
+//! (`~/.config/byovpc-checker.toml`) if it exists.
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use byovpc_checker_lib::types::InvariantError;
+
+const CONFIG_FILE: &str = "byovpc-checker.toml";
+
+/// The options that can be set in the config file. Enums use the same values
+/// as on the command line.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub region: Option<String>,
+    pub profile: Option<String>,
+    pub format: Option<String>,
+    #[serde(default)]
+    pub checks: Vec<String>,
+    pub policy: Option<String>,
+    pub fail_on: Option<String>,
+    /// The proxy of the AWS requests unless `HTTPS_PROXY` is set in the
+    /// environment.
+    pub proxy: Option<String>,
+}
+
+/// The config file in the user's config directory.
+fn default_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(CONFIG_FILE))
+}
+
+impl Config {
+    /// Reads the config file - an explicitly given file must exist, the
+    /// default file is optional.
+    pub fn load(path: Option<&Path>) -> Result<Config, InvariantError> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let content = std::fs::read_to_string(&path).map_err(|e| InvariantError {
+            msg: format!("Could not read config file {}: {}", path.display(), e),
+        })?;
+        Config::parse(&content).map_err(|e| InvariantError {
+            msg: format!("Invalid config file {}: {}", path.display(), e.msg),
+        })
+    }
+
+    pub fn parse(content: &str) -> Result<Config, InvariantError> {
+        toml::from_str(content).map_err(|e| InvariantError {
+            msg: e.message().to_string(),
+        })
+    }
+}
+
+/// Parses the value of a key of the config file like clap parses the option.
+pub fn parse_value<T: ValueEnum>(key: &str, value: &str) -> Result<T, InvariantError> {
+    T::from_str(value, true).map_err(|_| InvariantError {
+        msg: format!("Invalid value {} for {} in the config file", value, key),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+region = "eu-west-1"
+format = "html"
+checks = ["network", "vpc"]
+fail-on = "warning"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                region: Some("eu-west-1".to_string()),
+                format: Some("html".to_string()),
+                checks: vec!["network".to_string(), "vpc".to_string()],
+                fail_on: Some("warning".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(Config::parse("regoin = \"eu-west-1\"").is_err());
+    }
+}
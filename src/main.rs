@@ -2,118 +2,1418 @@
 //! possible problems when attempting to run Openshift clusters. It focuses on
 //! bring-your-own-VPC checks - meaning the networking setup was performed by
 //! the user, not the installer.
+//!
+//! The gathering and the checks are implemented in `byovpc-checker-lib`, this
+//! is the command line interface to it.
 
-mod checks;
-mod gatherer;
-mod types;
+mod batch;
+mod config;
+mod policy;
+mod serve;
 
+use aws_config::meta::region::RegionProviderChain;
+use aws_config::SdkConfig;
 use aws_sdk_ec2::Error;
-use checks::{dns::HostedZoneChecksBuilder, network::ClusterNetworkBuilder};
-use clap::Parser;
+use batch::{gathering_failed, read_cluster_ids, write_summary, ClusterRun, BATCH_CONCURRENCY};
+use byovpc_checker_lib::baseline::Baseline;
+use byovpc_checker_lib::checks::{
+    azure::AzureChecksBuilder,
+    custom::{load_rules, Rule},
+    doctor::{Binary, DoctorChecksBuilder},
+    leftovers::LeftoverResourceChecksBuilder,
+    preflight::{verdict, PreflightChecksBuilder},
+};
+use byovpc_checker_lib::diff::{diff_findings, diff_snapshots};
+use byovpc_checker_lib::gatherer::aws::credentials::{
+    active_profile, profile_for_sso_session, sso_login_for_profile, verify_credentials,
+};
+use byovpc_checker_lib::gatherer::aws::overrides::Override;
+use byovpc_checker_lib::gatherer::aws::patch::read_patch;
+use byovpc_checker_lib::gatherer::aws::retry::{self, DEFAULT_MAX_RETRIES};
+use byovpc_checker_lib::gatherer::aws::snapshot::Snapshot;
+use byovpc_checker_lib::gatherer::aws::{
+    https_proxy_from_env, proxy_without_credentials, AWSClusterData, Environment, GatherError,
+    GatherOptions, DEFAULT_MAX_CONCURRENCY, DEFAULT_MAX_LOAD_BALANCERS, DEFAULT_MAX_RECORDS,
+    DEFAULT_MAX_SUBNETS,
+};
+use byovpc_checker_lib::gatherer::azure::{self, AzureClient};
+use byovpc_checker_lib::gatherer::k8s::{self, KubeClient, KubernetesData};
+use byovpc_checker_lib::gatherer::local::{
+    binary_version, probe_egress, probe_proxy, required_endpoints, run_active_probes, ActiveProbes,
+    ProxyEgress,
+};
+use byovpc_checker_lib::gatherer::ocm::OcmClient;
+use byovpc_checker_lib::output::{
+    html::HtmlRenderer, junit::JunitRenderer, sarif::SarifRenderer, terraform::TerraformRenderer,
+    tree::TreeRenderer, CheckRunReport, Renderer, SummaryRenderer,
+};
+use byovpc_checker_lib::selection::{gathered_data_sets, required_data_sets, Check, CheckSetup};
+use byovpc_checker_lib::types::{
+    ClusterTagValue, ClusterType, DataSet, FindingCode, InvariantError, MinimalClusterInfo,
+    MinimalClusterInfoBuilder, Severity, VerificationResult,
+};
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
 use colored::Colorize;
-use gatherer::aws::AWSClusterData;
+use config::{parse_value, Config};
+use log::{error, info};
+use policy::Policy;
+use serve::{Metrics, DEFAULT_SERVE_INTERVAL};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use types::MinimalClusterInfo;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 
-use crate::types::Verifier;
+use byovpc_checker_lib::types::Verifier;
 
 #[derive(Clone, Debug, clap::ValueEnum)]
 enum OutputFormat {
     Checks,
     Debug,
+    /// A SARIF 2.1.0 log for CI pipelines and security dashboards.
+    Sarif,
+    /// A self-contained HTML report to attach to support cases.
+    Html,
+    /// A JUnit XML report for test stages of CI pipelines.
+    Junit,
 }
 
-#[derive(Clone, Debug, clap::ValueEnum)]
-enum Check {
-    Network,
-    HostedZone,
+impl OutputFormat {
+    /// Formats meant to be parsed by other tools - nothing but the results may
+    /// be written to the terminal.
+    fn is_machine_readable(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Debug | OutputFormat::Sarif | OutputFormat::Junit
+        )
+    }
+}
+
+/// The checks run without `--checks` and `--policy`.
+const DEFAULT_CHECKS: [Check; 18] = [
+    Check::Network,
+    Check::NetworkAcl,
+    Check::Cidr,
+    Check::Topology,
+    Check::DhcpOptions,
+    Check::Vpc,
+    Check::TargetGroups,
+    Check::DelegatedSubnet,
+    Check::HostedZone,
+    Check::AvailabilityZone,
+    Check::MachinePool,
+    Check::LoadBalancer,
+    Check::Endpoint,
+    Check::Permission,
+    Check::Ipv6,
+    Check::Proxy,
+    Check::SharedVpc,
+    Check::Hypershift,
+];
+
+/// The help of `--checks`, built from [`DEFAULT_CHECKS`] so it lists the
+/// checks that actually run by default.
+fn checks_help() -> String {
+    format!(
+        "Checks to run - defaults to the checks of the policy, or the {} checks without a policy",
+        DEFAULT_CHECKS
+            .iter()
+            .filter_map(|c| c.to_possible_value())
+            .map(|v| v.get_name().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// The formats the fixes of the findings can be written in.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum FixFormat {
+    /// `aws_ec2_tag` resources of the Terraform AWS provider.
+    Terraform,
+}
+
+impl FixFormat {
+    fn renderer(&self) -> Box<dyn Renderer> {
+        match self {
+            FixFormat::Terraform => Box::new(TerraformRenderer),
+        }
+    }
+}
+
+/// The lowest severity that makes the checks fail with a non-zero exit code.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum FailOn {
+    Warning,
+    Critical,
+}
+
+impl FailOn {
+    fn severity(&self) -> Severity {
+        match self {
+            FailOn::Warning => Severity::Warning,
+            FailOn::Critical => Severity::Critical,
+        }
+    }
 }
 
 #[derive(Parser, Debug, Clone)]
 #[command(
     version,
     about = "Verifies if the VPC setup for the cluster is valid. AWS configuration must be setup to access the cluster's AWS account.",
-    long_about = "Verifies if the VPC setup for the cluster is valid. AWS configuration must be setup to access the cluster's AWS account."
+    long_about = "Verifies if the VPC setup for the cluster is valid. AWS configuration must be setup to access the cluster's AWS account.",
+    subcommand_negates_reqs = true
 )]
 struct Options {
-    #[arg(short, long)]
-    clusterid: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Read the defaults of the options from this TOML file instead of
+    /// `~/.config/byovpc-checker.toml`.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// The cluster to check - repeat it to check several clusters at once.
+    #[arg(short, long, required_unless_present_any = ["from_snapshot", "list_checks", "clusters_file", "infra_name", "cluster_info_file"])]
+    clusterid: Vec<String>,
+    /// Check the clusters listed in this file, one ID per line, in addition
+    /// to the ones given with `--clusterid`.
+    #[arg(long, value_name = "FILE", conflicts_with = "from_snapshot")]
+    clusters_file: Option<PathBuf>,
+    /// Check the cluster with this infra name (e.g. `mycluster-abcde`)
+    /// without querying OCM - its resources are found by the cluster tag.
+    #[arg(long, conflicts_with_all = ["clusterid", "clusters_file", "from_snapshot", "cluster_info_file"])]
+    infra_name: Option<String>,
+    /// The subnets of the cluster given with `--infra-name`.
+    #[arg(long, value_delimiter = ',', requires = "infra_name")]
+    subnets: Vec<String>,
+    /// The base domain of the cluster given with `--infra-name`.
+    #[arg(long, requires = "infra_name")]
+    base_domain: Option<String>,
+    /// The type of the cluster given with `--infra-name`.
+    #[arg(long, value_enum, default_value_t = ClusterType::Osd, requires = "infra_name")]
+    cluster_type: ClusterType,
+    /// Read the information of the cluster from this JSON file instead of
+    /// OCM - it has the format of the `cluster_info` of a snapshot.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["clusterid", "clusters_file", "from_snapshot"])]
+    cluster_info_file: Option<PathBuf>,
     #[command(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
     #[arg(short, long, value_enum, default_value_t = OutputFormat::Checks)]
     format: OutputFormat,
-    #[arg(long, value_enum, default_values_t = vec![Check::Network, Check::HostedZone])]
+    /// Use the first AWS profile that is configured for this AWS IAM Identity
+    /// Center (SSO) session.
+    #[arg(long, conflicts_with = "profile")]
+    sso_session: Option<String>,
+    /// The AWS profile to use instead of the default profile (or
+    /// `AWS_PROFILE`).
+    #[arg(long)]
+    profile: Option<String>,
+    /// The AWS region of the cluster - defaults to the region of the cluster
+    /// in OCM, then to the region of the AWS configuration.
+    #[arg(long)]
+    region: Option<String>,
+    #[arg(long, value_enum, help = checks_help())]
     checks: Vec<Check>,
+    /// Run the custom rules of this YAML file in addition to the selected
+    /// checks - can be repeated.
+    #[arg(long, value_name = "FILE")]
+    rules: Vec<PathBuf>,
+    /// The rules loaded from `--rules`.
+    #[arg(skip)]
+    custom_rules: Vec<Rule>,
+    /// The proxy of the AWS requests - `HTTPS_PROXY`, then the proxy of the
+    /// configuration file.
+    #[arg(skip)]
+    proxy: Option<String>,
+    /// Read the Services and nodes of the cluster with the current context of
+    /// this kubeconfig and cross-check them with the AWS resources.
+    #[arg(long, value_name = "FILE", conflicts_with = "from_snapshot")]
+    kubeconfig: Option<PathBuf>,
+    /// Resolve and connect to the endpoints the cluster needs (OCM, the
+    /// registries, the AWS APIs of the region) from this machine, through the
+    /// proxy of the cluster if it has one.
+    #[arg(long, conflicts_with = "from_snapshot")]
+    active_probes: bool,
+    /// Bundles the checks to run, severity overrides and output verbosity for
+    /// a specific consumer.
+    #[arg(long, value_enum)]
+    policy: Option<Policy>,
+    /// Gather all records of the hosted zones instead of only the API,
+    /// ingress and apps records of the cluster.
+    #[arg(long)]
+    all_records: bool,
+    /// Abort if more subnets are found - filter by VPC instead of running
+    /// against huge shared accounts.
+    #[arg(long, default_value_t = DEFAULT_MAX_SUBNETS)]
+    max_subnets: usize,
+    /// Abort if the account has more load balancers.
+    #[arg(long, default_value_t = DEFAULT_MAX_LOAD_BALANCERS)]
+    max_load_balancers: usize,
+    /// Abort if the hosted zones have more records.
+    #[arg(long, default_value_t = DEFAULT_MAX_RECORDS)]
+    max_records: usize,
+    /// Retry AWS requests that were throttled or failed with a transient
+    /// server error this often, with an increasing, jittered delay.
+    #[arg(long, default_value_t = DEFAULT_MAX_RETRIES)]
+    max_retries: u32,
+    /// Gather at most this many data sets from the AWS APIs at the same time.
+    #[arg(long, default_value_t = DEFAULT_MAX_CONCURRENCY)]
+    max_concurrency: usize,
+    /// Write all gathered data to this file, so the checks can be run again
+    /// later with `--from-snapshot`.
+    #[arg(long, conflicts_with = "from_snapshot")]
+    snapshot: Option<PathBuf>,
+    /// Run the checks against a file written with `--snapshot` instead of
+    /// querying OCM and AWS.
+    #[arg(long)]
+    from_snapshot: Option<PathBuf>,
+    /// Read a data set from a file instead of gathering it, e.g.
+    /// `subnets=./subnets.json` - for APIs the credentials can not call. The
+    /// file has the format of the data set in a snapshot.
+    #[arg(
+        long = "override",
+        value_name = "DATA_SET=FILE",
+        conflicts_with = "from_snapshot"
+    )]
+    overrides: Vec<Override>,
+    /// Apply the changes of this patch file (e.g. adding a tag or deleting a
+    /// NAT gateway) to the gathered data before running the checks, to
+    /// predict the impact of a planned change.
+    #[arg(long)]
+    simulate: Option<PathBuf>,
+    /// JSON file mapping finding codes to the support articles describing the
+    /// fix, e.g. `{"NAT_GW_DELETED": "KCS 1234567"}`.
+    #[arg(long)]
+    articles: Option<PathBuf>,
+    /// The egress IPs of the cluster (e.g. from `oc get egressip`) to verify
+    /// the subnets can host.
+    #[arg(long, value_delimiter = ',')]
+    egress_ips: Vec<String>,
+    /// The value the cluster tag of a resource type must have, e.g.
+    /// `subnets=shared` for environments that tag the cluster subnets as
+    /// shared. Subnets default to either, load balancers to owned.
+    #[arg(long = "cluster-tag-value", value_name = "RESOURCE=VALUE")]
+    cluster_tag_values: Vec<ClusterTagValue>,
+    /// Exit with a non-zero code if any check reports a result of at least
+    /// this severity.
+    #[arg(long, value_enum, default_value_t = FailOn::Critical)]
+    fail_on: FailOn,
+    /// Only print the results of at least this severity with the checks
+    /// format.
+    #[arg(long, value_enum)]
+    min_severity: Option<Severity>,
+    /// Print one line per check with the number of results of each severity
+    /// instead of every result with the checks format.
+    #[arg(long)]
+    summary: bool,
+    /// Report only findings that are not recorded in this file. If it does
+    /// not exist, all findings of the run are recorded in it.
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+    /// Write the fixes of the fixable findings (missing or incorrect subnet
+    /// tags) in this format to `--fixes-file`.
+    #[arg(long, value_enum)]
+    emit_fixes: Option<FixFormat>,
+    /// The file `--emit-fixes` writes the fixes to.
+    #[arg(
+        long,
+        value_name = "FILE",
+        default_value = "fixes.tf",
+        requires = "emit_fixes"
+    )]
+    fixes_file: PathBuf,
+    /// Print the IDs and descriptions of all checks and exit.
+    #[arg(long)]
+    list_checks: bool,
+    /// Keep running and serve the results of the checks as Prometheus metrics
+    /// on `/metrics` of this address, e.g. `0.0.0.0:9090`.
+    #[arg(long, value_name = "ADDR", conflicts_with_all = ["from_snapshot", "snapshot", "simulate"])]
+    serve: Option<SocketAddr>,
+    /// Seconds between two runs of the checks with `--serve`.
+    #[arg(
+        long,
+        default_value_t = DEFAULT_SERVE_INTERVAL,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "serve"
+    )]
+    interval: u64,
+}
+
+#[derive(clap::Subcommand, Debug, Clone)]
+enum Command {
+    /// Verifies a VPC before a cluster is installed into it, e.g. before
+    /// running `rosa create cluster`.
+    Preflight(PreflightOptions),
+    /// Finds resources that are still tagged for a deleted cluster and prints
+    /// the commands to delete them.
+    CleanupAudit(CleanupAuditOptions),
+    /// Verifies the local environment: AWS credentials, region, OCM login,
+    /// proxy and the installed CLIs.
+    Doctor,
+    /// Compares two snapshots of a cluster and reports the resources and
+    /// findings that were added, removed or changed.
+    Diff(DiffOptions),
+    /// Gathers the data of the cluster without running any checks and prints
+    /// it in the format of a snapshot.
+    Gather(GatherCommandOptions),
+}
+
+/// The formats the gathered data can be printed in.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum DataFormat {
+    Json,
+    Yaml,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct GatherCommandOptions {
+    #[arg(long, value_enum, default_value_t = DataFormat::Json)]
+    format: DataFormat,
+    /// Write the data to this file instead of stdout.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct DiffOptions {
+    /// The older snapshot, e.g. of when the cluster last worked.
+    before: PathBuf,
+    /// The newer snapshot.
+    after: PathBuf,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct CleanupAuditOptions {
+    /// The infra ID of the deleted cluster.
+    #[arg(long)]
+    infra_id: String,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+struct PreflightOptions {
+    /// The VPC the cluster will be installed into.
+    #[arg(long, required_unless_present = "subnet_ids")]
+    vpc_id: Option<String>,
+    /// The subnets the cluster will be installed into - defaults to all
+    /// subnets of the VPC.
+    #[arg(long, value_delimiter = ',')]
+    subnet_ids: Vec<String>,
+    /// The base domain of the cluster.
+    #[arg(long)]
+    base_domain: Option<String>,
+}
+
+impl Options {
+    fn gather_options(&self) -> GatherOptions {
+        GatherOptions {
+            all_records: self.all_records,
+            max_subnets: self.max_subnets,
+            max_load_balancers: self.max_load_balancers,
+            max_records: self.max_records,
+            overrides: self.overrides.clone(),
+            health_events: self.required_data_sets().contains(&DataSet::HealthEvents),
+            instance_profiles: self
+                .required_data_sets()
+                .contains(&DataSet::InstanceProfiles),
+            service_quotas: self.required_data_sets().contains(&DataSet::ServiceQuotas),
+            flow_logs: self.required_data_sets().contains(&DataSet::FlowLogs),
+            progress: self.shows_progress(),
+            max_concurrency: self.max_concurrency,
+            data_sets: self.gathered_data_sets(),
+            proxy: self.proxy.clone(),
+        }
+    }
+
+    /// The data sets the selected checks and the custom rules are run on -
+    /// snapshots and the gather command keep all data sets, so other checks
+    /// can run on them later.
+    fn gathered_data_sets(&self) -> Option<Vec<DataSet>> {
+        if self.snapshot.is_some() || matches!(self.command, Some(Command::Gather(_))) {
+            return None;
+        }
+        let mut data_sets = gathered_data_sets(&self.selected_checks());
+        data_sets.extend(self.custom_rules.iter().map(|r| r.resource));
+        Some(data_sets)
+    }
+
+    /// The data sets the selected checks and the custom rules require.
+    fn required_data_sets(&self) -> Vec<DataSet> {
+        let mut data_sets = required_data_sets(&self.selected_checks());
+        data_sets.extend(self.custom_rules.iter().map(|r| r.resource));
+        data_sets
+    }
+
+    /// The lowest severity of the results that are printed - the higher one
+    /// of the policy and `--min-severity`, which only applies to the checks
+    /// format.
+    fn min_severity(&self) -> Severity {
+        let policy = self.policy.map_or(Severity::Ok, |p| p.min_severity());
+        match (&self.format, self.min_severity) {
+            (OutputFormat::Checks, Some(min_severity)) => policy.max(min_severity),
+            _ => policy,
+        }
+    }
+
+    /// Renders the results of the checks format, as they are or summarized
+    /// per check.
+    fn terminal_renderer(&self) -> Box<dyn Renderer> {
+        if self.summary {
+            Box::new(SummaryRenderer)
+        } else {
+            Box::new(TreeRenderer)
+        }
+    }
+
+    /// Whether several clusters are checked at once.
+    fn is_batch(&self) -> bool {
+        self.clusterid.len() > 1
+    }
+
+    /// The user is asked to refresh expired credentials only when a single
+    /// cluster is checked once from a terminal - the daemon and batch runs
+    /// continue without the data that could not be gathered.
+    fn prompts_on_expiry(&self) -> bool {
+        self.serve.is_none() && !self.is_batch() && std::io::stdin().is_terminal()
+    }
+
+    /// Progress is shown while gathering when the results are printed to a
+    /// terminal for humans, unless the daemon runs, several clusters are
+    /// gathered at once or log output is enabled.
+    fn shows_progress(&self) -> bool {
+        self.serve.is_none()
+            && !self.is_batch()
+            && !self.format.is_machine_readable()
+            && self.verbose.log_level_filter() <= log::LevelFilter::Warn
+            && std::io::stdout().is_terminal()
+            && std::io::stderr().is_terminal()
+    }
+
+    /// Sets the options that were not given on the command line from the
+    /// config file.
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches) -> Result<(), InvariantError> {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if !from_cli("region") && config.region.is_some() {
+            self.region = config.region;
+        }
+        if !from_cli("profile") && !from_cli("sso_session") && config.profile.is_some() {
+            self.profile = config.profile;
+        }
+        if let (false, Some(format)) = (from_cli("format"), config.format) {
+            self.format = parse_value("format", &format)?;
+        }
+        if !from_cli("checks") {
+            self.checks = config
+                .checks
+                .iter()
+                .map(|c| parse_value("checks", c))
+                .collect::<Result<_, _>>()?;
+        }
+        if let (false, Some(policy)) = (from_cli("policy"), config.policy) {
+            self.policy = Some(parse_value("policy", &policy)?);
+        }
+        if let (false, Some(fail_on)) = (from_cli("fail_on"), config.fail_on) {
+            self.fail_on = parse_value("fail-on", &fail_on)?;
+        }
+        self.proxy = https_proxy_from_env().or(config.proxy);
+        Ok(())
+    }
+
+    /// The checks to run - the custom rules, the Kubernetes checks and the
+    /// active probes run with any selection if a rule file, a kubeconfig or
+    /// `--active-probes` is given.
+    fn selected_checks(&self) -> Vec<Check> {
+        let mut checks = self.builtin_checks();
+        if self.kubeconfig.is_some() && !checks.contains(&Check::Kubernetes) {
+            checks.push(Check::Kubernetes);
+        }
+        if self.active_probes && !checks.contains(&Check::ActiveProbes) {
+            checks.push(Check::ActiveProbes);
+        }
+        if !self.custom_rules.is_empty() {
+            checks.push(Check::Custom);
+        }
+        checks
+    }
+
+    fn builtin_checks(&self) -> Vec<Check> {
+        if !self.checks.is_empty() {
+            return self.checks.clone();
+        }
+        match self.policy {
+            Some(policy) => policy.checks(),
+            None => DEFAULT_CHECKS.to_vec(),
+        }
+    }
+}
+
+/// Sets up the AWS configuration and verifies the credentials are usable.
+/// `--region` takes precedence over the region of the cluster.
+async fn setup_aws(
+    options: &Options,
+    cluster_region: Option<&str>,
+) -> Result<(SdkConfig, Environment), String> {
+    let profile = match options.sso_session {
+        Some(ref session) => match profile_for_sso_session(session) {
+            Some(profile) => Some(profile),
+            None => {
+                return Err(format!(
+                    "No AWS profile is configured for SSO session: {}",
+                    session
+                ));
+            }
+        },
+        None => options.profile.clone(),
+    };
+    let aws_config = byovpc_checker_lib::gatherer::aws::aws_setup(
+        profile.as_deref(),
+        options.region.as_deref().or(cluster_region),
+        options.proxy.as_deref(),
+    )
+    .await;
+    let profile = profile.unwrap_or_else(active_profile);
+    verify_credentials(&aws_config, &profile)
+        .await
+        .map_err(|e| e.to_string())?;
+    let environment = Environment::detect(&aws_config, &profile, options.proxy.as_deref());
+    Ok((aws_config, environment))
+}
+
+/// Runs the preflight checks for a VPC and exits with a non-zero code if the
+/// install is blocked.
+async fn run_preflight(preflight: &PreflightOptions, aws_config: &SdkConfig) {
+    let data = byovpc_checker_lib::gatherer::aws::gather_preflight(
+        preflight.vpc_id.as_ref(),
+        &preflight.subnet_ids,
+        preflight.base_domain.as_ref(),
+        aws_config,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+    let pc = PreflightChecksBuilder::default()
+        .vpc_id(preflight.vpc_id.clone())
+        .subnet_ids(preflight.subnet_ids.clone())
+        .base_domain(preflight.base_domain.clone())
+        .subnets(data.subnets)
+        .routetables(data.routetables)
+        .nat_gateways(data.nat_gateways)
+        .vpc_endpoints(data.vpc_endpoints)
+        .elastic_ips(data.elastic_ips)
+        .account_attributes(data.account_attributes)
+        .hosted_zones(data.hosted_zones)
+        .build()
+        .unwrap();
+    let results = pc.verify();
+    for res in results.iter() {
+        println!("{}", res);
+    }
+    let verdict = verdict(&results);
+    println!("{}", verdict);
+    if verdict.severity == Severity::Critical {
+        exit(1);
+    }
+}
+
+/// Verifies the local environment and exits with a non-zero code if the tool
+/// can not work in it.
+async fn run_doctor(options: &Options) {
+    let profile = match options.sso_session {
+        Some(ref session) => profile_for_sso_session(session),
+        None => Some(options.profile.clone().unwrap_or_else(active_profile)),
+    };
+    let mut dcb = DoctorChecksBuilder::default();
+    let mut binaries = vec![Binary {
+        name: "ocm",
+        purpose: "as a fallback if the OCM API can not be reached",
+        version: binary_version("ocm", "version"),
+        required: false,
+    }];
+    match profile {
+        Some(ref profile) => {
+            let aws_config = byovpc_checker_lib::gatherer::aws::aws_setup(
+                Some(profile),
+                None,
+                options.proxy.as_deref(),
+            )
+            .await;
+            let credentials_error = verify_credentials(&aws_config, profile)
+                .await
+                .err()
+                .map(|e| e.to_string());
+            dcb.profile(profile.clone())
+                .credentials_error(credentials_error);
+            binaries.push(Binary {
+                name: "aws",
+                purpose: "to log in to AWS SSO sessions",
+                version: binary_version("aws", "--version"),
+                required: sso_login_for_profile(profile).is_some(),
+            });
+        }
+        None => {
+            dcb.profile(String::new()).credentials_error(Some(format!(
+                "No AWS profile is configured for SSO session: {}",
+                options.sso_session.clone().unwrap_or_default()
+            )));
+        }
+    }
+    let region = match options.region {
+        Some(ref region) => Some(region.clone()),
+        None => RegionProviderChain::default_provider()
+            .region()
+            .await
+            .map(|r| r.to_string()),
+    };
+    dcb.region(region);
+    match OcmClient::from_config() {
+        Some(ocm) => {
+            let ocm_error = ocm
+                .get("/api/accounts_mgmt/v1/current_account", &[])
+                .await
+                .err()
+                .map(|e| e.to_string());
+            dcb.ocm_url(Some(ocm.url().to_string()))
+                .ocm_error(ocm_error);
+        }
+        None => {
+            dcb.ocm_url(None);
+        }
+    }
+    if let Some(proxy) = options.proxy.as_deref().and_then(proxy_without_credentials) {
+        dcb.proxy_error(probe_proxy(&proxy).await.err())
+            .proxy(Some(proxy));
+    }
+    let dc = dcb.binaries(binaries).build().unwrap();
+    let results = dc.verify();
+    for res in results.iter() {
+        println!("{}", res);
+    }
+    if results.iter().any(|r| r.severity == Severity::Critical) {
+        exit(1);
+    }
+}
+
+/// Reports the resources left over by a deleted cluster.
+async fn run_cleanup_audit(
+    audit: &CleanupAuditOptions,
+    aws_config: &SdkConfig,
+    gather_options: &GatherOptions,
+) {
+    let data = byovpc_checker_lib::gatherer::aws::gather_leftovers(
+        &audit.infra_id,
+        aws_config,
+        gather_options,
+    )
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+    let lrc = LeftoverResourceChecksBuilder::default()
+        .infra_id(audit.infra_id.clone())
+        .load_balancers(data.load_balancers)
+        .network_interfaces(data.network_interfaces)
+        .security_groups(data.security_groups)
+        .elastic_ips(data.elastic_ips)
+        .hosted_zones(data.hosted_zones)
+        .build()
+        .unwrap();
+    for res in lrc.verify() {
+        println!("{}", res);
+    }
+}
+
+/// Retrieves the cluster information from OCM, or builds it from the file or
+/// the flags given instead.
+async fn fetch_cluster_info(options: &Options) -> Result<MinimalClusterInfo, String> {
+    if let Some(ref path) = options.cluster_info_file {
+        let mut cluster_info = MinimalClusterInfo::read(path).map_err(|e| {
+            format!(
+                "Could not read cluster information {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        if cluster_info.region.is_none() {
+            cluster_info.region = options.region.clone();
+        }
+        return Ok(cluster_info);
+    }
+    if let Some(ref infra_name) = options.infra_name {
+        return Ok(MinimalClusterInfoBuilder::default()
+            .cluster_id(infra_name.clone())
+            .cluster_infra_name(infra_name.clone())
+            .cluster_type(options.cluster_type.clone())
+            .cloud_provider("aws".to_string())
+            .subnets(options.subnets.clone())
+            .base_domain(options.base_domain.clone())
+            .region(options.region.clone())
+            .build()
+            .expect("cluster information from the flags is complete"));
+    }
+    let Some(clusterid) = options.clusterid.first().filter(|c| !c.is_empty()) else {
+        return Err("Must set a clusterid to proceed.".to_string());
+    };
+    MinimalClusterInfo::get_cluster_info(clusterid)
+        .await
+        .map_err(|e| format!("Could not retrieve the cluster {}: {}", clusterid, e))
+}
+
+/// Retrieves the cluster information and gathers the data of the cluster -
+/// fails with a message for the user instead of exiting, so the daemon and
+/// batch runs can continue.
+async fn gather_cluster_data(
+    options: &Options,
+) -> Result<(MinimalClusterInfo, AWSClusterData, Option<Environment>), String> {
+    let cluster_info = fetch_cluster_info(options).await?;
+    gather_aws_data(options, cluster_info).await
+}
+
+async fn gather_aws_data(
+    options: &Options,
+    cluster_info: MinimalClusterInfo,
+) -> Result<(MinimalClusterInfo, AWSClusterData, Option<Environment>), String> {
+    if cluster_info.cloud_provider != "aws" {
+        return Err(format!(
+            "This only works for AWS clusters, not: {} - clusters on Azure can only be checked on their own",
+            cluster_info.cloud_provider
+        ));
+    }
+
+    let (aws_config, environment) = setup_aws(options, cluster_info.region.as_deref()).await?;
+    let gather_options = options.gather_options();
+    let aws_data = loop {
+        match byovpc_checker_lib::gatherer::aws::gather(&cluster_info, &aws_config, &gather_options)
+            .await
+        {
+            Ok(aws_data) => break aws_data,
+            Err(GatherError::CredentialsExpired { what, .. }) if options.prompts_on_expiry() => {
+                eprintln!(
+                    "The AWS credentials expired while gathering {}. Refresh them and press Enter to retry.",
+                    what
+                );
+                let mut input = String::new();
+                if std::io::stdin().read_line(&mut input).is_err() {
+                    return Err("Could not read from stdin - aborting".to_string());
+                }
+            }
+            Err(GatherError::CredentialsExpired {
+                what,
+                partial: Some(partial),
+            }) => {
+                eprintln!(
+                    "The AWS credentials expired while gathering {}. The checks run on the data gathered so far - refresh them and run the tool again for complete results.",
+                    what
+                );
+                break *partial;
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+    if let Some(ref path) = options.snapshot {
+        let mut snapshot = Snapshot::new(&cluster_info, &aws_data);
+        snapshot.environment = Some(environment.clone());
+        snapshot
+            .write(path)
+            .map_err(|e| format!("Could not write snapshot {}: {}", path.display(), e))?;
+    }
+    Ok((cluster_info, aws_data, Some(environment)))
+}
+
+/// Gathers the data of the cluster and prints it without running the checks.
+async fn run_gather(gather: &GatherCommandOptions, options: &Options) {
+    let (cluster_info, aws_data, environment) =
+        gather_cluster_data(options).await.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+    let mut snapshot = Snapshot::new(&cluster_info, &aws_data);
+    snapshot.environment = environment;
+    let serialized = match gather.format {
+        DataFormat::Json => serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string()),
+        DataFormat::Yaml => serde_yaml::to_string(&snapshot).map_err(|e| e.to_string()),
+    };
+    let serialized = serialized.unwrap_or_else(|e| {
+        eprintln!("Could not serialize the gathered data: {}", e);
+        exit(1);
+    });
+    match gather.output {
+        Some(ref path) => {
+            if let Err(e) = std::fs::write(path, serialized) {
+                eprintln!("Could not write {}: {}", path.display(), e);
+                exit(1);
+            }
+        }
+        None => println!("{}", serialized),
+    }
+}
+
+/// Gathers the data of a cluster on Azure and runs the Azure checks on it.
+async fn run_azure(options: &Options, cluster_info: &MinimalClusterInfo) {
+    let Some(ref azure_info) = cluster_info.azure else {
+        eprintln!("OCM has no Azure resources configured for the cluster");
+        exit(1);
+    };
+    let client = AzureClient::from_cli().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    });
+    let data = azure::gather(&client, azure_info, cluster_info.base_domain.as_deref()).await;
+    let renderer: Box<dyn Renderer> = match options.format {
+        OutputFormat::Debug => {
+            println!("{:#?}", data);
+            return;
+        }
+        OutputFormat::Checks => options.terminal_renderer(),
+        OutputFormat::Sarif => Box::new(SarifRenderer),
+        OutputFormat::Html => Box::new(HtmlRenderer),
+        OutputFormat::Junit => Box::new(JunitRenderer),
+    };
+    let results = AzureChecksBuilder::default()
+        .cluster_info(cluster_info)
+        .azure_info(azure_info)
+        .data(&data)
+        .build()
+        .unwrap()
+        .verify();
+    report(options, renderer.as_ref(), None, results);
+}
+
+/// Applies the changes of the patch file to the cluster data, so the checks
+/// run against the resulting topology.
+fn simulate(
+    path: &Path,
+    cluster_info: &MinimalClusterInfo,
+    aws_data: &AWSClusterData,
+) -> (MinimalClusterInfo, AWSClusterData) {
+    let changes = match read_patch(path) {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("Could not read patch {}: {}", path.display(), e);
+            exit(1);
+        }
+    };
+    let mut snapshot = Snapshot::new(cluster_info, aws_data);
+    for change in changes.iter() {
+        if let Err(e) = snapshot.apply(change) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        info!("Simulating: {}", change);
+    }
+    snapshot.into_parts()
+}
+
+/// Reads the objects of the cluster from its Kubernetes API if a kubeconfig
+/// is given.
+async fn gather_kubernetes_data(options: &Options) -> Option<KubernetesData> {
+    let path = options.kubeconfig.as_ref()?;
+    let client = match KubeClient::from_kubeconfig(path) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Could not read kubeconfig {}: {}", path.display(), e);
+            exit(1);
+        }
+    };
+    match k8s::gather(&client).await {
+        Ok(data) => Some(data),
+        Err(e) => {
+            eprintln!("Could not read the Kubernetes API: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Probes the endpoints the cluster needs through its proxy, if it has one
+/// and the proxy check runs against live data.
+async fn probe_cluster_egress(
+    options: &Options,
+    cluster_info: &MinimalClusterInfo,
+) -> Option<ProxyEgress> {
+    if options.from_snapshot.is_some() || !options.selected_checks().contains(&Check::Proxy) {
+        return None;
+    }
+    let proxy = cluster_info.proxy.as_ref()?;
+    let endpoints = required_endpoints(cluster_info.region.as_deref());
+    Some(probe_egress(proxy, &endpoints).await)
 }
 
-fn setup_checks(
+/// Connects to the endpoints the cluster needs, if the active probes run
+/// against live data.
+async fn probe_cluster_endpoints(
+    options: &Options,
+    cluster_info: &MinimalClusterInfo,
+) -> Option<ActiveProbes> {
+    if options.from_snapshot.is_some() || !options.selected_checks().contains(&Check::ActiveProbes)
+    {
+        return None;
+    }
+    let endpoints = required_endpoints(cluster_info.region.as_deref());
+    Some(run_active_probes(cluster_info.proxy.as_deref(), &endpoints).await)
+}
+
+/// Runs the checks selected by the options against the data.
+fn evaluate(
     options: Options,
     cluster_info: &MinimalClusterInfo,
     aws_data: AWSClusterData,
-) -> Vec<Box<dyn Verifier + '_>> {
-    let mut checks: Vec<Box<dyn Verifier>> = vec![];
-    for c in options.checks {
-        match c {
-            Check::Network => {
-                let mut cnb = ClusterNetworkBuilder::default();
-                let cn = cnb
-                    .cluster_info(&cluster_info)
-                    .all_subnets(aws_data.subnets.clone())
-                    .routetables(aws_data.routetables.clone())
-                    .load_balancers(aws_data.load_balancers.clone())
-                    .load_balancer_enis(aws_data.load_balancer_enis.clone())
-                    .build()
-                    .unwrap();
-                checks.push(Box::new(cn));
+    kubernetes: Option<&KubernetesData>,
+    proxy_egress: Option<&ProxyEgress>,
+    active_probes: Option<&ActiveProbes>,
+) -> Vec<(Check, VerificationResult)> {
+    let setup = CheckSetup {
+        cluster_info,
+        data: &aws_data,
+        cluster_tag_values: &options.cluster_tag_values,
+        egress_ips: &options.egress_ips,
+        custom_rules: &options.custom_rules,
+        kubernetes,
+        proxy_egress,
+        active_probes,
+    };
+    let overrides = options
+        .policy
+        .map(|p| p.severity_overrides())
+        .unwrap_or_default();
+    byovpc_checker_lib::evaluate(&options.selected_checks(), &setup, &overrides)
+}
+
+/// Gathers the data and runs the checks every `--interval` seconds, serving
+/// the results of the latest run as Prometheus metrics.
+async fn run_daemon(options: Options, addr: SocketAddr) {
+    let metrics = Arc::new(Mutex::new(Metrics::default()));
+    let server = match serve::serve(&addr, metrics.clone()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Could not listen on {}: {}", addr, e);
+            exit(1);
+        }
+    };
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            eprintln!("Serving metrics failed: {}", e);
+            exit(1);
+        }
+    });
+    info!("Serving metrics on http://{}/metrics", addr);
+    let mut interval = tokio::time::interval(Duration::from_secs(options.interval));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let start = Instant::now();
+        let (cluster_info, aws_data, _) = match gather_cluster_data(&options).await {
+            Ok(gathered) => gathered,
+            Err(e) => {
+                error!("Gathering failed, retrying in {}s: {}", options.interval, e);
+                metrics
+                    .lock()
+                    .expect("metrics lock poisoned")
+                    .record_failure();
+                continue;
+            }
+        };
+        let kubernetes = gather_kubernetes_data(&options).await;
+        let proxy_egress = probe_cluster_egress(&options, &cluster_info).await;
+        let active_probes = probe_cluster_endpoints(&options, &cluster_info).await;
+        let gather_duration = start.elapsed();
+        let results: Vec<VerificationResult> = evaluate(
+            options.clone(),
+            &cluster_info,
+            aws_data,
+            kubernetes.as_ref(),
+            proxy_egress.as_ref(),
+            active_probes.as_ref(),
+        )
+        .into_iter()
+        .map(|(_, res)| res)
+        .collect();
+        info!(
+            "Ran the checks with {} results after gathering for {:.1}s",
+            results.len(),
+            gather_duration.as_secs_f64()
+        );
+        metrics
+            .lock()
+            .expect("metrics lock poisoned")
+            .record(gather_duration, &results);
+    }
+}
+
+/// Gathers the data and runs the checks of all clusters, at most
+/// `BATCH_CONCURRENCY` at the same time, and prints a section per cluster and
+/// a summary of all clusters.
+async fn run_batch(options: Options) {
+    if options.snapshot.is_some()
+        || options.from_snapshot.is_some()
+        || options.simulate.is_some()
+        || options.baseline.is_some()
+        || options.emit_fixes.is_some()
+        || options.kubeconfig.is_some()
+        || !matches!(options.format, OutputFormat::Checks)
+    {
+        eprintln!(
+            "--snapshot, --from-snapshot, --simulate, --baseline, --emit-fixes, --kubeconfig and formats other than checks can only be used with a single cluster"
+        );
+        exit(1);
+    }
+    let articles = articles(&options);
+    let semaphore = Arc::new(Semaphore::new(BATCH_CONCURRENCY));
+    let handles: Vec<_> = options
+        .clusterid
+        .iter()
+        .map(|clusterid| {
+            let options = Options {
+                clusterid: vec![clusterid.clone()],
+                ..options.clone()
+            };
+            let semaphore = semaphore.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let (cluster_info, aws_data, environment) =
+                    match gather_cluster_data(&options).await {
+                        Ok(gathered) => gathered,
+                        Err(e) => return (options, None, vec![gathering_failed(&e)]),
+                    };
+                let proxy_egress = probe_cluster_egress(&options, &cluster_info).await;
+                let active_probes = probe_cluster_endpoints(&options, &cluster_info).await;
+                let results: Vec<VerificationResult> = evaluate(
+                    options.clone(),
+                    &cluster_info,
+                    aws_data,
+                    None,
+                    proxy_egress.as_ref(),
+                    active_probes.as_ref(),
+                )
+                .into_iter()
+                .map(|(_, res)| res)
+                .collect();
+                (options, environment, results)
+            })
+        })
+        .collect();
+    let mut runs = vec![];
+    for handle in handles {
+        let (options, environment, results) = handle.await.expect("cluster run panicked");
+        let clusterid = options.clusterid[0].clone();
+        println!("{}\n", format!("=== Cluster {} ===", clusterid).bold());
+        let report = CheckRunReport {
+            environment,
+            results: results
+                .iter()
+                .filter(|r| r.severity >= options.min_severity())
+                .cloned()
+                .collect(),
+            articles: articles.clone(),
+        };
+        if let Err(e) = options
+            .terminal_renderer()
+            .render(&report, &mut std::io::stdout().lock())
+        {
+            eprintln!("Could not write the results: {}", e);
+            exit(1);
+        }
+        println!();
+        runs.push(ClusterRun { clusterid, results });
+    }
+    println!("{}\n", "=== Summary ===".bold());
+    if let Err(e) = write_summary(&runs, &mut std::io::stdout().lock()) {
+        eprintln!("Could not write the results: {}", e);
+        exit(1);
+    }
+    if runs.iter().any(|r| r.worst() >= options.fail_on.severity()) {
+        exit(1);
+    }
+}
+
+/// Prints the resources and findings that differ between two snapshots - the
+/// findings are the ones of the selected checks run against each snapshot.
+fn run_diff(diff: &DiffOptions, options: &Options) {
+    let read = |path: &PathBuf| {
+        Snapshot::read(path).unwrap_or_else(|e| {
+            eprintln!("Could not read snapshot {}: {}", path.display(), e);
+            exit(1);
+        })
+    };
+    let (before, after) = (read(&diff.before), read(&diff.after));
+    let changes = diff_snapshots(&before, &after);
+    let results = |snapshot: Snapshot| -> Vec<VerificationResult> {
+        let (cluster_info, aws_data) = snapshot.into_parts();
+        evaluate(options.clone(), &cluster_info, aws_data, None, None, None)
+            .into_iter()
+            .map(|(_, res)| res)
+            .collect()
+    };
+    let (new, resolved) = diff_findings(&results(before), &results(after));
+    println!("{}\n", "=== Resources ===".bold());
+    if changes.is_empty() {
+        println!("No resources changed");
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    println!("\n{}\n", "=== Findings ===".bold());
+    if new.is_empty() && resolved.is_empty() {
+        println!("No findings changed");
+    }
+    for res in new {
+        println!("+ {}", res);
+    }
+    for res in resolved {
+        println!("- {}", res);
+    }
+}
+
+/// Reads the mapping of finding codes to support articles.
+fn load_articles(path: &Path) -> Result<HashMap<FindingCode, String>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+/// The support articles of `--articles` - none if it is not set.
+fn articles(options: &Options) -> HashMap<FindingCode, String> {
+    match options.articles {
+        Some(ref path) => match load_articles(path) {
+            Ok(articles) => articles,
+            Err(e) => {
+                eprintln!("Could not read articles {}: {}", path.display(), e);
+                exit(1);
             }
-            Check::HostedZone => {
-                let mut hzb = HostedZoneChecksBuilder::default();
-                let hz = hzb
-                    .hosted_zones(aws_data.hosted_zones.clone())
-                    .load_balancers(aws_data.load_balancers.clone())
-                    .build()
-                    .unwrap();
-                checks.push(Box::new(hz));
+        },
+        None => HashMap::new(),
+    }
+}
+
+/// Writes the fixes of all findings, including the ones below
+/// `--min-severity`, to the file.
+fn write_fixes(format: FixFormat, path: &Path, results: &[VerificationResult]) {
+    let report = CheckRunReport {
+        environment: None,
+        results: results.to_vec(),
+        articles: HashMap::new(),
+    };
+    let written = File::create(path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        format.renderer().render(&report, &mut writer)?;
+        writer.flush()
+    });
+    match written {
+        Ok(()) => info!("Wrote the fixes to {}", path.display()),
+        Err(e) => {
+            eprintln!("Could not write fixes {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+}
+
+/// Suppresses the findings of the baseline and renders the results - exits
+/// with an error if one of them is at least as severe as `--fail-on`.
+fn report(
+    options: &Options,
+    renderer: &dyn Renderer,
+    environment: Option<Environment>,
+    mut results: Vec<VerificationResult>,
+) {
+    let min_severity = options.min_severity();
+    let fail_on = options.fail_on;
+    let articles = articles(options);
+    if let Some(ref path) = options.baseline {
+        if path.exists() {
+            let baseline = Baseline::read(path).unwrap_or_else(|e| {
+                eprintln!("Could not read baseline {}: {}", path.display(), e);
+                exit(1);
+            });
+            let count = results.len();
+            results.retain(|res| !baseline.contains(res));
+            info!(
+                "Suppressed {} findings of baseline {}",
+                count - results.len(),
+                path.display()
+            );
+        } else {
+            let baseline = Baseline::record(&results);
+            if let Err(e) = baseline.write(path) {
+                eprintln!("Could not write baseline {}: {}", path.display(), e);
+                exit(1);
             }
+            info!(
+                "Recorded {} findings in baseline {}",
+                baseline.entries.len(),
+                path.display()
+            );
         }
     }
-    checks
+    if let Some(format) = options.emit_fixes {
+        write_fixes(format, &options.fixes_file, &results);
+    }
+    let mut worst = Severity::Ok;
+    let mut reported = vec![];
+    for res in results {
+        worst = worst.max(res.severity);
+        if res.severity < min_severity {
+            continue;
+        }
+        reported.push(res);
+    }
+    let report = CheckRunReport {
+        environment,
+        results: reported,
+        articles,
+    };
+    if let Err(e) = renderer.render(&report, &mut std::io::stdout().lock()) {
+        eprintln!("Could not write the results: {}", e);
+        exit(1);
+    }
+    if worst >= fail_on.severity() {
+        exit(1);
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    let options = Options::parse();
+    let matches = Options::command().get_matches();
+    let mut options = Options::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Err(e) = Config::load(options.config.as_deref())
+        .and_then(|config| options.apply_config(config, &matches))
+    {
+        eprintln!("{}", e);
+        exit(1);
+    }
     env_logger::Builder::new()
         .filter_level(options.verbose.log_level_filter())
         .init();
-    if options.clusterid.is_empty() {
-        eprintln!("Must set a clusterid to proceed.");
-        exit(1);
+    retry::set_max_retries(options.max_retries);
+    if let Some(ref path) = options.clusters_file {
+        match read_cluster_ids(path) {
+            Ok(clusterids) => options.clusterid.extend(clusterids),
+            Err(e) => {
+                eprintln!("Could not read clusters {}: {}", path.display(), e);
+                exit(1);
+            }
+        }
     }
-
-    let cluster_info = MinimalClusterInfo::get_cluster_info(&options.clusterid);
-    if cluster_info.cloud_provider != "aws" {
-        eprintln!(
-            "This check only works for AWS clusters, not: {}",
-            cluster_info.cloud_provider
-        );
-        exit(1)
+    for path in options.rules.clone() {
+        match load_rules(&path) {
+            Ok(rules) => options.custom_rules.extend(rules),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
     }
-
-    let aws_data = crate::gatherer::aws::gather(&cluster_info).await;
-
-    match options.format {
-        OutputFormat::Debug => {
-            println!("{}", &format!("{:#?}", aws_data))
+    if options.list_checks {
+        for check in byovpc_checker_lib::checks::REGISTRY {
+            println!("{:<9} {}", check.id, check.description);
         }
-        OutputFormat::Checks => {
-            let checks = setup_checks(options, &cluster_info, aws_data);
-            for check in checks {
-                for res in check.verify() {
-                    println!("{}", res);
+        for rule in options.custom_rules.iter() {
+            println!("{:<9} {}", rule.id, rule.description);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Preflight(ref preflight)) = options.command {
+        let (aws_config, _) = setup_aws(&options, None).await.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+        run_preflight(preflight, &aws_config).await;
+        return Ok(());
+    }
+    if let Some(Command::Diff(ref diff)) = options.command {
+        run_diff(diff, &options);
+        return Ok(());
+    }
+    if let Some(Command::Gather(ref gather)) = options.command {
+        run_gather(gather, &options).await;
+        return Ok(());
+    }
+    if let Some(Command::Doctor) = options.command {
+        run_doctor(&options).await;
+        return Ok(());
+    }
+    if let Some(Command::CleanupAudit(ref audit)) = options.command {
+        let (aws_config, _) = setup_aws(&options, None).await.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            exit(1);
+        });
+        run_cleanup_audit(audit, &aws_config, &options.gather_options()).await;
+        return Ok(());
+    }
+    if let Some(addr) = options.serve {
+        if options.is_batch() {
+            eprintln!("--serve can only be used with a single cluster");
+            exit(1);
+        }
+        run_daemon(options, addr).await;
+        return Ok(());
+    }
+    if options.is_batch() {
+        run_batch(options).await;
+        return Ok(());
+    }
+    let (cluster_info, aws_data, environment) = match options.from_snapshot {
+        Some(ref path) => match Snapshot::read(path) {
+            Ok(snapshot) => {
+                let environment = snapshot.environment.clone().map(|mut e| {
+                    e.from_snapshot = true;
+                    e
+                });
+                let (cluster_info, aws_data) = snapshot.into_parts();
+                (cluster_info, aws_data, environment)
+            }
+            Err(e) => {
+                eprintln!("Could not read snapshot {}: {}", path.display(), e);
+                exit(1);
+            }
+        },
+        None => {
+            let gathered = match fetch_cluster_info(&options).await {
+                Ok(cluster_info) if cluster_info.cloud_provider == "azure" => {
+                    run_azure(&options, &cluster_info).await;
+                    return Ok(());
                 }
+                Ok(cluster_info) => gather_aws_data(&options, cluster_info).await,
+                Err(e) => Err(e),
+            };
+            gathered.unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                exit(1);
+            })
+        }
+    };
+    let (cluster_info, aws_data) = match options.simulate {
+        Some(ref path) => simulate(path, &cluster_info, &aws_data),
+        None => (cluster_info, aws_data),
+    };
+    let kubernetes = gather_kubernetes_data(&options).await;
+    let proxy_egress = probe_cluster_egress(&options, &cluster_info).await;
+    let active_probes = probe_cluster_endpoints(&options, &cluster_info).await;
+
+    let renderer: Box<dyn Renderer> = match options.format {
+        OutputFormat::Debug => {
+            if let Some(ref environment) = environment {
+                println!("{}\n", environment);
             }
+            println!("{}", &format!("{:#?}", aws_data));
+            return Ok(());
         }
-    }
+        OutputFormat::Checks => options.terminal_renderer(),
+        OutputFormat::Sarif => Box::new(SarifRenderer),
+        OutputFormat::Html => Box::new(HtmlRenderer),
+        OutputFormat::Junit => Box::new(JunitRenderer),
+    };
+    let results: Vec<VerificationResult> = evaluate(
+        options.clone(),
+        &cluster_info,
+        aws_data,
+        kubernetes.as_ref(),
+        proxy_egress.as_ref(),
+        active_probes.as_ref(),
+    )
+    .into_iter()
+    .map(|(_, res)| res)
+    .collect();
+    report(&options, renderer.as_ref(), environment, results);
     Ok(())
 }
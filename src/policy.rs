@@ -0,0 +1,141 @@
+//! Policies bundle the settings that differ between the consumers of the tool:
+//! which checks run, how severities are adjusted and how verbose the output is.
+//!
+//! - sre: runs every check and prints every result for interactive triage.
+//! - customer: runs the checks relevant for a customer-facing preflight and
+//!   hides successful results.
+//! - ci: runs the same checks as customer but escalates warnings to critical
+//!   and only prints problems, so pipelines can gate on the result.
+
+use byovpc_checker_lib::{
+    selection::{Check, SeverityOverride},
+    types::Severity,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Policy {
+    Sre,
+    Customer,
+    Ci,
+}
+
+impl Policy {
+    /// The checks that run unless checks are explicitly selected.
+    pub fn checks(&self) -> Vec<Check> {
+        match self {
+            Policy::Sre => vec![
+                Check::Network,
+                Check::NetworkAcl,
+                Check::Cidr,
+                Check::Topology,
+                Check::DhcpOptions,
+                Check::Vpc,
+                Check::TargetGroups,
+                Check::DelegatedSubnet,
+                Check::HostedZone,
+                Check::AvailabilityZone,
+                Check::MachinePool,
+                Check::LoadBalancer,
+                Check::Instance,
+                Check::Endpoint,
+                Check::Permission,
+                Check::EgressIp,
+                Check::ResolverQueryLogs,
+                Check::Cost,
+                Check::Scaling,
+                Check::Ssm,
+                Check::HealthEvents,
+                Check::Iam,
+                Check::Ipv6,
+                Check::Proxy,
+                Check::SharedVpc,
+                Check::Hypershift,
+                Check::Quotas,
+                Check::FlowLogs,
+            ],
+            Policy::Customer | Policy::Ci => vec![
+                Check::Network,
+                Check::NetworkAcl,
+                Check::Cidr,
+                Check::Topology,
+                Check::DhcpOptions,
+                Check::Vpc,
+                Check::TargetGroups,
+                Check::DelegatedSubnet,
+                Check::HostedZone,
+                Check::AvailabilityZone,
+                Check::MachinePool,
+                Check::LoadBalancer,
+                Check::Endpoint,
+                Check::Permission,
+                Check::Ipv6,
+                Check::Proxy,
+                Check::SharedVpc,
+                Check::Hypershift,
+            ],
+        }
+    }
+
+    pub fn severity_overrides(&self) -> Vec<SeverityOverride> {
+        match self {
+            Policy::Sre | Policy::Customer => vec![],
+            Policy::Ci => [Check::Network, Check::AvailabilityZone, Check::MachinePool]
+                .into_iter()
+                .map(|check| SeverityOverride {
+                    check,
+                    from: Severity::Warning,
+                    to: Severity::Critical,
+                })
+                .collect(),
+        }
+    }
+
+    /// Results below this severity are not printed.
+    pub fn min_severity(&self) -> Severity {
+        match self {
+            Policy::Sre => Severity::Ok,
+            Policy::Customer => Severity::Info,
+            Policy::Ci => Severity::Warning,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byovpc_checker_lib::types::VerificationResult;
+
+    use super::*;
+
+    #[test]
+    fn test_ci_policy_escalates_network_warnings() {
+        let overrides = Policy::Ci.severity_overrides();
+        let result = SeverityOverride::apply(
+            &overrides,
+            Check::Network,
+            VerificationResult {
+                message: "warning".to_string(),
+                severity: Severity::Warning,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        );
+        assert_eq!(result.severity, Severity::Critical);
+        let result = SeverityOverride::apply(
+            &overrides,
+            Check::HostedZone,
+            VerificationResult {
+                message: "warning".to_string(),
+                severity: Severity::Warning,
+                code: None,
+                check_id: None,
+                resource_type: None,
+                resource_id: None,
+                evidence: None,
+            },
+        );
+        assert_eq!(result.severity, Severity::Warning);
+    }
+}
@@ -0,0 +1,231 @@
+//! Exposes the results of the checks as Prometheus metrics, so a cluster can be
+//! monitored for drift continuously instead of running the tool ad hoc.
+//!
+//! The daemon re-runs gathering and the checks on an interval and replaces the
+//! metrics of the previous run; `/metrics` always serves the latest run.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::Write,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+
+use byovpc_checker_lib::types::{Severity, VerificationResult};
+
+/// The default number of seconds between two runs of the checks.
+pub const DEFAULT_SERVE_INTERVAL: u64 = 300;
+
+/// The upper bounds of the buckets of the gather duration histogram, in
+/// seconds.
+const GATHER_DURATION_BUCKETS: [f64; 8] = [1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+const SEVERITIES: [Severity; 4] = [
+    Severity::Ok,
+    Severity::Info,
+    Severity::Warning,
+    Severity::Critical,
+];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [u64; GATHER_DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(GATHER_DURATION_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// The metrics of the latest run of the checks.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// The number of results of every check ID per severity.
+    check_status: BTreeMap<&'static str, [u64; SEVERITIES.len()]>,
+    gather_duration: Histogram,
+    last_run: Option<SystemTime>,
+    /// Whether the latest run gathered the data - the results of the last
+    /// successful run are kept if it did not.
+    last_run_success: Option<bool>,
+}
+
+impl Metrics {
+    /// Records a run of the checks, replacing the results of the previous
+    /// run.
+    pub fn record(&mut self, gather_duration: Duration, results: &[VerificationResult]) {
+        self.gather_duration.observe(gather_duration.as_secs_f64());
+        self.check_status.clear();
+        for res in results.iter() {
+            let Some(check_id) = res.check_id else {
+                continue;
+            };
+            let counts = self.check_status.entry(check_id).or_default();
+            counts[res.severity as usize] += 1;
+        }
+        self.last_run = Some(SystemTime::now());
+        self.last_run_success = Some(true);
+    }
+
+    /// Records a run that could not gather the data, keeping the results of
+    /// the previous run.
+    pub fn record_failure(&mut self) {
+        self.last_run = Some(SystemTime::now());
+        self.last_run_success = Some(false);
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP byovpc_check_status Number of results of a check with the severity in the latest run.\n");
+        out.push_str("# TYPE byovpc_check_status gauge\n");
+        for (check_id, counts) in self.check_status.iter() {
+            for (severity, count) in SEVERITIES.iter().zip(counts) {
+                let _ = writeln!(
+                    out,
+                    "byovpc_check_status{{check_id=\"{}\",severity=\"{}\"}} {}",
+                    check_id,
+                    severity.as_str(),
+                    count
+                );
+            }
+        }
+        out.push_str("# HELP byovpc_gather_duration_seconds Time it took to gather the data of the cluster.\n");
+        out.push_str("# TYPE byovpc_gather_duration_seconds histogram\n");
+        for (bound, count) in GATHER_DURATION_BUCKETS
+            .iter()
+            .zip(self.gather_duration.buckets)
+        {
+            let _ = writeln!(
+                out,
+                "byovpc_gather_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "byovpc_gather_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.gather_duration.count
+        );
+        let _ = writeln!(
+            out,
+            "byovpc_gather_duration_seconds_sum {}",
+            self.gather_duration.sum
+        );
+        let _ = writeln!(
+            out,
+            "byovpc_gather_duration_seconds_count {}",
+            self.gather_duration.count
+        );
+        if let Some(last_run) = self.last_run {
+            out.push_str("# HELP byovpc_last_run_timestamp_seconds Time the checks last ran.\n");
+            out.push_str("# TYPE byovpc_last_run_timestamp_seconds gauge\n");
+            let _ = writeln!(
+                out,
+                "byovpc_last_run_timestamp_seconds {}",
+                last_run
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs()
+            );
+        }
+        if let Some(success) = self.last_run_success {
+            out.push_str("# HELP byovpc_last_run_success Whether the latest run gathered the data of the cluster.\n");
+            out.push_str("# TYPE byovpc_last_run_success gauge\n");
+            let _ = writeln!(out, "byovpc_last_run_success {}", u8::from(success));
+        }
+        out
+    }
+}
+
+fn respond(req: &Request<Body>, metrics: &Mutex<Metrics>) -> Response<Body> {
+    if req.method() != Method::GET || req.uri().path() != "/metrics" {
+        let mut response = Response::new(Body::from("Not found\n"));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+    let body = metrics.lock().expect("metrics lock poisoned").render();
+    let mut response = Response::new(Body::from(body));
+    response.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    response
+}
+
+/// Binds the metrics endpoint to the address. The returned future serves
+/// `/metrics` until it fails.
+pub fn serve(
+    addr: &SocketAddr,
+    metrics: Arc<Mutex<Metrics>>,
+) -> hyper::Result<impl std::future::Future<Output = hyper::Result<()>>> {
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let response = respond(&req, &metrics);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    Ok(Server::try_bind(addr)?.serve(make_service))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let result = |check_id, severity| VerificationResult {
+            message: String::new(),
+            severity,
+            code: None,
+            check_id,
+            resource_type: None,
+            resource_id: None,
+            evidence: None,
+        };
+        let mut metrics = Metrics::default();
+        metrics.record(
+            Duration::from_secs(20),
+            &[
+                result(Some("NET-002"), Severity::Warning),
+                result(Some("NET-002"), Severity::Warning),
+                result(Some("NET-002"), Severity::Ok),
+                result(None, Severity::Ok),
+            ],
+        );
+        let rendered = metrics.render();
+        assert!(rendered.contains("byovpc_check_status{check_id=\"NET-002\",severity=\"ok\"} 1\n"));
+        assert!(
+            rendered.contains("byovpc_check_status{check_id=\"NET-002\",severity=\"warning\"} 2\n")
+        );
+        assert!(rendered
+            .contains("byovpc_check_status{check_id=\"NET-002\",severity=\"critical\"} 0\n"));
+        assert_eq!(rendered.matches("byovpc_check_status{").count(), 4);
+        assert!(rendered.contains("byovpc_gather_duration_seconds_bucket{le=\"10\"} 0\n"));
+        assert!(rendered.contains("byovpc_gather_duration_seconds_bucket{le=\"30\"} 1\n"));
+        assert!(rendered.contains("byovpc_gather_duration_seconds_sum 20\n"));
+        assert!(rendered.contains("byovpc_last_run_success 1\n"));
+        metrics.record_failure();
+        let rendered = metrics.render();
+        assert!(rendered.contains("byovpc_last_run_success 0\n"));
+        assert_eq!(rendered.matches("byovpc_check_status{").count(), 4);
+    }
+}